@@ -0,0 +1,13 @@
+fn main() {
+    // The protobuf compiler is only needed for the gRPC interface
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("No protoc for this platform");
+        std::env::set_var("PROTOC", protoc);
+        // Clients generate their own code from the proto, the generated client also needs edition 2021
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/rusty_pizza.proto"], &["proto"])
+            .unwrap_or_else(|e| panic!("Could not compile protos: {}", e));
+    }
+}
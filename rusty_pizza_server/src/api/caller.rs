@@ -0,0 +1,86 @@
+use crate::api::error::ApiError;
+use crate::api::state::AppState;
+use crate::auth::authenticator::AuthError;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+
+/// The user whose session token was sent as `Authorization: Bearer <token>`, `None` without header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Caller(Option<Id<User>>);
+
+impl Caller {
+    pub fn get_user_id(&self) -> Option<&Id<User>> {
+        self.0.as_ref()
+    }
+
+    /// Runs the check with the authenticated user, if the state requires authentication.
+    pub fn authorize(
+        &self,
+        state: &AppState,
+        check: impl FnOnce(&Id<User>) -> Result<(), AuthError>,
+    ) -> Result<(), ApiError> {
+        if !state.is_authentication_required() {
+            return Ok(());
+        }
+        let user_id = self.0.as_ref().ok_or(AuthError::NotAuthenticated)?;
+        Ok(check(user_id)?)
+    }
+
+    /// Who makes the change for the `*_as` methods of `Order`, which decide whether they may. That is the
+    /// authenticated user if the state requires authentication, else the user the change is made for, as before logins.
+    pub fn actor(&self, state: &AppState, on_behalf_of: &Id<User>) -> Result<Id<User>, ApiError> {
+        if !state.is_authentication_required() {
+            return Ok(on_behalf_of.clone());
+        }
+        Ok(self.0.clone().ok_or(AuthError::NotAuthenticated)?)
+    }
+
+    /// The user with the session token, `None` without token. Shared by REST and gRPC, which sends it as metadata.
+    pub fn from_headers(state: &AppState, headers: &HeaderMap) -> Result<Caller, ApiError> {
+        match bearer_token(headers)? {
+            Some(token) => Ok(Caller(Some(state.auth().authenticate(token)?))),
+            None => Ok(Caller(None)),
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for Caller {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Caller, ApiError> {
+        Caller::from_headers(state, &parts.headers)
+    }
+}
+
+/// An operator of the deployment, who sent the token of `AppState::with_admin_token` as `Authorization: Bearer <token>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Operator;
+
+impl FromRequestParts<AppState> for Operator {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Operator, ApiError> {
+        match bearer_token(&parts.headers)? {
+            Some(token) if state.is_admin_token(token) => Ok(Operator),
+            Some(_) => Err(AuthError::Forbidden.into()),
+            None => Err(AuthError::NotAuthenticated.into()),
+        }
+    }
+}
+
+/// The session token, a header with another scheme is rejected instead of ignored.
+pub fn bearer_token(headers: &HeaderMap) -> Result<Option<&str>, AuthError> {
+    match headers.get(AUTHORIZATION) {
+        Some(header) => header
+            .to_str()
+            .ok()
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(|token| Some(token.trim()))
+            .ok_or(AuthError::InvalidSession),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,346 @@
+use crate::api::state::DuplicateOrderError;
+use crate::api::v1::dto::ErrorResponse;
+use crate::auth::authenticator::AuthError;
+use crate::auth::provider::ProviderError;
+use crate::error::PizzaError;
+use crate::notifications::web_push::PushError;
+use crate::order_model::order::OrderError;
+use crate::order_model::payment::PaymentError;
+use crate::order_model::preparation::UnknownPreparationError;
+use crate::payments::epc::EpcError;
+use crate::payments::paypal::PayPalError;
+use crate::plugins::registry::PluginRejection;
+use crate::user_model::repository::RegistrationError;
+use crate::util::locale::FormatError;
+use crate::util::short_code::ShortCodeError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum ApiError {
+    OrderNotFound,
+    /// A time in the request could not be parsed
+    InvalidTime,
+    /// A period of the opening hours in the request could not be parsed
+    InvalidOpeningHours(String),
+    Order(OrderError),
+    DuplicateOrder(DuplicateOrderError),
+    Registration(RegistrationError),
+    Format(FormatError),
+    Plugin(PluginRejection),
+    Auth(AuthError),
+    /// No external login provider with the name is configured
+    UnknownAuthProvider(String),
+    AuthProvider(ProviderError),
+    Preparation(UnknownPreparationError),
+    /// The user has no delivered order whose meals could be added again
+    NoLastOrder,
+    UserNotFound,
+    ShortCode(ShortCodeError),
+    PayPal(PayPalError),
+    Epc(EpcError),
+    Push(PushError),
+    /// No VAPID key is configured, so browsers can't subscribe to push messages
+    PushNotConfigured,
+    PushSubscriptionNotFound,
+    /// The order doesn't know the sequence number anymore, so the client has to fetch it again
+    HistoryUnavailable(usize),
+    TemplateNotFound,
+    /// A time of day in the request is not like 11:30
+    InvalidTimeOfDay(String),
+    /// The `If-Match` header is not a version like "12"
+    InvalidVersion(String),
+    /// Changes could not be saved, they are kept until the server is restarted
+    Storage(String),
+    /// Errors of the domain model without a variant of their own
+    Pizza(PizzaError),
+}
+
+impl ApiError {
+    pub fn get_status_code(&self) -> StatusCode {
+        use ApiError::*;
+        match self {
+            OrderNotFound => StatusCode::NOT_FOUND,
+            InvalidTime => StatusCode::UNPROCESSABLE_ENTITY,
+            InvalidOpeningHours(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Order(OrderError::UserNotParticipating) => StatusCode::NOT_FOUND,
+            Order(OrderError::WrongStatus) => StatusCode::CONFLICT,
+            Order(OrderError::PaymentNotPending) => StatusCode::CONFLICT,
+            Order(OrderError::NoMenu) => StatusCode::CONFLICT,
+            Order(OrderError::Menu(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            Order(OrderError::MealNotFound) => StatusCode::NOT_FOUND,
+            Order(OrderError::UserAlreadyParticipating) => StatusCode::CONFLICT,
+            Order(OrderError::ManagerCannotLeave) => StatusCode::CONFLICT,
+            Order(OrderError::StalePreview) => StatusCode::CONFLICT,
+            Order(OrderError::Conflict(_)) => StatusCode::CONFLICT,
+            Order(OrderError::InvalidHistory) => StatusCode::INTERNAL_SERVER_ERROR,
+            Order(OrderError::Meals(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+            Order(OrderError::NotAuthorized) => StatusCode::FORBIDDEN,
+            Order(OrderError::PaymentNotFound) => StatusCode::NOT_FOUND,
+            Order(OrderError::Restaurant(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            Order(OrderError::Currency(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            Order(OrderError::NotReady(_)) => StatusCode::CONFLICT,
+            DuplicateOrder(_) => StatusCode::CONFLICT,
+            Registration(RegistrationError::EmptyName) => StatusCode::UNPROCESSABLE_ENTITY,
+            Registration(RegistrationError::NameTaken) => StatusCode::CONFLICT,
+            Format(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Plugin(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Auth(AuthError::EmptyPassword) => StatusCode::UNPROCESSABLE_ENTITY,
+            Auth(AuthError::Forbidden) => StatusCode::FORBIDDEN,
+            Auth(_) => StatusCode::UNAUTHORIZED,
+            UnknownAuthProvider(_) => StatusCode::NOT_FOUND,
+            AuthProvider(ProviderError::UnsupportedCredentials) => StatusCode::UNPROCESSABLE_ENTITY,
+            AuthProvider(ProviderError::WrongCredentials) => StatusCode::UNAUTHORIZED,
+            AuthProvider(ProviderError::Unavailable(_)) => StatusCode::BAD_GATEWAY,
+            Preparation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            NoLastOrder => StatusCode::NOT_FOUND,
+            UserNotFound => StatusCode::NOT_FOUND,
+            ShortCode(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            PayPal(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Epc(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Push(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            PushNotConfigured => StatusCode::NOT_FOUND,
+            PushSubscriptionNotFound => StatusCode::NOT_FOUND,
+            HistoryUnavailable(_) => StatusCode::GONE,
+            TemplateNotFound => StatusCode::NOT_FOUND,
+            InvalidTimeOfDay(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            InvalidVersion(_) => StatusCode::BAD_REQUEST,
+            Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Pizza(PizzaError::Order { error, .. }) => Order(error.clone()).get_status_code(),
+            Pizza(PizzaError::NotAllPaidEnough { .. }) => StatusCode::CONFLICT,
+            Pizza(PizzaError::ChangeMoney { .. }) => StatusCode::CONFLICT,
+            Pizza(PizzaError::Remove { .. }) => StatusCode::NOT_FOUND,
+            Pizza(PizzaError::Payment {
+                error: PaymentError::NotPending,
+                ..
+            }) => StatusCode::CONFLICT,
+            Pizza(PizzaError::Payment { .. }) => StatusCode::NOT_FOUND,
+            Pizza(PizzaError::Menu(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            Pizza(PizzaError::Restaurant(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            Pizza(PizzaError::Registration(RegistrationError::EmptyName)) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Pizza(PizzaError::Registration(RegistrationError::NameTaken)) => StatusCode::CONFLICT,
+            Pizza(PizzaError::CurrencyMismatch(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            Pizza(PizzaError::IdsExhausted(_)) => StatusCode::INSUFFICIENT_STORAGE,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ApiError::*;
+        match self {
+            OrderNotFound => write!(f, "order not found"),
+            InvalidTime => write!(f, "time is not in RFC 3339 format"),
+            InvalidOpeningHours(period) => write!(
+                f,
+                "{} is not an opening period like Mon 11:30-14:00",
+                period
+            ),
+            Order(error) => write!(f, "{}", error),
+            DuplicateOrder(error) => write!(f, "{}", error),
+            Registration(error) => write!(f, "{}", error),
+            Format(error) => write!(f, "{}", error),
+            Plugin(error) => write!(f, "{}", error),
+            Auth(error) => write!(f, "{}", error),
+            UnknownAuthProvider(name) => write!(f, "login provider {} is not configured", name),
+            AuthProvider(error) => write!(f, "{}", error),
+            Preparation(error) => write!(f, "{}", error),
+            NoLastOrder => write!(f, "user has no previous order"),
+            UserNotFound => write!(f, "user not found"),
+            ShortCode(error) => write!(f, "{}", error),
+            PayPal(error) => write!(f, "{}", error),
+            Epc(error) => write!(f, "{}", error),
+            Push(error) => write!(f, "{}", error),
+            PushNotConfigured => write!(f, "push messages are not configured"),
+            PushSubscriptionNotFound => write!(f, "push subscription not found"),
+            HistoryUnavailable(sequence_number) => write!(
+                f,
+                "events since {} are not available, fetch the order again",
+                sequence_number
+            ),
+            TemplateNotFound => write!(f, "order template not found"),
+            InvalidTimeOfDay(time) => write!(f, "{} is not a time of day like 11:30", time),
+            InvalidVersion(version) => write!(f, "{} is not a version like \"12\"", version),
+            Storage(error) => write!(f, "could not save changes: {}", error),
+            Pizza(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Order(error) => Some(error),
+            ApiError::DuplicateOrder(error) => Some(error),
+            ApiError::Registration(error) => Some(error),
+            ApiError::Format(error) => Some(error),
+            ApiError::Plugin(error) => Some(error),
+            ApiError::Auth(error) => Some(error),
+            ApiError::AuthProvider(error) => Some(error),
+            ApiError::Preparation(error) => Some(error),
+            ApiError::ShortCode(error) => Some(error),
+            ApiError::PayPal(error) => Some(error),
+            ApiError::Epc(error) => Some(error),
+            ApiError::Push(error) => Some(error),
+            ApiError::Pizza(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<OrderError> for ApiError {
+    fn from(error: OrderError) -> Self {
+        ApiError::Order(error)
+    }
+}
+
+impl From<DuplicateOrderError> for ApiError {
+    fn from(error: DuplicateOrderError) -> Self {
+        ApiError::DuplicateOrder(error)
+    }
+}
+
+impl From<RegistrationError> for ApiError {
+    fn from(error: RegistrationError) -> Self {
+        ApiError::Registration(error)
+    }
+}
+
+impl From<FormatError> for ApiError {
+    fn from(error: FormatError) -> Self {
+        ApiError::Format(error)
+    }
+}
+
+impl From<PluginRejection> for ApiError {
+    fn from(error: PluginRejection) -> Self {
+        ApiError::Plugin(error)
+    }
+}
+
+impl From<PayPalError> for ApiError {
+    fn from(error: PayPalError) -> Self {
+        ApiError::PayPal(error)
+    }
+}
+
+impl From<EpcError> for ApiError {
+    fn from(error: EpcError) -> Self {
+        ApiError::Epc(error)
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(error: AuthError) -> Self {
+        ApiError::Auth(error)
+    }
+}
+
+impl From<ProviderError> for ApiError {
+    fn from(error: ProviderError) -> Self {
+        ApiError::AuthProvider(error)
+    }
+}
+
+impl From<UnknownPreparationError> for ApiError {
+    fn from(error: UnknownPreparationError) -> Self {
+        ApiError::Preparation(error)
+    }
+}
+
+impl From<ShortCodeError> for ApiError {
+    fn from(error: ShortCodeError) -> Self {
+        ApiError::ShortCode(error)
+    }
+}
+
+impl From<PushError> for ApiError {
+    fn from(error: PushError) -> Self {
+        ApiError::Push(error)
+    }
+}
+
+/// Order and registration errors keep their own variants, whatever context they have.
+impl From<PizzaError> for ApiError {
+    fn from(error: PizzaError) -> Self {
+        match error {
+            PizzaError::Order { error, .. } => ApiError::Order(error),
+            PizzaError::Registration(error) => ApiError::Registration(error),
+            error => ApiError::Pizza(error),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse {
+            error: self.to_string(),
+        };
+        (self.get_status_code(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Subject;
+    use crate::menu::catalog::MenuError;
+    use crate::util::id::Id;
+    use rstest::rstest;
+
+    #[rstest(
+        error,
+        expected,
+        case(ApiError::OrderNotFound, StatusCode::NOT_FOUND),
+        case(
+            ApiError::Order(OrderError::UserAlreadyParticipating),
+            StatusCode::CONFLICT
+        ),
+        case(
+            ApiError::Order(OrderError::UserNotParticipating),
+            StatusCode::NOT_FOUND
+        ),
+        case(ApiError::Order(OrderError::WrongStatus), StatusCode::CONFLICT),
+        case(ApiError::Order(OrderError::PaymentNotPending), StatusCode::CONFLICT),
+        case(ApiError::Order(OrderError::NoMenu), StatusCode::CONFLICT),
+        case(
+            ApiError::Order(OrderError::Menu(MenuError::MealNotFound)),
+            StatusCode::UNPROCESSABLE_ENTITY
+        ),
+        case(ApiError::Order(OrderError::MealNotFound), StatusCode::NOT_FOUND),
+        case(ApiError::Order(OrderError::ManagerCannotLeave), StatusCode::CONFLICT),
+        case(ApiError::Auth(AuthError::WrongCredentials), StatusCode::UNAUTHORIZED),
+        case(ApiError::Auth(AuthError::Forbidden), StatusCode::FORBIDDEN),
+        case(ApiError::Order(OrderError::NotAuthorized), StatusCode::FORBIDDEN),
+        case(ApiError::NoLastOrder, StatusCode::NOT_FOUND),
+        case(ApiError::InvalidVersion(String::from("*")), StatusCode::BAD_REQUEST),
+        case(
+            ApiError::ShortCode(ShortCodeError::WrongCheck),
+            StatusCode::UNPROCESSABLE_ENTITY
+        ),
+        case(
+            PizzaError::not_found(Subject::MenuItem(String::from("03"))).into(),
+            StatusCode::NOT_FOUND
+        ),
+        case(
+            PizzaError::from(OrderError::WrongStatus).in_order(Id::new(1)).into(),
+            StatusCode::CONFLICT
+        ),
+        case(
+            PizzaError::from(PaymentError::NotPending).into(),
+            StatusCode::CONFLICT
+        )
+    )]
+    fn error_is_mapped_to_status_code(error: ApiError, expected: StatusCode) {
+        // When:
+        let response = error.into_response();
+
+        // Then:
+        assert_eq!(response.status(), expected);
+    }
+}
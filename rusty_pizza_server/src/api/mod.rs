@@ -0,0 +1,6 @@
+pub mod caller;
+pub mod error;
+pub mod routes;
+pub mod state;
+pub mod v1;
+pub mod websocket;
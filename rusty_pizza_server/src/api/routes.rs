@@ -0,0 +1,4982 @@
+use crate::api::caller::{bearer_token, Caller, Operator};
+use crate::api::error::ApiError;
+use crate::api::state::AppState;
+use crate::api::v1::dto::{
+    AddMealRequest, AddUserRequest, AmountRequest, BankStatementRequest, BankStatementResponse,
+    CopyOrderRequest, CostCenterRequest, CostCentersResponse, CreateOrderRequest,
+    CreatedMealsResponse, CreatedOrderResponse, CreatedResponse, CurrentSessionResponse,
+    DashboardResponse, DeadlineRequest, DeadlineResponse, DepositRefundResponse, EtaRequest,
+    EtaResponse, ExternalLoginRequest, FairnessResponse, HealthResponse, HistoryResponse,
+    ImportRequest, ImportResponse, IntegrityResponse, LoginRequest, MoneyStatsResponse,
+    NoteRequest, OpeningPeriodEntry, OrderRoleResponse, OrderStatisticsResponse,
+    OrderTemplateRequest, OrderTemplatesResponse, PaymentClaimRequest, PaymentRequestsRequest,
+    PaymentRequestsResponse, PaymentsResponse, PreparationsRequest, PriceBreakdownResponse,
+    PushKeyResponse, PushSubscriptionRequest, PushUnsubscribeRequest, ReadyRequest,
+    RealtimeResponse, ReceivedPaymentResponse, RegisterUserRequest, ReplayResponse,
+    ResolvedCodeResponse, RestaurantRequest, RestaurantResponse, RetentionResponse,
+    SessionResponse, SessionsEndedResponse, StatementFormat, StatusRequest, StorageResponse,
+    StuckOrderEntry, StuckOrdersQuery, StuckOrdersResponse, SummaryResponse, TotalsResponse,
+    UserIdsResponse,
+};
+use crate::api::websocket::order_events;
+use crate::auth::authenticator::AuthError;
+use crate::auth::provider::Credentials;
+use crate::auth::role::{require_manager, require_owner, Role};
+use crate::export::cost_centers::CostCenterReport;
+use crate::export::summary::plain_summary;
+use crate::import::{bank_statement, spreadsheet};
+use crate::notifications::event::OrderEvent;
+use crate::notifications::web_push::PushSubscription;
+use crate::order_model::order::{Order, OrderError, OrderStatus, Permission};
+use crate::order_model::order_template::{OrderTemplate, OrderTemplates};
+use crate::order_model::payment::ReceivedPayment;
+use crate::order_model::restaurant::{OpeningHours, OpeningPeriod, Restaurant};
+use crate::order_model::user::User;
+use crate::payments::epc::SepaRecipient;
+use crate::payments::request::{payment_requests, PaymentMethod};
+use crate::persistence::ephemeral::TemplateWrite;
+use crate::stats::fairness::FairnessReport;
+use crate::stats::money::{MoneyStats, OrderMoney, YearMonth};
+use crate::stats::orders::OrderStatistics;
+use crate::user_model::repository::{RegistrationError, UserRepository};
+use crate::util::id::Id;
+use crate::util::money::Money;
+use crate::util::short_code::{parse_short_code, ShortCodeError};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::from_extractor_with_state;
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use chrono::{DateTime, FixedOffset, NaiveTime, Utc, Weekday};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Minutes without change after which an order counts as stuck, unless the request says otherwise
+const DEFAULT_STUCK_MINUTES: u64 = 60;
+/// Header with which clients mark retries of the same payment
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .nest("/v1", v1_routes(&state))
+        // Clients predating the versioning keep using the unversioned paths, which are served by v1
+        .merge(v1_routes(&state))
+        .with_state(state)
+}
+
+fn v1_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/users", post(register_user))
+        .route("/users/{user_id}/cost-center", put(set_user_cost_center))
+        .route(
+            "/users/{user_id}/push-subscriptions",
+            post(subscribe_user).delete(unsubscribe_user),
+        )
+        .route("/push/key", get(get_push_key))
+        .route("/sessions", get(get_session).post(login).delete(logout))
+        .route("/sessions/{provider}", post(login_externally))
+        .route("/orders", post(create_order))
+        .route("/orders/{order_id}/status", put(set_status))
+        .route("/orders/{order_id}/copies", post(copy_order))
+        .route(
+            "/orders/{order_id}/deadline",
+            get(get_deadline).put(set_deadline),
+        )
+        .route("/orders/{order_id}/eta", get(get_eta).put(set_eta))
+        .route(
+            "/orders/{order_id}/restaurant",
+            get(get_restaurant).put(set_restaurant),
+        )
+        .route("/orders/{order_id}/events", get(order_events))
+        .route(
+            "/orders/{order_id}/events/since/{sequence_number}",
+            get(get_missed_events),
+        )
+        .route(
+            "/orders/{order_id}/push-subscriptions",
+            post(subscribe_order).delete(unsubscribe_order),
+        )
+        .route("/orders/{order_id}/users", post(add_user))
+        .route("/orders/{order_id}/users/{user_id}/meals", post(add_meal))
+        .route(
+            "/orders/{order_id}/users/{user_id}/last-order",
+            post(add_last_order),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/meals/{meal_id}",
+            put(update_meal),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/meals/{meal_id}/price",
+            get(get_price_breakdown),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/meals/{meal_id}/preparations",
+            put(set_preparations),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/meals/{meal_id}/note",
+            put(set_note),
+        )
+        .route("/orders/{order_id}/office-meals", post(add_office_meal))
+        .route("/orders/{order_id}/shared-meals", post(add_shared_meal))
+        .route(
+            "/orders/{order_id}/meals/{meal_id}/deposit-return",
+            post(return_deposit),
+        )
+        .route("/orders/{order_id}/users/{user_id}/paid", put(set_paid))
+        .route(
+            "/orders/{order_id}/users/{user_id}/payments",
+            get(get_payments).post(add_payment),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/payments/{payment_id}",
+            put(correct_payment).delete(remove_payment),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/held-payments/{payment_id}",
+            post(release_payment).delete(discard_payment),
+        )
+        .route("/orders/{order_id}/users/{user_id}/tip", put(set_tip))
+        .route("/orders/{order_id}/users/{user_id}/ready", put(set_ready))
+        .route(
+            "/orders/{order_id}/users/{user_id}/payment",
+            post(claim_payment),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/payment/confirm",
+            post(confirm_payment),
+        )
+        .route(
+            "/orders/{order_id}/users/{user_id}/payment/dispute",
+            post(dispute_payment),
+        )
+        .route(
+            "/orders/{order_id}/payment-requests",
+            post(create_payment_requests),
+        )
+        .route("/orders/{order_id}/cost-center", put(set_order_cost_center))
+        .route(
+            "/orders/{order_id}/cost-centers",
+            get(get_order_cost_centers),
+        )
+        .route("/orders/{order_id}/totals", get(get_totals))
+        .route("/orders/{order_id}/reminders", get(get_reminders))
+        .route("/orders/{order_id}/summary", get(get_summary))
+        .route("/orders/{order_id}/dashboard", get(get_dashboard))
+        .route("/orders/{order_id}/history", get(get_history))
+        .route("/templates", get(get_templates).post(create_template))
+        .route("/templates/{template_id}", delete(remove_template))
+        .route(
+            "/templates/{template_id}/orders",
+            post(create_order_from_template),
+        )
+        .route("/codes/{code}", get(resolve_code))
+        .route("/stats/money", get(get_money_stats))
+        .route("/stats/orders", get(get_order_statistics))
+        .route("/stats/fairness", get(get_fairness))
+        .route("/stats/cost-centers", get(get_cost_centers))
+        .route("/health", get(get_health))
+        .nest("/admin", admin_routes(state))
+}
+
+/// Maintenance of the deployment, only for operators sending the admin token, see `Operator`.
+fn admin_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/integrity", get(get_integrity))
+        .route("/storage", get(get_storage))
+        .route("/storage/replay", post(replay_storage))
+        .route("/realtime", get(get_realtime))
+        .route("/import", post(import_history))
+        .route("/bank-statements", post(reconcile_bank_statement))
+        .route("/retention", post(enforce_retention))
+        .route("/stuck-orders", get(get_stuck_orders))
+        .route("/sessions", delete(end_all_sessions))
+        .route_layer(from_extractor_with_state::<Operator, _>(state.clone()))
+}
+
+/// Changes the order and summarizes it again for the dashboard.
+fn with_order<T>(
+    state: &AppState,
+    order_id: u32,
+    f: impl FnOnce(&mut Order) -> Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    let id = Id::new(order_id);
+    let mut orders = state.orders();
+    let order = orders.get_order(&id).ok_or(ApiError::OrderNotFound)?;
+    let result = f(order)?;
+    state.summaries().update(&id, order);
+    Ok(result)
+}
+
+/// Fails if the meals of the user were changed since the version in the `If-Match` header, i.e. the sequence number
+/// the client last saw, so concurrent edits don't silently overwrite each other. Clients not sending it always win.
+pub(crate) fn check_meals_version(
+    headers: &HeaderMap,
+    order: &Order,
+    user_id: u32,
+) -> Result<(), ApiError> {
+    let value = match headers.get(header::IF_MATCH) {
+        Some(value) => value.to_str().unwrap_or_default(),
+        None => return Ok(()),
+    };
+    let expected = value
+        .trim()
+        .trim_matches('"')
+        .parse()
+        .map_err(|_| ApiError::InvalidVersion(String::from(value)))?;
+    Ok(order.check_meals_version(&Id::new(user_id), expected)?)
+}
+
+fn read_order<T>(
+    state: &AppState,
+    order_id: u32,
+    f: impl FnOnce(&Order) -> Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    let mut orders = state.orders();
+    let order = orders
+        .get_order(&Id::new(order_id))
+        .ok_or(ApiError::OrderNotFound)?;
+    f(order)
+}
+
+/// Applies a payment and runs the settlement plugins if everybody has paid enough afterwards but not before.
+fn with_settlement<T>(
+    state: &AppState,
+    order_id: u32,
+    order: &mut Order,
+    f: impl FnOnce(&mut Order) -> Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    let was_settled = order.calculate_total_change().is_ok();
+    let result = f(order)?;
+    if !was_settled && order.calculate_total_change().is_ok() {
+        state.plugins().run_settlement(&Id::new(order_id), order);
+    }
+    Ok(result)
+}
+
+async fn register_user(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterUserRequest>,
+) -> Result<(StatusCode, Json<CreatedResponse>), ApiError> {
+    if request.password.as_deref() == Some("") {
+        return Err(AuthError::EmptyPassword.into());
+    }
+    let mut users = state.users_mut();
+    let id = users.register(request.name)?.get_id();
+    if let Some(password) = request.password {
+        state.auth().set_password(id.clone(), &password)?;
+    }
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedResponse { id: id.get_value() }),
+    ))
+}
+
+async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> Result<(StatusCode, Json<SessionResponse>), ApiError> {
+    let user_id = state
+        .users()
+        .get_user_by_name(&request.name)
+        .map(|user| user.get_id())
+        .ok_or(AuthError::WrongCredentials)?;
+    let token = state.auth().login(user_id.clone(), &request.password)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(SessionResponse {
+            token,
+            user_id: user_id.get_value(),
+        }),
+    ))
+}
+
+/// Logs in at an external provider, registering the user on first login.
+///
+/// A user is linked to the account at the provider, not to the name. If the name is already taken, e.g. by a
+/// user with a password, the new user is registered with a number appended instead of taking over that user.
+async fn login_externally(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Json(request): Json<ExternalLoginRequest>,
+) -> Result<(StatusCode, Json<SessionResponse>), ApiError> {
+    let credentials = match request {
+        ExternalLoginRequest::Password { name, password } => {
+            Credentials::Password { name, password }
+        }
+        ExternalLoginRequest::Token { token } => Credentials::Token(token),
+    };
+    let identity = state
+        .auth_providers()
+        .get(&provider)
+        .ok_or_else(|| ApiError::UnknownAuthProvider(provider.clone()))?
+        .authenticate(credentials)
+        .await?;
+    let mut users = state.users_mut();
+    let mut auth = state.auth();
+    let linked = auth
+        .get_linked_user(&provider, identity.get_subject())
+        .filter(|user_id| users.get_user(user_id).is_some());
+    let user_id = match linked {
+        Some(user_id) => user_id,
+        None => {
+            let user_id = register_unique(&mut users, identity.get_name())?;
+            auth.link_account(&provider, identity.get_subject(), user_id.clone());
+            user_id
+        }
+    };
+    let token = auth.start_session(user_id.clone());
+    Ok((
+        StatusCode::CREATED,
+        Json(SessionResponse {
+            token,
+            user_id: user_id.get_value(),
+        }),
+    ))
+}
+
+/// Registers the user with the name, or with the first free name like "anna 2" if it is taken.
+fn register_unique(users: &mut UserRepository, name: &str) -> Result<Id<User>, ApiError> {
+    let mut candidate = String::from(name);
+    for number in 2.. {
+        match users.register(candidate) {
+            Ok(user) => return Ok(user.get_id()),
+            Err(RegistrationError::NameTaken) => candidate = format!("{} {}", name, number),
+            Err(error) => return Err(error.into()),
+        }
+    }
+    unreachable!("Some name is free")
+}
+
+/// Who the session token belongs to and what the user may do in the active orders, e.g. for `whoami` of the CLI.
+async fn get_session(
+    State(state): State<AppState>,
+    caller: Caller,
+) -> Result<Json<CurrentSessionResponse>, ApiError> {
+    let user_id = caller.get_user_id().ok_or(AuthError::NotAuthenticated)?;
+    let name = state
+        .users()
+        .get_user(user_id)
+        .map(|user| user.get_name().clone())
+        .ok_or(ApiError::UserNotFound)?;
+    let mut orders: Vec<OrderRoleResponse> = state
+        .orders()
+        .orders()
+        .filter_map(|(id, order)| {
+            Some(OrderRoleResponse {
+                order_id: id.get_value(),
+                role: Role::in_order(order, user_id)?.into(),
+            })
+        })
+        .collect();
+    orders.sort_by_key(|order| order.order_id);
+    Ok(Json(CurrentSessionResponse {
+        user_id: user_id.get_value(),
+        name,
+        orders,
+    }))
+}
+
+async fn logout(State(state): State<AppState>, headers: HeaderMap) -> Result<StatusCode, ApiError> {
+    let token = bearer_token(&headers)?.ok_or(AuthError::NotAuthenticated)?;
+    if state.auth().logout(token) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AuthError::InvalidSession.into())
+    }
+}
+
+async fn create_order(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(request): Json<CreateOrderRequest>,
+) -> Result<(StatusCode, Json<CreatedOrderResponse>), ApiError> {
+    let manager_id = Id::new(request.manager_id);
+    caller.authorize(&state, |user_id| require_owner(&manager_id, user_id))?;
+    let currency = request.currency.as_deref().map(str::parse).transpose()?;
+    let locale = request.locale.as_deref().map(str::parse).transpose()?;
+    let mut orders = state.orders();
+    let (id, duplicate_of) = match request.restaurant {
+        Some(restaurant) => {
+            let created = orders.create_order_for_restaurant(manager_id, restaurant)?;
+            (created.id, created.duplicate_of)
+        }
+        None => (orders.create_order(manager_id), None),
+    };
+    let order = orders.get_order(&id).expect("Order was just created");
+    order.set_currency(currency)?;
+    order.set_locale(locale);
+    state.announcer().announce_opened(&id, order);
+    state.summaries().update(&id, order);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedOrderResponse {
+            id: id.get_value(),
+            duplicate_of: duplicate_of.as_ref().map(Id::get_value),
+        }),
+    ))
+}
+
+/// Repeats an order, e.g. the team order every Friday, with the same participants and meals.
+async fn copy_order(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<CopyOrderRequest>,
+) -> Result<(StatusCode, Json<CreatedResponse>), ApiError> {
+    let manager_id = Id::new(request.manager_id);
+    caller.authorize(&state, |user_id| require_owner(&manager_id, user_id))?;
+    let mut orders = state.orders();
+    let id = orders
+        .copy_order(&Id::new(order_id), manager_id)
+        .ok_or(ApiError::OrderNotFound)?;
+    let order = orders.get_order(&id).expect("Order was just created");
+    state.announcer().announce_opened(&id, order);
+    state.summaries().update(&id, order);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedResponse { id: id.get_value() }),
+    ))
+}
+
+async fn set_status(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<StatusRequest>,
+) -> Result<StatusCode, ApiError> {
+    let now = state.orders().now();
+    with_order(&state, order_id, |order| {
+        let actor = caller.actor(&state, &order.get_manager_id())?;
+        match request {
+            StatusRequest::Ordering => {
+                order.check_authorized(&actor, &Permission::ChangeStatus)?;
+                state
+                    .plugins()
+                    .check_placement(order, DateTime::from(now))?;
+                order.start_ordering_as(&actor)?
+            }
+            StatusRequest::Ordered { eta } => {
+                order.mark_ordered_as(&actor, parse_time(eta)?)?;
+                state.announcer().announce_eta(&Id::new(order_id), order);
+            }
+            StatusRequest::Delivered => {
+                order.mark_delivered_as(&actor, now)?;
+                state.favorites().remember_order(order);
+                state
+                    .announcer()
+                    .announce_delivered(&Id::new(order_id), order);
+                state.push_delivered(&Id::new(order_id), order);
+            }
+            StatusRequest::Cancelled => order.cancel_as(&actor)?,
+        }
+        state.events().publish(OrderEvent::StatusChanged {
+            order_id,
+            status: order.get_status().to_string(),
+        });
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn get_deadline(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<DeadlineResponse>, ApiError> {
+    let now = DateTime::<Utc>::from(state.orders().now());
+    read_order(&state, order_id, |order| {
+        Ok(Json(DeadlineResponse {
+            deadline: order.get_deadline().map(|deadline| deadline.to_rfc3339()),
+            seconds_left: order
+                .time_until_deadline(now)
+                .map(|left| left.num_seconds()),
+        }))
+    })
+}
+
+async fn set_deadline(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<DeadlineRequest>,
+) -> Result<StatusCode, ApiError> {
+    let deadline = parse_time(request.deadline)?;
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        order.set_deadline(deadline)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// When the restaurant expects to deliver, `null` if the order wasn't placed or the restaurant didn't say.
+async fn get_eta(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<EtaResponse>, ApiError> {
+    let now = DateTime::<Utc>::from(state.orders().now());
+    read_order(&state, order_id, |order| {
+        Ok(Json(EtaResponse {
+            eta: order.get_eta().map(|eta| eta.to_rfc3339()),
+            seconds_left: order
+                .time_until_delivery(now)
+                .map(|left| left.num_seconds()),
+        }))
+    })
+}
+
+async fn set_eta(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<EtaRequest>,
+) -> Result<StatusCode, ApiError> {
+    let eta = parse_time(request.eta)?;
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        order.set_eta(eta)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// Parses an optional RFC 3339 time of a request.
+fn parse_time(time: Option<String>) -> Result<Option<DateTime<Utc>>, ApiError> {
+    time.map(|time| {
+        DateTime::parse_from_rfc3339(&time)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|_| ApiError::InvalidTime)
+    })
+    .transpose()
+}
+
+/// The restaurant of the order, `null` if none was set.
+async fn get_restaurant(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<Option<RestaurantResponse>>, ApiError> {
+    read_order(&state, order_id, |order| {
+        Ok(Json(order.get_restaurant().map(Into::into)))
+    })
+}
+
+/// Sets the restaurant, which closes the order when the restaurant stops taking orders unless it has a deadline.
+async fn set_restaurant(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<RestaurantRequest>,
+) -> Result<StatusCode, ApiError> {
+    let restaurant = restaurant_from_request(request)?;
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        order.set_restaurant(Some(restaurant))?;
+        order.set_deadline_to_cutoff()?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+fn restaurant_from_request(request: RestaurantRequest) -> Result<Restaurant, ApiError> {
+    let mut restaurant = Restaurant::new(request.name)
+        .with_min_order_value(Money::from_cents(request.min_order_value_cents))
+        .with_delivery_fee(Money::from_cents(request.delivery_fee_cents));
+    if let Some(phone) = request.phone {
+        restaurant = restaurant.with_phone(phone);
+    }
+    if let Some(periods) = request.opening_hours {
+        let invalid = |period: &OpeningPeriodEntry| {
+            ApiError::InvalidOpeningHours(format!(
+                "{} {}-{}",
+                period.day, period.opens, period.closes
+            ))
+        };
+        let offset_minutes = request.utc_offset_minutes;
+        let offset = offset_minutes
+            .checked_mul(60)
+            .and_then(FixedOffset::east_opt)
+            .ok_or_else(|| ApiError::InvalidOpeningHours(offset_minutes.to_string()))?;
+        let mut hours = OpeningHours::new(offset);
+        for period in &periods {
+            let day: Weekday = period.day.parse().map_err(|_| invalid(period))?;
+            let opens =
+                NaiveTime::parse_from_str(&period.opens, "%H:%M").map_err(|_| invalid(period))?;
+            let closes =
+                NaiveTime::parse_from_str(&period.closes, "%H:%M").map_err(|_| invalid(period))?;
+            hours = hours.with_period(OpeningPeriod::new(day, opens, closes));
+        }
+        for day in &request.closed_days {
+            let day = day
+                .parse()
+                .map_err(|_| ApiError::InvalidOpeningHours(day.clone()))?;
+            hours = hours.with_closed_day(day);
+        }
+        for cutoff in &request.cutoffs {
+            let invalid =
+                || ApiError::InvalidOpeningHours(format!("{} {}", cutoff.day, cutoff.time));
+            let day: Weekday = cutoff.day.parse().map_err(|_| invalid())?;
+            let time = NaiveTime::parse_from_str(&cutoff.time, "%H:%M").map_err(|_| invalid())?;
+            hours = hours.with_cutoff(day, time);
+        }
+        restaurant = restaurant.with_opening_hours(hours);
+    } else if !request.closed_days.is_empty() || !request.cutoffs.is_empty() {
+        return Err(ApiError::InvalidOpeningHours(String::from(
+            "closed days and cutoffs without opening hours",
+        )));
+    }
+    Ok(restaurant)
+}
+
+async fn get_templates(State(state): State<AppState>) -> Json<OrderTemplatesResponse> {
+    Json(OrderTemplatesResponse {
+        templates: state.templates().templates().map(Into::into).collect(),
+    })
+}
+
+/// Stores how a recurring group order starts, so it can be opened with one call each time.
+async fn create_template(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(request): Json<OrderTemplateRequest>,
+) -> Result<(StatusCode, Json<CreatedResponse>), ApiError> {
+    let manager_id = Id::new(request.manager_id);
+    caller.authorize(&state, |user_id| require_owner(&manager_id, user_id))?;
+    let mut template = OrderTemplate::new(
+        request.name,
+        manager_id,
+        restaurant_from_request(request.restaurant)?,
+    );
+    for user_id in request.participants {
+        template = template.with_participant(Id::new(user_id));
+    }
+    if let Some(deadline) = request.deadline {
+        let time = NaiveTime::parse_from_str(&deadline, "%H:%M")
+            .map_err(|_| ApiError::InvalidTimeOfDay(deadline))?;
+        template = template.with_deadline(time);
+    }
+    let mut templates = state.templates();
+    let id = templates.add(template.clone());
+    save_templates(
+        &state,
+        &templates,
+        TemplateWrite::Added(id.clone(), Box::new(template)),
+    )?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedResponse { id: id.get_value() }),
+    ))
+}
+
+async fn remove_template(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(template_id): Path<u32>,
+) -> Result<StatusCode, ApiError> {
+    let id = Id::new(template_id);
+    let mut templates = state.templates();
+    let manager_id = templates
+        .get(&id)
+        .ok_or(ApiError::TemplateNotFound)?
+        .get_manager_id();
+    caller.authorize(&state, |user_id| require_owner(&manager_id, user_id))?;
+    templates.remove(&id);
+    save_templates(&state, &templates, TemplateWrite::Removed(id))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn save_templates(
+    state: &AppState,
+    templates: &OrderTemplates,
+    write: TemplateWrite,
+) -> Result<(), ApiError> {
+    state
+        .save_templates(templates, write)
+        .map_err(|error| ApiError::Storage(error.to_string()))
+}
+
+/// Opens the order of the template, e.g. the pizza order of this Friday, managed by the manager of the template.
+async fn create_order_from_template(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(template_id): Path<u32>,
+) -> Result<(StatusCode, Json<CreatedOrderResponse>), ApiError> {
+    let template = state
+        .templates()
+        .get(&Id::new(template_id))
+        .cloned()
+        .ok_or(ApiError::TemplateNotFound)?;
+    let manager_id = template.get_manager_id();
+    caller.authorize(&state, |user_id| require_owner(&manager_id, user_id))?;
+    let mut orders = state.orders();
+    let created = orders.create_order_from_template(&template)?;
+    let order = orders
+        .get_order(&created.id)
+        .expect("Order was just created");
+    state.announcer().announce_opened(&created.id, order);
+    state.summaries().update(&created.id, order);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedOrderResponse {
+            id: created.id.get_value(),
+            duplicate_of: created.duplicate_of.as_ref().map(Id::get_value),
+        }),
+    ))
+}
+
+async fn add_user(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<AddUserRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(request.user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        order.add_user(Id::new(request.user_id))?;
+        state.events().publish(OrderEvent::UserJoined {
+            order_id,
+            user_id: request.user_id,
+        });
+        Ok(StatusCode::CREATED)
+    })
+}
+
+async fn add_meal(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<AddMealRequest>,
+) -> Result<(StatusCode, Json<CreatedResponse>), ApiError> {
+    let actor = caller.actor(&state, &Id::new(user_id))?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.check_meal_details(
+            request.note.as_ref(),
+            request.deposit_cents.map(Money::from_cents),
+        )?;
+        let meal = order.add_meal_as(
+            &actor,
+            Id::new(user_id),
+            request.meal_id,
+            request.variety,
+            Money::from_cents(request.price_cents),
+        )?;
+        let meal = match request.note {
+            Some(note) => {
+                let id = meal.get_id();
+                order.set_note_as(&actor, Id::new(user_id), id, Some(note))?
+            }
+            None => meal,
+        };
+        let meal = match request.deposit_cents {
+            Some(deposit) => {
+                let id = meal.get_id();
+                order.set_deposit(&id, Money::from_cents(deposit))?
+            }
+            None => meal,
+        };
+        state.events().publish(OrderEvent::MealAdded {
+            order_id,
+            user_id: Some(user_id),
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+        });
+        Ok((
+            StatusCode::CREATED,
+            Json(CreatedResponse {
+                id: meal.get_id().get_value(),
+            }),
+        ))
+    })
+}
+
+/// Adds the meals the user had in their last delivered order, including specials and preparations.
+async fn add_last_order(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<CreatedMealsResponse>), ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        let last_order = state
+            .favorites()
+            .get_last_order(&Id::new(user_id))
+            .ok_or(ApiError::NoLastOrder)?
+            .to_vec();
+        let ids = order.add_last_order_for_user(Id::new(user_id), &last_order)?;
+        for meal in &last_order {
+            state.events().publish(OrderEvent::MealAdded {
+                order_id,
+                user_id: Some(user_id),
+                meal_id: meal.get_meal_id().clone(),
+                variety: meal.get_variety().clone(),
+            });
+        }
+        Ok((
+            StatusCode::CREATED,
+            Json(CreatedMealsResponse {
+                ids: ids.iter().map(Id::get_value).collect(),
+            }),
+        ))
+    })
+}
+
+async fn update_meal(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, meal_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<AddMealRequest>,
+) -> Result<StatusCode, ApiError> {
+    let actor = caller.actor(&state, &Id::new(user_id))?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.update_meal_as(
+            &actor,
+            Id::new(user_id),
+            Id::new(meal_id),
+            request.meal_id,
+            request.variety,
+            Money::from_cents(request.price_cents),
+        )?;
+        if let Some(note) = request.note {
+            order.set_note_as(&actor, Id::new(user_id), Id::new(meal_id), Some(note))?;
+        }
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// Why the meal costs what it costs, for receipts and clients.
+async fn get_price_breakdown(
+    State(state): State<AppState>,
+    Path((order_id, user_id, meal_id)): Path<(u32, u32, u32)>,
+) -> Result<Json<PriceBreakdownResponse>, ApiError> {
+    read_order(&state, order_id, |order| {
+        let meal = order
+            .get_user_meals(&Id::new(user_id))
+            .ok_or(OrderError::UserNotParticipating)?
+            .get_meal(&Id::new(meal_id))
+            .ok_or(OrderError::MealNotFound)?;
+        Ok(Json((&meal.price_breakdown()).into()))
+    })
+}
+
+async fn set_preparations(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, meal_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<PreparationsRequest>,
+) -> Result<StatusCode, ApiError> {
+    let actor = caller.actor(&state, &Id::new(user_id))?;
+    let preparations = request
+        .preparations
+        .iter()
+        .map(|code| code.parse())
+        .collect::<Result<_, _>>()?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.set_preparations_as(&actor, Id::new(user_id), Id::new(meal_id), preparations)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn set_note(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, meal_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<NoteRequest>,
+) -> Result<StatusCode, ApiError> {
+    let actor = caller.actor(&state, &Id::new(user_id))?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.set_note_as(&actor, Id::new(user_id), Id::new(meal_id), request.note)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// Sets the cost center the meals of the user are billed to, unless their order has one.
+async fn set_user_cost_center(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(user_id): Path<u32>,
+    Json(request): Json<CostCenterRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = Id::new(user_id);
+    caller.authorize(&state, |caller_id| require_owner(&user_id, caller_id))?;
+    if state
+        .users_mut()
+        .set_cost_center(&user_id, request.cost_center)
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::UserNotFound)
+    }
+}
+
+/// Bills the whole order to the cost center, e.g. a department lunch, instead of to those of the participants.
+async fn set_order_cost_center(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<CostCenterRequest>,
+) -> Result<StatusCode, ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        order.set_cost_center(request.cost_center);
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn get_order_cost_centers(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<CostCentersResponse>, ApiError> {
+    let users = state.users();
+    read_order(&state, order_id, |order| {
+        Ok(Json(CostCentersResponse::from(
+            &CostCenterReport::from_orders(vec![order], &users),
+        )))
+    })
+}
+
+async fn add_office_meal(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<AddMealRequest>,
+) -> Result<(StatusCode, Json<CreatedResponse>), ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        order.check_meal_details(None, request.deposit_cents.map(Money::from_cents))?;
+        let meal = order.add_office_meal(
+            request.meal_id,
+            request.variety,
+            Money::from_cents(request.price_cents),
+        )?;
+        let meal = match request.deposit_cents {
+            Some(deposit) => {
+                let id = meal.get_id();
+                order.set_deposit(&id, Money::from_cents(deposit))?
+            }
+            None => meal,
+        };
+        state.events().publish(OrderEvent::MealAdded {
+            order_id,
+            user_id: None,
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+        });
+        Ok((
+            StatusCode::CREATED,
+            Json(CreatedResponse {
+                id: meal.get_id().get_value(),
+            }),
+        ))
+    })
+}
+
+/// Adds a meal for everybody, its price split among the participants who ordered meals. Only the manager may add it,
+/// as it ends up on everybody's bill.
+async fn add_shared_meal(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<AddMealRequest>,
+) -> Result<(StatusCode, Json<CreatedResponse>), ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        order.check_meal_details(None, request.deposit_cents.map(Money::from_cents))?;
+        let meal = order.add_shared_meal(
+            request.meal_id,
+            request.variety,
+            Money::from_cents(request.price_cents),
+        )?;
+        let meal = match request.deposit_cents {
+            Some(deposit) => {
+                let id = meal.get_id();
+                order.set_deposit(&id, Money::from_cents(deposit))?
+            }
+            None => meal,
+        };
+        state.events().publish(OrderEvent::MealAdded {
+            order_id,
+            user_id: None,
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+        });
+        Ok((
+            StatusCode::CREATED,
+            Json(CreatedResponse {
+                id: meal.get_id().get_value(),
+            }),
+        ))
+    })
+}
+
+/// Refunds the deposit of a returned bottle or can after delivery, as change for whoever paid the meal.
+async fn return_deposit(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, meal_id)): Path<(u32, u32)>,
+) -> Result<Json<DepositRefundResponse>, ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        let refund = order.return_deposit(&Id::new(meal_id))?;
+        Ok(Json(DepositRefundResponse {
+            refund_cents: refund.get_total_cents(),
+        }))
+    })
+}
+
+async fn set_paid(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<AmountRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        with_settlement(&state, order_id, order, |order| {
+            Ok(order
+                .set_paid_for_user(Id::new(user_id), Money::from_cents(request.amount_cents))?)
+        })?;
+        state.events().publish(OrderEvent::PaymentRecorded {
+            order_id,
+            user_id,
+            amount_cents: request.amount_cents,
+        });
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// Links or QR code payloads the manager can send to everybody who still owes money.
+async fn create_payment_requests(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<u32>,
+    Json(request): Json<PaymentRequestsRequest>,
+) -> Result<Json<PaymentRequestsResponse>, ApiError> {
+    let method = match request {
+        PaymentRequestsRequest::PaypalMe(profile) => PaymentMethod::PayPalMe(profile.parse()?),
+        PaymentRequestsRequest::Sepa { name, iban, bic } => {
+            PaymentMethod::Sepa(SepaRecipient::new(name, iban.parse()?, bic)?)
+        }
+    };
+    let defaults = state.orders().get_default_format();
+    read_order(&state, order_id, |order| {
+        caller.authorize(&state, |user_id| require_manager(order, user_id))?;
+        let currency = order.get_money_format(defaults).get_currency();
+        let requests = payment_requests(&Id::new(order_id), order, &method, currency)?;
+        Ok(Json(PaymentRequestsResponse {
+            requests: requests.iter().map(Into::into).collect(),
+        }))
+    })
+}
+
+async fn get_payments(
+    State(state): State<AppState>,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+) -> Result<Json<PaymentsResponse>, ApiError> {
+    read_order(&state, order_id, |order| {
+        let meals = order
+            .get_user_meals(&Id::new(user_id))
+            .ok_or(OrderError::UserNotParticipating)?;
+        Ok(Json(PaymentsResponse {
+            payments: meals.payments().iter().map(Into::into).collect(),
+            paid_cents: meals.get_paid().get_total_cents(),
+            held: meals.held_payments().iter().map(Into::into).collect(),
+        }))
+    })
+}
+
+/// Adds an installment to what the user paid, unlike `set_paid` which replaces it.
+///
+/// A client may send an `Idempotency-Key` header to retry safely. A retry answers 200 with the ID of the first
+/// payment, a suspected duplicate 202 with a warning, as it only counts once the manager releases it.
+async fn add_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<AmountRequest>,
+) -> Result<(StatusCode, Json<ReceivedPaymentResponse>), ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    let key = headers
+        .get(IDEMPOTENCY_KEY)
+        .and_then(|key| key.to_str().ok())
+        .map(String::from);
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        let received = with_settlement(&state, order_id, order, |order| {
+            Ok(order.receive_payment_for_user(
+                Id::new(user_id),
+                Money::from_cents(request.amount_cents),
+                key,
+            )?)
+        })?;
+        let status = match received {
+            ReceivedPayment::Booked(_) => {
+                state.events().publish(OrderEvent::PaymentRecorded {
+                    order_id,
+                    user_id,
+                    amount_cents: request.amount_cents,
+                });
+                StatusCode::CREATED
+            }
+            ReceivedPayment::Repeated(_) => StatusCode::OK,
+            ReceivedPayment::Held(_, _) => StatusCode::ACCEPTED,
+        };
+        Ok((status, Json((&received).into())))
+    })
+}
+
+async fn correct_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, payment_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<AmountRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        with_settlement(&state, order_id, order, |order| {
+            Ok(order.correct_payment_for_user(
+                Id::new(user_id),
+                Id::new(payment_id),
+                Money::from_cents(request.amount_cents),
+            )?)
+        })?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn remove_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, payment_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.remove_payment_for_user(Id::new(user_id), Id::new(payment_id))?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// Books a payment held as suspected duplicate, once the manager confirmed it was paid twice on purpose.
+async fn release_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, payment_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |caller_id| require_manager(order, caller_id))?;
+        check_meals_version(&headers, order, user_id)?;
+        let amount = with_settlement(&state, order_id, order, |order| {
+            Ok(order.release_payment_for_user(Id::new(user_id), Id::new(payment_id))?)
+        })?;
+        state.events().publish(OrderEvent::PaymentRecorded {
+            order_id,
+            user_id,
+            amount_cents: amount.get_total_cents(),
+        });
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn discard_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id, payment_id)): Path<(u32, u32, u32)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |caller_id| require_manager(order, caller_id))?;
+        check_meals_version(&headers, order, user_id)?;
+        order.discard_payment_for_user(Id::new(user_id), Id::new(payment_id))?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn set_tip(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<AmountRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.set_tip_for_user(Id::new(user_id), Money::from_cents(request.amount_cents))?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn set_ready(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<ReadyRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.set_ready_for_user(Id::new(user_id), request.ready)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn claim_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<PaymentClaimRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.authorize(&state, |caller_id| {
+        require_owner(&Id::new(user_id), caller_id)
+    })?;
+    with_order(&state, order_id, |order| {
+        check_meals_version(&headers, order, user_id)?;
+        order.claim_payment_for_user(
+            Id::new(user_id),
+            Money::from_cents(request.amount_cents),
+            request.method,
+        )?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn confirm_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |caller_id| require_manager(order, caller_id))?;
+        check_meals_version(&headers, order, user_id)?;
+        let amount = with_settlement(&state, order_id, order, |order| {
+            Ok(order.confirm_payment_for_user(Id::new(user_id))?)
+        })?;
+        state.events().publish(OrderEvent::PaymentRecorded {
+            order_id,
+            user_id,
+            amount_cents: amount.get_total_cents(),
+        });
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn dispute_payment(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((order_id, user_id)): Path<(u32, u32)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    with_order(&state, order_id, |order| {
+        caller.authorize(&state, |caller_id| require_manager(order, caller_id))?;
+        check_meals_version(&headers, order, user_id)?;
+        order.dispute_payment_for_user(Id::new(user_id))?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn get_reminders(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<UserIdsResponse>, ApiError> {
+    read_order(&state, order_id, |order| {
+        let mut user_ids: Vec<u32> = order.users_to_remind().iter().map(Id::get_value).collect();
+        user_ids.sort_unstable();
+        Ok(Json(UserIdsResponse { user_ids }))
+    })
+}
+
+async fn get_totals(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<TotalsResponse>, ApiError> {
+    read_order(&state, order_id, |order| {
+        Ok(Json(TotalsResponse::from(order)))
+    })
+}
+
+async fn get_history(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<HistoryResponse>, ApiError> {
+    read_order(&state, order_id, |order| {
+        Ok(Json(HistoryResponse::from(order)))
+    })
+}
+
+/// The events a client missed after the sequence number of the dashboard or replay it applied last, e.g. while
+/// its WebSocket was disconnected.
+async fn get_missed_events(
+    State(state): State<AppState>,
+    Path((order_id, sequence_number)): Path<(u32, usize)>,
+) -> Result<Json<ReplayResponse>, ApiError> {
+    read_order(&state, order_id, |order| {
+        let events = OrderEvent::replay(order_id, order, sequence_number)
+            .ok_or(ApiError::HistoryUnavailable(sequence_number))?;
+        Ok(Json(ReplayResponse {
+            events,
+            sequence_number: order.get_sequence_number(),
+        }))
+    })
+}
+
+async fn get_summary(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<SummaryResponse>, ApiError> {
+    let defaults = state.orders().get_default_format();
+    read_order(&state, order_id, |order| {
+        Ok(Json(SummaryResponse {
+            summary: plain_summary(order, order.get_money_format(defaults)),
+        }))
+    })
+}
+
+/// Served from the summary cache, so it neither waits for changes nor goes through the meals.
+async fn get_dashboard(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+) -> Result<Json<DashboardResponse>, ApiError> {
+    let id = Id::new(order_id);
+    let summary = match state.summaries().get(&id) {
+        Some(summary) => summary,
+        // Orders created without the API, e.g. imported ones, are summarized on first access
+        None => {
+            let mut orders = state.orders();
+            if let Some(archived) = orders.get_archived(&id) {
+                Arc::new(archived.get_summary().clone())
+            } else {
+                let order = orders.get_order(&id).ok_or(ApiError::OrderNotFound)?;
+                state.summaries().update(&id, order)
+            }
+        }
+    };
+    Ok(Json(DashboardResponse::from(&*summary)))
+}
+
+/// Resolves a short code, e.g. typed into a chat, to the order or user it stands for.
+async fn resolve_code(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<ResolvedCodeResponse>, ApiError> {
+    match parse_short_code::<Order>(&code) {
+        Ok(id) => {
+            state
+                .orders()
+                .get_order(&id)
+                .ok_or(ApiError::OrderNotFound)?;
+            Ok(Json(ResolvedCodeResponse::Order(id.get_value())))
+        }
+        Err(ShortCodeError::WrongPrefix(_)) => {
+            let id = parse_short_code::<User>(&code)?;
+            if !state.users().contains(&id) {
+                return Err(ApiError::UserNotFound);
+            }
+            Ok(Json(ResolvedCodeResponse::User(id.get_value())))
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// The key browsers need to subscribe to push messages of this server.
+async fn get_push_key(State(state): State<AppState>) -> Result<Json<PushKeyResponse>, ApiError> {
+    let web_push = state.web_push().ok_or(ApiError::PushNotConfigured)?;
+    Ok(Json(PushKeyResponse {
+        public_key: web_push.get_vapid().get_public_key(),
+    }))
+}
+
+/// Subscribes a browser of the user to push messages about all orders they take part in.
+async fn subscribe_user(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(user_id): Path<u32>,
+    Json(request): Json<PushSubscriptionRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = Id::new(user_id);
+    caller.authorize(&state, |caller_id| require_owner(&user_id, caller_id))?;
+    if !state.users().contains(&user_id) {
+        return Err(ApiError::UserNotFound);
+    }
+    let subscription = push_subscription(request)?;
+    Ok(subscribed(
+        state
+            .push_subscriptions()
+            .subscribe_user(user_id, subscription),
+    ))
+}
+
+async fn unsubscribe_user(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(user_id): Path<u32>,
+    Json(request): Json<PushUnsubscribeRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = Id::new(user_id);
+    caller.authorize(&state, |caller_id| require_owner(&user_id, caller_id))?;
+    if state
+        .push_subscriptions()
+        .unsubscribe_user(&user_id, &request.endpoint)
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::PushSubscriptionNotFound)
+    }
+}
+
+/// Subscribes a browser to push messages about the order, e.g. one showing it without anybody logged in.
+async fn subscribe_order(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+    Json(request): Json<PushSubscriptionRequest>,
+) -> Result<StatusCode, ApiError> {
+    read_order(&state, order_id, |_| Ok(()))?;
+    let subscription = push_subscription(request)?;
+    Ok(subscribed(
+        state
+            .push_subscriptions()
+            .subscribe_order(Id::new(order_id), subscription),
+    ))
+}
+
+async fn unsubscribe_order(
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+    Json(request): Json<PushUnsubscribeRequest>,
+) -> Result<StatusCode, ApiError> {
+    if state
+        .push_subscriptions()
+        .unsubscribe_order(&Id::new(order_id), &request.endpoint)
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::PushSubscriptionNotFound)
+    }
+}
+
+fn push_subscription(request: PushSubscriptionRequest) -> Result<PushSubscription, ApiError> {
+    Ok(PushSubscription::new(
+        request.endpoint,
+        &request.keys.p256dh,
+        &request.keys.auth,
+    )?)
+}
+
+/// Created for a new subscription, no content if the keys of a known one were replaced.
+fn subscribed(new: bool) -> StatusCode {
+    if new {
+        StatusCode::CREATED
+    } else {
+        StatusCode::NO_CONTENT
+    }
+}
+
+async fn get_money_stats(State(state): State<AppState>) -> Json<MoneyStatsResponse> {
+    let orders: Vec<OrderMoney> = state
+        .orders()
+        .orders_with_creation_time()
+        .map(|(order, created_at)| OrderMoney::from_order(order, YearMonth::from(created_at)))
+        .collect();
+    Json(MoneyStatsResponse::from(&MoneyStats::calculate(&orders)))
+}
+
+/// Statistics over all orders ever made, including the archived and imported ones.
+async fn get_order_statistics(State(state): State<AppState>) -> Json<OrderStatisticsResponse> {
+    let orders = state.orders();
+    Json(OrderStatisticsResponse::from(&OrderStatistics::calculate(
+        orders.stored_orders(),
+    )))
+}
+
+/// Shows who organized orders and covered fees more or less often than their share.
+async fn get_fairness(State(state): State<AppState>) -> Json<FairnessResponse> {
+    let orders = state.orders();
+    Json(FairnessResponse::from(&FairnessReport::calculate(
+        orders.stored_orders(),
+    )))
+}
+
+/// Amounts of all orders which were not cancelled by cost center, for re-billing e.g. department lunches.
+async fn get_cost_centers(State(state): State<AppState>) -> Json<CostCentersResponse> {
+    let users = state.users();
+    let orders = state.orders();
+    let report = CostCenterReport::from_orders(
+        orders
+            .stored_orders()
+            .filter(|order| order.get_status() != &OrderStatus::Cancelled),
+        &users,
+    );
+    Json(CostCentersResponse::from(&report))
+}
+
+async fn get_integrity(State(state): State<AppState>) -> Json<IntegrityResponse> {
+    let report = state.verify_integrity();
+    Json(IntegrityResponse {
+        healthy: report.is_healthy(),
+        issues: report.issues().iter().map(ToString::to_string).collect(),
+    })
+}
+
+/// Answers as long as the server is up, telling load balancers and monitoring whether changes are only kept in memory.
+async fn get_health(State(state): State<AppState>) -> Json<HealthResponse> {
+    let ephemeral = state.storage_status().is_ephemeral();
+    Json(HealthResponse {
+        status: String::from(if ephemeral { "degraded" } else { "ok" }),
+        ephemeral,
+    })
+}
+
+async fn get_storage(State(state): State<AppState>) -> Json<StorageResponse> {
+    Json(StorageResponse::from(&*state.storage_status()))
+}
+
+/// Replays the writes queued while running ephemeral, e.g. once the share with the template file is back.
+async fn replay_storage(State(state): State<AppState>) -> Result<Json<StorageResponse>, ApiError> {
+    state
+        .replay_storage()
+        .map_err(|error| ApiError::Storage(error.to_string()))?;
+    Ok(Json(StorageResponse::from(&*state.storage_status())))
+}
+
+/// Shows whether realtime clients keep up with the events.
+async fn get_realtime(State(state): State<AppState>) -> Json<RealtimeResponse> {
+    let metrics = state.events().metrics();
+    Json(RealtimeResponse {
+        connections: metrics.get_connections(),
+        delivered: metrics.get_delivered(),
+        dropped: metrics.get_dropped(),
+        resyncs: metrics.get_resyncs(),
+        queue_limit: state.events().get_queue_limit(),
+    })
+}
+
+/// Orders nobody changed for a while, which operators may have to cancel or close.
+async fn get_stuck_orders(
+    State(state): State<AppState>,
+    Query(query): Query<StuckOrdersQuery>,
+) -> Json<StuckOrdersResponse> {
+    let idle_minutes = query.idle_minutes.unwrap_or(DEFAULT_STUCK_MINUTES);
+    let stuck = state
+        .orders()
+        .stuck_orders(Duration::from_secs(idle_minutes * 60));
+    Json(StuckOrdersResponse {
+        orders: stuck
+            .into_iter()
+            .map(|(order_id, status, idle)| StuckOrderEntry {
+                order_id: order_id.get_value(),
+                status: status.to_string(),
+                idle_minutes: idle.as_secs() / 60,
+            })
+            .collect(),
+    })
+}
+
+/// Ends every session, e.g. after tokens leaked, so all users have to log in again.
+async fn end_all_sessions(State(state): State<AppState>) -> Json<SessionsEndedResponse> {
+    Json(SessionsEndedResponse {
+        ended: state.auth().end_all_sessions(),
+    })
+}
+
+/// Imports historical orders from a spreadsheet, or only validates it for a dry run.
+async fn import_history(
+    State(state): State<AppState>,
+    Json(request): Json<ImportRequest>,
+) -> (StatusCode, Json<ImportResponse>) {
+    let result = if request.dry_run {
+        spreadsheet::dry_run(&request.csv, &state.users())
+    } else {
+        let mut users = state.users_mut();
+        state.orders().import_history(&request.csv, &mut users)
+    };
+    match result {
+        Ok(report) => (StatusCode::OK, Json(ImportResponse::from(&report))),
+        Err(errors) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ImportResponse {
+                errors: errors.iter().map(ToString::to_string).collect(),
+                ..ImportResponse::default()
+            }),
+        ),
+    }
+}
+
+/// Purges archived orders right away instead of waiting for the background job.
+async fn enforce_retention(State(state): State<AppState>) -> Json<RetentionResponse> {
+    Json(RetentionResponse::from(&state.enforce_retention()))
+}
+
+/// Books the incoming transfers of a bank statement as payments and lists those which need manual review.
+///
+/// Only operators may send statements, as every matching transfer marks a debt as paid.
+async fn reconcile_bank_statement(
+    State(state): State<AppState>,
+    Json(request): Json<BankStatementRequest>,
+) -> (StatusCode, Json<BankStatementResponse>) {
+    let transfers = match request.format {
+        StatementFormat::Csv => bank_statement::parse_csv(&request.content),
+        StatementFormat::Camt => bank_statement::parse_camt(&request.content).map_err(|e| vec![e]),
+    };
+    let transfers = match transfers {
+        Ok(transfers) => transfers,
+        Err(errors) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(BankStatementResponse {
+                    errors: errors.iter().map(ToString::to_string).collect(),
+                    ..BankStatementResponse::default()
+                }),
+            )
+        }
+    };
+    let mut orders = state.orders();
+    let report = orders.reconcile_transfers(transfers);
+    for matched in report.matched() {
+        let id = matched.get_order_id();
+        if let Some(order) = orders.get_order(&id) {
+            state.summaries().update(&id, order);
+        }
+    }
+    (StatusCode::OK, Json(BankStatementResponse::from(&report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::v1::dto::{
+        ErrorResponse, HistoryEntryResponse, MealCountResponse, MonthlyMoneyResponse,
+        MonthlyTipResponse, MovedTemplateResponse, OrderTemplateResponse, PriceComponentEntry,
+        RoleResponse, RoundingAdjustmentResponse, SplitAmountResponse, UserFairnessResponse,
+    };
+    use crate::auth::provider::{
+        AuthFuture, AuthProvider, AuthProviders, ExternalIdentity, ProviderError,
+    };
+    use crate::notifications::announcement::{Announcer, Channel};
+    use crate::notifications::web_push::{Vapid, WebPush};
+    use crate::order_model::order::OrderStatus;
+    use crate::order_model::retention::RetentionPolicy;
+    use crate::persistence::templates;
+    use crate::plugins::registry::{PlacementCheck, PluginRegistry, SettlementAction};
+    use crate::util::clock::TestClock;
+    use crate::util::short_code::short_code;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use rstest::rstest;
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    const ADMIN_TOKEN: &str = "operator secret";
+
+    async fn send(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Vec<u8>) {
+        send_with_token(state, None, method, uri, body).await
+    }
+
+    async fn send_with_token(
+        state: &AppState,
+        token: Option<&str>,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Vec<u8>) {
+        let authorization = token.map(|token| format!("Bearer {}", token));
+        let headers: Vec<_> = authorization
+            .iter()
+            .map(|value| ("authorization", value.as_str()))
+            .collect();
+        send_with_headers(state, &headers, method, uri, body).await
+    }
+
+    async fn send_with_headers(
+        state: &AppState,
+        headers: &[(&str, &str)],
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Vec<u8>) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = request
+            .body(match body {
+                Some(body) => Body::from(body.to_string()),
+                None => Body::empty(),
+            })
+            .unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, bytes.to_vec())
+    }
+
+    /// Sent with the token operators use for the `/admin` routes.
+    async fn send_as_operator(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Vec<u8>) {
+        send_with_token(state, Some(ADMIN_TOKEN), method, uri, body).await
+    }
+
+    fn parse<T: DeserializeOwned>(body: &[u8]) -> T {
+        serde_json::from_slice(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn order_can_be_created() {
+        // Given:
+        let state = AppState::new();
+
+        // When:
+        let (status, body) = send(&state, "POST", "/orders", Some(json!({"manager_id": 0}))).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(parse::<CreatedResponse>(&body), CreatedResponse { id: 0 });
+        assert!(state.orders().get_order(&Id::new(0)).is_some());
+    }
+
+    #[rstest(uri, case("/v1/orders"), case("/orders"))]
+    #[tokio::test]
+    async fn order_can_be_created_with_and_without_version(uri: &str) {
+        // Given:
+        let state = AppState::new();
+
+        // When:
+        let (status, body) = send(&state, "POST", uri, Some(json!({"manager_id": 0}))).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(parse::<CreatedResponse>(&body), CreatedResponse { id: 0 });
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("Peter", StatusCode::CREATED),
+        case("  ", StatusCode::UNPROCESSABLE_ENTITY),
+        case("anna", StatusCode::CONFLICT)
+    )]
+    #[tokio::test]
+    async fn user_can_be_registered(name: &str, expected: StatusCode) {
+        // Given:
+        let state = AppState::new();
+        state.users_mut().register(String::from("Anna")).unwrap();
+
+        // When:
+        let (status, _) = send(&state, "POST", "/users", Some(json!({ "name": name }))).await;
+
+        // Then:
+        assert_eq!(status, expected);
+    }
+
+    async fn log_in(state: &AppState, name: &str) -> String {
+        let id = state
+            .users_mut()
+            .register(String::from(name))
+            .unwrap()
+            .get_id();
+        state.auth().set_password(id, "secret").unwrap();
+        let (status, body) = send(
+            state,
+            "POST",
+            "/sessions",
+            Some(json!({ "name": name, "password": "secret" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        parse::<SessionResponse>(&body).token
+    }
+
+    #[tokio::test]
+    async fn registered_user_can_log_in_and_out() {
+        // Given:
+        let state = AppState::new();
+        send(
+            &state,
+            "POST",
+            "/users",
+            Some(json!({ "name": "Anna", "password": "margherita" })),
+        )
+        .await;
+
+        // When:
+        let (wrong, _) = send(
+            &state,
+            "POST",
+            "/sessions",
+            Some(json!({ "name": "Anna", "password": "salami" })),
+        )
+        .await;
+        let (status, body) = send(
+            &state,
+            "POST",
+            "/sessions",
+            Some(json!({ "name": "anna", "password": "margherita" })),
+        )
+        .await;
+        let session = parse::<SessionResponse>(&body);
+        let (logged_out, _) =
+            send_with_token(&state, Some(&session.token), "DELETE", "/sessions", None).await;
+        let (again, _) =
+            send_with_token(&state, Some(&session.token), "DELETE", "/sessions", None).await;
+
+        // Then:
+        assert_eq!(wrong, StatusCode::UNAUTHORIZED);
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(session.user_id, 0);
+        assert_eq!(logged_out, StatusCode::NO_CONTENT);
+        assert_eq!(again, StatusCode::UNAUTHORIZED);
+    }
+
+    /// Directory knowing Anna with password "salami", her account is "uid=anna".
+    struct Directory;
+
+    impl AuthProvider for Directory {
+        fn get_name(&self) -> &str {
+            "ldap"
+        }
+
+        fn authenticate(&self, credentials: Credentials) -> AuthFuture {
+            Box::pin(async move {
+                match credentials {
+                    Credentials::Password { name, password } if password == "salami" => Ok(
+                        ExternalIdentity::new(format!("uid={}", name.to_lowercase()), name),
+                    ),
+                    Credentials::Password { .. } => Err(ProviderError::WrongCredentials),
+                    Credentials::Token(_) => Err(ProviderError::UnsupportedCredentials),
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn external_user_is_registered_on_first_login() {
+        // Given:
+        let mut providers = AuthProviders::new();
+        providers.add(Box::new(Directory));
+        let state = AppState::new().with_auth_providers(providers);
+        send(&state, "POST", "/users", Some(json!({ "name": "Anna" }))).await;
+        let login = json!({ "name": "Anna", "password": "salami" });
+
+        // When:
+        let (first, first_body) = send(&state, "POST", "/sessions/ldap", Some(login.clone())).await;
+        let (second, second_body) = send(&state, "POST", "/sessions/ldap", Some(login)).await;
+        let (wrong, _) = send(
+            &state,
+            "POST",
+            "/sessions/ldap",
+            Some(json!({ "name": "Anna", "password": "tonno" })),
+        )
+        .await;
+        let (token, _) = send(
+            &state,
+            "POST",
+            "/sessions/ldap",
+            Some(json!({ "token": "abc" })),
+        )
+        .await;
+        let (unknown, _) = send(
+            &state,
+            "POST",
+            "/sessions/oidc",
+            Some(json!({ "token": "abc" })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(first, StatusCode::CREATED);
+        assert_eq!(second, StatusCode::CREATED);
+        let first = parse::<SessionResponse>(&first_body);
+        let second = parse::<SessionResponse>(&second_body);
+        // The local Anna is not taken over, the directory user gets a name of her own
+        assert_eq!(first.user_id, 1);
+        assert_eq!(second.user_id, 1);
+        assert_eq!(
+            state.users().get_user(&Id::new(1)).unwrap().get_name(),
+            "Anna 2"
+        );
+        assert_eq!(state.auth().authenticate(&second.token), Ok(Id::new(1)));
+        assert_eq!(wrong, StatusCode::UNAUTHORIZED);
+        assert_eq!(token, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(unknown, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest(
+        user,
+        expected,
+        case(None, StatusCode::UNAUTHORIZED),
+        case(Some("Ben"), StatusCode::FORBIDDEN),
+        case(Some("Anna"), StatusCode::NO_CONTENT)
+    )]
+    #[tokio::test]
+    async fn only_manager_can_change_status(user: Option<&str>, expected: StatusCode) {
+        // Given:
+        let state = AppState::new().with_required_authentication();
+        let anna = log_in(&state, "Anna").await;
+        let ben = log_in(&state, "Ben").await;
+        state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_user(Id::new(1))
+            .unwrap();
+        let token = user.map(|user| if user == "Anna" { anna } else { ben });
+
+        // When:
+        let (status, _) = send_with_token(
+            &state,
+            token.as_deref(),
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordering"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, expected);
+    }
+
+    #[tokio::test]
+    async fn order_refuses_changes_to_meals_of_others() {
+        // Given:
+        let state = AppState::new().with_required_authentication();
+        log_in(&state, "Anna").await;
+        let ben = log_in(&state, "Ben").await;
+        state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 50),
+            )
+            .unwrap();
+
+        // When:
+        let (status, body) = send_with_token(
+            &state,
+            Some(&ben),
+            "PUT",
+            "/orders/0/users/0/meals/0/note",
+            Some(json!({"note": "ohne Zwiebeln"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            parse::<ErrorResponse>(&body).error,
+            OrderError::NotAuthorized.to_string()
+        );
+    }
+
+    #[rstest(
+        user_id,
+        expected,
+        case(1, StatusCode::CREATED),
+        case(0, StatusCode::FORBIDDEN)
+    )]
+    #[tokio::test]
+    async fn only_owner_can_add_meals(user_id: u32, expected: StatusCode) {
+        // Given:
+        let state = AppState::new().with_required_authentication();
+        log_in(&state, "Anna").await;
+        let ben = log_in(&state, "Ben").await;
+        state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_user(Id::new(1))
+            .unwrap();
+
+        // When:
+        let (status, _) = send_with_token(
+            &state,
+            Some(&ben),
+            "POST",
+            &format!("/orders/0/users/{}/meals", user_id),
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, expected);
+    }
+
+    #[rstest(
+        user_id,
+        expected,
+        case(1, StatusCode::CREATED),
+        case(2, StatusCode::FORBIDDEN)
+    )]
+    #[tokio::test]
+    async fn only_user_themselves_can_join(user_id: u32, expected: StatusCode) {
+        // Given:
+        let state = AppState::new().with_required_authentication();
+        log_in(&state, "Anna").await;
+        let ben = log_in(&state, "Ben").await;
+        log_in(&state, "Carl").await;
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send_with_token(
+            &state,
+            Some(&ben),
+            "POST",
+            "/orders/0/users",
+            Some(json!({ "user_id": user_id })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, expected);
+    }
+
+    #[rstest(
+        method,
+        uri,
+        body,
+        other,
+        case("PUT", "/orders/0/users/1/paid", Some(json!({"amount_cents": 850})), "Anna"),
+        case("POST", "/orders/0/users/1/payments", Some(json!({"amount_cents": 850})), "Anna"),
+        case("PUT", "/orders/0/users/1/payments/0", Some(json!({"amount_cents": 850})), "Anna"),
+        case("DELETE", "/orders/0/users/1/payments/0", None, "Anna"),
+        case("PUT", "/orders/0/users/1/tip", Some(json!({"amount_cents": 50})), "Anna"),
+        case("PUT", "/orders/0/users/1/ready", Some(json!({"ready": true})), "Anna"),
+        case(
+            "POST",
+            "/orders/0/users/1/payment",
+            Some(json!({"amount_cents": 850, "method": "PayPal"})),
+            "Anna"
+        ),
+        case("POST", "/orders/0/users/1/payment/confirm", None, "Ben"),
+        case("POST", "/orders/0/users/1/payment/dispute", None, "Ben"),
+        case(
+            "PUT",
+            "/orders/0/deadline",
+            Some(json!({"deadline": "2020-05-04T13:30:00+02:00"})),
+            "Ben"
+        ),
+        case(
+            "POST",
+            "/orders/0/office-meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+            "Ben"
+        ),
+        case(
+            "POST",
+            "/orders/0/shared-meals",
+            Some(json!({"meal_id": "Tiramisu", "variety": "", "price_cents": 600})),
+            "Ben"
+        )
+    )]
+    #[tokio::test]
+    async fn only_owner_or_manager_can_change_payments_and_order(
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+        other: &str,
+    ) {
+        // Given:
+        let state = AppState::new().with_required_authentication();
+        let anna = log_in(&state, "Anna").await;
+        let ben = log_in(&state, "Ben").await;
+        state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_user(Id::new(1))
+            .unwrap();
+        let token = if other == "Anna" { anna } else { ben };
+
+        // When:
+        let (anonymous, _) = send(&state, method, uri, body.clone()).await;
+        let (forbidden, _) = send_with_token(&state, Some(&token), method, uri, body).await;
+
+        // Then:
+        assert_eq!(anonymous, StatusCode::UNAUTHORIZED);
+        assert_eq!(forbidden, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn session_shows_user_and_roles() {
+        // Given:
+        let state = AppState::new();
+        log_in(&state, "Anna").await;
+        let ben = log_in(&state, "Ben").await;
+        for manager_id in 0..3 {
+            state.orders().create_order(Id::new(manager_id));
+        }
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_user(Id::new(1))
+            .unwrap();
+
+        // When:
+        let (status, body) = send_with_token(&state, Some(&ben), "GET", "/sessions", None).await;
+        let (anonymous, _) = send(&state, "GET", "/sessions", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<CurrentSessionResponse>(&body),
+            CurrentSessionResponse {
+                user_id: 1,
+                name: String::from("Ben"),
+                orders: vec![
+                    OrderRoleResponse {
+                        order_id: 0,
+                        role: RoleResponse::Participant
+                    },
+                    OrderRoleResponse {
+                        order_id: 1,
+                        role: RoleResponse::Manager
+                    },
+                ],
+            }
+        );
+        assert_eq!(anonymous, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn unknown_session_is_rejected_even_without_required_authentication() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send_with_token(
+            &state,
+            Some("forged"),
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            state
+                .orders()
+                .get_order(&Id::new(0))
+                .unwrap()
+                .calculate_total_price(),
+            Money::new(0, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn deadline_is_counted_down() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()));
+        state.orders().create_order(Id::new(0));
+        clock.set(
+            DateTime::parse_from_rfc3339("2020-05-04T11:00:00Z")
+                .unwrap()
+                .into(),
+        );
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/deadline",
+            Some(json!({"deadline": "2020-05-04T13:30:00+02:00"})),
+        )
+        .await;
+        let (_, body) = send(&state, "GET", "/orders/0/deadline", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            parse::<DeadlineResponse>(&body),
+            DeadlineResponse {
+                deadline: Some(String::from("2020-05-04T11:30:00+00:00")),
+                seconds_left: Some(30 * 60)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn eta_given_when_ordered_is_counted_down_and_changed() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()));
+        state.orders().create_order(Id::new(0));
+        clock.set(
+            DateTime::parse_from_rfc3339("2020-05-04T11:45:00Z")
+                .unwrap()
+                .into(),
+        );
+        send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordering"})),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordered", "eta": "2020-05-04T14:15:00+02:00"})),
+        )
+        .await;
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/eta",
+            Some(json!({"eta": "2020-05-04T14:30:00+02:00"})),
+        )
+        .await;
+        let (_, body) = send(&state, "GET", "/orders/0/eta", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            parse::<EtaResponse>(&body),
+            EtaResponse {
+                eta: Some(String::from("2020-05-04T12:30:00+00:00")),
+                seconds_left: Some(45 * 60)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn order_starts_ordering_after_deadline() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()));
+        state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "PUT",
+            "/orders/0/deadline",
+            Some(json!({"deadline": "1970-01-01T00:01:00Z"})),
+        )
+        .await;
+        clock.advance(std::time::Duration::from_secs(60));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/users",
+            Some(json!({"user_id": 1})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            state.orders().get_order(&Id::new(0)).unwrap().get_status(),
+            &crate::order_model::order::OrderStatus::Ordering
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_deadline_is_rejected() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/deadline",
+            Some(json!({"deadline": "11:30"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn realtime_metrics_are_reported() {
+        // Given:
+        let state = AppState::new()
+            .with_event_queue_limit(8)
+            .with_admin_token(String::from(ADMIN_TOKEN));
+
+        // When:
+        let (status, body) = send_as_operator(&state, "GET", "/admin/realtime", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<RealtimeResponse>(&body),
+            RealtimeResponse {
+                connections: 0,
+                delivered: 0,
+                dropped: 0,
+                resyncs: 0,
+                queue_limit: 8
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn integrity_issues_are_reported() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        state.users_mut().register(String::from("Anna")).unwrap();
+        state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_user(Id::new(1))
+            .unwrap();
+
+        // When:
+        let (status, body) = send_as_operator(&state, "GET", "/admin/integrity", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<IntegrityResponse>(&body),
+            IntegrityResponse {
+                healthy: false,
+                issues: vec![String::from(
+                    "order 0: user 1 is not registered, repair: register the user or remove them from the order"
+                )]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn orders_without_recent_changes_are_listed_as_stuck() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()))
+            .with_admin_token(String::from(ADMIN_TOKEN));
+        state.orders().create_order(Id::new(0));
+        clock.advance(std::time::Duration::from_secs(90 * 60));
+
+        // When:
+        let (status, body) = send_as_operator(&state, "GET", "/admin/stuck-orders", None).await;
+        let (_, patient) =
+            send_as_operator(&state, "GET", "/admin/stuck-orders?idle_minutes=120", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<StuckOrdersResponse>(&body),
+            StuckOrdersResponse {
+                orders: vec![StuckOrderEntry {
+                    order_id: 0,
+                    status: String::from("Open"),
+                    idle_minutes: 90
+                }]
+            }
+        );
+        assert_eq!(
+            parse::<StuckOrdersResponse>(&patient),
+            StuckOrdersResponse { orders: vec![] }
+        );
+    }
+
+    #[tokio::test]
+    async fn all_sessions_are_ended() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        let token = state.auth().start_session(Id::new(0));
+
+        // When:
+        let (status, body) = send_as_operator(&state, "DELETE", "/admin/sessions", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<SessionsEndedResponse>(&body),
+            SessionsEndedResponse { ended: 1 }
+        );
+        assert!(state.auth().authenticate(&token).is_err());
+    }
+
+    #[rstest(
+        method,
+        uri,
+        case("GET", "/admin/integrity"),
+        case("GET", "/admin/storage"),
+        case("POST", "/admin/storage/replay"),
+        case("GET", "/admin/realtime"),
+        case("POST", "/admin/import"),
+        case("POST", "/admin/retention"),
+        case("GET", "/v1/admin/stuck-orders"),
+        case("DELETE", "/v1/admin/sessions")
+    )]
+    #[tokio::test]
+    async fn admin_routes_are_only_for_operators(method: &str, uri: &str) {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        let anna = log_in(&state, "Anna").await;
+        let unconfigured = AppState::new();
+
+        // When:
+        let (anonymous, _) = send(&state, method, uri, None).await;
+        let (user, _) = send_with_token(&state, Some(&anna), method, uri, None).await;
+        let (closed, _) =
+            send_with_token(&unconfigured, Some(ADMIN_TOKEN), method, uri, None).await;
+
+        // Then:
+        assert_eq!(anonymous, StatusCode::UNAUTHORIZED);
+        assert_eq!(user, StatusCode::FORBIDDEN);
+        assert_eq!(closed, StatusCode::FORBIDDEN);
+        assert!(state.auth().authenticate(&anna).is_ok());
+    }
+
+    #[tokio::test]
+    async fn summary_uses_currency_and_locale_of_order() {
+        // Given:
+        let state = AppState::new();
+        send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 0, "currency": "CHF", "locale": "de-CH"})),
+        )
+        .await;
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 1450})),
+        )
+        .await;
+
+        // When:
+        let (_, body) = send(&state, "GET", "/orders/0/summary", None).await;
+
+        // Then:
+        assert_eq!(
+            parse::<SummaryResponse>(&body).summary,
+            "1 person, 1 meal, CHF 14.50 total, 1 person still owes money."
+        );
+    }
+
+    #[tokio::test]
+    async fn order_with_unknown_currency_is_rejected() {
+        // Given:
+        let state = AppState::new();
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 0, "currency": "USD"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(state.orders().get_order(&Id::new(0)).is_none());
+    }
+
+    #[tokio::test]
+    async fn duplicate_order_is_reported() {
+        // Given:
+        let state = AppState::new();
+        send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 0, "restaurant": "Pizzeria Mario"})),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 1, "restaurant": "Pizzeria Mario"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            parse::<CreatedOrderResponse>(&body),
+            CreatedOrderResponse {
+                id: 1,
+                duplicate_of: Some(0)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn blocked_duplicate_order_is_conflict() {
+        // Given:
+        let state = AppState::new();
+        state
+            .orders()
+            .set_duplicate_policy(crate::api::state::DuplicatePolicy::Block);
+        send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 0, "restaurant": "Pizzeria Mario"})),
+        )
+        .await;
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 1, "restaurant": "Pizzeria Mario"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn unknown_version_is_not_found() {
+        // Given:
+        let state = AppState::new();
+
+        // When:
+        let (status, _) = send(&state, "POST", "/v2/orders", Some(json!({"manager_id": 0}))).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn user_can_join_order() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/users",
+            Some(json!({"user_id": 1})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .is_participating(&Id::new(1)));
+    }
+
+    #[tokio::test]
+    async fn user_cannot_join_order_twice() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/users",
+            Some(json!({"user_id": 0})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn meal_can_be_added_for_user() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 550})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(parse::<CreatedResponse>(&body), CreatedResponse { id: 0 });
+        assert_eq!(
+            state
+                .orders()
+                .get_order(&Id::new(0))
+                .unwrap()
+                .calculate_total_price(),
+            Money::new(5, 50)
+        );
+    }
+
+    #[tokio::test]
+    async fn meal_cannot_be_added_for_user_not_participating() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/users/1/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 550})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn meal_can_be_updated() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 550})),
+        )
+        .await;
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/meals/0",
+            Some(json!({"meal_id": "03", "variety": "klein", "price_cents": 450})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            state
+                .orders()
+                .get_order(&Id::new(0))
+                .unwrap()
+                .calculate_total_price(),
+            Money::new(4, 50)
+        );
+    }
+
+    #[rstest(
+        preparations,
+        expected,
+        case(json!(["well-done", "unsliced"]), StatusCode::NO_CONTENT),
+        case(json!(["extra-crispy"]), StatusCode::UNPROCESSABLE_ENTITY)
+    )]
+    #[tokio::test]
+    async fn meal_preparations_can_be_set(preparations: Value, expected: StatusCode) {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 550})),
+        )
+        .await;
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/meals/0/preparations",
+            Some(json!({ "preparations": preparations })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, expected);
+        let prepared = state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .all_meals()
+            .next()
+            .unwrap()
+            .get_preparations()
+            .len();
+        assert_eq!(prepared, if expected.is_success() { 2 } else { 0 });
+    }
+
+    #[tokio::test]
+    async fn meal_price_is_broken_down() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 790})),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/meals/0/preparations",
+            Some(json!({ "preparations": ["well-done"] })),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(&state, "GET", "/orders/0/users/0/meals/0/price", None).await;
+        let (missing, _) = send(&state, "GET", "/orders/0/users/0/meals/1/price", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<PriceBreakdownResponse>(&body),
+            PriceBreakdownResponse {
+                components: vec![
+                    PriceComponentEntry::Base {
+                        meal_id: String::from("03"),
+                        variety: String::from("groß"),
+                        price_cents: 790,
+                    },
+                    PriceComponentEntry::Preparation {
+                        preparation: String::from("well-done"),
+                        description: String::from("well done"),
+                    },
+                ],
+                total_cents: 790,
+                deposit_cents: 0,
+            }
+        );
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn meal_note_can_be_added_and_removed() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({
+                "meal_id": "03",
+                "variety": "groß",
+                "price_cents": 550,
+                "note": "cut into 8 slices"
+            })),
+        )
+        .await;
+        let note = |state: &AppState| {
+            state
+                .orders()
+                .get_order(&Id::new(0))
+                .unwrap()
+                .all_meals()
+                .next()
+                .unwrap()
+                .get_note()
+                .cloned()
+        };
+        let added = note(&state);
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/meals/0/note",
+            Some(json!({})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(added, Some(String::from("cut into 8 slices")));
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(note(&state), None);
+    }
+
+    #[tokio::test]
+    async fn meal_with_note_is_not_added_once_ordering() {
+        // Given:
+        let state = AppState::new();
+        let id = state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&id)
+            .unwrap()
+            .start_ordering()
+            .unwrap();
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({
+                "meal_id": "03",
+                "variety": "groß",
+                "price_cents": 550,
+                "note": "cut into 8 slices"
+            })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            state.orders().get_order(&id).unwrap().all_meals().count(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn office_meal_can_be_added() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/office-meals",
+            Some(json!({"meal_id": "61", "variety": "Salat", "price_cents": 400})),
+        )
+        .await;
+        let (_, body) = send(&state, "GET", "/orders/0/totals", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        let totals = parse::<TotalsResponse>(&body);
+        assert_eq!(totals.price_cents, 400);
+        assert_eq!(totals.office_price_cents, 400);
+        assert_eq!(totals.change_cents, Some(0));
+    }
+
+    #[tokio::test]
+    async fn shared_meal_is_split_among_participants() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            for user_id in [0, 1] {
+                order
+                    .add_meal_for_user(
+                        Id::new(user_id),
+                        String::from("03"),
+                        String::from("groß"),
+                        Money::new(5, 0),
+                    )
+                    .unwrap();
+            }
+        }
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/paid",
+            Some(json!({"amount_cents": 500})),
+        )
+        .await;
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/shared-meals",
+            Some(json!({"meal_id": "90", "variety": "1,5l", "price_cents": 300})),
+        )
+        .await;
+        let (_, body) = send(&state, "GET", "/orders/0/totals", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        let totals = parse::<TotalsResponse>(&body);
+        assert_eq!(totals.price_cents, 1300);
+        assert_eq!(totals.shared_price_cents, 300);
+        assert_eq!(totals.paid_less, vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn rounding_of_shared_meals_is_listed_in_totals() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            for user_id in [0, 1] {
+                order
+                    .add_meal_for_user(
+                        Id::new(user_id),
+                        String::from("03"),
+                        String::from("groß"),
+                        Money::new(5, 0),
+                    )
+                    .unwrap();
+            }
+        }
+        send(
+            &state,
+            "POST",
+            "/orders/0/shared-meals",
+            Some(json!({"meal_id": "90", "variety": "1,5l", "price_cents": 301})),
+        )
+        .await;
+
+        // When:
+        let (_, body) = send(&state, "GET", "/orders/0/totals", None).await;
+
+        // Then:
+        let totals = parse::<TotalsResponse>(&body);
+        assert_eq!(totals.price_cents, 1301);
+        assert_eq!(
+            totals.rounding,
+            vec![RoundingAdjustmentResponse {
+                user_id: 0,
+                split: SplitAmountResponse::SharedMeals,
+                amount_cents: 1,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_order_is_not_found() {
+        // Given:
+        let state = AppState::new();
+
+        // When:
+        let (status, _) = send(&state, "GET", "/orders/7/totals", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn deposits_are_refunded_after_delivery() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "POST",
+            "/orders/0/shared-meals",
+            Some(
+                json!({"meal_id": "90", "variety": "1l", "price_cents": 300, "deposit_cents": 15}),
+            ),
+        )
+        .await;
+        for status in ["Ordering", "Ordered", "Delivered"] {
+            send(
+                &state,
+                "PUT",
+                "/orders/0/status",
+                Some(json!({ "status": status })),
+            )
+            .await;
+        }
+        let (_, price) = send(&state, "GET", "/orders/0/totals", None).await;
+
+        // When:
+        let (status, body) = send(&state, "POST", "/orders/0/meals/0/deposit-return", None).await;
+        let (missing, _) = send(&state, "POST", "/orders/0/meals/7/deposit-return", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<DepositRefundResponse>(&body),
+            DepositRefundResponse { refund_cents: 15 }
+        );
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+        assert_eq!(parse::<TotalsResponse>(&price).deposit_cents, 15);
+        let (_, body) = send(&state, "GET", "/orders/0/totals", None).await;
+        let totals = parse::<TotalsResponse>(&body);
+        assert_eq!(totals.price_cents, 300);
+        assert_eq!(totals.deposit_cents, 0);
+        assert_eq!(totals.returned_deposit_cents, 15);
+    }
+
+    #[tokio::test]
+    async fn totals_reflect_paid_and_tip() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(0),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(1),
+                    String::from("17"),
+                    String::from("klein"),
+                    Money::new(4, 0),
+                )
+                .unwrap();
+        }
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/paid",
+            Some(json!({"amount_cents": 1000})),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/tip",
+            Some(json!({"amount_cents": 50})),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/paid",
+            Some(json!({"amount_cents": 300})),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(&state, "GET", "/orders/0/totals", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<TotalsResponse>(&body),
+            TotalsResponse {
+                price_cents: 950,
+                office_price_cents: 0,
+                shared_price_cents: 0,
+                tip_cents: 50,
+                deposit_cents: 0,
+                returned_deposit_cents: 0,
+                change_cents: Some(300),
+                underpaid_cents: None,
+                paid_less: vec![1],
+                rounding: vec![],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn dashboard_follows_changes() {
+        // Given:
+        let state = AppState::new();
+        send(&state, "POST", "/orders", Some(json!({"manager_id": 0}))).await;
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+
+        // When:
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/paid",
+            Some(json!({"amount_cents": 1000})),
+        )
+        .await;
+        let (status, body) = send(&state, "GET", "/orders/0/dashboard", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        let dashboard: DashboardResponse = parse(&body);
+        assert_eq!(dashboard.participants, 1);
+        assert_eq!(dashboard.meals, 1);
+        assert_eq!(dashboard.price_cents, 750);
+        assert_eq!(dashboard.paid_cents, 1000);
+        assert_eq!(dashboard.change_cents, 250);
+        assert_eq!(dashboard.balance_cents.get(&0), Some(&250));
+    }
+
+    #[tokio::test]
+    async fn dashboard_of_order_created_outside_api_is_summarized_on_demand() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, body) = send(&state, "GET", "/orders/0/dashboard", None).await;
+        let (missing, _) = send(&state, "GET", "/orders/1/dashboard", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(parse::<DashboardResponse>(&body).participants, 1);
+        assert!(state.summaries().get(&Id::new(0)).is_some());
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn meals_of_last_delivered_order_can_be_added_again() {
+        // Given:
+        let state = AppState::new();
+        for _ in 0..2 {
+            send(&state, "POST", "/orders", Some(json!({"manager_id": 0}))).await;
+        }
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+        for status in &[
+            json!({"status": "Ordering"}),
+            json!({"status": "Ordered", "eta": "2020-05-04T12:15:00+02:00"}),
+            json!({"status": "Delivered"}),
+        ] {
+            send(&state, "PUT", "/orders/0/status", Some(status.clone())).await;
+        }
+
+        // When:
+        let (status, body) = send(&state, "POST", "/orders/1/users/0/last-order", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            parse::<CreatedMealsResponse>(&body),
+            CreatedMealsResponse { ids: vec![0] }
+        );
+        let mut orders = state.orders();
+        let order = orders.get_order(&Id::new(1)).unwrap();
+        assert_eq!(order.calculate_total_price(), Money::new(7, 50));
+    }
+
+    #[tokio::test]
+    async fn last_order_of_user_without_delivered_order_is_not_found() {
+        // Given:
+        let state = AppState::new();
+        send(&state, "POST", "/orders", Some(json!({"manager_id": 0}))).await;
+
+        // When:
+        let (status, _) = send(&state, "POST", "/orders/0/users/0/last-order", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest(
+        code,
+        expected_status,
+        expected,
+        case("ord-0000", StatusCode::OK, Some(ResolvedCodeResponse::Order(0))),
+        case("USR-0000", StatusCode::OK, Some(ResolvedCodeResponse::User(0))),
+        case("ORD-0013", StatusCode::NOT_FOUND, None),
+        case("USR-0013", StatusCode::NOT_FOUND, None),
+        case("ORD-0001", StatusCode::UNPROCESSABLE_ENTITY, None),
+        case("PIZZA", StatusCode::UNPROCESSABLE_ENTITY, None)
+    )]
+    #[tokio::test]
+    async fn short_code_is_resolved(
+        code: &str,
+        expected_status: StatusCode,
+        expected: Option<ResolvedCodeResponse>,
+    ) {
+        // Given:
+        let state = AppState::new();
+        state.users_mut().register(String::from("Anna")).unwrap();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, body) = send(&state, "GET", &format!("/codes/{}", code), None).await;
+
+        // Then:
+        assert_eq!(status, expected_status);
+        if let Some(expected) = expected {
+            assert_eq!(parse::<ResolvedCodeResponse>(&body), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn order_can_be_copied() {
+        // Given:
+        let state = AppState::new();
+        send(
+            &state,
+            "POST",
+            "/orders",
+            Some(json!({"manager_id": 0, "restaurant": "Luigi"})),
+        )
+        .await;
+        send(
+            &state,
+            "POST",
+            "/orders/0/users",
+            Some(json!({"user_id": 1})),
+        )
+        .await;
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/1/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Cancelled"})),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "POST",
+            "/orders/0/copies",
+            Some(json!({"manager_id": 1})),
+        )
+        .await;
+        let (missing, _) = send(
+            &state,
+            "POST",
+            "/orders/5/copies",
+            Some(json!({"manager_id": 1})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(parse::<CreatedResponse>(&body), CreatedResponse { id: 1 });
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+        let mut orders = state.orders();
+        assert_eq!(orders.find_duplicate("Luigi"), Some(Id::new(1)));
+        let copy = orders.get_order(&Id::new(1)).unwrap();
+        assert_eq!(copy.get_manager_id(), Id::new(1));
+        assert_eq!(copy.calculate_total_price(), Money::new(7, 50));
+    }
+
+    #[tokio::test]
+    async fn amounts_are_reported_by_cost_center() {
+        // Given:
+        let state = AppState::new();
+        for name in &["Anna", "Ben"] {
+            send(&state, "POST", "/users", Some(json!({ "name": name }))).await;
+        }
+        for order_id in 0..2 {
+            send(&state, "POST", "/orders", Some(json!({"manager_id": 0}))).await;
+            send(
+                &state,
+                "POST",
+                &format!("/orders/{}/users", order_id),
+                Some(json!({"user_id": 1})),
+            )
+            .await;
+            for user_id in 0..2 {
+                send(
+                    &state,
+                    "POST",
+                    &format!("/orders/{}/users/{}/meals", order_id, user_id),
+                    Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+                )
+                .await;
+            }
+        }
+
+        // When:
+        let (user, _) = send(
+            &state,
+            "PUT",
+            "/users/1/cost-center",
+            Some(json!({"cost_center": "IT"})),
+        )
+        .await;
+        let (unknown, _) = send(
+            &state,
+            "PUT",
+            "/users/5/cost-center",
+            Some(json!({"cost_center": "IT"})),
+        )
+        .await;
+        let (order, _) = send(
+            &state,
+            "PUT",
+            "/orders/1/cost-center",
+            Some(json!({"cost_center": "Marketing"})),
+        )
+        .await;
+        let (_, single) = send(&state, "GET", "/orders/0/cost-centers", None).await;
+        let (_, all) = send(&state, "GET", "/stats/cost-centers", None).await;
+
+        // Then:
+        assert_eq!(user, StatusCode::NO_CONTENT);
+        assert_eq!(unknown, StatusCode::NOT_FOUND);
+        assert_eq!(order, StatusCode::NO_CONTENT);
+        let totals = |body: &[u8]| {
+            parse::<CostCentersResponse>(body)
+                .cost_centers
+                .into_iter()
+                .map(|entry| (entry.cost_center, entry.total_cents))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            totals(&single),
+            vec![(None, 750), (Some(String::from("IT")), 750)]
+        );
+        assert_eq!(
+            totals(&all),
+            vec![
+                (None, 750),
+                (Some(String::from("IT")), 750),
+                (Some(String::from("Marketing")), 1500)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn order_is_created_from_template() {
+        // Given:
+        let clock = TestClock::default();
+        clock.set(
+            "2020-05-08T08:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .into(),
+        );
+        let state = AppState::with_clock(Arc::new(clock));
+        let (created, body) = send(
+            &state,
+            "POST",
+            "/templates",
+            Some(json!({
+                "name": "Friday pizza",
+                "manager_id": 0,
+                "restaurant": {"name": "Luigi"},
+                "participants": [1, 2],
+                "deadline": "11:30"
+            })),
+        )
+        .await;
+
+        // When:
+        let (status, order) = send(&state, "POST", "/templates/0/orders", None).await;
+        let (_, again) = send(&state, "POST", "/templates/0/orders", None).await;
+        let (missing, _) = send(&state, "POST", "/templates/1/orders", None).await;
+
+        // Then:
+        assert_eq!(created, StatusCode::CREATED);
+        assert_eq!(parse::<CreatedResponse>(&body), CreatedResponse { id: 0 });
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            parse::<CreatedOrderResponse>(&order),
+            CreatedOrderResponse {
+                id: 0,
+                duplicate_of: None
+            }
+        );
+        assert_eq!(parse::<CreatedOrderResponse>(&again).duplicate_of, Some(0));
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+        let mut orders = state.orders();
+        let order = orders.get_order(&Id::new(0)).unwrap();
+        assert_eq!(order.participants().count(), 3);
+        assert_eq!(
+            order.get_deadline(),
+            Some("2020-05-08T11:30:00Z".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn templates_are_listed_and_removed() {
+        // Given:
+        let state = AppState::new();
+        for name in &["Friday pizza", "Sushi"] {
+            send(
+                &state,
+                "POST",
+                "/templates",
+                Some(json!({"name": name, "manager_id": 0, "restaurant": {"name": "Luigi"}})),
+            )
+            .await;
+        }
+
+        // When:
+        let (removed, _) = send(&state, "DELETE", "/templates/0", None).await;
+        let (missing, _) = send(&state, "DELETE", "/templates/0", None).await;
+        let (_, body) = send(&state, "GET", "/templates", None).await;
+
+        // Then:
+        assert_eq!(removed, StatusCode::NO_CONTENT);
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+        let templates = parse::<OrderTemplatesResponse>(&body).templates;
+        assert_eq!(
+            templates
+                .iter()
+                .map(|template: &OrderTemplateResponse| (template.id, template.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "Sushi")]
+        );
+    }
+
+    #[tokio::test]
+    async fn template_with_invalid_deadline_is_rejected() {
+        // Given:
+        let state = AppState::new();
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/templates",
+            Some(json!({
+                "name": "Friday pizza",
+                "manager_id": 0,
+                "restaurant": {"name": "Luigi"},
+                "deadline": "half past eleven"
+            })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(state.templates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn templates_are_kept_across_restarts() {
+        // Given:
+        let path = std::env::temp_dir().join(format!(
+            "rusty_pizza_templates_{}.jsonl",
+            std::process::id()
+        ));
+        let state = AppState::new().with_template_file(path.clone()).unwrap();
+        send(
+            &state,
+            "POST",
+            "/templates",
+            Some(json!({"name": "Friday pizza", "manager_id": 0, "restaurant": {"name": "Luigi"}})),
+        )
+        .await;
+
+        // When:
+        let restarted = AppState::new().with_template_file(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Then:
+        assert_eq!(*restarted.templates(), *state.templates());
+        assert_eq!(restarted.templates().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn templates_created_while_storage_is_unreachable_are_replayed() {
+        // Given:
+        let path = std::env::temp_dir().join(format!(
+            "rusty_pizza_unreachable_templates_{}.jsonl",
+            std::process::id()
+        ));
+        std::fs::create_dir(&path).unwrap();
+        let state = AppState::new()
+            .with_template_file_or_ephemeral(path.clone())
+            .with_admin_token(String::from(ADMIN_TOKEN));
+        let (_, body) = send(&state, "GET", "/health", None).await;
+        let degraded: HealthResponse = parse(&body);
+        send(
+            &state,
+            "POST",
+            "/templates",
+            Some(json!({"name": "Friday pizza", "manager_id": 0, "restaurant": {"name": "Luigi"}})),
+        )
+        .await;
+        let (_, body) = send_as_operator(&state, "GET", "/admin/storage", None).await;
+        let queued: StorageResponse = parse(&body);
+        std::fs::remove_dir(&path).unwrap();
+        let mut stored = OrderTemplates::new();
+        stored.add(OrderTemplate::new(
+            String::from("Team lunch"),
+            Id::new(1),
+            Restaurant::new(String::from("Luigi")),
+        ));
+        templates::save_templates(&stored, &path).unwrap();
+
+        // When:
+        let (status, body) = send_as_operator(&state, "POST", "/admin/storage/replay", None).await;
+
+        // Then:
+        let restarted = AppState::new().with_template_file(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            degraded,
+            HealthResponse {
+                status: String::from("degraded"),
+                ephemeral: true,
+            }
+        );
+        assert!(queued.ephemeral);
+        assert_eq!(queued.queued_writes, 1);
+        assert_eq!(status, StatusCode::OK);
+        let replayed: StorageResponse = parse(&body);
+        assert!(!replayed.ephemeral);
+        assert_eq!(replayed.queued_writes, 0);
+        assert_eq!(
+            replayed.moved_templates,
+            vec![MovedTemplateResponse {
+                old_id: 0,
+                new_id: 1
+            }]
+        );
+        assert_eq!(restarted.templates().len(), 2);
+        assert_eq!(
+            restarted.templates().get(&Id::new(1)).unwrap().get_name(),
+            "Friday pizza"
+        );
+    }
+
+    #[tokio::test]
+    async fn paid_cannot_be_set_for_user_not_participating() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/paid",
+            Some(json!({"amount_cents": 1000})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn user_can_mark_themselves_ready() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/ready",
+            Some(json!({"ready": true})),
+        )
+        .await;
+        let (unknown_status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/ready",
+            Some(json!({"ready": true})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(unknown_status, StatusCode::NOT_FOUND);
+        assert!(state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .get_meals_for_user(Id::new(0))
+            .unwrap()
+            .is_ready());
+    }
+
+    #[tokio::test]
+    async fn manager_gets_qr_codes_for_debts() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(1),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(8, 50),
+                )
+                .unwrap();
+        }
+        let sepa = json!({"sepa": {"name": "Franz Pizza", "iban": "DE89 3704 0044 0532 0130 00"}});
+        let invalid =
+            json!({"sepa": {"name": "Franz Pizza", "iban": "DE88 3704 0044 0532 0130 00"}});
+
+        // When:
+        let (status, body) = send(&state, "POST", "/orders/0/payment-requests", Some(sepa)).await;
+        let (invalid_status, _) =
+            send(&state, "POST", "/orders/0/payment-requests", Some(invalid)).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        let requests = parse::<PaymentRequestsResponse>(&body).requests;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].user_id, 1);
+        assert_eq!(requests[0].amount_cents, 850);
+        assert!(requests[0].link.starts_with("BCD\n002\n1\nSCT\n"));
+        assert!(requests[0].link.contains("\nEUR8.50\n"));
+        assert_eq!(invalid_status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn payments_are_added_in_installments() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(1),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(8, 50),
+                )
+                .unwrap();
+        }
+        let path = "/orders/0/users/1/payments";
+
+        // When:
+        let (cash_status, cash) =
+            send(&state, "POST", path, Some(json!({"amount_cents": 500}))).await;
+        send(&state, "POST", path, Some(json!({"amount_cents": 300}))).await;
+        let (correct_status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/payments/1",
+            Some(json!({"amount_cents": 350})),
+        )
+        .await;
+        let (remove_status, _) = send(&state, "DELETE", "/orders/0/users/1/payments/7", None).await;
+        let (_, payments) = send(&state, "GET", path, None).await;
+
+        // Then:
+        assert_eq!(cash_status, StatusCode::CREATED);
+        assert_eq!(parse::<CreatedResponse>(&cash), CreatedResponse { id: 0 });
+        assert_eq!(correct_status, StatusCode::NO_CONTENT);
+        assert_eq!(remove_status, StatusCode::NOT_FOUND);
+        let payments = parse::<PaymentsResponse>(&payments);
+        assert_eq!(payments.paid_cents, 850);
+        assert_eq!(payments.payments.len(), 2);
+        assert!(payments.payments[1].paid_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn duplicate_payments_are_held_for_manager() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(1),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(8, 50),
+                )
+                .unwrap();
+        }
+        let path = "/orders/0/users/1/payments";
+        let key = [("idempotency-key", "tx-1")];
+        let (first_status, _) = send_with_headers(
+            &state,
+            &key,
+            "POST",
+            path,
+            Some(json!({"amount_cents": 850})),
+        )
+        .await;
+
+        // When:
+        let (retry_status, retry) = send_with_headers(
+            &state,
+            &key,
+            "POST",
+            path,
+            Some(json!({"amount_cents": 850})),
+        )
+        .await;
+        let (twice_status, twice) =
+            send(&state, "POST", path, Some(json!({"amount_cents": 850}))).await;
+        let (_, held) = send(&state, "GET", path, None).await;
+        let (release_status, _) =
+            send(&state, "POST", "/orders/0/users/1/held-payments/1", None).await;
+        let (_, released) = send(&state, "GET", path, None).await;
+
+        // Then:
+        assert_eq!(first_status, StatusCode::CREATED);
+        assert_eq!(retry_status, StatusCode::OK);
+        assert_eq!(parse::<ReceivedPaymentResponse>(&retry).id, 0);
+        assert_eq!(twice_status, StatusCode::ACCEPTED);
+        assert_eq!(
+            parse::<ReceivedPaymentResponse>(&twice).duplicate_of,
+            Some(0)
+        );
+        let held = parse::<PaymentsResponse>(&held);
+        assert_eq!(held.paid_cents, 850);
+        assert_eq!(held.held.len(), 1);
+        assert_eq!(release_status, StatusCode::NO_CONTENT);
+        let released = parse::<PaymentsResponse>(&released);
+        assert_eq!(released.paid_cents, 1700);
+        assert!(released.held.is_empty());
+    }
+
+    #[tokio::test]
+    async fn claimed_payment_is_confirmed_by_manager() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(1),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(8, 50),
+                )
+                .unwrap();
+        }
+
+        // When:
+        let (claim_status, _) = send(
+            &state,
+            "POST",
+            "/orders/0/users/1/payment",
+            Some(json!({"amount_cents": 850, "method": "PayPal"})),
+        )
+        .await;
+        let (_, reminders_before) = send(&state, "GET", "/orders/0/reminders", None).await;
+        let (confirm_status, _) =
+            send(&state, "POST", "/orders/0/users/1/payment/confirm", None).await;
+        let (confirm_again_status, _) =
+            send(&state, "POST", "/orders/0/users/1/payment/confirm", None).await;
+
+        // Then:
+        assert_eq!(claim_status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            parse::<UserIdsResponse>(&reminders_before),
+            UserIdsResponse { user_ids: vec![] }
+        );
+        assert_eq!(confirm_status, StatusCode::NO_CONTENT);
+        assert_eq!(confirm_again_status, StatusCode::CONFLICT);
+        assert_eq!(
+            state
+                .orders()
+                .get_order(&Id::new(0))
+                .unwrap()
+                .calculate_total_change(),
+            Ok(Money::zero())
+        );
+    }
+
+    #[tokio::test]
+    async fn disputed_payment_is_reminded() {
+        // Given:
+        let state = AppState::new();
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            orders
+                .get_order(&id)
+                .unwrap()
+                .add_meal_for_user(
+                    Id::new(0),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(8, 50),
+                )
+                .unwrap();
+        }
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/payment",
+            Some(json!({"amount_cents": 850, "method": "PayPal"})),
+        )
+        .await;
+
+        // When:
+        let (status, _) = send(&state, "POST", "/orders/0/users/0/payment/dispute", None).await;
+        let (_, body) = send(&state, "GET", "/orders/0/reminders", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            parse::<UserIdsResponse>(&body),
+            UserIdsResponse { user_ids: vec![0] }
+        );
+    }
+
+    #[tokio::test]
+    async fn summary_is_plain_text() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, body) = send(&state, "GET", "/orders/0/summary", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<SummaryResponse>(&body),
+            SummaryResponse {
+                summary: String::from("1 person, 0 meals, 0,00€ total, everybody has paid."),
+            }
+        );
+    }
+
+    #[rstest(
+        dry_run,
+        imported,
+        case(true, Vec::<u32>::new()),
+        case(false, vec![0])
+    )]
+    #[tokio::test]
+    async fn spreadsheet_is_imported(dry_run: bool, imported: Vec<u32>) {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        let csv = "date,user,meal,price,paid,tip\n2020-05-04,Anna,03,7.50,5.00,0\n";
+
+        // When:
+        let (status, body) = send_as_operator(
+            &state,
+            "POST",
+            "/admin/import",
+            Some(json!({"csv": csv, "dry_run": dry_run})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        let response = parse::<ImportResponse>(&body);
+        assert_eq!(response.dates, vec![String::from("2020-05-04")]);
+        assert_eq!(response.balance_cents["Anna"], -250);
+        assert_eq!(response.imported, imported);
+        assert_eq!(state.users().get_user_by_name("Anna").is_some(), !dry_run);
+    }
+
+    #[tokio::test]
+    async fn old_imported_orders_are_purged() {
+        // Given:
+        let clock = TestClock::new(
+            DateTime::parse_from_rfc3339("2021-05-06T12:00:00Z")
+                .unwrap()
+                .into(),
+        );
+        let state =
+            AppState::with_clock(Arc::new(clock)).with_admin_token(String::from(ADMIN_TOKEN));
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        state
+            .orders()
+            .set_retention_policy(RetentionPolicy::new(Some(30 * day), Some(365 * day)));
+        let csv = "date,user,meal,price,paid,tip
+                   2020-05-04,Anna,03,7.50,8.00,0.50
+                   2020-05-08,Anna,03,7.50,7.50,0
+";
+        send_as_operator(&state, "POST", "/admin/import", Some(json!({ "csv": csv }))).await;
+
+        // When:
+        let (status, body) = send_as_operator(&state, "POST", "/admin/retention", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<RetentionResponse>(&body),
+            RetentionResponse {
+                archived: vec![],
+                anonymized: vec![1],
+                deleted: vec![0]
+            }
+        );
+        let (_, statistics) = send(&state, "GET", "/stats/orders", None).await;
+        assert_eq!(parse::<OrderStatisticsResponse>(&statistics).orders, 1);
+    }
+
+    #[tokio::test]
+    async fn delivered_orders_are_archived_and_keep_their_dashboard() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()))
+            .with_admin_token(String::from(ADMIN_TOKEN));
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        state
+            .orders()
+            .set_retention_policy(RetentionPolicy::default().with_archive_after(7 * day));
+        let id = state.orders().create_order(Id::new(0));
+        {
+            let mut orders = state.orders();
+            let order = orders.get_order(&id).unwrap();
+            order.start_ordering().unwrap();
+            order.mark_ordered(None).unwrap();
+            order
+                .mark_delivered(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap();
+        }
+        let (_, before) = send(&state, "GET", "/orders/0/dashboard", None).await;
+        clock.advance(8 * day);
+
+        // When:
+        let (status, body) = send_as_operator(&state, "POST", "/admin/retention", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(parse::<RetentionResponse>(&body).archived, vec![0]);
+        assert_eq!(state.orders().archived().count(), 1);
+        assert!(state.summaries().get(&id).is_none());
+        let (status, after) = send(&state, "GET", "/orders/0/dashboard", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<DashboardResponse>(&after),
+            parse::<DashboardResponse>(&before)
+        );
+    }
+
+    #[rstest(
+        version,
+        expected,
+        case("\"2\"", StatusCode::NO_CONTENT),
+        case("1", StatusCode::CONFLICT),
+        case("latest", StatusCode::BAD_REQUEST)
+    )]
+    #[tokio::test]
+    async fn stale_meal_changes_are_rejected(version: &str, expected: StatusCode) {
+        // Given:
+        let state = AppState::new();
+        let id = state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&id)
+            .unwrap()
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(8, 50),
+            )
+            .unwrap();
+
+        // When:
+        let (status, _) = send_with_headers(
+            &state,
+            &[("if-match", version)],
+            "PUT",
+            "/orders/0/users/0/meals/0/note",
+            Some(json!({"note": "ohne Zwiebeln"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, expected);
+    }
+
+    #[rstest(
+        method,
+        uri,
+        body,
+        case("PUT", "/orders/0/users/0/paid", Some(json!({"amount_cents": 850}))),
+        case("PUT", "/orders/0/users/0/tip", Some(json!({"amount_cents": 50}))),
+        case("POST", "/orders/0/users/0/payments", Some(json!({"amount_cents": 850}))),
+        case("DELETE", "/orders/0/users/0/held-payments/0", None),
+        case("POST", "/orders/0/users/0/payment/confirm", None)
+    )]
+    #[tokio::test]
+    async fn stale_payment_changes_are_rejected(method: &str, uri: &str, body: Option<Value>) {
+        // Given:
+        let state = AppState::new();
+        let id = state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&id)
+            .unwrap()
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(8, 50),
+            )
+            .unwrap();
+
+        // When:
+        let (status, _) = send_with_headers(&state, &[("if-match", "1")], method, uri, body).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CONFLICT);
+        let sequence_number = state.orders().get_order(&id).unwrap().get_sequence_number();
+        assert_eq!(sequence_number, 2);
+    }
+
+    #[tokio::test]
+    async fn organizer_of_every_order_is_flagged_as_unfair() {
+        // Given:
+        let state = AppState::new();
+        for order_id in 0..3 {
+            state.orders().create_order(Id::new(0));
+            state
+                .orders()
+                .get_order(&Id::new(order_id))
+                .unwrap()
+                .add_user(Id::new(1))
+                .unwrap();
+        }
+
+        // When:
+        let (status, body) = send(&state, "GET", "/stats/fairness", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        let fairness = parse::<FairnessResponse>(&body);
+        assert_eq!(
+            fairness.users[0],
+            UserFairnessResponse {
+                user_id: 0,
+                participations: 3,
+                organized: 3,
+                fees_covered: 0,
+                imbalance: Some(String::from("too_often"))
+            }
+        );
+        assert_eq!(fairness.users[1].imbalance.as_deref(), Some("too_rarely"));
+    }
+
+    #[tokio::test]
+    async fn statistics_include_imported_orders() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        let csv = "date,user,meal,price,paid,tip\n\
+                   2020-05-04,Anna,03,7.50,8.00,0.50\n\
+                   2020-05-08,Anna,03,7.50,7.50,0\n\
+                   2020-05-08,Ben,17,5.00,5.00,0\n";
+        send_as_operator(&state, "POST", "/admin/import", Some(json!({ "csv": csv }))).await;
+
+        // When:
+        let (status, body) = send(&state, "GET", "/stats/orders", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        let statistics = parse::<OrderStatisticsResponse>(&body);
+        assert_eq!(statistics.orders, 2);
+        assert_eq!(
+            statistics.meals[0],
+            MealCountResponse {
+                meal_id: String::from("03"),
+                count: 2
+            }
+        );
+        assert_eq!(statistics.average_spend_per_user_cents, Some(1025));
+        assert_eq!(
+            statistics.tips,
+            vec![MonthlyTipResponse {
+                year: 2020,
+                month: 5,
+                tip_cents: 50
+            }]
+        );
+        assert_eq!(statistics.orders_per_weekday, [1, 0, 0, 0, 1, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn invalid_spreadsheet_is_rejected() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+
+        // When:
+        let (status, body) = send_as_operator(
+            &state,
+            "POST",
+            "/admin/import",
+            Some(json!({"csv": "date,user,meal,price,paid,tip\n2020-05-04,Anna,03\n"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            parse::<ImportResponse>(&body).errors,
+            vec![String::from("line 2: 3 columns instead of 6")]
+        );
+    }
+
+    #[tokio::test]
+    async fn bank_statement_books_matching_transfers() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        let id = state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "POST",
+            &format!("/orders/{}/users/0/meals", id.get_value()),
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+        let csv = format!(
+            "date,amount,reference\n2020-05-04,7.50,Pizza {}\n2020-05-04,3.00,Kaffee\n",
+            short_code(&id)
+        );
+
+        // When:
+        let (status, body) = send_as_operator(
+            &state,
+            "POST",
+            "/admin/bank-statements",
+            Some(json!({"format": "csv", "content": csv})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        let response = parse::<BankStatementResponse>(&body);
+        assert_eq!(response.matched.len(), 1);
+        assert_eq!(response.matched[0].user_id, 0);
+        assert_eq!(response.unmatched[0].transfer.reference, "Kaffee");
+        assert_eq!(
+            state
+                .summaries()
+                .get(&id)
+                .unwrap()
+                .get_report()
+                .get_total_owed(),
+            Money::zero()
+        );
+    }
+
+    #[tokio::test]
+    async fn bank_statement_of_user_books_nothing() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+        let anna = log_in(&state, "Anna").await;
+        let id = state.orders().create_order(Id::new(0));
+        send_with_token(
+            &state,
+            Some(&anna),
+            "POST",
+            &format!("/orders/{}/users/0/meals", id.get_value()),
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+        let csv = format!(
+            "date,amount,reference\n2020-05-04,7.50,Pizza {}\n",
+            short_code(&id)
+        );
+
+        // When:
+        let (status, _) = send_with_token(
+            &state,
+            Some(&anna),
+            "POST",
+            "/admin/bank-statements",
+            Some(json!({"format": "csv", "content": csv})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            state
+                .summaries()
+                .get(&id)
+                .unwrap()
+                .get_report()
+                .get_total_owed(),
+            Money::new(7, 50)
+        );
+    }
+
+    #[tokio::test]
+    async fn unreadable_bank_statement_is_rejected() {
+        // Given:
+        let state = AppState::new().with_admin_token(String::from(ADMIN_TOKEN));
+
+        // When:
+        let (status, body) = send_as_operator(
+            &state,
+            "POST",
+            "/admin/bank-statements",
+            Some(json!({"format": "camt", "content": "<Ntry><CdtDbtInd>CRDT</CdtDbtInd></Ntry>"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            parse::<BankStatementResponse>(&body).errors,
+            vec![String::from("entry 1 is incomplete")]
+        );
+    }
+
+    #[tokio::test]
+    async fn history_lists_changes_of_order() {
+        // Given:
+        let state = AppState::with_clock(Arc::new(TestClock::default()));
+        let id = state.orders().create_order(Id::new(0));
+        send(
+            &state,
+            "PUT",
+            &format!("/orders/{}/users/0/paid", id.get_value()),
+            Some(json!({"amount_cents": 500})),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "GET",
+            &format!("/orders/{}/history", id.get_value()),
+            None,
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<HistoryResponse>(&body),
+            HistoryResponse {
+                events: vec![
+                    HistoryEntryResponse {
+                        time: String::from("1970-01-01T00:00:00+00:00"),
+                        user_id: None,
+                        change: String::from("order created by user 0"),
+                    },
+                    HistoryEntryResponse {
+                        time: String::from("1970-01-01T00:00:00+00:00"),
+                        user_id: Some(0),
+                        change: String::from("user 0 paid 5,00€"),
+                    },
+                ]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn money_stats_are_aggregated_over_orders() {
+        // Given:
+        let state = AppState::with_clock(Arc::new(TestClock::default()));
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(0),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(8, 0),
+                )
+                .unwrap();
+            order
+                .set_tip_for_user(Id::new(0), Money::new(1, 0))
+                .unwrap();
+        }
+
+        // When:
+        let (status, body) = send(&state, "GET", "/stats/money", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<MoneyStatsResponse>(&body),
+            MoneyStatsResponse {
+                largest_order_cents: Some(900),
+                average_per_head_cents: Some(900),
+                tip_ratio_basis_points: Some(1250),
+                months: vec![MonthlyMoneyResponse {
+                    year: 1970,
+                    month: 1,
+                    orders: 1,
+                    spend_cents: 900,
+                    trend_cents: None,
+                }],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn joining_user_is_published() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        let mut events = state.events().subscribe();
+
+        // When:
+        send(
+            &state,
+            "POST",
+            "/orders/0/users",
+            Some(json!({"user_id": 1})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(
+            events.try_recv(),
+            Ok(OrderEvent::UserJoined {
+                order_id: 0,
+                user_id: 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn added_meal_and_payment_are_published() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        state
+            .orders()
+            .get_order(&Id::new(0))
+            .unwrap()
+            .add_user(Id::new(1))
+            .unwrap();
+        let mut events = state.events().subscribe();
+
+        // When:
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/1/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 550})),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/paid",
+            Some(json!({"amount_cents": 600})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(
+            events.try_recv(),
+            Ok(OrderEvent::MealAdded {
+                order_id: 0,
+                user_id: Some(1),
+                meal_id: String::from("03"),
+                variety: String::from("groß")
+            })
+        );
+        assert_eq!(
+            events.try_recv(),
+            Ok(OrderEvent::PaymentRecorded {
+                order_id: 0,
+                user_id: 1,
+                amount_cents: 600
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_request_publishes_nothing() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        let mut events = state.events().subscribe();
+
+        // When:
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/1/paid",
+            Some(json!({"amount_cents": 600})),
+        )
+        .await;
+
+        // Then:
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn status_change_is_applied_and_published() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock));
+        state.orders().create_order(Id::new(0));
+        let mut events = state.events().subscribe();
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordering"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            events.try_recv(),
+            Ok(OrderEvent::StatusChanged {
+                order_id: 0,
+                status: String::from("Ordering")
+            })
+        );
+    }
+
+    struct NoOrdering;
+
+    impl PlacementCheck for NoOrdering {
+        fn get_name(&self) -> &str {
+            "no ordering"
+        }
+
+        fn check(&self, _: &Order, _: DateTime<Utc>) -> Result<(), String> {
+            Err(String::from("not today"))
+        }
+    }
+
+    #[derive(Default)]
+    struct CountSettled(Arc<AtomicUsize>);
+
+    impl SettlementAction for CountSettled {
+        fn get_name(&self) -> &str {
+            "count settled"
+        }
+
+        fn on_settled(&self, _: &Id<Order>, _: &Order) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct RecordingChannel(Arc<Mutex<Vec<String>>>);
+
+    impl Channel for RecordingChannel {
+        fn get_name(&self) -> &str {
+            "recording"
+        }
+
+        fn post(&self, message: &str) {
+            self.0.lock().unwrap().push(String::from(message));
+        }
+    }
+
+    #[tokio::test]
+    async fn created_order_is_announced() {
+        // Given:
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let mut announcer = Announcer::new(String::from("https://pizza.example"));
+        announcer.add_channel(Box::new(RecordingChannel(messages.clone())));
+        let state = AppState::new().with_announcer(announcer);
+
+        // When:
+        send(&state, "POST", "/orders", Some(json!({"manager_id": 3}))).await;
+
+        // Then:
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec!["Pizza order 0 is open, 1 joined so far: https://pizza.example/orders/0"]
+        );
+    }
+
+    #[tokio::test]
+    async fn placement_is_rejected_by_plugin() {
+        // Given:
+        let mut plugins = PluginRegistry::new();
+        plugins.register_check(Box::new(NoOrdering));
+        let state = AppState::new().with_plugins(plugins);
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordering"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            parse::<Value>(&body),
+            json!({"error": "rejected by no ordering: not today"})
+        );
+        assert_eq!(
+            state.orders().get_order(&Id::new(0)).unwrap().get_status(),
+            &OrderStatus::Open
+        );
+    }
+
+    #[tokio::test]
+    async fn settlement_plugins_run_once_everybody_paid() {
+        // Given:
+        let action = CountSettled::default();
+        let settled = action.0.clone();
+        let mut plugins = PluginRegistry::new();
+        plugins.register_action(Box::new(action));
+        let state = AppState::new().with_plugins(plugins);
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            let order = orders.get_order(&id).unwrap();
+            order.add_user(Id::new(1)).unwrap();
+            for user_id in 0..2 {
+                order
+                    .add_meal_for_user(
+                        Id::new(user_id),
+                        String::from("03"),
+                        String::from("groß"),
+                        Money::new(5, 0),
+                    )
+                    .unwrap();
+            }
+        }
+
+        // When:
+        send(
+            &state,
+            "PUT",
+            "/orders/0/users/0/paid",
+            Some(json!({"amount_cents": 500})),
+        )
+        .await;
+        let settled_after_first = settled.load(Ordering::SeqCst);
+        for _ in 0..2 {
+            send(
+                &state,
+                "PUT",
+                "/orders/0/users/1/paid",
+                Some(json!({"amount_cents": 500})),
+            )
+            .await;
+        }
+
+        // Then:
+        assert_eq!(settled_after_first, 0);
+        assert_eq!(settled.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn invalid_status_change_is_rejected() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Delivered"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            state.orders().get_order(&Id::new(0)).unwrap().get_status(),
+            &crate::order_model::order::OrderStatus::Open
+        );
+    }
+
+    #[tokio::test]
+    async fn order_below_minimum_value_of_restaurant_is_not_placed() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        let (restaurant_status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/restaurant",
+            Some(json!({
+                "name": "Pizzeria Mario",
+                "phone": "+49 30 1234567",
+                "min_order_value_cents": 1500,
+                "delivery_fee_cents": 250,
+                "utc_offset_minutes": 120,
+                "opening_hours": [{"day": "Fri", "opens": "18:00", "closes": "01:00"}]
+            })),
+        )
+        .await;
+        send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordering"})),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Ordered", "eta": "2020-05-04T12:15:00+02:00"})),
+        )
+        .await;
+        let (_, restaurant) = send(&state, "GET", "/orders/0/restaurant", None).await;
+
+        // Then:
+        assert_eq!(restaurant_status, StatusCode::NO_CONTENT);
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(String::from_utf8(body).unwrap().contains("delivers from"));
+        let restaurant = parse::<Option<RestaurantResponse>>(&restaurant).unwrap();
+        assert_eq!(restaurant.delivery_fee_cents, 250);
+        assert_eq!(restaurant.opening_hours.unwrap()[0].closes, "01:00");
+    }
+
+    #[tokio::test]
+    async fn deadline_follows_cutoff_of_restaurant() {
+        // Given:
+        let clock = TestClock::new(
+            DateTime::parse_from_rfc3339("2020-05-10T19:00:00+02:00")
+                .unwrap()
+                .into(),
+        );
+        let state = AppState::with_clock(Arc::new(clock));
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/restaurant",
+            Some(json!({
+                "name": "Pizzeria Mario",
+                "utc_offset_minutes": 120,
+                "opening_hours": [
+                    {"day": "Sun", "opens": "18:00", "closes": "23:00"},
+                    {"day": "Mon", "opens": "18:00", "closes": "23:00"}
+                ],
+                "closed_days": ["Mon"],
+                "cutoffs": [{"day": "Sun", "time": "20:30"}]
+            })),
+        )
+        .await;
+        let (_, deadline) = send(&state, "GET", "/orders/0/deadline", None).await;
+        let (_, restaurant) = send(&state, "GET", "/orders/0/restaurant", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            parse::<DeadlineResponse>(&deadline).deadline,
+            Some(String::from("2020-05-10T18:30:00+00:00"))
+        );
+        let restaurant = parse::<Option<RestaurantResponse>>(&restaurant).unwrap();
+        assert_eq!(restaurant.closed_days, vec![String::from("Mon")]);
+        assert_eq!(restaurant.cutoffs[0].time, "20:30");
+    }
+
+    #[tokio::test]
+    async fn cutoffs_without_opening_hours_are_rejected() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/restaurant",
+            Some(json!({
+                "name": "Pizzeria Mario",
+                "cutoffs": [{"day": "Sun", "time": "20:30"}]
+            })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn invalid_opening_hours_are_rejected() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+
+        // When:
+        let (status, _) = send(
+            &state,
+            "PUT",
+            "/orders/0/restaurant",
+            Some(json!({
+                "name": "Pizzeria Mario",
+                "opening_hours": [{"day": "Fri", "opens": "6pm", "closes": "01:00"}]
+            })),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    /// Keys of a browser's push subscription, from the example in RFC 8291
+    fn push_subscription(endpoint: &str) -> Value {
+        json!({
+            "endpoint": endpoint,
+            "keys": {
+                "p256dh": "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+                "auth": "BTBZMqHH6r4Tts7J_aSIgg"
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn push_key_is_only_served_if_configured() {
+        // Given:
+        let vapid = Vapid::generate(String::from("mailto:pizza@example.com"));
+        let public_key = vapid.get_public_key();
+        let configured = AppState::new().with_web_push(WebPush::new(vapid));
+
+        // When:
+        let (missing, _) = send(&AppState::new(), "GET", "/push/key", None).await;
+        let (status, body) = send(&configured, "GET", "/push/key", None).await;
+
+        // Then:
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<PushKeyResponse>(&body),
+            PushKeyResponse { public_key }
+        );
+    }
+
+    #[tokio::test]
+    async fn user_can_subscribe_and_unsubscribe_browser() {
+        // Given:
+        let state = AppState::new();
+        state.users_mut().register(String::from("Anna")).unwrap();
+        let subscription = push_subscription("https://push.example.net/anna");
+
+        // When:
+        let (created, _) = send(
+            &state,
+            "POST",
+            "/users/0/push-subscriptions",
+            Some(subscription.clone()),
+        )
+        .await;
+        let (replaced, _) = send(
+            &state,
+            "POST",
+            "/users/0/push-subscriptions",
+            Some(subscription),
+        )
+        .await;
+        let (unknown_user, _) = send(
+            &state,
+            "POST",
+            "/users/1/push-subscriptions",
+            Some(push_subscription("https://push.example.net/ben")),
+        )
+        .await;
+        let (invalid, _) = send(
+            &state,
+            "POST",
+            "/users/0/push-subscriptions",
+            Some(json!({"endpoint": "https://push.example.net/anna", "keys": {"p256dh": "abc", "auth": "abc"}})),
+        )
+        .await;
+        let unsubscribe = json!({"endpoint": "https://push.example.net/anna"});
+        let (removed, _) = send(
+            &state,
+            "DELETE",
+            "/users/0/push-subscriptions",
+            Some(unsubscribe.clone()),
+        )
+        .await;
+        let (removed_again, _) = send(
+            &state,
+            "DELETE",
+            "/users/0/push-subscriptions",
+            Some(unsubscribe),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(created, StatusCode::CREATED);
+        assert_eq!(replaced, StatusCode::NO_CONTENT);
+        assert_eq!(unknown_user, StatusCode::NOT_FOUND);
+        assert_eq!(invalid, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(removed, StatusCode::NO_CONTENT);
+        assert_eq!(removed_again, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn subscribers_are_pushed_when_order_is_delivered() {
+        // Given:
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/push/screen", listener.local_addr().unwrap());
+        let vapid = Vapid::generate(String::from("mailto:pizza@example.com"));
+        let state = AppState::new().with_web_push(WebPush::new(vapid));
+        state.orders().create_order(Id::new(0));
+        let (subscribed, _) = send(
+            &state,
+            "POST",
+            "/orders/0/push-subscriptions",
+            Some(push_subscription(&endpoint)),
+        )
+        .await;
+        for status in &[
+            json!({"status": "Ordering"}),
+            json!({"status": "Ordered", "eta": "2020-05-04T12:15:00+02:00"}),
+        ] {
+            send(&state, "PUT", "/orders/0/status", Some(status.clone())).await;
+        }
+
+        // When:
+        send(
+            &state,
+            "PUT",
+            "/orders/0/status",
+            Some(json!({"status": "Delivered"})),
+        )
+        .await;
+
+        // Then:
+        assert_eq!(subscribed, StatusCode::CREATED);
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = tokio::io::BufReader::new(stream);
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            tokio::io::AsyncBufReadExt::read_line(&mut stream, &mut head)
+                .await
+                .unwrap();
+        }
+        assert!(head.starts_with("POST /push/screen HTTP/1.1\r\n"));
+        assert!(head.contains("Content-Encoding: aes128gcm\r\n"));
+        // The browser is gone, so the subscription is forgotten
+        tokio::io::AsyncWriteExt::write_all(stream.get_mut(), b"HTTP/1.1 410 Gone\r\n\r\n")
+            .await
+            .unwrap();
+        drop(stream);
+        for _ in 0..100 {
+            if state
+                .push_subscriptions()
+                .get_order_subscriptions(&Id::new(0))
+                .is_empty()
+            {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("Expired subscription was not removed");
+    }
+
+    #[tokio::test]
+    async fn reconnecting_client_gets_missed_events() {
+        // Given:
+        let state = AppState::new();
+        send(&state, "POST", "/orders", Some(json!({"manager_id": 0}))).await;
+        let (_, dashboard) = send(&state, "GET", "/orders/0/dashboard", None).await;
+        let seen = parse::<DashboardResponse>(&dashboard).sequence_number;
+        send(
+            &state,
+            "POST",
+            "/orders/0/users/0/meals",
+            Some(json!({"meal_id": "03", "variety": "groß", "price_cents": 750})),
+        )
+        .await;
+
+        // When:
+        let (status, body) = send(
+            &state,
+            "GET",
+            &format!("/orders/0/events/since/{}", seen),
+            None,
+        )
+        .await;
+        let (unknown, _) = send(&state, "GET", "/orders/0/events/since/100", None).await;
+
+        // Then:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            parse::<ReplayResponse>(&body),
+            ReplayResponse {
+                events: vec![OrderEvent::MealAdded {
+                    order_id: 0,
+                    user_id: Some(0),
+                    meal_id: String::from("03"),
+                    variety: String::from("groß"),
+                }],
+                sequence_number: seen + 1,
+            }
+        );
+        assert_eq!(unknown, StatusCode::GONE);
+    }
+}
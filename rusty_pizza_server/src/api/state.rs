@@ -0,0 +1,1056 @@
+use crate::auth::authenticator::Authenticator;
+use crate::auth::provider::AuthProviders;
+use crate::import::bank_statement::Transfer;
+use crate::import::spreadsheet::{self, ImportError, ImportReport};
+use crate::notifications::announcement::Announcer;
+use crate::notifications::bus::EventBus;
+use crate::notifications::event::OrderEvent;
+use crate::notifications::signage::SignageDisplay;
+use crate::notifications::web_push::{PushSubscriptions, WebPush};
+use crate::order_model::archive::ArchivedOrder;
+use crate::order_model::integrity::IntegrityReport;
+use crate::order_model::manager::OrderManager;
+use crate::order_model::order::{Order, OrderStatus};
+use crate::order_model::order_template::{OrderTemplate, OrderTemplates};
+use crate::order_model::retention::{RetentionPolicy, RetentionReport};
+use crate::order_model::summary::SummaryCache;
+use crate::order_model::user::User;
+use crate::persistence::ephemeral::{self, StorageStatus, TemplateWrite};
+use crate::persistence::intern::LogError;
+use crate::persistence::templates;
+use crate::plugins::registry::PluginRegistry;
+use crate::settlement::reconciliation::{self, ReconciliationReport};
+use crate::user_model::favorites::Favorites;
+use crate::user_model::repository::UserRepository;
+use crate::util::clock::{Clock, SystemClock};
+use crate::util::id::Id;
+use crate::util::locale::MoneyFormat;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime};
+
+/// Time in which a second order for the same restaurant is considered a duplicate, unless configured otherwise.
+pub const DEFAULT_DUPLICATE_WINDOW: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// What happens if an order is created while another one for the same restaurant is still open.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// The order is created, but the existing one is reported, so users can be pointed to it
+    #[default]
+    Warn,
+    /// The order is not created
+    Block,
+}
+
+/// ID of a newly created order, together with an open order for the same restaurant if there is one.
+#[derive(Debug, PartialEq)]
+pub struct CreatedOrder {
+    pub id: Id<Order>,
+    pub duplicate_of: Option<Id<Order>>,
+}
+
+/// An open order for the same restaurant already exists, which should be joined instead.
+#[derive(Debug, PartialEq)]
+pub struct DuplicateOrderError {
+    existing: Id<Order>,
+}
+
+impl DuplicateOrderError {
+    pub fn get_existing(&self) -> Id<Order> {
+        self.existing.clone()
+    }
+}
+
+impl fmt::Display for DuplicateOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order {} for this restaurant is still open",
+            self.existing.get_value()
+        )
+    }
+}
+
+impl Error for DuplicateOrderError {}
+
+/// All orders known to the server.
+pub struct Orders {
+    orders: OrderManager,
+    /// Creation time by order ID
+    created_at: HashMap<Id<Order>, SystemTime>,
+    /// Restaurant by order ID, for orders created for a restaurant
+    restaurants: HashMap<Id<Order>, String>,
+    /// ETA by order ID, of orders whose ETA was reported as passed
+    passed_etas: HashMap<Id<Order>, DateTime<Utc>>,
+    duplicate_policy: DuplicatePolicy,
+    duplicate_window: Duration,
+    retention_policy: RetentionPolicy,
+    /// Currency and locale of orders which don't set their own
+    default_format: MoneyFormat,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl Orders {
+    pub fn new() -> Orders {
+        Orders::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock + Send + Sync>) -> Orders {
+        Orders {
+            orders: OrderManager::new(),
+            created_at: HashMap::new(),
+            restaurants: HashMap::new(),
+            passed_etas: HashMap::new(),
+            duplicate_policy: DuplicatePolicy::default(),
+            duplicate_window: DEFAULT_DUPLICATE_WINDOW,
+            retention_policy: RetentionPolicy::default(),
+            default_format: MoneyFormat::default(),
+            clock,
+        }
+    }
+
+    /// Creates a new `Order` managed by the given user and returns its ID.
+    pub fn create_order(&mut self, manager_id: Id<User>) -> Id<Order> {
+        let id = self
+            .orders
+            .create_order_with_clock(manager_id, self.clock.clone());
+        self.created_at.insert(id.clone(), self.clock.now());
+        id
+    }
+
+    /// Creates a new `Order` for the given restaurant, unless the `DuplicatePolicy` forbids it.
+    pub fn create_order_for_restaurant(
+        &mut self,
+        manager_id: Id<User>,
+        restaurant: String,
+    ) -> Result<CreatedOrder, DuplicateOrderError> {
+        let duplicate_of = self.find_duplicate(&restaurant);
+        if let (DuplicatePolicy::Block, Some(existing)) = (self.duplicate_policy, &duplicate_of) {
+            return Err(DuplicateOrderError {
+                existing: existing.clone(),
+            });
+        }
+        let id = self.create_order(manager_id);
+        self.restaurants.insert(id.clone(), restaurant);
+        Ok(CreatedOrder { id, duplicate_of })
+    }
+
+    /// Opens a new `Order` from the template, unless the `DuplicatePolicy` forbids it, see `OrderTemplate::instantiate`.
+    pub fn create_order_from_template(
+        &mut self,
+        template: &OrderTemplate,
+    ) -> Result<CreatedOrder, DuplicateOrderError> {
+        let restaurant = template.get_restaurant().get_name();
+        let duplicate_of = self.find_duplicate(restaurant);
+        if let (DuplicatePolicy::Block, Some(existing)) = (self.duplicate_policy, &duplicate_of) {
+            return Err(DuplicateOrderError {
+                existing: existing.clone(),
+            });
+        }
+        let id = self
+            .orders
+            .add_order(template.instantiate(self.clock.clone()));
+        self.created_at.insert(id.clone(), self.clock.now());
+        self.restaurants.insert(id.clone(), restaurant.clone());
+        Ok(CreatedOrder { id, duplicate_of })
+    }
+
+    /// Finds an open order for the restaurant created within the duplicate window, the oldest if there are several.
+    pub fn find_duplicate(&self, restaurant: &str) -> Option<Id<Order>> {
+        let now = self.clock.now();
+        self.restaurants
+            .iter()
+            .filter(|(_, other)| other.trim().to_lowercase() == restaurant.trim().to_lowercase())
+            .filter(|(id, _)| {
+                self.orders.get_order(id).map(Order::get_status) == Some(&OrderStatus::Open)
+            })
+            .filter(|(id, _)| {
+                now.duration_since(self.created_at[id]).unwrap_or_default() < self.duplicate_window
+            })
+            .map(|(id, _)| id.clone())
+            .min_by_key(Id::get_value)
+    }
+
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy) {
+        self.duplicate_policy = policy;
+    }
+
+    pub fn set_duplicate_window(&mut self, window: Duration) {
+        self.duplicate_window = window;
+    }
+
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) {
+        self.retention_policy = policy;
+    }
+
+    /// Archives delivered orders and anonymizes and deletes archived ones as the retention policy demands.
+    pub fn enforce_retention(&mut self) -> RetentionReport {
+        let report = self
+            .orders
+            .enforce_retention(&self.retention_policy, self.clock.now());
+        for id in report.deleted() {
+            self.created_at.remove(id);
+            self.restaurants.remove(id);
+        }
+        report
+    }
+
+    pub fn get_default_format(&self) -> MoneyFormat {
+        self.default_format
+    }
+
+    pub fn set_default_format(&mut self, format: MoneyFormat) {
+        self.default_format = format;
+    }
+
+    /// Imports historical orders from a spreadsheet as archived orders, see `spreadsheet::import`.
+    pub fn import_history(
+        &mut self,
+        csv: &str,
+        users: &mut UserRepository,
+    ) -> Result<ImportReport, Vec<ImportError>> {
+        spreadsheet::import(csv, users, &mut self.orders)
+    }
+
+    /// Books incoming transfers as payments of the debts they settle, see `reconciliation::reconcile`.
+    pub fn reconcile_transfers(&mut self, transfers: Vec<Transfer>) -> ReconciliationReport {
+        reconciliation::reconcile(transfers, &mut self.orders)
+    }
+
+    /// Scans all orders for broken invariants, see `OrderManager::verify_integrity`.
+    pub fn verify_integrity(&self, users: &UserRepository) -> IntegrityReport {
+        self.orders.verify_integrity(users)
+    }
+
+    /// Orders without change for longer than `idle`, see `OrderManager::stuck_orders`.
+    pub fn stuck_orders(&self, idle: Duration) -> Vec<(Id<Order>, OrderStatus, Duration)> {
+        self.orders.stuck_orders(self.clock.now(), idle)
+    }
+
+    /// Current time according to the clock of the orders.
+    pub fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    pub fn get_clock(&self) -> Arc<dyn Clock + Send + Sync> {
+        self.clock.clone()
+    }
+
+    /// Looks up an order, first starting ordering if its deadline passed or closing it if its grace period
+    /// after delivery is over.
+    pub fn get_order(&mut self, id: &Id<Order>) -> Option<&mut Order> {
+        let now = self.clock.now();
+        let order = self.orders.get_order_mut(id)?;
+        order.start_ordering_if_past_deadline(DateTime::from(now));
+        order.close_if_expired(now);
+        Some(order)
+    }
+
+    /// Starts a new order with the participants and meals of an active or archived one, see `Order::clone_from`.
+    ///
+    /// The copy goes to the same restaurant. Returns `None` if there is no order with the given ID or its
+    /// participants were anonymized.
+    pub fn copy_order(&mut self, id: &Id<Order>, manager_id: Id<User>) -> Option<Id<Order>> {
+        let previous = self
+            .orders
+            .get_order(id)
+            .or_else(|| self.orders.get_archived_order(id))
+            .filter(|order| !order.is_anonymized())?;
+        let copy = self
+            .orders
+            .add_order(Order::clone_from(previous, manager_id));
+        self.created_at.insert(copy.clone(), self.clock.now());
+        if let Some(restaurant) = self.restaurants.get(id).cloned() {
+            self.restaurants.insert(copy.clone(), restaurant);
+        }
+        Some(copy)
+    }
+
+    /// Iterates over the active and the archived orders.
+    pub fn stored_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders
+            .orders()
+            .chain(self.orders.archived_orders())
+            .map(|(_, order)| order)
+    }
+
+    /// Iterates over all orders which are not archived.
+    pub fn orders(&self) -> impl Iterator<Item = (&Id<Order>, &Order)> {
+        self.orders.orders()
+    }
+
+    /// Orders not delivered by their ETA, each reported once per ETA, sorted by ID.
+    pub fn take_passed_etas(&mut self) -> Vec<(Id<Order>, DateTime<Utc>)> {
+        let now = DateTime::<Utc>::from(self.clock.now());
+        let mut passed: Vec<(Id<Order>, DateTime<Utc>)> = self
+            .orders
+            .orders()
+            .filter_map(|(id, order)| match order.get_status() {
+                OrderStatus::Ordered { eta: Some(eta), .. }
+                    if *eta <= now && self.passed_etas.get(id) != Some(eta) =>
+                {
+                    Some((id.clone(), *eta))
+                }
+                _ => None,
+            })
+            .collect();
+        passed.sort();
+        self.passed_etas.extend(passed.iter().cloned());
+        passed
+    }
+
+    pub fn get_archived(&self, id: &Id<Order>) -> Option<&ArchivedOrder> {
+        self.orders.get_archived(id)
+    }
+
+    /// Iterates over the archived orders together with the summaries taken when they were archived.
+    pub fn archived(&self) -> impl Iterator<Item = (&Id<Order>, &ArchivedOrder)> {
+        self.orders.archived()
+    }
+
+    /// Iterates over all orders together with the time they were created.
+    pub fn orders_with_creation_time(&self) -> impl Iterator<Item = (&Order, SystemTime)> {
+        self.orders
+            .orders()
+            .map(move |(id, order)| (order, self.created_at[id]))
+    }
+}
+
+impl Default for Orders {
+    fn default() -> Orders {
+        Orders::new()
+    }
+}
+
+impl fmt::Debug for Orders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Orders")
+            .field("orders", &self.orders)
+            .field("created_at", &self.created_at)
+            .field("restaurants", &self.restaurants)
+            .field("passed_etas", &self.passed_etas)
+            .field("duplicate_policy", &self.duplicate_policy)
+            .field("duplicate_window", &self.duplicate_window)
+            .field("retention_policy", &self.retention_policy)
+            .field("default_format", &self.default_format)
+            .finish_non_exhaustive()
+    }
+}
+
+/// State shared by all request handlers, which may run on different threads.
+#[derive(Clone, Debug, Default)]
+pub struct AppState {
+    orders: Arc<Mutex<Orders>>,
+    /// Users are read far more often than registered, so readers don't block each other
+    users: Arc<RwLock<UserRepository>>,
+    events: EventBus,
+    plugins: Arc<PluginRegistry>,
+    announcer: Arc<Announcer>,
+    auth: Arc<Mutex<Authenticator>>,
+    auth_providers: Arc<AuthProviders>,
+    /// Locked after the orders, as the favorites are taken from them
+    favorites: Arc<Mutex<Favorites>>,
+    /// Read without locking the orders, so dashboards don't wait for changes
+    summaries: Arc<SummaryCache>,
+    /// Sends the push messages if configured, subscriptions are accepted anyway
+    web_push: Option<Arc<WebPush>>,
+    push_subscriptions: Arc<Mutex<PushSubscriptions>>,
+    templates: Arc<Mutex<OrderTemplates>>,
+    /// Where the templates are saved on every change, they only live in memory without one
+    template_file: Option<Arc<PathBuf>>,
+    /// Whether the template file could be read, changes are only queued while it can't
+    storage: Arc<Mutex<StorageStatus>>,
+    /// Whether changes need a session, off so clients from before logins keep working
+    authentication_required: bool,
+    /// Bearer token of operators for the `/admin` routes, which are closed without one
+    admin_token: Option<Arc<str>>,
+}
+
+impl AppState {
+    pub fn new() -> AppState {
+        AppState::default()
+    }
+
+    /// Creates the state with orders timestamped by the given clock.
+    pub fn with_clock(clock: Arc<dyn Clock + Send + Sync>) -> AppState {
+        AppState {
+            orders: Arc::new(Mutex::new(Orders::with_clock(clock))),
+            users: Arc::new(RwLock::new(UserRepository::new())),
+            events: EventBus::default(),
+            plugins: Arc::default(),
+            announcer: Arc::default(),
+            auth: Arc::default(),
+            auth_providers: Arc::default(),
+            favorites: Arc::default(),
+            summaries: Arc::default(),
+            web_push: None,
+            push_subscriptions: Arc::default(),
+            templates: Arc::default(),
+            template_file: None,
+            storage: Arc::default(),
+            authentication_required: false,
+            admin_token: None,
+        }
+    }
+
+    /// Replaces the plugins, meant to be called once at startup before serving requests.
+    pub fn with_plugins(mut self, plugins: PluginRegistry) -> AppState {
+        self.plugins = Arc::new(plugins);
+        self
+    }
+
+    pub fn orders(&self) -> MutexGuard<'_, Orders> {
+        self.orders.lock().expect("Orders lock is poisoned")
+    }
+
+    pub fn users(&self) -> RwLockReadGuard<'_, UserRepository> {
+        self.users.read().expect("Users lock is poisoned")
+    }
+
+    pub fn users_mut(&self) -> RwLockWriteGuard<'_, UserRepository> {
+        self.users.write().expect("Users lock is poisoned")
+    }
+
+    /// Checks the integrity of all orders against the registered users.
+    pub fn verify_integrity(&self) -> IntegrityReport {
+        let users = self.users();
+        self.orders().verify_integrity(&users)
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Limits the events queued per realtime connection, meant to be called once at startup before serving requests.
+    pub fn with_event_queue_limit(mut self, limit: usize) -> AppState {
+        self.events = self.events.with_queue_limit(limit);
+        self
+    }
+
+    pub fn plugins(&self) -> &PluginRegistry {
+        &self.plugins
+    }
+
+    /// Replaces the announcer, meant to be called once at startup before serving requests.
+    pub fn with_announcer(mut self, announcer: Announcer) -> AppState {
+        self.announcer = Arc::new(announcer);
+        self
+    }
+
+    pub fn announcer(&self) -> &Announcer {
+        &self.announcer
+    }
+
+    /// Sends push messages to subscribed browsers, meant to be called once at startup before serving requests.
+    pub fn with_web_push(mut self, web_push: WebPush) -> AppState {
+        self.web_push = Some(Arc::new(web_push));
+        self
+    }
+
+    pub fn web_push(&self) -> Option<&WebPush> {
+        self.web_push.as_deref()
+    }
+
+    pub fn push_subscriptions(&self) -> MutexGuard<'_, PushSubscriptions> {
+        self.push_subscriptions
+            .lock()
+            .expect("Push subscriptions lock is poisoned")
+    }
+
+    /// Loads the templates saved to the file and saves them there on every change, meant to be called once at
+    /// startup before serving requests.
+    pub fn with_template_file(mut self, path: PathBuf) -> Result<AppState, LogError> {
+        self.templates = Arc::new(Mutex::new(templates::load_templates(&path)?));
+        self.template_file = Some(Arc::new(path));
+        Ok(self)
+    }
+
+    /// Like `with_template_file`, but starts without templates if the file can't be read, e.g. because the share it
+    /// is on is down. Changes are then only kept in memory until `replay_storage` succeeds.
+    pub fn with_template_file_or_ephemeral(self, path: PathBuf) -> AppState {
+        match templates::load_templates(&path) {
+            Ok(loaded) => AppState {
+                templates: Arc::new(Mutex::new(loaded)),
+                template_file: Some(Arc::new(path)),
+                ..self
+            },
+            Err(error) => {
+                let since = self.orders().now();
+                AppState {
+                    template_file: Some(Arc::new(path)),
+                    storage: Arc::new(Mutex::new(StorageStatus::unavailable(
+                        error.to_string(),
+                        since,
+                    ))),
+                    ..self
+                }
+            }
+        }
+    }
+
+    pub fn templates(&self) -> MutexGuard<'_, OrderTemplates> {
+        self.templates.lock().expect("Templates lock is poisoned")
+    }
+
+    /// Saves the templates changed by `write` to the template file, if there is one. While ephemeral, the write is
+    /// only queued, as the file may hold templates which were never loaded.
+    pub fn save_templates(
+        &self,
+        templates: &OrderTemplates,
+        write: TemplateWrite,
+    ) -> io::Result<()> {
+        let mut storage = self.storage_status();
+        if storage.is_ephemeral() {
+            storage.queue_write(write);
+            return Ok(());
+        }
+        match &self.template_file {
+            Some(path) => templates::save_templates(templates, path),
+            None => Ok(()),
+        }
+    }
+
+    /// Locked after the templates, as saving them checks whether the storage is available.
+    pub fn storage_status(&self) -> MutexGuard<'_, StorageStatus> {
+        self.storage.lock().expect("Storage lock is poisoned")
+    }
+
+    /// Loads the template file again if running ephemeral, applies the writes queued since and saves the result.
+    /// Returns how many queued writes were replayed, none if the storage was available anyway.
+    ///
+    /// Templates whose ID was taken by a stored one are moved, see `StorageStatus::moved_templates`.
+    pub fn replay_storage(&self) -> Result<usize, LogError> {
+        let path = match &self.template_file {
+            Some(path) => path,
+            None => return Ok(0),
+        };
+        // Checked with the templates locked, so concurrent replays can't both replay the queued writes
+        let mut templates = self.templates();
+        if !self.storage_status().is_ephemeral() {
+            return Ok(0);
+        }
+        let writes = self.storage_status().queued_writes().to_vec();
+        let replayed = ephemeral::replay_templates(templates::load_templates(path)?, &writes);
+        templates::save_templates(&replayed.templates, path)?;
+        *templates = replayed.templates;
+        Ok(self.storage_status().mark_available(replayed.moved).len())
+    }
+
+    /// Tries to replay the queued writes every `interval` from now on while running ephemeral. Why it failed the last
+    /// time is kept as the reason of the `storage_status`.
+    pub fn spawn_storage_recovery(&self, interval: Duration) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            while state.storage_status().is_ephemeral() {
+                ticks.tick().await;
+                if let Err(error) = state.replay_storage() {
+                    state.storage_status().set_reason(error.to_string());
+                }
+            }
+        });
+    }
+
+    /// Tells everybody subscribed to the order that it arrived, if Web Push is configured.
+    pub fn push_delivered(&self, order_id: &Id<Order>, order: &Order) {
+        if let Some(web_push) = &self.web_push {
+            let recipients = self.push_subscriptions().recipients(order_id, order);
+            let message = json!({
+                "type": "Delivered",
+                "order_id": order_id.get_value(),
+                "text": self.announcer.delivered_message(order_id, order),
+            });
+            web_push.post_in_background(
+                self.push_subscriptions.clone(),
+                recipients,
+                message.to_string(),
+            );
+        }
+    }
+
+    /// Requires a session for changes, meant to be called once at startup before serving requests.
+    pub fn with_required_authentication(mut self) -> AppState {
+        self.authentication_required = true;
+        self
+    }
+
+    pub fn is_authentication_required(&self) -> bool {
+        self.authentication_required
+    }
+
+    /// Opens the `/admin` routes to operators sending the token, meant to be called once at startup before serving
+    /// requests.
+    pub fn with_admin_token(mut self, token: String) -> AppState {
+        self.admin_token = Some(Arc::from(token));
+        self
+    }
+
+    /// Whether the token is the one of the operators, never if none was configured.
+    pub fn is_admin_token(&self, token: &str) -> bool {
+        self.admin_token.as_deref() == Some(token)
+    }
+
+    pub fn summaries(&self) -> &SummaryCache {
+        &self.summaries
+    }
+
+    /// Enforces the retention policy of the orders every `interval` from now on, logging what was purged.
+    pub fn spawn_retention(&self, interval: Duration) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                let report = state.enforce_retention();
+                if !report.is_empty() {
+                    println!(
+                        "Retention: archived {:?}, anonymized {:?}, deleted {:?}",
+                        report
+                            .archived()
+                            .iter()
+                            .map(Id::get_value)
+                            .collect::<Vec<_>>(),
+                        report
+                            .anonymized()
+                            .iter()
+                            .map(Id::get_value)
+                            .collect::<Vec<_>>(),
+                        report
+                            .deleted()
+                            .iter()
+                            .map(Id::get_value)
+                            .collect::<Vec<_>>()
+                    );
+                }
+            }
+        });
+    }
+
+    /// `Orders::enforce_retention`, also dropping the summaries of archived and deleted orders. Dashboards of archived
+    /// orders show the summary taken when they were archived.
+    pub fn enforce_retention(&self) -> RetentionReport {
+        let report = self.orders().enforce_retention();
+        for id in report.archived().iter().chain(report.deleted()) {
+            self.summaries.remove(id);
+        }
+        report
+    }
+
+    /// Pushes the summaries to the display in the background from now on, see `SignageDisplay::run`.
+    pub fn spawn_signage(&self, display: SignageDisplay) {
+        let clock = self.orders().get_clock();
+        tokio::spawn(display.run(self.summaries.clone(), clock));
+    }
+
+    pub fn auth(&self) -> MutexGuard<'_, Authenticator> {
+        self.auth.lock().expect("Auth lock is poisoned")
+    }
+
+    /// Replaces the external login providers, meant to be called once at startup before serving requests.
+    pub fn with_auth_providers(mut self, providers: AuthProviders) -> AppState {
+        self.auth_providers = Arc::new(providers);
+        self
+    }
+
+    pub fn auth_providers(&self) -> &AuthProviders {
+        &self.auth_providers
+    }
+
+    pub fn favorites(&self) -> MutexGuard<'_, Favorites> {
+        self.favorites.lock().expect("Favorites lock is poisoned")
+    }
+
+    /// Publishes an `OrderEvent::EtaPassed` for every order not delivered by its ETA every `interval` from now on.
+    pub fn spawn_eta_watch(&self, interval: Duration) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                state.publish_passed_etas();
+            }
+        });
+    }
+
+    /// Publishes the orders not delivered by their ETA, to be called periodically. Returns how many were published.
+    pub fn publish_passed_etas(&self) -> usize {
+        let passed = self.orders().take_passed_etas();
+        for (id, eta) in &passed {
+            self.events.publish(OrderEvent::EtaPassed {
+                order_id: id.get_value(),
+                eta: eta.to_rfc3339(),
+            });
+        }
+        passed.len()
+    }
+
+    /// Announces the orders whose deadline is near every `interval` from now on.
+    pub fn spawn_closing_announcements(&self, interval: Duration) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                state.announce_closing_soon();
+            }
+        });
+    }
+
+    /// Announces the orders whose deadline is near, to be called periodically. Returns how many were announced.
+    pub fn announce_closing_soon(&self) -> usize {
+        let orders = self.orders();
+        self.announcer
+            .announce_closing_soon(DateTime::from(orders.now()), orders.orders())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::order::{OrderStatus, DEFAULT_GRACE_PERIOD};
+    use crate::order_model::restaurant::Restaurant;
+    use crate::util::clock::TestClock;
+    use rstest::rstest;
+
+    #[test]
+    fn queued_writes_are_replayed_once_by_concurrent_replays() {
+        // Given:
+        let path = std::env::temp_dir().join(format!(
+            "rusty_pizza_concurrent_replay_{}.jsonl",
+            std::process::id()
+        ));
+        std::fs::create_dir(&path).unwrap();
+        let state = AppState::new().with_template_file_or_ephemeral(path.clone());
+        {
+            let mut templates = state.templates();
+            let template = OrderTemplate::new(
+                String::from("Friday pizza"),
+                Id::new(0),
+                Restaurant::new(String::from("Luigi")),
+            );
+            let id = templates.add(template.clone());
+            state
+                .save_templates(&templates, TemplateWrite::Added(id, Box::new(template)))
+                .unwrap();
+        }
+        std::fs::remove_dir(&path).unwrap();
+
+        // When:
+        let replays: Vec<_> = (0..4)
+            .map(|_| {
+                let state = state.clone();
+                std::thread::spawn(move || state.replay_storage().unwrap())
+            })
+            .collect();
+        let replayed: usize = replays
+            .into_iter()
+            .map(|replay| replay.join().unwrap())
+            .sum();
+
+        // Then:
+        let saved = templates::load_templates(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replayed, 1);
+        assert_eq!(saved.len(), 1);
+        assert_eq!(state.templates().len(), 1);
+    }
+
+    #[test]
+    fn passed_eta_is_published_once() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()));
+        let mut events = state.events().subscribe();
+        let id = state.orders().create_order(Id::new(0));
+        {
+            let mut orders = state.orders();
+            let order = orders.get_order(&id).unwrap();
+            order.start_ordering().unwrap();
+            let eta = DateTime::from(clock.now() + Duration::from_secs(60));
+            order.mark_ordered(Some(eta)).unwrap();
+        }
+
+        // When:
+        let early = state.publish_passed_etas();
+        clock.advance(Duration::from_secs(60));
+        let passed = state.publish_passed_etas();
+        let again = state.publish_passed_etas();
+
+        // Then:
+        assert_eq!((early, passed, again), (0, 1, 0));
+        assert_eq!(
+            events.try_recv(),
+            Ok(OrderEvent::EtaPassed {
+                order_id: 0,
+                eta: String::from("1970-01-01T00:01:00+00:00")
+            })
+        );
+    }
+
+    #[test]
+    fn created_orders_have_unique_ids() {
+        // Given:
+        let mut orders = Orders::new();
+
+        // When:
+        let order1_id = orders.create_order(Id::new(0));
+        let order2_id = orders.create_order(Id::new(0));
+
+        // Then:
+        assert!(order1_id != order2_id);
+    }
+
+    #[test]
+    fn created_order_can_be_looked_up() {
+        // Given:
+        let mut orders = Orders::new();
+        let id = orders.create_order(Id::new(3));
+
+        // When:
+        let order = orders.get_order(&id);
+
+        // Then:
+        assert_eq!(order, Some(&mut Order::new(Id::new(3))));
+    }
+
+    #[test]
+    fn unknown_order_cannot_be_looked_up() {
+        // Given:
+        let mut orders = Orders::new();
+
+        // When:
+        let order = orders.get_order(&Id::new(0));
+
+        // Then:
+        assert_eq!(order, None);
+    }
+
+    #[test]
+    fn orders_are_created_at_current_time() {
+        // Given:
+        let clock = TestClock::default();
+        let mut orders = Orders::with_clock(Arc::new(clock.clone()));
+        clock.advance(Duration::from_secs(60));
+
+        // When:
+        orders.create_order(Id::new(0));
+
+        // Then:
+        assert_eq!(
+            orders
+                .orders_with_creation_time()
+                .map(|(_, created_at)| created_at)
+                .collect::<Vec<SystemTime>>(),
+            vec![SystemTime::UNIX_EPOCH + Duration::from_secs(60)]
+        );
+    }
+
+    #[test]
+    fn delivered_order_is_closed_on_lookup_after_grace_period() {
+        // Given:
+        let clock = TestClock::default();
+        let mut orders = Orders::with_clock(Arc::new(clock.clone()));
+        let id = orders.create_order(Id::new(0));
+        {
+            let order = orders.get_order(&id).unwrap();
+            order.start_ordering().unwrap();
+            order.mark_ordered(None).unwrap();
+            order.mark_delivered(clock.now()).unwrap();
+        }
+        clock.advance(DEFAULT_GRACE_PERIOD);
+
+        // When:
+        let order = orders.get_order(&id).unwrap();
+
+        // Then:
+        assert_eq!(order.get_status(), &OrderStatus::Closed);
+    }
+
+    #[test]
+    fn duplicate_order_is_reported() {
+        // Given:
+        let mut orders = Orders::new();
+        let existing = orders
+            .create_order_for_restaurant(Id::new(0), String::from("Pizzeria Mario"))
+            .unwrap();
+
+        // When:
+        let created =
+            orders.create_order_for_restaurant(Id::new(1), String::from("pizzeria mario "));
+
+        // Then:
+        assert_eq!(
+            created,
+            Ok(CreatedOrder {
+                id: Id::new(1),
+                duplicate_of: Some(existing.id)
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_order_can_be_blocked() {
+        // Given:
+        let mut orders = Orders::new();
+        orders.set_duplicate_policy(DuplicatePolicy::Block);
+        orders
+            .create_order_for_restaurant(Id::new(0), String::from("Pizzeria Mario"))
+            .unwrap();
+
+        // When:
+        let created =
+            orders.create_order_for_restaurant(Id::new(1), String::from("Pizzeria Mario"));
+
+        // Then:
+        assert_eq!(created.map_err(|e| e.get_existing()), Err(Id::new(0)));
+        assert!(orders.get_order(&Id::new(1)).is_none());
+    }
+
+    #[test]
+    fn order_from_template_counts_as_duplicate() {
+        // Given:
+        let mut orders = Orders::new();
+        let template = OrderTemplate::new(
+            String::from("Friday pizza"),
+            Id::new(0),
+            Restaurant::new(String::from("Pizzeria Mario")),
+        );
+        let first = orders.create_order_from_template(&template).unwrap();
+
+        // When:
+        let created =
+            orders.create_order_for_restaurant(Id::new(1), String::from("pizzeria mario"));
+
+        // Then:
+        assert_eq!(first.duplicate_of, None);
+        assert_eq!(created.unwrap().duplicate_of, Some(first.id));
+    }
+
+    #[rstest(
+        restaurant,
+        elapsed,
+        start_ordering,
+        case("Pizzeria Luigi", Duration::from_secs(0), false),
+        case("Pizzeria Mario", DEFAULT_DUPLICATE_WINDOW, false),
+        case("Pizzeria Mario", Duration::from_secs(0), true)
+    )]
+    fn order_is_no_duplicate(restaurant: &str, elapsed: Duration, start_ordering: bool) {
+        // Given:
+        let clock = TestClock::default();
+        let mut orders = Orders::with_clock(Arc::new(clock.clone()));
+        orders.set_duplicate_policy(DuplicatePolicy::Block);
+        let existing = orders
+            .create_order_for_restaurant(Id::new(0), String::from("Pizzeria Mario"))
+            .unwrap();
+        if start_ordering {
+            orders
+                .get_order(&existing.id)
+                .unwrap()
+                .start_ordering()
+                .unwrap();
+        }
+        clock.advance(elapsed);
+
+        // When:
+        let created = orders.create_order_for_restaurant(Id::new(1), String::from(restaurant));
+
+        // Then:
+        assert_eq!(
+            created,
+            Ok(CreatedOrder {
+                id: Id::new(1),
+                duplicate_of: None
+            })
+        );
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn state_can_be_shared_between_threads() {
+        assert_send_sync::<AppState>();
+        assert_send_sync::<Order>();
+        assert_send_sync::<UserRepository>();
+    }
+
+    #[test]
+    fn concurrent_changes_are_not_lost() {
+        // Given:
+        let state = AppState::new();
+        let id = state.orders().create_order(Id::new(0));
+
+        // When:
+        let threads: Vec<_> = (1..=8)
+            .map(|user_id| {
+                let state = state.clone();
+                let id = id.clone();
+                std::thread::spawn(move || {
+                    state
+                        .users_mut()
+                        .register(format!("User {}", user_id))
+                        .unwrap();
+                    state
+                        .orders()
+                        .get_order(&id)
+                        .unwrap()
+                        .add_user(Id::new(user_id))
+                        .unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // Then:
+        assert_eq!(state.users().users().count(), 8);
+        let mut orders = state.orders();
+        let order = orders.get_order(&id).unwrap();
+        assert!((0..=8).all(|user_id| order.is_participating(&Id::new(user_id))));
+    }
+
+    #[test]
+    fn state_clones_share_orders() {
+        // Given:
+        let state = AppState::new();
+        let clone = state.clone();
+
+        // When:
+        let id = state.orders().create_order(Id::new(0));
+
+        // Then:
+        assert!(clone.orders().get_order(&id).is_some());
+    }
+
+    #[test]
+    fn orders_closing_soon_are_announced() {
+        // Given:
+        let clock = TestClock::default();
+        let state = AppState::with_clock(Arc::new(clock.clone()));
+        let deadline = DateTime::from(clock.now()) + chrono::Duration::minutes(30);
+        {
+            let mut orders = state.orders();
+            let id = orders.create_order(Id::new(0));
+            orders
+                .get_order(&id)
+                .unwrap()
+                .set_deadline(Some(deadline))
+                .unwrap();
+        }
+
+        // When:
+        let early = state.announce_closing_soon();
+        clock.advance(Duration::from_secs(25 * 60));
+        let late = state.announce_closing_soon();
+
+        // Then:
+        assert_eq!(early, 0);
+        assert_eq!(late, 1);
+    }
+}
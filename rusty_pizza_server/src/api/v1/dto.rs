@@ -0,0 +1,1254 @@
+use crate::auth::role::Role;
+use crate::export::cost_centers::CostCenterReport;
+use crate::import::bank_statement::Transfer;
+use crate::import::spreadsheet::ImportReport;
+use crate::notifications::event::OrderEvent;
+use crate::order_model::meal::{PriceBreakdown, PriceComponent};
+use crate::order_model::order::{NotAllPaidEnoughError, Order};
+use crate::order_model::order_template::OrderTemplate;
+use crate::order_model::payment::{HeldPayment, Installment, ReceivedPayment};
+use crate::order_model::report::{Balance, SplitAmount};
+use crate::order_model::restaurant::{OpeningPeriod, Restaurant};
+use crate::order_model::retention::RetentionReport;
+use crate::order_model::summary::OrderSummary;
+use crate::payments::request::PaymentRequest;
+use crate::persistence::ephemeral::StorageStatus;
+use crate::settlement::reconciliation::{ReconciliationReport, UnmatchedReason};
+use crate::stats::fairness::{FairnessReport, Imbalance};
+use crate::stats::money::{MoneyStats, Trend};
+use crate::stats::orders::OrderStatistics;
+use crate::util::id::Id;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateOrderRequest {
+    pub manager_id: u32,
+    /// Restaurant the order goes to, used to detect duplicate orders
+    #[serde(default)]
+    pub restaurant: Option<String>,
+    /// ISO 4217 code, e.g. "CHF", the server default if missing
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Locale tag, e.g. "de-CH", the server default if missing
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyOrderRequest {
+    /// Manager of the copy, who doesn't have to be the manager of the copied order
+    pub manager_id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatedOrderResponse {
+    pub id: u32,
+    /// Open order for the same restaurant, which could have been joined instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatedResponse {
+    pub id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositRefundResponse {
+    /// Zero if the deposit was refunded before or the meal has none
+    pub refund_cents: u32,
+}
+
+/// Next status of an order, e.g. `{"status": "Ordered", "time": "12:15"}`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status")]
+pub enum StatusRequest {
+    Ordering,
+    Ordered {
+        /// RFC 3339 time the restaurant expects to deliver at, if it said when
+        #[serde(default)]
+        eta: Option<String>,
+    },
+    Delivered,
+    Cancelled,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EtaRequest {
+    /// RFC 3339 time, e.g. "2020-05-04T12:15:00+02:00", `None` removes the ETA
+    pub eta: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadlineRequest {
+    /// RFC 3339 time, e.g. "2020-05-04T11:30:00+02:00", `None` removes the deadline
+    pub deadline: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadlineResponse {
+    /// RFC 3339 time in UTC
+    pub deadline: Option<String>,
+    /// Countdown until the deadline, zero once it passed
+    pub seconds_left: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EtaResponse {
+    /// RFC 3339 time in UTC
+    pub eta: Option<String>,
+    /// Countdown until the expected delivery, zero once it passed
+    pub seconds_left: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterUserRequest {
+    pub name: String,
+    /// Needed to log in, users without password can only take part while authentication is not required
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginRequest {
+    pub name: String,
+    pub password: String,
+}
+
+/// Credentials for an external login provider, which takes either a password or a token.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExternalLoginRequest {
+    Password { name: String, password: String },
+    Token { token: String },
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionResponse {
+    /// Sent as `Authorization: Bearer <token>` by later requests
+    pub token: String,
+    pub user_id: u32,
+}
+
+/// Role of a user in an order, see `auth::role::Role`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoleResponse {
+    Manager,
+    Participant,
+}
+
+impl From<Role> for RoleResponse {
+    fn from(role: Role) -> RoleResponse {
+        match role {
+            Role::Manager => RoleResponse::Manager,
+            Role::Participant => RoleResponse::Participant,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderRoleResponse {
+    pub order_id: u32,
+    pub role: RoleResponse,
+}
+
+/// The user a session token belongs to.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentSessionResponse {
+    pub user_id: u32,
+    pub name: String,
+    /// Active orders the user takes part in, sorted by ID
+    pub orders: Vec<OrderRoleResponse>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddUserRequest {
+    pub user_id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddMealRequest {
+    pub meal_id: String,
+    pub variety: String,
+    pub price_cents: u32,
+    /// Free text like "cut into 8 slices", kept as it is if missing when a meal is changed; not kept for office
+    /// meals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Deposit on top of the price, e.g. for a bottle, the one of the menu if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deposit_cents: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatedMealsResponse {
+    /// IDs of the added meals, in the order they were added
+    pub ids: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreparationsRequest {
+    /// Codes like "well-done" or "gluten-free-base", replacing the ones set before
+    pub preparations: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteRequest {
+    /// Replaces the note set before, removing it if missing
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountRequest {
+    pub amount_cents: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallmentResponse {
+    pub id: u32,
+    pub amount_cents: u32,
+    /// RFC 3339 time in UTC, missing for amounts set as a whole
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paid_at: Option<String>,
+}
+
+impl From<&Installment> for InstallmentResponse {
+    fn from(installment: &Installment) -> InstallmentResponse {
+        InstallmentResponse {
+            id: installment.get_id().get_value(),
+            amount_cents: installment.get_amount().get_total_cents(),
+            paid_at: installment
+                .get_paid_at()
+                .map(|time| DateTime::<Utc>::from(time).to_rfc3339()),
+        }
+    }
+}
+
+/// A suspected duplicate payment waiting for the manager.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeldPaymentResponse {
+    pub id: u32,
+    pub amount_cents: u32,
+    pub paid_at: Option<String>,
+    /// The payment this one seems to repeat
+    pub duplicate_of: u32,
+    pub warning: String,
+}
+
+impl From<&HeldPayment> for HeldPaymentResponse {
+    fn from(held: &HeldPayment) -> HeldPaymentResponse {
+        let installment = InstallmentResponse::from(held.get_installment());
+        HeldPaymentResponse {
+            id: installment.id,
+            amount_cents: installment.amount_cents,
+            paid_at: installment.paid_at,
+            duplicate_of: held.get_reason().get_original().get_value(),
+            warning: held.get_reason().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentsResponse {
+    /// Oldest first
+    pub payments: Vec<InstallmentResponse>,
+    pub paid_cents: u32,
+    /// Not included in `paid_cents`
+    #[serde(default)]
+    pub held: Vec<HeldPaymentResponse>,
+}
+
+/// The ID of the added payment, or of the payment it repeats, with a warning if it was held as duplicate.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivedPaymentResponse {
+    pub id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+impl From<&ReceivedPayment> for ReceivedPaymentResponse {
+    fn from(received: &ReceivedPayment) -> ReceivedPaymentResponse {
+        match received {
+            ReceivedPayment::Booked(id) | ReceivedPayment::Repeated(id) => {
+                ReceivedPaymentResponse {
+                    id: id.get_value(),
+                    duplicate_of: None,
+                    warning: None,
+                }
+            }
+            ReceivedPayment::Held(id, reason) => ReceivedPaymentResponse {
+                id: id.get_value(),
+                duplicate_of: Some(reason.get_original().get_value()),
+                warning: Some(reason.to_string()),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadyRequest {
+    pub ready: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentClaimRequest {
+    pub amount_cents: u32,
+    /// How the money was transferred, e.g. "PayPal"
+    pub method: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserIdsResponse {
+    pub user_ids: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotalsResponse {
+    pub price_cents: u32,
+    /// Part of the price paid from the office budget
+    pub office_price_cents: u32,
+    /// Part of the price shared by the participants, e.g. drinks for everybody
+    #[serde(default)]
+    pub shared_price_cents: u32,
+    pub tip_cents: u32,
+    /// Part of the price that is refunded when the bottles and cans are returned
+    #[serde(default)]
+    pub deposit_cents: u32,
+    /// Deposits already refunded, no longer part of the price
+    #[serde(default)]
+    pub returned_deposit_cents: u32,
+    /// Change the manager gets back, if enough money was paid in total
+    pub change_cents: Option<u32>,
+    /// Money missing to pay the bill
+    pub underpaid_cents: Option<u32>,
+    /// IDs of the users that didn't pay enough
+    pub paid_less: Vec<u32>,
+    /// Cents added to the shares of the users so the split amounts add up, already part of the price
+    #[serde(default)]
+    pub rounding: Vec<RoundingAdjustmentResponse>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitAmountResponse {
+    DeliveryFee,
+    SharedMeals,
+}
+
+impl From<SplitAmount> for SplitAmountResponse {
+    fn from(split: SplitAmount) -> SplitAmountResponse {
+        match split {
+            SplitAmount::DeliveryFee => SplitAmountResponse::DeliveryFee,
+            SplitAmount::SharedMeals => SplitAmountResponse::SharedMeals,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundingAdjustmentResponse {
+    pub user_id: u32,
+    pub split: SplitAmountResponse,
+    pub amount_cents: u32,
+}
+
+impl From<&Order> for TotalsResponse {
+    fn from(order: &Order) -> TotalsResponse {
+        let (change_cents, underpaid_cents, paid_less) = match order.calculate_total_change() {
+            Ok(change) => (Some(change.get_total_cents()), None, HashSet::new()),
+            Err(NotAllPaidEnoughError::EnoughInTotal { change, paid_less }) => {
+                (Some(change.get_total_cents()), None, paid_less)
+            }
+            Err(NotAllPaidEnoughError::Underpaid {
+                underpaid,
+                paid_less,
+            }) => (None, Some(underpaid.get_total_cents()), paid_less),
+        };
+        let mut paid_less: Vec<u32> = paid_less.iter().map(Id::get_value).collect();
+        paid_less.sort_unstable();
+        let rounding = order
+            .payment_report()
+            .users()
+            .iter()
+            .flat_map(|user| {
+                user.adjustments()
+                    .iter()
+                    .map(move |adjustment| RoundingAdjustmentResponse {
+                        user_id: user.get_user_id().get_value(),
+                        split: adjustment.get_split().into(),
+                        amount_cents: adjustment.get_amount().get_total_cents(),
+                    })
+            })
+            .collect();
+        TotalsResponse {
+            price_cents: order.calculate_total_price().get_total_cents(),
+            office_price_cents: order.calculate_office_price().get_total_cents(),
+            shared_price_cents: order.calculate_shared_price().get_total_cents(),
+            tip_cents: order.calculate_total_tip().get_total_cents(),
+            deposit_cents: order.calculate_open_deposit().get_total_cents(),
+            returned_deposit_cents: order.calculate_returned_deposit().get_total_cents(),
+            change_cents,
+            underpaid_cents,
+            paid_less,
+            rounding,
+        }
+    }
+}
+
+/// What a short code like "ORD-0039" stands for.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id")]
+pub enum ResolvedCodeResponse {
+    Order(u32),
+    User(u32),
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonthlyMoneyResponse {
+    pub year: i32,
+    pub month: u32,
+    pub orders: u32,
+    pub spend_cents: u32,
+    /// Change compared to the calendar month before, `None` for the first month
+    pub trend_cents: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostCenterRequest {
+    /// Replaces the cost center set before, removing it if missing
+    #[serde(default)]
+    pub cost_center: Option<String>,
+}
+
+/// Amounts billed to a cost center, `null` for the untagged ones
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostCenterEntry {
+    pub cost_center: Option<String>,
+    /// Net prices of the meals
+    pub net_cents: u32,
+    /// VAT included in the prices of the meals
+    pub tax_cents: u32,
+    /// Shares of delivery fees and shared meals
+    pub shares_cents: u32,
+    pub tips_cents: u32,
+    pub total_cents: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostCentersResponse {
+    /// Untagged amounts first, then by cost center
+    pub cost_centers: Vec<CostCenterEntry>,
+}
+
+impl From<&CostCenterReport> for CostCentersResponse {
+    fn from(report: &CostCenterReport) -> CostCentersResponse {
+        CostCentersResponse {
+            cost_centers: report
+                .cost_centers()
+                .iter()
+                .map(|(cost_center, amounts)| {
+                    let breakdown = amounts.tax_breakdown();
+                    CostCenterEntry {
+                        cost_center: cost_center.clone(),
+                        net_cents: breakdown.get_net().get_total_cents(),
+                        tax_cents: breakdown.get_tax().get_total_cents(),
+                        shares_cents: amounts.get_shares().get_total_cents(),
+                        tips_cents: amounts.get_tips().get_total_cents(),
+                        total_cents: amounts.get_total().get_total_cents(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoneyStatsResponse {
+    pub largest_order_cents: Option<u32>,
+    pub average_per_head_cents: Option<u32>,
+    /// Tips as hundredths of a percent of the meal prices
+    pub tip_ratio_basis_points: Option<u32>,
+    pub months: Vec<MonthlyMoneyResponse>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MealCountResponse {
+    pub meal_id: String,
+    pub count: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonthlyTipResponse {
+    pub year: i32,
+    pub month: u32,
+    pub tip_cents: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderStatisticsResponse {
+    /// Orders which were not cancelled
+    pub orders: u32,
+    /// Most ordered first
+    pub meals: Vec<MealCountResponse>,
+    pub average_spend_per_user_cents: Option<u32>,
+    /// Oldest first
+    pub tips: Vec<MonthlyTipResponse>,
+    /// Monday first
+    pub orders_per_weekday: [u32; 7],
+}
+
+impl From<&OrderStatistics> for OrderStatisticsResponse {
+    fn from(statistics: &OrderStatistics) -> OrderStatisticsResponse {
+        OrderStatisticsResponse {
+            orders: statistics.get_orders(),
+            meals: statistics
+                .meals()
+                .iter()
+                .map(|meal| MealCountResponse {
+                    meal_id: meal.get_meal_id().clone(),
+                    count: meal.get_count(),
+                })
+                .collect(),
+            average_spend_per_user_cents: statistics
+                .get_average_spend_per_user()
+                .map(|money| money.get_total_cents()),
+            tips: statistics
+                .tips_by_month()
+                .iter()
+                .map(|(month, tip)| MonthlyTipResponse {
+                    year: month.get_year(),
+                    month: month.get_month(),
+                    tip_cents: tip.get_total_cents(),
+                })
+                .collect(),
+            orders_per_weekday: statistics.get_orders_per_weekday(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserFairnessResponse {
+    pub user_id: u32,
+    pub participations: u32,
+    pub organized: u32,
+    pub fees_covered: u32,
+    /// "too_often" or "too_rarely" if the user is off their fair share of duties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imbalance: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FairnessResponse {
+    /// Sorted by user ID
+    pub users: Vec<UserFairnessResponse>,
+}
+
+impl From<&FairnessReport> for FairnessResponse {
+    fn from(report: &FairnessReport) -> FairnessResponse {
+        FairnessResponse {
+            users: report
+                .users()
+                .iter()
+                .map(|user| UserFairnessResponse {
+                    user_id: user.get_user_id().get_value(),
+                    participations: user.get_participations(),
+                    organized: user.get_organized(),
+                    fees_covered: user.get_fees_covered(),
+                    imbalance: user.get_imbalance().map(|imbalance| {
+                        String::from(match imbalance {
+                            Imbalance::TooOften => "too_often",
+                            Imbalance::TooRarely => "too_rarely",
+                        })
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryResponse {
+    pub summary: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntryResponse {
+    /// RFC 3339 time in UTC
+    pub time: String,
+    /// Participant whose meals or payments were changed, missing for changes of the whole order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<u32>,
+    pub change: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryResponse {
+    /// Oldest first
+    pub events: Vec<HistoryEntryResponse>,
+}
+
+impl From<&Order> for HistoryResponse {
+    fn from(order: &Order) -> HistoryResponse {
+        HistoryResponse {
+            events: order
+                .history()
+                .iter()
+                .map(|event| HistoryEntryResponse {
+                    time: DateTime::<Utc>::from(event.get_time()).to_rfc3339(),
+                    user_id: event.get_mutation().get_user_id().map(Id::get_value),
+                    change: event.get_mutation().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&MoneyStats> for MoneyStatsResponse {
+    fn from(stats: &MoneyStats) -> MoneyStatsResponse {
+        MoneyStatsResponse {
+            largest_order_cents: stats
+                .get_largest_order()
+                .map(|money| money.get_total_cents()),
+            average_per_head_cents: stats
+                .get_average_per_head()
+                .map(|money| money.get_total_cents()),
+            tip_ratio_basis_points: stats.get_tip_ratio().map(|ratio| ratio.get_basis_points()),
+            months: stats
+                .months()
+                .iter()
+                .map(|month| MonthlyMoneyResponse {
+                    year: month.get_month().get_year(),
+                    month: month.get_month().get_month(),
+                    orders: month.get_orders(),
+                    spend_cents: month.get_spend().get_total_cents(),
+                    trend_cents: month.get_trend().map(|trend| match trend {
+                        Trend::Up(money) => i64::from(money.get_total_cents()),
+                        Trend::Down(money) => -i64::from(money.get_total_cents()),
+                        Trend::Unchanged => 0,
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Spreadsheet in the layout of `import::spreadsheet::HEADER`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportRequest {
+    pub csv: String,
+    /// Only validate and report what would be imported
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Positive for change, negative for money owed.
+fn balance_cents(balance: Balance) -> i64 {
+    match balance {
+        Balance::Change(change) => i64::from(change.get_total_cents()),
+        Balance::Owed(owed) => -i64::from(owed.get_total_cents()),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DashboardResponse {
+    pub participants: usize,
+    pub meals: usize,
+    /// Participants who completed their meal selection
+    pub ready: usize,
+    /// Including office meals and the delivery fee
+    pub price_cents: u32,
+    pub tip_cents: u32,
+    pub paid_cents: u32,
+    pub owed_cents: u32,
+    pub change_cents: u32,
+    /// Balance by user ID, positive for change, negative for money owed
+    pub balance_cents: BTreeMap<u32, i64>,
+    /// Of the last change included, to request only the events missed after it
+    pub sequence_number: usize,
+}
+
+impl From<&OrderSummary> for DashboardResponse {
+    fn from(summary: &OrderSummary) -> DashboardResponse {
+        let report = summary.get_report();
+        DashboardResponse {
+            participants: summary.get_participants(),
+            meals: summary.get_meals(),
+            ready: summary.get_ready(),
+            price_cents: report.get_total_price().get_total_cents(),
+            tip_cents: report.get_total_tip().get_total_cents(),
+            paid_cents: report.get_total_paid().get_total_cents(),
+            owed_cents: report.get_total_owed().get_total_cents(),
+            change_cents: report.get_total_change().get_total_cents(),
+            balance_cents: report
+                .users()
+                .iter()
+                .map(|user| {
+                    (
+                        user.get_user_id().get_value(),
+                        balance_cents(user.get_balance()),
+                    )
+                })
+                .collect(),
+            sequence_number: summary.get_sequence_number(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventsQuery {
+    /// Send the `DashboardResponse` of the order as first message, before any event
+    #[serde(default)]
+    pub catch_up: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayResponse {
+    /// Oldest first, as they would have been received over the WebSocket
+    pub events: Vec<OrderEvent>,
+    /// Of the last change, to request the events after these next time
+    pub sequence_number: usize,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportResponse {
+    pub rows: usize,
+    /// Day of every order as "2020-05-04", oldest first
+    pub dates: Vec<String>,
+    pub new_users: Vec<String>,
+    /// Balance over all imported orders by name, positive for change, negative for money owed
+    pub balance_cents: BTreeMap<String, i64>,
+    /// IDs of the archived orders, empty for a dry run
+    pub imported: Vec<u32>,
+    /// Invalid rows, nothing is imported if there are any
+    pub errors: Vec<String>,
+}
+
+impl From<&ImportReport> for ImportResponse {
+    fn from(report: &ImportReport) -> ImportResponse {
+        ImportResponse {
+            rows: report.get_rows(),
+            dates: report.dates().iter().map(ToString::to_string).collect(),
+            new_users: report.new_users().to_vec(),
+            balance_cents: report
+                .balances()
+                .iter()
+                .map(|(name, balance)| (name.clone(), balance_cents(*balance)))
+                .collect(),
+            imported: report.imported().iter().map(Id::get_value).collect(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Orders archived or purged by the retention policy, IDs sorted ascending
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionResponse {
+    /// Delivered orders moved to the archive
+    #[serde(default)]
+    pub archived: Vec<u32>,
+    pub anonymized: Vec<u32>,
+    pub deleted: Vec<u32>,
+}
+
+impl From<&RetentionReport> for RetentionResponse {
+    fn from(report: &RetentionReport) -> RetentionResponse {
+        RetentionResponse {
+            archived: report.archived().iter().map(Id::get_value).collect(),
+            anonymized: report.anonymized().iter().map(Id::get_value).collect(),
+            deleted: report.deleted().iter().map(Id::get_value).collect(),
+        }
+    }
+}
+
+/// Where participants send their money, e.g. `{"paypal_me": "pizzafranz"}`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentRequestsRequest {
+    PaypalMe(String),
+    Sepa {
+        name: String,
+        iban: String,
+        #[serde(default)]
+        bic: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequestResponse {
+    pub user_id: u32,
+    pub amount_cents: u32,
+    pub reference: String,
+    /// PayPal.Me link or content of the EPC QR code
+    pub link: String,
+}
+
+impl From<&PaymentRequest> for PaymentRequestResponse {
+    fn from(request: &PaymentRequest) -> PaymentRequestResponse {
+        PaymentRequestResponse {
+            user_id: request.get_user_id().get_value(),
+            amount_cents: request.get_amount().get_total_cents(),
+            reference: request.get_reference().clone(),
+            link: request.get_link().clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequestsResponse {
+    pub requests: Vec<PaymentRequestResponse>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    /// In the layout of `import::bank_statement::CSV_HEADER`
+    Csv,
+    /// ISO 20022 CAMT.053 or CAMT.054 XML
+    Camt,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BankStatementRequest {
+    pub format: StatementFormat,
+    pub content: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferResponse {
+    /// Booking day as "2020-05-04"
+    pub date: String,
+    pub amount_cents: u32,
+    pub reference: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchedTransferResponse {
+    pub transfer: TransferResponse,
+    pub order_id: u32,
+    pub user_id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnmatchedTransferResponse {
+    pub transfer: TransferResponse,
+    /// Why the transfer has to be booked by hand, for the admin reviewing it
+    pub reason: String,
+    /// Order named in the reference, if any
+    pub order_id: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BankStatementResponse {
+    /// Transfers booked as payments
+    pub matched: Vec<MatchedTransferResponse>,
+    /// Transfers left for manual review
+    pub unmatched: Vec<UnmatchedTransferResponse>,
+    /// Unreadable bookings, nothing is booked if there are any
+    pub errors: Vec<String>,
+}
+
+impl From<&Transfer> for TransferResponse {
+    fn from(transfer: &Transfer) -> TransferResponse {
+        TransferResponse {
+            date: transfer.get_date().to_string(),
+            amount_cents: transfer.get_amount().get_total_cents(),
+            reference: transfer.get_reference().clone(),
+        }
+    }
+}
+
+impl From<&ReconciliationReport> for BankStatementResponse {
+    fn from(report: &ReconciliationReport) -> BankStatementResponse {
+        BankStatementResponse {
+            matched: report
+                .matched()
+                .iter()
+                .map(|matched| MatchedTransferResponse {
+                    transfer: TransferResponse::from(matched.get_transfer()),
+                    order_id: matched.get_order_id().get_value(),
+                    user_id: matched.get_user_id().get_value(),
+                })
+                .collect(),
+            unmatched: report
+                .unmatched()
+                .iter()
+                .map(|unmatched| {
+                    let (reason, order_id) = match unmatched.get_reason() {
+                        UnmatchedReason::NoOrderCode => {
+                            (String::from("no order code in reference"), None)
+                        }
+                        UnmatchedReason::UnknownOrder(id) => {
+                            (String::from("unknown order"), Some(id))
+                        }
+                        UnmatchedReason::OrderNotPayable(id) => {
+                            (String::from("order takes no payments"), Some(id))
+                        }
+                        UnmatchedReason::NoMatchingDebt(id) => {
+                            (String::from("nobody owes the amount"), Some(id))
+                        }
+                        UnmatchedReason::Ambiguous(id, users) => (
+                            format!(
+                                "users {} owe the amount",
+                                users
+                                    .iter()
+                                    .map(|user| user.get_value().to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                            Some(id),
+                        ),
+                    };
+                    UnmatchedTransferResponse {
+                        transfer: TransferResponse::from(unmatched.get_transfer()),
+                        reason,
+                        order_id: order_id.map(Id::get_value),
+                    }
+                })
+                .collect(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityResponse {
+    pub healthy: bool,
+    /// Every issue with the order it was found in and how to repair it
+    pub issues: Vec<String>,
+}
+
+/// Whether the server is up and saves changes, answered even while it only keeps them in memory
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// "ok", or "degraded" while running ephemeral
+    pub status: String,
+    pub ephemeral: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageResponse {
+    /// Changes are only kept in memory, because the storage could not be reached at startup
+    pub ephemeral: bool,
+    /// Why the storage could not be reached
+    pub reason: Option<String>,
+    /// Since when the server runs ephemeral, in RFC 3339
+    pub since: Option<String>,
+    /// Writes to be replayed once the storage can be reached again
+    pub queued_writes: usize,
+    /// Templates whose ID was taken by a stored template when the writes were replayed
+    pub moved_templates: Vec<MovedTemplateResponse>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovedTemplateResponse {
+    /// ID the template was created with while running ephemeral
+    pub old_id: u32,
+    pub new_id: u32,
+}
+
+impl From<&StorageStatus> for StorageResponse {
+    fn from(status: &StorageStatus) -> StorageResponse {
+        StorageResponse {
+            ephemeral: status.is_ephemeral(),
+            reason: status.get_reason().cloned(),
+            since: status
+                .get_since()
+                .map(|since| DateTime::<Utc>::from(since).to_rfc3339()),
+            queued_writes: status.queued_writes().len(),
+            moved_templates: status
+                .moved_templates()
+                .iter()
+                .map(|(old_id, new_id)| MovedTemplateResponse {
+                    old_id: old_id.get_value(),
+                    new_id: new_id.get_value(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StuckOrdersQuery {
+    /// Minutes without change after which an order counts as stuck, an hour if missing
+    #[serde(default)]
+    pub idle_minutes: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StuckOrderEntry {
+    pub order_id: u32,
+    pub status: String,
+    /// Minutes since the last change
+    pub idle_minutes: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StuckOrdersResponse {
+    pub orders: Vec<StuckOrderEntry>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionsEndedResponse {
+    /// Number of sessions ended, their users have to log in again
+    pub ended: usize,
+}
+
+/// Counters of the realtime connections since the start of the server
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RealtimeResponse {
+    /// Open WebSocket connections
+    pub connections: usize,
+    pub delivered: u64,
+    /// Events slow clients lost, each replaced by a resync
+    pub dropped: u64,
+    pub resyncs: u64,
+    pub queue_limit: usize,
+}
+
+/// Weekly opening period in the local time of the restaurant, e.g. `{"day": "Fri", "opens": "18:00", "closes": "01:00"}`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpeningPeriodEntry {
+    pub day: String,
+    pub opens: String,
+    /// At or before `opens` for periods lasting past midnight
+    pub closes: String,
+}
+
+impl From<&OpeningPeriod> for OpeningPeriodEntry {
+    fn from(period: &OpeningPeriod) -> OpeningPeriodEntry {
+        OpeningPeriodEntry {
+            day: period.get_day().to_string(),
+            opens: period.get_opens().format("%H:%M").to_string(),
+            closes: period.get_closes().format("%H:%M").to_string(),
+        }
+    }
+}
+
+/// Last time orders are taken on a day of the week in the local time of the restaurant, e.g.
+/// `{"day": "Sun", "time": "20:30"}`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CutoffEntry {
+    pub day: String,
+    pub time: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestaurantRequest {
+    pub name: String,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub min_order_value_cents: u32,
+    #[serde(default)]
+    pub delivery_fee_cents: u32,
+    /// Offset of the local time of the restaurant to UTC, e.g. 120 for CEST
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Unknown if missing, the order can be placed any time then
+    #[serde(default)]
+    pub opening_hours: Option<Vec<OpeningPeriodEntry>>,
+    /// Days like "Mon" without orders, only with opening hours
+    #[serde(default)]
+    pub closed_days: Vec<String>,
+    /// Only with opening hours
+    #[serde(default)]
+    pub cutoffs: Vec<CutoffEntry>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestaurantResponse {
+    pub name: String,
+    pub phone: Option<String>,
+    pub min_order_value_cents: u32,
+    pub delivery_fee_cents: u32,
+    pub utc_offset_minutes: i32,
+    pub opening_hours: Option<Vec<OpeningPeriodEntry>>,
+    pub closed_days: Vec<String>,
+    pub cutoffs: Vec<CutoffEntry>,
+}
+
+impl From<&Restaurant> for RestaurantResponse {
+    fn from(restaurant: &Restaurant) -> RestaurantResponse {
+        let hours = restaurant.get_opening_hours();
+        RestaurantResponse {
+            name: restaurant.get_name().clone(),
+            phone: restaurant.get_phone().cloned(),
+            min_order_value_cents: restaurant.get_min_order_value().get_total_cents(),
+            delivery_fee_cents: restaurant.get_delivery_fee().get_total_cents(),
+            utc_offset_minutes: hours
+                .map(|hours| hours.get_utc_offset().local_minus_utc() / 60)
+                .unwrap_or_default(),
+            opening_hours: hours.map(|hours| hours.periods().iter().map(Into::into).collect()),
+            closed_days: hours
+                .map(|hours| {
+                    hours
+                        .closed_days()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            cutoffs: hours
+                .map(|hours| {
+                    hours
+                        .cutoffs()
+                        .iter()
+                        .map(|(day, time)| CutoffEntry {
+                            day: day.to_string(),
+                            time: time.format("%H:%M").to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A recurring group order, e.g. `{"name": "Friday pizza", "manager_id": 0, "restaurant": {"name": "Luigi"},
+/// "participants": [1, 2], "deadline": "11:30"}`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderTemplateRequest {
+    pub name: String,
+    pub manager_id: u32,
+    pub restaurant: RestaurantRequest,
+    /// Besides the manager
+    #[serde(default)]
+    pub participants: Vec<u32>,
+    /// Time of day in the local time of the restaurant, its last order time is used if missing
+    #[serde(default)]
+    pub deadline: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderTemplateResponse {
+    pub id: u32,
+    pub name: String,
+    pub manager_id: u32,
+    pub restaurant: RestaurantResponse,
+    pub participants: Vec<u32>,
+    pub deadline: Option<String>,
+}
+
+impl From<(&Id<OrderTemplate>, &OrderTemplate)> for OrderTemplateResponse {
+    fn from((id, template): (&Id<OrderTemplate>, &OrderTemplate)) -> OrderTemplateResponse {
+        OrderTemplateResponse {
+            id: id.get_value(),
+            name: template.get_name().clone(),
+            manager_id: template.get_manager_id().get_value(),
+            restaurant: template.get_restaurant().into(),
+            participants: template.participants().iter().map(Id::get_value).collect(),
+            deadline: template
+                .get_deadline()
+                .map(|deadline| deadline.format("%H:%M").to_string()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderTemplatesResponse {
+    pub templates: Vec<OrderTemplateResponse>,
+}
+
+/// Keys of a browser's push subscription, as `PushSubscription.toJSON()` has them
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushKeysEntry {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// A browser's push subscription, the JSON of `PushSubscription.toJSON()`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushSubscriptionRequest {
+    pub endpoint: String,
+    pub keys: PushKeysEntry,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushUnsubscribeRequest {
+    pub endpoint: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushKeyResponse {
+    /// Base64url encoded, for the `applicationServerKey` of `PushManager.subscribe()`
+    pub public_key: String,
+}
+
+/// One line of a price breakdown, e.g. `{"kind": "Special", "description": "extra cheese", "price_cents": 150}`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum PriceComponentEntry {
+    Base {
+        meal_id: String,
+        variety: String,
+        price_cents: u32,
+    },
+    Special {
+        description: String,
+        price_cents: u32,
+    },
+    /// Refunded when the bottle or can is returned
+    Deposit { price_cents: u32 },
+    /// Costs nothing, listed so clients can show everything that was chosen
+    Preparation {
+        /// Code like "well-done"
+        preparation: String,
+        description: String,
+    },
+}
+
+impl From<&PriceComponent> for PriceComponentEntry {
+    fn from(component: &PriceComponent) -> PriceComponentEntry {
+        match component {
+            PriceComponent::Base {
+                meal_id,
+                variety,
+                price,
+            } => PriceComponentEntry::Base {
+                meal_id: meal_id.clone(),
+                variety: variety.clone(),
+                price_cents: price.get_total_cents(),
+            },
+            PriceComponent::Special { description, price } => PriceComponentEntry::Special {
+                description: description.clone(),
+                price_cents: price.get_total_cents(),
+            },
+            PriceComponent::Deposit(deposit) => PriceComponentEntry::Deposit {
+                price_cents: deposit.get_total_cents(),
+            },
+            PriceComponent::Preparation(preparation) => PriceComponentEntry::Preparation {
+                preparation: String::from(preparation.get_code()),
+                description: preparation.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceBreakdownResponse {
+    pub components: Vec<PriceComponentEntry>,
+    pub total_cents: u32,
+    /// Part of the total that is refunded when the bottle or can is returned
+    #[serde(default)]
+    pub deposit_cents: u32,
+}
+
+impl From<&PriceBreakdown> for PriceBreakdownResponse {
+    fn from(breakdown: &PriceBreakdown) -> PriceBreakdownResponse {
+        PriceBreakdownResponse {
+            components: breakdown.components().iter().map(Into::into).collect(),
+            total_cents: breakdown.get_total().get_total_cents(),
+            deposit_cents: breakdown.get_deposit().get_total_cents(),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::api::error::ApiError;
+use crate::api::state::AppState;
+use crate::api::v1::dto::{DashboardResponse, EventsQuery};
+use crate::notifications::bus::EventBus;
+use crate::notifications::event::OrderEvent;
+use crate::notifications::queue::{EventQueue, RealtimeMetrics};
+use crate::util::id::Id;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::Response;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::Notify;
+
+/// Upgrades to a WebSocket which receives every `OrderEvent` of the order as JSON text message.
+///
+/// With `?catch_up=true` the current `DashboardResponse` is sent first, so clients connecting late
+/// don't have to fetch the whole order.
+pub async fn order_events(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(order_id): Path<u32>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Response, ApiError> {
+    let id = Id::new(order_id);
+    let summary = {
+        let mut orders = state.orders();
+        let order = orders.get_order(&id).ok_or(ApiError::OrderNotFound)?;
+        match state.summaries().get(&id) {
+            Some(summary) => summary,
+            None => state.summaries().update(&id, order),
+        }
+    };
+    // Subscribe before upgrading, so no event published after the handshake is missed
+    let events = state.events().subscribe();
+    let bus = state.events().clone();
+    let catch_up = if query.catch_up {
+        Some(
+            serde_json::to_string(&DashboardResponse::from(&*summary))
+                .expect("Dashboards are always serializable"),
+        )
+    } else {
+        None
+    };
+    Ok(ws.on_upgrade(move |socket| forward_events(socket, catch_up, events, order_id, bus)))
+}
+
+/// Sends the events of the order from the queue of the connection, until the client closes it.
+///
+/// A separate task reads the bus into the queue, so a client too slow to take the messages loses non-critical
+/// events to a resync marker instead of making the bus lag or the server buffer endlessly.
+async fn forward_events(
+    mut socket: WebSocket,
+    catch_up: Option<String>,
+    events: Receiver<OrderEvent>,
+    order_id: u32,
+    bus: EventBus,
+) {
+    let metrics = bus.get_metrics();
+    let queue = Arc::new(Mutex::new(EventQueue::new(order_id, bus.get_queue_limit())));
+    let queued = Arc::new(Notify::new());
+    metrics.connected();
+    let reader = tokio::spawn(queue_events(
+        events,
+        order_id,
+        queue.clone(),
+        queued.clone(),
+        metrics.clone(),
+    ));
+    if let Some(text) = catch_up {
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            reader.abort();
+            metrics.disconnected();
+            return;
+        }
+    }
+    loop {
+        let next = queue.lock().unwrap().pop();
+        match next {
+            Some(delivery) => {
+                if socket
+                    .send(Message::Text(delivery.to_json().into()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                metrics.delivered(&delivery);
+            }
+            None => tokio::select! {
+                _ = queued.notified() => {}
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            },
+        }
+    }
+    reader.abort();
+    metrics.disconnected();
+}
+
+async fn queue_events(
+    mut events: Receiver<OrderEvent>,
+    order_id: u32,
+    queue: Arc<Mutex<EventQueue>>,
+    queued: Arc<Notify>,
+    metrics: Arc<RealtimeMetrics>,
+) {
+    loop {
+        match events.recv().await {
+            Ok(event) if event.get_order_id() == order_id => {
+                let dropped = queue.lock().unwrap().push(event);
+                metrics.dropped(dropped);
+            }
+            Ok(_) => continue,
+            // Missed events may have been of any order, so the client has to resync to be sure
+            Err(RecvError::Lagged(missed)) => {
+                queue.lock().unwrap().push_resync();
+                metrics.dropped(missed as usize);
+            }
+            Err(RecvError::Closed) => break,
+        }
+        queued.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::routes::router;
+    use crate::api::state::AppState;
+    use crate::api::v1::dto::DashboardResponse;
+    use crate::notifications::event::OrderEvent;
+    use crate::util::id::Id;
+    use futures_util::StreamExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::connect_async;
+
+    #[tokio::test]
+    async fn events_of_order_are_pushed() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        state.orders().create_order(Id::new(0));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let (mut socket, _) = connect_async(format!("ws://{}/orders/1/events", address))
+            .await
+            .unwrap();
+
+        // When:
+        state.events().publish(OrderEvent::UserJoined {
+            order_id: 0,
+            user_id: 1,
+        });
+        state.events().publish(OrderEvent::UserJoined {
+            order_id: 1,
+            user_id: 2,
+        });
+
+        // Then:
+        let message = socket.next().await.unwrap().unwrap();
+        assert_eq!(
+            message.into_text().unwrap().as_str(),
+            r#"{"type":"UserJoined","order_id":1,"user_id":2}"#
+        );
+        assert_eq!(state.events().metrics().get_connections(), 1);
+        assert_eq!(state.events().metrics().get_dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn catch_up_sends_dashboard_before_events() {
+        // Given:
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // When:
+        let (mut socket, _) =
+            connect_async(format!("ws://{}/orders/0/events?catch_up=true", address))
+                .await
+                .unwrap();
+        state.events().publish(OrderEvent::UserJoined {
+            order_id: 0,
+            user_id: 1,
+        });
+
+        // Then:
+        let message = socket.next().await.unwrap().unwrap();
+        let dashboard: DashboardResponse =
+            serde_json::from_str(message.into_text().unwrap().as_str()).unwrap();
+        assert_eq!(dashboard.participants, 1);
+        let message = socket.next().await.unwrap().unwrap();
+        assert_eq!(
+            message.into_text().unwrap().as_str(),
+            r#"{"type":"UserJoined","order_id":0,"user_id":1}"#
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use crate::auth::password::{hash_password, verify_password};
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Random bytes per session token, which is sent hex encoded.
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AuthError {
+    EmptyPassword,
+    /// Unknown user or wrong password, which are not told apart to not reveal who is registered
+    WrongCredentials,
+    /// The session token is unknown, e.g. after logging out
+    InvalidSession,
+    /// No session token was given where one is required
+    NotAuthenticated,
+    /// The user is authenticated, but must not do this
+    Forbidden,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use AuthError::*;
+        match self {
+            EmptyPassword => write!(f, "Password must not be empty"),
+            WrongCredentials => write!(f, "User name or password is wrong"),
+            InvalidSession => write!(f, "Session is not valid"),
+            NotAuthenticated => write!(f, "Login is required"),
+            Forbidden => write!(f, "User is not allowed to do this"),
+        }
+    }
+}
+
+impl Error for AuthError {}
+
+/// Passwords and sessions of registered users.
+#[derive(Debug, Default)]
+pub struct Authenticator {
+    /// Argon2 hash by user ID
+    password_hashes: HashMap<Id<User>, String>,
+    /// User ID by session token
+    sessions: HashMap<String, Id<User>>,
+    /// User ID by name of the external provider and subject there, see `AuthProvider`
+    external_accounts: HashMap<(String, String), Id<User>>,
+}
+
+impl Authenticator {
+    pub fn new() -> Authenticator {
+        Authenticator::default()
+    }
+
+    /// Sets or replaces the password of the user, ending all their sessions.
+    pub fn set_password(&mut self, user_id: Id<User>, password: &str) -> Result<(), AuthError> {
+        if password.is_empty() {
+            return Err(AuthError::EmptyPassword);
+        }
+        self.sessions.retain(|_, owner| *owner != user_id);
+        self.password_hashes
+            .insert(user_id, hash_password(password));
+        Ok(())
+    }
+
+    pub fn has_password(&self, user_id: &Id<User>) -> bool {
+        self.password_hashes.contains_key(user_id)
+    }
+
+    /// Starts a session if the password is right and returns its token.
+    pub fn login(&mut self, user_id: Id<User>, password: &str) -> Result<String, AuthError> {
+        let hash = self
+            .password_hashes
+            .get(&user_id)
+            .ok_or(AuthError::WrongCredentials)?;
+        if !verify_password(password, hash) {
+            return Err(AuthError::WrongCredentials);
+        }
+        Ok(self.start_session(user_id))
+    }
+
+    /// Starts a session for a user who authenticated otherwise, e.g. at an external provider.
+    pub fn start_session(&mut self, user_id: Id<User>) -> String {
+        let token = new_token();
+        self.sessions.insert(token.clone(), user_id);
+        token
+    }
+
+    /// Remembers which user logs in with the account of the external provider.
+    pub fn link_account(&mut self, provider: &str, subject: &str, user_id: Id<User>) {
+        self.external_accounts
+            .insert((String::from(provider), String::from(subject)), user_id);
+    }
+
+    /// The user linked to the account of the external provider, `None` before the first login.
+    pub fn get_linked_user(&self, provider: &str, subject: &str) -> Option<Id<User>> {
+        self.external_accounts
+            .get(&(String::from(provider), String::from(subject)))
+            .cloned()
+    }
+
+    /// Ends the session, returns whether it existed.
+    pub fn logout(&mut self, token: &str) -> bool {
+        self.sessions.remove(token).is_some()
+    }
+
+    /// Ends the sessions of all users, e.g. after tokens leaked, and returns how many there were.
+    ///
+    /// Users have to log in again and get new tokens, their passwords and linked accounts are kept.
+    pub fn end_all_sessions(&mut self) -> usize {
+        let ended = self.sessions.len();
+        self.sessions.clear();
+        ended
+    }
+
+    /// The user the session belongs to.
+    pub fn authenticate(&self, token: &str) -> Result<Id<User>, AuthError> {
+        self.sessions
+            .get(token)
+            .cloned()
+            .ok_or(AuthError::InvalidSession)
+    }
+}
+
+fn new_token() -> String {
+    let mut bytes = [0; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_can_log_in_and_out() {
+        // Given:
+        let mut authenticator = Authenticator::new();
+        authenticator.set_password(Id::new(1), "salami").unwrap();
+
+        // When:
+        let token = authenticator.login(Id::new(1), "salami").unwrap();
+        let user_id = authenticator.authenticate(&token);
+        let logged_out = authenticator.logout(&token);
+
+        // Then:
+        assert_eq!(token.len(), 2 * TOKEN_BYTES);
+        assert_eq!(user_id, Ok(Id::new(1)));
+        assert!(logged_out);
+        assert_eq!(
+            authenticator.authenticate(&token),
+            Err(AuthError::InvalidSession)
+        );
+    }
+
+    #[test]
+    fn wrong_password_and_unknown_user_are_rejected_alike() {
+        // Given:
+        let mut authenticator = Authenticator::new();
+        authenticator.set_password(Id::new(1), "salami").unwrap();
+
+        // Then:
+        assert_eq!(
+            authenticator.login(Id::new(1), "tonno"),
+            Err(AuthError::WrongCredentials)
+        );
+        assert_eq!(
+            authenticator.login(Id::new(2), "salami"),
+            Err(AuthError::WrongCredentials)
+        );
+    }
+
+    #[test]
+    fn changing_password_ends_sessions() {
+        // Given:
+        let mut authenticator = Authenticator::new();
+        authenticator.set_password(Id::new(1), "salami").unwrap();
+        let token = authenticator.login(Id::new(1), "salami").unwrap();
+
+        // When:
+        authenticator.set_password(Id::new(1), "tonno").unwrap();
+
+        // Then:
+        assert_eq!(
+            authenticator.authenticate(&token),
+            Err(AuthError::InvalidSession)
+        );
+        assert!(authenticator.login(Id::new(1), "tonno").is_ok());
+    }
+
+    #[test]
+    fn all_sessions_can_be_ended() {
+        // Given:
+        let mut authenticator = Authenticator::new();
+        authenticator.set_password(Id::new(1), "salami").unwrap();
+        let first = authenticator.login(Id::new(1), "salami").unwrap();
+        let second = authenticator.start_session(Id::new(2));
+
+        // When:
+        let ended = authenticator.end_all_sessions();
+
+        // Then:
+        assert_eq!(ended, 2);
+        assert_eq!(
+            authenticator.authenticate(&first),
+            Err(AuthError::InvalidSession)
+        );
+        assert_eq!(
+            authenticator.authenticate(&second),
+            Err(AuthError::InvalidSession)
+        );
+        assert!(authenticator.login(Id::new(1), "salami").is_ok());
+    }
+
+    #[test]
+    fn empty_password_is_rejected() {
+        // Given:
+        let mut authenticator = Authenticator::new();
+
+        // When:
+        let result = authenticator.set_password(Id::new(1), "");
+
+        // Then:
+        assert_eq!(result, Err(AuthError::EmptyPassword));
+        assert!(!authenticator.has_password(&Id::new(1)));
+    }
+
+    #[test]
+    fn external_account_is_linked_per_provider() {
+        // Given:
+        let mut authenticator = Authenticator::new();
+
+        // When:
+        authenticator.link_account("ldap", "uid=anna", Id::new(1));
+        let token = authenticator.start_session(Id::new(1));
+
+        // Then:
+        assert_eq!(
+            authenticator.get_linked_user("ldap", "uid=anna"),
+            Some(Id::new(1))
+        );
+        assert_eq!(authenticator.get_linked_user("oidc", "uid=anna"), None);
+        assert_eq!(authenticator.authenticate(&token), Ok(Id::new(1)));
+    }
+}
@@ -0,0 +1,328 @@
+use crate::auth::provider::{
+    AuthFuture, AuthProvider, Credentials, ExternalIdentity, ProviderError,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Placeholder for the escaped user name in the DN template.
+pub const USER_PLACEHOLDER: &str = "{user}";
+
+/// BER tags of the LDAP messages, see RFC 4511.
+const SEQUENCE: u8 = 0x30;
+const INTEGER: u8 = 0x02;
+const ENUMERATED: u8 = 0x0a;
+const OCTET_STRING: u8 = 0x04;
+const BIND_REQUEST: u8 = 0x60;
+const BIND_RESPONSE: u8 = 0x61;
+const SIMPLE_AUTHENTICATION: u8 = 0x80;
+const LDAP_VERSION: u8 = 3;
+const SUCCESS: u8 = 0;
+const INVALID_CREDENTIALS: u8 = 49;
+/// Bind responses are small, anything larger is not a server to talk to
+const MAX_MESSAGE_LENGTH: usize = 64 * 1024;
+
+/// Logs users in by binding to an LDAP directory, e.g. Active Directory or OpenLDAP, with their password.
+///
+/// The DN to bind with is the template with `{user}` replaced by the user name, e.g.
+/// "uid={user},ou=people,dc=example,dc=com". The server only speaks plain LDAP, so the directory should be
+/// reached within a trusted network or through a TLS tunnel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LdapProvider {
+    /// "host:port" of the directory server
+    server: String,
+    user_dn: String,
+}
+
+impl LdapProvider {
+    pub fn new(server: String, user_dn: String) -> LdapProvider {
+        LdapProvider { server, user_dn }
+    }
+
+    pub fn get_server(&self) -> &String {
+        &self.server
+    }
+
+    pub fn get_user_dn(&self) -> &String {
+        &self.user_dn
+    }
+
+    /// The DN of the user, with the characters escaped which have a meaning in DNs.
+    pub fn dn_of(&self, name: &str) -> String {
+        let mut escaped = String::new();
+        for (index, character) in name.chars().enumerate() {
+            let leading = index == 0 && (character == ' ' || character == '#');
+            let trailing = index == name.chars().count() - 1 && character == ' ';
+            match character {
+                '\0' => escaped.push_str("\\00"),
+                ',' | '+' | '"' | '\\' | '<' | '>' | ';' | '=' => {
+                    escaped.push('\\');
+                    escaped.push(character);
+                }
+                _ if leading || trailing => {
+                    escaped.push('\\');
+                    escaped.push(character);
+                }
+                _ => escaped.push(character),
+            }
+        }
+        self.user_dn.replace(USER_PLACEHOLDER, &escaped)
+    }
+
+    async fn bind(&self, name: &str, password: &str) -> Result<ExternalIdentity, ProviderError> {
+        // A bind without password is an anonymous bind, which most directories accept for any DN
+        if name.trim().is_empty() || password.is_empty() {
+            return Err(ProviderError::WrongCredentials);
+        }
+        let dn = self.dn_of(name.trim());
+        let unavailable = |error: std::io::Error| ProviderError::Unavailable(error.to_string());
+        let mut stream = TcpStream::connect(self.server.as_str())
+            .await
+            .map_err(unavailable)?;
+        stream
+            .write_all(&bind_request(&dn, password))
+            .await
+            .map_err(unavailable)?;
+        match read_bind_result(&mut stream).await? {
+            SUCCESS => Ok(ExternalIdentity::new(dn, String::from(name.trim()))),
+            INVALID_CREDENTIALS => Err(ProviderError::WrongCredentials),
+            code => Err(ProviderError::Unavailable(format!(
+                "bind failed with result code {}",
+                code
+            ))),
+        }
+    }
+}
+
+impl AuthProvider for LdapProvider {
+    fn get_name(&self) -> &str {
+        "ldap"
+    }
+
+    fn authenticate(&self, credentials: Credentials) -> AuthFuture {
+        let provider = self.clone();
+        Box::pin(async move {
+            match credentials {
+                Credentials::Password { name, password } => provider.bind(&name, &password).await,
+                Credentials::Token(_) => Err(ProviderError::UnsupportedCredentials),
+            }
+        })
+    }
+}
+
+/// BER element with a definite length.
+fn element(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    let length = content.len();
+    if length < 0x80 {
+        element.push(length as u8);
+    } else {
+        let bytes: Vec<u8> = length
+            .to_be_bytes()
+            .iter()
+            .copied()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        element.push(0x80 | bytes.len() as u8);
+        element.extend(bytes);
+    }
+    element.extend_from_slice(content);
+    element
+}
+
+/// Simple bind as the only message of the connection, so its message ID is always 1.
+fn bind_request(dn: &str, password: &str) -> Vec<u8> {
+    let bind = [
+        element(INTEGER, &[LDAP_VERSION]),
+        element(OCTET_STRING, dn.as_bytes()),
+        element(SIMPLE_AUTHENTICATION, password.as_bytes()),
+    ]
+    .concat();
+    let message = [element(INTEGER, &[1]), element(BIND_REQUEST, &bind)].concat();
+    element(SEQUENCE, &message)
+}
+
+/// Reads the bind response and returns its result code.
+async fn read_bind_result(stream: &mut (impl AsyncRead + Unpin)) -> Result<u8, ProviderError> {
+    let malformed = || ProviderError::Unavailable(String::from("malformed bind response"));
+    let unavailable = |error: std::io::Error| ProviderError::Unavailable(error.to_string());
+    let mut header = [0; 2];
+    stream.read_exact(&mut header).await.map_err(unavailable)?;
+    if header[0] != SEQUENCE {
+        return Err(malformed());
+    }
+    let length = if header[1] < 0x80 {
+        header[1] as usize
+    } else {
+        let mut bytes = vec![0; (header[1] & 0x7f) as usize];
+        if bytes.len() > 4 {
+            return Err(malformed());
+        }
+        stream.read_exact(&mut bytes).await.map_err(unavailable)?;
+        bytes
+            .iter()
+            .fold(0, |length, byte| length << 8 | *byte as usize)
+    };
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(malformed());
+    }
+    let mut message = vec![0; length];
+    stream.read_exact(&mut message).await.map_err(unavailable)?;
+    let (_, rest) = split_element(&message, INTEGER).ok_or_else(malformed)?;
+    let (response, _) = split_element(rest, BIND_RESPONSE).ok_or_else(malformed)?;
+    match split_element(response, ENUMERATED) {
+        Some(([code], _)) => Ok(*code),
+        _ => Err(malformed()),
+    }
+}
+
+/// Content of the element with the given tag at the start of the bytes, and the bytes after it.
+fn split_element(bytes: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual, rest) = bytes.split_first()?;
+    if actual != tag {
+        return None;
+    }
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count > 4 || rest.len() < count {
+            return None;
+        }
+        let (bytes, rest) = rest.split_at(count);
+        let length = bytes
+            .iter()
+            .fold(0, |length, byte| length << 8 | *byte as usize);
+        (length, rest)
+    };
+    if rest.len() < length {
+        return None;
+    }
+    Some(rest.split_at(length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tokio::net::TcpListener;
+
+    /// Directory answering the first bind with the result code, returning the bind request it received.
+    async fn directory_answering(
+        result_code: u8,
+    ) -> (LdapProvider, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = LdapProvider::new(
+            listener.local_addr().unwrap().to_string(),
+            String::from("uid={user},ou=people,dc=example,dc=com"),
+        );
+        let directory = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut header = [0; 2];
+            stream.read_exact(&mut header).await.unwrap();
+            let mut request = vec![0; header[1] as usize];
+            stream.read_exact(&mut request).await.unwrap();
+            let result = [
+                element(ENUMERATED, &[result_code]),
+                element(OCTET_STRING, b""),
+                element(OCTET_STRING, b""),
+            ]
+            .concat();
+            let response = [element(INTEGER, &[1]), element(BIND_RESPONSE, &result)].concat();
+            stream
+                .write_all(&element(SEQUENCE, &response))
+                .await
+                .unwrap();
+            [&header[..], &request].concat()
+        });
+        (provider, directory)
+    }
+
+    fn password(name: &str, password: &str) -> Credentials {
+        Credentials::Password {
+            name: String::from(name),
+            password: String::from(password),
+        }
+    }
+
+    #[tokio::test]
+    async fn user_is_bound_with_password() {
+        // Given:
+        let (provider, directory) = directory_answering(SUCCESS).await;
+
+        // When:
+        let identity = provider.authenticate(password("anna", "salami")).await;
+
+        // Then:
+        assert_eq!(
+            identity,
+            Ok(ExternalIdentity::new(
+                String::from("uid=anna,ou=people,dc=example,dc=com"),
+                String::from("anna")
+            ))
+        );
+        let request = directory.await.unwrap();
+        assert_eq!(
+            request,
+            bind_request("uid=anna,ou=people,dc=example,dc=com", "salami")
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_credentials_are_wrong_credentials() {
+        // Given:
+        let (provider, _) = directory_answering(INVALID_CREDENTIALS).await;
+
+        // When:
+        let identity = provider.authenticate(password("anna", "tonno")).await;
+
+        // Then:
+        assert_eq!(identity, Err(ProviderError::WrongCredentials));
+    }
+
+    #[tokio::test]
+    async fn empty_password_is_not_sent_as_anonymous_bind() {
+        // Given:
+        let provider = LdapProvider::new(
+            String::from("127.0.0.1:1"),
+            String::from("uid={user},dc=example,dc=com"),
+        );
+
+        // When:
+        let identity = provider.authenticate(password("anna", "")).await;
+
+        // Then:
+        assert_eq!(identity, Err(ProviderError::WrongCredentials));
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("anna", "uid=anna,dc=example,dc=com"),
+        case("anna,ou=admins", "uid=anna\\,ou\\=admins,dc=example,dc=com"),
+        case("#anna ", "uid=\\#anna\\ ,dc=example,dc=com")
+    )]
+    fn user_name_is_escaped_in_dn(name: &str, expected: &str) {
+        // Given:
+        let provider = LdapProvider::new(
+            String::from("localhost:389"),
+            String::from("uid={user},dc=example,dc=com"),
+        );
+
+        // Then:
+        assert_eq!(provider.dn_of(name), expected);
+    }
+
+    #[test]
+    fn long_content_has_long_form_length() {
+        // When:
+        let encoded = element(OCTET_STRING, &[b'a'; 300]);
+
+        // Then:
+        assert_eq!(encoded[..4], [OCTET_STRING, 0x82, 0x01, 0x2c]);
+        assert_eq!(
+            split_element(&encoded, OCTET_STRING),
+            Some((&[b'a'; 300][..], &[][..]))
+        );
+    }
+}
@@ -0,0 +1,6 @@
+pub mod authenticator;
+pub mod ldap;
+pub mod oidc;
+pub mod password;
+pub mod provider;
+pub mod role;
@@ -0,0 +1,155 @@
+use crate::auth::provider::{
+    AuthFuture, AuthProvider, Credentials, ExternalIdentity, ProviderError,
+};
+use crate::util::http::HttpEndpoint;
+use serde::Deserialize;
+
+/// Claims of the userinfo response, of which only `sub` is required by OpenID Connect.
+#[derive(Deserialize)]
+struct UserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    name: Option<String>,
+    email: Option<String>,
+}
+
+/// Logs users in with an access token of an OpenID Connect provider, e.g. Keycloak or Azure AD.
+///
+/// The client runs the login flow with the provider itself and sends the access token, which is checked by
+/// asking the userinfo endpoint of the provider for the user it belongs to. The server only speaks plain HTTP,
+/// so an endpoint requiring HTTPS is reached through a relay in the local network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OidcProvider {
+    userinfo: HttpEndpoint,
+}
+
+impl OidcProvider {
+    pub fn new(userinfo: HttpEndpoint) -> OidcProvider {
+        OidcProvider { userinfo }
+    }
+
+    pub fn get_userinfo(&self) -> &HttpEndpoint {
+        &self.userinfo
+    }
+
+    async fn identify(&self, token: &str) -> Result<ExternalIdentity, ProviderError> {
+        if token.is_empty() {
+            return Err(ProviderError::WrongCredentials);
+        }
+        let body = self
+            .userinfo
+            .fetch(token)
+            .await
+            .map_err(|error| match error.get_status() {
+                Some(401) | Some(403) => ProviderError::WrongCredentials,
+                _ => ProviderError::Unavailable(error.to_string()),
+            })?;
+        let info: UserInfo = serde_json::from_str(&body)
+            .map_err(|error| ProviderError::Unavailable(error.to_string()))?;
+        let UserInfo {
+            sub,
+            preferred_username,
+            name,
+            email,
+        } = info;
+        let name = preferred_username
+            .or(name)
+            .or(email)
+            .unwrap_or_else(|| sub.clone());
+        Ok(ExternalIdentity::new(sub, name))
+    }
+}
+
+impl AuthProvider for OidcProvider {
+    fn get_name(&self) -> &str {
+        "oidc"
+    }
+
+    fn authenticate(&self, credentials: Credentials) -> AuthFuture {
+        let provider = self.clone();
+        Box::pin(async move {
+            match credentials {
+                Credentials::Token(token) => provider.identify(&token).await,
+                Credentials::Password { .. } => Err(ProviderError::UnsupportedCredentials),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Provider whose userinfo endpoint answers once with the given response.
+    async fn provider_answering(response: &'static str) -> OidcProvider {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/userinfo", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !String::from_utf8_lossy(&request).ends_with("\r\n\r\n") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        OidcProvider::new(url.parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn token_is_checked_at_userinfo_endpoint() {
+        // Given:
+        let provider = provider_answering(
+            "HTTP/1.1 200 OK\r\n\r\n{\"sub\":\"f3a9\",\"preferred_username\":\"anna\",\"email\":\"anna@example.com\"}",
+        )
+        .await;
+
+        // When:
+        let identity = provider
+            .authenticate(Credentials::Token(String::from("valid")))
+            .await;
+
+        // Then:
+        assert_eq!(
+            identity,
+            Ok(ExternalIdentity::new(
+                String::from("f3a9"),
+                String::from("anna")
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_token_means_wrong_credentials() {
+        // Given:
+        let provider = provider_answering("HTTP/1.1 401 Unauthorized\r\n\r\n").await;
+
+        // When:
+        let identity = provider
+            .authenticate(Credentials::Token(String::from("expired")))
+            .await;
+
+        // Then:
+        assert_eq!(identity, Err(ProviderError::WrongCredentials));
+    }
+
+    #[tokio::test]
+    async fn password_is_not_taken() {
+        // Given:
+        let provider = OidcProvider::new("http://localhost/userinfo".parse().unwrap());
+
+        // When:
+        let identity = provider
+            .authenticate(Credentials::Password {
+                name: String::from("anna"),
+                password: String::from("secret"),
+            })
+            .await;
+
+        // Then:
+        assert_eq!(identity, Err(ProviderError::UnsupportedCredentials));
+    }
+}
@@ -0,0 +1,56 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+
+/// Hashes the password with argon2 and a random salt, in PHC format which includes the parameters.
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Default argon2 parameters are valid")
+        .to_string()
+}
+
+/// Checks the password against a hash from `hash_password`, a malformed hash never matches.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        password,
+        expected,
+        case("margherita", true),
+        case("Margherita", false),
+        case("", false)
+    )]
+    fn password_is_verified_against_hash(password: &str, expected: bool) {
+        // Given:
+        let hash = hash_password("margherita");
+
+        // When:
+        let verified = verify_password(password, &hash);
+
+        // Then:
+        assert_eq!(verified, expected);
+    }
+
+    #[test]
+    fn same_password_is_hashed_with_different_salts() {
+        assert_ne!(hash_password("margherita"), hash_password("margherita"));
+    }
+
+    #[test]
+    fn malformed_hash_never_matches() {
+        assert!(!verify_password("margherita", "margherita"));
+    }
+}
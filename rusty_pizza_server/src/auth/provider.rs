@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProviderError {
+    /// The provider takes another kind of credentials, e.g. a token instead of a password
+    UnsupportedCredentials,
+    /// The provider does not know the user or the password or token is wrong
+    WrongCredentials,
+    /// The provider could not be reached or answered unexpectedly
+    Unavailable(String),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ProviderError::*;
+        match self {
+            UnsupportedCredentials => write!(f, "Login provider does not take these credentials"),
+            WrongCredentials => write!(f, "User name, password or token is wrong"),
+            Unavailable(error) => write!(f, "Login provider is not available: {}", error),
+        }
+    }
+}
+
+impl Error for ProviderError {}
+
+/// What a user logs in with at an external provider.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Credentials {
+    Password {
+        name: String,
+        password: String,
+    },
+    /// Access token the client got from the provider, e.g. via the OpenID Connect authorization code flow
+    Token(String),
+}
+
+/// A user as known to an external provider.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalIdentity {
+    /// Never changes for the user and is unique at the provider
+    subject: String,
+    /// Name the user is registered with on first login
+    name: String,
+}
+
+impl ExternalIdentity {
+    pub fn new(subject: String, name: String) -> ExternalIdentity {
+        ExternalIdentity { subject, name }
+    }
+
+    pub fn get_subject(&self) -> &String {
+        &self.subject
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+}
+
+pub type AuthFuture = Pin<Box<dyn Future<Output = Result<ExternalIdentity, ProviderError>> + Send>>;
+
+/// Somewhere users are logged in with accounts the server doesn't manage, e.g. the directory of the company.
+pub trait AuthProvider: Send + Sync {
+    /// Name the provider is chosen by when logging in, e.g. "ldap"
+    fn get_name(&self) -> &str;
+
+    /// Checks the credentials with the provider, without blocking the caller while waiting for it.
+    fn authenticate(&self, credentials: Credentials) -> AuthFuture;
+}
+
+/// Providers configured at startup, besides the passwords the server stores itself.
+#[derive(Default)]
+pub struct AuthProviders {
+    providers: Vec<Box<dyn AuthProvider>>,
+}
+
+impl AuthProviders {
+    pub fn new() -> AuthProviders {
+        AuthProviders::default()
+    }
+
+    /// Adds the provider, replacing one with the same name.
+    pub fn add(&mut self, provider: Box<dyn AuthProvider>) {
+        self.providers
+            .retain(|existing| existing.get_name() != provider.get_name());
+        self.providers.push(provider);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn AuthProvider> {
+        self.providers
+            .iter()
+            .find(|provider| provider.get_name() == name)
+            .map(Box::as_ref)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.providers
+            .iter()
+            .map(|provider| provider.get_name())
+            .collect()
+    }
+}
+
+impl fmt::Debug for AuthProviders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AuthProviders")
+            .field("providers", &self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedProvider(&'static str, &'static str);
+
+    impl AuthProvider for FixedProvider {
+        fn get_name(&self) -> &str {
+            self.0
+        }
+
+        fn authenticate(&self, _: Credentials) -> AuthFuture {
+            let subject = String::from(self.1);
+            Box::pin(async move { Ok(ExternalIdentity::new(subject.clone(), subject)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn provider_is_replaced_by_one_with_same_name() {
+        // Given:
+        let mut providers = AuthProviders::new();
+        providers.add(Box::new(FixedProvider("ldap", "first")));
+        providers.add(Box::new(FixedProvider("oidc", "other")));
+
+        // When:
+        providers.add(Box::new(FixedProvider("ldap", "second")));
+
+        // Then:
+        assert_eq!(providers.names(), vec!["oidc", "ldap"]);
+        let identity = providers
+            .get("ldap")
+            .unwrap()
+            .authenticate(Credentials::Token(String::from("token")))
+            .await
+            .unwrap();
+        assert_eq!(identity.get_subject(), "second");
+        assert!(providers.get("saml").is_none());
+    }
+}
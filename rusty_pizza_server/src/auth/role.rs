@@ -0,0 +1,90 @@
+use crate::auth::authenticator::AuthError;
+use crate::order_model::order::{Order, Permission};
+use crate::order_model::user::User;
+use crate::util::id::Id;
+
+/// What a user may do in an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Created the order and decides when it is ordered, delivered or cancelled
+    Manager,
+    /// Only changes their own meals and payments
+    Participant,
+}
+
+impl Role {
+    /// The role of the user in the order, `None` if not participating.
+    pub fn in_order(order: &Order, user_id: &Id<User>) -> Option<Role> {
+        if order.get_manager_id() == *user_id {
+            Some(Role::Manager)
+        } else if order.is_participating(user_id) {
+            Some(Role::Participant)
+        } else {
+            None
+        }
+    }
+}
+
+/// Only the manager may change the status of the order, see `Order::check_authorized`.
+pub fn require_manager(order: &Order, user_id: &Id<User>) -> Result<(), AuthError> {
+    order
+        .check_authorized(user_id, &Permission::ChangeStatus)
+        .map_err(|_| AuthError::Forbidden)
+}
+
+/// Users may only act for themselves, e.g. when editing their meals.
+pub fn require_owner(owner_id: &Id<User>, user_id: &Id<User>) -> Result<(), AuthError> {
+    if owner_id == user_id {
+        Ok(())
+    } else {
+        Err(AuthError::Forbidden)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        user_id,
+        expected,
+        case(0, Some(Role::Manager)),
+        case(1, Some(Role::Participant)),
+        case(2, None)
+    )]
+    fn role_depends_on_participation(user_id: u32, expected: Option<Role>) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let role = Role::in_order(&order, &Id::new(user_id));
+
+        // Then:
+        assert_eq!(role, expected);
+    }
+
+    #[test]
+    fn only_manager_passes_manager_check() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+
+        // Then:
+        assert_eq!(require_manager(&order, &Id::new(0)), Ok(()));
+        assert_eq!(
+            require_manager(&order, &Id::new(1)),
+            Err(AuthError::Forbidden)
+        );
+    }
+
+    #[test]
+    fn only_owner_passes_owner_check() {
+        assert_eq!(require_owner(&Id::new(1), &Id::new(1)), Ok(()));
+        assert_eq!(
+            require_owner(&Id::new(1), &Id::new(0)),
+            Err(AuthError::Forbidden)
+        );
+    }
+}
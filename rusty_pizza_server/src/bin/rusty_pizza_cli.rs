@@ -0,0 +1,54 @@
+use clap::Parser;
+use rusty_pizza_server::cli::backend::{CliError, LocalBackend, RemoteBackend};
+use rusty_pizza_server::cli::command::{Cli, CliCommand};
+use rusty_pizza_server::cli::profile::{self, Config};
+use rusty_pizza_server::render::table::TableStyle;
+use std::env;
+use std::process;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    match run(Cli::parse()).await {
+        Ok(text) => println!("{}", text.trim_end()),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Runs the command at the server if one is given, on the orders of the journal otherwise.
+///
+/// Server, token and user left out on the command line are taken from the profile.
+async fn run(cli: Cli) -> Result<String, CliError> {
+    let path = cli.config.unwrap_or_else(profile::default_config_path);
+    let mut config = Config::load(&path)?;
+    let name = config.select(cli.profile.as_deref());
+    let mut profile = config.get(&name);
+    profile.server = cli.server.or(profile.server);
+    profile.token = cli.token.or(profile.token);
+    let command = match cli.command {
+        CliCommand::Session(command) => {
+            return profile::run(&command, &mut config, &path, &name, profile).await;
+        }
+        CliCommand::Orders(command) => command.with_default_user(profile.user),
+    };
+    let outcome = match profile.server {
+        Some(url) => {
+            RemoteBackend::new(url.parse()?, profile.token)
+                .execute(&command)
+                .await?
+        }
+        None => LocalBackend::open(cli.journal)?.execute(&command)?,
+    };
+    let style = if cli.ascii {
+        TableStyle::Ascii
+    } else {
+        TableStyle::Unicode
+    };
+    // Set by most shells, tables are only narrowed to the terminal if it is known
+    let max_width = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok());
+    Ok(outcome.render(style, max_width))
+}
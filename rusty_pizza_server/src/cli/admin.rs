@@ -0,0 +1,373 @@
+use crate::api::v1::dto::{IntegrityResponse, SessionsEndedResponse, StuckOrdersResponse};
+use crate::cli::backend::{CliError, RemoteBackend};
+use crate::persistence::intern::{self, LogError};
+use crate::persistence::journal;
+use crate::persistence::templates;
+use crate::render::table::{Alignment, Table};
+use clap::{Args, Parser, Subcommand};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Server the admin commands ask, the one `rusty_pizza_server` serves without configuration.
+pub const DEFAULT_SERVER: &str = "http://127.0.0.1:8080";
+/// Name of the event log in a backup directory.
+pub const BACKUP_LOG: &str = "events.jsonl";
+/// Name of the order templates in a backup directory.
+pub const BACKUP_TEMPLATES: &str = "templates.jsonl";
+/// Variable with the token of operators, read by the server and by the admin commands asking it.
+pub const ADMIN_TOKEN_VARIABLE: &str = "RUSTY_PIZZA_ADMIN_TOKEN";
+
+/// Command line of the server, which serves the API without a subcommand.
+#[derive(Debug, Parser)]
+#[command(name = "rusty_pizza_server", version)]
+pub struct ServerCli {
+    #[command(subcommand)]
+    pub command: Option<ServerCommand>,
+}
+
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum ServerCommand {
+    /// Maintenance tasks for operators of a deployment
+    Admin(AdminArgs),
+}
+
+#[derive(Debug, PartialEq, Eq, Args)]
+pub struct AdminArgs {
+    /// Base URL of the running server, for the commands asking it
+    #[arg(long, global = true, default_value = DEFAULT_SERVER)]
+    pub server: String,
+    /// Token the server was started with, `RUSTY_PIZZA_ADMIN_TOKEN` if missing
+    #[arg(long, global = true)]
+    pub admin_token: Option<String>,
+    #[command(subcommand)]
+    pub command: AdminCommand,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand)]
+pub enum AdminCommand {
+    /// Rewrites an event log to the current format, e.g. one written before interning
+    MigrateLog {
+        log: PathBuf,
+        /// New file for the migrated log, which must not exist yet
+        output: PathBuf,
+    },
+    /// Checks the orders of the running server for broken invariants, failing if there are any
+    Verify,
+    /// Copies the event log and order templates into a new directory, ready to be restored
+    Backup {
+        /// New directory for the backup
+        target: PathBuf,
+        /// Event log of the server
+        #[arg(long)]
+        log: Option<PathBuf>,
+        /// Write-ahead journal of the event log, whose records are included in the backup
+        #[arg(long, requires = "log")]
+        journal: Option<PathBuf>,
+        /// File of the order templates, see `RUSTY_PIZZA_TEMPLATES_FILE`
+        #[arg(long)]
+        templates: Option<PathBuf>,
+    },
+    /// Lists orders of the running server nobody changed for a while
+    StuckOrders {
+        /// Minutes without change after which an order counts as stuck, an hour by default
+        #[arg(long)]
+        idle_minutes: Option<u64>,
+    },
+    /// Ends all sessions at the running server, so every user has to log in again and gets a new token
+    RotateTokens,
+}
+
+/// Runs the command and returns what it did, for the operator.
+pub async fn run(args: &AdminArgs) -> Result<String, CliError> {
+    match &args.command {
+        AdminCommand::MigrateLog { log, output } => migrate_log(log, output),
+        AdminCommand::Backup {
+            target,
+            log,
+            journal,
+            templates,
+        } => backup(
+            target,
+            log.as_deref(),
+            journal.as_deref(),
+            templates.as_deref(),
+        ),
+        AdminCommand::Verify => {
+            let report: IntegrityResponse = server(args)?
+                .send("GET", "/v1/admin/integrity", None::<&()>)
+                .await?;
+            if report.healthy {
+                Ok(String::from("No issues found"))
+            } else {
+                Err(CliError::Unhealthy(report.issues))
+            }
+        }
+        AdminCommand::StuckOrders { idle_minutes } => {
+            let path = match idle_minutes {
+                Some(minutes) => format!("/v1/admin/stuck-orders?idle_minutes={}", minutes),
+                None => String::from("/v1/admin/stuck-orders"),
+            };
+            let stuck: StuckOrdersResponse = server(args)?.send("GET", &path, None::<&()>).await?;
+            if stuck.orders.is_empty() {
+                return Ok(String::from("No stuck orders"));
+            }
+            let mut table = Table::new(vec!["Order", "Status", "Idle minutes"])
+                .with_alignment(0, Alignment::Right)
+                .with_alignment(2, Alignment::Right);
+            for order in stuck.orders {
+                table.add_row(vec![
+                    order.order_id.to_string(),
+                    order.status,
+                    order.idle_minutes.to_string(),
+                ]);
+            }
+            Ok(table.to_string())
+        }
+        AdminCommand::RotateTokens => {
+            let ended: SessionsEndedResponse = server(args)?
+                .send("DELETE", "/v1/admin/sessions", None::<&()>)
+                .await?;
+            Ok(format!(
+                "Sessions ended: {}, users have to log in again",
+                ended.ended
+            ))
+        }
+    }
+}
+
+fn server(args: &AdminArgs) -> Result<RemoteBackend, CliError> {
+    let token = args
+        .admin_token
+        .clone()
+        .or_else(|| env::var(ADMIN_TOKEN_VARIABLE).ok());
+    Ok(RemoteBackend::new(args.server.parse()?, token))
+}
+
+fn storage_error(path: &Path, error: impl ToString) -> CliError {
+    CliError::Storage(format!("{}: {}", path.display(), error.to_string()))
+}
+
+fn migrate_log(log: &Path, output: &Path) -> Result<String, CliError> {
+    let reader = BufReader::new(File::open(log).map_err(|error| storage_error(log, error))?);
+    let writer = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(output)
+        .map_err(|error| storage_error(output, error))?;
+    let migration = intern::migrate(reader, writer).map_err(|error| storage_error(log, error))?;
+    Ok(format!(
+        "Migrated {} events with {} distinct strings to {}",
+        migration.events,
+        migration.strings,
+        output.display()
+    ))
+}
+
+/// Copies the files into the new directory, checking that they can be read back.
+///
+/// The journal is only read, the backup gets the records it holds in addition to the log, see
+/// `journal::recover`.
+fn backup(
+    target: &Path,
+    log: Option<&Path>,
+    journal: Option<&Path>,
+    templates: Option<&Path>,
+) -> Result<String, CliError> {
+    fs::create_dir(target).map_err(|error| storage_error(target, error))?;
+    let mut backed_up = Vec::new();
+    if let Some(log) = log {
+        let copy = target.join(BACKUP_LOG);
+        fs::copy(log, &copy).map_err(|error| storage_error(log, error))?;
+        if let Some(journal) = journal {
+            let mut storage = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&copy)
+                .map_err(|error| storage_error(&copy, error))?;
+            let mut journal_file =
+                File::open(journal).map_err(|error| storage_error(journal, error))?;
+            journal::recover(&mut storage, &mut journal_file)
+                .map_err(|error| storage_error(journal, error))?;
+        }
+        let mut events = 0;
+        let file = File::open(&copy).map_err(|error| storage_error(&copy, error))?;
+        intern::read_log(BufReader::new(file), |_| events += 1)
+            .map_err(|error: LogError| storage_error(log, error))?;
+        backed_up.push(format!("{} events", events));
+    }
+    if let Some(path) = templates {
+        let loaded = templates::load_templates(path).map_err(|error| storage_error(path, error))?;
+        let copy = target.join(BACKUP_TEMPLATES);
+        templates::save_templates(&loaded, &copy).map_err(|error| storage_error(&copy, error))?;
+        backed_up.push(format!("{} templates", loaded.len()));
+    }
+    if backed_up.is_empty() {
+        return Ok(format!(
+            "Nothing to back up, created empty {}",
+            target.display()
+        ));
+    }
+    Ok(format!(
+        "Backed up {} to {}",
+        backed_up.join(" and "),
+        target.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::routes::router;
+    use crate::api::state::AppState;
+    use crate::notifications::event::OrderEvent;
+    use crate::order_model::order_template::{OrderTemplate, OrderTemplates};
+    use crate::order_model::restaurant::Restaurant;
+    use crate::util::id::Id;
+    use rstest::rstest;
+    use std::process;
+    use tokio::net::TcpListener;
+
+    #[rstest(
+        args,
+        expected,
+        case(
+            vec!["admin", "migrate-log", "old.jsonl", "new.jsonl"],
+            AdminArgs {
+                server: String::from(DEFAULT_SERVER),
+                admin_token: None,
+                command: AdminCommand::MigrateLog {
+                    log: PathBuf::from("old.jsonl"),
+                    output: PathBuf::from("new.jsonl"),
+                },
+            }
+        ),
+        case(
+            vec!["admin", "stuck-orders", "--idle-minutes", "30", "--server", "http://pizza:80"],
+            AdminArgs {
+                server: String::from("http://pizza:80"),
+                admin_token: None,
+                command: AdminCommand::StuckOrders { idle_minutes: Some(30) },
+            }
+        ),
+        case(
+            vec!["admin", "--server", "http://pizza:80", "rotate-tokens", "--admin-token", "secret"],
+            AdminArgs {
+                server: String::from("http://pizza:80"),
+                admin_token: Some(String::from("secret")),
+                command: AdminCommand::RotateTokens,
+            }
+        )
+    )]
+    fn admin_command_is_parsed(args: Vec<&str>, expected: AdminArgs) {
+        // When:
+        let cli =
+            ServerCli::try_parse_from([&["rusty_pizza_server"], args.as_slice()].concat()).unwrap();
+
+        // Then:
+        assert_eq!(cli.command, Some(ServerCommand::Admin(expected)));
+    }
+
+    #[test]
+    fn server_is_started_without_subcommand() {
+        // When:
+        let cli = ServerCli::try_parse_from(["rusty_pizza_server"]).unwrap();
+
+        // Then:
+        assert_eq!(cli.command, None);
+    }
+
+    #[tokio::test]
+    async fn log_and_templates_are_backed_up() {
+        // Given:
+        let directory = std::env::temp_dir().join(format!("rusty_pizza_admin_{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir(&directory).unwrap();
+        let log = directory.join("log.jsonl");
+        let event = OrderEvent::MealAdded {
+            order_id: 0,
+            user_id: Some(1),
+            meal_id: String::from("03"),
+            variety: String::from("groß"),
+        };
+        fs::write(
+            &log,
+            format!("{}\n", serde_json::to_string(&event).unwrap()),
+        )
+        .unwrap();
+        let template_file = directory.join("templates.jsonl");
+        let mut saved = OrderTemplates::new();
+        saved.add(OrderTemplate::new(
+            String::from("Friday pizza"),
+            Id::new(0),
+            Restaurant::new(String::from("Pizzeria Luigi")),
+        ));
+        templates::save_templates(&saved, &template_file).unwrap();
+        let args = AdminArgs {
+            server: String::from(DEFAULT_SERVER),
+            admin_token: None,
+            command: AdminCommand::Backup {
+                target: directory.join("backup"),
+                log: Some(log),
+                journal: None,
+                templates: Some(template_file),
+            },
+        };
+
+        // When:
+        let first = run(&args).await;
+        let second = run(&args).await;
+
+        // Then:
+        assert_eq!(
+            first,
+            Ok(format!(
+                "Backed up 1 events and 1 templates to {}",
+                directory.join("backup").display()
+            ))
+        );
+        assert!(matches!(second, Err(CliError::Storage(_))));
+        assert_eq!(
+            templates::load_templates(&directory.join("backup").join(BACKUP_TEMPLATES)).unwrap(),
+            saved
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn running_server_is_maintained_via_api() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        let state = AppState::new().with_admin_token(String::from("secret"));
+        state.orders().create_order(Id::new(0));
+        state.auth().start_session(Id::new(0));
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let args = |command| AdminArgs {
+            server: server.clone(),
+            admin_token: Some(String::from("secret")),
+            command,
+        };
+
+        // When:
+        let verified = run(&args(AdminCommand::Verify)).await;
+        let stuck = run(&args(AdminCommand::StuckOrders {
+            idle_minutes: Some(0),
+        }))
+        .await;
+        let rotated = run(&args(AdminCommand::RotateTokens)).await;
+
+        // Then:
+        assert_eq!(verified, Err(CliError::Unhealthy(vec![String::from(
+            "order 0: user 0 is not registered, repair: register the user or remove them from the order"
+        )])));
+        assert!(stuck.is_ok());
+        assert_eq!(
+            rotated,
+            Ok(String::from(
+                "Sessions ended: 1, users have to log in again"
+            ))
+        );
+    }
+}
@@ -0,0 +1,491 @@
+use crate::api::error::ApiError;
+use crate::api::v1::dto::{
+    AddMealRequest, AddUserRequest, AmountRequest, CreateOrderRequest, CreatedOrderResponse,
+    CreatedResponse, ErrorResponse, TotalsResponse,
+};
+use crate::cli::command::{Command, MealCommand, OrderCommand, PaidCommand};
+use crate::order_model::manager::OrderManager;
+use crate::order_model::order::Order;
+use crate::render::table::{Alignment, Table, TableStyle};
+use crate::util::http::{HttpEndpoint, HttpError, HttpResponse};
+use crate::util::id::Id;
+use crate::util::money::Money;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    /// The command was refused by the local model
+    Local(ApiError),
+    /// The command was refused by the server
+    Server {
+        status: u16,
+        message: String,
+    },
+    Http(HttpError),
+    /// The journal could not be read or written
+    Journal(String),
+    /// Files of the server could not be read or written, see `AdminCommand`
+    Storage(String),
+    /// The integrity check of the server found the issues
+    Unhealthy(Vec<String>),
+    /// The config file with the profiles could not be read or written, see `profile::Config`
+    Config(String),
+    /// The command needs a server, but neither the command line nor the profile gives one
+    NoServer,
+    /// The command leaves the user out, but the profile has no default user
+    NoUser,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use CliError::*;
+        match self {
+            Local(error) => write!(f, "{}", error),
+            Server { status, message } => write!(f, "server answered {}: {}", status, message),
+            Http(error) => write!(f, "{}", error),
+            Journal(error) => write!(f, "journal not usable: {}", error),
+            Storage(error) => write!(f, "storage not usable: {}", error),
+            Unhealthy(issues) => write!(f, "integrity check found issues:\n{}", issues.join("\n")),
+            Config(error) => write!(f, "config not usable: {}", error),
+            NoServer => write!(f, "no server given, pass --server or log in with it"),
+            NoUser => write!(f, "no user given and the profile has no default user"),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+impl From<ApiError> for CliError {
+    fn from(error: ApiError) -> Self {
+        CliError::Local(error)
+    }
+}
+
+impl From<HttpError> for CliError {
+    fn from(error: HttpError) -> Self {
+        CliError::Http(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Journal(error.to_string())
+    }
+}
+
+/// What a command did, printed for the user.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    OrderCreated(u32),
+    UserJoined { order: u32, user: u32 },
+    MealAdded { order: u32, meal_id: u32 },
+    PaidSet { order: u32, user: u32, amount: u32 },
+    Totals(TotalsResponse),
+}
+
+impl Outcome {
+    /// Text for the user, with tables drawn in the style and narrowed to the width, if given.
+    pub fn render(&self, style: TableStyle, max_width: Option<usize>) -> String {
+        use Outcome::*;
+        match self {
+            OrderCreated(order) => format!("Created order {}", order),
+            UserJoined { order, user } => format!("User {} joined order {}", user, order),
+            MealAdded { order, meal_id } => format!("Added meal {} to order {}", meal_id, order),
+            PaidSet {
+                order,
+                user,
+                amount,
+            } => format!(
+                "User {} paid {} in order {}",
+                user,
+                Money::from_cents(*amount),
+                order
+            ),
+            Totals(totals) => {
+                let mut table = Table::new(vec!["Total", "Amount"])
+                    .with_style(style)
+                    .with_alignment(1, Alignment::Right);
+                if let Some(max_width) = max_width {
+                    table = table.with_max_width(max_width);
+                }
+                table.add_row(vec![
+                    String::from("Price"),
+                    Money::from_cents(totals.price_cents).to_string(),
+                ]);
+                table.add_row(vec![
+                    String::from("Tip"),
+                    Money::from_cents(totals.tip_cents).to_string(),
+                ]);
+                match (totals.change_cents, totals.underpaid_cents) {
+                    (Some(change), _) => table.add_row(vec![
+                        String::from("Change"),
+                        Money::from_cents(change).to_string(),
+                    ]),
+                    (None, Some(underpaid)) => table.add_row(vec![
+                        String::from("Missing"),
+                        Money::from_cents(underpaid).to_string(),
+                    ]),
+                    (None, None) => {}
+                }
+                let mut text = table.to_string();
+                if !totals.paid_less.is_empty() {
+                    let users: Vec<_> = totals.paid_less.iter().map(u32::to_string).collect();
+                    text.push_str(&format!("Paid too little: users {}\n", users.join(", ")));
+                }
+                text
+            }
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(TableStyle::default(), None))
+    }
+}
+
+/// Runs commands on orders kept in memory, which only live as long as the process.
+///
+/// To use the orders across runs, the changes are appended to a journal file and made again on the next run.
+#[derive(Debug, Default)]
+pub struct LocalBackend {
+    orders: OrderManager,
+    journal: Option<PathBuf>,
+}
+
+impl LocalBackend {
+    /// Orders which are lost at the end of the process.
+    pub fn in_memory() -> LocalBackend {
+        LocalBackend::default()
+    }
+
+    /// Orders from the journal, which is created on the first change if it doesn't exist.
+    pub fn open(journal: PathBuf) -> Result<LocalBackend, CliError> {
+        let mut backend = LocalBackend::default();
+        if journal.exists() {
+            for (index, line) in fs::read_to_string(&journal)?.lines().enumerate() {
+                let command: Command = serde_json::from_str(line)
+                    .map_err(|error| CliError::Journal(format!("line {}: {}", index + 1, error)))?;
+                backend.apply(&command)?;
+            }
+        }
+        backend.journal = Some(journal);
+        Ok(backend)
+    }
+
+    pub fn execute(&mut self, command: &Command) -> Result<Outcome, CliError> {
+        let outcome = self.apply(command)?;
+        if let (Some(journal), true) = (&self.journal, command.is_change()) {
+            let mut file = OpenOptions::new().create(true).append(true).open(journal)?;
+            let line = serde_json::to_string(command).expect("Commands can be serialized");
+            writeln!(file, "{}", line)?;
+        }
+        Ok(outcome)
+    }
+
+    fn apply(&mut self, command: &Command) -> Result<Outcome, CliError> {
+        let outcome = match command {
+            Command::Order(OrderCommand::Create { manager }) => {
+                let manager = manager.ok_or(CliError::NoUser)?;
+                Outcome::OrderCreated(self.orders.create_order(Id::new(manager)).get_value())
+            }
+            Command::Order(OrderCommand::Join { order, user }) => {
+                let user = user.ok_or(CliError::NoUser)?;
+                let order_model = self.get_order(*order)?;
+                order_model
+                    .add_user(Id::new(user))
+                    .map_err(ApiError::from)?;
+                Outcome::UserJoined {
+                    order: *order,
+                    user,
+                }
+            }
+            Command::Meal(MealCommand::Add { order, user, meal }) => {
+                // The journal has no sessions, commands act as the user they name
+                let meal = self
+                    .get_order(*order)?
+                    .add_meal_as(
+                        &Id::new(*user),
+                        Id::new(*user),
+                        meal.meal.clone(),
+                        meal.variety.clone(),
+                        Money::from_cents(meal.price),
+                    )
+                    .map_err(ApiError::from)?;
+                Outcome::MealAdded {
+                    order: *order,
+                    meal_id: meal.get_id().get_value(),
+                }
+            }
+            Command::Paid(PaidCommand::Set {
+                order,
+                user,
+                amount,
+            }) => {
+                self.get_order(*order)?
+                    .set_paid_for_user(Id::new(*user), Money::from_cents(*amount))
+                    .map_err(ApiError::from)?;
+                Outcome::PaidSet {
+                    order: *order,
+                    user: *user,
+                    amount: *amount,
+                }
+            }
+            Command::Totals { order } => {
+                Outcome::Totals(TotalsResponse::from(&*self.get_order(*order)?))
+            }
+        };
+        Ok(outcome)
+    }
+
+    fn get_order(&mut self, id: u32) -> Result<&mut Order, CliError> {
+        self.orders
+            .get_order_mut(&Id::new(id))
+            .ok_or_else(|| ApiError::OrderNotFound.into())
+    }
+}
+
+/// Runs commands via the HTTP API of a server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteBackend {
+    server: HttpEndpoint,
+    /// Session token sent with every request, if any
+    token: Option<String>,
+}
+
+impl RemoteBackend {
+    pub fn new(server: HttpEndpoint, token: Option<String>) -> RemoteBackend {
+        RemoteBackend { server, token }
+    }
+
+    pub async fn execute(&self, command: &Command) -> Result<Outcome, CliError> {
+        let outcome = match command {
+            Command::Order(OrderCommand::Create { manager }) => {
+                let request = CreateOrderRequest {
+                    manager_id: manager.ok_or(CliError::NoUser)?,
+                    restaurant: None,
+                    currency: None,
+                    locale: None,
+                };
+                let created: CreatedOrderResponse =
+                    self.send("POST", "/v1/orders", Some(&request)).await?;
+                Outcome::OrderCreated(created.id)
+            }
+            Command::Order(OrderCommand::Join { order, user }) => {
+                let user = user.ok_or(CliError::NoUser)?;
+                let path = format!("/v1/orders/{}/users", order);
+                let request = AddUserRequest { user_id: user };
+                self.send_without_answer("POST", &path, Some(&request))
+                    .await?;
+                Outcome::UserJoined {
+                    order: *order,
+                    user,
+                }
+            }
+            Command::Meal(MealCommand::Add { order, user, meal }) => {
+                let path = format!("/v1/orders/{}/users/{}/meals", order, user);
+                let request = AddMealRequest {
+                    meal_id: meal.meal.clone(),
+                    variety: meal.variety.clone(),
+                    price_cents: meal.price,
+                    note: None,
+                    deposit_cents: None,
+                };
+                let created: CreatedResponse = self.send("POST", &path, Some(&request)).await?;
+                Outcome::MealAdded {
+                    order: *order,
+                    meal_id: created.id,
+                }
+            }
+            Command::Paid(PaidCommand::Set {
+                order,
+                user,
+                amount,
+            }) => {
+                let path = format!("/v1/orders/{}/users/{}/paid", order, user);
+                let request = AmountRequest {
+                    amount_cents: *amount,
+                };
+                self.send_without_answer("PUT", &path, Some(&request))
+                    .await?;
+                Outcome::PaidSet {
+                    order: *order,
+                    user: *user,
+                    amount: *amount,
+                }
+            }
+            Command::Totals { order } => {
+                let path = format!("/v1/orders/{}/totals", order);
+                Outcome::Totals(self.send("GET", &path, None::<&()>).await?)
+            }
+        };
+        Ok(outcome)
+    }
+
+    /// Sends the request and reads the answer of the server.
+    pub async fn send<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        request: Option<&impl Serialize>,
+    ) -> Result<T, CliError> {
+        let response = self.send_without_answer(method, path, request).await?;
+        serde_json::from_str(response.get_body()).map_err(|error| CliError::Server {
+            status: response.get_status(),
+            message: format!("unexpected answer: {}", error),
+        })
+    }
+
+    /// Sends the request and checks that the server accepted it.
+    pub async fn send_without_answer(
+        &self,
+        method: &str,
+        path: &str,
+        request: Option<&impl Serialize>,
+    ) -> Result<HttpResponse, CliError> {
+        let json = request
+            .map(|request| serde_json::to_string(request).expect("Requests can be serialized"));
+        let response = self
+            .server
+            .join(path)
+            .send_json(method, self.token.as_deref(), json.as_deref())
+            .await?;
+        if response.is_success() {
+            return Ok(response);
+        }
+        let message = serde_json::from_str::<ErrorResponse>(response.get_body())
+            .map(|error| error.error)
+            .unwrap_or_else(|_| response.get_body().clone());
+        Err(CliError::Server {
+            status: response.get_status(),
+            message,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::routes::router;
+    use crate::api::state::AppState;
+    use crate::cli::command::MealEntry;
+    use crate::order_model::order::OrderError;
+    use std::process;
+    use tokio::net::TcpListener;
+
+    /// Creates order 0 with user 1, who ordered a meal for 7.50 and paid 10.
+    fn commands() -> Vec<Command> {
+        vec![
+            Command::Order(OrderCommand::Create { manager: Some(0) }),
+            Command::Order(OrderCommand::Join {
+                order: 0,
+                user: Some(1),
+            }),
+            Command::Meal(MealCommand::Add {
+                order: 0,
+                user: 1,
+                meal: MealEntry {
+                    meal: String::from("03"),
+                    variety: String::from("groß"),
+                    price: 750,
+                },
+            }),
+            Command::Paid(PaidCommand::Set {
+                order: 0,
+                user: 1,
+                amount: 1000,
+            }),
+        ]
+    }
+
+    fn expected_totals() -> Outcome {
+        Outcome::Totals(TotalsResponse {
+            price_cents: 750,
+            office_price_cents: 0,
+            shared_price_cents: 0,
+            tip_cents: 0,
+            deposit_cents: 0,
+            returned_deposit_cents: 0,
+            change_cents: Some(250),
+            underpaid_cents: None,
+            paid_less: vec![],
+            rounding: vec![],
+        })
+    }
+
+    #[test]
+    fn changes_are_replayed_from_journal() {
+        // Given:
+        let journal = std::env::temp_dir().join(format!("rusty_pizza_cli_{}.jsonl", process::id()));
+        let _ = fs::remove_file(&journal);
+        let mut backend = LocalBackend::open(journal.clone()).unwrap();
+        for command in commands() {
+            backend.execute(&command).unwrap();
+        }
+        let refused = backend.execute(&Command::Order(OrderCommand::Join {
+            order: 0,
+            user: Some(1),
+        }));
+
+        // When:
+        let mut reopened = LocalBackend::open(journal.clone()).unwrap();
+        let totals = reopened.execute(&Command::Totals { order: 0 });
+
+        // Then:
+        assert_eq!(
+            refused,
+            Err(CliError::Local(ApiError::Order(
+                OrderError::UserAlreadyParticipating
+            )))
+        );
+        assert_eq!(totals, Ok(expected_totals()));
+        assert_eq!(fs::read_to_string(&journal).unwrap().lines().count(), 4);
+        fs::remove_file(&journal).unwrap();
+    }
+
+    #[tokio::test]
+    async fn commands_are_run_via_api() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(AppState::new())).await });
+        let backend = RemoteBackend::new(url.parse().unwrap(), None);
+
+        // When:
+        for command in commands() {
+            backend.execute(&command).await.unwrap();
+        }
+        let totals = backend.execute(&Command::Totals { order: 0 }).await;
+        let unknown = backend.execute(&Command::Totals { order: 7 }).await;
+
+        // Then:
+        assert_eq!(totals, Ok(expected_totals()));
+        assert_eq!(
+            unknown,
+            Err(CliError::Server {
+                status: 404,
+                message: String::from("order not found")
+            })
+        );
+    }
+
+    #[test]
+    fn totals_are_shown_with_change() {
+        assert_eq!(
+            expected_totals().render(TableStyle::Ascii, None),
+            "+--------+--------+\n\
+             | Total  | Amount |\n\
+             +--------+--------+\n\
+             | Price  |  7,50€ |\n\
+             | Tip    |  0,00€ |\n\
+             | Change |  2,50€ |\n\
+             +--------+--------+\n"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::cli::profile::SessionCommand;
+use crate::quick_entry::parser;
+use crate::util::money::Money;
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// File the orders are kept in without server, in the current directory.
+pub const DEFAULT_JOURNAL: &str = "rusty_pizza.jsonl";
+
+/// Manages pizza orders from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "rusty_pizza_cli", version)]
+pub struct Cli {
+    /// Base URL of the server, e.g. http://localhost:8080; without it the orders are kept in the journal
+    #[arg(long)]
+    pub server: Option<String>,
+    /// Profile with the server, session and default user, see `login`
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// File with the profiles, `.rusty_pizza_cli.json` in the home directory by default
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Session token, for servers requiring authentication
+    #[arg(long)]
+    pub token: Option<String>,
+    /// File the changes are kept in without server, replayed on every run
+    #[arg(long, default_value = DEFAULT_JOURNAL)]
+    pub journal: PathBuf,
+    /// Draws tables with ASCII characters only, for terminals without UTF-8
+    #[arg(long)]
+    pub ascii: bool,
+    #[command(subcommand)]
+    pub command: CliCommand,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand)]
+pub enum CliCommand {
+    #[command(flatten)]
+    Orders(Command),
+    #[command(flatten)]
+    Session(SessionCommand),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand, Serialize, Deserialize)]
+pub enum Command {
+    /// Creates and joins orders
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// Adds meals to orders
+    #[command(subcommand)]
+    Meal(MealCommand),
+    /// Records what participants paid
+    #[command(subcommand)]
+    Paid(PaidCommand),
+    /// Shows the total price of an order and the change
+    Totals { order: u32 },
+}
+
+impl Command {
+    /// Whether the command changes orders, rather than only showing them.
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Command::Totals { .. })
+    }
+
+    /// Fills in the user where the command line left it out, e.g. the default user of the profile.
+    pub fn with_default_user(self, default: Option<u32>) -> Command {
+        match self {
+            Command::Order(OrderCommand::Create { manager: None }) => {
+                Command::Order(OrderCommand::Create { manager: default })
+            }
+            Command::Order(OrderCommand::Join { order, user: None }) => {
+                Command::Order(OrderCommand::Join {
+                    order,
+                    user: default,
+                })
+            }
+            command => command,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand, Serialize, Deserialize)]
+pub enum OrderCommand {
+    /// Creates an order managed by the user, the default user of the profile if left out
+    Create {
+        #[arg(long)]
+        manager: Option<u32>,
+    },
+    /// Adds the user to the order, the default user of the profile if left out
+    Join { order: u32, user: Option<u32> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand, Serialize, Deserialize)]
+pub enum MealCommand {
+    /// Adds a meal for the user, e.g. `meal add 0 1 "03 groß @7,50"`
+    Add {
+        order: u32,
+        user: u32,
+        /// Meal as in a quick entry, with its price after the `@`
+        #[arg(value_parser = parse_meal)]
+        #[serde(flatten)]
+        meal: MealEntry,
+    },
+}
+
+/// A single meal with its price, see `parse_meal`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MealEntry {
+    /// Number of the meal on the menu
+    pub meal: String,
+    pub variety: String,
+    pub price: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand, Serialize, Deserialize)]
+pub enum PaidCommand {
+    /// Sets how much the user paid in total, e.g. `paid set 0 1 10`
+    Set {
+        order: u32,
+        user: u32,
+        /// Amount like "10" or "10.50"
+        #[arg(value_parser = parse_cents)]
+        amount: u32,
+    },
+}
+
+/// Parses a meal written like in a quick entry, see `quick_entry::parser`, e.g. "03 groß @7,50".
+///
+/// Only a single meal with a price and without specials can be added at a time.
+fn parse_meal(entry: &str) -> Result<MealEntry, String> {
+    let mut specs = parser::parse(entry).map_err(|error| error.to_string())?;
+    if specs.len() != 1 || specs[0].get_quantity() != 1 {
+        return Err(String::from("Only a single meal can be added at a time"));
+    }
+    let spec = specs.remove(0);
+    if !spec.get_specials().is_empty() {
+        return Err(String::from(
+            "Specials can't be added from the command line",
+        ));
+    }
+    let price = spec
+        .get_price()
+        .ok_or_else(|| String::from("Missing price, e.g. \"03 groß @7,50\""))?;
+    Ok(MealEntry {
+        meal: spec.get_meal_id().clone(),
+        variety: spec.get_variety().clone(),
+        price: price.get_total_cents(),
+    })
+}
+
+fn parse_cents(amount: &str) -> Result<u32, String> {
+    amount
+        .parse::<Money>()
+        .map(|money| money.get_total_cents())
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        args,
+        expected,
+        case(
+            vec!["order", "create", "--manager", "0"],
+            Command::Order(OrderCommand::Create { manager: Some(0) })
+        ),
+        case(
+            vec!["meal", "add", "3", "1", "03 groß @7,50"],
+            Command::Meal(MealCommand::Add {
+                order: 3,
+                user: 1,
+                meal: MealEntry {
+                    meal: String::from("03"),
+                    variety: String::from("groß"),
+                    price: 750
+                }
+            })
+        ),
+        case(
+            vec!["paid", "set", "3", "1", "10"],
+            Command::Paid(PaidCommand::Set { order: 3, user: 1, amount: 1000 })
+        ),
+        case(vec!["totals", "3"], Command::Totals { order: 3 })
+    )]
+    fn command_is_parsed(args: Vec<&str>, expected: Command) {
+        // When:
+        let cli = Cli::try_parse_from([&["rusty_pizza_cli"], args.as_slice()].concat()).unwrap();
+
+        // Then:
+        assert_eq!(cli.command, CliCommand::Orders(expected));
+        assert_eq!(cli.server, None);
+        assert_eq!(cli.journal, PathBuf::from(DEFAULT_JOURNAL));
+    }
+
+    #[rstest(
+        args,
+        expected,
+        case(vec!["order", "create"], Command::Order(OrderCommand::Create { manager: Some(2) })),
+        case(vec!["order", "join", "3"], Command::Order(OrderCommand::Join { order: 3, user: Some(2) })),
+        case(vec!["order", "join", "3", "1"], Command::Order(OrderCommand::Join { order: 3, user: Some(1) }))
+    )]
+    fn left_out_user_is_the_default_user(args: Vec<&str>, expected: Command) {
+        // Given:
+        let cli = Cli::try_parse_from([&["rusty_pizza_cli"], args.as_slice()].concat()).unwrap();
+
+        // When:
+        let command = match cli.command {
+            CliCommand::Orders(command) => command.with_default_user(Some(2)),
+            command => panic!("Unexpected command {:?}", command),
+        };
+
+        // Then:
+        assert_eq!(command, expected);
+    }
+
+    #[rstest(
+        args,
+        expected,
+        case(
+            vec!["--profile", "work", "login", "anna"],
+            SessionCommand::Login { name: String::from("anna"), password: None }
+        ),
+        case(vec!["whoami"], SessionCommand::Whoami)
+    )]
+    fn session_command_is_parsed(args: Vec<&str>, expected: SessionCommand) {
+        // When:
+        let cli = Cli::try_parse_from([&["rusty_pizza_cli"], args.as_slice()].concat()).unwrap();
+
+        // Then:
+        assert_eq!(cli.command, CliCommand::Session(expected));
+    }
+
+    #[rstest(
+        entry,
+        case("03 groß @7,5x"),
+        case("03 groß"),
+        case("03 groß +Käserand @7,50"),
+        case("2x03 groß @7,50"),
+        case("03 groß @7,50; 05 klein @4,50")
+    )]
+    fn meal_which_cannot_be_added_is_rejected(entry: &str) {
+        // When:
+        let cli = Cli::try_parse_from(["rusty_pizza_cli", "meal", "add", "3", "1", entry]);
+
+        // Then:
+        assert!(cli.is_err());
+    }
+
+    #[test]
+    fn meal_is_journaled_in_its_fields() {
+        // Given:
+        let command = Command::Meal(MealCommand::Add {
+            order: 3,
+            user: 1,
+            meal: parse_meal("03 groß @7,50").unwrap(),
+        });
+
+        // When:
+        let json = serde_json::to_string(&command).unwrap();
+
+        // Then:
+        assert_eq!(
+            json,
+            r#"{"Meal":{"Add":{"order":3,"user":1,"meal":"03","variety":"groß","price":750}}}"#
+        );
+        assert_eq!(serde_json::from_str::<Command>(&json).unwrap(), command);
+    }
+}
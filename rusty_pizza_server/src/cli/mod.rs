@@ -0,0 +1,4 @@
+pub mod admin;
+pub mod backend;
+pub mod command;
+pub mod profile;
@@ -0,0 +1,340 @@
+use crate::api::v1::dto::{CurrentSessionResponse, LoginRequest, RoleResponse, SessionResponse};
+use crate::cli::backend::{CliError, RemoteBackend};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Name of the config file in the home directory.
+pub const DEFAULT_CONFIG: &str = ".rusty_pizza_cli.json";
+/// Profile used unless another one is given or set as default in the config file.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings of the CLI for one server and user, so they don't have to be passed on every invocation.
+///
+/// Options on the command line take precedence over the profile.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Base URL of the server, the journal is used without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Session token saved by `login`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// User to act as when a command leaves the user out, e.g. `order join 3`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<u32>,
+}
+
+/// Named profiles, kept as JSON in a file, e.g. `{"profiles": {"work": {"server": "http://pizza:8080"}}}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Profile used unless another one is given, `DEFAULT_PROFILE` if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// Profiles from the file, none if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Config, CliError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(path).map_err(|error| config_error(path, error))?;
+        serde_json::from_str(&content).map_err(|error| config_error(path, error))
+    }
+
+    /// Writes the profiles to the file, only readable by the user as it contains session tokens.
+    pub fn save(&self, path: &Path) -> Result<(), CliError> {
+        let json = serde_json::to_string_pretty(self).expect("Configs can be serialized");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(path)
+            .map_err(|error| config_error(path, error))?;
+        writeln!(file, "{}", json).map_err(|error| config_error(path, error))
+    }
+
+    /// Name of the profile to use, the given one or else the default one.
+    pub fn select(&self, name: Option<&str>) -> String {
+        name.or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string()
+    }
+
+    /// The profile with the name, an empty one if there is none.
+    pub fn get(&self, name: &str) -> Profile {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, name: String, profile: Profile) {
+        self.profiles.insert(name, profile);
+    }
+}
+
+/// Path of the config file in the home directory, or in the current directory without home.
+pub fn default_config_path() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(DEFAULT_CONFIG)
+}
+
+fn config_error(path: &Path, error: impl ToString) -> CliError {
+    CliError::Config(format!("{}: {}", path.display(), error.to_string()))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand)]
+pub enum SessionCommand {
+    /// Logs in at the server and saves the session and the user in the profile
+    Login {
+        name: String,
+        /// Read from the standard input if missing, so it doesn't end up in the shell history
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Shows the user of the profile and their role in the orders of the server
+    Whoami,
+}
+
+/// Runs the command with the profile, the saved one with the options of the command line applied, and saves
+/// changes of the profile in the config file at `path`.
+pub async fn run(
+    command: &SessionCommand,
+    config: &mut Config,
+    path: &Path,
+    name: &str,
+    profile: Profile,
+) -> Result<String, CliError> {
+    match command {
+        SessionCommand::Login {
+            name: user,
+            password,
+        } => {
+            let url = profile.server.clone().ok_or(CliError::NoServer)?;
+            let password = match password {
+                Some(password) => password.clone(),
+                None => read_password()?,
+            };
+            let request = LoginRequest {
+                name: user.clone(),
+                password,
+            };
+            let session: SessionResponse = RemoteBackend::new(url.parse()?, None)
+                .send("POST", "/v1/sessions", Some(&request))
+                .await?;
+            config.set(
+                String::from(name),
+                Profile {
+                    server: Some(url.clone()),
+                    token: Some(session.token),
+                    user: Some(session.user_id),
+                },
+            );
+            config.save(path)?;
+            Ok(format!(
+                "Logged in as user {} at {}, saved in profile {}",
+                session.user_id, url, name
+            ))
+        }
+        SessionCommand::Whoami => {
+            let mut lines = vec![format!("Profile: {}", name)];
+            let (url, token) = match (profile.server, profile.token) {
+                (Some(url), Some(token)) => (url, token),
+                (server, _) => {
+                    lines.push(format!(
+                        "Server: {}",
+                        server.as_deref().unwrap_or("none, using the journal")
+                    ));
+                    lines.push(match profile.user {
+                        Some(user) => format!("User: {}, not logged in", user),
+                        None => String::from("Not logged in"),
+                    });
+                    return Ok(lines.join("\n"));
+                }
+            };
+            let session: CurrentSessionResponse = RemoteBackend::new(url.parse()?, Some(token))
+                .send("GET", "/v1/sessions", None::<&()>)
+                .await?;
+            lines.push(format!("Server: {}", url));
+            lines.push(format!("User: {} ({})", session.user_id, session.name));
+            for (role, label) in [
+                (RoleResponse::Manager, "Manager of orders"),
+                (RoleResponse::Participant, "Participant in orders"),
+            ]
+            .iter()
+            {
+                let orders: Vec<String> = session
+                    .orders
+                    .iter()
+                    .filter(|order| order.role == *role)
+                    .map(|order| order.order_id.to_string())
+                    .collect();
+                if !orders.is_empty() {
+                    lines.push(format!("{}: {}", label, orders.join(", ")));
+                }
+            }
+            Ok(lines.join("\n"))
+        }
+    }
+}
+
+fn read_password() -> Result<String, CliError> {
+    eprint!("Password: ");
+    let mut password = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .map_err(|error| CliError::Config(error.to_string()))?;
+    Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::routes::router;
+    use crate::api::state::AppState;
+    use crate::order_model::user::User;
+    use crate::util::id::Id;
+    use std::process;
+    use tokio::net::TcpListener;
+
+    fn config_path(test: &str) -> PathBuf {
+        env::temp_dir().join(format!("rusty_pizza_cli_{}_{}.json", test, process::id()))
+    }
+
+    #[test]
+    fn missing_config_has_no_profiles() {
+        // When:
+        let config = Config::load(&config_path("missing")).unwrap();
+
+        // Then:
+        assert_eq!(config, Config::default());
+        assert_eq!(config.select(None), DEFAULT_PROFILE);
+        assert_eq!(config.get(DEFAULT_PROFILE), Profile::default());
+    }
+
+    #[test]
+    fn default_profile_is_selected_unless_another_is_given() {
+        // Given:
+        let path = config_path("select");
+        let mut config = Config {
+            default_profile: Some(String::from("work")),
+            profiles: BTreeMap::new(),
+        };
+        config.set(
+            String::from("work"),
+            Profile {
+                server: Some(String::from("http://pizza:8080")),
+                token: None,
+                user: Some(3),
+            },
+        );
+        config.save(&path).unwrap();
+
+        // When:
+        let loaded = Config::load(&path).unwrap();
+
+        // Then:
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.select(None), "work");
+        assert_eq!(loaded.select(Some("home")), "home");
+        assert_eq!(loaded.get("work").user, Some(3));
+    }
+
+    #[tokio::test]
+    async fn login_is_saved_and_shown_by_whoami() {
+        // Given:
+        let state = AppState::new();
+        let user_id = state
+            .users_mut()
+            .register(String::from("Anna"))
+            .unwrap()
+            .get_id();
+        state.auth().set_password(user_id, "secret").unwrap();
+        state.orders().create_order(Id::<User>::new(0));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let path = config_path("login");
+        let mut config = Config::default();
+        let login = SessionCommand::Login {
+            name: String::from("Anna"),
+            password: Some(String::from("secret")),
+        };
+        let profile = Profile {
+            server: Some(url.clone()),
+            token: None,
+            user: None,
+        };
+
+        // When:
+        let logged_in = run(&login, &mut config, &path, "work", profile).await;
+        let saved = Config::load(&path).unwrap();
+        let whoami = run(
+            &SessionCommand::Whoami,
+            &mut config,
+            &path,
+            "work",
+            saved.get("work"),
+        )
+        .await;
+
+        // Then:
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            logged_in,
+            Ok(format!(
+                "Logged in as user 0 at {}, saved in profile work",
+                url
+            ))
+        );
+        assert_eq!(saved.get("work").user, Some(0));
+        assert!(saved.get("work").token.is_some());
+        assert_eq!(
+            whoami,
+            Ok(format!(
+                "Profile: work\nServer: {}\nUser: 0 (Anna)\nManager of orders: 0",
+                url
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn whoami_without_session_shows_profile() {
+        // Given:
+        let profile = Profile {
+            server: None,
+            token: None,
+            user: Some(2),
+        };
+
+        // When:
+        let whoami = run(
+            &SessionCommand::Whoami,
+            &mut Config::default(),
+            &config_path("whoami"),
+            DEFAULT_PROFILE,
+            profile,
+        )
+        .await;
+
+        // Then:
+        assert_eq!(
+            whoami,
+            Ok(String::from(
+                "Profile: default\nServer: none, using the journal\nUser: 2, not logged in"
+            ))
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use crate::menu::catalog::MenuError;
+use crate::order_model::meals::ChangeMoneyError;
+use crate::order_model::order::{NotAllPaidEnoughError, Order, OrderError};
+use crate::order_model::payment::PaymentError;
+use crate::order_model::restaurant::RestaurantError;
+use crate::order_model::user::User;
+use crate::user_model::repository::RegistrationError;
+use crate::util::errors::RemoveError;
+use crate::util::id::Id;
+use crate::util::id_provider::IdsExhaustedError;
+use crate::util::money::CurrencyMismatchError;
+use std::error::Error;
+use std::fmt;
+
+/// What was looked up or removed when an error happened, e.g. for "meal 03 not found".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Subject {
+    Order(Id<Order>),
+    User(Id<User>),
+    /// Meal number on the menu
+    MenuItem(String),
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Subject::*;
+        match self {
+            Order(id) => write!(f, "order {}", id.get_value()),
+            User(id) => write!(f, "user {}", id.get_value()),
+            MenuItem(meal_id) => write!(f, "menu item {}", meal_id),
+        }
+    }
+}
+
+/// Any error of the domain model, so callers can handle them with one type and `?`.
+///
+/// The errors of the modules convert into it, the order and user they happened with can be added with
+/// `in_order` and `for_user`. The API maps it to a status code, see `ApiError`.
+#[derive(Debug, PartialEq)]
+pub enum PizzaError {
+    Order {
+        order_id: Option<Id<Order>>,
+        error: OrderError,
+    },
+    NotAllPaidEnough {
+        order_id: Option<Id<Order>>,
+        error: NotAllPaidEnoughError,
+    },
+    ChangeMoney {
+        order_id: Option<Id<Order>>,
+        user_id: Option<Id<User>>,
+        error: ChangeMoneyError,
+    },
+    Remove {
+        subject: Option<Subject>,
+        error: RemoveError,
+    },
+    Payment {
+        order_id: Option<Id<Order>>,
+        user_id: Option<Id<User>>,
+        error: PaymentError,
+    },
+    Menu(MenuError),
+    Restaurant(RestaurantError),
+    Registration(RegistrationError),
+    CurrencyMismatch(CurrencyMismatchError),
+    IdsExhausted(IdsExhaustedError),
+}
+
+impl PizzaError {
+    /// Nothing was found for the subject.
+    pub fn not_found(subject: Subject) -> PizzaError {
+        PizzaError::Remove {
+            subject: Some(subject),
+            error: RemoveError::NotFound,
+        }
+    }
+
+    /// Adds the order the error happened with, unless it is already known.
+    pub fn in_order(mut self, id: Id<Order>) -> PizzaError {
+        use PizzaError::*;
+        match &mut self {
+            Order { order_id, .. }
+            | NotAllPaidEnough { order_id, .. }
+            | ChangeMoney { order_id, .. }
+            | Payment { order_id, .. } => {
+                order_id.get_or_insert(id);
+            }
+            Remove { subject, .. } => {
+                subject.get_or_insert(Subject::Order(id));
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Adds the user the error happened with, unless it is already known.
+    pub fn for_user(mut self, id: Id<User>) -> PizzaError {
+        use PizzaError::*;
+        match &mut self {
+            ChangeMoney { user_id, .. } | Payment { user_id, .. } => {
+                user_id.get_or_insert(id);
+            }
+            Remove { subject, .. } => {
+                subject.get_or_insert(Subject::User(id));
+            }
+            _ => {}
+        }
+        self
+    }
+
+    pub fn get_order_id(&self) -> Option<Id<Order>> {
+        use PizzaError::*;
+        match self {
+            Order { order_id, .. }
+            | NotAllPaidEnough { order_id, .. }
+            | ChangeMoney { order_id, .. }
+            | Payment { order_id, .. } => order_id.clone(),
+            Remove {
+                subject: Some(Subject::Order(id)),
+                ..
+            } => Some(id.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn get_user_id(&self) -> Option<Id<User>> {
+        use PizzaError::*;
+        match self {
+            ChangeMoney { user_id, .. } | Payment { user_id, .. } => user_id.clone(),
+            Remove {
+                subject: Some(Subject::User(id)),
+                ..
+            } => Some(id.clone()),
+            _ => None,
+        }
+    }
+
+    fn get_source(&self) -> &(dyn Error + 'static) {
+        use PizzaError::*;
+        match self {
+            Order { error, .. } => error,
+            NotAllPaidEnough { error, .. } => error,
+            ChangeMoney { error, .. } => error,
+            Remove { error, .. } => error,
+            Payment { error, .. } => error,
+            Menu(error) => error,
+            Restaurant(error) => error,
+            Registration(error) => error,
+            CurrencyMismatch(error) => error,
+            IdsExhausted(error) => error,
+        }
+    }
+}
+
+impl fmt::Display for PizzaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let PizzaError::Remove {
+            subject: Some(subject),
+            ..
+        } = self
+        {
+            return write!(f, "{}: {}", subject, self.get_source());
+        }
+        if let Some(order_id) = self.get_order_id() {
+            write!(f, "order {}: ", order_id.get_value())?;
+        }
+        if let Some(user_id) = self.get_user_id() {
+            write!(f, "user {}: ", user_id.get_value())?;
+        }
+        write!(f, "{}", self.get_source())
+    }
+}
+
+impl Error for PizzaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.get_source())
+    }
+}
+
+impl From<OrderError> for PizzaError {
+    fn from(error: OrderError) -> Self {
+        PizzaError::Order {
+            order_id: None,
+            error,
+        }
+    }
+}
+
+impl From<NotAllPaidEnoughError> for PizzaError {
+    fn from(error: NotAllPaidEnoughError) -> Self {
+        PizzaError::NotAllPaidEnough {
+            order_id: None,
+            error,
+        }
+    }
+}
+
+impl From<ChangeMoneyError> for PizzaError {
+    fn from(error: ChangeMoneyError) -> Self {
+        PizzaError::ChangeMoney {
+            order_id: None,
+            user_id: None,
+            error,
+        }
+    }
+}
+
+impl From<RemoveError> for PizzaError {
+    fn from(error: RemoveError) -> Self {
+        PizzaError::Remove {
+            subject: None,
+            error,
+        }
+    }
+}
+
+impl From<PaymentError> for PizzaError {
+    fn from(error: PaymentError) -> Self {
+        PizzaError::Payment {
+            order_id: None,
+            user_id: None,
+            error,
+        }
+    }
+}
+
+impl From<MenuError> for PizzaError {
+    fn from(error: MenuError) -> Self {
+        PizzaError::Menu(error)
+    }
+}
+
+impl From<RestaurantError> for PizzaError {
+    fn from(error: RestaurantError) -> Self {
+        PizzaError::Restaurant(error)
+    }
+}
+
+impl From<RegistrationError> for PizzaError {
+    fn from(error: RegistrationError) -> Self {
+        PizzaError::Registration(error)
+    }
+}
+
+impl From<CurrencyMismatchError> for PizzaError {
+    fn from(error: CurrencyMismatchError) -> Self {
+        PizzaError::CurrencyMismatch(error)
+    }
+}
+
+impl From<IdsExhaustedError> for PizzaError {
+    fn from(error: IdsExhaustedError) -> Self {
+        PizzaError::IdsExhausted(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::money::Money;
+
+    fn remove_meal(meals: &mut Vec<String>, meal_id: &str) -> Result<String, PizzaError> {
+        let index = meals
+            .iter()
+            .position(|meal| meal == meal_id)
+            .ok_or_else(|| PizzaError::not_found(Subject::MenuItem(String::from(meal_id))))?;
+        Ok(meals.remove(index))
+    }
+
+    fn pay(paid: Money, price: Money) -> Result<Money, PizzaError> {
+        if paid < price {
+            return Err(ChangeMoneyError::Underpaid(price - paid).into());
+        }
+        Ok(paid - price)
+    }
+
+    #[test]
+    fn context_is_added_to_converted_error() {
+        // Given:
+        let error = PizzaError::from(OrderError::WrongStatus);
+
+        // When:
+        let error = error.in_order(Id::new(3)).in_order(Id::new(4));
+
+        // Then:
+        assert_eq!(error.get_order_id(), Some(Id::new(3)));
+        assert_eq!(error.get_user_id(), None);
+        assert_eq!(
+            error.to_string(),
+            "order 3: operation is not allowed in current order status"
+        );
+        assert!(error.source().unwrap().is::<OrderError>());
+    }
+
+    #[test]
+    fn errors_of_different_modules_are_propagated_with_question_mark() {
+        // Given:
+        let mut meals = vec![String::from("03")];
+
+        // When:
+        let removed = remove_meal(&mut meals, "04");
+        let underpaid = pay(Money::new(5, 0), Money::new(8, 50))
+            .map_err(|error| error.in_order(Id::new(1)).for_user(Id::new(2)));
+
+        // Then:
+        assert_eq!(removed.unwrap_err().to_string(), "menu item 04: Not found");
+        let underpaid = underpaid.unwrap_err();
+        assert_eq!(
+            underpaid,
+            PizzaError::ChangeMoney {
+                order_id: Some(Id::new(1)),
+                user_id: Some(Id::new(2)),
+                error: ChangeMoneyError::Underpaid(Money::new(3, 50)),
+            }
+        );
+        assert!(underpaid.to_string().starts_with("order 1: user 2: "));
+    }
+}
@@ -0,0 +1,497 @@
+use crate::export::consolidation::{consolidate, ConsolidatedMeal};
+use crate::export::csv::{csv_field, decimal};
+use crate::order_model::meal::Meal;
+use crate::order_model::order::Order;
+use crate::order_model::preparation::Preparation;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::locale::MoneyFormat;
+use crate::util::money::Money;
+use crate::util::short_code::IdFormat;
+use std::io::{self, Write};
+
+/// Meals of one participant, listed after the order lines so the delivery can be handed out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppendixEntry<'a> {
+    /// `None` for the office meals
+    user_id: Option<Id<User>>,
+    meals: Vec<&'a Meal>,
+}
+
+impl<'a> AppendixEntry<'a> {
+    pub fn get_user_id(&self) -> Option<Id<User>> {
+        self.user_id.clone()
+    }
+
+    pub fn get_meals(&self) -> &[&'a Meal] {
+        &self.meals
+    }
+}
+
+/// Everything the manager needs when calling the restaurant: the consolidated meals, the total and who gets what.
+///
+/// The sheet borrows the meals of the order, its renderers stream to any `Write` so even orders with
+/// hundreds of participants are never built up as one string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallSheet<'a> {
+    lines: Vec<ConsolidatedMeal>,
+    total_price: Money,
+    /// Sorted by user ID, office meals last
+    appendix: Vec<AppendixEntry<'a>>,
+    /// How participants are named in the plain text, the CSV always has numeric IDs
+    id_format: IdFormat,
+}
+
+impl<'a> CallSheet<'a> {
+    pub fn from_order(order: &'a Order) -> CallSheet<'a> {
+        let mut user_ids: Vec<&Id<User>> = order.participants().collect();
+        user_ids.sort_by_key(|id| id.get_value());
+        let mut appendix: Vec<AppendixEntry> = user_ids
+            .into_iter()
+            .map(|user_id| AppendixEntry {
+                user_id: Some(user_id.clone()),
+                meals: sorted(order.get_user_meals(user_id).unwrap().meals()),
+            })
+            .filter(|entry| !entry.meals.is_empty())
+            .collect();
+        let office_meals = sorted(order.office_meals());
+        if !office_meals.is_empty() {
+            appendix.push(AppendixEntry {
+                user_id: None,
+                meals: office_meals,
+            });
+        }
+        let lines = consolidate(order.all_meals());
+        CallSheet {
+            total_price: lines
+                .iter()
+                .fold(Money::zero(), |total, line| total + line.get_total_price()),
+            lines,
+            appendix,
+            id_format: IdFormat::default(),
+        }
+    }
+
+    pub fn with_id_format(mut self, id_format: IdFormat) -> CallSheet<'a> {
+        self.id_format = id_format;
+        self
+    }
+
+    pub fn lines(&self) -> &[ConsolidatedMeal] {
+        &self.lines
+    }
+
+    /// Price of all meals, without delivery fee and tips.
+    pub fn get_total_price(&self) -> Money {
+        self.total_price
+    }
+
+    pub fn appendix(&self) -> &[AppendixEntry<'a>] {
+        &self.appendix
+    }
+
+    /// Renders the order lines and the total as CSV with a header row, prices with a decimal point.
+    pub fn to_csv(&self) -> String {
+        render(|out| self.write_csv(out))
+    }
+
+    /// Streams the CSV of `to_csv` to `out` line by line.
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "quantity,meal_id,variety,specials,preparation,note,price"
+        )?;
+        for line in &self.lines {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                line.get_quantity(),
+                csv_field(line.get_meal_id()),
+                csv_field(line.get_variety()),
+                csv_field(&line.get_specials().join("; ")),
+                csv_field(&line.get_preparations().join("; ")),
+                csv_field(line.get_note().map_or("", String::as_str)),
+                decimal(line.get_total_price())
+            )?;
+        }
+        writeln!(out, ",,,,,total,{}", decimal(self.total_price))
+    }
+
+    /// Renders the appendix as CSV with one row per meal, the user ID being empty for office meals.
+    pub fn appendix_to_csv(&self) -> String {
+        render(|out| self.write_appendix_csv(out))
+    }
+
+    /// Streams the CSV of `appendix_to_csv` to `out` line by line.
+    pub fn write_appendix_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "user_id,meal_id,variety,specials,preparation,note,price"
+        )?;
+        for entry in &self.appendix {
+            let user_id = entry
+                .user_id
+                .as_ref()
+                .map_or(String::new(), |id| id.get_value().to_string());
+            for meal in &entry.meals {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{}",
+                    user_id,
+                    csv_field(meal.get_meal_id()),
+                    csv_field(meal.get_variety()),
+                    csv_field(&specials(meal).join("; ")),
+                    csv_field(&preparations(meal).join("; ")),
+                    csv_field(meal.get_note().map_or("", String::as_str)),
+                    decimal(meal.get_total_price())
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the sheet as text to be read out on the phone, prices written in the given `format`.
+    pub fn to_plain_text(&self, format: MoneyFormat) -> String {
+        render(|out| self.write_plain_text(format, out))
+    }
+
+    /// Streams the text of `to_plain_text` to `out` line by line.
+    pub fn write_plain_text<W: Write>(&self, format: MoneyFormat, out: &mut W) -> io::Result<()> {
+        for line in &self.lines {
+            writeln!(
+                out,
+                "{}x {} - {}",
+                line.get_quantity(),
+                describe(
+                    line.get_meal_id(),
+                    line.get_variety(),
+                    line.get_specials(),
+                    line.get_preparations(),
+                    line.get_note()
+                ),
+                format.format(line.get_total_price())
+            )?;
+        }
+        writeln!(out, "Total: {}", format.format(self.total_price))?;
+        if !self.appendix.is_empty() {
+            writeln!(out)?;
+        }
+        for entry in &self.appendix {
+            match &entry.user_id {
+                Some(user_id) => write!(out, "User {}: ", self.id_format.format(user_id))?,
+                None => write!(out, "Office: ")?,
+            }
+            for (i, meal) in entry.meals.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write!(
+                    out,
+                    "{}",
+                    describe(
+                        meal.get_meal_id(),
+                        meal.get_variety(),
+                        &specials(meal),
+                        &preparations(meal),
+                        meal.get_note()
+                    )
+                )?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the output of a renderer in memory, for small orders.
+fn render(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).expect("Writing to memory does not fail");
+    String::from_utf8(out).expect("Renderers only write UTF-8")
+}
+
+fn sorted<'a>(meals: impl Iterator<Item = &'a Meal>) -> Vec<&'a Meal> {
+    let mut meals: Vec<&Meal> = meals.collect();
+    meals.sort_by_key(|meal| meal.get_id().get_value());
+    meals
+}
+
+fn specials(meal: &Meal) -> Vec<String> {
+    let mut specials: Vec<String> = meal
+        .specials()
+        .map(|special| special.get_description())
+        .collect();
+    specials.sort();
+    specials
+}
+
+fn preparations(meal: &Meal) -> Vec<String> {
+    meal.get_preparations()
+        .iter()
+        .map(Preparation::to_string)
+        .collect()
+}
+
+fn describe(
+    meal_id: &str,
+    variety: &str,
+    specials: &[String],
+    preparations: &[String],
+    note: Option<&String>,
+) -> String {
+    let mut description = format!("{} {}", meal_id, variety);
+    if !specials.is_empty() {
+        description.push_str(&format!(" with {}", specials.join(", ")));
+    }
+    if !preparations.is_empty() {
+        description.push_str(&format!(" ({})", preparations.join(", ")));
+    }
+    if let Some(note) = note {
+        description.push_str(&format!(" \"{}\"", note));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(2)).unwrap();
+        for user_id in [2, 0] {
+            let meal = order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap()
+                .get_id();
+            order
+                .add_special_for_user(
+                    Id::new(user_id),
+                    meal,
+                    String::from("Knoblauch, extra"),
+                    None,
+                )
+                .unwrap();
+        }
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 5))
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn call_sheet_is_written_as_plain_text() {
+        // Given:
+        let order = order();
+        let sheet = CallSheet::from_order(&order);
+
+        // When:
+        let text = sheet.to_plain_text(MoneyFormat::default());
+
+        // Then:
+        assert_eq!(
+            text,
+            "2x 03 groß with Knoblauch, extra - 11,00€\n\
+             1x 61 Salat - 4,05€\n\
+             Total: 15,05€\n\
+             \n\
+             User 0: 03 groß with Knoblauch, extra\n\
+             User 2: 03 groß with Knoblauch, extra\n\
+             Office: 61 Salat\n"
+        );
+    }
+
+    #[test]
+    fn participants_can_be_named_by_short_code() {
+        // Given:
+        let order = order();
+        let sheet = CallSheet::from_order(&order).with_id_format(IdFormat::ShortCode);
+
+        // When:
+        let text = sheet.to_plain_text(MoneyFormat::default());
+
+        // Then:
+        assert!(text.contains("User USR-0000: 03 groß with Knoblauch, extra\n"));
+        assert!(text.contains("User USR-0026: 03 groß with Knoblauch, extra\n"));
+    }
+
+    #[test]
+    fn call_sheet_is_written_as_csv() {
+        // Given:
+        let order = order();
+        let sheet = CallSheet::from_order(&order);
+
+        // When:
+        let lines = sheet.to_csv();
+        let appendix = sheet.appendix_to_csv();
+
+        // Then:
+        assert_eq!(
+            lines,
+            "quantity,meal_id,variety,specials,preparation,note,price\n\
+             2,03,groß,\"Knoblauch, extra\",,,11.00\n\
+             1,61,Salat,,,,4.05\n\
+             ,,,,,total,15.05\n"
+        );
+        assert_eq!(
+            appendix,
+            "user_id,meal_id,variety,specials,preparation,note,price\n\
+             0,03,groß,\"Knoblauch, extra\",,,5.50\n\
+             2,03,groß,\"Knoblauch, extra\",,,5.50\n\
+             ,61,Salat,,,,4.05\n"
+        );
+    }
+
+    #[test]
+    fn preparations_are_written_in_own_column() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        let meal = order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap()
+            .get_id();
+        order
+            .set_preparations_for_user(
+                Id::new(0),
+                meal,
+                vec![Preparation::GlutenFreeBase, Preparation::WellDone]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+        let sheet = CallSheet::from_order(&order);
+
+        // When:
+        let lines = sheet.to_csv();
+        let text = sheet.to_plain_text(MoneyFormat::default());
+
+        // Then:
+        assert_eq!(
+            lines,
+            "quantity,meal_id,variety,specials,preparation,note,price\n\
+             1,03,groß,,well done; gluten-free base,,5.50\n\
+             ,,,,,total,5.50\n"
+        );
+        assert_eq!(
+            text,
+            "1x 03 groß (well done, gluten-free base) - 5,50€\n\
+             Total: 5,50€\n\
+             \n\
+             User 0: 03 groß (well done, gluten-free base)\n"
+        );
+    }
+
+    #[test]
+    fn notes_are_written_as_csv_and_read_out() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        let meal = order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap()
+            .get_id();
+        order
+            .set_note_for_user(Id::new(0), meal, Some(String::from("cut into 8 slices")))
+            .unwrap();
+        let sheet = CallSheet::from_order(&order);
+
+        // When:
+        let appendix = sheet.appendix_to_csv();
+        let text = sheet.to_plain_text(MoneyFormat::default());
+
+        // Then:
+        assert_eq!(
+            appendix,
+            "user_id,meal_id,variety,specials,preparation,note,price\n\
+             0,03,groß,,,cut into 8 slices,5.50\n"
+        );
+        assert!(text.starts_with("1x 03 groß \"cut into 8 slices\" - 5,50€\n"));
+    }
+
+    /// Counts what is written without keeping it, remembering the largest single write.
+    #[derive(Default)]
+    struct CountingWriter {
+        lines: usize,
+        largest_write: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.lines += buf.iter().filter(|byte| **byte == b'\n').count();
+            self.largest_write = self.largest_write.max(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn call_sheet_of_thousand_participants_is_streamed() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        for user_id in 0..1000 {
+            // The manager with ID 0 takes part from the start
+            if user_id > 0 {
+                order.add_user(Id::new(user_id)).unwrap();
+            }
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    format!("{:02}", user_id % 40),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        let sheet = CallSheet::from_order(&order);
+        let mut appendix = CountingWriter::default();
+        let mut text = CountingWriter::default();
+
+        // When:
+        sheet.write_appendix_csv(&mut appendix).unwrap();
+        sheet
+            .write_plain_text(MoneyFormat::default(), &mut text)
+            .unwrap();
+
+        // Then:
+        assert_eq!(sheet.lines().len(), 40);
+        assert_eq!(sheet.get_total_price(), Money::new(5500, 0));
+        assert_eq!(appendix.lines, 1001);
+        assert_eq!(text.lines, 40 + 2 + 1000);
+        assert!(appendix.largest_write < 100);
+        assert!(text.largest_write < 100);
+    }
+
+    #[test]
+    fn users_without_meals_are_left_out() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let sheet = CallSheet::from_order(&order);
+
+        // Then:
+        assert!(sheet.appendix().is_empty());
+        assert_eq!(
+            sheet.to_plain_text(MoneyFormat::default()),
+            "Total: 0,00€\n"
+        );
+    }
+}
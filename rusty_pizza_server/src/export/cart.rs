@@ -0,0 +1,202 @@
+use crate::export::consolidation::consolidate;
+use crate::order_model::order::Order;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CartError {
+    /// Meal numbers the delivery platform has no item for
+    UnmappedMeals(Vec<String>),
+}
+
+impl fmt::Display for CartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use CartError::*;
+        match self {
+            UnmappedMeals(meal_ids) => write!(
+                f,
+                "Meals not known by the delivery platform: {}",
+                meal_ids.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for CartError {}
+
+/// Item ids of the delivery platform for the meal numbers of the restaurant's menu.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlatformMenuMapping {
+    /// Platform item id by meal number
+    item_ids: HashMap<String, String>,
+}
+
+impl PlatformMenuMapping {
+    pub fn new() -> PlatformMenuMapping {
+        PlatformMenuMapping::default()
+    }
+
+    /// Maps the meal number to the given platform item id and returns the id it replaces, if any.
+    pub fn map(&mut self, meal_id: String, item_id: String) -> Option<String> {
+        self.item_ids.insert(meal_id, item_id)
+    }
+
+    pub fn get_item_id(&self, meal_id: &str) -> Option<&String> {
+        self.item_ids.get(meal_id)
+    }
+}
+
+/// One line of the cart as the delivery platform expects it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CartItem {
+    pub item_id: String,
+    pub variety: String,
+    pub quantity: u32,
+    /// Descriptions of the specials, followed by the preparations
+    pub options: Vec<String>,
+}
+
+/// Cart of the delivery platform containing all meals of an order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Cart {
+    pub items: Vec<CartItem>,
+}
+
+impl Cart {
+    /// Builds the cart for the whole order, merging identical meals into one item.
+    ///
+    /// Fails without a partial cart if any meal has no platform item, so nothing is submitted incompletely.
+    pub fn from_order(order: &Order, mapping: &PlatformMenuMapping) -> Result<Cart, CartError> {
+        let mut items = Vec::new();
+        let mut unmapped = Vec::new();
+        for line in consolidate(order.all_meals()) {
+            match mapping.get_item_id(line.get_meal_id()) {
+                Some(item_id) => items.push(CartItem {
+                    item_id: item_id.clone(),
+                    variety: line.get_variety().clone(),
+                    quantity: line.get_quantity(),
+                    options: line
+                        .get_specials()
+                        .iter()
+                        .chain(line.get_preparations())
+                        .cloned()
+                        .collect(),
+                }),
+                None => unmapped.push(line.get_meal_id().clone()),
+            }
+        }
+        if !unmapped.is_empty() {
+            unmapped.dedup();
+            return Err(CartError::UnmappedMeals(unmapped));
+        }
+        Ok(Cart { items })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Cart is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::id::Id;
+    use crate::util::money::Money;
+
+    fn order_with_pizzas_and_salad() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        for user_id in 0..2 {
+            let meal = order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap()
+                .get_id();
+            order
+                .add_special_for_user(Id::new(user_id), meal, String::from("Käserand"), None)
+                .unwrap();
+        }
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn cart_contains_consolidated_meals() {
+        // Given:
+        let order = order_with_pizzas_and_salad();
+        let mut mapping = PlatformMenuMapping::new();
+        mapping.map(String::from("03"), String::from("luigi-margherita"));
+        mapping.map(String::from("61"), String::from("luigi-salat"));
+
+        // When:
+        let cart = Cart::from_order(&order, &mapping);
+
+        // Then:
+        assert_eq!(
+            cart,
+            Ok(Cart {
+                items: vec![
+                    CartItem {
+                        item_id: String::from("luigi-margherita"),
+                        variety: String::from("groß"),
+                        quantity: 2,
+                        options: vec![String::from("Käserand")],
+                    },
+                    CartItem {
+                        item_id: String::from("luigi-salat"),
+                        variety: String::from("Salat"),
+                        quantity: 1,
+                        options: vec![],
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn cart_with_unmapped_meals_is_rejected() {
+        // Given:
+        let order = order_with_pizzas_and_salad();
+        let mut mapping = PlatformMenuMapping::new();
+        mapping.map(String::from("61"), String::from("luigi-salat"));
+
+        // When:
+        let cart = Cart::from_order(&order, &mapping);
+
+        // Then:
+        assert_eq!(
+            cart,
+            Err(CartError::UnmappedMeals(vec![String::from("03")]))
+        );
+    }
+
+    #[test]
+    fn cart_is_exported_as_json() {
+        // Given:
+        let cart = Cart {
+            items: vec![CartItem {
+                item_id: String::from("luigi-salat"),
+                variety: String::from("Salat"),
+                quantity: 1,
+                options: vec![String::from("Essig")],
+            }],
+        };
+
+        // When:
+        let json = cart.to_json();
+
+        // Then:
+        assert_eq!(
+            json,
+            r#"{"items":[{"item_id":"luigi-salat","variety":"Salat","quantity":1,"options":["Essig"]}]}"#
+        );
+    }
+}
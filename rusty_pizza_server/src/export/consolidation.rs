@@ -0,0 +1,275 @@
+use crate::order_model::meal::Meal;
+use crate::order_model::preparation::Preparation;
+use crate::util::money::Money;
+use std::collections::BTreeMap;
+
+/// Identical meals merged into one line, as they are ordered at the restaurant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsolidatedMeal {
+    /// Number of the meal in the menu
+    meal_id: String,
+    variety: String,
+    /// Sorted descriptions of the specials
+    specials: Vec<String>,
+    /// How the kitchen should prepare the meals, as they read it
+    preparations: Vec<String>,
+    note: Option<String>,
+    quantity: u32,
+    /// Price of all meals of this line together
+    total_price: Money,
+}
+
+impl ConsolidatedMeal {
+    pub fn new(
+        meal_id: String,
+        variety: String,
+        specials: Vec<String>,
+        preparations: Vec<String>,
+        note: Option<String>,
+        quantity: u32,
+        total_price: Money,
+    ) -> ConsolidatedMeal {
+        ConsolidatedMeal {
+            meal_id,
+            variety,
+            specials,
+            preparations,
+            note,
+            quantity,
+            total_price,
+        }
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    pub fn get_specials(&self) -> &Vec<String> {
+        &self.specials
+    }
+
+    pub fn get_preparations(&self) -> &Vec<String> {
+        &self.preparations
+    }
+
+    pub fn get_note(&self) -> Option<&String> {
+        self.note.as_ref()
+    }
+
+    pub fn get_quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    pub fn get_total_price(&self) -> Money {
+        self.total_price
+    }
+}
+
+/// Meal number, variety, specials, preparations and note of a line.
+type LineKey = (String, String, Vec<String>, Vec<String>, Option<String>);
+
+/// Merges meals with equal meal number, variety, specials, preparations and note, sorted by these.
+pub fn consolidate<'a>(meals: impl Iterator<Item = &'a Meal>) -> Vec<ConsolidatedMeal> {
+    let mut lines: BTreeMap<LineKey, (u32, Money)> = BTreeMap::new();
+    for meal in meals {
+        let mut specials: Vec<String> = meal
+            .specials()
+            .map(|special| special.get_description())
+            .collect();
+        specials.sort();
+        let preparations = meal
+            .get_preparations()
+            .iter()
+            .map(Preparation::to_string)
+            .collect();
+        let line = lines
+            .entry((
+                meal.get_meal_id().clone(),
+                meal.get_variety().clone(),
+                specials,
+                preparations,
+                meal.get_note().cloned(),
+            ))
+            .or_insert((0, Money::zero()));
+        line.0 += 1;
+        line.1 += meal.get_total_price();
+    }
+    lines
+        .into_iter()
+        .map(
+            |((meal_id, variety, specials, preparations, note), (quantity, total_price))| {
+                ConsolidatedMeal::new(
+                    meal_id,
+                    variety,
+                    specials,
+                    preparations,
+                    note,
+                    quantity,
+                    total_price,
+                )
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::meal::MealFactory;
+
+    #[test]
+    fn equal_meals_are_merged() {
+        // Given:
+        let mut meal_factory = MealFactory::new();
+        let mut meals = Vec::new();
+        for specials in [
+            vec!["Knoblauch", "Salami"],
+            vec!["Salami", "Knoblauch"],
+            vec![],
+        ] {
+            let mut meal = meal_factory.create_meal(
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            );
+            for special in specials {
+                meal.add_special(String::from(special));
+            }
+            meals.push(meal);
+        }
+        meals.push(meal_factory.create_meal(
+            String::from("03"),
+            String::from("klein"),
+            Money::new(4, 50),
+        ));
+
+        // When:
+        let consolidated = consolidate(meals.iter());
+
+        // Then:
+        assert_eq!(
+            consolidated,
+            vec![
+                ConsolidatedMeal::new(
+                    String::from("03"),
+                    String::from("groß"),
+                    vec![],
+                    vec![],
+                    None,
+                    1,
+                    Money::new(5, 50)
+                ),
+                ConsolidatedMeal::new(
+                    String::from("03"),
+                    String::from("groß"),
+                    vec![String::from("Knoblauch"), String::from("Salami")],
+                    vec![],
+                    None,
+                    2,
+                    Money::new(11, 0)
+                ),
+                ConsolidatedMeal::new(
+                    String::from("03"),
+                    String::from("klein"),
+                    vec![],
+                    vec![],
+                    None,
+                    1,
+                    Money::new(4, 50)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn meals_prepared_differently_are_not_merged() {
+        // Given:
+        let mut meal_factory = MealFactory::new();
+        let mut meals = Vec::new();
+        for well_done in [true, false, true] {
+            let mut meal = meal_factory.create_meal(
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            );
+            if well_done {
+                meal.set_preparations(
+                    vec![Preparation::WellDone, Preparation::GlutenFreeBase]
+                        .into_iter()
+                        .collect(),
+                );
+            }
+            meals.push(meal);
+        }
+
+        // When:
+        let consolidated = consolidate(meals.iter());
+
+        // Then:
+        assert_eq!(
+            consolidated,
+            vec![
+                ConsolidatedMeal::new(
+                    String::from("03"),
+                    String::from("groß"),
+                    vec![],
+                    vec![],
+                    None,
+                    1,
+                    Money::new(5, 50)
+                ),
+                ConsolidatedMeal::new(
+                    String::from("03"),
+                    String::from("groß"),
+                    vec![],
+                    vec![String::from("well done"), String::from("gluten-free base")],
+                    None,
+                    2,
+                    Money::new(11, 0)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn meals_with_different_notes_are_not_merged() {
+        // Given:
+        let mut meal_factory = MealFactory::new();
+        let mut meals = Vec::new();
+        for note in [Some("cut into 8 slices"), None, None] {
+            let mut meal = meal_factory.create_meal(
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            );
+            meal.set_note(note.map(String::from));
+            meals.push(meal);
+        }
+
+        // When:
+        let consolidated = consolidate(meals.iter());
+
+        // Then:
+        assert_eq!(consolidated.len(), 2);
+        assert_eq!(consolidated[0].get_note(), None);
+        assert_eq!(consolidated[0].get_quantity(), 2);
+        assert_eq!(
+            consolidated[1].get_note(),
+            Some(&String::from("cut into 8 slices"))
+        );
+        assert_eq!(consolidated[1].get_quantity(), 1);
+    }
+
+    #[test]
+    fn no_meals_are_consolidated_to_nothing() {
+        // When:
+        let consolidated = consolidate(Vec::<Meal>::new().iter());
+
+        // Then:
+        assert!(consolidated.is_empty());
+    }
+}
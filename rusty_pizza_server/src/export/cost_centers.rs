@@ -0,0 +1,272 @@
+use crate::export::csv::{csv_field, decimal};
+use crate::order_model::order::Order;
+use crate::order_model::tax::{TaxBreakdown, TaxRate};
+use crate::user_model::repository::UserRepository;
+use crate::util::money::Money;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// What was spent on behalf of one cost center.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CostCenterAmounts {
+    /// Gross prices of the meals by tax rate, `None` for meals without one
+    meal_prices: BTreeMap<Option<TaxRate>, Money>,
+    /// Shares of delivery fees and shared meals
+    shares: Money,
+    tips: Money,
+}
+
+impl Default for CostCenterAmounts {
+    fn default() -> CostCenterAmounts {
+        CostCenterAmounts {
+            meal_prices: BTreeMap::new(),
+            shares: Money::zero(),
+            tips: Money::zero(),
+        }
+    }
+}
+
+impl CostCenterAmounts {
+    /// Net amounts and taxes of the meals, as needed for the expense report.
+    pub fn tax_breakdown(&self) -> TaxBreakdown {
+        TaxBreakdown::calculate(self.meal_prices.iter().map(|(rate, gross)| (*rate, *gross)))
+    }
+
+    pub fn get_shares(&self) -> Money {
+        self.shares
+    }
+
+    pub fn get_tips(&self) -> Money {
+        self.tips
+    }
+
+    /// Everything to re-bill: the meals, the shares and the tips.
+    pub fn get_total(&self) -> Money {
+        self.meal_prices
+            .values()
+            .fold(self.shares + self.tips, |total, gross| total + *gross)
+    }
+
+    fn add_meal(&mut self, rate: Option<TaxRate>, gross: Money) {
+        *self.meal_prices.entry(rate).or_insert_with(Money::zero) += gross;
+    }
+}
+
+/// Amounts of one or more orders grouped by cost center, so finance can re-bill e.g. department lunches.
+///
+/// The meals of a participant go to the cost center of the order if it has one, otherwise to the one of the
+/// participant. Office meals only go to the cost center of the order. Everything else is untagged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CostCenterReport {
+    /// `None` for the untagged amounts, which come first
+    cost_centers: BTreeMap<Option<String>, CostCenterAmounts>,
+}
+
+impl CostCenterReport {
+    pub fn new() -> CostCenterReport {
+        CostCenterReport::default()
+    }
+
+    /// Report of the orders, which should all be in the same currency.
+    pub fn from_orders<'a>(
+        orders: impl IntoIterator<Item = &'a Order>,
+        users: &UserRepository,
+    ) -> CostCenterReport {
+        let mut report = CostCenterReport::new();
+        for order in orders {
+            report.add_order(order, users);
+        }
+        report
+    }
+
+    pub fn add_order(&mut self, order: &Order, users: &UserRepository) {
+        let payments = order.payment_report();
+        for payment in payments.users() {
+            let user_id = payment.get_user_id();
+            let cost_center = order
+                .get_cost_center()
+                .or_else(|| users.get_cost_center(&user_id))
+                .cloned();
+            let amounts = self.cost_centers.entry(cost_center).or_default();
+            if let Some(meals) = order.get_user_meals(&user_id) {
+                for meal in meals.meals() {
+                    amounts.add_meal(meal.get_tax_rate(), meal.get_total_price());
+                }
+            }
+            amounts.shares += payment.get_fee_share() + payment.get_shared_share();
+            amounts.tips += payment.get_tip();
+        }
+        let mut office_meals = order.office_meals().peekable();
+        if office_meals.peek().is_some() {
+            let amounts = self
+                .cost_centers
+                .entry(order.get_cost_center().cloned())
+                .or_default();
+            for meal in office_meals {
+                amounts.add_meal(meal.get_tax_rate(), meal.get_total_price());
+            }
+        }
+    }
+
+    /// Amounts by cost center, the untagged ones first.
+    pub fn cost_centers(&self) -> &BTreeMap<Option<String>, CostCenterAmounts> {
+        &self.cost_centers
+    }
+
+    pub fn get_cost_center(&self, cost_center: &str) -> Option<&CostCenterAmounts> {
+        self.cost_centers.get(&Some(String::from(cost_center)))
+    }
+
+    pub fn get_untagged(&self) -> Option<&CostCenterAmounts> {
+        self.cost_centers.get(&None)
+    }
+
+    /// Renders one row per cost center as CSV with a header row, the cost center being empty for untagged amounts.
+    pub fn to_csv(&self) -> String {
+        let mut out = Vec::new();
+        self.write_csv(&mut out)
+            .expect("Writing to a Vec never fails");
+        String::from_utf8(out).expect("CSV is always UTF-8")
+    }
+
+    /// Streams the CSV of `to_csv` to `out` line by line.
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "cost_center,net,tax,shares,tips,total")?;
+        for (cost_center, amounts) in &self.cost_centers {
+            let breakdown = amounts.tax_breakdown();
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                csv_field(cost_center.as_deref().unwrap_or("")),
+                decimal(breakdown.get_net()),
+                decimal(breakdown.get_tax()),
+                decimal(amounts.shares),
+                decimal(amounts.tips),
+                decimal(amounts.get_total())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::catalog::Menu;
+    use crate::menu::item::MenuItem;
+    use crate::order_model::fee::FeeSplitStrategy;
+    use crate::util::id::Id;
+    use std::sync::Arc;
+
+    fn users() -> UserRepository {
+        let mut users = UserRepository::new();
+        for name in &["Anna", "Ben", "Clara"] {
+            users.register(String::from(*name)).unwrap();
+        }
+        users.set_cost_center(&Id::new(1), Some(String::from("Sales, EU")));
+        users.set_cost_center(&Id::new(2), Some(String::from("IT")));
+        users
+    }
+
+    fn menu() -> Menu {
+        let mut pizza = MenuItem::new(String::from("03"), String::from("Salami"));
+        pizza.set_price(String::from("groß"), Money::new(10, 70));
+        pizza.set_tax_rate(Some(TaxRate::percent(7)));
+        let mut salad = MenuItem::new(String::from("61"), String::from("Salat"));
+        salad.set_price(String::from("Salat"), Money::new(4, 0));
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+        menu.add_item(pizza);
+        menu.add_item(salad);
+        menu
+    }
+
+    fn order() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(Arc::new(menu()));
+        for user_id in 1..3 {
+            order.add_user(Id::new(user_id)).unwrap();
+        }
+        for user_id in 0..3 {
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(10, 70),
+                )
+                .unwrap();
+        }
+        order
+            .set_delivery_fee(Money::new(3, 0), FeeSplitStrategy::Equal)
+            .unwrap();
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn amounts_are_grouped_by_cost_center_of_participant() {
+        // Given:
+        let users = users();
+        let order = order();
+
+        // When:
+        let report = CostCenterReport::from_orders(vec![&order, &order], &users);
+
+        // Then:
+        assert_eq!(
+            report.cost_centers().keys().collect::<Vec<_>>(),
+            vec![
+                &None,
+                &Some(String::from("IT")),
+                &Some(String::from("Sales, EU"))
+            ]
+        );
+        let it = report.get_cost_center("IT").unwrap();
+        assert_eq!(it.tax_breakdown().get_net(), Money::new(20, 0));
+        assert_eq!(it.tax_breakdown().get_tax(), Money::new(1, 40));
+        assert_eq!(it.get_shares(), Money::new(2, 0));
+        assert_eq!(it.get_total(), Money::new(23, 40));
+        assert_eq!(
+            report.get_untagged().unwrap().get_total(),
+            Money::new(31, 40)
+        );
+    }
+
+    #[test]
+    fn cost_center_of_order_overrides_participants() {
+        // Given:
+        let users = users();
+        let mut order = order();
+        order.set_cost_center(Some(String::from("Marketing")));
+
+        // When:
+        let report = CostCenterReport::from_orders(vec![&order], &users);
+
+        // Then:
+        assert_eq!(report.cost_centers().len(), 1);
+        assert_eq!(
+            report.get_cost_center("Marketing").unwrap().get_total(),
+            order.calculate_total_price()
+        );
+    }
+
+    #[test]
+    fn report_is_written_as_csv() {
+        // Given:
+        let report = CostCenterReport::from_orders(vec![&order()], &users());
+
+        // When:
+        let csv = report.to_csv();
+
+        // Then:
+        assert_eq!(
+            csv,
+            "cost_center,net,tax,shares,tips,total\n\
+             ,14.00,0.70,1.00,0.00,15.70\n\
+             IT,10.00,0.70,1.00,0.00,11.70\n\
+             \"Sales, EU\",10.00,0.70,1.00,0.00,11.70\n"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use crate::util::money::Money;
+
+/// The amount with a decimal point and without currency, e.g. "7.50", as spreadsheets expect it.
+pub fn decimal(money: Money) -> String {
+    format!("{}.{:02}", money.get_euros(), money.get_cents())
+}
+
+/// Quotes the field if it contains characters with a meaning in CSV.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        field,
+        expected,
+        case("Käserand", "Käserand"),
+        case("scharf, ohne Zwiebeln", "\"scharf, ohne Zwiebeln\""),
+        case("\"extra\" scharf", "\"\"\"extra\"\" scharf\""),
+        case("erste\nzweite", "\"erste\nzweite\""),
+        case("erste\r\nzweite", "\"erste\r\nzweite\""),
+        case("erste\rzweite", "\"erste\rzweite\"")
+    )]
+    fn fields_with_special_characters_are_quoted(field: &str, expected: &str) {
+        // When:
+        let quoted = csv_field(field);
+
+        // Then:
+        assert_eq!(quoted, expected);
+    }
+
+    #[test]
+    fn amounts_have_two_decimal_places() {
+        // When:
+        let amount = decimal(Money::new(7, 5));
+
+        // Then:
+        assert_eq!(amount, "7.05");
+    }
+}
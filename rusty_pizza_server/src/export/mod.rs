@@ -0,0 +1,6 @@
+pub mod call_sheet;
+pub mod cart;
+pub mod consolidation;
+pub mod cost_centers;
+pub mod csv;
+pub mod summary;
@@ -0,0 +1,111 @@
+use crate::order_model::order::Order;
+use crate::order_model::report::Balance;
+use crate::util::locale::MoneyFormat;
+
+/// Short sentence describing the state of the order, e.g. for screen readers:
+/// "7 people, 9 meals, 61,40€ total, 3 people still owe money."
+///
+/// The total is written in the given `format`, usually `Order::get_money_format`.
+pub fn plain_summary(order: &Order, format: MoneyFormat) -> String {
+    let report = order.payment_report();
+    let people = report.users().len();
+    let meals = order.all_meals().count();
+    let owing = report
+        .users()
+        .iter()
+        .filter(|user| matches!(user.get_balance(), Balance::Owed(_)))
+        .count();
+    let payment_state = match owing {
+        0 => String::from("everybody has paid"),
+        1 => String::from("1 person still owes money"),
+        owing => format!("{} people still owe money", owing),
+    };
+    format!(
+        "{}, {}, {} total, {}.",
+        count(people, "person", "people"),
+        count(meals, "meal", "meals"),
+        format.format(report.get_total_price()),
+        payment_state
+    )
+}
+
+fn count(amount: usize, singular: &str, plural: &str) -> String {
+    if amount == 1 {
+        format!("1 {}", singular)
+    } else {
+        format!("{} {}", amount, plural)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::id::Id;
+    use crate::util::locale::{Currency, Locale};
+    use crate::util::money::Money;
+
+    #[test]
+    fn summary_describes_people_meals_and_debts() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        for user_id in 1..3 {
+            order.add_user(Id::new(user_id)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        order
+            .set_paid_for_user(Id::new(1), Money::new(5, 50))
+            .unwrap();
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 5))
+            .unwrap();
+
+        // When:
+        let summary = plain_summary(&order, MoneyFormat::default());
+
+        // Then:
+        assert_eq!(
+            summary,
+            "3 people, 3 meals, 15,05€ total, 1 person still owes money."
+        );
+    }
+
+    #[test]
+    fn summary_uses_money_format() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 5))
+            .unwrap();
+
+        // When:
+        let summary = plain_summary(&order, MoneyFormat::new(Currency::Chf, Locale::DeCh));
+
+        // Then:
+        assert_eq!(
+            summary,
+            "1 person, 1 meal, CHF 4.05 total, everybody has paid."
+        );
+    }
+
+    #[test]
+    fn summary_of_new_order_uses_singular() {
+        // Given:
+        let order = Order::new(Id::new(0));
+
+        // When:
+        let summary = plain_summary(&order, MoneyFormat::default());
+
+        // Then:
+        assert_eq!(
+            summary,
+            "1 person, 0 meals, 0,00€ total, everybody has paid."
+        );
+    }
+}
@@ -0,0 +1,2 @@
+// Generated from proto/rusty_pizza.proto by the build script
+tonic::include_proto!("rusty_pizza");
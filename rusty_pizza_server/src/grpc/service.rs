@@ -0,0 +1,454 @@
+use crate::api::caller::Caller;
+use crate::api::error::ApiError;
+use crate::api::routes::check_meals_version;
+use crate::api::state::AppState;
+use crate::api::v1::dto::TotalsResponse;
+use crate::auth::role::require_owner;
+use crate::grpc::proto::order_event::Kind;
+use crate::grpc::proto::pizza_server::{Pizza, PizzaServer};
+use crate::grpc::proto::{
+    self, AddMealRequest, AddMealResponse, AddUserRequest, AddUserResponse, CreateOrderRequest,
+    CreateOrderResponse, GetTotalsRequest, StreamEventsRequest, Totals,
+};
+use crate::notifications::event::OrderEvent;
+use crate::order_model::order::Order;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use axum::http::{HeaderMap, StatusCode};
+use std::pin::Pin;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Code, Request, Response, Status};
+
+/// Serves the gRPC interface on the same state as the REST API, so changes are visible in both.
+#[derive(Clone, Debug)]
+pub struct PizzaService {
+    state: AppState,
+}
+
+impl PizzaService {
+    pub fn new(state: AppState) -> PizzaService {
+        PizzaService { state }
+    }
+
+    /// Wraps the service for `tonic::transport::Server::add_service`.
+    pub fn into_server(self) -> PizzaServer<PizzaService> {
+        PizzaServer::new(self)
+    }
+
+    fn with_order<T>(
+        &self,
+        order_id: u32,
+        f: impl FnOnce(&mut Order) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        let id = Id::new(order_id);
+        let mut orders = self.state.orders();
+        let order = orders.get_order(&id).ok_or(ApiError::OrderNotFound)?;
+        let result = f(order)?;
+        self.state.summaries().update(&id, order);
+        Ok(result)
+    }
+}
+
+/// Metadata of the request as HTTP headers, so the session token and `If-Match` are sent like on the REST API.
+fn headers<T>(request: &Request<T>) -> HeaderMap {
+    request.metadata().clone().into_headers()
+}
+
+/// Uses the gRPC code closest to the HTTP status of the REST API.
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Status {
+        let code = match error.get_status_code() {
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::CONFLICT => Code::FailedPrecondition,
+            StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            _ => Code::Internal,
+        };
+        Status::new(code, error.to_string())
+    }
+}
+
+impl From<OrderEvent> for proto::OrderEvent {
+    fn from(event: OrderEvent) -> proto::OrderEvent {
+        let order_id = event.get_order_id();
+        let kind = match event {
+            OrderEvent::UserJoined { user_id, .. } => {
+                Kind::UserJoined(proto::UserJoined { user_id })
+            }
+            OrderEvent::MealAdded {
+                user_id,
+                meal_id,
+                variety,
+                ..
+            } => Kind::MealAdded(proto::MealAdded {
+                user_id,
+                meal_id,
+                variety,
+            }),
+            OrderEvent::StatusChanged { status, .. } => {
+                Kind::StatusChanged(proto::StatusChanged { status })
+            }
+            OrderEvent::PaymentRecorded {
+                user_id,
+                amount_cents,
+                ..
+            } => Kind::PaymentRecorded(proto::PaymentRecorded {
+                user_id,
+                amount_cents,
+            }),
+            OrderEvent::EtaPassed { eta, .. } => Kind::EtaPassed(proto::EtaPassed { eta }),
+        };
+        proto::OrderEvent {
+            order_id,
+            kind: Some(kind),
+        }
+    }
+}
+
+impl From<TotalsResponse> for Totals {
+    fn from(totals: TotalsResponse) -> Totals {
+        Totals {
+            price_cents: totals.price_cents,
+            office_price_cents: totals.office_price_cents,
+            tip_cents: totals.tip_cents,
+            change_cents: totals.change_cents,
+            underpaid_cents: totals.underpaid_cents,
+            paid_less: totals.paid_less,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Pizza for PizzaService {
+    async fn create_order(
+        &self,
+        request: Request<CreateOrderRequest>,
+    ) -> Result<Response<CreateOrderResponse>, Status> {
+        let caller = Caller::from_headers(&self.state, &headers(&request))?;
+        let request = request.into_inner();
+        let manager_id = Id::new(request.manager_id);
+        caller.authorize(&self.state, |user_id| require_owner(&manager_id, user_id))?;
+        let currency = request
+            .currency
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(ApiError::from)?;
+        let locale = request
+            .locale
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(ApiError::from)?;
+        let mut orders = self.state.orders();
+        let (id, duplicate_of) = match request.restaurant {
+            Some(restaurant) => {
+                let created = orders
+                    .create_order_for_restaurant(manager_id, restaurant)
+                    .map_err(ApiError::from)?;
+                (created.id, created.duplicate_of)
+            }
+            None => (orders.create_order(manager_id), None),
+        };
+        let order = orders.get_order(&id).expect("Order was just created");
+        order.set_currency(currency).map_err(ApiError::from)?;
+        order.set_locale(locale);
+        self.state.announcer().announce_opened(&id, order);
+        self.state.summaries().update(&id, order);
+        Ok(Response::new(CreateOrderResponse {
+            order_id: id.get_value(),
+            duplicate_of: duplicate_of.as_ref().map(Id::get_value),
+        }))
+    }
+
+    async fn add_user(
+        &self,
+        request: Request<AddUserRequest>,
+    ) -> Result<Response<AddUserResponse>, Status> {
+        let caller = Caller::from_headers(&self.state, &headers(&request))?;
+        let request = request.into_inner();
+        caller.authorize(&self.state, |caller_id| {
+            require_owner(&Id::new(request.user_id), caller_id)
+        })?;
+        self.with_order(request.order_id, |order| {
+            order.add_user(Id::new(request.user_id))?;
+            self.state.events().publish(OrderEvent::UserJoined {
+                order_id: request.order_id,
+                user_id: request.user_id,
+            });
+            Ok(Response::new(AddUserResponse {}))
+        })
+        .map_err(Status::from)
+    }
+
+    async fn add_meal(
+        &self,
+        request: Request<AddMealRequest>,
+    ) -> Result<Response<AddMealResponse>, Status> {
+        let headers = headers(&request);
+        let caller = Caller::from_headers(&self.state, &headers)?;
+        let request = request.into_inner();
+        let actor = caller.actor(&self.state, &Id::new(request.user_id))?;
+        self.with_order(request.order_id, |order| {
+            check_meals_version(&headers, order, request.user_id)?;
+            let meal = order.add_meal_as(
+                &actor,
+                Id::new(request.user_id),
+                request.meal_id,
+                request.variety,
+                Money::from_cents(request.price_cents),
+            )?;
+            self.state.events().publish(OrderEvent::MealAdded {
+                order_id: request.order_id,
+                user_id: Some(request.user_id),
+                meal_id: meal.get_meal_id().clone(),
+                variety: meal.get_variety().clone(),
+            });
+            Ok(Response::new(AddMealResponse {
+                meal_id: meal.get_id().get_value(),
+            }))
+        })
+        .map_err(Status::from)
+    }
+
+    async fn get_totals(
+        &self,
+        request: Request<GetTotalsRequest>,
+    ) -> Result<Response<Totals>, Status> {
+        self.with_order(request.into_inner().order_id, |order| {
+            Ok(Response::new(Totals::from(TotalsResponse::from(&*order))))
+        })
+        .map_err(Status::from)
+    }
+
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<proto::OrderEvent, Status>> + Send>>;
+
+    async fn stream_events(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let order_id = request.into_inner().order_id;
+        self.with_order(order_id, |_| Ok(()))?;
+        // Subscribe before responding, so no event published afterwards is missed
+        let events = BroadcastStream::new(self.state.events().subscribe());
+        // Events a slow client missed are skipped, like on the WebSocket
+        let stream = events.filter_map(move |event| match event {
+            Ok(event) if event.get_order_id() == order_id => Some(Ok(event.into())),
+            _ => None,
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service_with_order() -> PizzaService {
+        let state = AppState::new();
+        state.orders().create_order(Id::new(0));
+        PizzaService::new(state)
+    }
+
+    fn with_metadata<T>(message: T, metadata: &[(&'static str, &str)]) -> Request<T> {
+        let mut request = Request::new(message);
+        for (key, value) in metadata {
+            request.metadata_mut().insert(*key, value.parse().unwrap());
+        }
+        request
+    }
+
+    fn meal_for(user_id: u32) -> AddMealRequest {
+        AddMealRequest {
+            order_id: 0,
+            user_id,
+            meal_id: String::from("03"),
+            variety: String::from("groß"),
+            price_cents: 750,
+        }
+    }
+
+    #[tokio::test]
+    async fn meals_added_via_grpc_show_up_in_totals() {
+        // Given:
+        let service = service_with_order();
+        service
+            .add_user(Request::new(AddUserRequest {
+                order_id: 0,
+                user_id: 1,
+            }))
+            .await
+            .unwrap();
+
+        // When:
+        let meal = service
+            .add_meal(Request::new(AddMealRequest {
+                order_id: 0,
+                user_id: 1,
+                meal_id: String::from("03"),
+                variety: String::from("groß"),
+                price_cents: 750,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let totals = service
+            .get_totals(Request::new(GetTotalsRequest { order_id: 0 }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // Then:
+        assert_eq!(meal.meal_id, 0);
+        assert_eq!(totals.price_cents, 750);
+        assert_eq!(totals.underpaid_cents, Some(750));
+        assert_eq!(totals.paid_less, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn orders_created_via_grpc_are_shared_with_rest() {
+        // Given:
+        let state = AppState::new();
+        let service = PizzaService::new(state.clone());
+
+        // When:
+        let created = service
+            .create_order(Request::new(CreateOrderRequest {
+                manager_id: 4,
+                restaurant: None,
+                currency: Some(String::from("CHF")),
+                locale: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // Then:
+        let mut orders = state.orders();
+        let order = orders.get_order(&Id::new(created.order_id)).unwrap();
+        assert!(order.is_participating(&Id::new(4)));
+    }
+
+    #[tokio::test]
+    async fn errors_are_mapped_to_grpc_codes() {
+        // Given:
+        let service = service_with_order();
+
+        // When:
+        let missing = service
+            .get_totals(Request::new(GetTotalsRequest { order_id: 9 }))
+            .await
+            .unwrap_err();
+        let twice = service
+            .add_user(Request::new(AddUserRequest {
+                order_id: 0,
+                user_id: 0,
+            }))
+            .await
+            .unwrap_err();
+
+        // Then:
+        assert_eq!(missing.code(), Code::NotFound);
+        assert_eq!(twice.code(), Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn events_of_order_are_streamed() {
+        // Given:
+        let service = service_with_order();
+        service.state.orders().create_order(Id::new(0));
+        let mut events = service
+            .stream_events(Request::new(StreamEventsRequest { order_id: 1 }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // When:
+        for order_id in 0..2 {
+            service
+                .add_user(Request::new(AddUserRequest {
+                    order_id,
+                    user_id: 7,
+                }))
+                .await
+                .unwrap();
+        }
+
+        // Then:
+        assert_eq!(
+            events.next().await.unwrap().unwrap(),
+            proto::OrderEvent {
+                order_id: 1,
+                kind: Some(Kind::UserJoined(proto::UserJoined { user_id: 7 })),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn changes_via_grpc_require_session_of_user() {
+        // Given:
+        let state = AppState::new().with_required_authentication();
+        state.orders().create_order(Id::new(0));
+        let ben = format!("Bearer {}", state.auth().start_session(Id::new(1)));
+        let service = PizzaService::new(state);
+        let join = |user_id| AddUserRequest {
+            order_id: 0,
+            user_id,
+        };
+
+        // When:
+        let anonymous = service.add_user(Request::new(join(1))).await.unwrap_err();
+        let other_user = service
+            .add_user(with_metadata(join(2), &[("authorization", &ben)]))
+            .await
+            .unwrap_err();
+        let joined = service
+            .add_user(with_metadata(join(1), &[("authorization", &ben)]))
+            .await;
+        let other_meal = service
+            .add_meal(with_metadata(meal_for(0), &[("authorization", &ben)]))
+            .await
+            .unwrap_err();
+        let other_manager = service
+            .create_order(with_metadata(
+                CreateOrderRequest {
+                    manager_id: 0,
+                    restaurant: None,
+                    currency: None,
+                    locale: None,
+                },
+                &[("authorization", &ben)],
+            ))
+            .await
+            .unwrap_err();
+
+        // Then:
+        assert_eq!(anonymous.code(), Code::Unauthenticated);
+        assert_eq!(other_user.code(), Code::PermissionDenied);
+        assert!(joined.is_ok());
+        assert_eq!(other_meal.code(), Code::PermissionDenied);
+        assert_eq!(other_manager.code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn stale_meal_via_grpc_is_rejected() {
+        // Given:
+        let service = service_with_order();
+        service.add_meal(Request::new(meal_for(0))).await.unwrap();
+
+        // When:
+        let stale = service
+            .add_meal(with_metadata(meal_for(0), &[("if-match", "1")]))
+            .await
+            .unwrap_err();
+        let current = service
+            .add_meal(with_metadata(meal_for(0), &[("if-match", "2")]))
+            .await;
+
+        // Then:
+        assert_eq!(stale.code(), Code::FailedPrecondition);
+        assert!(current.is_ok());
+    }
+}
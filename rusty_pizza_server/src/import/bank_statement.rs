@@ -0,0 +1,322 @@
+use crate::import::spreadsheet::split_record;
+use crate::util::decimal::DecimalError;
+use crate::util::money::Money;
+use chrono::NaiveDate;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::error::Error;
+use std::fmt;
+
+/// Header of the CSV layout, one row per booking:
+///
+/// * `date` - booking day as "2020-05-04"
+/// * `amount` - decimal like "7.50", negative for outgoing transfers, which are skipped
+/// * `reference` - text the sender entered, e.g. "Pizza ORD-0039"
+pub const CSV_HEADER: &str = "date,amount,reference";
+
+/// A booking which can't be read. Lines count from 1 including the header, entries of CAMT files from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StatementError {
+    /// The first line is not `CSV_HEADER`
+    WrongHeader(String),
+    WrongColumnCount {
+        line: usize,
+        found: usize,
+    },
+    InvalidDate {
+        line: usize,
+        value: String,
+    },
+    InvalidAmount {
+        line: usize,
+        error: DecimalError,
+    },
+    /// A CAMT entry lacks its amount, booking date or credit/debit indicator
+    IncompleteEntry(usize),
+    /// The CAMT file is not well-formed XML
+    Xml(String),
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use StatementError::*;
+        match self {
+            WrongHeader(header) => {
+                write!(f, "header is \"{}\" instead of \"{}\"", header, CSV_HEADER)
+            }
+            WrongColumnCount { line, found } => {
+                write!(f, "line {}: {} columns instead of 3", line, found)
+            }
+            InvalidDate { line, value } => {
+                write!(f, "line {}: {} is not a date like 2020-05-04", line, value)
+            }
+            InvalidAmount { line, error } => write!(f, "line {}: amount: {}", line, error),
+            IncompleteEntry(entry) => write!(f, "entry {} is incomplete", entry),
+            Xml(error) => write!(f, "statement is not valid XML: {}", error),
+        }
+    }
+}
+
+impl Error for StatementError {}
+
+/// Money that came in on the bank account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    date: NaiveDate,
+    amount: Money,
+    reference: String,
+}
+
+impl Transfer {
+    pub fn new(date: NaiveDate, amount: Money, reference: String) -> Transfer {
+        Transfer {
+            date,
+            amount,
+            reference,
+        }
+    }
+
+    pub fn get_date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn get_amount(&self) -> Money {
+        self.amount
+    }
+
+    pub fn get_reference(&self) -> &String {
+        &self.reference
+    }
+}
+
+/// Reads the incoming transfers of a CSV statement, reporting every invalid row instead of stopping at the first.
+pub fn parse_csv(csv: &str) -> Result<Vec<Transfer>, Vec<StatementError>> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+    let header = lines
+        .next()
+        .map(|(_, line)| line.trim())
+        .unwrap_or_default();
+    if header.to_lowercase().replace(' ', "") != CSV_HEADER {
+        return Err(vec![StatementError::WrongHeader(String::from(header))]);
+    }
+    let mut transfers = Vec::new();
+    let mut errors = Vec::new();
+    for (line, text) in lines.filter(|(_, text)| !text.trim().is_empty()) {
+        match parse_row(line, text) {
+            Ok(Some(transfer)) => transfers.push(transfer),
+            Ok(None) => {}
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        Ok(transfers)
+    } else {
+        Err(errors)
+    }
+}
+
+/// `None` for outgoing transfers.
+fn parse_row(line: usize, text: &str) -> Result<Option<Transfer>, StatementError> {
+    let fields = split_record(text);
+    if fields.len() != 3 {
+        return Err(StatementError::WrongColumnCount {
+            line,
+            found: fields.len(),
+        });
+    }
+    let date = NaiveDate::parse_from_str(&fields[0], "%Y-%m-%d").map_err(|_| {
+        StatementError::InvalidDate {
+            line,
+            value: fields[0].clone(),
+        }
+    })?;
+    match fields[1].parse::<Money>() {
+        Ok(amount) => Ok(Some(Transfer::new(date, amount, fields[2].clone()))),
+        Err(DecimalError::Negative(_)) => Ok(None),
+        Err(error) => Err(StatementError::InvalidAmount { line, error }),
+    }
+}
+
+/// Entry of a CAMT statement while it is read.
+#[derive(Default)]
+struct EntryFields {
+    amount: Option<String>,
+    credit: Option<bool>,
+    date: Option<String>,
+    reference: Option<String>,
+}
+
+/// Reads the credited entries of an ISO 20022 CAMT.053 or CAMT.054 statement.
+///
+/// Only the fields needed for matching are read: `Amt`, `CdtDbtInd`, the `Dt` of `BookgDt` and the first
+/// unstructured remittance information `Ustrd`.
+pub fn parse_camt(xml: &str) -> Result<Vec<Transfer>, StatementError> {
+    let mut reader = Reader::from_str(xml);
+    let mut path: Vec<String> = Vec::new();
+    let mut entries = 0;
+    let mut entry: Option<EntryFields> = None;
+    let mut transfers = Vec::new();
+    loop {
+        match reader
+            .read_event()
+            .map_err(|error| StatementError::Xml(error.to_string()))?
+        {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+                if name == "Ntry" {
+                    entries += 1;
+                    entry = Some(EntryFields::default());
+                }
+                path.push(name);
+            }
+            Event::End(_) if path.last().map(String::as_str) == Some("Ntry") => {
+                path.pop();
+                let fields = entry.take().unwrap_or_default();
+                if let Some(transfer) = to_transfer(entries, fields)? {
+                    transfers.push(transfer);
+                }
+            }
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Text(text) => {
+                if let Some(fields) = entry.as_mut() {
+                    let text = text
+                        .unescape()
+                        .map_err(|error| StatementError::Xml(error.to_string()))?
+                        .trim()
+                        .to_string();
+                    let parent = path.len().checked_sub(2).map(|index| path[index].as_str());
+                    match (parent, path.last().map(String::as_str)) {
+                        (Some("Ntry"), Some("Amt")) => fields.amount = Some(text),
+                        (Some("Ntry"), Some("CdtDbtInd")) => fields.credit = Some(text == "CRDT"),
+                        (Some("BookgDt"), Some("Dt")) => fields.date = Some(text),
+                        (_, Some("Ustrd")) if fields.reference.is_none() => {
+                            fields.reference = Some(text)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(transfers)
+}
+
+/// `None` for debited entries.
+fn to_transfer(number: usize, fields: EntryFields) -> Result<Option<Transfer>, StatementError> {
+    let incomplete = || StatementError::IncompleteEntry(number);
+    if !fields.credit.ok_or_else(incomplete)? {
+        return Ok(None);
+    }
+    let date = fields
+        .date
+        .and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+        .ok_or_else(incomplete)?;
+    let amount = fields
+        .amount
+        .ok_or_else(incomplete)?
+        .parse::<Money>()
+        .map_err(|error| StatementError::InvalidAmount {
+            line: number,
+            error,
+        })?;
+    Ok(Some(Transfer::new(
+        date,
+        amount,
+        fields.reference.unwrap_or_default(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incoming_transfers_are_read_from_csv() {
+        // Given:
+        let csv = "date,amount,reference\n\
+                   2020-05-04,7.50,\"Pizza ORD-0039, Anna\"\n\
+                   2020-05-04,-20.00,Rent\n";
+
+        // When:
+        let transfers = parse_csv(csv);
+
+        // Then:
+        assert_eq!(
+            transfers,
+            Ok(vec![Transfer::new(
+                NaiveDate::from_ymd_opt(2020, 5, 4).unwrap(),
+                Money::new(7, 50),
+                String::from("Pizza ORD-0039, Anna")
+            )])
+        );
+    }
+
+    #[test]
+    fn invalid_csv_rows_are_all_reported() {
+        // Given:
+        let csv = "date,amount,reference\n04.05.2020,7.50,Pizza\n2020-05-04,7.5.0,Pizza\n";
+
+        // When:
+        let errors = parse_csv(csv).unwrap_err();
+
+        // Then:
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0],
+            StatementError::InvalidDate {
+                line: 2,
+                value: String::from("04.05.2020")
+            }
+        );
+    }
+
+    #[test]
+    fn credited_entries_are_read_from_camt() {
+        // Given:
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.04">
+              <BkToCstmrStmt><Stmt>
+                <Ntry>
+                  <Amt Ccy="CHF">7.50</Amt>
+                  <CdtDbtInd>CRDT</CdtDbtInd>
+                  <BookgDt><Dt>2020-05-04</Dt></BookgDt>
+                  <NtryDtls><TxDtls><RmtInf><Ustrd>Pizza ORD-0039</Ustrd></RmtInf></TxDtls></NtryDtls>
+                </Ntry>
+                <Ntry>
+                  <Amt Ccy="CHF">20.00</Amt>
+                  <CdtDbtInd>DBIT</CdtDbtInd>
+                  <BookgDt><Dt>2020-05-04</Dt></BookgDt>
+                </Ntry>
+              </Stmt></BkToCstmrStmt>
+            </Document>"#;
+
+        // When:
+        let transfers = parse_camt(xml);
+
+        // Then:
+        assert_eq!(
+            transfers,
+            Ok(vec![Transfer::new(
+                NaiveDate::from_ymd_opt(2020, 5, 4).unwrap(),
+                Money::new(7, 50),
+                String::from("Pizza ORD-0039")
+            )])
+        );
+    }
+
+    #[test]
+    fn camt_entry_without_date_is_rejected() {
+        // Given:
+        let xml = "<Document><Ntry><Amt>7.50</Amt><CdtDbtInd>CRDT</CdtDbtInd></Ntry></Document>";
+
+        // Then:
+        assert_eq!(parse_camt(xml), Err(StatementError::IncompleteEntry(1)));
+    }
+}
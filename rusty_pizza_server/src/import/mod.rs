@@ -0,0 +1,2 @@
+pub mod bank_statement;
+pub mod spreadsheet;
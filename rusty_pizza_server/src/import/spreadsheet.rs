@@ -0,0 +1,482 @@
+use crate::order_model::manager::OrderManager;
+use crate::order_model::order::Order;
+use crate::order_model::report::Balance;
+use crate::order_model::user::User;
+use crate::settlement::ledger::{settle, Participant, SettlementInput};
+use crate::user_model::repository::UserRepository;
+use crate::util::clock::TestClock;
+use crate::util::decimal::DecimalError;
+use crate::util::id::Id;
+use crate::util::locale::Currency;
+use crate::util::money::Money;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Header of the documented layout, one row per meal:
+///
+/// * `date` - day of the order as "2020-05-04", all rows of a day make up one order
+/// * `user` - name of the participant, unknown names are registered, the first of a day is the manager
+/// * `meal` - meal as ordered, e.g. "03 Margherita"
+/// * `price`, `paid`, `tip` - decimals like "7.50", optionally with a currency code like "7.50 CHF"
+///
+/// `paid` and `tip` of several rows of the same user and day are added up.
+pub const HEADER: &str = "date,user,meal,price,paid,tip";
+
+/// A row which can't be imported, lines count from 1 including the header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    /// The first line is not `HEADER`
+    WrongHeader(String),
+    WrongColumnCount {
+        line: usize,
+        found: usize,
+    },
+    InvalidDate {
+        line: usize,
+        value: String,
+    },
+    EmptyUser {
+        line: usize,
+    },
+    EmptyMeal {
+        line: usize,
+    },
+    InvalidAmount {
+        line: usize,
+        column: &'static str,
+        error: DecimalError,
+    },
+    /// The amount is in another currency than the amounts before
+    MixedCurrency {
+        line: usize,
+        currency: Currency,
+    },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ImportError::*;
+        match self {
+            WrongHeader(header) => write!(f, "header is \"{}\" instead of \"{}\"", header, HEADER),
+            WrongColumnCount { line, found } => {
+                write!(f, "line {}: {} columns instead of 6", line, found)
+            }
+            InvalidDate { line, value } => {
+                write!(f, "line {}: {} is not a date like 2020-05-04", line, value)
+            }
+            EmptyUser { line } => write!(f, "line {}: user is empty", line),
+            EmptyMeal { line } => write!(f, "line {}: meal is empty", line),
+            InvalidAmount {
+                line,
+                column,
+                error,
+            } => write!(f, "line {}: {}: {}", line, column, error),
+            MixedCurrency { line, currency } => write!(
+                f,
+                "line {}: {} differs from the currency of the lines before",
+                line,
+                currency.get_code()
+            ),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+/// A valid row of the spreadsheet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportRow {
+    date: NaiveDate,
+    user: String,
+    meal: String,
+    price: Money,
+    paid: Money,
+    tip: Money,
+}
+
+/// What an import did or, for a dry run, would do.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    rows: usize,
+    /// Dates of the orders, oldest first
+    dates: Vec<NaiveDate>,
+    /// Names not registered yet, sorted
+    new_users: Vec<String>,
+    /// Balance of every participant over all imported orders, by name
+    balances: BTreeMap<String, Balance>,
+    /// IDs of the archived orders, oldest first, empty for a dry run
+    imported: Vec<Id<Order>>,
+}
+
+impl ImportReport {
+    pub fn get_rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn dates(&self) -> &[NaiveDate] {
+        &self.dates
+    }
+
+    pub fn new_users(&self) -> &[String] {
+        &self.new_users
+    }
+
+    pub fn balances(&self) -> &BTreeMap<String, Balance> {
+        &self.balances
+    }
+
+    pub fn imported(&self) -> &[Id<Order>] {
+        &self.imported
+    }
+}
+
+/// Parses the spreadsheet, reporting every invalid row instead of stopping at the first.
+pub fn parse(csv: &str) -> Result<Vec<ImportRow>, Vec<ImportError>> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+    let header = lines
+        .next()
+        .map(|(_, line)| line.trim())
+        .unwrap_or_default();
+    if header.to_lowercase().replace(' ', "") != HEADER {
+        return Err(vec![ImportError::WrongHeader(String::from(header))]);
+    }
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut currency = None;
+    for (line, text) in lines.filter(|(_, text)| !text.trim().is_empty()) {
+        match parse_row(line, text, &mut currency) {
+            Ok(row) => rows.push(row),
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_row(
+    line: usize,
+    text: &str,
+    currency: &mut Option<Currency>,
+) -> Result<ImportRow, ImportError> {
+    let fields = split_record(text);
+    if fields.len() != 6 {
+        return Err(ImportError::WrongColumnCount {
+            line,
+            found: fields.len(),
+        });
+    }
+    let date = NaiveDate::parse_from_str(&fields[0], "%Y-%m-%d").map_err(|_| {
+        ImportError::InvalidDate {
+            line,
+            value: fields[0].clone(),
+        }
+    })?;
+    if fields[1].is_empty() {
+        return Err(ImportError::EmptyUser { line });
+    }
+    if fields[2].is_empty() {
+        return Err(ImportError::EmptyMeal { line });
+    }
+    let mut amount = |index: usize, column: &'static str| {
+        let money: Money = fields[index]
+            .parse()
+            .map_err(|error| ImportError::InvalidAmount {
+                line,
+                column,
+                error,
+            })?;
+        match (money.get_currency(), *currency) {
+            (Some(found), Some(expected)) if found != expected => Err(ImportError::MixedCurrency {
+                line,
+                currency: found,
+            }),
+            (Some(found), None) => {
+                *currency = Some(found);
+                Ok(money)
+            }
+            _ => Ok(money),
+        }
+    };
+    Ok(ImportRow {
+        price: amount(3, "price")?,
+        paid: amount(4, "paid")?,
+        tip: amount(5, "tip")?,
+        date,
+        user: fields[1].clone(),
+        meal: fields[2].clone(),
+    })
+}
+
+/// Splits a line at commas, fields may be quoted like Excel does if they contain commas or quotes.
+pub fn split_record(text: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(String::from(field.trim()));
+                field.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    fields.push(String::from(field.trim()));
+    fields
+}
+
+/// Validates the spreadsheet and reports what an import would do, without changing anything.
+pub fn dry_run(csv: &str, users: &UserRepository) -> Result<ImportReport, Vec<ImportError>> {
+    let rows = parse(csv)?;
+    Ok(plan(&rows, users))
+}
+
+/// Imports the spreadsheet as archived orders, registering unknown users. Nothing is changed if a row is invalid.
+pub fn import(
+    csv: &str,
+    users: &mut UserRepository,
+    orders: &mut OrderManager,
+) -> Result<ImportReport, Vec<ImportError>> {
+    let rows = parse(csv)?;
+    let mut report = plan(&rows, users);
+    for name in &report.new_users {
+        users
+            .register(name.clone())
+            .expect("New names are not empty and not taken");
+    }
+    for rows in group_by_date(&rows).values() {
+        let user_id = |name: &str| -> Id<User> {
+            users
+                .get_user_by_name(name)
+                .expect("All users are registered")
+                .get_id()
+        };
+        let date = rows[0].date;
+        let day = SystemTime::from(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+        // Dates the history on the day of the order, so statistics see when it really happened
+        let mut order =
+            Order::with_audit_clock(user_id(&rows[0].user), Arc::new(TestClock::new(day)));
+        let mut paid: HashMap<Id<User>, (Money, Money)> = HashMap::new();
+        for row in rows {
+            let id = user_id(&row.user);
+            if !order.is_participating(&id) {
+                order.add_user(id.clone()).expect("New order is open");
+            }
+            order
+                .add_meal_for_user(id.clone(), row.meal.clone(), String::new(), row.price)
+                .expect("New order is open and has no menu");
+            let (sum_paid, sum_tip) = paid.entry(id).or_insert((Money::zero(), Money::zero()));
+            *sum_paid += row.paid;
+            *sum_tip += row.tip;
+        }
+        for (id, (sum_paid, sum_tip)) in paid {
+            order
+                .set_paid_for_user(id.clone(), sum_paid)
+                .and_then(|_| order.set_tip_for_user(id, sum_tip))
+                .expect("User is participating");
+        }
+        order.start_ordering().expect("New order is open");
+        order.mark_ordered(None).expect("Order is being ordered");
+        order.mark_delivered(day).expect("Order was ordered");
+        report.imported.push(orders.archive_order(order));
+    }
+    Ok(report)
+}
+
+fn group_by_date(rows: &[ImportRow]) -> BTreeMap<NaiveDate, Vec<&ImportRow>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&ImportRow>> = BTreeMap::new();
+    for row in rows {
+        days.entry(row.date).or_default().push(row);
+    }
+    days
+}
+
+/// Settles every order with the ledger and adds up what each participant consumed, tipped and paid.
+fn plan(rows: &[ImportRow], users: &UserRepository) -> ImportReport {
+    let days = group_by_date(rows);
+    let names = canonical_names(rows, users);
+    let mut totals: BTreeMap<String, (Money, Money)> = BTreeMap::new();
+    for rows in days.values() {
+        let mut participants: BTreeMap<String, (Money, Money, Money)> = BTreeMap::new();
+        for row in rows {
+            let (consumed, tip, paid) = participants
+                .entry(names[&row.user.to_lowercase()].clone())
+                .or_insert((Money::zero(), Money::zero(), Money::zero()));
+            *consumed += row.price;
+            *tip += row.tip;
+            *paid += row.paid;
+        }
+        let mut input = SettlementInput::new(names[&rows[0].user.to_lowercase()].clone());
+        for (name, (consumed, tip, paid)) in participants {
+            input.add_participant(Participant::new(name, consumed, tip, paid));
+        }
+        for entry in settle(&input).entries() {
+            let (due, paid) = totals
+                .entry(entry.get_key().clone())
+                .or_insert((Money::zero(), Money::zero()));
+            *due += entry.get_due();
+            *paid += entry.get_paid();
+        }
+    }
+    let mut new_users: Vec<String> = totals
+        .keys()
+        .filter(|name| users.get_user_by_name(name).is_none())
+        .cloned()
+        .collect();
+    new_users.sort();
+    ImportReport {
+        rows: rows.len(),
+        dates: days.keys().copied().collect(),
+        new_users,
+        balances: totals
+            .into_iter()
+            .map(|(name, (due, paid))| (name, Balance::new(due, paid)))
+            .collect(),
+        imported: Vec::new(),
+    }
+}
+
+/// Maps lower case names to the registered name, or to the name as first written in the spreadsheet, so
+/// spelling variants like "anna" and "Anna" are one user.
+fn canonical_names(rows: &[ImportRow], users: &UserRepository) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for row in rows {
+        names.entry(row.user.to_lowercase()).or_insert_with(|| {
+            users
+                .get_user_by_name(&row.user)
+                .map(|user| user.get_name().clone())
+                .unwrap_or_else(|| row.user.clone())
+        });
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::order::OrderStatus;
+    use rstest::rstest;
+
+    const SPREADSHEET: &str = "date,user,meal,price,paid,tip\n\
+                               2020-05-04,Anna,03 Margherita,7.50,10.00,0.50\n\
+                               2020-05-04,Ben,\"12 Salami, scharf\",8.00,5.00,0\n\
+                               2020-05-11,ben,03 Margherita,7.50,8.00,0.50\n";
+
+    #[test]
+    fn dry_run_reports_orders_and_balances_without_changes() {
+        // Given:
+        let mut users = UserRepository::new();
+        users.register(String::from("Anna")).unwrap();
+
+        // When:
+        let report = dry_run(SPREADSHEET, &users).unwrap();
+
+        // Then:
+        assert_eq!(report.get_rows(), 3);
+        assert_eq!(
+            report.dates(),
+            &[
+                NaiveDate::from_ymd_opt(2020, 5, 4).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 5, 11).unwrap()
+            ]
+        );
+        assert_eq!(report.new_users(), &[String::from("Ben")]);
+        assert_eq!(report.balances()["Anna"], Balance::Change(Money::new(2, 0)));
+        assert_eq!(report.balances()["Ben"], Balance::Owed(Money::new(3, 0)));
+        assert!(report.imported().is_empty());
+        assert!(users.get_user_by_name("Ben").is_none());
+    }
+
+    #[test]
+    fn spreadsheet_is_imported_as_archived_orders() {
+        // Given:
+        let mut users = UserRepository::new();
+        let mut orders = OrderManager::new();
+
+        // When:
+        let report = import(SPREADSHEET, &mut users, &mut orders).unwrap();
+
+        // Then:
+        assert_eq!(report.imported().len(), 2);
+        let anna = users.get_user_by_name("anna").unwrap().get_id();
+        let ben = users.get_user_by_name("Ben").unwrap().get_id();
+        let first = orders.get_archived_order(&report.imported()[0]).unwrap();
+        assert_eq!(first.get_manager_id(), anna);
+        assert_eq!(first.get_status(), &OrderStatus::Delivered);
+        assert_eq!(first.calculate_total_price(), Money::new(15, 50));
+        assert_eq!(
+            first
+                .get_user_meals(&ben)
+                .unwrap()
+                .meals()
+                .map(|meal| meal.get_meal_id().clone())
+                .collect::<Vec<String>>(),
+            vec![String::from("12 Salami, scharf")]
+        );
+        assert_eq!(
+            first.get_user_meals(&anna).unwrap().get_tip(),
+            Money::new(0, 50)
+        );
+        assert_eq!(orders.open_orders(), vec![]);
+    }
+
+    #[rstest(
+        csv,
+        expected,
+        case(
+            "date;user;meal\n",
+            vec![ImportError::WrongHeader(String::from("date;user;meal"))]
+        ),
+        case(
+            "date,user,meal,price,paid,tip\n04.05.2020,Anna,03,7.50,7.50,0\n2020-05-04,,03,7.50,7.50,0\n",
+            vec![
+                ImportError::InvalidDate { line: 2, value: String::from("04.05.2020") },
+                ImportError::EmptyUser { line: 3 }
+            ]
+        ),
+        case(
+            "date,user,meal,price,paid,tip\n2020-05-04,Anna,03,7.505,7.50,0\n2020-05-04,Anna,03\n",
+            vec![
+                ImportError::InvalidAmount {
+                    line: 2,
+                    column: "price",
+                    error: DecimalError::TooPrecise(String::from("7.505"))
+                },
+                ImportError::WrongColumnCount { line: 3, found: 3 }
+            ]
+        ),
+        case(
+            "date,user,meal,price,paid,tip\n2020-05-04,Anna,03,7.50 EUR,0,0\n2020-05-04,Ben,03,7.50 CHF,0,0\n",
+            vec![ImportError::MixedCurrency { line: 3, currency: Currency::Chf }]
+        )
+    )]
+    fn invalid_rows_are_reported(csv: &str, expected: Vec<ImportError>) {
+        // Given:
+        let mut users = UserRepository::new();
+        let mut orders = OrderManager::new();
+
+        // When:
+        let result = import(csv, &mut users, &mut orders);
+
+        // Then:
+        assert_eq!(result, Err(expected));
+        assert_eq!(users.users().count(), 0);
+        assert_eq!(orders.archived_orders().count(), 0);
+    }
+}
@@ -0,0 +1,20 @@
+pub mod api;
+pub mod auth;
+pub mod cli;
+pub mod error;
+pub mod export;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod import;
+pub mod menu;
+pub mod notifications;
+pub mod order_model;
+pub mod payments;
+pub mod persistence;
+pub mod plugins;
+pub mod quick_entry;
+pub mod render;
+pub mod settlement;
+pub mod stats;
+pub mod user_model;
+pub mod util;
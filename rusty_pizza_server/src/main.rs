@@ -1,6 +1,251 @@
-mod order_model;
-mod util;
+use clap::Parser;
+use rusty_pizza_server::api::routes::router;
+use rusty_pizza_server::api::state::AppState;
+use rusty_pizza_server::auth::ldap::{LdapProvider, USER_PLACEHOLDER};
+use rusty_pizza_server::auth::oidc::OidcProvider;
+use rusty_pizza_server::auth::provider::AuthProviders;
+use rusty_pizza_server::cli::admin::{self, ServerCli, ServerCommand, ADMIN_TOKEN_VARIABLE};
+use rusty_pizza_server::notifications::announcement::Announcer;
+use rusty_pizza_server::notifications::email::SmtpMailer;
+use rusty_pizza_server::notifications::signage::{SignageDisplay, SignageFormat};
+use rusty_pizza_server::notifications::slack::SlackWebhook;
+use rusty_pizza_server::notifications::web_push::{Vapid, WebPush};
+use rusty_pizza_server::order_model::retention::RetentionPolicy;
+use rusty_pizza_server::util::short_code::IdFormat;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
-fn main() {
-    println!("Hello, world!");
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+/// How often archived orders are checked against the retention policy
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How often open orders are checked for being about to close
+const CLOSING_ANNOUNCEMENT_INTERVAL: Duration = Duration::from_secs(30);
+/// How often ordered orders are checked for being late
+const ETA_WATCH_INTERVAL: Duration = Duration::from_secs(30);
+/// How often an unreachable storage is tried again while running ephemeral
+const STORAGE_RECOVERY_INTERVAL: Duration = Duration::from_secs(60);
+#[cfg(feature = "grpc")]
+const DEFAULT_GRPC_ADDRESS: &str = "127.0.0.1:50051";
+
+#[tokio::main]
+async fn main() {
+    if let Some(ServerCommand::Admin(args)) = ServerCli::parse().command {
+        match admin::run(&args).await {
+            Ok(outcome) => println!("{}", outcome.trim_end()),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    let address = env::var("RUSTY_PIZZA_ADDRESS").unwrap_or_else(|_| String::from(DEFAULT_ADDRESS));
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .unwrap_or_else(|e| panic!("Could not bind to {}: {}", address, e));
+    let mut state = AppState::new();
+    if env::var_os("RUSTY_PIZZA_REQUIRE_AUTH").is_some() {
+        state = state.with_required_authentication();
+    }
+    // The admin routes stay closed without a token
+    if let Ok(token) = env::var(ADMIN_TOKEN_VARIABLE) {
+        assert!(
+            !token.trim().is_empty(),
+            "{} is empty",
+            ADMIN_TOKEN_VARIABLE
+        );
+        state = state.with_admin_token(token);
+    }
+    if let Ok(limit) = env::var("RUSTY_PIZZA_EVENT_QUEUE_LIMIT") {
+        let limit = limit
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_EVENT_QUEUE_LIMIT: {}", e));
+        state = state.with_event_queue_limit(limit);
+    }
+    state = state
+        .with_announcer(announcer())
+        .with_auth_providers(auth_providers());
+    if let Some(web_push) = web_push() {
+        state = state.with_web_push(web_push);
+    }
+    if let Ok(path) = env::var("RUSTY_PIZZA_TEMPLATES_FILE") {
+        state = template_storage(state, &path);
+    }
+    state.spawn_closing_announcements(CLOSING_ANNOUNCEMENT_INTERVAL);
+    state.spawn_eta_watch(ETA_WATCH_INTERVAL);
+    let mut retention = RetentionPolicy::new(
+        days_from_env("RUSTY_PIZZA_ANONYMIZE_AFTER_DAYS"),
+        days_from_env("RUSTY_PIZZA_DELETE_AFTER_DAYS"),
+    );
+    if let Some(archive_after) = days_from_env("RUSTY_PIZZA_ARCHIVE_AFTER_DAYS") {
+        retention = retention.with_archive_after(archive_after);
+    }
+    if !retention.keeps_all() {
+        state.orders().set_retention_policy(retention);
+        state.spawn_retention(RETENTION_INTERVAL);
+    }
+    let report = state.verify_integrity();
+    if !report.is_healthy() {
+        eprint!("Integrity check found issues:\n{}", report);
+    }
+    if let Ok(url) = env::var("RUSTY_PIZZA_SIGNAGE_URL") {
+        state.spawn_signage(signage_display(&url));
+    }
+    #[cfg(feature = "grpc")]
+    serve_grpc(state.clone());
+    println!("Serving pizza on {}", address);
+    axum::serve(listener, router(state))
+        .await
+        .expect("Server error");
+}
+
+/// Announcer with the ID format of `RUSTY_PIZZA_ID_FORMAT`, posting to the configured channels:
+///
+/// * `RUSTY_PIZZA_SLACK_WEBHOOK_URL` - incoming webhook, see `SlackWebhook`
+/// * `RUSTY_PIZZA_SMTP_SERVER` - "host:port" of a mail relay, with `RUSTY_PIZZA_SMTP_FROM` and the
+///   comma-separated `RUSTY_PIZZA_SMTP_TO`
+fn announcer() -> Announcer {
+    let mut announcer = Announcer::default();
+    if let Ok(id_format) = env::var("RUSTY_PIZZA_ID_FORMAT") {
+        let id_format: IdFormat = id_format
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_ID_FORMAT: {}", e));
+        announcer.set_id_format(id_format);
+    }
+    if let Ok(url) = env::var("RUSTY_PIZZA_SLACK_WEBHOOK_URL") {
+        let endpoint = url
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_SLACK_WEBHOOK_URL: {}", e));
+        println!("Announcing orders via webhook {}", url);
+        announcer.add_channel(Box::new(SlackWebhook::new(endpoint)));
+    }
+    if let Ok(server) = env::var("RUSTY_PIZZA_SMTP_SERVER") {
+        let from = env::var("RUSTY_PIZZA_SMTP_FROM").expect("RUSTY_PIZZA_SMTP_FROM is missing");
+        let recipients = env::var("RUSTY_PIZZA_SMTP_TO")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(String::from)
+            .collect();
+        let mailer = SmtpMailer::new(server.clone(), from, recipients)
+            .unwrap_or_else(|e| panic!("Invalid SMTP configuration: {}", e));
+        println!("Announcing orders via mail server {}", server);
+        announcer.add_channel(Box::new(mailer));
+    }
+    announcer
+}
+
+/// Push messages to browsers if `RUSTY_PIZZA_VAPID_PRIVATE_KEY` is set, a base64url encoded P-256 private key:
+///
+/// * `RUSTY_PIZZA_VAPID_SUBJECT` - contact for the push services, e.g. "mailto:pizza@example.com"
+/// * `RUSTY_PIZZA_PUSH_RELAY_URL` - relay forwarding the messages to the HTTPS push services, see
+///   `HttpEndpoint::forward`
+fn web_push() -> Option<WebPush> {
+    let private_key = env::var("RUSTY_PIZZA_VAPID_PRIVATE_KEY").ok()?;
+    let subject =
+        env::var("RUSTY_PIZZA_VAPID_SUBJECT").expect("RUSTY_PIZZA_VAPID_SUBJECT is missing");
+    let vapid = Vapid::new(&private_key, subject)
+        .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_VAPID_PRIVATE_KEY: {}", e));
+    let mut web_push = WebPush::new(vapid);
+    if let Ok(url) = env::var("RUSTY_PIZZA_PUSH_RELAY_URL") {
+        let relay = url
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_PUSH_RELAY_URL: {}", e));
+        println!("Sending push messages via {}", url);
+        web_push = web_push.with_relay(relay);
+    }
+    Some(web_push)
+}
+
+/// Login providers besides the passwords of the server, logged in with via `/sessions/<name>`:
+///
+/// * `RUSTY_PIZZA_OIDC_USERINFO_URL` - userinfo endpoint of an OpenID Connect provider, see `OidcProvider`
+/// * `RUSTY_PIZZA_LDAP_SERVER` - "host:port" of a directory, with `RUSTY_PIZZA_LDAP_USER_DN` like
+///   "uid={user},ou=people,dc=example,dc=com"
+fn auth_providers() -> AuthProviders {
+    let mut providers = AuthProviders::new();
+    if let Ok(url) = env::var("RUSTY_PIZZA_OIDC_USERINFO_URL") {
+        let userinfo = url
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_OIDC_USERINFO_URL: {}", e));
+        println!("Logging in via OpenID Connect at {}", url);
+        providers.add(Box::new(OidcProvider::new(userinfo)));
+    }
+    if let Ok(server) = env::var("RUSTY_PIZZA_LDAP_SERVER") {
+        let user_dn =
+            env::var("RUSTY_PIZZA_LDAP_USER_DN").expect("RUSTY_PIZZA_LDAP_USER_DN is missing");
+        if !user_dn.contains(USER_PLACEHOLDER) {
+            panic!("RUSTY_PIZZA_LDAP_USER_DN must contain {}", USER_PLACEHOLDER);
+        }
+        println!("Logging in via LDAP at {}", server);
+        providers.add(Box::new(LdapProvider::new(server, user_dn)));
+    }
+    providers
+}
+
+/// Loads the templates from the file and saves them there. If the file can't be read, the server still starts, but
+/// only keeps changes in memory and replays them once it can, unless `RUSTY_PIZZA_REQUIRE_STORAGE` is set.
+fn template_storage(state: AppState, path: &str) -> AppState {
+    if env::var_os("RUSTY_PIZZA_REQUIRE_STORAGE").is_some() {
+        return state
+            .with_template_file(PathBuf::from(path))
+            .unwrap_or_else(|e| panic!("Could not load order templates from {}: {}", path, e));
+    }
+    let state = state.with_template_file_or_ephemeral(PathBuf::from(path));
+    let reason = state.storage_status().get_reason().cloned();
+    if let Some(reason) = reason {
+        eprintln!(
+            "WARNING: could not load order templates from {}: {}",
+            path, reason
+        );
+        eprintln!("WARNING: running EPHEMERAL, changes are only kept in memory until the file can be written again");
+        state.spawn_storage_recovery(STORAGE_RECOVERY_INTERVAL);
+    }
+    state
+}
+
+fn days_from_env(name: &str) -> Option<Duration> {
+    let days: u64 = env::var(name)
+        .ok()?
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid {}: {}", name, e));
+    Some(Duration::from_secs(days * 24 * 60 * 60))
+}
+
+/// Display at the given URL, in the format of `RUSTY_PIZZA_SIGNAGE_FORMAT` (HTML by default).
+fn signage_display(url: &str) -> SignageDisplay {
+    let endpoint = url
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_SIGNAGE_URL: {}", e));
+    let format = match env::var("RUSTY_PIZZA_SIGNAGE_FORMAT") {
+        Ok(format) => format
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid RUSTY_PIZZA_SIGNAGE_FORMAT: {}", e)),
+        Err(_) => SignageFormat::Html,
+    };
+    println!("Showing order summaries on {}", url);
+    SignageDisplay::new(endpoint, format)
+}
+
+/// Serves the gRPC interface in the background, on the same state and with the same session checks as the REST API.
+#[cfg(feature = "grpc")]
+fn serve_grpc(state: AppState) {
+    use rusty_pizza_server::grpc::service::PizzaService;
+
+    let address =
+        env::var("RUSTY_PIZZA_GRPC_ADDRESS").unwrap_or_else(|_| String::from(DEFAULT_GRPC_ADDRESS));
+    let socket = address
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid gRPC address {}: {}", address, e));
+    println!("Serving pizza via gRPC on {}", address);
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(PizzaService::new(state).into_server())
+            .serve(socket)
+            .await
+            .expect("gRPC server error");
+    });
 }
@@ -0,0 +1,487 @@
+use crate::menu::diff::{MenuDiff, PriceChange};
+use crate::menu::item::MenuItem;
+use crate::order_model::preparation::Preparation;
+use crate::util::errors::RemoveError;
+use crate::util::money::Money;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::iter::Iterator;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuError {
+    /// There is no item with the meal number on the menu
+    MealNotFound,
+    /// The menu item is not offered in the variety
+    VarietyNotFound,
+    /// The given price differs from the price on the menu
+    PriceMismatch { expected: Money },
+    /// The restaurant did not declare that it can prepare meals like this
+    PreparationNotOffered(Preparation),
+    /// Nothing was chosen from a required option group of the meal
+    MissingChoice { group: String, options: Vec<String> },
+    /// The choice is no option of the meal
+    UnknownChoice(String),
+    /// More than one option of the group was chosen
+    TooManyChoices { group: String },
+}
+
+impl fmt::Display for MenuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use MenuError::*;
+        match self {
+            MealNotFound => write!(f, "Meal is not on the menu"),
+            VarietyNotFound => write!(f, "Meal is not offered in this variety"),
+            PriceMismatch { expected } => write!(f, "Price differs from menu price {}", expected),
+            PreparationNotOffered(preparation) => {
+                write!(f, "Restaurant does not offer {}", preparation)
+            }
+            MissingChoice { group, options } => {
+                write!(f, "Choose {}: {}", group, options.join(", "))
+            }
+            UnknownChoice(choice) => write!(f, "{} is no option of the meal", choice),
+            TooManyChoices { group } => write!(f, "Choose only one {}", group),
+        }
+    }
+}
+
+impl Error for MenuError {}
+
+pub struct MenuItems<'a>(std::collections::hash_map::Values<'a, String, MenuItem>);
+
+impl<'a> Iterator for MenuItems<'a> {
+    type Item = &'a MenuItem;
+
+    fn next(&mut self) -> Option<&'a MenuItem> {
+        self.0.next()
+    }
+}
+
+/// The meals offered by a restaurant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Menu {
+    restaurant: String,
+    /// Menu item by meal number
+    items: HashMap<String, MenuItem>,
+    /// Price of the specials by lowercase description
+    special_prices: HashMap<String, Money>,
+    /// Preparations the kitchen declared it can do
+    preparations: HashSet<Preparation>,
+}
+
+impl Menu {
+    pub fn new(restaurant: String) -> Menu {
+        Menu {
+            restaurant,
+            items: HashMap::new(),
+            special_prices: HashMap::new(),
+            preparations: HashSet::new(),
+        }
+    }
+
+    pub fn get_restaurant(&self) -> &String {
+        &self.restaurant
+    }
+
+    /// Adds the given item and returns the item with the same meal number it replaces, if any.
+    pub fn add_item(&mut self, item: MenuItem) -> Option<MenuItem> {
+        self.items.insert(item.get_meal_id().clone(), item)
+    }
+
+    pub fn get_item(&self, meal_id: &str) -> Option<&MenuItem> {
+        self.items.get(meal_id)
+    }
+
+    pub fn remove_item(&mut self, meal_id: &str) -> Result<MenuItem, RemoveError> {
+        self.items.remove(meal_id).ok_or(RemoveError::NotFound)
+    }
+
+    /// Looks up the price of a meal in the given variety.
+    pub fn get_price(&self, meal_id: &str, variety: &str) -> Result<Money, MenuError> {
+        self.get_item(meal_id)
+            .ok_or(MenuError::MealNotFound)?
+            .get_price(variety)
+            .ok_or(MenuError::VarietyNotFound)
+    }
+
+    /// Checks that the meal is offered in the given variety for exactly `price`.
+    pub fn validate_price(
+        &self,
+        meal_id: &str,
+        variety: &str,
+        price: Money,
+    ) -> Result<(), MenuError> {
+        let expected = self.get_price(meal_id, variety)?;
+        if !price.matches(expected) {
+            return Err(MenuError::PriceMismatch { expected });
+        }
+        Ok(())
+    }
+
+    /// Sets the price of a special, ignoring case of the description, and returns the price it replaces.
+    pub fn set_special_price(&mut self, description: &str, price: Money) -> Option<Money> {
+        self.special_prices
+            .insert(description.trim().to_lowercase(), price)
+    }
+
+    pub fn get_special_price(&self, description: &str) -> Option<Money> {
+        self.special_prices
+            .get(&description.trim().to_lowercase())
+            .copied()
+    }
+
+    /// Declares that the kitchen can prepare meals like this, returns whether it was not declared before.
+    pub fn offer_preparation(&mut self, preparation: Preparation) -> bool {
+        self.preparations.insert(preparation)
+    }
+
+    pub fn offers_preparation(&self, preparation: Preparation) -> bool {
+        self.preparations.contains(&preparation)
+    }
+
+    /// Checks that the kitchen can do all the preparations, reporting the first one it can't.
+    pub fn validate_preparations(
+        &self,
+        preparations: &BTreeSet<Preparation>,
+    ) -> Result<(), MenuError> {
+        match preparations
+            .iter()
+            .find(|preparation| !self.offers_preparation(**preparation))
+        {
+            Some(preparation) => Err(MenuError::PreparationNotOffered(*preparation)),
+            None => Ok(()),
+        }
+    }
+
+    /// Matches the choices to the option groups of the meal, returning the chosen option of every group in
+    /// the order of the groups.
+    ///
+    /// Reports the first group nothing was chosen from, listing its options, so the user can be asked.
+    pub fn resolve_choices(
+        &self,
+        meal_id: &str,
+        choices: &[String],
+    ) -> Result<Vec<String>, MenuError> {
+        let groups = self
+            .get_item(meal_id)
+            .ok_or(MenuError::MealNotFound)?
+            .option_groups();
+        let mut chosen: Vec<Option<&String>> = vec![None; groups.len()];
+        for choice in choices {
+            let (index, option) = groups
+                .iter()
+                .enumerate()
+                .find_map(|(index, group)| group.find_option(choice).map(|option| (index, option)))
+                .ok_or_else(|| MenuError::UnknownChoice(choice.clone()))?;
+            if chosen[index].replace(option).is_some() {
+                return Err(MenuError::TooManyChoices {
+                    group: groups[index].get_name().clone(),
+                });
+            }
+        }
+        groups
+            .iter()
+            .zip(chosen)
+            .map(|(group, option)| {
+                option.cloned().ok_or_else(|| MenuError::MissingChoice {
+                    group: group.get_name().clone(),
+                    options: group.options().to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn items(&self) -> MenuItems<'_> {
+        MenuItems(self.items.values())
+    }
+
+    /// Compares two versions of a menu, e.g. before and after re-importing it.
+    ///
+    /// Items are matched by their meal number. For items on both menus, every variety whose price was changed, added or
+    /// dropped is reported as a `PriceChange`.
+    pub fn diff(old: &Menu, new: &Menu) -> MenuDiff {
+        let mut added: Vec<String> = new
+            .items
+            .keys()
+            .filter(|meal_id| !old.items.contains_key(*meal_id))
+            .cloned()
+            .collect();
+        let mut removed: Vec<String> = old
+            .items
+            .keys()
+            .filter(|meal_id| !new.items.contains_key(*meal_id))
+            .cloned()
+            .collect();
+        let mut repriced = Vec::new();
+        for (meal_id, old_item) in old.items.iter() {
+            if let Some(new_item) = new.items.get(meal_id) {
+                let mut varieties: Vec<&String> =
+                    old_item.varieties().chain(new_item.varieties()).collect();
+                varieties.sort();
+                varieties.dedup();
+                for variety in varieties {
+                    let old_price = old_item.get_price(variety);
+                    let new_price = new_item.get_price(variety);
+                    if old_price != new_price {
+                        repriced.push(PriceChange::new(
+                            meal_id.clone(),
+                            variety.clone(),
+                            old_price,
+                            new_price,
+                        ));
+                    }
+                }
+            }
+        }
+        added.sort();
+        removed.sort();
+        repriced.sort_by(|a, b| a.get_meal_id().cmp(b.get_meal_id()));
+        MenuDiff::new(added, removed, repriced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::item::OptionGroup;
+    use crate::util::locale::Currency;
+    use rstest::rstest;
+
+    fn item(meal_id: &str, prices: Vec<(&str, Money)>) -> MenuItem {
+        let mut item = MenuItem::new(String::from(meal_id), format!("Pizza {}", meal_id));
+        for (variety, price) in prices {
+            item.set_price(String::from(variety), price);
+        }
+        item
+    }
+
+    fn menu(items: Vec<MenuItem>) -> Menu {
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+        for item in items {
+            menu.add_item(item);
+        }
+        menu
+    }
+
+    fn pasta_menu() -> Menu {
+        let mut pasta = item("40", vec![("normal", Money::new(8, 0))]);
+        pasta.add_option_group(OptionGroup::new(
+            String::from("Nudeln"),
+            vec![String::from("Penne"), String::from("Spaghetti")],
+        ));
+        pasta.add_option_group(OptionGroup::new(
+            String::from("Soße"),
+            vec![String::from("Tomate"), String::from("Sahne")],
+        ));
+        menu(vec![pasta])
+    }
+
+    #[rstest(
+        choices,
+        expected,
+        case(
+            vec!["sahne", "Penne"],
+            Ok(vec![String::from("Penne"), String::from("Sahne")])
+        ),
+        case(
+            vec!["Penne"],
+            Err(MenuError::MissingChoice {
+                group: String::from("Soße"),
+                options: vec![String::from("Tomate"), String::from("Sahne")]
+            })
+        ),
+        case(vec!["Pesto"], Err(MenuError::UnknownChoice(String::from("Pesto")))),
+        case(
+            vec!["Penne", "Spaghetti", "Tomate"],
+            Err(MenuError::TooManyChoices {
+                group: String::from("Nudeln")
+            })
+        )
+    )]
+    fn choices_are_resolved(choices: Vec<&str>, expected: Result<Vec<String>, MenuError>) {
+        // Given:
+        let menu = pasta_menu();
+        let choices: Vec<String> = choices.into_iter().map(String::from).collect();
+
+        // Then:
+        assert_eq!(menu.resolve_choices("40", &choices), expected);
+    }
+
+    #[test]
+    fn item_can_be_added_and_looked_up() {
+        // Given:
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+
+        // When:
+        let replaced = menu.add_item(item("03", vec![("groß", Money::new(5, 50))]));
+
+        // Then:
+        assert_eq!(replaced, None);
+        assert_eq!(
+            menu.get_item("03"),
+            Some(&item("03", vec![("groß", Money::new(5, 50))]))
+        );
+        assert_eq!(menu.get_item("04"), None);
+    }
+
+    #[test]
+    fn adding_item_with_same_meal_id_replaces_it() {
+        // Given:
+        let mut menu = menu(vec![item("03", vec![("groß", Money::new(5, 50))])]);
+
+        // When:
+        let replaced = menu.add_item(item("03", vec![("groß", Money::new(5, 90))]));
+
+        // Then:
+        assert_eq!(
+            replaced,
+            Some(item("03", vec![("groß", Money::new(5, 50))]))
+        );
+        assert_eq!(menu.items().count(), 1);
+    }
+
+    #[test]
+    fn item_can_be_removed() {
+        // Given:
+        let mut menu = menu(vec![item("03", vec![])]);
+
+        // When:
+        let removed = menu.remove_item("03");
+        let removed_again = menu.remove_item("03");
+
+        // Then:
+        assert_eq!(removed, Ok(item("03", vec![])));
+        assert_eq!(removed_again, Err(RemoveError::NotFound));
+    }
+
+    #[rstest(
+        meal_id,
+        variety,
+        expected,
+        case("03", "groß", Ok(Money::new(5, 50))),
+        case("03", "riesig", Err(MenuError::VarietyNotFound)),
+        case("04", "groß", Err(MenuError::MealNotFound))
+    )]
+    fn price_can_be_looked_up(meal_id: &str, variety: &str, expected: Result<Money, MenuError>) {
+        // Given:
+        let menu = menu(vec![item("03", vec![("groß", Money::new(5, 50))])]);
+
+        // When:
+        let price = menu.get_price(meal_id, variety);
+
+        // Then:
+        assert_eq!(price, expected);
+    }
+
+    #[rstest(
+        price,
+        expected,
+        case(Money::new(5, 50), Ok(())),
+        case(Money::new(5, 50).with_currency(Currency::Eur), Ok(())),
+        case(
+            Money::new(15, 50),
+            Err(MenuError::PriceMismatch {
+                expected: Money::new(5, 50)
+            })
+        )
+    )]
+    fn price_is_validated_against_menu(price: Money, expected: Result<(), MenuError>) {
+        // Given:
+        let menu = menu(vec![item("03", vec![("groß", Money::new(5, 50))])]);
+
+        // When:
+        let result = menu.validate_price("03", "groß", price);
+
+        // Then:
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn diff_of_equal_menus_is_empty() {
+        // Given:
+        let old = menu(vec![item("03", vec![("groß", Money::new(5, 50))])]);
+        let new = old.clone();
+
+        // When:
+        let diff = Menu::diff(&old, &new);
+
+        // Then:
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_repriced_items() {
+        // Given:
+        let old = menu(vec![
+            item(
+                "03",
+                vec![("klein", Money::new(4, 50)), ("groß", Money::new(5, 50))],
+            ),
+            item("17", vec![("klein", Money::new(4, 0))]),
+            item("42", vec![("normal", Money::new(6, 0))]),
+        ]);
+        let new = menu(vec![
+            item(
+                "03",
+                vec![("groß", Money::new(5, 90)), ("riesig", Money::new(8, 0))],
+            ),
+            item("17", vec![("klein", Money::new(4, 0))]),
+            item("51", vec![("normal", Money::new(7, 0))]),
+        ]);
+
+        // When:
+        let diff = Menu::diff(&old, &new);
+
+        // Then:
+        assert_eq!(diff.get_added(), &vec![String::from("51")]);
+        assert_eq!(diff.get_removed(), &vec![String::from("42")]);
+        assert_eq!(
+            diff.get_repriced(),
+            &vec![
+                PriceChange::new(
+                    String::from("03"),
+                    String::from("groß"),
+                    Some(Money::new(5, 50)),
+                    Some(Money::new(5, 90))
+                ),
+                PriceChange::new(
+                    String::from("03"),
+                    String::from("klein"),
+                    Some(Money::new(4, 50)),
+                    None
+                ),
+                PriceChange::new(
+                    String::from("03"),
+                    String::from("riesig"),
+                    None,
+                    Some(Money::new(8, 0))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_offered_preparations_are_valid() {
+        // Given:
+        let mut menu = Menu::new(String::from("Pizzeria"));
+        menu.offer_preparation(Preparation::WellDone);
+        let preparations: BTreeSet<Preparation> =
+            vec![Preparation::WellDone, Preparation::GlutenFreeBase]
+                .into_iter()
+                .collect();
+
+        // When:
+        let valid = menu.validate_preparations(&preparations);
+
+        // Then:
+        assert_eq!(
+            valid,
+            Err(MenuError::PreparationNotOffered(
+                Preparation::GlutenFreeBase
+            ))
+        );
+        assert!(!menu.offer_preparation(Preparation::WellDone));
+        assert!(menu.offer_preparation(Preparation::GlutenFreeBase));
+        assert_eq!(menu.validate_preparations(&preparations), Ok(()));
+    }
+}
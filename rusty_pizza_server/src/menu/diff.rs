@@ -0,0 +1,168 @@
+use crate::util::money::Money;
+use std::fmt;
+
+/// The price of a variety of a menu item changed between two versions of a menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PriceChange {
+    /// Number of the meal in the menu
+    meal_id: String,
+    variety: String,
+    /// `None` if the variety was not offered before
+    old_price: Option<Money>,
+    /// `None` if the variety is no longer offered
+    new_price: Option<Money>,
+}
+
+impl PriceChange {
+    pub fn new(
+        meal_id: String,
+        variety: String,
+        old_price: Option<Money>,
+        new_price: Option<Money>,
+    ) -> PriceChange {
+        PriceChange {
+            meal_id,
+            variety,
+            old_price,
+            new_price,
+        }
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    pub fn get_old_price(&self) -> Option<Money> {
+        self.old_price
+    }
+
+    pub fn get_new_price(&self) -> Option<Money> {
+        self.new_price
+    }
+}
+
+impl fmt::Display for PriceChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.old_price, self.new_price) {
+            (Some(old_price), Some(new_price)) => write!(
+                f,
+                "{} {}: {} -> {}",
+                self.meal_id, self.variety, old_price, new_price
+            ),
+            (None, Some(new_price)) => {
+                write!(
+                    f,
+                    "{} {}: new for {}",
+                    self.meal_id, self.variety, new_price
+                )
+            }
+            (Some(old_price), None) => write!(
+                f,
+                "{} {}: no longer offered (was {})",
+                self.meal_id, self.variety, old_price
+            ),
+            (None, None) => write!(f, "{} {}: unchanged", self.meal_id, self.variety),
+        }
+    }
+}
+
+/// Differences between two versions of a menu, see `Menu::diff`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuDiff {
+    /// Meal numbers only on the new menu
+    added: Vec<String>,
+    /// Meal numbers only on the old menu. Anything referencing them is stale.
+    removed: Vec<String>,
+    /// Price changes of meals on both menus
+    repriced: Vec<PriceChange>,
+}
+
+impl MenuDiff {
+    pub fn new(added: Vec<String>, removed: Vec<String>, repriced: Vec<PriceChange>) -> MenuDiff {
+        MenuDiff {
+            added,
+            removed,
+            repriced,
+        }
+    }
+
+    pub fn get_added(&self) -> &Vec<String> {
+        &self.added
+    }
+
+    pub fn get_removed(&self) -> &Vec<String> {
+        &self.removed
+    }
+
+    pub fn get_repriced(&self) -> &Vec<PriceChange> {
+        &self.repriced
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.repriced.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        old_price,
+        new_price,
+        expected,
+        case(
+            Some(Money::new(5, 50)),
+            Some(Money::new(5, 90)),
+            "03 groß: 5,50€ -> 5,90€"
+        ),
+        case(None, Some(Money::new(5, 90)), "03 groß: new for 5,90€"),
+        case(
+            Some(Money::new(5, 50)),
+            None,
+            "03 groß: no longer offered (was 5,50€)"
+        )
+    )]
+    fn price_change_is_formatted_for_admin(
+        old_price: Option<Money>,
+        new_price: Option<Money>,
+        expected: &str,
+    ) {
+        // Given:
+        let change = PriceChange::new(
+            String::from("03"),
+            String::from("groß"),
+            old_price,
+            new_price,
+        );
+
+        // When:
+        let formatted = change.to_string();
+
+        // Then:
+        assert_eq!(formatted, expected);
+    }
+
+    #[test]
+    fn diff_without_changes_is_empty() {
+        // When:
+        let diff = MenuDiff::new(vec![], vec![], vec![]);
+
+        // Then:
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn diff_with_removed_items_is_not_empty() {
+        // When:
+        let diff = MenuDiff::new(vec![], vec![String::from("03")], vec![]);
+
+        // Then:
+        assert!(!diff.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::order_model::tax::TaxRate;
+use crate::util::money::Money;
+use std::collections::HashMap;
+
+/// Options of which exactly one has to be chosen for a meal, e.g. the sauce of the pasta.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionGroup {
+    name: String,
+    options: Vec<String>,
+}
+
+impl OptionGroup {
+    pub fn new(name: String, options: Vec<String>) -> OptionGroup {
+        OptionGroup { name, options }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    /// The option matching the choice, ignoring case and surrounding whitespace.
+    pub fn find_option(&self, choice: &str) -> Option<&String> {
+        let choice = choice.trim().to_lowercase();
+        self.options
+            .iter()
+            .find(|option| option.to_lowercase() == choice)
+    }
+}
+
+/// A meal offered by a restaurant, with a price per variety.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuItem {
+    /// Number of the meal in the menu
+    meal_id: String,
+    name: String,
+    /// Price by variety, e.g. by size of the pizza
+    prices: HashMap<String, Money>,
+    /// Choices the restaurant needs for every order of the meal
+    option_groups: Vec<OptionGroup>,
+    /// VAT included in the prices, `None` if unknown
+    tax_rate: Option<TaxRate>,
+    /// Deposit (Pfand) charged on top of every price, e.g. for drinks in bottles
+    deposit: Money,
+}
+
+impl MenuItem {
+    pub fn new(meal_id: String, name: String) -> MenuItem {
+        MenuItem {
+            meal_id,
+            name,
+            prices: HashMap::new(),
+            option_groups: Vec::new(),
+            tax_rate: None,
+            deposit: Money::zero(),
+        }
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// Sets the price of the given variety and returns the previous one, if any.
+    pub fn set_price(&mut self, variety: String, price: Money) -> Option<Money> {
+        self.prices.insert(variety, price)
+    }
+
+    pub fn get_price(&self, variety: &str) -> Option<Money> {
+        self.prices.get(variety).copied()
+    }
+
+    pub fn varieties(&self) -> impl Iterator<Item = &String> {
+        self.prices.keys()
+    }
+
+    /// Requires one of the options of the group to be chosen whenever the meal is ordered.
+    pub fn add_option_group(&mut self, group: OptionGroup) {
+        self.option_groups.push(group);
+    }
+
+    pub fn option_groups(&self) -> &[OptionGroup] {
+        &self.option_groups
+    }
+
+    pub fn get_tax_rate(&self) -> Option<TaxRate> {
+        self.tax_rate
+    }
+
+    /// Sets the VAT included in the prices, which meals added from the menu take over.
+    pub fn set_tax_rate(&mut self, tax_rate: Option<TaxRate>) {
+        self.tax_rate = tax_rate;
+    }
+
+    pub fn get_deposit(&self) -> Money {
+        self.deposit
+    }
+
+    /// Sets the deposit charged on top of the prices, which meals added from the menu take over.
+    pub fn set_deposit(&mut self, deposit: Money) {
+        self.deposit = deposit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_item_can_be_created() {
+        // When:
+        let item = MenuItem::new(String::from("03"), String::from("Salami"));
+
+        // Then:
+        assert_eq!(item.get_meal_id(), "03");
+        assert_eq!(item.get_name(), "Salami");
+        assert_eq!(item.varieties().next(), None);
+    }
+
+    #[test]
+    fn price_can_be_set_per_variety() {
+        // Given:
+        let mut item = MenuItem::new(String::from("03"), String::from("Salami"));
+
+        // When:
+        item.set_price(String::from("klein"), Money::new(4, 50));
+        let previous = item.set_price(String::from("groß"), Money::new(5, 50));
+
+        // Then:
+        assert_eq!(previous, None);
+        assert_eq!(item.get_price("klein"), Some(Money::new(4, 50)));
+        assert_eq!(item.get_price("groß"), Some(Money::new(5, 50)));
+        assert_eq!(item.get_price("riesig"), None);
+    }
+
+    #[test]
+    fn option_is_found_ignoring_case() {
+        // Given:
+        let group = OptionGroup::new(
+            String::from("Soße"),
+            vec![String::from("Tomate"), String::from("Sahne")],
+        );
+
+        // Then:
+        assert_eq!(group.find_option(" sahne"), Some(&String::from("Sahne")));
+        assert_eq!(group.find_option("Pesto"), None);
+    }
+
+    #[test]
+    fn setting_price_again_returns_previous_price() {
+        // Given:
+        let mut item = MenuItem::new(String::from("03"), String::from("Salami"));
+        item.set_price(String::from("groß"), Money::new(5, 50));
+
+        // When:
+        let previous = item.set_price(String::from("groß"), Money::new(5, 90));
+
+        // Then:
+        assert_eq!(previous, Some(Money::new(5, 50)));
+        assert_eq!(item.get_price("groß"), Some(Money::new(5, 90)));
+    }
+}
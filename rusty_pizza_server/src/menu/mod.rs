@@ -0,0 +1,6 @@
+pub mod catalog;
+pub mod diff;
+pub mod item;
+pub mod repository;
+pub mod resolution;
+pub mod source;
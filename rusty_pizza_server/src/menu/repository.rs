@@ -0,0 +1,115 @@
+use crate::menu::catalog::Menu;
+use crate::menu::source::{MenuSource, MenuSourceError};
+use crate::util::errors::RemoveError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Menus of all known restaurants.
+#[derive(Debug, Default)]
+pub struct MenuRepository {
+    /// Menu by restaurant name
+    menus: HashMap<String, Arc<Menu>>,
+}
+
+impl MenuRepository {
+    pub fn new() -> MenuRepository {
+        MenuRepository::default()
+    }
+
+    /// Adds the given menu and returns the menu of the same restaurant it replaces, if any.
+    pub fn add_menu(&mut self, menu: Menu) -> Option<Arc<Menu>> {
+        self.menus
+            .insert(menu.get_restaurant().clone(), Arc::new(menu))
+    }
+
+    pub fn get_menu(&self, restaurant: &str) -> Option<Arc<Menu>> {
+        self.menus.get(restaurant).cloned()
+    }
+
+    pub fn remove_menu(&mut self, restaurant: &str) -> Result<Arc<Menu>, RemoveError> {
+        self.menus.remove(restaurant).ok_or(RemoveError::NotFound)
+    }
+
+    pub fn restaurants(&self) -> impl Iterator<Item = &String> {
+        self.menus.keys()
+    }
+}
+
+impl MenuSource for MenuRepository {
+    fn load_menu(&self, restaurant: &str) -> Result<Menu, MenuSourceError> {
+        self.menus
+            .get(restaurant)
+            .map(|menu| Menu::clone(menu))
+            .ok_or(MenuSourceError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_can_be_added_and_looked_up() {
+        // Given:
+        let mut repository = MenuRepository::new();
+
+        // When:
+        let replaced = repository.add_menu(Menu::new(String::from("Pizzeria Luigi")));
+
+        // Then:
+        assert_eq!(replaced, None);
+        assert_eq!(
+            repository.get_menu("Pizzeria Luigi"),
+            Some(Arc::new(Menu::new(String::from("Pizzeria Luigi"))))
+        );
+        assert_eq!(repository.get_menu("Pizzeria Mario"), None);
+        assert_eq!(
+            repository.restaurants().collect::<Vec<&String>>(),
+            vec!["Pizzeria Luigi"]
+        );
+    }
+
+    #[test]
+    fn adding_menu_of_same_restaurant_replaces_it() {
+        // Given:
+        let mut repository = MenuRepository::new();
+        repository.add_menu(Menu::new(String::from("Pizzeria Luigi")));
+
+        // When:
+        let replaced = repository.add_menu(Menu::new(String::from("Pizzeria Luigi")));
+
+        // Then:
+        assert!(replaced.is_some());
+        assert_eq!(repository.restaurants().count(), 1);
+    }
+
+    #[test]
+    fn menu_can_be_removed() {
+        // Given:
+        let mut repository = MenuRepository::new();
+        repository.add_menu(Menu::new(String::from("Pizzeria Luigi")));
+
+        // When:
+        let removed = repository.remove_menu("Pizzeria Luigi");
+        let removed_again = repository.remove_menu("Pizzeria Luigi");
+
+        // Then:
+        assert!(removed.is_ok());
+        assert_eq!(removed_again, Err(RemoveError::NotFound));
+    }
+
+    #[test]
+    fn repository_is_a_menu_source() {
+        // Given:
+        let mut repository = MenuRepository::new();
+        repository.add_menu(Menu::new(String::from("Pizzeria Luigi")));
+
+        // When:
+        let found = repository.load_menu("Pizzeria Luigi");
+        let missing = repository.load_menu("Pizzeria Mario");
+
+        // Then:
+        assert_eq!(found, Ok(Menu::new(String::from("Pizzeria Luigi"))));
+        assert_eq!(missing, Err(MenuSourceError::NotFound));
+    }
+}
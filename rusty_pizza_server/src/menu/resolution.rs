@@ -0,0 +1,127 @@
+use crate::menu::catalog::Menu;
+use crate::menu::item::MenuItem;
+use crate::util::money::Money;
+
+/// A meal found on another restaurant's menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedMeal {
+    /// Number of the meal in the new menu
+    meal_id: String,
+    /// Variety as spelled in the new menu
+    variety: String,
+    price: Money,
+}
+
+impl ResolvedMeal {
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    pub fn get_price(&self) -> Money {
+        self.price
+    }
+}
+
+/// Finds the meal `meal_id` in `variety` of the `old_menu` on the `new_menu`.
+///
+/// If the old menu knows the meal, it is looked up by name, since numbers differ between restaurants.
+/// Otherwise the number is used. Names and varieties are compared ignoring case and surrounding whitespace.
+pub fn resolve_meal(
+    old_menu: Option<&Menu>,
+    new_menu: &Menu,
+    meal_id: &str,
+    variety: &str,
+) -> Option<ResolvedMeal> {
+    let item = match old_menu.and_then(|menu| menu.get_item(meal_id)) {
+        Some(old_item) => new_menu
+            .items()
+            .find(|item| same_text(item.get_name(), old_item.get_name()))?,
+        None => new_menu.get_item(meal_id)?,
+    };
+    let variety = find_variety(item, variety)?;
+    Some(ResolvedMeal {
+        meal_id: item.get_meal_id().clone(),
+        price: item.get_price(variety)?,
+        variety: variety.clone(),
+    })
+}
+
+fn find_variety<'a>(item: &'a MenuItem, variety: &str) -> Option<&'a String> {
+    item.varieties()
+        .find(|candidate| same_text(candidate, variety))
+}
+
+fn same_text(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn menu(restaurant: &str, items: Vec<(&str, &str, &str, Money)>) -> Menu {
+        let mut menu = Menu::new(String::from(restaurant));
+        for (meal_id, name, variety, price) in items {
+            let mut item = MenuItem::new(String::from(meal_id), String::from(name));
+            item.set_price(String::from(variety), price);
+            menu.add_item(item);
+        }
+        menu
+    }
+
+    fn marios_menu() -> Menu {
+        menu(
+            "Pizzeria Mario",
+            vec![
+                ("03", "Margherita", "Groß", Money::new(6, 0)),
+                ("12", "salami ", "groß", Money::new(6, 50)),
+            ],
+        )
+    }
+
+    #[rstest(
+        old_menu,
+        meal_id,
+        variety,
+        expected,
+        case(
+            Some(menu("Pizzeria Luigi", vec![("03", "Salami", "groß", Money::new(5, 50))])),
+            "03",
+            "groß",
+            Some(("12", "groß", Money::new(6, 50)))
+        ),
+        case(None, "03", "groß", Some(("03", "Groß", Money::new(6, 0)))),
+        case(None, "03", "klein", None),
+        case(None, "99", "groß", None),
+        case(
+            Some(menu("Pizzeria Luigi", vec![("03", "Hawaii", "groß", Money::new(5, 50))])),
+            "03",
+            "groß",
+            None
+        )
+    )]
+    fn meal_is_resolved_on_new_menu(
+        old_menu: Option<Menu>,
+        meal_id: &str,
+        variety: &str,
+        expected: Option<(&str, &str, Money)>,
+    ) {
+        // When:
+        let resolved = resolve_meal(old_menu.as_ref(), &marios_menu(), meal_id, variety);
+
+        // Then:
+        assert_eq!(
+            resolved,
+            expected.map(|(meal_id, variety, price)| ResolvedMeal {
+                meal_id: String::from(meal_id),
+                variety: String::from(variety),
+                price,
+            })
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::menu::catalog::Menu;
+use crate::util::cache::TtlCache;
+use crate::util::clock::{Clock, SystemClock};
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuSourceError {
+    /// The source has no menu for the restaurant
+    NotFound,
+    /// The source could not be read, e.g. a file is missing or a remote service is down
+    Unavailable(String),
+}
+
+impl fmt::Display for MenuSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use MenuSourceError::*;
+        match self {
+            NotFound => write!(f, "Menu not found"),
+            Unavailable(reason) => write!(f, "Menu source unavailable: {}", reason),
+        }
+    }
+}
+
+impl Error for MenuSourceError {}
+
+/// Somewhere menus of restaurants can be loaded from, e.g. files or an external service.
+pub trait MenuSource {
+    fn load_menu(&self, restaurant: &str) -> Result<Menu, MenuSourceError>;
+}
+
+/// Keeps menus loaded from a `MenuSource` for a while, so browsing them doesn't hit the source on every request.
+pub struct CachedMenuSource<S: MenuSource, C: Clock = SystemClock> {
+    source: S,
+    cache: Mutex<TtlCache<String, Arc<Menu>>>,
+    /// Decides when cached menus expire
+    clock: C,
+}
+
+impl<S: MenuSource> CachedMenuSource<S> {
+    pub fn new(source: S, ttl: Duration) -> CachedMenuSource<S> {
+        CachedMenuSource::with_clock(source, ttl, SystemClock)
+    }
+}
+
+impl<S: MenuSource, C: Clock> CachedMenuSource<S, C> {
+    pub fn with_clock(source: S, ttl: Duration, clock: C) -> CachedMenuSource<S, C> {
+        CachedMenuSource {
+            source,
+            cache: Mutex::new(TtlCache::new(ttl)),
+            clock,
+        }
+    }
+
+    pub fn get_menu(&self, restaurant: &str) -> Result<Arc<Menu>, MenuSourceError> {
+        self.lock()
+            .get_or_try_insert_with(String::from(restaurant), self.clock.now(), || {
+                self.source.load_menu(restaurant).map(Arc::new)
+            })
+    }
+
+    /// Drops the cached menu of the given restaurant, e.g. after it was re-imported.
+    pub fn invalidate(&self, restaurant: &str) -> bool {
+        self.lock().invalidate(&String::from(restaurant))
+    }
+
+    pub fn invalidate_all(&self) {
+        self.lock().invalidate_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TtlCache<String, Arc<Menu>>> {
+        self.cache.lock().expect("Menu cache lock is poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::TestClock;
+    use std::cell::Cell;
+
+    struct CountingSource {
+        loads: Cell<u32>,
+    }
+
+    impl MenuSource for CountingSource {
+        fn load_menu(&self, restaurant: &str) -> Result<Menu, MenuSourceError> {
+            self.loads.set(self.loads.get() + 1);
+            match restaurant {
+                "Pizzeria Luigi" => Ok(Menu::new(String::from(restaurant))),
+                _ => Err(MenuSourceError::NotFound),
+            }
+        }
+    }
+
+    fn cached_source(clock: &TestClock) -> CachedMenuSource<CountingSource, TestClock> {
+        CachedMenuSource::with_clock(
+            CountingSource {
+                loads: Cell::new(0),
+            },
+            Duration::from_secs(300),
+            clock.clone(),
+        )
+    }
+
+    #[test]
+    fn menu_is_loaded_once_within_ttl() {
+        // Given:
+        let clock = TestClock::default();
+        let source = cached_source(&clock);
+
+        // When:
+        let first = source.get_menu("Pizzeria Luigi");
+        clock.advance(Duration::from_secs(299));
+        let second = source.get_menu("Pizzeria Luigi");
+
+        // Then:
+        assert_eq!(
+            first,
+            Ok(Arc::new(Menu::new(String::from("Pizzeria Luigi"))))
+        );
+        assert_eq!(first, second);
+        assert_eq!(source.source.loads.get(), 1);
+    }
+
+    #[test]
+    fn menu_is_reloaded_after_ttl() {
+        // Given:
+        let clock = TestClock::default();
+        let source = cached_source(&clock);
+        source.get_menu("Pizzeria Luigi").unwrap();
+
+        // When:
+        clock.advance(Duration::from_secs(300));
+        source.get_menu("Pizzeria Luigi").unwrap();
+
+        // Then:
+        assert_eq!(source.source.loads.get(), 2);
+    }
+
+    #[test]
+    fn menu_is_reloaded_after_invalidation() {
+        // Given:
+        let clock = TestClock::default();
+        let source = cached_source(&clock);
+        source.get_menu("Pizzeria Luigi").unwrap();
+
+        // When:
+        let invalidated = source.invalidate("Pizzeria Luigi");
+        source.get_menu("Pizzeria Luigi").unwrap();
+
+        // Then:
+        assert!(invalidated);
+        assert_eq!(source.source.loads.get(), 2);
+    }
+
+    #[test]
+    fn missing_menu_is_not_cached() {
+        // Given:
+        let clock = TestClock::default();
+        let source = cached_source(&clock);
+
+        // When:
+        let first = source.get_menu("Pizzeria Mario");
+        let second = source.get_menu("Pizzeria Mario");
+
+        // Then:
+        assert_eq!(first, Err(MenuSourceError::NotFound));
+        assert_eq!(second, Err(MenuSourceError::NotFound));
+        assert_eq!(source.source.loads.get(), 2);
+    }
+}
@@ -0,0 +1,487 @@
+use crate::notifications::template::{TemplateKey, TemplateRegistry, Variables};
+use crate::order_model::order::{Order, OrderStatus};
+use crate::order_model::report::Balance;
+use crate::order_model::restaurant::{OpeningHours, Restaurant};
+use crate::order_model::user::User;
+use crate::user_model::repository::UserRepository;
+use crate::util::id::Id;
+use crate::util::short_code::IdFormat;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::runtime::Handle;
+
+/// How long before the deadline an order is announced as closing soon, unless configured otherwise.
+pub const DEFAULT_LEAD_TIME_MINUTES: i64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnnouncementKind {
+    /// The order was created
+    Opened,
+    /// The deadline of the order is near
+    ClosingSoon,
+    /// The restaurant told when the order will be delivered
+    Eta,
+    /// The pizza arrived
+    Delivered,
+}
+
+impl AnnouncementKind {
+    fn get_template_key(&self) -> TemplateKey {
+        match self {
+            AnnouncementKind::Opened => TemplateKey::OrderOpened,
+            AnnouncementKind::ClosingSoon => TemplateKey::OrderClosingSoon,
+            AnnouncementKind::Eta => TemplateKey::DeliveryEta,
+            AnnouncementKind::Delivered => TemplateKey::OrderDelivered,
+        }
+    }
+}
+
+/// Somewhere announcements are posted to, e.g. a chat room.
+pub trait Channel: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    fn post(&self, message: &str);
+}
+
+/// Runs the delivery of a message to a channel in the background, so announcing never waits for the network.
+///
+/// Failures are logged, as there is nobody to tell who could retry.
+pub fn post_in_background<E: fmt::Display>(
+    channel: &str,
+    delivery: impl Future<Output = Result<(), E>> + Send + 'static,
+) {
+    let channel = String::from(channel);
+    match Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn(async move {
+                if let Err(error) = delivery.await {
+                    eprintln!("Announcement not posted to {}: {}", channel, error);
+                }
+            });
+        }
+        Err(_) => eprintln!("Announcement not posted to {}: no runtime", channel),
+    }
+}
+
+/// Posts announcements about orders to all configured channels, each at most once per order and kind.
+pub struct Announcer {
+    channels: Vec<Box<dyn Channel>>,
+    templates: TemplateRegistry,
+    /// Whose template overrides are used, the defaults if `None`
+    tenant: Option<String>,
+    /// Base URL of the server, the link to join is the URL of the order below it
+    base_url: String,
+    /// How the order is named in the texts, links always use the numeric ID
+    id_format: IdFormat,
+    lead_time: Duration,
+    announced: Mutex<HashSet<(Id<Order>, AnnouncementKind)>>,
+}
+
+impl Announcer {
+    pub fn new(base_url: String) -> Announcer {
+        Announcer {
+            channels: Vec::new(),
+            templates: TemplateRegistry::default(),
+            tenant: None,
+            base_url,
+            id_format: IdFormat::default(),
+            lead_time: Duration::minutes(DEFAULT_LEAD_TIME_MINUTES),
+            announced: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn add_channel(&mut self, channel: Box<dyn Channel>) {
+        self.channels.push(channel);
+    }
+
+    pub fn set_templates(&mut self, templates: TemplateRegistry) {
+        self.templates = templates;
+    }
+
+    pub fn set_tenant(&mut self, tenant: Option<String>) {
+        self.tenant = tenant;
+    }
+
+    pub fn set_id_format(&mut self, id_format: IdFormat) {
+        self.id_format = id_format;
+    }
+
+    pub fn set_lead_time(&mut self, lead_time: Duration) {
+        self.lead_time = lead_time;
+    }
+
+    /// Announces that the order was opened. Returns whether it was announced, i.e. not before.
+    pub fn announce_opened(&self, order_id: &Id<Order>, order: &Order) -> bool {
+        self.announce(
+            AnnouncementKind::Opened,
+            order_id,
+            self.order_variables(order_id, order),
+        )
+    }
+
+    /// Announces when an ordered order will be delivered. Returns whether it was announced, i.e. not before or
+    /// without ETA.
+    ///
+    /// The time is written in the time zone of the restaurant if its opening hours are known, in UTC otherwise.
+    pub fn announce_eta(&self, order_id: &Id<Order>, order: &Order) -> bool {
+        match order.get_status() {
+            OrderStatus::Ordered { eta: Some(eta), .. } => {
+                let offset = order
+                    .get_restaurant()
+                    .and_then(Restaurant::get_opening_hours)
+                    .map(OpeningHours::get_utc_offset)
+                    .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+                let eta = eta.with_timezone(&offset).format("%H:%M").to_string();
+                self.announce(
+                    AnnouncementKind::Eta,
+                    order_id,
+                    self.order_variables(order_id, order).with("eta", eta),
+                )
+            }
+            _ => false,
+        }
+    }
+
+    /// Announces that a delivered order arrived. Returns whether it was announced, i.e. not before.
+    pub fn announce_delivered(&self, order_id: &Id<Order>, order: &Order) -> bool {
+        match order.get_status() {
+            OrderStatus::Delivered => self.announce(
+                AnnouncementKind::Delivered,
+                order_id,
+                self.order_variables(order_id, order),
+            ),
+            _ => false,
+        }
+    }
+
+    /// The text announcing that the order arrived, e.g. for messages sent to the participants directly.
+    pub fn delivered_message(&self, order_id: &Id<Order>, order: &Order) -> String {
+        self.render(
+            TemplateKey::OrderDelivered,
+            &self.order_variables(order_id, order),
+        )
+    }
+
+    /// Reminders for everybody who still owes money, by user ID, to be sent to them directly.
+    pub fn payment_reminders(
+        &self,
+        order_id: &Id<Order>,
+        order: &Order,
+        users: &UserRepository,
+    ) -> Vec<(Id<User>, String)> {
+        let mut reminders: Vec<(Id<User>, String)> = order
+            .payment_report()
+            .users()
+            .iter()
+            .filter_map(|payment| match payment.get_balance() {
+                Balance::Owed(owed) => {
+                    let user_id = payment.get_user_id();
+                    let name = users
+                        .get_user(&user_id)
+                        .map(|user| user.get_name().clone())
+                        .unwrap_or_else(|| self.id_format.format(&user_id));
+                    let variables = self
+                        .order_variables(order_id, order)
+                        .with("user", name)
+                        .with("owed", owed);
+                    Some((
+                        user_id,
+                        self.render(TemplateKey::PaymentReminder, &variables),
+                    ))
+                }
+                Balance::Change(_) => None,
+            })
+            .collect();
+        reminders.sort_by_key(|(user_id, _)| user_id.get_value());
+        reminders
+    }
+
+    /// Announces all open orders whose deadline is within the lead time at `now` and returns how many.
+    ///
+    /// Meant to be called periodically, orders are only announced once.
+    pub fn announce_closing_soon<'a>(
+        &self,
+        now: DateTime<Utc>,
+        orders: impl Iterator<Item = (&'a Id<Order>, &'a Order)>,
+    ) -> usize {
+        let mut due: Vec<(&Id<Order>, &Order, Duration)> = orders
+            .filter(|(_, order)| order.get_status() == &OrderStatus::Open)
+            .filter_map(|(id, order)| {
+                order
+                    .time_until_deadline(now)
+                    .filter(|left| *left > Duration::zero() && *left <= self.lead_time)
+                    .map(|left| (id, order, left))
+            })
+            .collect();
+        due.sort_by_key(|(id, _, _)| *id);
+        due.into_iter()
+            .filter(|(id, order, left)| {
+                // Rounded up, so the last minute is announced as 1 and not 0
+                let minutes = (left.num_seconds() + 59) / 60;
+                self.announce(
+                    AnnouncementKind::ClosingSoon,
+                    id,
+                    self.order_variables(id, order).with("minutes", minutes),
+                )
+            })
+            .count()
+    }
+
+    fn announce(&self, kind: AnnouncementKind, order_id: &Id<Order>, variables: Variables) -> bool {
+        let first = self
+            .announced
+            .lock()
+            .expect("Announcements lock is poisoned")
+            .insert((order_id.clone(), kind));
+        if first {
+            let message = self.render(kind.get_template_key(), &variables);
+            for channel in &self.channels {
+                channel.post(&message);
+            }
+        }
+        first
+    }
+
+    /// Variables every text about an order can use.
+    fn order_variables(&self, order_id: &Id<Order>, order: &Order) -> Variables {
+        let link = format!(
+            "{}/orders/{}",
+            self.base_url.trim_end_matches('/'),
+            order_id.get_value()
+        );
+        Variables::new()
+            .with("order", self.id_format.format(order_id))
+            .with("participants", order.participants().count())
+            .with("link", link)
+    }
+
+    fn render(&self, key: TemplateKey, variables: &Variables) -> String {
+        self.templates
+            .render(self.tenant.as_deref(), key, variables)
+            .expect("Templates only use the variables of their key")
+    }
+}
+
+impl Default for Announcer {
+    fn default() -> Announcer {
+        Announcer::new(String::from("http://localhost:8080"))
+    }
+}
+
+impl fmt::Debug for Announcer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Announcer")
+            .field(
+                "channels",
+                &self
+                    .channels
+                    .iter()
+                    .map(|c| c.get_name())
+                    .collect::<Vec<_>>(),
+            )
+            .field("templates", &self.templates)
+            .field("tenant", &self.tenant)
+            .field("base_url", &self.base_url)
+            .field("lead_time", &self.lead_time)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::template::Template;
+    use crate::util::money::Money;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Channel for RecordingChannel {
+        fn get_name(&self) -> &str {
+            "recording"
+        }
+
+        fn post(&self, message: &str) {
+            self.messages.lock().unwrap().push(String::from(message));
+        }
+    }
+
+    fn announcer() -> (Announcer, Arc<Mutex<Vec<String>>>) {
+        let channel = RecordingChannel::default();
+        let messages = channel.messages.clone();
+        let mut announcer = Announcer::new(String::from("https://pizza.example/"));
+        announcer.add_channel(Box::new(channel));
+        (announcer, messages)
+    }
+
+    #[test]
+    fn opened_order_is_announced_once() {
+        // Given:
+        let (announcer, messages) = announcer();
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let first = announcer.announce_opened(&Id::new(4), &order);
+        let second = announcer.announce_opened(&Id::new(4), &order);
+
+        // Then:
+        assert!(first);
+        assert!(!second);
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec!["Pizza order 4 is open, 2 joined so far: https://pizza.example/orders/4"]
+        );
+    }
+
+    #[test]
+    fn order_can_be_announced_by_short_code() {
+        // Given:
+        let (mut announcer, messages) = announcer();
+        announcer.set_id_format(IdFormat::ShortCode);
+
+        // When:
+        announcer.announce_opened(&Id::new(4), &Order::new(Id::new(0)));
+
+        // Then:
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec!["Pizza order ORD-004C is open, 1 joined so far: https://pizza.example/orders/4"]
+        );
+    }
+
+    #[test]
+    fn orders_closing_soon_are_announced() {
+        // Given:
+        let (mut announcer, messages) = announcer();
+        let mut templates = TemplateRegistry::new();
+        templates
+            .set_default(
+                TemplateKey::OrderClosingSoon,
+                Template::parse("{{order}}: {{minutes}} min").unwrap(),
+            )
+            .unwrap();
+        announcer.set_templates(templates);
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 11, 50, 0).unwrap();
+        let mut orders = Vec::new();
+        for (minutes, start_ordering) in [(5, false), (30, false), (-1, false), (3, true)] {
+            let mut order = Order::new(Id::new(0));
+            order
+                .set_deadline(Some(
+                    now + Duration::minutes(minutes) + Duration::seconds(1),
+                ))
+                .unwrap();
+            if start_ordering {
+                order.start_ordering().unwrap();
+            }
+            orders.push((Id::new(orders.len() as u32), order));
+        }
+
+        // When:
+        let announced = announcer.announce_closing_soon(now, orders.iter().map(|(id, o)| (id, o)));
+        let announced_again =
+            announcer.announce_closing_soon(now, orders.iter().map(|(id, o)| (id, o)));
+
+        // Then:
+        assert_eq!(announced, 1);
+        assert_eq!(announced_again, 0);
+        assert_eq!(*messages.lock().unwrap(), vec!["0: 6 min"]);
+    }
+
+    #[test]
+    fn eta_is_announced_with_tenant_wording() {
+        // Given:
+        let (mut announcer, messages) = announcer();
+        let mut templates = TemplateRegistry::new();
+        templates
+            .set_override(
+                "kitchen",
+                TemplateKey::DeliveryEta,
+                Template::parse("Bestellung {{order}} kommt um {{eta}}").unwrap(),
+            )
+            .unwrap();
+        announcer.set_templates(templates);
+        announcer.set_tenant(Some(String::from("kitchen")));
+        let mut order = Order::new(Id::new(0));
+        let open = announcer.announce_eta(&Id::new(2), &order);
+        order.start_ordering().unwrap();
+        let eta = Utc.with_ymd_and_hms(2020, 5, 4, 12, 15, 0).unwrap();
+        order.mark_ordered(Some(eta)).unwrap();
+
+        // When:
+        let announced = announcer.announce_eta(&Id::new(2), &order);
+
+        // Then:
+        assert!(!open);
+        assert!(announced);
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec!["Bestellung 2 kommt um 12:15"]
+        );
+    }
+
+    #[test]
+    fn arrival_is_announced_once_delivered() {
+        // Given:
+        let (announcer, messages) = announcer();
+        let mut order = Order::new(Id::new(0));
+        order.start_ordering().unwrap();
+        order.mark_ordered(None).unwrap();
+        let ordered = announcer.announce_delivered(&Id::new(5), &order);
+        order.mark_delivered(std::time::SystemTime::now()).unwrap();
+
+        // When:
+        let delivered = announcer.announce_delivered(&Id::new(5), &order);
+
+        // Then:
+        assert!(!ordered);
+        assert!(delivered);
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec!["Pizza order 5 has arrived, see what you owe: https://pizza.example/orders/5"]
+        );
+    }
+
+    #[test]
+    fn users_who_owe_money_are_reminded() {
+        // Given:
+        let (announcer, _) = announcer();
+        let mut users = UserRepository::new();
+        let anna = users.register(String::from("Anna")).unwrap().get_id();
+        let ben = users.register(String::from("Ben")).unwrap().get_id();
+        let mut order = Order::new(anna.clone());
+        order.add_user(ben.clone()).unwrap();
+        for user_id in [&anna, &ben] {
+            order
+                .add_meal_for_user(
+                    user_id.clone(),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(7, 50),
+                )
+                .unwrap();
+        }
+        order.set_paid_for_user(anna, Money::new(7, 50)).unwrap();
+
+        // When:
+        let reminders = announcer.payment_reminders(&Id::new(1), &order, &users);
+
+        // Then:
+        assert_eq!(
+            reminders,
+            vec![(
+                ben,
+                String::from(
+                    "Hi Ben, you still owe 7,50€ for pizza order 1: https://pizza.example/orders/1"
+                )
+            )]
+        );
+    }
+}
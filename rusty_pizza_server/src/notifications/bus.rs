@@ -0,0 +1,105 @@
+use crate::notifications::event::OrderEvent;
+use crate::notifications::queue::{RealtimeMetrics, DEFAULT_QUEUE_LIMIT};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Number of events a slow subscriber may fall behind before it misses some.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Distributes `OrderEvent`s to everybody who subscribed, e.g. open WebSocket connections.
+///
+/// Subscribers read the bus quickly into an `EventQueue` of their own, which limits how far a slow client may
+/// fall behind.
+#[derive(Clone, Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<OrderEvent>,
+    /// Limit of the queue of every connection
+    queue_limit: usize,
+    metrics: Arc<RealtimeMetrics>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> EventBus {
+        let (sender, _) = broadcast::channel(capacity);
+        EventBus {
+            sender,
+            queue_limit: DEFAULT_QUEUE_LIMIT,
+            metrics: Arc::default(),
+        }
+    }
+
+    pub fn with_queue_limit(mut self, queue_limit: usize) -> EventBus {
+        self.queue_limit = queue_limit;
+        self
+    }
+
+    pub fn get_queue_limit(&self) -> usize {
+        self.queue_limit
+    }
+
+    /// Counters of the connections receiving the events.
+    pub fn metrics(&self) -> &RealtimeMetrics {
+        &self.metrics
+    }
+
+    pub fn get_metrics(&self) -> Arc<RealtimeMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Sends the event to all current subscribers and returns how many there are.
+    pub fn publish(&self, event: OrderEvent) -> usize {
+        // Without subscribers nobody is interested, which is not an error
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Receives all events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> EventBus {
+        EventBus::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_joined() -> OrderEvent {
+        OrderEvent::UserJoined {
+            order_id: 0,
+            user_id: 1,
+        }
+    }
+
+    #[test]
+    fn subscribers_receive_published_events() {
+        // Given:
+        let bus = EventBus::default();
+        let mut first = bus.subscribe();
+        let mut second = bus.clone().subscribe();
+
+        // When:
+        let receivers = bus.publish(user_joined());
+
+        // Then:
+        assert_eq!(receivers, 2);
+        assert_eq!(first.try_recv(), Ok(user_joined()));
+        assert_eq!(second.try_recv(), Ok(user_joined()));
+    }
+
+    #[test]
+    fn events_can_be_published_without_subscribers() {
+        // Given:
+        let bus = EventBus::default();
+
+        // When:
+        let receivers = bus.publish(user_joined());
+
+        // Then:
+        assert_eq!(receivers, 0);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::notifications::announcement::{post_in_background, Channel};
+use chrono::Utc;
+use std::error::Error;
+use std::fmt;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Subject of the mails, unless configured otherwise.
+pub const DEFAULT_SUBJECT: &str = "Pizza";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MailError {
+    /// Not an address like "pizza@example.com"
+    InvalidAddress(String),
+    NoRecipients,
+    /// The mail server could not be reached or closed the connection
+    Connection(String),
+    /// The mail server answered a command with an error
+    Rejected {
+        command: String,
+        reply: String,
+    },
+}
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use MailError::*;
+        match self {
+            InvalidAddress(address) => write!(f, "{} is not a mail address", address),
+            NoRecipients => write!(f, "no recipients"),
+            Connection(error) => write!(f, "mail server could not be reached: {}", error),
+            Rejected { command, reply } => write!(f, "mail server rejected {}: {}", command, reply),
+        }
+    }
+}
+
+impl Error for MailError {}
+
+/// Mails announcements via SMTP to fixed recipients, e.g. the mailing list of the team.
+///
+/// The server only speaks plain SMTP without authentication, so it is meant to hand the mails to a relay in the
+/// local network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmtpMailer {
+    /// "host:port" of the mail server
+    server: String,
+    from: String,
+    recipients: Vec<String>,
+    subject: String,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        server: String,
+        from: String,
+        recipients: Vec<String>,
+    ) -> Result<SmtpMailer, MailError> {
+        if recipients.is_empty() {
+            return Err(MailError::NoRecipients);
+        }
+        for address in recipients.iter().chain(Some(&from)) {
+            validate_address(address)?;
+        }
+        Ok(SmtpMailer {
+            server,
+            from,
+            recipients,
+            subject: String::from(DEFAULT_SUBJECT),
+        })
+    }
+
+    /// Only ASCII is sent as is, so the subject should not contain umlauts.
+    pub fn with_subject(mut self, subject: String) -> SmtpMailer {
+        self.subject = subject;
+        self
+    }
+
+    pub fn get_server(&self) -> &String {
+        &self.server
+    }
+
+    pub fn get_from(&self) -> &String {
+        &self.from
+    }
+
+    pub fn recipients(&self) -> &[String] {
+        &self.recipients
+    }
+
+    pub fn get_subject(&self) -> &String {
+        &self.subject
+    }
+
+    /// Mails the message to all recipients and waits until the server accepted it.
+    pub async fn send(&self, message: &str) -> Result<(), MailError> {
+        let connection_error = |error: std::io::Error| MailError::Connection(error.to_string());
+        let stream = TcpStream::connect(self.server.as_str())
+            .await
+            .map_err(connection_error)?;
+        let mut session = SmtpSession {
+            stream: BufReader::new(stream),
+        };
+        session.expect_reply("connect", "2").await?;
+        session.command("HELO rusty-pizza", "2").await?;
+        session
+            .command(&format!("MAIL FROM:<{}>", self.from), "2")
+            .await?;
+        for recipient in &self.recipients {
+            session
+                .command(&format!("RCPT TO:<{}>", recipient), "2")
+                .await?;
+        }
+        session.command("DATA", "3").await?;
+        session.write_line(&self.content(message)).await?;
+        session.expect_reply("DATA", "2").await?;
+        session.command("QUIT", "2").await
+    }
+
+    /// Headers and body of the mail, terminated by the line with the single dot.
+    fn content(&self, message: &str) -> String {
+        let mut content = format!(
+            "Date: {}\r\nFrom: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+            Utc::now().to_rfc2822(),
+            self.from,
+            self.recipients.join(", "),
+            self.subject.replace(['\r', '\n'], " ")
+        );
+        for line in message.lines() {
+            // Lines starting with a dot are escaped, as a single dot ends the mail
+            if line.starts_with('.') {
+                content.push('.');
+            }
+            content.push_str(line);
+            content.push_str("\r\n");
+        }
+        content.push('.');
+        content
+    }
+}
+
+impl Channel for SmtpMailer {
+    fn get_name(&self) -> &str {
+        "mail"
+    }
+
+    fn post(&self, message: &str) {
+        let mailer = self.clone();
+        let message = String::from(message);
+        post_in_background(self.get_name(), async move { mailer.send(&message).await });
+    }
+}
+
+fn validate_address(address: &str) -> Result<(), MailError> {
+    let valid = match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !address
+                    .chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || c == '<' || c == '>')
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(MailError::InvalidAddress(String::from(address)))
+    }
+}
+
+struct SmtpSession {
+    stream: BufReader<TcpStream>,
+}
+
+impl SmtpSession {
+    /// Sends the command and checks that the reply code starts with `expected`.
+    async fn command(&mut self, command: &str, expected: &str) -> Result<(), MailError> {
+        self.write_line(command).await?;
+        let name = command.split([' ', ':']).next().unwrap_or_default();
+        self.expect_reply(name, expected).await
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), MailError> {
+        self.stream
+            .get_mut()
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .await
+            .map_err(|error| MailError::Connection(error.to_string()))
+    }
+
+    /// Reads a reply, which spans several lines like "250-first" up to "250 last".
+    async fn expect_reply(&mut self, command: &str, expected: &str) -> Result<(), MailError> {
+        loop {
+            let mut line = String::new();
+            let read = self
+                .stream
+                .read_line(&mut line)
+                .await
+                .map_err(|error| MailError::Connection(error.to_string()))?;
+            if read == 0 {
+                return Err(MailError::Connection(String::from("connection closed")));
+            }
+            if line.get(3..4) == Some("-") {
+                continue;
+            }
+            return if line.starts_with(expected) {
+                Ok(())
+            } else {
+                Err(MailError::Rejected {
+                    command: String::from(command),
+                    reply: String::from(line.trim_end()),
+                })
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tokio::net::TcpListener;
+
+    /// Mail server accepting one mail, returning the received lines.
+    async fn serve_one_mail(listener: TcpListener) -> Vec<String> {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        stream
+            .get_mut()
+            .write_all(b"220-mail.example\r\n220 ready\r\n")
+            .await
+            .unwrap();
+        let mut received = Vec::new();
+        let mut in_data = false;
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            let line = String::from(line.trim_end_matches("\r\n"));
+            let reply: &[u8] = if in_data {
+                in_data = line != ".";
+                if in_data {
+                    b""
+                } else {
+                    b"250 queued\r\n"
+                }
+            } else if line == "DATA" {
+                in_data = true;
+                b"354 go ahead\r\n"
+            } else if line == "QUIT" {
+                b"221 bye\r\n"
+            } else {
+                b"250 ok\r\n"
+            };
+            stream.get_mut().write_all(reply).await.unwrap();
+            received.push(line);
+        }
+        received
+    }
+
+    #[rstest(
+        from,
+        recipients,
+        expected,
+        case("pizza@example.com", vec!["team@example.com"], Ok(())),
+        case("pizza", vec!["team@example.com"], Err(MailError::InvalidAddress(String::from("pizza")))),
+        case(
+            "pizza@example.com",
+            vec!["team@example.com>\r\nRCPT TO:<x@example.com"],
+            Err(MailError::InvalidAddress(String::from("team@example.com>\r\nRCPT TO:<x@example.com")))
+        ),
+        case("pizza@example.com", vec![], Err(MailError::NoRecipients))
+    )]
+    fn addresses_are_validated(from: &str, recipients: Vec<&str>, expected: Result<(), MailError>) {
+        // When:
+        let mailer = SmtpMailer::new(
+            String::from("localhost:25"),
+            String::from(from),
+            recipients.into_iter().map(String::from).collect(),
+        );
+
+        // Then:
+        assert_eq!(mailer.map(|_| ()), expected);
+    }
+
+    #[tokio::test]
+    async fn message_is_mailed_to_all_recipients() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mailer = SmtpMailer::new(
+            listener.local_addr().unwrap().to_string(),
+            String::from("pizza@example.com"),
+            vec![
+                String::from("anna@example.com"),
+                String::from("ben@example.com"),
+            ],
+        )
+        .unwrap()
+        .with_subject(String::from("Pizza time"));
+        let server = tokio::spawn(serve_one_mail(listener));
+
+        // When:
+        let sent = mailer
+            .send("Pizza order 4 has arrived\n.nothing else")
+            .await;
+
+        // Then:
+        assert_eq!(sent, Ok(()));
+        let received = server.await.unwrap();
+        assert_eq!(
+            received[..4],
+            [
+                "HELO rusty-pizza",
+                "MAIL FROM:<pizza@example.com>",
+                "RCPT TO:<anna@example.com>",
+                "RCPT TO:<ben@example.com>"
+            ]
+        );
+        assert!(received.contains(&String::from("To: anna@example.com, ben@example.com")));
+        assert!(received.contains(&String::from("Subject: Pizza time")));
+        let body_start = received.iter().position(String::is_empty).unwrap() + 1;
+        assert_eq!(
+            received[body_start..],
+            ["Pizza order 4 has arrived", "..nothing else", ".", "QUIT"]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_recipient_fails_the_mail() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mailer = SmtpMailer::new(
+            listener.local_addr().unwrap().to_string(),
+            String::from("pizza@example.com"),
+            vec![String::from("unknown@example.com")],
+        )
+        .unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            stream.get_mut().write_all(b"220 ready\r\n").await.unwrap();
+            for reply in [&b"250 ok\r\n"[..], b"250 ok\r\n", b"550 no such user\r\n"] {
+                stream.read_line(&mut String::new()).await.unwrap();
+                stream.get_mut().write_all(reply).await.unwrap();
+            }
+        });
+
+        // When:
+        let sent = mailer.send("Pizza order 4 has arrived").await;
+
+        // Then:
+        assert_eq!(
+            sent,
+            Err(MailError::Rejected {
+                command: String::from("RCPT"),
+                reply: String::from("550 no such user")
+            })
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use crate::order_model::audit::Mutation;
+use crate::order_model::order::{Order, OrderStatus};
+use crate::util::money::Money;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Something that happened to an order which participants should see live.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OrderEvent {
+    UserJoined {
+        order_id: u32,
+        user_id: u32,
+    },
+    MealAdded {
+        order_id: u32,
+        /// `None` for office meals
+        user_id: Option<u32>,
+        meal_id: String,
+        variety: String,
+    },
+    StatusChanged {
+        order_id: u32,
+        status: String,
+    },
+    PaymentRecorded {
+        order_id: u32,
+        user_id: u32,
+        amount_cents: u32,
+    },
+    /// The order was not delivered by the time the restaurant expected
+    EtaPassed {
+        order_id: u32,
+        /// RFC 3339 time in UTC
+        eta: String,
+    },
+}
+
+impl OrderEvent {
+    pub fn get_order_id(&self) -> u32 {
+        use OrderEvent::*;
+        match self {
+            UserJoined { order_id, .. }
+            | MealAdded { order_id, .. }
+            | StatusChanged { order_id, .. }
+            | PaymentRecorded { order_id, .. }
+            | EtaPassed { order_id, .. } => *order_id,
+        }
+    }
+
+    /// The events published for the changes made to the order after the sequence number, e.g. for a client which
+    /// reconnects after missing them. `None` if the order doesn't know the sequence number anymore.
+    ///
+    /// Changes without event, e.g. of a tip, are skipped. Confirmed and released payments are published with the
+    /// amounts claimed or held before, so the whole history is gone through.
+    pub fn replay(order_id: u32, order: &Order, sequence_number: usize) -> Option<Vec<OrderEvent>> {
+        order.events_since(sequence_number)?;
+        let mut claimed = HashMap::new();
+        let mut held = HashMap::new();
+        let mut events = Vec::new();
+        for (index, change) in order.history().iter().enumerate() {
+            let event = match change.get_mutation() {
+                Mutation::UserAdded(user_id) => Some(OrderEvent::UserJoined {
+                    order_id,
+                    user_id: user_id.get_value(),
+                }),
+                Mutation::MealAdded {
+                    user_id,
+                    meal_id,
+                    variety,
+                    ..
+                } => Some(OrderEvent::MealAdded {
+                    order_id,
+                    user_id: Some(user_id.get_value()),
+                    meal_id: meal_id.clone(),
+                    variety: variety.clone(),
+                }),
+                Mutation::OfficeMealAdded {
+                    meal_id, variety, ..
+                }
+                | Mutation::SharedMealAdded {
+                    meal_id, variety, ..
+                } => Some(OrderEvent::MealAdded {
+                    order_id,
+                    user_id: None,
+                    meal_id: meal_id.clone(),
+                    variety: variety.clone(),
+                }),
+                Mutation::StatusChanged(status) => Some(OrderEvent::StatusChanged {
+                    order_id,
+                    status: status.to_string(),
+                }),
+                Mutation::Delivered(_) => Some(OrderEvent::StatusChanged {
+                    order_id,
+                    status: OrderStatus::Delivered.to_string(),
+                }),
+                Mutation::PaidSet {
+                    user_id,
+                    paid: amount,
+                }
+                | Mutation::PaymentAdded {
+                    user_id, amount, ..
+                } => Some(payment_recorded(order_id, user_id.get_value(), *amount)),
+                Mutation::PaymentClaimed {
+                    user_id, amount, ..
+                } => {
+                    claimed.insert(user_id.clone(), *amount);
+                    None
+                }
+                Mutation::PaymentConfirmed { user_id, .. } => claimed
+                    .remove(user_id)
+                    .map(|amount| payment_recorded(order_id, user_id.get_value(), amount)),
+                Mutation::PaymentHeld {
+                    user_id,
+                    id,
+                    amount,
+                    ..
+                } => {
+                    held.insert((user_id.clone(), id.clone()), *amount);
+                    None
+                }
+                Mutation::HeldPaymentReleased { user_id, id } => held
+                    .remove(&(user_id.clone(), id.clone()))
+                    .map(|amount| payment_recorded(order_id, user_id.get_value(), amount)),
+                _ => None,
+            };
+            if index >= sequence_number {
+                events.extend(event);
+            }
+        }
+        Some(events)
+    }
+
+    /// Whether the event is kept for slow clients, as a resync would not tell them what happened.
+    ///
+    /// A client learns from a resync that meals were added, but not that the order was just placed, which it may
+    /// want to notify its user about.
+    pub fn is_critical(&self) -> bool {
+        matches!(
+            self,
+            OrderEvent::StatusChanged { .. }
+                | OrderEvent::PaymentRecorded { .. }
+                | OrderEvent::EtaPassed { .. }
+        )
+    }
+}
+
+fn payment_recorded(order_id: u32, user_id: u32, amount: Money) -> OrderEvent {
+    OrderEvent::PaymentRecorded {
+        order_id,
+        user_id,
+        amount_cents: amount.get_total_cents(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::id::Id;
+
+    #[test]
+    fn event_is_serialized_with_type() {
+        // Given:
+        let event = OrderEvent::UserJoined {
+            order_id: 3,
+            user_id: 1,
+        };
+
+        // When:
+        let json = serde_json::to_string(&event).unwrap();
+
+        // Then:
+        assert_eq!(json, r#"{"type":"UserJoined","order_id":3,"user_id":1}"#);
+        assert_eq!(event.get_order_id(), 3);
+    }
+
+    #[test]
+    fn missed_changes_are_replayed_as_events() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        let seen = order.get_sequence_number();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 50),
+            )
+            .unwrap();
+        order
+            .set_tip_for_user(Id::new(1), Money::new(1, 0))
+            .unwrap();
+        order.start_ordering().unwrap();
+        order
+            .claim_payment_for_user(Id::new(1), Money::new(8, 50), String::from("PayPal"))
+            .unwrap();
+        order.confirm_payment_for_user(Id::new(1)).unwrap();
+
+        // When:
+        let events = OrderEvent::replay(3, &order, seen).unwrap();
+
+        // Then:
+        assert_eq!(
+            events,
+            vec![
+                OrderEvent::MealAdded {
+                    order_id: 3,
+                    user_id: Some(1),
+                    meal_id: String::from("03"),
+                    variety: String::from("groß"),
+                },
+                OrderEvent::StatusChanged {
+                    order_id: 3,
+                    status: String::from("Ordering"),
+                },
+                OrderEvent::PaymentRecorded {
+                    order_id: 3,
+                    user_id: 1,
+                    amount_cents: 850,
+                },
+            ]
+        );
+        assert_eq!(OrderEvent::replay(3, &order, 100), None);
+    }
+}
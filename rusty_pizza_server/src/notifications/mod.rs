@@ -0,0 +1,9 @@
+pub mod announcement;
+pub mod bus;
+pub mod email;
+pub mod event;
+pub mod queue;
+pub mod signage;
+pub mod slack;
+pub mod template;
+pub mod web_push;
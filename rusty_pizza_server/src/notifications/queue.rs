@@ -0,0 +1,276 @@
+use crate::notifications::event::OrderEvent;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Number of messages a connection may fall behind before events are dropped, unless configured otherwise.
+pub const DEFAULT_QUEUE_LIMIT: usize = 32;
+
+/// What is sent to a connection next.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Event(OrderEvent),
+    /// Events were dropped, so the client has to fetch the order again to be up to date
+    Resync {
+        order_id: u32,
+    },
+}
+
+impl Delivery {
+    /// The JSON text message, a resync as `{"type":"Resync","order_id":...}` like the events.
+    pub fn to_json(&self) -> String {
+        match self {
+            Delivery::Event(event) => {
+                serde_json::to_string(event).expect("Events are always serializable")
+            }
+            // Written by hand to have the type first, like serde does for the events
+            Delivery::Resync { order_id } => {
+                format!(r#"{{"type":"Resync","order_id":{}}}"#, order_id)
+            }
+        }
+    }
+}
+
+/// Messages waiting to be sent to one connection, bounded so a slow client can't make the server buffer endlessly.
+///
+/// If the queue is full, non-critical events are dropped and replaced by a single resync marker. Critical events
+/// are only dropped if the queue holds nothing else.
+#[derive(Debug)]
+pub struct EventQueue {
+    order_id: u32,
+    limit: usize,
+    pending: VecDeque<Delivery>,
+}
+
+impl EventQueue {
+    pub fn new(order_id: u32, limit: usize) -> EventQueue {
+        EventQueue {
+            order_id,
+            limit: limit.max(1),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Queues the event and returns how many events were dropped to make room.
+    ///
+    /// Non-critical events are dropped while a resync is waiting, as the client fetches them with the order anyway.
+    pub fn push(&mut self, event: OrderEvent) -> usize {
+        if !event.is_critical() && self.is_resync_pending() {
+            return 1;
+        }
+        if self.pending.len() < self.limit {
+            self.pending.push_back(Delivery::Event(event));
+            return 0;
+        }
+        let before = self.pending.len();
+        self.pending.retain(|delivery| match delivery {
+            Delivery::Event(event) => event.is_critical(),
+            Delivery::Resync { .. } => false,
+        });
+        // A full queue of critical events is given up as well, the resync replaces them all
+        if self.pending.len() + 1 >= self.limit {
+            self.pending.clear();
+        }
+        let mut dropped = before - self.pending.len();
+        self.pending.push_back(Delivery::Resync {
+            order_id: self.order_id,
+        });
+        if event.is_critical() && self.pending.len() < self.limit {
+            self.pending.push_back(Delivery::Event(event));
+        } else {
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// Marks that events were missed before they could be queued, e.g. because the bus overflowed.
+    pub fn push_resync(&mut self) {
+        self.pending
+            .retain(|delivery| !matches!(delivery, Delivery::Resync { .. }));
+        if self.pending.len() >= self.limit {
+            self.pending.clear();
+        }
+        self.pending.push_back(Delivery::Resync {
+            order_id: self.order_id,
+        });
+    }
+
+    fn is_resync_pending(&self) -> bool {
+        self.pending
+            .iter()
+            .any(|delivery| matches!(delivery, Delivery::Resync { .. }))
+    }
+
+    pub fn pop(&mut self) -> Option<Delivery> {
+        self.pending.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Counters of all realtime connections, to see whether clients keep up.
+#[derive(Debug, Default)]
+pub struct RealtimeMetrics {
+    connections: AtomicUsize,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    resyncs: AtomicU64,
+}
+
+impl RealtimeMetrics {
+    pub fn connected(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn disconnected(&self) {
+        self.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Counts a message sent to a client, resync markers included.
+    pub fn delivered(&self, delivery: &Delivery) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        if let Delivery::Resync { .. } = delivery {
+            self.resyncs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn dropped(&self, count: usize) {
+        self.dropped.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn get_connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn get_delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    pub fn get_dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn get_resyncs(&self) -> u64 {
+        self.resyncs.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meal_added(meal_id: &str) -> OrderEvent {
+        OrderEvent::MealAdded {
+            order_id: 0,
+            user_id: Some(1),
+            meal_id: String::from(meal_id),
+            variety: String::from("groß"),
+        }
+    }
+
+    fn ordered() -> OrderEvent {
+        OrderEvent::StatusChanged {
+            order_id: 0,
+            status: String::from("ordered"),
+        }
+    }
+
+    fn drain(queue: &mut EventQueue) -> Vec<Delivery> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn events_are_queued_up_to_limit() {
+        // Given:
+        let mut queue = EventQueue::new(0, 3);
+
+        // When:
+        let dropped: usize = ["01", "02", "03"]
+            .iter()
+            .map(|meal| queue.push(meal_added(meal)))
+            .sum();
+
+        // Then:
+        assert_eq!(dropped, 0);
+        assert_eq!(
+            drain(&mut queue),
+            vec![
+                Delivery::Event(meal_added("01")),
+                Delivery::Event(meal_added("02")),
+                Delivery::Event(meal_added("03"))
+            ]
+        );
+    }
+
+    #[test]
+    fn full_queue_keeps_critical_events_and_coalesces_the_rest() {
+        // Given:
+        let mut queue = EventQueue::new(0, 4);
+        queue.push(meal_added("01"));
+        queue.push(ordered());
+        queue.push(meal_added("02"));
+        queue.push(meal_added("03"));
+
+        // When:
+        let first = queue.push(meal_added("04"));
+        let second = queue.push(meal_added("05"));
+
+        // Then:
+        assert_eq!(first, 4);
+        assert_eq!(second, 1);
+        assert_eq!(
+            drain(&mut queue),
+            vec![Delivery::Event(ordered()), Delivery::Resync { order_id: 0 }]
+        );
+    }
+
+    #[test]
+    fn critical_event_is_queued_after_resync() {
+        // Given:
+        let mut queue = EventQueue::new(0, 3);
+        for meal in ["01", "02", "03"] {
+            queue.push(meal_added(meal));
+        }
+
+        // When:
+        let dropped = queue.push(ordered());
+
+        // Then:
+        assert_eq!(dropped, 3);
+        assert_eq!(
+            drain(&mut queue),
+            vec![Delivery::Resync { order_id: 0 }, Delivery::Event(ordered())]
+        );
+    }
+
+    #[test]
+    fn queue_full_of_critical_events_is_replaced_by_resync() {
+        // Given:
+        let mut queue = EventQueue::new(0, 2);
+        queue.push(ordered());
+        queue.push(ordered());
+
+        // When:
+        let dropped = queue.push(ordered());
+
+        // Then:
+        assert_eq!(dropped, 2);
+        assert_eq!(
+            drain(&mut queue),
+            vec![Delivery::Resync { order_id: 0 }, Delivery::Event(ordered())]
+        );
+    }
+
+    #[test]
+    fn resync_is_sent_as_json() {
+        assert_eq!(
+            Delivery::Resync { order_id: 3 }.to_json(),
+            r#"{"type":"Resync","order_id":3}"#
+        );
+    }
+}
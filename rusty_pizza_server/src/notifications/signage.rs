@@ -0,0 +1,263 @@
+use crate::order_model::order::Order;
+use crate::order_model::summary::{OrderSummary, SummaryCache};
+use crate::util::clock::Clock;
+use crate::util::http::{HttpEndpoint, HttpError};
+use crate::util::id::Id;
+use crate::util::short_code::short_code;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Shortest time between two pushes, changes in between are sent together afterwards.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignageError {
+    UnknownFormat(String),
+    /// The display could not be reached or rejected the summary
+    Http(HttpError),
+}
+
+impl fmt::Display for SignageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SignageError::*;
+        match self {
+            UnknownFormat(format) => write!(f, "{} is neither html nor json", format),
+            Http(error) => write!(f, "display: {}", error),
+        }
+    }
+}
+
+impl Error for SignageError {}
+
+impl From<HttpError> for SignageError {
+    fn from(error: HttpError) -> SignageError {
+        SignageError::Http(error)
+    }
+}
+
+/// How the summary is sent to the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignageFormat {
+    /// Fragment to be inserted into the page of the display
+    Html,
+    Json,
+}
+
+impl SignageFormat {
+    pub fn get_content_type(&self) -> &'static str {
+        match self {
+            SignageFormat::Html => "text/html; charset=utf-8",
+            SignageFormat::Json => "application/json",
+        }
+    }
+}
+
+impl FromStr for SignageFormat {
+    type Err = SignageError;
+
+    fn from_str(format: &str) -> Result<SignageFormat, SignageError> {
+        match format.trim().to_lowercase().as_str() {
+            "html" => Ok(SignageFormat::Html),
+            "json" => Ok(SignageFormat::Json),
+            _ => Err(SignageError::UnknownFormat(String::from(format))),
+        }
+    }
+}
+
+/// A screen, e.g. in the kitchen area, showing how many pizzas were ordered and how long ordering is open.
+///
+/// The server pushes the summary whenever it changes, so the display doesn't have to poll.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignageDisplay {
+    endpoint: HttpEndpoint,
+    format: SignageFormat,
+    min_interval: Duration,
+}
+
+impl SignageDisplay {
+    pub fn new(endpoint: HttpEndpoint, format: SignageFormat) -> SignageDisplay {
+        SignageDisplay {
+            endpoint,
+            format,
+            min_interval: DEFAULT_MIN_INTERVAL,
+        }
+    }
+
+    pub fn with_min_interval(mut self, min_interval: Duration) -> SignageDisplay {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn get_endpoint(&self) -> &HttpEndpoint {
+        &self.endpoint
+    }
+
+    pub fn get_format(&self) -> SignageFormat {
+        self.format
+    }
+
+    /// Compact summary with the countdown as of `now`, which the display keeps counting down itself.
+    pub fn render(
+        &self,
+        order_id: &Id<Order>,
+        summary: &OrderSummary,
+        now: DateTime<Utc>,
+    ) -> String {
+        let seconds_left = summary
+            .time_until_deadline(now)
+            .map(|left| left.num_seconds());
+        match self.format {
+            SignageFormat::Json => json!({
+                "order": short_code(order_id),
+                "meals": summary.get_meals(),
+                "participants": summary.get_participants(),
+                "ready": summary.get_ready(),
+                "deadline": summary.get_deadline().map(|deadline| deadline.to_rfc3339()),
+                "seconds_left": seconds_left,
+            })
+            .to_string(),
+            SignageFormat::Html => {
+                let countdown = match (summary.get_deadline(), seconds_left) {
+                    (Some(deadline), Some(seconds)) => format!(
+                        "<p class=\"countdown\" data-deadline=\"{}\">{}:{:02} left</p>",
+                        deadline.to_rfc3339(),
+                        seconds / 60,
+                        seconds % 60
+                    ),
+                    _ => String::new(),
+                };
+                format!(
+                    "<section class=\"pizza-order\" data-order=\"{code}\">\
+                     <h1>{code}</h1>\
+                     <p class=\"meals\">{} meals</p>\
+                     <p class=\"ready\">{} of {} ready</p>\
+                     {}</section>",
+                    summary.get_meals(),
+                    summary.get_ready(),
+                    summary.get_participants(),
+                    countdown,
+                    code = short_code(order_id)
+                )
+            }
+        }
+    }
+
+    /// Sends the rendered summary to the display.
+    pub async fn push(&self, body: &str) -> Result<(), SignageError> {
+        Ok(self
+            .endpoint
+            .post(self.format.get_content_type(), body)
+            .await?)
+    }
+
+    /// Pushes every changed summary of the cache, at most once per `min_interval`, as long as the cache exists.
+    ///
+    /// A failed push is logged and not repeated, the next change of the order sends its summary again.
+    pub async fn run(self, cache: Arc<SummaryCache>, clock: Arc<dyn Clock + Send + Sync>) {
+        let mut changes = cache.subscribe();
+        let mut pushed: HashMap<Id<Order>, Arc<OrderSummary>> = HashMap::new();
+        while changes.changed().await.is_ok() {
+            let summaries = cache.snapshot();
+            let now = DateTime::<Utc>::from(clock.now());
+            for (id, summary) in summaries.iter() {
+                if pushed
+                    .get(id)
+                    .is_some_and(|last| Arc::ptr_eq(last, summary))
+                {
+                    continue;
+                }
+                if let Err(error) = self.push(&self.render(id, summary, now)).await {
+                    eprintln!("Summary of order {} not shown: {}", id.get_value(), error);
+                }
+                pushed.insert(id.clone(), summary.clone());
+            }
+            pushed.retain(|id, _| summaries.contains_key(id));
+            tokio::time::sleep(self.min_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::TestClock;
+    use crate::util::money::Money;
+    use chrono::TimeZone;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn order_with_deadline() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 50),
+            )
+            .unwrap();
+        order
+            .set_deadline(Some(Utc.with_ymd_and_hms(2020, 5, 4, 11, 30, 0).unwrap()))
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn html_shows_meals_and_countdown() {
+        // Given:
+        let display =
+            SignageDisplay::new("http://kitchen/pizza".parse().unwrap(), SignageFormat::Html);
+        let summary = OrderSummary::from_order(&order_with_deadline());
+        let now = Utc.with_ymd_and_hms(2020, 5, 4, 11, 17, 30).unwrap();
+
+        // When:
+        let html = display.render(&Id::new(3), &summary, now);
+
+        // Then:
+        assert!(html.contains("<p class=\"meals\">1 meals</p>"));
+        assert!(html.contains("<p class=\"ready\">0 of 1 ready</p>"));
+        assert!(html.contains(">12:30 left</p>"));
+    }
+
+    #[tokio::test]
+    async fn changed_summary_is_pushed_to_display() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/pizza", listener.local_addr().unwrap());
+        let display = SignageDisplay::new(url.parse().unwrap(), SignageFormat::Json)
+            .with_min_interval(Duration::from_millis(10));
+        let cache = Arc::new(SummaryCache::new());
+        let clock = TestClock::new(Utc.with_ymd_and_hms(2020, 5, 4, 11, 0, 0).unwrap().into());
+        tokio::spawn(display.run(cache.clone(), Arc::new(clock)));
+        tokio::task::yield_now().await;
+
+        // When:
+        cache.update(&Id::new(3), &order_with_deadline());
+
+        // Then:
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        while !String::from_utf8_lossy(&request).ends_with('}') {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+            .await
+            .unwrap();
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.starts_with("POST /pizza HTTP/1.1\r\n"));
+        let body: serde_json::Value =
+            serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["order"], short_code::<Order>(&Id::new(3)));
+        assert_eq!(body["meals"], 1);
+        assert_eq!(body["seconds_left"], 30 * 60);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::notifications::announcement::{post_in_background, Channel};
+use crate::util::http::{HttpEndpoint, HttpError};
+use serde_json::json;
+
+/// A chat channel with an incoming webhook taking `{"text": ...}`, as Slack, Mattermost and Rocket.Chat have.
+///
+/// The server only speaks plain HTTP, so a Slack webhook, which requires HTTPS, is reached through a relay in
+/// the local network that forwards the requests to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlackWebhook {
+    endpoint: HttpEndpoint,
+}
+
+impl SlackWebhook {
+    pub fn new(endpoint: HttpEndpoint) -> SlackWebhook {
+        SlackWebhook { endpoint }
+    }
+
+    pub fn get_endpoint(&self) -> &HttpEndpoint {
+        &self.endpoint
+    }
+
+    /// Posts the message and waits until the webhook accepted it.
+    pub async fn send(&self, message: &str) -> Result<(), HttpError> {
+        let payload = json!({ "text": message }).to_string();
+        self.endpoint.post("application/json", &payload).await
+    }
+}
+
+impl Channel for SlackWebhook {
+    fn get_name(&self) -> &str {
+        "Slack"
+    }
+
+    fn post(&self, message: &str) {
+        let webhook = self.clone();
+        let message = String::from(message);
+        post_in_background(self.get_name(), async move { webhook.send(&message).await });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn message_is_posted_as_text() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks/pizza", listener.local_addr().unwrap());
+        let webhook = SlackWebhook::new(url.parse().unwrap());
+
+        // When:
+        webhook.post("Pizza order 4 has \"arrived\"");
+
+        // Then:
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        while !String::from_utf8_lossy(&request).ends_with('}') {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\n\r\nok")
+            .await
+            .unwrap();
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.starts_with("POST /hooks/pizza HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/json\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"text\":\"Pizza order 4 has \\\"arrived\\\"\"}"));
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{{` without matching `}}`, at the given byte offset
+    Unclosed(usize),
+    /// The template uses a variable which is not given or not available for its `TemplateKey`
+    UnknownVariable(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use TemplateError::*;
+        match self {
+            Unclosed(position) => write!(f, "{{{{ at {} is not closed", position),
+            UnknownVariable(name) => write!(f, "variable {} is not available", name),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+/// A text with variables written as `{{ name }}`, e.g. "Hi {{user}}, you owe {{owed}}".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template, TemplateError> {
+        let mut parts = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(String::from(&rest[..start])));
+            }
+            let end = rest[start..]
+                .find("}}")
+                .ok_or(TemplateError::Unclosed(source.len() - rest.len() + start))?;
+            let name = rest[start + 2..start + end].trim();
+            parts.push(Part::Variable(String::from(name)));
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(String::from(rest)));
+        }
+        Ok(Template {
+            source: String::from(source),
+            parts,
+        })
+    }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    /// Names of all variables used, in order of appearance.
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Variable(name) => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    pub fn render(&self, variables: &Variables) -> Result<String, TemplateError> {
+        let mut text = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(part) => text.push_str(part),
+                Part::Variable(name) => text.push_str(
+                    variables
+                        .get(name)
+                        .ok_or_else(|| TemplateError::UnknownVariable(name.clone()))?,
+                ),
+            }
+        }
+        Ok(text)
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(source: &str) -> Result<Template, TemplateError> {
+        Template::parse(source)
+    }
+}
+
+/// Values of the variables a `Template` is rendered with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Variables {
+    values: HashMap<String, String>,
+}
+
+impl Variables {
+    pub fn new() -> Variables {
+        Variables::default()
+    }
+
+    /// Sets the variable and returns the variables, so they can be chained.
+    pub fn with(mut self, name: &str, value: impl ToString) -> Variables {
+        self.values.insert(String::from(name), value.to_string());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.values.get(name)
+    }
+}
+
+/// Every text sent to users, each with the variables it can use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TemplateKey {
+    OrderOpened,
+    OrderClosingSoon,
+    PaymentReminder,
+    /// The restaurant told when the order will be delivered
+    DeliveryEta,
+    /// The pizza arrived
+    OrderDelivered,
+}
+
+impl TemplateKey {
+    pub fn variables(&self) -> &'static [&'static str] {
+        use TemplateKey::*;
+        match self {
+            OrderOpened => &["order", "participants", "link"],
+            OrderClosingSoon => &["order", "participants", "link", "minutes"],
+            PaymentReminder => &["order", "user", "owed", "link"],
+            DeliveryEta => &["order", "eta"],
+            OrderDelivered => &["order", "participants", "link"],
+        }
+    }
+
+    fn default_source(&self) -> &'static str {
+        use TemplateKey::*;
+        match self {
+            OrderOpened => "Pizza order {{order}} is open, {{participants}} joined so far: {{link}}",
+            OrderClosingSoon => {
+                "Pizza order {{order}} closes in {{minutes}} minutes, {{participants}} joined so far: {{link}}"
+            }
+            PaymentReminder => "Hi {{user}}, you still owe {{owed}} for pizza order {{order}}: {{link}}",
+            DeliveryEta => "Pizza order {{order}} arrives at {{eta}}",
+            OrderDelivered => "Pizza order {{order}} has arrived, see what you owe: {{link}}",
+        }
+    }
+
+    /// Checks that the template only uses variables available for the key.
+    pub fn validate(&self, template: &Template) -> Result<(), TemplateError> {
+        match template
+            .variables()
+            .find(|name| !self.variables().contains(name))
+        {
+            Some(name) => Err(TemplateError::UnknownVariable(String::from(name))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Templates for all texts, with overrides for tenants like teams or offices that want their own wording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateRegistry {
+    defaults: HashMap<TemplateKey, Template>,
+    /// Template by tenant and key
+    overrides: HashMap<(String, TemplateKey), Template>,
+}
+
+impl TemplateRegistry {
+    pub fn new() -> TemplateRegistry {
+        TemplateRegistry {
+            defaults: HashMap::new(),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Replaces the built-in template of the key for everybody without an override.
+    pub fn set_default(
+        &mut self,
+        key: TemplateKey,
+        template: Template,
+    ) -> Result<(), TemplateError> {
+        key.validate(&template)?;
+        self.defaults.insert(key, template);
+        Ok(())
+    }
+
+    pub fn set_override(
+        &mut self,
+        tenant: &str,
+        key: TemplateKey,
+        template: Template,
+    ) -> Result<(), TemplateError> {
+        key.validate(&template)?;
+        self.overrides.insert((String::from(tenant), key), template);
+        Ok(())
+    }
+
+    pub fn remove_override(&mut self, tenant: &str, key: TemplateKey) -> Option<Template> {
+        self.overrides.remove(&(String::from(tenant), key))
+    }
+
+    /// The override of the tenant if there is one, otherwise the default.
+    pub fn get(&self, tenant: Option<&str>, key: TemplateKey) -> Template {
+        tenant
+            .and_then(|tenant| self.overrides.get(&(String::from(tenant), key)))
+            .or_else(|| self.defaults.get(&key))
+            .cloned()
+            .unwrap_or_else(|| {
+                Template::parse(key.default_source()).expect("Built-in templates are valid")
+            })
+    }
+
+    /// Renders the template of the key, the variables have to include all of `TemplateKey::variables`.
+    pub fn render(
+        &self,
+        tenant: Option<&str>,
+        key: TemplateKey,
+        variables: &Variables,
+    ) -> Result<String, TemplateError> {
+        self.get(tenant, key).render(variables)
+    }
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> TemplateRegistry {
+        TemplateRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        source,
+        expected,
+        case(
+            "Hi {{ user }}, you owe {{owed}}!",
+            Ok(String::from("Hi Anna, you owe 5,50€!"))
+        ),
+        case("No variables", Ok(String::from("No variables"))),
+        case("{ single } braces", Ok(String::from("{ single } braces"))),
+        case(
+            "Hi {{name}}",
+            Err(TemplateError::UnknownVariable(String::from("name")))
+        )
+    )]
+    fn template_is_rendered(source: &str, expected: Result<String, TemplateError>) {
+        // Given:
+        let template = Template::parse(source).unwrap();
+        let variables = Variables::new().with("user", "Anna").with("owed", "5,50€");
+
+        // When:
+        let text = template.render(&variables);
+
+        // Then:
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn unclosed_variable_is_rejected() {
+        assert_eq!(
+            "Hi {{user}}, {{owed".parse::<Template>(),
+            Err(TemplateError::Unclosed(13))
+        );
+    }
+
+    #[test]
+    fn tenant_override_replaces_default() {
+        // Given:
+        let mut registry = TemplateRegistry::new();
+        registry
+            .set_override(
+                "kitchen",
+                TemplateKey::DeliveryEta,
+                Template::parse("Essen kommt um {{eta}}").unwrap(),
+            )
+            .unwrap();
+        let variables = Variables::new().with("order", 3).with("eta", "12:15");
+
+        // When:
+        let overridden = registry.render(Some("kitchen"), TemplateKey::DeliveryEta, &variables);
+        let default = registry.render(Some("office"), TemplateKey::DeliveryEta, &variables);
+
+        // Then:
+        assert_eq!(overridden, Ok(String::from("Essen kommt um 12:15")));
+        assert_eq!(default, Ok(String::from("Pizza order 3 arrives at 12:15")));
+    }
+
+    #[test]
+    fn override_with_unavailable_variable_is_rejected() {
+        // Given:
+        let mut registry = TemplateRegistry::new();
+
+        // When:
+        let result = registry.set_override(
+            "kitchen",
+            TemplateKey::OrderOpened,
+            Template::parse("Order {{order}} closes in {{minutes}}").unwrap(),
+        );
+
+        // Then:
+        assert_eq!(
+            result,
+            Err(TemplateError::UnknownVariable(String::from("minutes")))
+        );
+        assert_eq!(
+            registry
+                .get(Some("kitchen"), TemplateKey::OrderOpened)
+                .get_source(),
+            TemplateKey::OrderOpened.default_source()
+        );
+    }
+}
@@ -0,0 +1,606 @@
+use crate::order_model::order::Order;
+use crate::order_model::user::User;
+use crate::util::http::{HttpEndpoint, HttpError};
+use crate::util::id::Id;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use hkdf::Hkdf;
+use p256::ecdh::diffie_hellman;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+
+/// Size of the records a message is encrypted in, messages always fit into a single one
+const RECORD_SIZE: u32 = 4096;
+/// How long push services keep a message for a browser which is offline, in seconds
+const TIME_TO_LIVE: u32 = 60 * 60;
+/// How long the VAPID token of a request is valid, push services accept at most 24 hours
+const TOKEN_VALIDITY_HOURS: i64 = 12;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PushError {
+    /// The endpoint of a subscription is not an HTTP(S) URL
+    InvalidEndpoint(String),
+    /// The key of a subscription is not a base64url encoded P-256 public key
+    InvalidKey,
+    /// The authentication secret of a subscription is not 16 base64url encoded bytes
+    InvalidAuthSecret,
+    /// The VAPID private key is not a base64url encoded P-256 private key
+    InvalidVapidKey,
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PushError::*;
+        match self {
+            InvalidEndpoint(endpoint) => write!(f, "{} is not an HTTP(S) URL", endpoint),
+            InvalidKey => write!(f, "key is not a base64url encoded P-256 public key"),
+            InvalidAuthSecret => write!(f, "auth secret is not 16 base64url encoded bytes"),
+            InvalidVapidKey => write!(f, "VAPID key is not a base64url encoded P-256 private key"),
+        }
+    }
+}
+
+impl Error for PushError {}
+
+/// Where a browser receives push messages, with the keys of its `PushSubscription` to encrypt them for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PushSubscription {
+    endpoint: String,
+    key: PublicKey,
+    auth: [u8; 16],
+}
+
+impl PushSubscription {
+    /// Subscription with the keys `p256dh` and `auth` as the browser encodes them, base64url without padding.
+    pub fn new(endpoint: String, p256dh: &str, auth: &str) -> Result<PushSubscription, PushError> {
+        let endpoint = String::from(endpoint.trim());
+        let host = endpoint
+            .strip_prefix("https://")
+            .or_else(|| endpoint.strip_prefix("http://"))
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or_default();
+        if host.is_empty() || endpoint.contains(char::is_whitespace) {
+            return Err(PushError::InvalidEndpoint(endpoint));
+        }
+        let key = decode(p256dh)
+            .and_then(|key| PublicKey::from_sec1_bytes(&key).ok())
+            .ok_or(PushError::InvalidKey)?;
+        let auth = decode(auth)
+            .and_then(|auth| auth.try_into().ok())
+            .ok_or(PushError::InvalidAuthSecret)?;
+        Ok(PushSubscription {
+            endpoint,
+            key,
+            auth,
+        })
+    }
+
+    pub fn get_endpoint(&self) -> &String {
+        &self.endpoint
+    }
+
+    /// Scheme and authority of the endpoint, the audience of the VAPID token.
+    fn get_origin(&self) -> &str {
+        let authority_start = self.endpoint.find("://").map_or(0, |index| index + 3);
+        match self.endpoint[authority_start..].find('/') {
+            Some(index) => &self.endpoint[..authority_start + index],
+            None => &self.endpoint,
+        }
+    }
+}
+
+fn decode(value: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim().trim_end_matches('='))
+        .ok()
+}
+
+/// Identification of the server towards push services (RFC 8292), so only it can send to the subscriptions made
+/// with its public key.
+#[derive(Clone, Debug)]
+pub struct Vapid {
+    key: SigningKey,
+    /// Contact of the operator for the push services, a `mailto:` or `https:` URL
+    subject: String,
+}
+
+impl Vapid {
+    pub fn new(private_key: &str, subject: String) -> Result<Vapid, PushError> {
+        let key = decode(private_key)
+            .and_then(|key| SigningKey::from_slice(&key).ok())
+            .ok_or(PushError::InvalidVapidKey)?;
+        Ok(Vapid { key, subject })
+    }
+
+    /// Identification with a new key, which invalidates all subscriptions made with another one.
+    pub fn generate(subject: String) -> Vapid {
+        Vapid {
+            key: SigningKey::random(&mut OsRng),
+            subject,
+        }
+    }
+
+    /// The key browsers subscribe with as `applicationServerKey`, base64url encoded.
+    pub fn get_public_key(&self) -> String {
+        let point = self.key.verifying_key().to_encoded_point(false);
+        URL_SAFE_NO_PAD.encode(point.as_bytes())
+    }
+
+    pub fn get_subject(&self) -> &String {
+        &self.subject
+    }
+
+    /// The value of the `Authorization` header of a message to the subscription.
+    pub fn authorization(&self, subscription: &PushSubscription) -> String {
+        let expires = Utc::now() + Duration::hours(TOKEN_VALIDITY_HOURS);
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = json!({
+            "aud": subscription.get_origin(),
+            "exp": expires.timestamp(),
+            "sub": self.subject,
+        });
+        let unsigned = format!("{}.{}", header, URL_SAFE_NO_PAD.encode(claims.to_string()));
+        let signature: Signature = self.key.sign(unsigned.as_bytes());
+        format!(
+            "vapid t={}.{}, k={}",
+            unsigned,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.get_public_key()
+        )
+    }
+}
+
+/// Encrypts the message for the subscription as `aes128gcm` content (RFC 8291), with a key pair and salt which
+/// have to be new for every message.
+pub fn encrypt(
+    subscription: &PushSubscription,
+    message: &[u8],
+    sender: &SecretKey,
+    salt: &[u8; 16],
+) -> Vec<u8> {
+    let sender_key = sender.public_key().to_encoded_point(false);
+    let receiver_key = subscription.key.to_encoded_point(false);
+    let shared = diffie_hellman(sender.to_nonzero_scalar(), subscription.key.as_affine());
+    let mut info = b"WebPush: info\0".to_vec();
+    info.extend_from_slice(receiver_key.as_bytes());
+    info.extend_from_slice(sender_key.as_bytes());
+    let mut input_key = [0; 32];
+    Hkdf::<Sha256>::new(Some(&subscription.auth), shared.raw_secret_bytes())
+        .expand(&info, &mut input_key)
+        .expect("32 bytes are a valid HKDF output");
+    let hkdf = Hkdf::<Sha256>::new(Some(salt), &input_key);
+    let mut content_key = [0; 16];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut content_key)
+        .expect("16 bytes are a valid HKDF output");
+    let mut nonce = [0; 12];
+    hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .expect("12 bytes are a valid HKDF output");
+    let mut record = message.to_vec();
+    // Delimiter of the last record, without further padding
+    record.push(2);
+    let ciphertext = Aes128Gcm::new(&content_key.into())
+        .encrypt(Nonce::from_slice(&nonce), record.as_slice())
+        .expect("Messages fit into a record");
+    let mut content = salt.to_vec();
+    content.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    content.push(sender_key.len() as u8);
+    content.extend_from_slice(sender_key.as_bytes());
+    content.extend_from_slice(&ciphertext);
+    content
+}
+
+/// Sends push messages to browsers via their push services, e.g. that the food is here.
+///
+/// Push services only accept HTTPS, which the server doesn't speak, so messages to them are sent through a relay
+/// in the local network, see `HttpEndpoint::forward`. Endpoints with plain HTTP, e.g. of a self-hosted push
+/// service, are sent to directly.
+#[derive(Clone, Debug)]
+pub struct WebPush {
+    vapid: Vapid,
+    relay: Option<HttpEndpoint>,
+}
+
+impl WebPush {
+    pub fn new(vapid: Vapid) -> WebPush {
+        WebPush { vapid, relay: None }
+    }
+
+    pub fn with_relay(mut self, relay: HttpEndpoint) -> WebPush {
+        self.relay = Some(relay);
+        self
+    }
+
+    pub fn get_vapid(&self) -> &Vapid {
+        &self.vapid
+    }
+
+    /// Sends the message and waits until the push service accepted it.
+    pub async fn send(
+        &self,
+        subscription: &PushSubscription,
+        message: &str,
+    ) -> Result<(), HttpError> {
+        let endpoint = match subscription.endpoint.parse::<HttpEndpoint>() {
+            Ok(endpoint) => endpoint,
+            Err(error) => self
+                .relay
+                .as_ref()
+                .ok_or(error)?
+                .forward(&subscription.endpoint),
+        };
+        let mut salt = [0; 16];
+        OsRng.fill_bytes(&mut salt);
+        let content = encrypt(
+            subscription,
+            message.as_bytes(),
+            &SecretKey::random(&mut OsRng),
+            &salt,
+        );
+        let authorization = self.vapid.authorization(subscription);
+        let time_to_live = TIME_TO_LIVE.to_string();
+        endpoint
+            .post_bytes(
+                "application/octet-stream",
+                &[
+                    ("Authorization", &authorization),
+                    ("Content-Encoding", "aes128gcm"),
+                    ("TTL", &time_to_live),
+                    ("Urgency", "high"),
+                ],
+                &content,
+            )
+            .await
+    }
+
+    /// Sends the message to all recipients in the background, forgetting subscriptions the push service reports as
+    /// expired.
+    pub fn post_in_background(
+        &self,
+        subscriptions: Arc<Mutex<PushSubscriptions>>,
+        recipients: Vec<PushSubscription>,
+        message: String,
+    ) {
+        let runtime = match Handle::try_current() {
+            Ok(runtime) => runtime,
+            Err(_) => {
+                eprintln!("Push message not sent: no runtime");
+                return;
+            }
+        };
+        for subscription in recipients {
+            let push = self.clone();
+            let subscriptions = subscriptions.clone();
+            let message = message.clone();
+            runtime.spawn(async move {
+                match push.send(&subscription, &message).await {
+                    Ok(()) => {}
+                    // Gone or never known, the browser unsubscribed or the subscription expired
+                    Err(error) if matches!(error.get_status(), Some(404) | Some(410)) => {
+                        subscriptions
+                            .lock()
+                            .expect("Push subscriptions lock is poisoned")
+                            .remove_endpoint(subscription.get_endpoint());
+                    }
+                    Err(error) => eprintln!(
+                        "Push message not sent to {}: {}",
+                        subscription.get_endpoint(),
+                        error
+                    ),
+                }
+            });
+        }
+    }
+}
+
+/// Browsers subscribed to push messages, either of a user for all orders they take part in, or of a single order,
+/// e.g. one shown on a screen nobody is logged in at.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PushSubscriptions {
+    by_user: HashMap<Id<User>, Vec<PushSubscription>>,
+    by_order: HashMap<Id<Order>, Vec<PushSubscription>>,
+}
+
+impl PushSubscriptions {
+    pub fn new() -> PushSubscriptions {
+        PushSubscriptions::default()
+    }
+
+    /// Subscribes the browser for the user. Returns whether it is new, a known endpoint gets its keys replaced.
+    pub fn subscribe_user(&mut self, user_id: Id<User>, subscription: PushSubscription) -> bool {
+        subscribe(self.by_user.entry(user_id).or_default(), subscription)
+    }
+
+    /// Returns whether the endpoint was subscribed for the user.
+    pub fn unsubscribe_user(&mut self, user_id: &Id<User>, endpoint: &str) -> bool {
+        self.by_user
+            .get_mut(user_id)
+            .is_some_and(|subscriptions| unsubscribe(subscriptions, endpoint))
+    }
+
+    /// Subscribes the browser for the order. Returns whether it is new, a known endpoint gets its keys replaced.
+    pub fn subscribe_order(&mut self, order_id: Id<Order>, subscription: PushSubscription) -> bool {
+        subscribe(self.by_order.entry(order_id).or_default(), subscription)
+    }
+
+    /// Returns whether the endpoint was subscribed for the order.
+    pub fn unsubscribe_order(&mut self, order_id: &Id<Order>, endpoint: &str) -> bool {
+        self.by_order
+            .get_mut(order_id)
+            .is_some_and(|subscriptions| unsubscribe(subscriptions, endpoint))
+    }
+
+    pub fn get_user_subscriptions(&self, user_id: &Id<User>) -> &[PushSubscription] {
+        self.by_user.get(user_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn get_order_subscriptions(&self, order_id: &Id<Order>) -> &[PushSubscription] {
+        self.by_order.get(order_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Everybody to notify about the order, its subscribers and those of its participants, each browser once.
+    pub fn recipients(&self, order_id: &Id<Order>, order: &Order) -> Vec<PushSubscription> {
+        let mut recipients: Vec<PushSubscription> = Vec::new();
+        let participants = order
+            .participants()
+            .flat_map(|user_id| self.get_user_subscriptions(user_id));
+        for subscription in self
+            .get_order_subscriptions(order_id)
+            .iter()
+            .chain(participants)
+        {
+            if !recipients
+                .iter()
+                .any(|known| known.endpoint == subscription.endpoint)
+            {
+                recipients.push(subscription.clone());
+            }
+        }
+        recipients
+    }
+
+    /// Forgets the endpoint for all users and orders, e.g. because the push service reported it as expired.
+    pub fn remove_endpoint(&mut self, endpoint: &str) {
+        for subscriptions in self.by_user.values_mut().chain(self.by_order.values_mut()) {
+            unsubscribe(subscriptions, endpoint);
+        }
+    }
+}
+
+fn subscribe(subscriptions: &mut Vec<PushSubscription>, subscription: PushSubscription) -> bool {
+    let new = !unsubscribe(subscriptions, &subscription.endpoint);
+    subscriptions.push(subscription);
+    new
+}
+
+fn unsubscribe(subscriptions: &mut Vec<PushSubscription>, endpoint: &str) -> bool {
+    let before = subscriptions.len();
+    subscriptions.retain(|subscription| subscription.endpoint != endpoint);
+    subscriptions.len() < before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::VerifyingKey;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Keys of the browser from the example in RFC 8291, appendix A
+    const RECEIVER_KEY: &str =
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+    const AUTH_SECRET: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+
+    fn subscription(endpoint: &str) -> PushSubscription {
+        PushSubscription::new(String::from(endpoint), RECEIVER_KEY, AUTH_SECRET).unwrap()
+    }
+
+    #[test]
+    fn message_is_encrypted_as_in_rfc_8291() {
+        // Given:
+        let sender =
+            SecretKey::from_slice(&decode("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw").unwrap())
+                .unwrap();
+        let salt: [u8; 16] = decode("DGv6ra1nlYgDCS1FRnbzlw")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        // When:
+        let content = encrypt(
+            &subscription("https://push.example.net/push/JzLQ3raZJfFBR0aqvOMsLrt54w4rJUsV"),
+            b"When I grow up, I want to be a watermelon",
+            &sender,
+            &salt,
+        );
+
+        // Then:
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(content),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzA\
+             C8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+        );
+    }
+
+    #[test]
+    fn authorization_is_signed_for_origin_of_endpoint() {
+        // Given:
+        let vapid = Vapid::generate(String::from("mailto:pizza@example.com"));
+        let subscription = subscription("https://fcm.googleapis.com/fcm/send/abc");
+
+        // When:
+        let authorization = vapid.authorization(&subscription);
+
+        // Then:
+        let (token, key) = authorization
+            .strip_prefix("vapid t=")
+            .unwrap()
+            .split_once(", k=")
+            .unwrap();
+        assert_eq!(key, vapid.get_public_key());
+        let (unsigned, signature) = token.rsplit_once('.').unwrap();
+        let signature = Signature::from_slice(&decode(signature).unwrap()).unwrap();
+        let verifying_key = VerifyingKey::from_sec1_bytes(&decode(key).unwrap()).unwrap();
+        assert!(verifying_key
+            .verify(unsigned.as_bytes(), &signature)
+            .is_ok());
+        let claims: serde_json::Value =
+            serde_json::from_slice(&decode(unsigned.split('.').nth(1).unwrap()).unwrap()).unwrap();
+        assert_eq!(claims["aud"], "https://fcm.googleapis.com");
+        assert_eq!(claims["sub"], "mailto:pizza@example.com");
+    }
+
+    #[test]
+    fn vapid_key_is_restored_from_private_key() {
+        // Given:
+        let private_key = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+
+        // When:
+        let vapid = Vapid::new(private_key, String::from("mailto:pizza@example.com")).unwrap();
+
+        // Then:
+        assert_eq!(
+            vapid.get_public_key(),
+            "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8"
+        );
+        assert_eq!(
+            Vapid::new("no key", String::new()).unwrap_err(),
+            PushError::InvalidVapidKey
+        );
+    }
+
+    #[test]
+    fn invalid_subscriptions_are_rejected() {
+        assert_eq!(
+            PushSubscription::new(String::from("push.example.net"), RECEIVER_KEY, AUTH_SECRET),
+            Err(PushError::InvalidEndpoint(String::from("push.example.net")))
+        );
+        assert_eq!(
+            PushSubscription::new(String::from("https://push.example.net"), "abc", AUTH_SECRET),
+            Err(PushError::InvalidKey)
+        );
+        assert_eq!(
+            PushSubscription::new(
+                String::from("https://push.example.net"),
+                RECEIVER_KEY,
+                "abc"
+            ),
+            Err(PushError::InvalidAuthSecret)
+        );
+    }
+
+    #[test]
+    fn participants_and_order_subscribers_are_notified_once() {
+        // Given:
+        let mut order = Order::new(Id::new(1));
+        order.add_user(Id::new(2)).unwrap();
+        let mut subscriptions = PushSubscriptions::new();
+        subscriptions.subscribe_user(Id::new(1), subscription("https://push.example.net/anna"));
+        subscriptions.subscribe_user(Id::new(2), subscription("https://push.example.net/screen"));
+        subscriptions.subscribe_user(Id::new(3), subscription("https://push.example.net/carl"));
+        subscriptions.subscribe_order(Id::new(0), subscription("https://push.example.net/screen"));
+        subscriptions.subscribe_order(Id::new(4), subscription("https://push.example.net/other"));
+
+        // When:
+        let recipients = subscriptions.recipients(&Id::new(0), &order);
+
+        // Then:
+        let endpoints: Vec<&String> = recipients
+            .iter()
+            .map(PushSubscription::get_endpoint)
+            .collect();
+        assert_eq!(
+            endpoints,
+            vec![
+                "https://push.example.net/screen",
+                "https://push.example.net/anna"
+            ]
+        );
+    }
+
+    #[test]
+    fn resubscribing_replaces_subscription() {
+        // Given:
+        let mut subscriptions = PushSubscriptions::new();
+        let first =
+            subscriptions.subscribe_user(Id::new(1), subscription("https://push.example.net/a"));
+
+        // When:
+        let second =
+            subscriptions.subscribe_user(Id::new(1), subscription("https://push.example.net/a"));
+        let removed = subscriptions.unsubscribe_user(&Id::new(1), "https://push.example.net/a");
+
+        // Then:
+        assert!(first);
+        assert!(!second);
+        assert!(removed);
+        assert!(subscriptions.get_user_subscriptions(&Id::new(1)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn message_is_posted_encrypted_with_vapid() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/push/abc", listener.local_addr().unwrap());
+        let push = WebPush::new(Vapid::generate(String::from("mailto:pizza@example.com")));
+        let subscription = subscription(&url);
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        // When:
+        let result = push.send(&subscription, "Food is here").await;
+
+        // Then:
+        assert_eq!(result, Ok(()));
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /push/abc HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/octet-stream\r\n"));
+        assert!(request.contains("Content-Encoding: aes128gcm\r\n"));
+        assert!(request.contains("Authorization: vapid t="));
+        assert!(request.contains("TTL: 3600\r\n"));
+    }
+
+    #[tokio::test]
+    async fn https_endpoint_needs_relay() {
+        // Given:
+        let push = WebPush::new(Vapid::generate(String::from("mailto:pizza@example.com")));
+
+        // When:
+        let result = push
+            .send(
+                &subscription("https://push.example.net/abc"),
+                "Food is here",
+            )
+            .await;
+
+        // Then:
+        assert!(matches!(result, Err(HttpError::UnsupportedUrl(_))));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::order_model::order::Order;
+use crate::order_model::summary::OrderSummary;
+
+/// An order moved out of the active ones, with a summary taken when it was archived, so reports don't have to
+/// recompute it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchivedOrder {
+    order: Order,
+    summary: OrderSummary,
+}
+
+impl ArchivedOrder {
+    pub fn new(order: Order) -> ArchivedOrder {
+        let summary = OrderSummary::from_order(&order);
+        ArchivedOrder { order, summary }
+    }
+
+    pub fn get_order(&self) -> &Order {
+        &self.order
+    }
+
+    pub fn get_summary(&self) -> &OrderSummary {
+        &self.summary
+    }
+
+    /// Anonymizes the order, see `Order::anonymize`, and takes the summary again, as it names the participants.
+    pub fn anonymize(&mut self) {
+        self.order.anonymize();
+        self.summary = OrderSummary::from_order(&self.order);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::id::Id;
+    use crate::util::money::Money;
+
+    #[test]
+    fn summary_of_anonymized_order_names_no_participants() {
+        // Given:
+        let mut order = Order::new(Id::new(3));
+        order.add_user(Id::new(7)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(7),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(8, 0),
+            )
+            .unwrap();
+        let mut archived = ArchivedOrder::new(order);
+
+        // When:
+        archived.anonymize();
+
+        // Then:
+        assert!(archived.get_order().is_anonymized());
+        assert_eq!(
+            archived.get_summary(),
+            &OrderSummary::from_order(archived.get_order())
+        );
+        assert_eq!(archived.get_summary().get_participants(), 2);
+        assert!(archived
+            .get_summary()
+            .get_report()
+            .get_user(&Id::new(7))
+            .is_none());
+    }
+}
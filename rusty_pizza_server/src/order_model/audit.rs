@@ -0,0 +1,616 @@
+use crate::menu::catalog::Menu;
+use crate::order_model::fee::FeeSplitStrategy;
+use crate::order_model::meal::Meal;
+use crate::order_model::order::OrderStatus;
+use crate::order_model::payment::{DuplicateReason, Installment};
+use crate::order_model::preparation::Preparation;
+use crate::order_model::restaurant::Restaurant;
+use crate::order_model::special::Special;
+use crate::order_model::user::User;
+use crate::util::clock::{Clock, SystemClock};
+use crate::util::id::Id;
+use crate::util::locale::{Currency, Locale};
+use crate::util::money::Money;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// A change made to an `Order` through one of its methods, carrying everything needed to make it again.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mutation {
+    /// The order was created, always the first mutation
+    Created {
+        manager_id: Id<User>,
+    },
+    UserAdded(Id<User>),
+    UserRemoved(Id<User>),
+    MenuSet(Arc<Menu>),
+    MealAdded {
+        user_id: Id<User>,
+        id: Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    },
+    MealUpdated {
+        user_id: Id<User>,
+        id: Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    },
+    MealRemoved {
+        user_id: Id<User>,
+        id: Id<Meal>,
+    },
+    PreparationsSet {
+        user_id: Id<User>,
+        id: Id<Meal>,
+        preparations: BTreeSet<Preparation>,
+    },
+    NoteSet {
+        user_id: Id<User>,
+        id: Id<Meal>,
+        note: Option<String>,
+    },
+    OfficeMealAdded {
+        id: Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    },
+    OfficeMealRemoved(Id<Meal>),
+    SharedMealAdded {
+        id: Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    },
+    SharedMealRemoved(Id<Meal>),
+    SpecialAdded {
+        user_id: Id<User>,
+        meal: Id<Meal>,
+        id: Id<Special>,
+        description: String,
+        /// Extra charge, `None` for free specials
+        price: Option<Money>,
+    },
+    SpecialRemoved {
+        user_id: Id<User>,
+        meal: Id<Meal>,
+        id: Id<Special>,
+    },
+    SpecialAddedToAll(String),
+    PaidSet {
+        user_id: Id<User>,
+        paid: Money,
+    },
+    TipSet {
+        user_id: Id<User>,
+        tip: Money,
+    },
+    ReadySet {
+        user_id: Id<User>,
+        ready: bool,
+    },
+    PaymentClaimed {
+        user_id: Id<User>,
+        amount: Money,
+        method: String,
+    },
+    /// Confirmation of a claimed payment, which becomes an installment paid at the given time
+    PaymentConfirmed {
+        user_id: Id<User>,
+        time: SystemTime,
+    },
+    PaymentDisputed(Id<User>),
+    PaymentAdded {
+        user_id: Id<User>,
+        id: Id<Installment>,
+        amount: Money,
+        time: SystemTime,
+        key: Option<String>,
+    },
+    /// A suspected duplicate was kept aside for the manager
+    PaymentHeld {
+        user_id: Id<User>,
+        id: Id<Installment>,
+        amount: Money,
+        time: SystemTime,
+        key: Option<String>,
+        reason: DuplicateReason,
+    },
+    HeldPaymentReleased {
+        user_id: Id<User>,
+        id: Id<Installment>,
+    },
+    HeldPaymentDiscarded {
+        user_id: Id<User>,
+        id: Id<Installment>,
+    },
+    PaymentRemoved {
+        user_id: Id<User>,
+        id: Id<Installment>,
+    },
+    PaymentCorrected {
+        user_id: Id<User>,
+        id: Id<Installment>,
+        amount: Money,
+    },
+    Undone {
+        user_id: Id<User>,
+        steps: usize,
+    },
+    Redone(Id<User>),
+    /// Any change of the status except delivery, which has its own mutation because of its time
+    StatusChanged(OrderStatus),
+    Delivered(SystemTime),
+    GracePeriodSet(Duration),
+    CurrencySet(Option<Currency>),
+    LocaleSet(Option<Locale>),
+    CostCenterSet(Option<String>),
+    DeadlineSet(Option<DateTime<Utc>>),
+    DeliveryFeeSet {
+        fee: Money,
+        fee_split: FeeSplitStrategy,
+    },
+    SharedSplitSet(FeeSplitStrategy),
+    /// Also sets the delivery fee to the one of the restaurant
+    RestaurantSet(Option<Restaurant>),
+    ReadyRequiredSet(bool),
+    /// Expected delivery time given by the restaurant after the order was placed
+    EtaSet(Option<DateTime<Utc>>),
+    DepositSet {
+        id: Id<Meal>,
+        deposit: Money,
+    },
+    /// The bottle or can of the meal was returned and its deposit refunded
+    DepositReturned(Id<Meal>),
+}
+
+impl Mutation {
+    /// The participant whose meals or payments were changed, `None` for changes of the whole order.
+    pub fn get_user_id(&self) -> Option<&Id<User>> {
+        use Mutation::*;
+        match self {
+            UserAdded(user_id)
+            | UserRemoved(user_id)
+            | MealAdded { user_id, .. }
+            | MealUpdated { user_id, .. }
+            | MealRemoved { user_id, .. }
+            | PreparationsSet { user_id, .. }
+            | NoteSet { user_id, .. }
+            | SpecialAdded { user_id, .. }
+            | SpecialRemoved { user_id, .. }
+            | PaidSet { user_id, .. }
+            | TipSet { user_id, .. }
+            | ReadySet { user_id, .. }
+            | PaymentClaimed { user_id, .. }
+            | PaymentConfirmed { user_id, .. }
+            | PaymentDisputed(user_id)
+            | PaymentAdded { user_id, .. }
+            | PaymentHeld { user_id, .. }
+            | HeldPaymentReleased { user_id, .. }
+            | HeldPaymentDiscarded { user_id, .. }
+            | PaymentRemoved { user_id, .. }
+            | PaymentCorrected { user_id, .. }
+            | Undone { user_id, .. }
+            | Redone(user_id) => Some(user_id),
+            _ => None,
+        }
+    }
+
+    /// Whether the mutation may have changed the meal with the given ID.
+    ///
+    /// Undo and redo are included for the meals of their user, as they can bring back or take away any of them.
+    pub fn affects_meal(&self, meal: &Id<Meal>) -> bool {
+        use Mutation::*;
+        match self {
+            MealAdded { id, .. }
+            | MealUpdated { id, .. }
+            | MealRemoved { id, .. }
+            | PreparationsSet { id, .. }
+            | NoteSet { id, .. }
+            | OfficeMealAdded { id, .. }
+            | OfficeMealRemoved(id)
+            | SharedMealAdded { id, .. }
+            | SharedMealRemoved(id)
+            | DepositSet { id, .. }
+            | DepositReturned(id) => id == meal,
+            SpecialAdded { meal: id, .. } | SpecialRemoved { meal: id, .. } => id == meal,
+            SpecialAddedToAll(_) | Undone { .. } | Redone(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Mutation::*;
+        match self {
+            Created { manager_id } => {
+                write!(f, "order created by user {}", manager_id.get_value())
+            }
+            UserAdded(user_id) => write!(f, "user {} joined", user_id.get_value()),
+            UserRemoved(user_id) => write!(f, "user {} left", user_id.get_value()),
+            MenuSet(_) => write!(f, "menu set"),
+            MealAdded {
+                user_id,
+                id,
+                meal_id,
+                variety,
+                price,
+            } => write!(
+                f,
+                "meal {} ({} {}, {}) added for user {}",
+                id.get_value(),
+                meal_id,
+                variety,
+                price,
+                user_id.get_value()
+            ),
+            MealUpdated {
+                user_id,
+                id,
+                meal_id,
+                variety,
+                price,
+            } => write!(
+                f,
+                "meal {} of user {} changed to {} {}, {}",
+                id.get_value(),
+                user_id.get_value(),
+                meal_id,
+                variety,
+                price
+            ),
+            MealRemoved { user_id, id } => write!(
+                f,
+                "meal {} of user {} removed",
+                id.get_value(),
+                user_id.get_value()
+            ),
+            PreparationsSet {
+                user_id,
+                id,
+                preparations,
+            } => {
+                let preparations: Vec<String> =
+                    preparations.iter().map(Preparation::to_string).collect();
+                write!(
+                    f,
+                    "meal {} of user {} prepared {}",
+                    id.get_value(),
+                    user_id.get_value(),
+                    if preparations.is_empty() {
+                        String::from("as usual")
+                    } else {
+                        preparations.join(", ")
+                    }
+                )
+            }
+            NoteSet { user_id, id, note } => match note {
+                Some(note) => write!(
+                    f,
+                    "meal {} of user {} noted \"{}\"",
+                    id.get_value(),
+                    user_id.get_value(),
+                    note
+                ),
+                None => write!(
+                    f,
+                    "note of meal {} of user {} removed",
+                    id.get_value(),
+                    user_id.get_value()
+                ),
+            },
+            OfficeMealAdded {
+                id,
+                meal_id,
+                variety,
+                price,
+            } => write!(
+                f,
+                "office meal {} ({} {}, {}) added",
+                id.get_value(),
+                meal_id,
+                variety,
+                price
+            ),
+            OfficeMealRemoved(id) => write!(f, "office meal {} removed", id.get_value()),
+            SharedMealAdded {
+                id,
+                meal_id,
+                variety,
+                price,
+            } => write!(
+                f,
+                "shared meal {} ({} {}, {}) added",
+                id.get_value(),
+                meal_id,
+                variety,
+                price
+            ),
+            SharedMealRemoved(id) => write!(f, "shared meal {} removed", id.get_value()),
+            SpecialAdded {
+                user_id,
+                meal,
+                description,
+                ..
+            } => write!(
+                f,
+                "special \"{}\" added to meal {} of user {}",
+                description,
+                meal.get_value(),
+                user_id.get_value()
+            ),
+            SpecialRemoved { user_id, meal, id } => write!(
+                f,
+                "special {} removed from meal {} of user {}",
+                id.get_value(),
+                meal.get_value(),
+                user_id.get_value()
+            ),
+            SpecialAddedToAll(description) => {
+                write!(f, "special \"{}\" added to all meals", description)
+            }
+            PaidSet { user_id, paid } => {
+                write!(f, "user {} paid {}", user_id.get_value(), paid)
+            }
+            TipSet { user_id, tip } => write!(f, "user {} tips {}", user_id.get_value(), tip),
+            ReadySet { user_id, ready } => write!(
+                f,
+                "user {} is {}",
+                user_id.get_value(),
+                if *ready { "ready" } else { "not ready" }
+            ),
+            PaymentClaimed {
+                user_id,
+                amount,
+                method,
+            } => write!(
+                f,
+                "user {} claims to have paid {} via {}",
+                user_id.get_value(),
+                amount,
+                method
+            ),
+            PaymentConfirmed { user_id, .. } => {
+                write!(f, "payment of user {} confirmed", user_id.get_value())
+            }
+            PaymentDisputed(user_id) => {
+                write!(f, "payment of user {} disputed", user_id.get_value())
+            }
+            PaymentAdded {
+                user_id,
+                id,
+                amount,
+                ..
+            } => write!(
+                f,
+                "user {} paid {} as payment {}",
+                user_id.get_value(),
+                amount,
+                id.get_value()
+            ),
+            PaymentHeld {
+                user_id,
+                id,
+                amount,
+                reason,
+                ..
+            } => write!(
+                f,
+                "payment {} of {} by user {} held: {}",
+                id.get_value(),
+                amount,
+                user_id.get_value(),
+                reason
+            ),
+            HeldPaymentReleased { user_id, id } => write!(
+                f,
+                "held payment {} of user {} booked",
+                id.get_value(),
+                user_id.get_value()
+            ),
+            HeldPaymentDiscarded { user_id, id } => write!(
+                f,
+                "held payment {} of user {} discarded",
+                id.get_value(),
+                user_id.get_value()
+            ),
+            PaymentRemoved { user_id, id } => write!(
+                f,
+                "payment {} of user {} removed",
+                id.get_value(),
+                user_id.get_value()
+            ),
+            PaymentCorrected {
+                user_id,
+                id,
+                amount,
+            } => write!(
+                f,
+                "payment {} of user {} corrected to {}",
+                id.get_value(),
+                user_id.get_value(),
+                amount
+            ),
+            Undone { user_id, steps } => write!(
+                f,
+                "{} changes of user {} undone",
+                steps,
+                user_id.get_value()
+            ),
+            Redone(user_id) => write!(f, "change of user {} redone", user_id.get_value()),
+            StatusChanged(status) => write!(f, "status changed to {}", status),
+            Delivered(_) => write!(f, "status changed to {}", OrderStatus::Delivered),
+            GracePeriodSet(period) => {
+                write!(f, "grace period set to {} seconds", period.as_secs())
+            }
+            CurrencySet(Some(currency)) => write!(f, "currency set to {}", currency.get_code()),
+            CurrencySet(None) => write!(f, "currency reset to default"),
+            LocaleSet(Some(locale)) => write!(f, "locale set to {}", locale.get_tag()),
+            LocaleSet(None) => write!(f, "locale reset to default"),
+            CostCenterSet(Some(cost_center)) => write!(f, "cost center set to {}", cost_center),
+            CostCenterSet(None) => write!(f, "cost center removed"),
+            DeadlineSet(Some(deadline)) => write!(f, "deadline set to {}", deadline.to_rfc3339()),
+            DeadlineSet(None) => write!(f, "deadline removed"),
+            DeliveryFeeSet { fee, fee_split } => {
+                write!(f, "delivery fee set to {} split {:?}", fee, fee_split)
+            }
+            SharedSplitSet(split) => write!(f, "shared meals split {:?}", split),
+            RestaurantSet(Some(restaurant)) => {
+                write!(f, "restaurant set to {}", restaurant.get_name())
+            }
+            RestaurantSet(None) => write!(f, "restaurant removed"),
+            ReadyRequiredSet(true) => write!(f, "readiness of all participants required"),
+            ReadyRequiredSet(false) => write!(f, "readiness no longer required"),
+            EtaSet(Some(eta)) => write!(f, "ETA set to {}", eta.to_rfc3339()),
+            EtaSet(None) => write!(f, "ETA removed"),
+            DepositSet { id, deposit } => {
+                write!(f, "deposit of meal {} set to {}", id.get_value(), deposit)
+            }
+            DepositReturned(id) => write!(f, "deposit of meal {} returned", id.get_value()),
+        }
+    }
+}
+
+/// A `Mutation` together with the time it was made.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderEvent {
+    time: SystemTime,
+    mutation: Mutation,
+}
+
+impl OrderEvent {
+    pub fn new(time: SystemTime, mutation: Mutation) -> OrderEvent {
+        OrderEvent { time, mutation }
+    }
+
+    pub fn get_time(&self) -> SystemTime {
+        self.time
+    }
+
+    pub fn get_mutation(&self) -> &Mutation {
+        &self.mutation
+    }
+}
+
+/// Every `OrderEvent` of an order in the order they happened, timestamped by a `Clock`.
+#[derive(Clone)]
+pub struct AuditLog {
+    events: Vec<OrderEvent>,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl AuditLog {
+    pub fn new() -> AuditLog {
+        AuditLog::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock + Send + Sync>) -> AuditLog {
+        AuditLog {
+            events: Vec::new(),
+            clock,
+        }
+    }
+
+    pub fn get_clock(&self) -> Arc<dyn Clock + Send + Sync> {
+        self.clock.clone()
+    }
+
+    pub fn record(&mut self, mutation: Mutation) {
+        let time = self.clock.now();
+        self.events.push(OrderEvent::new(time, mutation));
+    }
+
+    pub fn events(&self) -> &[OrderEvent] {
+        &self.events
+    }
+
+    /// Replaces all events, e.g. by the ones an order was replayed from, so their original times are kept.
+    pub fn set_events(&mut self, events: Vec<OrderEvent>) {
+        self.events = events;
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> AuditLog {
+        AuditLog::new()
+    }
+}
+
+impl fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("events", &self.events)
+            .finish()
+    }
+}
+
+/// The log records how an order got to its state but is not part of it, so it never makes orders unequal.
+impl PartialEq for AuditLog {
+    fn eq(&self, _other: &AuditLog) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::TestClock;
+
+    #[test]
+    fn events_are_timestamped_by_clock() {
+        // Given:
+        let clock = TestClock::default();
+        let mut log = AuditLog::with_clock(Arc::new(clock.clone()));
+        log.record(Mutation::UserAdded(Id::new(1)));
+        clock.advance(Duration::from_secs(60));
+
+        // When:
+        log.record(Mutation::UserRemoved(Id::new(1)));
+
+        // Then:
+        assert_eq!(
+            log.events(),
+            &[
+                OrderEvent::new(SystemTime::UNIX_EPOCH, Mutation::UserAdded(Id::new(1))),
+                OrderEvent::new(
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+                    Mutation::UserRemoved(Id::new(1))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mutations_are_attributed_to_users_and_meals() {
+        // Given:
+        let added = Mutation::MealAdded {
+            user_id: Id::new(2),
+            id: Id::new(5),
+            meal_id: String::from("03"),
+            variety: String::from("groß"),
+            price: Money::new(7, 50),
+        };
+        let fee = Mutation::DeliveryFeeSet {
+            fee: Money::new(2, 0),
+            fee_split: FeeSplitStrategy::default(),
+        };
+
+        // Then:
+        assert_eq!(added.get_user_id(), Some(&Id::new(2)));
+        assert!(added.affects_meal(&Id::new(5)));
+        assert!(!added.affects_meal(&Id::new(4)));
+        assert_eq!(fee.get_user_id(), None);
+        assert!(!fee.affects_meal(&Id::new(5)));
+        assert_eq!(
+            added.to_string(),
+            "meal 5 (03 groß, 7,50€) added for user 2"
+        );
+    }
+}
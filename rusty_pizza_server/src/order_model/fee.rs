@@ -0,0 +1,240 @@
+use crate::util::money::Money;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// How a delivery or service fee is shared among the participants of an order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FeeSplitStrategy {
+    /// Everybody who ordered a meal pays the same, differing by at most one cent
+    #[default]
+    Equal,
+    /// Everybody pays according to the price of their meals
+    Proportional,
+    /// The manager pays the whole fee
+    ManagerPays,
+}
+
+/// Splits the `fee` among the users according to the `strategy`, so the shares sum up to the fee.
+///
+/// `meal_prices` contains the price of the meals of every participant. Only users with meals share the fee,
+/// if nobody ordered anything the manager pays it. Leftover cents go to the users with the lowest IDs.
+///
+/// Users can be identified by anything ordered, e.g. `Id<User>` or names, see `settlement::ledger`.
+pub fn split_fee<K: Clone + Eq + Hash + Ord>(
+    fee: Money,
+    strategy: FeeSplitStrategy,
+    manager_id: &K,
+    meal_prices: &HashMap<K, Money>,
+) -> HashMap<K, Money> {
+    split_fee_with_rounding(fee, strategy, manager_id, meal_prices).0
+}
+
+/// Like `split_fee`, but also returns the cents every user pays on top of their exact share, rounded down, so the
+/// shares sum up to the fee. Users without such a leftover cent are left out.
+pub fn split_fee_with_rounding<K: Clone + Eq + Hash + Ord>(
+    fee: Money,
+    strategy: FeeSplitStrategy,
+    manager_id: &K,
+    meal_prices: &HashMap<K, Money>,
+) -> (HashMap<K, Money>, HashMap<K, Money>) {
+    let mut payers: Vec<(&K, Money)> = meal_prices
+        .iter()
+        .filter(|(_, price)| **price > Money::zero())
+        .map(|(id, price)| (id, *price))
+        .collect();
+    payers.sort_by_key(|(id, _)| *id);
+    let mut shares = HashMap::new();
+    let mut rounding = HashMap::new();
+    if fee.is_zero() {
+        return (shares, rounding);
+    }
+    match strategy {
+        FeeSplitStrategy::ManagerPays => {
+            shares.insert(manager_id.clone(), fee);
+        }
+        _ if payers.is_empty() => {
+            shares.insert(manager_id.clone(), fee);
+        }
+        FeeSplitStrategy::Equal => {
+            let exact = (fee / payers.len() as u32).get_quotient();
+            for ((id, _), share) in payers.iter().zip(fee.split(payers.len() as u32)) {
+                if share > exact {
+                    rounding.insert((*id).clone(), share - exact);
+                }
+                shares.insert((*id).clone(), share);
+            }
+        }
+        FeeSplitStrategy::Proportional => {
+            let total: u64 = payers
+                .iter()
+                .map(|(_, price)| u64::from(price.get_total_cents()))
+                .sum();
+            let fee_cents = u64::from(fee.get_total_cents());
+            // Exact share in cents split into its whole cents and the fraction, scaled by `total`
+            let mut parts: Vec<(&K, u64, u64)> = payers
+                .iter()
+                .map(|(id, price)| {
+                    let scaled = fee_cents * u64::from(price.get_total_cents());
+                    (*id, scaled / total, scaled % total)
+                })
+                .collect();
+            let distributed: u64 = parts.iter().map(|(_, cents, _)| cents).sum();
+            let mut leftover = fee_cents - distributed;
+            // Largest remaining fractions get the leftover cents first, the sort is stable for equal ones
+            let mut by_fraction: Vec<usize> = (0..parts.len()).collect();
+            by_fraction.sort_by(|a, b| parts[*b].2.cmp(&parts[*a].2));
+            for index in by_fraction {
+                if leftover == 0 {
+                    break;
+                }
+                parts[index].1 += 1;
+                rounding.insert(parts[index].0.clone(), Money::from_cents(1));
+                leftover -= 1;
+            }
+            for (id, cents, _) in parts {
+                shares.insert(id.clone(), Money::from_cents(cents as u32));
+            }
+        }
+    }
+    (shares, rounding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::user::User;
+    use crate::util::id::Id;
+    use rstest::rstest;
+
+    /// Money by user ID
+    fn amounts(amounts: Vec<(u32, Money)>) -> HashMap<Id<User>, Money> {
+        amounts
+            .into_iter()
+            .map(|(id, amount)| (Id::new(id), amount))
+            .collect()
+    }
+
+    #[rstest(
+        strategy,
+        expected,
+        case(
+            FeeSplitStrategy::Equal,
+            amounts(vec![(1, Money::new(0, 67)), (2, Money::new(0, 67)), (3, Money::new(0, 66))])
+        ),
+        case(
+            FeeSplitStrategy::Proportional,
+            amounts(vec![(1, Money::new(0, 50)), (2, Money::new(0, 75)), (3, Money::new(0, 75))])
+        ),
+        case(FeeSplitStrategy::ManagerPays, amounts(vec![(0, Money::new(2, 0))]))
+    )]
+    fn fee_is_split_by_strategy(strategy: FeeSplitStrategy, expected: HashMap<Id<User>, Money>) {
+        // Given:
+        let prices = amounts(vec![
+            (0, Money::zero()),
+            (1, Money::new(4, 0)),
+            (2, Money::new(6, 0)),
+            (3, Money::new(6, 0)),
+        ]);
+
+        // When:
+        let result = split_fee(Money::new(2, 0), strategy, &Id::new(0), &prices);
+
+        // Then:
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn proportional_shares_sum_up_to_fee() {
+        // Given:
+        let prices = amounts(vec![
+            (1, Money::new(3, 33)),
+            (2, Money::new(5, 0)),
+            (3, Money::new(7, 77)),
+        ]);
+
+        // When:
+        let result = split_fee(
+            Money::new(2, 99),
+            FeeSplitStrategy::Proportional,
+            &Id::new(1),
+            &prices,
+        );
+
+        // Then:
+        let mut sum = Money::zero();
+        for share in result.values() {
+            sum += *share;
+        }
+        assert_eq!(sum, Money::new(2, 99));
+        assert_eq!(result[&Id::new(1)], Money::new(0, 62));
+        assert_eq!(result[&Id::new(2)], Money::new(0, 93));
+        assert_eq!(result[&Id::new(3)], Money::new(1, 44));
+    }
+
+    #[test]
+    fn manager_pays_fee_if_nobody_ordered() {
+        // Given:
+        let prices = amounts(vec![(0, Money::zero()), (1, Money::zero())]);
+
+        // When:
+        let result = split_fee(
+            Money::new(2, 0),
+            FeeSplitStrategy::Equal,
+            &Id::new(0),
+            &prices,
+        );
+
+        // Then:
+        assert_eq!(result, amounts(vec![(0, Money::new(2, 0))]));
+    }
+
+    #[test]
+    fn no_fee_has_no_shares() {
+        // When:
+        let result = split_fee(
+            Money::zero(),
+            FeeSplitStrategy::Equal,
+            &Id::new(0),
+            &amounts(vec![(1, Money::new(4, 0))]),
+        );
+
+        // Then:
+        assert!(result.is_empty());
+    }
+
+    #[rstest(
+        strategy,
+        expected,
+        case(
+            FeeSplitStrategy::Equal,
+            amounts(vec![(1, Money::new(0, 1)), (2, Money::new(0, 1))])
+        ),
+        case(
+            FeeSplitStrategy::Proportional,
+            amounts(vec![(2, Money::new(0, 1))])
+        ),
+        case(FeeSplitStrategy::ManagerPays, amounts(vec![]))
+    )]
+    fn rounding_of_shares_is_recorded(
+        strategy: FeeSplitStrategy,
+        expected: HashMap<Id<User>, Money>,
+    ) {
+        // Given:
+        let prices = amounts(vec![
+            (1, Money::new(4, 0)),
+            (2, Money::new(6, 50)),
+            (3, Money::new(6, 50)),
+        ]);
+
+        // When:
+        let (shares, rounding) =
+            split_fee_with_rounding(Money::new(2, 0), strategy, &Id::new(0), &prices);
+
+        // Then:
+        assert_eq!(rounding, expected);
+        assert_eq!(
+            shares,
+            split_fee(Money::new(2, 0), strategy, &Id::new(0), &prices)
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::order_model::meal::Meal;
+use crate::order_model::order::Order;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::fmt;
+
+/// A broken invariant found in loaded data, e.g. after a migration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// The `Meals` stored for a user belong to somebody else
+    OwnerMismatch {
+        user_id: Id<User>,
+        owner_id: Id<User>,
+    },
+    /// Several meals of the order share an ID
+    DuplicateMealId(Id<Meal>),
+    ManagerNotParticipating(Id<User>),
+    /// A participant is not a registered user
+    UnknownUser(Id<User>),
+    /// The total price differs from the sum of the meals and the delivery fee
+    InconsistentTotal {
+        expected: Money,
+        actual: Money,
+    },
+    /// The order is both active and archived
+    ArchivedAndActive,
+}
+
+impl IntegrityIssue {
+    /// How the issue can be repaired.
+    pub fn get_repair(&self) -> &'static str {
+        use IntegrityIssue::*;
+        match self {
+            OwnerMismatch { .. } => "store the meals under the ID of their owner",
+            DuplicateMealId(_) => "assign new IDs to all but one of the meals",
+            ManagerNotParticipating(_) => "add the manager as participant",
+            UnknownUser(_) => "register the user or remove them from the order",
+            InconsistentTotal { .. } => "recalculate the prices from the menu",
+            ArchivedAndActive => "remove the active copy of the order",
+        }
+    }
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use IntegrityIssue::*;
+        match self {
+            OwnerMismatch { user_id, owner_id } => write!(
+                f,
+                "meals of user {} belong to user {}",
+                user_id.get_value(),
+                owner_id.get_value()
+            ),
+            DuplicateMealId(id) => write!(f, "meal ID {} is used several times", id.get_value()),
+            ManagerNotParticipating(id) => {
+                write!(f, "manager {} is not participating", id.get_value())
+            }
+            UnknownUser(id) => write!(f, "user {} is not registered", id.get_value()),
+            InconsistentTotal { expected, actual } => {
+                write!(f, "total price is {} instead of {}", actual, expected)
+            }
+            ArchivedAndActive => write!(f, "order is both active and archived"),
+        }
+    }
+}
+
+/// An `IntegrityIssue` of a specific order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderIssue<V = u32> {
+    order_id: Id<Order, V>,
+    issue: IntegrityIssue,
+}
+
+impl<V: Clone> OrderIssue<V> {
+    pub fn new(order_id: Id<Order, V>, issue: IntegrityIssue) -> OrderIssue<V> {
+        OrderIssue { order_id, issue }
+    }
+
+    pub fn get_order_id(&self) -> Id<Order, V> {
+        self.order_id.clone()
+    }
+
+    pub fn get_issue(&self) -> &IntegrityIssue {
+        &self.issue
+    }
+}
+
+impl<V: fmt::Display> fmt::Display for OrderIssue<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order {}: {}, repair: {}",
+            self.order_id.value(),
+            self.issue,
+            self.issue.get_repair()
+        )
+    }
+}
+
+/// Result of `OrderManager::verify_integrity`, listing everything that needs to be repaired.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntegrityReport<V = u32> {
+    /// Sorted by order ID
+    issues: Vec<OrderIssue<V>>,
+}
+
+impl<V: Ord> IntegrityReport<V> {
+    pub fn new(mut issues: Vec<OrderIssue<V>>) -> IntegrityReport<V> {
+        issues.sort_by(|first, second| first.order_id.cmp(&second.order_id));
+        IntegrityReport { issues }
+    }
+}
+
+impl<V> IntegrityReport<V> {
+    pub fn issues(&self) -> &[OrderIssue<V>] {
+        &self.issues
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl<V: fmt::Display> fmt::Display for IntegrityReport<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_healthy() {
+            return writeln!(f, "no issues found");
+        }
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_issues_with_repairs_by_order() {
+        // Given:
+        let report = IntegrityReport::new(vec![
+            OrderIssue::new(Id::new(2), IntegrityIssue::UnknownUser(Id::new(7))),
+            OrderIssue::new(Id::new(1), IntegrityIssue::DuplicateMealId(Id::new(3))),
+        ]);
+
+        // When:
+        let text = report.to_string();
+
+        // Then:
+        assert!(!report.is_healthy());
+        assert_eq!(
+            text,
+            "order 1: meal ID 3 is used several times, repair: assign new IDs to all but one of the meals\n\
+             order 2: user 7 is not registered, repair: register the user or remove them from the order\n"
+        );
+    }
+
+    #[test]
+    fn empty_report_is_healthy() {
+        // When:
+        let report: IntegrityReport = IntegrityReport::default();
+
+        // Then:
+        assert!(report.is_healthy());
+        assert_eq!(report.to_string(), "no issues found\n");
+    }
+}
@@ -0,0 +1,700 @@
+use crate::order_model::archive::ArchivedOrder;
+use crate::order_model::integrity::{IntegrityIssue, IntegrityReport, OrderIssue};
+use crate::order_model::order::{Order, OrderStatus};
+use crate::order_model::retention::{RetentionAction, RetentionPolicy, RetentionReport};
+use crate::order_model::user::User;
+use crate::user_model::repository::UserRepository;
+use crate::util::clock::Clock;
+use crate::util::id::Id;
+use crate::util::id_provider::{IdProvider, IdScheme, Sequential};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Creates orders with IDs of the given scheme, consecutive ones by default.
+#[derive(Debug, Default, PartialEq)]
+pub struct OrderFactory<S = Sequential> {
+    id_provider: IdProvider<Order, S>,
+}
+
+impl OrderFactory {
+    pub fn new() -> OrderFactory {
+        OrderFactory {
+            id_provider: IdProvider::new(),
+        }
+    }
+}
+
+impl<S: IdScheme> OrderFactory<S> {
+    /// Creates orders with IDs of the provider, e.g. to continue after the ones of restored orders.
+    pub fn with_id_provider(id_provider: IdProvider<Order, S>) -> OrderFactory<S> {
+        OrderFactory { id_provider }
+    }
+
+    /// Creates an `Order` managed by the given user together with its unique ID.
+    pub fn create_order(&mut self, manager_id: Id<User>) -> (Id<Order, S::Value>, Order) {
+        (self.id_provider.generate_next(), Order::new(manager_id))
+    }
+
+    /// Like `create_order`, but the history of the order is timestamped with the given clock.
+    pub fn create_order_with_clock(
+        &mut self,
+        manager_id: Id<User>,
+        clock: Arc<dyn Clock + Send + Sync>,
+    ) -> (Id<Order, S::Value>, Order) {
+        (
+            self.id_provider.generate_next(),
+            Order::with_audit_clock(manager_id, clock),
+        )
+    }
+}
+
+/// Keeps track of all concurrent orders and of the archived ones, identified by IDs of the given scheme,
+/// consecutive ones by default.
+#[derive(Debug, Default)]
+pub struct OrderManager<S: IdScheme = Sequential> {
+    /// Active orders by ID
+    orders: HashMap<Id<Order, S::Value>, Order>,
+    /// Delivered orders moved out of the way, so the active ones stay few
+    archive: HashMap<Id<Order, S::Value>, ArchivedOrder>,
+    order_factory: OrderFactory<S>,
+}
+
+// Implemented by hand, deriving would only require the IDs to be comparable, not to be hashable as well
+impl<S: IdScheme + PartialEq> PartialEq for OrderManager<S>
+where
+    S::Value: Eq + Hash,
+{
+    fn eq(&self, other: &OrderManager<S>) -> bool {
+        self.orders == other.orders
+            && self.archive == other.archive
+            && self.order_factory == other.order_factory
+    }
+}
+
+impl OrderManager {
+    pub fn new() -> OrderManager {
+        OrderManager::with_order_factory(OrderFactory::new())
+    }
+}
+
+impl<S: IdScheme> OrderManager<S>
+where
+    S::Value: Clone + Eq + Hash + Ord,
+{
+    /// Creates orders with the factory, e.g. one with random IDs which don't collide with other servers.
+    pub fn with_order_factory(order_factory: OrderFactory<S>) -> OrderManager<S> {
+        OrderManager {
+            orders: HashMap::new(),
+            archive: HashMap::new(),
+            order_factory,
+        }
+    }
+
+    /// Creates a new `Order` managed by the given user and returns its ID.
+    pub fn create_order(&mut self, manager_id: Id<User>) -> Id<Order, S::Value> {
+        let (id, order) = self.order_factory.create_order(manager_id);
+        self.orders.insert(id.clone(), order);
+        id
+    }
+
+    /// Like `create_order`, but the history of the order is timestamped with the given clock.
+    pub fn create_order_with_clock(
+        &mut self,
+        manager_id: Id<User>,
+        clock: Arc<dyn Clock + Send + Sync>,
+    ) -> Id<Order, S::Value> {
+        let (id, order) = self
+            .order_factory
+            .create_order_with_clock(manager_id, clock);
+        self.orders.insert(id.clone(), order);
+        id
+    }
+
+    /// Adds an order made elsewhere, e.g. a copy of another one, and returns its new ID.
+    pub fn add_order(&mut self, order: Order) -> Id<Order, S::Value> {
+        let id = self.order_factory.id_provider.generate_next();
+        self.orders.insert(id.clone(), order);
+        id
+    }
+
+    pub fn get_order(&self, id: &Id<Order, S::Value>) -> Option<&Order> {
+        self.orders.get(id)
+    }
+
+    pub fn get_order_mut(&mut self, id: &Id<Order, S::Value>) -> Option<&mut Order> {
+        self.orders.get_mut(id)
+    }
+
+    /// Iterates over all orders which are not archived.
+    pub fn orders(&self) -> impl Iterator<Item = (&Id<Order, S::Value>, &Order)> {
+        self.orders.iter()
+    }
+
+    /// IDs of the orders users can still join, sorted ascending.
+    pub fn open_orders(&self) -> Vec<Id<Order, S::Value>> {
+        let mut open: Vec<Id<Order, S::Value>> = self
+            .find_by_status(&OrderStatus::Open)
+            .map(|(id, _)| id.clone())
+            .collect();
+        open.sort();
+        open
+    }
+
+    /// Iterates over the orders which are not archived and have the same status as the given one, regardless of
+    /// when an order was placed or is expected.
+    pub fn find_by_status<'a>(
+        &'a self,
+        status: &OrderStatus,
+    ) -> impl Iterator<Item = (&'a Id<Order, S::Value>, &'a Order)> {
+        let status = mem::discriminant(status);
+        self.orders
+            .iter()
+            .filter(move |(_, order)| mem::discriminant(order.get_status()) == status)
+    }
+
+    /// Iterates over the orders which are not archived and the given user takes part in.
+    pub fn find_by_participant<'a>(
+        &'a self,
+        user_id: &'a Id<User>,
+    ) -> impl Iterator<Item = (&'a Id<Order, S::Value>, &'a Order)> {
+        self.orders
+            .iter()
+            .filter(move |(_, order)| order.is_participating(user_id))
+    }
+
+    /// Iterates over the orders which are not archived and were created from `from` until before `to`.
+    pub fn find_by_date_range(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> impl Iterator<Item = (&Id<Order, S::Value>, &Order)> {
+        self.orders.iter().filter(move |(_, order)| {
+            let created_at = order.get_created_at();
+            from <= created_at && created_at < to
+        })
+    }
+
+    /// Iterates over the open orders whose deadline is before the given one, e.g. to remind their participants.
+    pub fn find_open_before(
+        &self,
+        deadline: DateTime<Utc>,
+    ) -> impl Iterator<Item = (&Id<Order, S::Value>, &Order)> {
+        self.find_by_status(&OrderStatus::Open)
+            .filter(move |(_, order)| order.get_deadline().is_some_and(|due| due < deadline))
+    }
+
+    /// Orders being ordered or still open whose last change is more than `idle` ago, e.g. because the manager
+    /// forgot them, sorted ascending with their status and the time since the last change.
+    pub fn stuck_orders(
+        &self,
+        now: SystemTime,
+        idle: Duration,
+    ) -> Vec<(Id<Order, S::Value>, OrderStatus, Duration)> {
+        let mut stuck: Vec<(Id<Order, S::Value>, OrderStatus, Duration)> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| {
+                matches!(
+                    order.get_status(),
+                    OrderStatus::Open | OrderStatus::Ordering | OrderStatus::Ordered { .. }
+                )
+            })
+            .filter_map(|(id, order)| {
+                let last_change = order.history().last()?.get_time();
+                let since = now.duration_since(last_change).ok()?;
+                Some((id.clone(), order.get_status().clone(), since)).filter(|_| since > idle)
+            })
+            .collect();
+        stuck.sort_by(|(first, _, _), (second, _, _)| first.cmp(second));
+        stuck
+    }
+
+    /// Moves all delivered and closed orders to the archive and returns their IDs, sorted ascending.
+    pub fn archive_delivered(&mut self) -> Vec<Id<Order, S::Value>> {
+        let mut delivered: Vec<Id<Order, S::Value>> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| {
+                matches!(
+                    order.get_status(),
+                    OrderStatus::Delivered | OrderStatus::Closed
+                )
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        delivered.sort();
+        self.move_to_archive(&delivered);
+        delivered
+    }
+
+    /// Moves the delivered and closed orders to the archive which were delivered at least as long ago at `now` as
+    /// the policy says, and returns their IDs, sorted ascending.
+    pub fn archive_due(
+        &mut self,
+        policy: &RetentionPolicy,
+        now: SystemTime,
+    ) -> Vec<Id<Order, S::Value>> {
+        let mut due: Vec<Id<Order, S::Value>> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| {
+                order.get_delivered_at().is_some_and(|delivered_at| {
+                    policy.archives(now.duration_since(delivered_at).unwrap_or_default())
+                })
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        due.sort();
+        self.move_to_archive(&due);
+        due
+    }
+
+    fn move_to_archive(&mut self, ids: &[Id<Order, S::Value>]) {
+        for id in ids {
+            let order = self.orders.remove(id).unwrap();
+            self.archive.insert(id.clone(), ArchivedOrder::new(order));
+        }
+    }
+
+    /// Scans all orders for broken invariants, e.g. after loading them, and reports how to repair them.
+    pub fn verify_integrity(&self, users: &UserRepository) -> IntegrityReport<S::Value> {
+        let mut issues = Vec::new();
+        let archived = self
+            .archive
+            .iter()
+            .map(|(id, archived)| (id, archived.get_order()));
+        for (order_id, order) in self.orders.iter().chain(archived) {
+            issues.extend(
+                order
+                    .check_integrity()
+                    .into_iter()
+                    .map(|issue| OrderIssue::new(order_id.clone(), issue)),
+            );
+            // The participants of anonymized orders are pseudonyms on purpose
+            let mut unknown: Vec<&Id<User>> = order
+                .participants()
+                .filter(|_| !order.is_anonymized())
+                .filter(|user_id| !users.contains(user_id))
+                .collect();
+            unknown.sort_by_key(|id| id.get_value());
+            issues.extend(unknown.into_iter().map(|user_id| {
+                OrderIssue::new(
+                    order_id.clone(),
+                    IntegrityIssue::UnknownUser(user_id.clone()),
+                )
+            }));
+        }
+        issues.extend(
+            self.orders
+                .keys()
+                .filter(|id| self.archive.contains_key(id))
+                .map(|id| OrderIssue::new(id.clone(), IntegrityIssue::ArchivedAndActive)),
+        );
+        IntegrityReport::new(issues)
+    }
+
+    /// Adds an order straight to the archive, e.g. one imported from elsewhere, and returns its new ID.
+    pub fn archive_order(&mut self, order: Order) -> Id<Order, S::Value> {
+        let id = self.order_factory.id_provider.generate_next();
+        self.archive.insert(id.clone(), ArchivedOrder::new(order));
+        id
+    }
+
+    pub fn get_archived_order(&self, id: &Id<Order, S::Value>) -> Option<&Order> {
+        self.archive.get(id).map(ArchivedOrder::get_order)
+    }
+
+    pub fn archived_orders(&self) -> impl Iterator<Item = (&Id<Order, S::Value>, &Order)> {
+        self.archive
+            .iter()
+            .map(|(id, archived)| (id, archived.get_order()))
+    }
+
+    pub fn get_archived(&self, id: &Id<Order, S::Value>) -> Option<&ArchivedOrder> {
+        self.archive.get(id)
+    }
+
+    /// Iterates over the archived orders together with the summaries taken when they were archived.
+    pub fn archived(&self) -> impl Iterator<Item = (&Id<Order, S::Value>, &ArchivedOrder)> {
+        self.archive.iter()
+    }
+
+    /// Archives the delivered orders and anonymizes or deletes the archived orders which are older at `now` than the
+    /// policy allows.
+    pub fn enforce_retention(
+        &mut self,
+        policy: &RetentionPolicy,
+        now: SystemTime,
+    ) -> RetentionReport<S::Value> {
+        let archived = self.archive_due(policy, now);
+        let mut anonymized = Vec::new();
+        let mut deleted = Vec::new();
+        for (id, order) in self.archived_orders() {
+            let age = now
+                .duration_since(order.get_created_at())
+                .unwrap_or_default();
+            match policy.action_for(age) {
+                Some(RetentionAction::Delete) => deleted.push(id.clone()),
+                Some(RetentionAction::Anonymize) if !order.is_anonymized() => {
+                    anonymized.push(id.clone())
+                }
+                _ => {}
+            }
+        }
+        anonymized.sort();
+        deleted.sort();
+        for id in &anonymized {
+            self.archive.get_mut(id).unwrap().anonymize();
+        }
+        for id in &deleted {
+            self.archive.remove(id);
+        }
+        RetentionReport::new(anonymized, deleted).with_archived(archived)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::TestClock;
+    #[cfg(feature = "uuid")]
+    use crate::util::id_provider::RandomUuid;
+    use std::time::{Duration, SystemTime};
+
+    const YEAR: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+    #[test]
+    fn order_factory_creates_orders_with_unique_ids() {
+        // Given:
+        let mut order_factory = OrderFactory::new();
+
+        // When:
+        let (first_id, first) = order_factory.create_order(Id::new(0));
+        let (second_id, _) = order_factory.create_order(Id::new(1));
+
+        // Then:
+        assert_eq!(first_id, Id::new(0));
+        assert_eq!(second_id, Id::new(1));
+        assert_eq!(first, Order::new(Id::new(0)));
+    }
+
+    #[test]
+    fn order_factory_continues_after_given_id() {
+        // Given:
+        let mut order_factory = OrderFactory::with_id_provider(IdProvider::starting_at(7));
+
+        // When:
+        let (id, _) = order_factory.create_order(Id::new(0));
+
+        // Then:
+        assert_eq!(id, Id::new(7));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn order_factory_can_create_orders_with_random_ids() {
+        // Given:
+        let mut order_factory = OrderFactory::with_id_provider(IdProvider::with_scheme(RandomUuid));
+
+        // When:
+        let (first_id, _) = order_factory.create_order(Id::new(0));
+        let (second_id, _) = order_factory.create_order(Id::new(0));
+
+        // Then:
+        assert_ne!(first_id, second_id);
+        assert_eq!(first_id.get_uuid().get_version_num(), 4);
+    }
+
+    #[test]
+    fn created_order_can_be_looked_up() {
+        // Given:
+        let mut manager = OrderManager::new();
+
+        // When:
+        let id = manager.create_order(Id::new(3));
+
+        // Then:
+        assert_eq!(manager.get_order(&id), Some(&Order::new(Id::new(3))));
+        assert_eq!(manager.get_order(&Id::new(1)), None);
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn manager_can_identify_orders_by_random_ids() {
+        // Given:
+        let mut manager = OrderManager::with_order_factory(OrderFactory::with_id_provider(
+            IdProvider::with_scheme(RandomUuid),
+        ));
+        let first = manager.create_order(Id::new(3));
+        let second = manager.create_order(Id::new(4));
+        {
+            let order = manager.get_order_mut(&second).unwrap();
+            order.start_ordering().unwrap();
+            order.mark_ordered(None).unwrap();
+            order.mark_delivered(SystemTime::UNIX_EPOCH).unwrap();
+        }
+
+        // When:
+        let archived = manager.archive_delivered();
+
+        // Then:
+        assert_ne!(first, second);
+        assert_eq!(first.get_uuid().get_version_num(), 4);
+        assert_eq!(manager.open_orders(), vec![first.clone()]);
+        assert_eq!(manager.get_order(&first), Some(&Order::new(Id::new(3))));
+        assert_eq!(archived, vec![second.clone()]);
+        assert!(manager.get_archived_order(&second).is_some());
+    }
+
+    #[test]
+    fn only_open_orders_are_listed_as_open() {
+        // Given:
+        let mut manager = OrderManager::new();
+        let first = manager.create_order(Id::new(0));
+        let second = manager.create_order(Id::new(0));
+        let third = manager.create_order(Id::new(0));
+        manager
+            .get_order_mut(&second)
+            .unwrap()
+            .start_ordering()
+            .unwrap();
+
+        // When:
+        let open = manager.open_orders();
+
+        // Then:
+        assert_eq!(open, vec![first, third]);
+    }
+
+    #[test]
+    fn orders_are_found_by_status_and_participant() {
+        // Given:
+        let mut manager = OrderManager::new();
+        let open = manager.create_order(Id::new(0));
+        let ordered = manager.create_order(Id::new(1));
+        let order = manager.get_order_mut(&ordered).unwrap();
+        order.add_user(Id::new(0)).unwrap();
+        order.start_ordering().unwrap();
+        order.mark_ordered(None).unwrap();
+
+        // When:
+        let by_status: Vec<Id<Order>> = manager
+            .find_by_status(&OrderStatus::Ordered {
+                placed_at: Utc::now(),
+                eta: None,
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        let mut by_participant: Vec<Id<Order>> = manager
+            .find_by_participant(&Id::new(0))
+            .map(|(id, _)| id.clone())
+            .collect();
+        by_participant.sort_by_key(Id::get_value);
+
+        // Then:
+        assert_eq!(by_status, vec![ordered.clone()]);
+        assert_eq!(by_participant, vec![open, ordered]);
+        assert_eq!(manager.find_by_participant(&Id::new(2)).count(), 0);
+    }
+
+    #[test]
+    fn orders_are_found_by_creation_date_and_deadline() {
+        // Given:
+        let clock = TestClock::default();
+        let hour = Duration::from_secs(60 * 60);
+        let mut manager = OrderManager::new();
+        let early = manager.create_order_with_clock(Id::new(0), Arc::new(clock.clone()));
+        clock.advance(hour);
+        let late = manager.create_order_with_clock(Id::new(0), Arc::new(clock.clone()));
+        let noon = DateTime::parse_from_rfc3339("2020-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let deadline = chrono::Duration::hours(1);
+        for (id, due) in [(&early, noon), (&late, noon + deadline)] {
+            manager
+                .get_order_mut(id)
+                .unwrap()
+                .set_deadline(Some(due))
+                .unwrap();
+        }
+
+        // When:
+        let created: Vec<&Id<Order>> = manager
+            .find_by_date_range(SystemTime::UNIX_EPOCH, SystemTime::UNIX_EPOCH + hour)
+            .map(|(id, _)| id)
+            .collect();
+        let due: Vec<&Id<Order>> = manager
+            .find_open_before(noon + deadline)
+            .map(|(id, _)| id)
+            .collect();
+
+        // Then:
+        assert_eq!(created, vec![&early]);
+        assert_eq!(due, vec![&early]);
+    }
+
+    #[test]
+    fn integrity_of_orders_is_verified() {
+        // Given:
+        let mut users = UserRepository::new();
+        let manager_id = users.register(String::from("Anna")).unwrap().get_id();
+        let mut manager = OrderManager::new();
+        let healthy = manager.create_order(manager_id.clone());
+        let broken = manager.create_order(manager_id);
+        manager
+            .get_order_mut(&broken)
+            .unwrap()
+            .add_user(Id::new(7))
+            .unwrap();
+        let (_, archived) = OrderFactory::new().create_order(Id::new(0));
+        manager
+            .archive
+            .insert(broken.clone(), ArchivedOrder::new(archived));
+
+        // When:
+        let report = manager.verify_integrity(&users);
+
+        // Then:
+        assert!(report
+            .issues()
+            .iter()
+            .all(|issue| issue.get_order_id() != healthy));
+        assert_eq!(
+            report.issues(),
+            &[
+                OrderIssue::new(broken.clone(), IntegrityIssue::UnknownUser(Id::new(7))),
+                OrderIssue::new(broken, IntegrityIssue::ArchivedAndActive),
+            ]
+        );
+    }
+
+    #[test]
+    fn delivered_orders_are_archived() {
+        // Given:
+        let mut manager = OrderManager::new();
+        let open = manager.create_order(Id::new(0));
+        let delivered = manager.create_order(Id::new(0));
+        {
+            let order = manager.get_order_mut(&delivered).unwrap();
+            order.start_ordering().unwrap();
+            order.mark_ordered(None).unwrap();
+            order.mark_delivered(SystemTime::UNIX_EPOCH).unwrap();
+        }
+
+        // When:
+        let archived = manager.archive_delivered();
+
+        // Then:
+        assert_eq!(archived, vec![delivered.clone()]);
+        assert!(manager.get_order(&delivered).is_none());
+        assert!(manager.get_archived_order(&delivered).is_some());
+        assert!(manager.get_order(&open).is_some());
+        assert_eq!(manager.archived_orders().count(), 1);
+    }
+
+    #[test]
+    fn orders_delivered_long_enough_ago_are_archived_with_summary() {
+        // Given:
+        let day = Duration::from_secs(24 * 60 * 60);
+        let mut manager = OrderManager::new();
+        let mut deliver = |delivered_at: SystemTime| {
+            let id = manager.create_order(Id::new(0));
+            let order = manager.get_order_mut(&id).unwrap();
+            order.start_ordering().unwrap();
+            order.mark_ordered(None).unwrap();
+            order.mark_delivered(delivered_at).unwrap();
+            id
+        };
+        let old = deliver(SystemTime::UNIX_EPOCH);
+        let recent = deliver(SystemTime::UNIX_EPOCH + 6 * day);
+        let open = manager.create_order(Id::new(0));
+        let policy = RetentionPolicy::default().with_archive_after(7 * day);
+
+        // When:
+        let report = manager.enforce_retention(&policy, SystemTime::UNIX_EPOCH + 10 * day);
+
+        // Then:
+        assert_eq!(report.archived().to_vec(), vec![old.clone()]);
+        assert!(manager.get_order(&old).is_none());
+        assert!(manager.get_order(&recent).is_some());
+        assert!(manager.get_order(&open).is_some());
+        let archived: Vec<(&Id<Order>, &ArchivedOrder)> = manager.archived().collect();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].0, &old);
+        assert_eq!(archived[0].1.get_summary().get_participants(), 1);
+    }
+
+    #[test]
+    fn orders_without_recent_changes_are_stuck() {
+        // Given:
+        let clock = TestClock::default();
+        let hour = Duration::from_secs(60 * 60);
+        let mut manager = OrderManager::new();
+        let forgotten = manager.create_order_with_clock(Id::new(0), Arc::new(clock.clone()));
+        let cancelled = manager.create_order_with_clock(Id::new(0), Arc::new(clock.clone()));
+        manager.get_order_mut(&cancelled).unwrap().cancel().unwrap();
+        clock.advance(2 * hour);
+        let recent = manager.create_order_with_clock(Id::new(1), Arc::new(clock.clone()));
+        clock.advance(hour / 2);
+
+        // When:
+        let stuck = manager.stuck_orders(clock.now(), hour);
+
+        // Then:
+        assert_eq!(
+            stuck,
+            vec![(forgotten, OrderStatus::Open, 2 * hour + hour / 2)]
+        );
+        assert!(manager.get_order(&recent).is_some());
+    }
+
+    #[test]
+    fn archived_orders_are_anonymized_and_deleted_by_age() {
+        // Given:
+        let clock = TestClock::default();
+        let mut users = UserRepository::new();
+        let anna = users.register(String::from("Anna")).unwrap().get_id();
+        let mut manager = OrderManager::new();
+        let mut old = Order::with_audit_clock(Id::new(7), Arc::new(clock.clone()));
+        old.add_user(anna.clone()).unwrap();
+        let old = manager.archive_order(old);
+        clock.advance(YEAR);
+        let recent = manager.archive_order(Order::with_audit_clock(
+            anna.clone(),
+            Arc::new(clock.clone()),
+        ));
+        let active = manager.create_order_with_clock(anna, Arc::new(clock.clone()));
+        clock.advance(YEAR);
+        let policy = RetentionPolicy::new(Some(YEAR), Some(3 * YEAR));
+
+        // When:
+        let first = manager.enforce_retention(&policy, clock.now());
+        let second = manager.enforce_retention(&policy, clock.now());
+
+        // Then:
+        assert_eq!(
+            first,
+            RetentionReport::new(vec![old.clone(), recent], vec![])
+        );
+        assert!(second.is_empty());
+        let anonymized = manager.get_archived_order(&old).unwrap();
+        let mut participants: Vec<u32> = anonymized.participants().map(Id::get_value).collect();
+        participants.sort_unstable();
+        assert_eq!(participants, vec![0, 1]);
+        assert_eq!(anonymized.history().len(), 1);
+        assert!(manager.verify_integrity(&users).is_healthy());
+        assert!(!manager.get_order(&active).unwrap().is_anonymized());
+
+        // When:
+        clock.advance(YEAR);
+        let third = manager.enforce_retention(&policy, clock.now());
+
+        // Then:
+        assert_eq!(third.deleted(), &[Id::new(0)]);
+        assert!(manager.get_archived_order(&old).is_none());
+    }
+}
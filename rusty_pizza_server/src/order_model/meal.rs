@@ -1,14 +1,19 @@
+use crate::order_model::order::sum_amounts;
+use crate::order_model::preparation::Preparation;
 use crate::order_model::special::{Special, SpecialFactory};
+use crate::order_model::summary::{describe_meal, Verbosity};
+use crate::order_model::tax::TaxRate;
 use crate::util::errors::RemoveError;
 use crate::util::id::Id;
 use crate::util::id_provider::IdProvider;
 use crate::util::money::Money;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::iter::Iterator;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct MealFactory {
-    id_provider: IdProvider,
+    id_provider: IdProvider<Meal>,
 }
 
 impl MealFactory {
@@ -23,7 +28,7 @@ impl MealFactory {
     }
 }
 
-pub struct Specials<'a>(std::collections::hash_map::Values<'a, Id, Special>);
+pub struct Specials<'a>(std::collections::hash_map::Values<'a, Id<Special>, Special>);
 
 impl<'a> Iterator for Specials<'a> {
     type Item = &'a Special;
@@ -33,7 +38,7 @@ impl<'a> Iterator for Specials<'a> {
     }
 }
 
-pub struct SpecialsMut<'a>(std::collections::hash_map::ValuesMut<'a, Id, Special>);
+pub struct SpecialsMut<'a>(std::collections::hash_map::ValuesMut<'a, Id<Special>, Special>);
 
 impl<'a> Iterator for SpecialsMut<'a> {
     type Item = &'a mut Special;
@@ -43,21 +48,90 @@ impl<'a> Iterator for SpecialsMut<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// One reason for the price of a meal, see `Meal::price_breakdown`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PriceComponent {
+    /// The meal as it is on the menu
+    Base {
+        meal_id: String,
+        variety: String,
+        price: Money,
+    },
+    /// A special, which may cost nothing
+    Special { description: String, price: Money },
+    /// Deposit on the bottle or can, until it is returned
+    Deposit(Money),
+    /// How the meal is prepared, which costs nothing extra
+    Preparation(Preparation),
+}
+
+impl PriceComponent {
+    pub fn get_price(&self) -> Money {
+        match self {
+            PriceComponent::Base { price, .. } => *price,
+            PriceComponent::Special { price, .. } => *price,
+            PriceComponent::Deposit(deposit) => *deposit,
+            PriceComponent::Preparation(_) => Money::zero(),
+        }
+    }
+}
+
+/// Why a meal costs what it costs, e.g. for receipts: the base price, then the specials in the order they were
+/// added, then the deposit, then the preparations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PriceBreakdown {
+    components: Vec<PriceComponent>,
+}
+
+impl PriceBreakdown {
+    pub fn components(&self) -> &[PriceComponent] {
+        &self.components
+    }
+
+    /// Sum of the components, the total price of the meal.
+    pub fn get_total(&self) -> Money {
+        self.components
+            .iter()
+            .fold(Money::zero(), |total, component| {
+                total + component.get_price()
+            })
+    }
+
+    /// Part of the total that is refunded when the bottle or can is returned, as a subtotal on receipts.
+    pub fn get_deposit(&self) -> Money {
+        self.components
+            .iter()
+            .fold(Money::zero(), |total, component| match component {
+                PriceComponent::Deposit(deposit) => total + *deposit,
+                _ => total,
+            })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Meal {
     /// Unique ID of this meal
-    id: Id,
+    id: Id<Meal>,
     /// Number of the meal in the menu
     meal_id: String,
     /// Size of the pizza or noodle type etc.
     variety: String,
     price: Money,
-    specials: HashMap<Id, Special>,
+    specials: HashMap<Id<Special>, Special>,
     special_factory: SpecialFactory,
+    preparations: BTreeSet<Preparation>,
+    /// Free text for anything specials and preparations don't cover, e.g. "cut into 8 slices"
+    note: Option<String>,
+    /// VAT included in the price, `None` if unknown
+    tax_rate: Option<TaxRate>,
+    /// Deposit (Pfand) charged on top of the price, e.g. for a bottle
+    deposit: Money,
+    /// Whether the bottle or can was returned and the deposit refunded
+    deposit_returned: bool,
 }
 
 impl Meal {
-    pub fn new(id: Id, meal_id: String, variety: String, price: Money) -> Meal {
+    pub fn new(id: Id<Meal>, meal_id: String, variety: String, price: Money) -> Meal {
         Meal {
             id,
             meal_id,
@@ -65,36 +139,184 @@ impl Meal {
             price,
             specials: HashMap::new(),
             special_factory: SpecialFactory::new(),
+            preparations: BTreeSet::new(),
+            note: None,
+            tax_rate: None,
+            deposit: Money::zero(),
+            deposit_returned: false,
         }
     }
 
-    pub fn get_id(&self) -> Id {
+    pub fn get_id(&self) -> Id<Meal> {
         self.id.clone()
     }
 
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub(crate) fn set_meal_id(&mut self, meal_id: String) {
+        self.meal_id = meal_id;
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    pub(crate) fn set_variety(&mut self, variety: String) {
+        self.variety = variety;
+    }
+
+    /// Price of the meal itself, without specials
     pub fn get_price(&self) -> Money {
         self.price
     }
 
+    pub(crate) fn set_price(&mut self, price: Money) {
+        self.price = price;
+    }
+
+    /// Price of the meal including the prices of all its specials and the deposit, unless it was returned
+    pub fn get_total_price(&self) -> Money {
+        sum_amounts(
+            [self.price, self.get_open_deposit()]
+                .iter()
+                .copied()
+                .chain(self.specials.values().filter_map(Special::get_price)),
+        )
+    }
+
+    pub fn price_breakdown(&self) -> PriceBreakdown {
+        let mut specials: Vec<&Special> = self.specials.values().collect();
+        specials.sort_by_key(|special| special.get_id());
+        let components = std::iter::once(PriceComponent::Base {
+            meal_id: self.meal_id.clone(),
+            variety: self.variety.clone(),
+            price: self.price,
+        })
+        .chain(specials.into_iter().map(|special| PriceComponent::Special {
+            description: special.get_description(),
+            price: special.get_price().unwrap_or_else(Money::zero),
+        }))
+        .chain(
+            Some(self.get_open_deposit())
+                .filter(|deposit| *deposit > Money::zero())
+                .map(PriceComponent::Deposit),
+        )
+        .chain(
+            self.preparations
+                .iter()
+                .map(|preparation| PriceComponent::Preparation(*preparation)),
+        )
+        .collect();
+        PriceBreakdown { components }
+    }
+
     /// Creates and adds a new special and returns a mutable reference to it.
-    pub fn add_special(&mut self, description: String) -> &mut Special {
+    pub(crate) fn add_special(&mut self, description: String) -> &mut Special {
         let special = self.special_factory.create_special(description);
+        self.insert_special(special)
+    }
+
+    /// Creates and adds a new special which costs extra and returns a mutable reference to it.
+    pub(crate) fn add_special_with_price(
+        &mut self,
+        description: String,
+        price: Money,
+    ) -> &mut Special {
+        let special = self
+            .special_factory
+            .create_special_with_price(description, price);
+        self.insert_special(special)
+    }
+
+    /// Adds a special created before, e.g. to restore a removed one, replacing any special with the same ID.
+    pub(crate) fn insert_special(&mut self, special: Special) -> &mut Special {
         let id = special.get_id();
         self.specials.insert(id.clone(), special);
         self.specials.get_mut(&id).unwrap()
     }
 
-    pub fn remove_special(&mut self, id: Id) -> Result<Special, RemoveError> {
+    pub(crate) fn remove_special(&mut self, id: Id<Special>) -> Result<Special, RemoveError> {
         self.specials.remove(&id).ok_or(RemoveError::NotFound)
     }
 
-    pub fn specials(&self) -> Specials {
+    pub fn specials(&self) -> Specials<'_> {
         Specials(self.specials.values())
     }
 
-    pub fn specials_mut(&mut self) -> SpecialsMut {
+    pub fn specials_mut(&mut self) -> SpecialsMut<'_> {
         SpecialsMut(self.specials.values_mut())
     }
+
+    pub fn get_preparations(&self) -> &BTreeSet<Preparation> {
+        &self.preparations
+    }
+
+    pub(crate) fn set_preparations(&mut self, preparations: BTreeSet<Preparation>) {
+        self.preparations = preparations;
+    }
+
+    pub fn get_note(&self) -> Option<&String> {
+        self.note.as_ref()
+    }
+
+    pub(crate) fn set_note(&mut self, note: Option<String>) {
+        self.note = note;
+    }
+
+    pub fn get_tax_rate(&self) -> Option<TaxRate> {
+        self.tax_rate
+    }
+
+    /// Specials are taxed at the rate of their meal.
+    pub(crate) fn set_tax_rate(&mut self, tax_rate: Option<TaxRate>) {
+        self.tax_rate = tax_rate;
+    }
+
+    /// Deposit charged on top of the price, whether or not it was returned.
+    pub fn get_deposit(&self) -> Money {
+        self.deposit
+    }
+
+    pub(crate) fn set_deposit(&mut self, deposit: Money) {
+        self.deposit = deposit;
+    }
+
+    /// Deposit still included in the total price, zero once it was returned.
+    pub fn get_open_deposit(&self) -> Money {
+        if self.deposit_returned {
+            Money::zero()
+        } else {
+            self.deposit
+        }
+    }
+
+    pub fn is_deposit_returned(&self) -> bool {
+        self.deposit_returned
+    }
+
+    /// Marks the bottle or can as returned and gives the refunded deposit, zero if it was returned before.
+    pub(crate) fn return_deposit(&mut self) -> Money {
+        let refund = self.get_open_deposit();
+        self.deposit_returned = true;
+        refund
+    }
+
+    /// Human-readable summary, e.g. "03 groß (Käserand) — 6,50€", see `Verbosity`.
+    pub fn summary(&self, verbosity: Verbosity) -> String {
+        let description = describe_meal(self, verbosity);
+        if verbosity == Verbosity::Brief {
+            return description;
+        }
+        format!("{} — {}", description, self.get_total_price())
+    }
+}
+
+impl fmt::Display for Meal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.summary(Verbosity::Normal))
+    }
 }
 
 #[cfg(test)]
@@ -121,10 +343,32 @@ mod tests {
                 price: Money::new(5, 50),
                 specials: HashMap::new(),
                 special_factory: SpecialFactory::new(),
+                preparations: BTreeSet::new(),
+                note: None,
+                tax_rate: None,
+                deposit: Money::zero(),
+                deposit_returned: false,
             }
         );
     }
 
+    #[test]
+    fn meal_attributes_can_be_read() {
+        // When:
+        let meal = Meal::new(
+            Id::new(0),
+            String::from("03"),
+            String::from("groß"),
+            Money::new(5, 50),
+        );
+
+        // Then:
+        assert_eq!(meal.get_id(), Id::new(0));
+        assert_eq!(meal.get_meal_id(), "03");
+        assert_eq!(meal.get_variety(), "groß");
+        assert_eq!(meal.get_price(), Money::new(5, 50));
+    }
+
     #[test]
     fn special_can_be_added_to_meal() {
         //Given
@@ -135,6 +379,11 @@ mod tests {
             price: Money::new(5, 50),
             specials: HashMap::new(),
             special_factory: SpecialFactory::new(),
+            preparations: BTreeSet::new(),
+            note: None,
+            tax_rate: None,
+            deposit: Money::zero(),
+            deposit_returned: false,
         };
 
         //When
@@ -156,10 +405,35 @@ mod tests {
                 price: Money::new(5, 50),
                 specials: expected_specials,
                 special_factory: expected_special_factory,
+                preparations: BTreeSet::new(),
+                note: None,
+                tax_rate: None,
+                deposit: Money::zero(),
+                deposit_returned: false,
             }
         );
     }
 
+    #[test]
+    fn special_prices_are_part_of_total_price() {
+        // Given:
+        let mut meal = Meal::new(
+            Id::new(0),
+            String::from("03"),
+            String::from("groß"),
+            Money::new(5, 50),
+        );
+
+        // When:
+        meal.add_special_with_price(String::from("extra Salami"), Money::new(1, 0));
+        meal.add_special_with_price(String::from("Käserand"), Money::new(0, 75));
+        meal.add_special(String::from("ohne Zwiebeln"));
+
+        // Then:
+        assert_eq!(meal.get_price(), Money::new(5, 50));
+        assert_eq!(meal.get_total_price(), Money::new(7, 25));
+    }
+
     #[test]
     fn added_special_is_mutable() {
         //Given
@@ -170,6 +444,11 @@ mod tests {
             price: Money::new(5, 50),
             specials: HashMap::new(),
             special_factory: SpecialFactory::new(),
+            preparations: BTreeSet::new(),
+            note: None,
+            tax_rate: None,
+            deposit: Money::zero(),
+            deposit_returned: false,
         };
         let special = meal.add_special(String::from("Kaserand"));
 
@@ -199,6 +478,11 @@ mod tests {
                 price: Money::new(5, 50),
                 specials: HashMap::new(),
                 special_factory: SpecialFactory::new(),
+                preparations: BTreeSet::new(),
+                note: None,
+                tax_rate: None,
+                deposit: Money::zero(),
+                deposit_returned: false,
             }
         );
     }
@@ -263,6 +547,11 @@ mod tests {
                 price: Money::new(5, 50),
                 specials: HashMap::new(),
                 special_factory: expected_special_factory,
+                preparations: BTreeSet::new(),
+                note: None,
+                tax_rate: None,
+                deposit: Money::zero(),
+                deposit_returned: false,
             }
         )
     }
@@ -288,6 +577,11 @@ mod tests {
                 price: Money::new(5, 50),
                 specials: HashMap::new(),
                 special_factory: SpecialFactory::new(),
+                preparations: BTreeSet::new(),
+                note: None,
+                tax_rate: None,
+                deposit: Money::zero(),
+                deposit_returned: false,
             }
         )
     }
@@ -329,4 +623,100 @@ mod tests {
         );
         assert_eq!(specials.next(), None);
     }
+
+    #[test]
+    fn price_breakdown_explains_total_price() {
+        // Given:
+        let mut meal = Meal::new(
+            Id::new(0),
+            String::from("03"),
+            String::from("groß"),
+            Money::new(7, 90),
+        );
+        meal.add_special_with_price(String::from("extra cheese"), Money::new(1, 50));
+        meal.add_special(String::from("no onions"));
+        meal.set_preparations(vec![Preparation::WellDone].into_iter().collect());
+
+        // When:
+        let breakdown = meal.price_breakdown();
+
+        // Then:
+        assert_eq!(
+            breakdown.components(),
+            &[
+                PriceComponent::Base {
+                    meal_id: String::from("03"),
+                    variety: String::from("groß"),
+                    price: Money::new(7, 90),
+                },
+                PriceComponent::Special {
+                    description: String::from("extra cheese"),
+                    price: Money::new(1, 50),
+                },
+                PriceComponent::Special {
+                    description: String::from("no onions"),
+                    price: Money::zero(),
+                },
+                PriceComponent::Preparation(Preparation::WellDone),
+            ]
+        );
+        assert_eq!(breakdown.get_total(), Money::new(9, 40));
+        assert_eq!(breakdown.get_total(), meal.get_total_price());
+    }
+
+    #[test]
+    fn meal_is_summarized_with_given_verbosity() {
+        // Given:
+        let mut meal = Meal::new(
+            Id::new(0),
+            String::from("03"),
+            String::from("groß"),
+            Money::new(9, 50),
+        );
+        meal.add_special_with_price(String::from("Käserand"), Money::new(1, 50));
+        meal.set_preparations(vec![Preparation::WellDone].into_iter().collect());
+        meal.set_note(Some(String::from("für Anna")));
+
+        // When:
+        let brief = meal.summary(Verbosity::Brief);
+        let detailed = meal.summary(Verbosity::Detailed);
+
+        // Then:
+        assert_eq!(brief, "03 groß");
+        assert_eq!(meal.to_string(), "03 groß (Käserand, well done) — 11,00€");
+        assert_eq!(
+            detailed,
+            "03 groß (Käserand, well done) \"für Anna\" — 11,00€"
+        );
+    }
+
+    #[test]
+    fn deposit_is_refunded_once() {
+        // Given:
+        let mut meal = Meal::new(
+            Id::new(0),
+            String::from("90"),
+            String::from("0,5l"),
+            Money::new(2, 0),
+        );
+        meal.set_deposit(Money::new(0, 25));
+        let breakdown = meal.price_breakdown();
+
+        // When:
+        let refund = meal.return_deposit();
+        let second_refund = meal.return_deposit();
+
+        // Then:
+        assert_eq!(
+            breakdown.components().last(),
+            Some(&PriceComponent::Deposit(Money::new(0, 25)))
+        );
+        assert_eq!(breakdown.get_deposit(), Money::new(0, 25));
+        assert_eq!(breakdown.get_total(), Money::new(2, 25));
+        assert_eq!(refund, Money::new(0, 25));
+        assert_eq!(second_refund, Money::zero());
+        assert_eq!(meal.get_total_price(), Money::new(2, 0));
+        assert_eq!(meal.get_deposit(), Money::new(0, 25));
+        assert_eq!(meal.price_breakdown().get_deposit(), Money::zero());
+    }
 }
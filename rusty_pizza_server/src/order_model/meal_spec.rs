@@ -0,0 +1,77 @@
+use crate::util::money::Money;
+
+/// Description of one or more identical meals a user wants to order, before they become `Meal`s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MealSpec {
+    /// How many meals of this kind should be ordered
+    quantity: u32,
+    /// Number of the meal in the menu
+    meal_id: String,
+    /// Size of the pizza or noodle type etc.
+    variety: String,
+    /// Descriptions of the specials
+    specials: Vec<String>,
+    /// Price of a single meal as the user typed it, `None` to take it from the menu
+    price: Option<Money>,
+}
+
+impl MealSpec {
+    pub fn new(quantity: u32, meal_id: String, variety: String, specials: Vec<String>) -> MealSpec {
+        MealSpec {
+            quantity,
+            meal_id,
+            variety,
+            specials,
+            price: None,
+        }
+    }
+
+    pub fn with_price(self, price: Money) -> MealSpec {
+        MealSpec {
+            price: Some(price),
+            ..self
+        }
+    }
+
+    pub fn get_quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    pub fn get_specials(&self) -> &Vec<String> {
+        &self.specials
+    }
+
+    pub fn get_price(&self) -> Option<Money> {
+        self.price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meal_spec_can_be_created() {
+        // When:
+        let spec = MealSpec::new(
+            2,
+            String::from("03"),
+            String::from("groß"),
+            vec![String::from("Käserand")],
+        );
+
+        // Then:
+        assert_eq!(spec.get_quantity(), 2);
+        assert_eq!(spec.get_meal_id(), "03");
+        assert_eq!(spec.get_variety(), "groß");
+        assert_eq!(spec.get_specials(), &vec![String::from("Käserand")]);
+    }
+}
@@ -1,9 +1,22 @@
 use crate::order_model::meal::Meal;
+use crate::order_model::order::sum_amounts;
+use crate::order_model::payment::{
+    Duplicate, DuplicateReason, HeldPayment, Installment, Payment, PaymentError, DUPLICATE_WINDOW,
+};
+use crate::order_model::preparation::Preparation;
+use crate::order_model::special::Special;
+use crate::order_model::summary::{summarize_meals, Verbosity};
+use crate::order_model::tip::TipStrategy;
+use crate::order_model::user::User;
+use crate::util::history::History;
 use crate::util::id::Id;
+use crate::util::id_provider::IdProvider;
+use crate::util::locale::MoneyFormat;
 use crate::util::money::Money;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
 use std::fmt;
+use std::time::SystemTime;
 
 #[derive(Debug, PartialEq)]
 pub enum ChangeMoneyError {
@@ -35,41 +48,379 @@ impl ChangeMoneyError {
 
 impl Error for ChangeMoneyError {}
 
-#[derive(Debug, PartialEq)]
+/// The undo history of a `Meals` doesn't match its meals anymore, so the operation can't be undone or redone.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MealsError {
+    /// The history refers to a meal that does not exist
+    MealNotFound(Id<Meal>),
+    /// The history refers to a special that does not exist on the meal
+    SpecialNotFound(Id<Meal>, Id<Special>),
+}
+
+impl fmt::Display for MealsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MealsError::MealNotFound(meal) => {
+                write!(
+                    f,
+                    "history refers to meal {} which does not exist",
+                    meal.get_value()
+                )
+            }
+            MealsError::SpecialNotFound(meal, special) => write!(
+                f,
+                "history refers to special {} of meal {} which does not exist",
+                special.get_value(),
+                meal.get_value()
+            ),
+        }
+    }
+}
+
+impl Error for MealsError {}
+
+pub struct MealsIter<'a>(std::collections::hash_map::Values<'a, Id<Meal>, Meal>);
+
+impl<'a> Iterator for MealsIter<'a> {
+    type Item = &'a Meal;
+
+    fn next(&mut self) -> Option<&'a Meal> {
+        self.0.next()
+    }
+}
+
+pub(crate) struct MealsIterMut<'a>(std::collections::hash_map::ValuesMut<'a, Id<Meal>, Meal>);
+
+impl<'a> Iterator for MealsIterMut<'a> {
+    type Item = &'a mut Meal;
+
+    fn next(&mut self) -> Option<&'a mut Meal> {
+        self.0.next()
+    }
+}
+
+/// Number of operations on a `Meals` that can be undone.
+pub const UNDO_LIMIT: usize = 10;
+
+/// A single modification of a `Meals`, used to undo and redo operations.
+#[derive(Clone, Debug, PartialEq)]
+enum MealsChange {
+    InsertMeal(Meal),
+    RemoveMeal(Id<Meal>),
+    /// Puts back the contained state of a meal that was changed
+    ReplaceMeal(Meal),
+    InsertSpecial(Id<Meal>, Special),
+    RemoveSpecial(Id<Meal>, Id<Special>),
+    SetPayments(Vec<Installment>),
+    SetTip(Money),
+}
+
+impl MealsChange {
+    /// Whether applying the change adds, removes or alters meals, as opposed to payments only.
+    fn changes_meals(&self) -> bool {
+        use MealsChange::*;
+        match self {
+            InsertMeal(_) | RemoveMeal(_) | ReplaceMeal(_) | InsertSpecial(..)
+            | RemoveSpecial(..) => true,
+            SetPayments(_) | SetTip(_) => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Meals {
     /// Meal by unique ID
-    meals: HashMap<Id, Meal>,
+    meals: HashMap<Id<Meal>, Meal>,
     /// User ID of this `Meals` owner
-    owner_id: Id,
+    owner_id: Id<User>,
     /// Whether the meals selection has been completed
     ready: bool,
-    paid: Money,
+    /// Installments in the order they were made
+    payments: Vec<Installment>,
+    /// Suspected duplicates, not counted as paid until the manager confirms them
+    held_payments: Vec<HeldPayment>,
+    payment_ids: IdProvider<Installment>,
     tip: Money,
+    /// Payment the owner claims to have made, waiting for or resolved by the manager
+    payment: Option<Payment>,
+    /// Reverts of the last operations for undo and redo
+    history: History<MealsChange>,
 }
 
 impl Meals {
-    pub fn new(user_id: Id) -> Meals {
+    pub fn new(user_id: Id<User>) -> Meals {
         Meals {
             meals: HashMap::new(),
             owner_id: user_id,
             ready: false,
-            paid: Money::new(0, 0),
+            payments: Vec::new(),
+            held_payments: Vec::new(),
+            payment_ids: IdProvider::new(),
             tip: Money::new(0, 0),
+            payment: None,
+            history: History::new(UNDO_LIMIT),
         }
     }
 
     pub fn add_meal(&mut self, meal: Meal) -> &mut Meal {
         let id = meal.get_id();
+        self.history.record(MealsChange::RemoveMeal(id.clone()));
         self.meals.insert(id.clone(), meal);
         self.meals.get_mut(&id).unwrap()
     }
 
-    pub fn get_owner_id(&self) -> Id {
+    pub fn meals(&self) -> MealsIter<'_> {
+        MealsIter(self.meals.values())
+    }
+
+    pub fn get_meal(&self, id: &Id<Meal>) -> Option<&Meal> {
+        self.meals.get(id)
+    }
+
+    pub(crate) fn get_meal_mut(&mut self, id: &Id<Meal>) -> Option<&mut Meal> {
+        self.meals.get_mut(id)
+    }
+
+    /// Changes number, variety and price of the meal with the given ID, which can be undone.
+    pub fn update_meal(
+        &mut self,
+        id: &Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Option<&mut Meal> {
+        let meal = self.meals.get_mut(id)?;
+        self.history.record(MealsChange::ReplaceMeal(meal.clone()));
+        meal.set_meal_id(meal_id);
+        meal.set_variety(variety);
+        meal.set_price(price);
+        Some(meal)
+    }
+
+    /// Replaces how the meal with the given ID is prepared, which can be undone.
+    pub fn set_preparations(
+        &mut self,
+        id: &Id<Meal>,
+        preparations: BTreeSet<Preparation>,
+    ) -> Option<&mut Meal> {
+        let meal = self.meals.get_mut(id)?;
+        self.history.record(MealsChange::ReplaceMeal(meal.clone()));
+        meal.set_preparations(preparations);
+        Some(meal)
+    }
+
+    /// Replaces the note of the meal with the given ID, which can be undone.
+    pub fn set_note(&mut self, id: &Id<Meal>, note: Option<String>) -> Option<&mut Meal> {
+        let meal = self.meals.get_mut(id)?;
+        self.history.record(MealsChange::ReplaceMeal(meal.clone()));
+        meal.set_note(note);
+        Some(meal)
+    }
+
+    /// Adds a special to the meal with the given ID, costing `price` extra if given, which can be undone.
+    pub fn add_special(
+        &mut self,
+        meal: &Id<Meal>,
+        description: String,
+        price: Option<Money>,
+    ) -> Option<&mut Special> {
+        let target = self.meals.get_mut(meal)?;
+        let special = match price {
+            Some(price) => target.add_special_with_price(description, price),
+            None => target.add_special(description),
+        };
+        self.history
+            .record(MealsChange::RemoveSpecial(meal.clone(), special.get_id()));
+        Some(special)
+    }
+
+    /// Removes a special from the meal with the given ID, which can be undone.
+    pub fn remove_special(&mut self, meal: &Id<Meal>, id: Id<Special>) -> Option<Special> {
+        let special = self.meals.get_mut(meal)?.remove_special(id).ok()?;
+        self.history
+            .record(MealsChange::InsertSpecial(meal.clone(), special.clone()));
+        Some(special)
+    }
+
+    pub(crate) fn meals_mut(&mut self) -> MealsIterMut<'_> {
+        MealsIterMut(self.meals.values_mut())
+    }
+
+    pub fn get_owner_id(&self) -> Id<User> {
         self.owner_id.clone()
     }
 
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Marks that the owner completed their meal selection.
+    pub fn mark_ready(&mut self) {
+        self.ready = true;
+    }
+
+    /// Marks that the owner is still choosing, e.g. after changing their mind.
+    pub fn mark_not_ready(&mut self) {
+        self.ready = false;
+    }
+
+    /// Sum of all installments.
+    pub fn get_paid(&self) -> Money {
+        self.payments
+            .iter()
+            .fold(Money::zero(), |paid, payment| paid + payment.get_amount())
+    }
+
+    /// Replaces all installments by the given total, e.g. when the manager counts the money at once.
     pub fn set_paid(&mut self, paid: Money) {
-        self.paid = paid;
+        let mut payments = Vec::new();
+        if !paid.is_zero() {
+            payments.push(Installment::new(
+                self.payment_ids.generate_next(),
+                paid,
+                None,
+            ));
+        }
+        self.replace_payments(payments);
+    }
+
+    pub fn payments(&self) -> &[Installment] {
+        &self.payments
+    }
+
+    /// Records that the owner paid a part of their share at the given time.
+    pub fn add_payment(&mut self, amount: Money, paid_at: SystemTime) -> Id<Installment> {
+        self.add_payment_with_key(amount, paid_at, None)
+    }
+
+    /// Like `add_payment`, remembering the idempotency key the client sent to recognize retries.
+    pub fn add_payment_with_key(
+        &mut self,
+        amount: Money,
+        paid_at: SystemTime,
+        key: Option<String>,
+    ) -> Id<Installment> {
+        let id = self.payment_ids.generate_next();
+        let mut payments = self.payments.clone();
+        payments.push(Installment::new(id.clone(), amount, Some(paid_at)).with_key(key));
+        self.replace_payments(payments);
+        id
+    }
+
+    pub fn held_payments(&self) -> &[HeldPayment] {
+        &self.held_payments
+    }
+
+    /// Compares an installment about to be added with the booked and held ones.
+    ///
+    /// The idempotency key is checked first, a reused key is a retry if the amount matches and a conflict
+    /// otherwise. Without a matching key, an installment of the same amount paid within the `DUPLICATE_WINDOW`
+    /// before or after `paid_at` makes it suspicious.
+    pub fn find_duplicate(
+        &self,
+        amount: Money,
+        paid_at: SystemTime,
+        key: Option<&str>,
+    ) -> Option<Duplicate> {
+        let held = self.held_payments.iter().map(HeldPayment::get_installment);
+        if let Some(original) = key.and_then(|key| {
+            self.payments
+                .iter()
+                .chain(held)
+                .find(|payment| payment.get_key() == Some(key))
+        }) {
+            return Some(if original.get_amount() == amount {
+                Duplicate::Retry(original.get_id())
+            } else {
+                Duplicate::Suspected(DuplicateReason::KeyReused(original.get_id()))
+            });
+        }
+        self.payments
+            .iter()
+            .filter(|payment| payment.get_amount() == amount)
+            .find(|payment| {
+                payment.get_paid_at().is_some_and(|time| {
+                    let distance = paid_at
+                        .duration_since(time)
+                        .or_else(|_| time.duration_since(paid_at))
+                        .unwrap_or_default();
+                    distance <= DUPLICATE_WINDOW
+                })
+            })
+            .map(|payment| Duplicate::Suspected(DuplicateReason::SameAmount(payment.get_id())))
+    }
+
+    /// Keeps a suspected duplicate aside until the manager decides about it.
+    pub fn hold_payment(
+        &mut self,
+        amount: Money,
+        paid_at: SystemTime,
+        key: Option<String>,
+        reason: DuplicateReason,
+    ) -> Id<Installment> {
+        let id = self.payment_ids.generate_next();
+        let installment = Installment::new(id.clone(), amount, Some(paid_at)).with_key(key);
+        self.held_payments
+            .push(HeldPayment::new(installment, reason));
+        id
+    }
+
+    /// Books a held payment after all, keeping its ID.
+    pub fn release_payment(&mut self, id: &Id<Installment>) -> Result<Money, PaymentError> {
+        let held = self.take_held_payment(id)?;
+        let amount = held.get_installment().get_amount();
+        let mut payments = self.payments.clone();
+        payments.push(held.into_installment());
+        self.replace_payments(payments);
+        Ok(amount)
+    }
+
+    /// Drops a held payment which really was a duplicate.
+    pub fn discard_payment(&mut self, id: &Id<Installment>) -> Result<HeldPayment, PaymentError> {
+        self.take_held_payment(id)
+    }
+
+    fn take_held_payment(&mut self, id: &Id<Installment>) -> Result<HeldPayment, PaymentError> {
+        let index = self
+            .held_payments
+            .iter()
+            .position(|held| &held.get_installment().get_id() == id)
+            .ok_or_else(|| PaymentError::UnknownInstallment(id.clone()))?;
+        Ok(self.held_payments.remove(index))
+    }
+
+    /// Removes an installment that was recorded by mistake.
+    pub fn remove_payment(&mut self, id: &Id<Installment>) -> Result<Installment, PaymentError> {
+        let mut payments = self.payments.clone();
+        let index = payments
+            .iter()
+            .position(|payment| &payment.get_id() == id)
+            .ok_or_else(|| PaymentError::UnknownInstallment(id.clone()))?;
+        let removed = payments.remove(index);
+        self.replace_payments(payments);
+        Ok(removed)
+    }
+
+    /// Changes the amount of an installment, keeping when it was paid. Returns the previous amount.
+    pub fn correct_payment(
+        &mut self,
+        id: &Id<Installment>,
+        amount: Money,
+    ) -> Result<Money, PaymentError> {
+        let mut payments = self.payments.clone();
+        let payment = payments
+            .iter_mut()
+            .find(|payment| &payment.get_id() == id)
+            .ok_or_else(|| PaymentError::UnknownInstallment(id.clone()))?;
+        let previous = payment.get_amount();
+        payment.set_amount(amount);
+        self.replace_payments(payments);
+        Ok(previous)
+    }
+
+    fn replace_payments(&mut self, payments: Vec<Installment>) {
+        let previous = std::mem::replace(&mut self.payments, payments);
+        self.history.record(MealsChange::SetPayments(previous));
     }
 
     pub fn get_tip(&self) -> Money {
@@ -77,23 +428,93 @@ impl Meals {
     }
 
     pub fn set_tip(&mut self, tip: Money) {
+        self.history.record(MealsChange::SetTip(self.tip));
         self.tip = tip;
     }
 
+    /// Records that the owner paid the given `amount` themselves, replacing any previous claim.
+    ///
+    /// The amount only counts as paid once the manager confirms it.
+    pub fn claim_payment(&mut self, amount: Money, method: String) {
+        self.payment = Some(Payment::new(amount, method));
+    }
+
+    pub fn get_payment(&self) -> Option<&Payment> {
+        self.payment.as_ref()
+    }
+
+    /// Confirms the claimed payment and adds its amount as installment paid at the given time.
+    pub fn confirm_payment(&mut self, confirmed_at: SystemTime) -> Result<Money, PaymentError> {
+        let payment = self.payment.as_mut().ok_or(PaymentError::NotPending)?;
+        payment.confirm()?;
+        let amount = payment.get_amount();
+        self.add_payment(amount, confirmed_at);
+        Ok(amount)
+    }
+
+    pub fn dispute_payment(&mut self) -> Result<(), PaymentError> {
+        self.payment
+            .as_mut()
+            .ok_or(PaymentError::NotPending)?
+            .dispute()
+    }
+
+    /// Whether the owner should be reminded to pay: they paid too little and are not waiting for a confirmation.
+    pub fn needs_payment_reminder(&self) -> bool {
+        self.needs_payment_reminder_with_fee(Money::zero())
+    }
+
+    /// Like `needs_payment_reminder`, but the owner also has to pay the given share of the delivery fee.
+    pub fn needs_payment_reminder_with_fee(&self, fee_share: Money) -> bool {
+        let pending = self.payment.as_ref().is_some_and(Payment::is_pending);
+        self.calculate_change_with_fee(fee_share).is_err() && !pending
+    }
+
     pub fn calculate_total_price(&self) -> Money {
-        let mut total_price = Money::new(0, 0);
-        for meal in self.meals.values() {
-            total_price = total_price + meal.get_price();
+        sum_amounts(self.meals.values().map(Meal::get_total_price))
+    }
+
+    /// Human-readable summary, e.g. "2x 03 groß (Käserand) — 11,00€, 1x 05 klein — 4,50€", see `Verbosity`.
+    pub fn summary(&self, verbosity: Verbosity) -> String {
+        self.summary_in(
+            verbosity,
+            MoneyFormat::usual_for(self.calculate_total_price()),
+        )
+    }
+
+    /// Like `summary`, with the amounts written in the given format, e.g. the one of the order.
+    pub fn summary_in(&self, verbosity: Verbosity, format: MoneyFormat) -> String {
+        let meals = summarize_meals(self.meals(), verbosity, format);
+        if verbosity != Verbosity::Detailed {
+            return meals;
         }
-        return total_price;
+        let ready = if self.ready { ", ready" } else { "" };
+        format!(
+            "{}; paid {} of {}{}",
+            meals,
+            format.format(self.get_paid()),
+            format.format(self.calculate_total_price()),
+            ready
+        )
+    }
+
+    /// Suggests a tip for the price of the meals, to be set with `set_tip`.
+    pub fn suggest_tip(&self, strategy: TipStrategy) -> Money {
+        strategy.suggest(self.calculate_total_price())
     }
 
     pub fn calculate_change(&self) -> Result<Money, ChangeMoneyError> {
-        let has_to_pay = self.calculate_total_price() + self.tip;
-        if self.paid.get_total_cents() < has_to_pay.get_total_cents() {
-            return Err(ChangeMoneyError::Underpaid(has_to_pay - self.paid));
+        self.calculate_change_with_fee(Money::zero())
+    }
+
+    /// Like `calculate_change`, but the owner also has to pay the given share of the delivery fee.
+    pub fn calculate_change_with_fee(&self, fee_share: Money) -> Result<Money, ChangeMoneyError> {
+        let has_to_pay = self.calculate_total_price() + fee_share + self.tip;
+        let paid = self.get_paid();
+        if paid.get_total_cents() < has_to_pay.get_total_cents() {
+            return Err(ChangeMoneyError::Underpaid(has_to_pay - paid));
         }
-        return Ok(self.paid - has_to_pay);
+        Ok(paid - has_to_pay)
     }
 
     /// Removes the given `Meal` from `meals` and returns `true` if succeeded
@@ -106,10 +527,7 @@ impl Meals {
     ///
     /// * boolean value if succeeded or not
     pub fn remove_meal(&mut self, meal: Meal) -> bool {
-        match self.meals.remove(&meal.get_id()) {
-            Some(_) => return true,
-            None => return false,
-        }
+        self.remove_meal_by_id(meal.get_id()).is_some()
     }
 
     /// Removes a `Meal` belonging to the given `id` from `meals` and returns the removed `Meal` object if succeeded
@@ -121,8 +539,139 @@ impl Meals {
     /// # Return
     ///
     /// * The removed `Meal` object if succeeded or None
-    pub fn remove_meal_by_id(&mut self, id: Id) -> Option<Meal> {
-        self.meals.remove(&id)
+    pub fn remove_meal_by_id(&mut self, id: Id<Meal>) -> Option<Meal> {
+        let removed = self.meals.remove(&id)?;
+        self.history
+            .record(MealsChange::InsertMeal(removed.clone()));
+        Some(removed)
+    }
+
+    /// Reverts the last `steps` operations and returns how many could actually be undone.
+    ///
+    /// Fails without changing anything if the history doesn't match the meals, which then can't be undone anymore.
+    pub fn undo(&mut self, steps: usize) -> Result<usize, MealsError> {
+        let mut undone = 0;
+        while undone < steps {
+            let change = match self.history.pop_undo() {
+                Some(change) => change,
+                None => break,
+            };
+            match self.apply(change) {
+                Ok(revert) => {
+                    self.history.push_redo(revert);
+                    undone += 1;
+                }
+                Err(error) => {
+                    for _ in 0..undone {
+                        if let Some(change) = self.history.pop_redo() {
+                            self.apply(change)
+                                .expect("Operations just undone can be redone");
+                        }
+                    }
+                    self.clear_history();
+                    return Err(error);
+                }
+            }
+        }
+        Ok(undone)
+    }
+
+    /// Whether undoing the last `steps` operations would change meals, not only payments.
+    pub fn undo_changes_meals(&self, steps: usize) -> bool {
+        self.history
+            .peek_undo(steps)
+            .any(MealsChange::changes_meals)
+    }
+
+    /// Whether redoing the last undone operation would change meals, not only payments.
+    pub fn redo_changes_meals(&self) -> bool {
+        self.history
+            .peek_redo()
+            .is_some_and(MealsChange::changes_meals)
+    }
+
+    /// Restores the last undone operation and returns `true` if there was one.
+    ///
+    /// Fails without changing anything if the history doesn't match the meals, which then can't be redone anymore.
+    pub fn redo(&mut self) -> Result<bool, MealsError> {
+        let change = match self.history.pop_redo() {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+        match self.apply(change) {
+            Ok(revert) => {
+                self.history.push_undo(revert);
+                Ok(true)
+            }
+            Err(error) => {
+                self.clear_history();
+                Err(error)
+            }
+        }
+    }
+
+    /// Forgets all operations, so they can no longer be undone or redone.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Hands the meals to a pseudonymous owner and drops the claimed payment and the undo history.
+    pub fn anonymize(&mut self, owner_id: Id<User>) {
+        self.owner_id = owner_id;
+        self.payment = None;
+        self.clear_history();
+    }
+
+    /// Applies the given change and returns the change reverting it.
+    ///
+    /// Fails without changing anything if the change refers to a meal or special that does not exist.
+    fn apply(&mut self, change: MealsChange) -> Result<MealsChange, MealsError> {
+        use MealsChange::*;
+        let revert = match change {
+            InsertMeal(meal) => {
+                let id = meal.get_id();
+                self.meals.insert(id.clone(), meal);
+                RemoveMeal(id)
+            }
+            RemoveMeal(id) => {
+                let meal = self.meals.remove(&id).ok_or(MealsError::MealNotFound(id))?;
+                InsertMeal(meal)
+            }
+            ReplaceMeal(meal) => {
+                let target = self
+                    .meals
+                    .get_mut(&meal.get_id())
+                    .ok_or_else(|| MealsError::MealNotFound(meal.get_id()))?;
+                ReplaceMeal(std::mem::replace(target, meal))
+            }
+            InsertSpecial(meal, special) => {
+                let id = self
+                    .meals
+                    .get_mut(&meal)
+                    .ok_or_else(|| MealsError::MealNotFound(meal.clone()))?
+                    .insert_special(special)
+                    .get_id();
+                RemoveSpecial(meal, id)
+            }
+            RemoveSpecial(meal, id) => {
+                let special = self
+                    .meals
+                    .get_mut(&meal)
+                    .ok_or_else(|| MealsError::MealNotFound(meal.clone()))?
+                    .remove_special(id.clone())
+                    .map_err(|_| MealsError::SpecialNotFound(meal.clone(), id))?;
+                InsertSpecial(meal, special)
+            }
+            SetPayments(payments) => SetPayments(std::mem::replace(&mut self.payments, payments)),
+            SetTip(tip) => SetTip(std::mem::replace(&mut self.tip, tip)),
+        };
+        Ok(revert)
+    }
+}
+
+impl fmt::Display for Meals {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.summary(Verbosity::Normal))
     }
 }
 
@@ -131,6 +680,7 @@ mod tests {
     use super::*;
     use crate::order_model::meal::MealFactory;
     use rstest::rstest;
+    use std::time::Duration;
 
     #[test]
     fn meals_can_be_created() {
@@ -147,8 +697,12 @@ mod tests {
                 meals: HashMap::new(),
                 owner_id: user_id,
                 ready: false,
-                paid: Money::new(0, 0),
+                payments: Vec::new(),
+                held_payments: Vec::new(),
+                payment_ids: IdProvider::new(),
                 tip: Money::new(0, 0),
+                payment: None,
+                history: History::new(UNDO_LIMIT),
             }
         );
     }
@@ -190,20 +744,39 @@ mod tests {
                 Money::new(5, 50),
             ),
         );
+        let mut expected_history = History::new(UNDO_LIMIT);
+        expected_history.record(MealsChange::RemoveMeal(Id::new(0)));
         assert_eq!(
             meals,
             Meals {
                 meals: expected_meals,
                 owner_id: user_id,
                 ready: false,
-                paid: Money::new(0, 0),
+                payments: Vec::new(),
+                held_payments: Vec::new(),
+                payment_ids: IdProvider::new(),
                 tip: Money::new(0, 0),
+                payment: None,
+                history: expected_history,
             }
         );
     }
 
+    #[test]
+    fn meals_can_be_iterated() {
+        // Given:
+        let meals = meals_with_two_meals();
+
+        // When:
+        let mut meal_ids: Vec<&String> = meals.meals().map(Meal::get_meal_id).collect();
+
+        // Then:
+        meal_ids.sort();
+        assert_eq!(meal_ids, vec!["03", "35"]);
+    }
+
     #[rstest(prices, expected_total,
-        case(vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 33)], Money::new(15, 08)),
+        case(vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 33)], Money::new(15, 8)),
         case(vec![Money::new(3, 50), Money::new(4, 42)], Money::new(7, 92)),
     )]
     fn total_price_is_calculated_correctly(prices: Vec<Money>, expected_total: Money) {
@@ -372,7 +945,7 @@ mod tests {
         case(Id::new(2), None, 2)
     )]
     fn meal_can_be_removed_from_meals_by_id(
-        id: Id,
+        id: Id<Meal>,
         expected_removed: Option<Meal>,
         remaining_length: usize,
     ) {
@@ -401,4 +974,554 @@ mod tests {
         assert_eq!(expected_removed, removed_meal);
         assert_eq!(remaining_length, meals.meals.len());
     }
+
+    fn meals_with_two_meals() -> Meals {
+        let mut meals = Meals::new(Id::new(0));
+        let mut meal_factory = MealFactory::new();
+        meals.add_meal(meal_factory.create_meal(
+            String::from("03"),
+            String::from("groß"),
+            Money::new(5, 50),
+        ));
+        meals.add_meal(meal_factory.create_meal(
+            String::from("35"),
+            String::from("Spaghetti"),
+            Money::new(4, 35),
+        ));
+        meals
+    }
+
+    #[test]
+    fn special_prices_have_to_be_paid() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        meals
+            .add_meal(Meal::new(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            ))
+            .add_special_with_price(String::from("extra Salami"), Money::new(1, 0));
+        meals.set_paid(Money::new(6, 0));
+
+        // When:
+        let change = meals.calculate_change();
+
+        // Then:
+        assert_eq!(meals.calculate_total_price(), Money::new(6, 50));
+        assert_eq!(change, Err(ChangeMoneyError::Underpaid(Money::new(0, 50))));
+    }
+
+    #[test]
+    fn added_meal_can_be_undone() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+
+        // When:
+        let undone = meals.undo(1).unwrap();
+
+        // Then:
+        assert_eq!(undone, 1);
+        assert_eq!(meals.meals.len(), 1);
+        assert!(meals.meals.contains_key(&Id::new(0)));
+    }
+
+    #[test]
+    fn removed_meal_can_be_restored_by_undo() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        let removed = meals.remove_meal_by_id(Id::new(1)).unwrap();
+
+        // When:
+        meals.undo(1).unwrap();
+
+        // Then:
+        assert_eq!(meals.meals.get(&Id::new(1)), Some(&removed));
+    }
+
+    #[test]
+    fn removed_special_can_be_restored_by_undo_and_removed_again_by_redo() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        let special = meals
+            .add_special(
+                &Id::new(0),
+                String::from("Käserand"),
+                Some(Money::new(1, 0)),
+            )
+            .unwrap()
+            .clone();
+        meals.remove_special(&Id::new(0), special.get_id()).unwrap();
+
+        // When:
+        meals.undo(1).unwrap();
+
+        // Then:
+        let meal = meals.meals.get(&Id::new(0)).unwrap();
+        assert_eq!(meal.specials().collect::<Vec<&Special>>(), vec![&special]);
+        assert_eq!(meals.calculate_total_price(), Money::new(10, 85));
+        assert!(meals.redo().unwrap());
+        assert_eq!(meals.meals[&Id::new(0)].specials().count(), 0);
+    }
+
+    #[test]
+    fn added_special_can_be_undone() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals
+            .add_special(&Id::new(1), String::from("ohne Zwiebeln"), None)
+            .unwrap();
+
+        // When:
+        let undone = meals.undo(1).unwrap();
+
+        // Then:
+        assert_eq!(undone, 1);
+        assert_eq!(meals.meals[&Id::new(1)].specials().count(), 0);
+    }
+
+    #[test]
+    fn updated_meal_can_be_undone() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        let before = meals.meals[&Id::new(0)].clone();
+        meals
+            .update_meal(
+                &Id::new(0),
+                String::from("04"),
+                String::from("klein"),
+                Money::new(4, 50),
+            )
+            .unwrap();
+
+        // When:
+        meals.undo(1).unwrap();
+
+        // Then:
+        assert_eq!(meals.meals[&Id::new(0)], before);
+    }
+
+    #[test]
+    fn undo_of_unrecorded_change_fails_without_changing_anything() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals
+            .add_special(&Id::new(1), String::from("ohne Zwiebeln"), None)
+            .unwrap();
+        let before = meals.meals[&Id::new(1)].clone();
+        meals.meals.remove(&Id::new(0));
+
+        // When:
+        let undone = meals.undo(3);
+
+        // Then:
+        assert_eq!(undone, Err(MealsError::MealNotFound(Id::new(0))));
+        assert_eq!(meals.meals.len(), 1);
+        assert_eq!(meals.meals[&Id::new(1)], before);
+        assert_eq!(meals.history.undo_len(), 0);
+        assert_eq!(meals.redo(), Ok(false));
+    }
+
+    #[test]
+    fn special_of_unknown_meal_is_not_recorded() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+
+        // When:
+        let special = meals.add_special(&Id::new(7), String::from("Käserand"), None);
+
+        // Then:
+        assert_eq!(special, None);
+        assert_eq!(meals.history.undo_len(), 2);
+    }
+
+    #[test]
+    fn paid_and_tip_can_be_undone() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        meals.set_paid(Money::new(10, 0));
+        meals.set_paid(Money::new(12, 0));
+        meals.set_tip(Money::new(1, 0));
+
+        // When:
+        meals.undo(2).unwrap();
+
+        // Then:
+        assert_eq!(meals.get_paid(), Money::new(10, 0));
+        assert_eq!(meals.tip, Money::zero());
+    }
+
+    #[test]
+    fn undo_stops_when_history_is_exhausted() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+
+        // When:
+        let undone = meals.undo(5).unwrap();
+
+        // Then:
+        assert_eq!(undone, 2);
+        assert!(meals.meals.is_empty());
+    }
+
+    #[test]
+    fn only_the_last_operations_can_be_undone() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        for euros in 1..=(UNDO_LIMIT as u32 + 2) {
+            meals.set_paid(Money::new(euros, 0));
+        }
+
+        // When:
+        let undone = meals.undo(UNDO_LIMIT + 2).unwrap();
+
+        // Then:
+        assert_eq!(undone, UNDO_LIMIT);
+        assert_eq!(meals.get_paid(), Money::new(2, 0));
+    }
+
+    #[test]
+    fn undone_operations_can_be_redone() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals.remove_meal_by_id(Id::new(0));
+        meals.undo(2).unwrap();
+
+        // When:
+        let first = meals.redo().unwrap();
+        let second = meals.redo().unwrap();
+        let third = meals.redo().unwrap();
+
+        // Then:
+        assert!(first);
+        assert!(second);
+        assert!(!third);
+        assert_eq!(meals.meals.len(), 1);
+        assert!(meals.meals.contains_key(&Id::new(1)));
+    }
+
+    #[test]
+    fn new_operation_invalidates_redo() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals.undo(1).unwrap();
+
+        // When:
+        meals.set_tip(Money::new(1, 0));
+
+        // Then:
+        assert!(!meals.redo().unwrap());
+    }
+
+    #[test]
+    fn cleared_history_cannot_be_undone() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+
+        // When:
+        meals.clear_history();
+
+        // Then:
+        assert_eq!(meals.undo(1), Ok(0));
+        assert_eq!(meals.meals.len(), 2);
+    }
+
+    #[test]
+    fn claimed_payment_does_not_count_as_paid() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+
+        // When:
+        meals.claim_payment(Money::new(9, 85), String::from("PayPal"));
+
+        // Then:
+        assert_eq!(meals.get_paid(), Money::zero());
+        assert_eq!(
+            meals.get_payment(),
+            Some(&Payment::new(Money::new(9, 85), String::from("PayPal")))
+        );
+    }
+
+    #[test]
+    fn confirmed_payment_is_added_to_paid() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals.set_paid(Money::new(1, 0));
+        meals.claim_payment(Money::new(8, 85), String::from("PayPal"));
+
+        // When:
+        let confirmed = meals.confirm_payment(SystemTime::UNIX_EPOCH);
+
+        // Then:
+        assert_eq!(confirmed, Ok(Money::new(8, 85)));
+        assert_eq!(meals.get_paid(), Money::new(9, 85));
+        assert_eq!(
+            meals.confirm_payment(SystemTime::UNIX_EPOCH),
+            Err(PaymentError::NotPending)
+        );
+    }
+
+    #[test]
+    fn disputed_payment_is_not_added_to_paid() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals.claim_payment(Money::new(9, 85), String::from("PayPal"));
+
+        // When:
+        let disputed = meals.dispute_payment();
+
+        // Then:
+        assert_eq!(disputed, Ok(()));
+        assert_eq!(meals.get_paid(), Money::zero());
+    }
+
+    #[test]
+    fn payment_cannot_be_confirmed_without_claim() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+
+        // When:
+        let confirmed = meals.confirm_payment(SystemTime::UNIX_EPOCH);
+        let disputed = meals.dispute_payment();
+
+        // Then:
+        assert_eq!(confirmed, Err(PaymentError::NotPending));
+        assert_eq!(disputed, Err(PaymentError::NotPending));
+    }
+
+    #[test]
+    fn installments_are_summed_up() {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        let lunch = SystemTime::UNIX_EPOCH;
+        let next_day = lunch + std::time::Duration::from_secs(24 * 60 * 60);
+
+        // When:
+        let cash = meals.add_payment(Money::new(5, 0), lunch);
+        let transfer = meals.add_payment(Money::new(4, 85), next_day);
+
+        // Then:
+        assert_eq!(meals.get_paid(), Money::new(9, 85));
+        assert_eq!(
+            meals.payments(),
+            &[
+                Installment::new(cash, Money::new(5, 0), Some(lunch)),
+                Installment::new(transfer, Money::new(4, 85), Some(next_day))
+            ]
+        );
+    }
+
+    #[test]
+    fn installments_can_be_corrected_and_removed() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        let first = meals.add_payment(Money::new(5, 0), SystemTime::UNIX_EPOCH);
+        let second = meals.add_payment(Money::new(4, 0), SystemTime::UNIX_EPOCH);
+
+        // When:
+        let corrected = meals.correct_payment(&first, Money::new(5, 50));
+        let removed = meals
+            .remove_payment(&second)
+            .map(|payment| payment.get_amount());
+
+        // Then:
+        assert_eq!(corrected, Ok(Money::new(5, 0)));
+        assert_eq!(removed, Ok(Money::new(4, 0)));
+        assert_eq!(meals.get_paid(), Money::new(5, 50));
+        assert_eq!(
+            meals.remove_payment(&second),
+            Err(PaymentError::UnknownInstallment(second))
+        );
+    }
+
+    #[rstest(
+        amount,
+        minutes_later,
+        key,
+        expected,
+        case(
+            Money::new(5, 0),
+            5,
+            None,
+            Some(Duplicate::Suspected(DuplicateReason::SameAmount(Id::new(0))))
+        ),
+        case(Money::new(5, 0), 11, None, None),
+        case(Money::new(4, 0), 5, None, None),
+        case(Money::new(5, 0), 5, Some("tx-1"), Some(Duplicate::Retry(Id::new(0)))),
+        case(
+            Money::new(4, 0),
+            60,
+            Some("tx-1"),
+            Some(Duplicate::Suspected(DuplicateReason::KeyReused(Id::new(0))))
+        ),
+        case(Money::new(4, 0), 60, Some("tx-2"), None)
+    )]
+    fn duplicate_installments_are_found(
+        amount: Money,
+        minutes_later: u64,
+        key: Option<&str>,
+        expected: Option<Duplicate>,
+    ) {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        let lunch = SystemTime::UNIX_EPOCH + Duration::from_secs(12 * 60 * 60);
+        meals.add_payment_with_key(Money::new(5, 0), lunch, Some(String::from("tx-1")));
+
+        // When:
+        let duplicate =
+            meals.find_duplicate(amount, lunch + Duration::from_secs(minutes_later * 60), key);
+
+        // Then:
+        assert_eq!(duplicate, expected);
+    }
+
+    #[test]
+    fn held_payment_only_counts_when_released() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        meals.add_payment(Money::new(5, 0), SystemTime::UNIX_EPOCH);
+        let reason = DuplicateReason::SameAmount(Id::new(0));
+        let kept = meals.hold_payment(
+            Money::new(5, 0),
+            SystemTime::UNIX_EPOCH,
+            None,
+            reason.clone(),
+        );
+        let dropped = meals.hold_payment(Money::new(5, 0), SystemTime::UNIX_EPOCH, None, reason);
+
+        // When:
+        let released = meals.release_payment(&kept);
+        let discarded = meals
+            .discard_payment(&dropped)
+            .map(|held| held.get_installment().get_id());
+
+        // Then:
+        assert_eq!(released, Ok(Money::new(5, 0)));
+        assert_eq!(discarded, Ok(dropped.clone()));
+        assert_eq!(meals.get_paid(), Money::new(10, 0));
+        assert!(meals.held_payments().is_empty());
+        assert_eq!(meals.payments()[1].get_id(), kept);
+        assert_eq!(
+            meals.release_payment(&dropped),
+            Err(PaymentError::UnknownInstallment(dropped))
+        );
+    }
+
+    #[test]
+    fn removed_installment_can_be_restored_by_undo() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        let id = meals.add_payment(Money::new(5, 0), SystemTime::UNIX_EPOCH);
+        meals.remove_payment(&id).unwrap();
+
+        // When:
+        meals.undo(1).unwrap();
+
+        // Then:
+        assert_eq!(meals.get_paid(), Money::new(5, 0));
+        assert_eq!(meals.payments()[0].get_id(), id);
+    }
+
+    #[test]
+    fn set_paid_replaces_installments() {
+        // Given:
+        let mut meals = Meals::new(Id::new(0));
+        meals.add_payment(Money::new(5, 0), SystemTime::UNIX_EPOCH);
+        meals.add_payment(Money::new(4, 0), SystemTime::UNIX_EPOCH);
+
+        // When:
+        meals.set_paid(Money::new(8, 0));
+
+        // Then:
+        assert_eq!(meals.payments().len(), 1);
+        assert_eq!(meals.payments()[0].get_paid_at(), None);
+        assert_eq!(meals.get_paid(), Money::new(8, 0));
+    }
+
+    #[rstest(
+        paid,
+        claim,
+        dispute,
+        expected,
+        case(Money::zero(), false, false, true),
+        case(Money::new(9, 85), false, false, false),
+        case(Money::zero(), true, false, false),
+        case(Money::zero(), true, true, true)
+    )]
+    fn owner_is_reminded_to_pay_unless_paid_or_pending(
+        paid: Money,
+        claim: bool,
+        dispute: bool,
+        expected: bool,
+    ) {
+        // Given:
+        let mut meals = meals_with_two_meals();
+        meals.set_paid(paid);
+        if claim {
+            meals.claim_payment(Money::new(9, 85), String::from("PayPal"));
+        }
+        if dispute {
+            meals.dispute_payment().unwrap();
+        }
+
+        // When:
+        let needs_reminder = meals.needs_payment_reminder();
+
+        // Then:
+        assert_eq!(needs_reminder, expected);
+    }
+
+    #[test]
+    fn tip_is_suggested_for_price_of_meals() {
+        // Given:
+        let mut meal_factory = MealFactory::new();
+        let mut meals = Meals::new(Id::new(0));
+        meals.add_meal(meal_factory.create_meal(
+            String::from("03"),
+            String::from("groß"),
+            Money::new(7, 20),
+        ));
+
+        // When:
+        let tip = meals.suggest_tip(TipStrategy::RoundUpTo(Money::new(1, 0)));
+
+        // Then:
+        assert_eq!(tip, Money::new(0, 80));
+    }
+
+    #[test]
+    fn identical_meals_are_summarized_on_one_line() {
+        // Given:
+        let mut meal_factory = MealFactory::new();
+        let mut meals = Meals::new(Id::new(0));
+        for (meal_id, variety, price) in [("03", "groß", 9), ("05", "klein", 4), ("03", "groß", 9)]
+        {
+            let meal = meals.add_meal(meal_factory.create_meal(
+                String::from(meal_id),
+                String::from(variety),
+                Money::new(price, 50),
+            ));
+            if meal_id == "03" {
+                meal.add_special_with_price(String::from("Käserand"), Money::new(1, 0));
+            }
+        }
+        meals.set_paid(Money::new(20, 0));
+        meals.mark_ready();
+
+        // When:
+        let brief = meals.summary(Verbosity::Brief);
+        let detailed = meals.summary(Verbosity::Detailed);
+
+        // Then:
+        assert_eq!(brief, "3 meals — 25,50€");
+        assert_eq!(
+            meals.to_string(),
+            "2x 03 groß (Käserand) — 21,00€, 1x 05 klein — 4,50€"
+        );
+        assert_eq!(
+            detailed,
+            "2x 03 groß (Käserand) — 21,00€, 1x 05 klein — 4,50€; paid 20,00€ of 25,50€, ready"
+        );
+    }
 }
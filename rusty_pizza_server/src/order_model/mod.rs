@@ -1,5 +1,24 @@
+pub mod archive;
+pub mod audit;
+pub mod fee;
+pub mod integrity;
+pub mod manager;
 pub mod meal;
+pub mod meal_spec;
 pub mod meals;
 pub mod order;
+pub mod order_template;
+pub mod payment;
+pub mod placement;
+pub mod preparation;
+pub mod preview;
+pub mod report;
+pub mod restaurant;
+pub mod retention;
+pub mod settlement;
 pub mod special;
+pub mod summary;
+pub mod tax;
+pub mod tip;
+pub mod trial;
 pub mod user;
@@ -1,10 +1,34 @@
+use crate::menu::catalog::{Menu, MenuError};
+use crate::menu::item::MenuItem;
+use crate::menu::resolution::resolve_meal;
+use crate::order_model::audit::{AuditLog, Mutation, OrderEvent};
+use crate::order_model::fee::{split_fee, split_fee_with_rounding, FeeSplitStrategy};
+use crate::order_model::integrity::IntegrityIssue;
 use crate::order_model::meal::{Meal, MealFactory};
-use crate::order_model::meals::Meals;
+use crate::order_model::meals::{Meals, MealsError};
+use crate::order_model::payment::{
+    Duplicate, DuplicateReason, HeldPayment, Installment, Payment, PaymentError, ReceivedPayment,
+};
+use crate::order_model::preparation::Preparation;
+use crate::order_model::report::{PaymentReport, RoundingAdjustment, SplitAmount, UserPayment};
+use crate::order_model::restaurant::{Restaurant, RestaurantError};
+use crate::order_model::special::Special;
+use crate::order_model::summary::{summarize_meals, Verbosity};
+use crate::order_model::tax::{TaxBreakdown, TaxRate};
+use crate::order_model::user::User;
+use crate::user_model::favorites::FavoriteMeal;
+use crate::util::clock::{Clock, SystemClock};
 use crate::util::id::Id;
-use crate::util::money::Money;
-use std::collections::{HashMap, HashSet};
+use crate::util::locale::{Currency, Locale, MoneyFormat};
+use crate::util::money::{CurrencyMismatchError, Money};
+use crate::util::short_code::ShortCodePrefix;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error;
 use std::fmt;
+use std::iter;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, PartialEq)]
 /// Not all users who take part in this Order have paid enough.
@@ -13,20 +37,20 @@ pub enum NotAllPaidEnoughError {
     Underpaid {
         underpaid: Money,
         /// IDs of the users that didn't pay enough
-        paid_less: HashSet<Id>,
+        paid_less: HashSet<Id<User>>,
     },
     /// There is enough money to pay the bill, but somebody did not pay enough
     EnoughInTotal {
         change: Money,
         /// IDs of the users that didn't pay enough
-        paid_less: HashSet<Id>,
+        paid_less: HashSet<Id<User>>,
     },
 }
 
 impl fmt::Display for NotAllPaidEnoughError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use NotAllPaidEnoughError::*;
-        match &*self {
+        match self {
             Underpaid {
                 underpaid,
                 paid_less,
@@ -44,29 +68,122 @@ impl fmt::Display for NotAllPaidEnoughError {
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum OrderStatus {
+impl error::Error for NotAllPaidEnoughError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrderStatus {
     Open,
     Ordering,
-    Ordered(String),
+    /// Placed at the restaurant, which expects to deliver at `eta` if it said when
+    Ordered {
+        placed_at: DateTime<Utc>,
+        eta: Option<DateTime<Utc>>,
+    },
     Delivered,
+    /// The grace period after delivery is over, the order can't be changed anymore and may be archived
+    Closed,
+    /// The order was called off before delivery and can't be changed anymore
+    Cancelled,
+}
+
+/// Kind of change to an `Order`, which is allowed or not depending on its `OrderStatus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Modification {
+    /// Adding, removing or changing meals, allowed until the order is delivered
+    Meals,
+    /// Recording payments and tips, allowed until the order is closed
+    Payments,
+}
+
+/// Change to an `Order` on behalf of a user, which is allowed or not depending on who they are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// Moving the order to another `OrderStatus`, only allowed for the manager
+    ChangeStatus,
+    /// Removing the given user, allowed for the manager and the user themselves
+    RemoveUser(Id<User>),
+    /// Changing the meals of the given user, only allowed for that user
+    ModifyMeals(Id<User>),
 }
 
+/// Time after delivery during which payments can still be recorded, unless configured otherwise.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
 impl fmt::Display for OrderStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            OrderStatus::Ordered { .. } => write!(f, "Ordered"),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum OrderError {
     UserNotParticipating,
+    /// The user joined the order before
+    UserAlreadyParticipating,
+    /// The operation is not allowed in the current `OrderStatus`
+    WrongStatus,
+    /// The user has no claimed payment waiting for confirmation
+    PaymentNotPending,
+    /// No menu was set for the order
+    NoMenu,
+    /// The meal does not match the menu of the order
+    Menu(MenuError),
+    MealNotFound,
+    /// The manager can't leave their own order
+    ManagerCannotLeave,
+    /// The order was changed after a `Preview` was made, so it can't be applied
+    StalePreview,
+    /// The events don't start with the creation of an order or can't be applied in their order
+    InvalidHistory,
+    /// The acting user must not make the change, see `Permission`
+    NotAuthorized,
+    /// The user made no installment with the given ID
+    PaymentNotFound,
+    /// The restaurant doesn't take the order as it is
+    Restaurant(RestaurantError),
+    /// Ordering requires every participant to be ready, but the listed users aren't
+    NotReady(Vec<Id<User>>),
+    /// The change was based on an outdated version, see `Order::check_meals_version`
+    Conflict(ConflictError),
+    /// The undo history of the user's meals doesn't match them anymore
+    Meals(MealsError),
+    /// The amount is in another currency than the order, see `Order::check_currency`
+    Currency(CurrencyMismatchError),
 }
 
 impl fmt::Display for OrderError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             OrderError::UserNotParticipating => write!(f, "user is not participating in order"),
+            OrderError::UserAlreadyParticipating => {
+                write!(f, "user is already participating in order")
+            }
+            OrderError::WrongStatus => {
+                write!(f, "operation is not allowed in current order status")
+            }
+            OrderError::PaymentNotPending => write!(f, "no payment is waiting for confirmation"),
+            OrderError::NoMenu => write!(f, "order has no menu"),
+            OrderError::Menu(ref error) => write!(f, "{}", error),
+            OrderError::MealNotFound => write!(f, "meal not found in order"),
+            OrderError::ManagerCannotLeave => write!(f, "manager cannot leave the order"),
+            OrderError::StalePreview => write!(f, "order was changed since the preview"),
+            OrderError::InvalidHistory => write!(f, "events can't be replayed into an order"),
+            OrderError::NotAuthorized => write!(f, "user is not allowed to change this"),
+            OrderError::PaymentNotFound => write!(f, "payment not found for user"),
+            OrderError::Restaurant(ref error) => write!(f, "{}", error),
+            OrderError::NotReady(ref user_ids) => {
+                let user_ids: Vec<String> = user_ids
+                    .iter()
+                    .map(|user_id| user_id.get_value().to_string())
+                    .collect();
+                write!(f, "users {} are not ready", user_ids.join(", "))
+            }
+            OrderError::Conflict(ref error) => write!(f, "{}", error),
+            OrderError::Meals(ref error) => write!(f, "{}", error),
+            OrderError::Currency(ref error) => write!(f, "{}", error),
         }
     }
 }
@@ -75,463 +192,2774 @@ impl error::Error for OrderError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             OrderError::UserNotParticipating => None,
+            OrderError::UserAlreadyParticipating => None,
+            OrderError::WrongStatus => None,
+            OrderError::PaymentNotPending => None,
+            OrderError::NoMenu => None,
+            OrderError::Menu(ref error) => Some(error),
+            OrderError::MealNotFound => None,
+            OrderError::ManagerCannotLeave => None,
+            OrderError::StalePreview => None,
+            OrderError::InvalidHistory => None,
+            OrderError::NotAuthorized => None,
+            OrderError::PaymentNotFound => None,
+            OrderError::Restaurant(ref error) => Some(error),
+            OrderError::NotReady(_) => None,
+            OrderError::Conflict(ref error) => Some(error),
+            OrderError::Meals(ref error) => Some(error),
+            OrderError::Currency(ref error) => Some(error),
+        }
+    }
+}
+
+/// Somebody else changed what a client wanted to change since the client last saw it, so it would overwrite their
+/// change unseen. Clients fetch the order again and retry with the current version, or ask their user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictError {
+    expected: usize,
+    current: usize,
+}
+
+impl ConflictError {
+    /// The version the client based its change on.
+    pub fn get_expected(&self) -> usize {
+        self.expected
+    }
+
+    /// The version of the order now, to retry with after fetching it again.
+    pub fn get_current(&self) -> usize {
+        self.current
+    }
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "changed since version {}, the order is at version {} now",
+            self.expected, self.current
+        )
+    }
+}
+
+impl error::Error for ConflictError {}
+
+impl From<MealsError> for OrderError {
+    fn from(error: MealsError) -> Self {
+        OrderError::Meals(error)
+    }
+}
+
+impl From<CurrencyMismatchError> for OrderError {
+    fn from(error: CurrencyMismatchError) -> Self {
+        OrderError::Currency(error)
+    }
+}
+
+impl From<MenuError> for OrderError {
+    fn from(error: MenuError) -> Self {
+        OrderError::Menu(error)
+    }
+}
+
+impl From<RestaurantError> for OrderError {
+    fn from(error: RestaurantError) -> Self {
+        OrderError::Restaurant(error)
+    }
+}
+
+impl From<PaymentError> for OrderError {
+    fn from(error: PaymentError) -> Self {
+        match error {
+            PaymentError::NotPending => OrderError::PaymentNotPending,
+            PaymentError::UnknownInstallment(_) => OrderError::PaymentNotFound,
         }
     }
 }
 
+/// A meal that could not be found on the menu an order was cloned for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnmatchedMeal {
+    /// User the meal was ordered by, `None` for office and shared meals
+    user_id: Option<Id<User>>,
+    meal_id: String,
+    variety: String,
+}
+
+impl UnmatchedMeal {
+    pub fn get_user_id(&self) -> Option<Id<User>> {
+        self.user_id.clone()
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+}
+
+/// A copy of an order for another restaurant, together with the meals that could not be carried over.
+#[derive(Debug, PartialEq)]
+pub struct ClonedOrder {
+    pub order: Order,
+    pub unmatched: Vec<UnmatchedMeal>,
+}
+
+/// The `Meals` of a user who left an order, together with the money to give back to them.
 #[derive(Debug, PartialEq)]
+pub struct RemovedUser {
+    pub meals: Meals,
+    /// Everything the user already paid, zero if they didn't pay yet
+    pub refund: Money,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Order {
     /// Maps IDs of users to their `Meals`
-    meals: HashMap<Id, Meals>,
+    meals: HashMap<Id<User>, Meals>,
     status: OrderStatus,
     /// User ID of the manager
-    manager_id: Id,
+    manager_id: Id<User>,
     meal_factory: MealFactory,
+    /// Menu of the restaurant the order goes to, meals are checked against it
+    menu: Option<Arc<Menu>>,
+    /// Restaurant the order goes to, checked when the order is placed
+    restaurant: Option<Restaurant>,
+    /// Meals bought for the office, paid from the shared budget instead of by a user
+    office_meals: HashMap<Id<Meal>, Meal>,
+    /// Meals for everybody, e.g. a bottle of cola, their price split among the participants like a fee
+    shared_meals: HashMap<Id<Meal>, Meal>,
+    shared_split: FeeSplitStrategy,
+    /// When the order was marked as delivered
+    delivered_at: Option<SystemTime>,
+    /// Time after delivery until the order is closed
+    grace_period: Duration,
+    /// Time at which the order stops taking meals and ordering starts automatically
+    deadline: Option<DateTime<Utc>>,
+    /// Delivery or service fee charged by the restaurant
+    delivery_fee: Money,
+    fee_split: FeeSplitStrategy,
+    /// Currency of the prices, the server default if `None`
+    currency: Option<Currency>,
+    /// Locale amounts are written in, the server default if `None`
+    locale: Option<Locale>,
+    /// Cost center the whole order is billed to, overriding the ones of the participants
+    cost_center: Option<String>,
+    /// Every change made through the methods of the order
+    audit: AuditLog,
+    /// Whether the participants were replaced by pseudonyms, see `anonymize`
+    anonymized: bool,
+    /// Whether ordering only starts once every participant is ready
+    ready_required: bool,
 }
 
 impl Order {
-    pub fn new(manager_id: Id) -> Order {
+    pub fn new(manager_id: Id<User>) -> Order {
+        Order::with_audit_clock(manager_id, Arc::new(SystemClock))
+    }
+
+    /// Creates an order whose history is timestamped with the given clock.
+    pub fn with_audit_clock(manager_id: Id<User>, clock: Arc<dyn Clock + Send + Sync>) -> Order {
         let mut order = Order {
             meals: HashMap::new(),
             status: OrderStatus::Open,
             manager_id: manager_id.clone(),
             meal_factory: MealFactory::new(),
+            menu: None,
+            restaurant: None,
+            office_meals: HashMap::new(),
+            shared_meals: HashMap::new(),
+            shared_split: FeeSplitStrategy::default(),
+            delivered_at: None,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            deadline: None,
+            currency: None,
+            locale: None,
+            cost_center: None,
+            delivery_fee: Money::zero(),
+            fee_split: FeeSplitStrategy::default(),
+            audit: AuditLog::with_clock(clock),
+            anonymized: false,
+            ready_required: false,
         };
-        order.add_user(manager_id);
+        order
+            .meals
+            .insert(manager_id.clone(), Meals::new(manager_id.clone()));
+        order.audit.record(Mutation::Created { manager_id });
         order
     }
 
-    pub fn add_user(&mut self, user_id: Id) -> &mut Meals {
+    /// Lets the given user join the order, which is possible as long as meals can be changed.
+    pub fn add_user(&mut self, user_id: Id<User>) -> Result<&Meals, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if self.is_participating(&user_id) {
+            return Err(OrderError::UserAlreadyParticipating);
+        }
         let meals = Meals::new(user_id.clone());
         self.meals.insert(user_id.clone(), meals);
-        self.meals.get_mut(&user_id).unwrap()
+        self.audit.record(Mutation::UserAdded(user_id.clone()));
+        Ok(&self.meals[&user_id])
     }
 
-    pub fn add_meal_for_user(
-        &mut self,
-        user_id: Id,
-        meal_id: String,
-        variety: String,
-        price: Money,
-    ) -> Result<&mut Meal, OrderError> {
-        match self.meals.get_mut(&user_id) {
-            Some(meals) => {
-                let meal = self.meal_factory.create_meal(meal_id, variety, price);
-                Ok(meals.add_meal(meal))
-            }
-            None => Err(OrderError::UserNotParticipating),
+    /// Rebuilds an order by making all changes of the given events again, e.g. to inspect an earlier state.
+    ///
+    /// The events have to start with the creation of the order. The rebuilt order keeps them as its history.
+    pub fn replay(events: &[OrderEvent]) -> Result<Order, OrderError> {
+        let mut order = match events.first().map(OrderEvent::get_mutation) {
+            Some(Mutation::Created { manager_id }) => Order::new(manager_id.clone()),
+            _ => return Err(OrderError::InvalidHistory),
+        };
+        for event in &events[1..] {
+            order
+                .apply(event.get_mutation().clone())
+                .map_err(|_| OrderError::InvalidHistory)?;
         }
+        order.audit.set_events(events.to_vec());
+        Ok(order)
     }
 
-    pub fn get_meals_for_user(&mut self, user_id: Id) -> Option<&mut Meals> {
-        self.meals.get_mut(&user_id)
+    /// Rebuilds the order as it was at the given time, so changes made afterwards can be undone.
+    pub fn state_at(&self, time: SystemTime) -> Result<Order, OrderError> {
+        let until = self
+            .history()
+            .iter()
+            .take_while(|event| event.get_time() <= time)
+            .count();
+        Order::replay(&self.history()[..until])
     }
 
-    pub fn calculate_total_price(&self) -> Money {
-        let mut total_price = Money::zero();
-        for single_order in self.meals.values() {
-            total_price += single_order.calculate_total_price();
-        }
-        return total_price;
+    /// Time the order was created, according to its history.
+    pub fn get_created_at(&self) -> SystemTime {
+        self.history()
+            .first()
+            .map(OrderEvent::get_time)
+            .expect("History starts with the creation of the order")
     }
 
-    pub fn calculate_total_tip(&self) -> Money {
-        let mut total_tip = Money::zero();
-        for single_order in self.meals.values() {
-            total_tip += single_order.get_tip();
-        }
-        return total_tip;
+    /// Every change made to the order, oldest first.
+    ///
+    /// Changes made directly on the `Meals` or `Meal` returned by some methods are not recorded.
+    pub fn history(&self) -> &[OrderEvent] {
+        self.audit.events()
     }
 
-    pub fn calculate_total_change(&self) -> Result<Money, NotAllPaidEnoughError> {
-        let mut total_change = Money::zero();
-        let mut underpaid = Money::zero();
-        let mut paid_less: HashSet<Id> = HashSet::new();
-        for single_order in self.meals.values() {
-            match single_order.calculate_change() {
-                Ok(change) => total_change += change,
-                Err(e) => {
-                    paid_less.insert(single_order.get_owner_id());
-                    underpaid += e.get_value()
-                }
-            }
-        }
-        if underpaid.get_total_cents() == 0 {
-            Ok(total_change)
-        } else if total_change > underpaid {
-            Err(NotAllPaidEnoughError::EnoughInTotal {
-                change: total_change - underpaid,
-                paid_less,
-            })
-        } else {
-            Err(NotAllPaidEnoughError::Underpaid {
-                underpaid: underpaid - total_change,
-                paid_less,
-            })
-        }
+    /// Changes of the meals or payments of the given user, oldest first.
+    pub fn history_for_user<'a>(
+        &'a self,
+        user_id: &'a Id<User>,
+    ) -> impl Iterator<Item = &'a OrderEvent> {
+        self.history()
+            .iter()
+            .filter(move |event| event.get_mutation().get_user_id() == Some(user_id))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rstest::rstest;
+    /// Changes which may have touched the given meal, oldest first, e.g. to find out who changed a pizza.
+    pub fn history_for_meal<'a>(
+        &'a self,
+        id: &'a Id<Meal>,
+    ) -> impl Iterator<Item = &'a OrderEvent> {
+        self.history()
+            .iter()
+            .filter(move |event| event.get_mutation().affects_meal(id))
+    }
 
-    #[test]
-    fn order_can_be_created() {
-        //Given
-        let user_id = Id::new(0);
+    /// Number of changes made to the order so far, e.g. for clients to tell up to which change they are up to date.
+    pub fn get_sequence_number(&self) -> usize {
+        self.history().len()
+    }
 
-        //When
-        let order = Order::new(user_id.clone());
+    /// Version of the meals and payments of the user, the sequence number of the last change to them. `None` if the
+    /// user is not participating.
+    ///
+    /// Changes of other users don't count, so clients editing different meals of the same order don't conflict.
+    pub fn get_meals_version(&self, user_id: &Id<User>) -> Option<usize> {
+        let meals = self.meals.get(user_id)?;
+        let last_change = self.history().iter().rposition(|event| {
+            use Mutation::*;
+            match event.get_mutation() {
+                SpecialAddedToAll(_) => true,
+                DepositSet { id, .. } | DepositReturned(id) => meals.get_meal(id).is_some(),
+                mutation => mutation.get_user_id() == Some(user_id),
+            }
+        });
+        Some(last_change.map_or(0, |index| index + 1))
+    }
 
-        //Then
-        assert_eq!(order.meals.len(), 1);
-        assert_eq!(order.meals[&user_id], Meals::new(user_id.clone()));
-        assert_eq!(order.status, OrderStatus::Open);
-        assert_eq!(order.manager_id, user_id);
+    /// Fails if the meals or payments of the user were changed after the version the client `expected`, i.e. the
+    /// sequence number it last saw, so it doesn't overwrite a change it missed.
+    pub fn check_meals_version(
+        &self,
+        user_id: &Id<User>,
+        expected: usize,
+    ) -> Result<(), OrderError> {
+        let current = self.get_sequence_number();
+        let changed = self.get_meals_version(user_id).unwrap_or(0);
+        if expected > current || changed > expected {
+            return Err(OrderError::Conflict(ConflictError { expected, current }));
+        }
+        Ok(())
     }
 
-    #[test]
-    fn user_can_be_added_to_order() {
-        //Given
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id.clone());
+    /// Changes made after the given sequence number, oldest first, e.g. the ones a reconnecting client missed.
+    ///
+    /// `None` if the order never had that many changes, e.g. because its history was anonymized since.
+    pub fn events_since(&self, sequence_number: usize) -> Option<&[OrderEvent]> {
+        self.history().get(sequence_number..)
+    }
 
-        let user_id = Id::new(1);
+    /// Makes the change of the mutation again through the method which recorded it.
+    fn apply(&mut self, mutation: Mutation) -> Result<(), OrderError> {
+        use Mutation::*;
+        match mutation {
+            Created { .. } => return Err(OrderError::InvalidHistory),
+            UserAdded(user_id) => {
+                self.add_user(user_id)?;
+            }
+            UserRemoved(user_id) => {
+                self.remove_user(user_id)?;
+            }
+            MenuSet(menu) => self.set_menu(menu),
+            MealAdded {
+                user_id,
+                id,
+                meal_id,
+                variety,
+                price,
+            } => {
+                if self
+                    .add_meal_for_user(user_id, meal_id, variety, price)?
+                    .get_id()
+                    != id
+                {
+                    return Err(OrderError::InvalidHistory);
+                }
+            }
+            MealUpdated {
+                user_id,
+                id,
+                meal_id,
+                variety,
+                price,
+            } => {
+                self.update_meal_for_user(user_id, id, meal_id, variety, price)?;
+            }
+            MealRemoved { user_id, id } => {
+                self.remove_meal_for_user(user_id, id)?;
+            }
+            PreparationsSet {
+                user_id,
+                id,
+                preparations,
+            } => {
+                self.set_preparations_for_user(user_id, id, preparations)?;
+            }
+            NoteSet { user_id, id, note } => {
+                self.set_note_for_user(user_id, id, note)?;
+            }
+            OfficeMealAdded {
+                id,
+                meal_id,
+                variety,
+                price,
+            } => {
+                if self.add_office_meal(meal_id, variety, price)?.get_id() != id {
+                    return Err(OrderError::InvalidHistory);
+                }
+            }
+            OfficeMealRemoved(id) => {
+                self.remove_office_meal(id)?;
+            }
+            SharedMealAdded {
+                id,
+                meal_id,
+                variety,
+                price,
+            } => {
+                if self.add_shared_meal(meal_id, variety, price)?.get_id() != id {
+                    return Err(OrderError::InvalidHistory);
+                }
+            }
+            SharedMealRemoved(id) => {
+                self.remove_shared_meal(id)?;
+            }
+            SpecialAdded {
+                user_id,
+                meal,
+                id,
+                description,
+                price,
+            } => {
+                if self.add_special_for_user(user_id, meal, description, price)? != id {
+                    return Err(OrderError::InvalidHistory);
+                }
+            }
+            SpecialRemoved { user_id, meal, id } => {
+                self.remove_special_for_user(user_id, meal, id)?;
+            }
+            SpecialAddedToAll(description) => {
+                self.add_special_to_all_meals(description)?;
+            }
+            PaidSet { user_id, paid } => self.set_paid_for_user(user_id, paid)?,
+            TipSet { user_id, tip } => self.set_tip_for_user(user_id, tip)?,
+            ReadySet { user_id, ready } => self.set_ready_for_user(user_id, ready)?,
+            PaymentClaimed {
+                user_id,
+                amount,
+                method,
+            } => self.claim_payment_for_user(user_id, amount, method)?,
+            PaymentConfirmed { user_id, time } => {
+                self.confirm_payment_at(user_id, time)?;
+            }
+            PaymentDisputed(user_id) => self.dispute_payment_for_user(user_id)?,
+            PaymentAdded {
+                user_id,
+                id,
+                amount,
+                time,
+                key,
+            } => {
+                if self.add_payment_at(user_id, amount, time, key)? != id {
+                    return Err(OrderError::InvalidHistory);
+                }
+            }
+            PaymentHeld {
+                user_id,
+                id,
+                amount,
+                time,
+                key,
+                reason,
+            } => {
+                if self.hold_payment_at(user_id, amount, time, key, reason)? != id {
+                    return Err(OrderError::InvalidHistory);
+                }
+            }
+            HeldPaymentReleased { user_id, id } => {
+                self.release_payment_for_user(user_id, id)?;
+            }
+            HeldPaymentDiscarded { user_id, id } => {
+                self.discard_payment_for_user(user_id, id)?;
+            }
+            PaymentRemoved { user_id, id } => {
+                self.remove_payment_for_user(user_id, id)?;
+            }
+            PaymentCorrected {
+                user_id,
+                id,
+                amount,
+            } => {
+                self.correct_payment_for_user(user_id, id, amount)?;
+            }
+            Undone { user_id, steps } => {
+                self.undo_for_user(user_id, steps)?;
+            }
+            Redone(user_id) => {
+                self.redo_for_user(user_id)?;
+            }
+            // Unchecked, as a deadline starts ordering without waiting for the participants
+            StatusChanged(OrderStatus::Ordering) => self.begin_ordering()?,
+            // Unchecked, the restaurant was checked at the time the order was placed
+            StatusChanged(OrderStatus::Ordered { placed_at, eta }) => {
+                self.set_ordered(placed_at, eta)?
+            }
+            StatusChanged(OrderStatus::Cancelled) => self.cancel()?,
+            StatusChanged(OrderStatus::Closed) => self.close(),
+            StatusChanged(_) => return Err(OrderError::InvalidHistory),
+            Delivered(time) => self.mark_delivered(time)?,
+            GracePeriodSet(grace_period) => self.set_grace_period(grace_period),
+            CurrencySet(currency) => self.set_currency(currency)?,
+            LocaleSet(locale) => self.set_locale(locale),
+            CostCenterSet(cost_center) => self.set_cost_center(cost_center),
+            DeadlineSet(deadline) => self.set_deadline(deadline)?,
+            DeliveryFeeSet { fee, fee_split } => self.set_delivery_fee(fee, fee_split)?,
+            SharedSplitSet(split) => self.set_shared_split(split)?,
+            RestaurantSet(restaurant) => self.set_restaurant(restaurant)?,
+            EtaSet(eta) => self.set_eta(eta)?,
+            ReadyRequiredSet(required) => self.set_ready_required(required)?,
+            DepositSet { id, deposit } => {
+                self.set_deposit(&id, deposit)?;
+            }
+            DepositReturned(id) => {
+                self.return_deposit(&id)?;
+            }
+        }
+        Ok(())
+    }
 
-        //When
-        let meal = order.add_user(user_id.clone());
+    pub fn get_manager_id(&self) -> Id<User> {
+        self.manager_id.clone()
+    }
 
-        //Then
-        assert_eq!(meal, &mut Meals::new(user_id.clone()));
-        assert_eq!(order.meals.len(), 2);
-        assert_eq!(order.meals[&user_id], Meals::new(user_id));
-        assert_eq!(order.status, OrderStatus::Open);
-        assert_eq!(order.manager_id, manager_id);
+    /// Sets the menu of the restaurant, so meals can be picked from it and prices are validated against it.
+    pub fn set_menu(&mut self, menu: Arc<Menu>) {
+        self.menu = Some(menu.clone());
+        self.audit.record(Mutation::MenuSet(menu));
     }
 
-    #[rstest(
-        status,
-        expected,
-        case(OrderStatus::Open, String::from("Open")),
-        case(OrderStatus::Ordering, String::from("Ordering")),
-        case(
-            OrderStatus::Ordered(String::from("12:15")),
-            String::from("Ordered(\"12:15\")")
-        ),
-        case(OrderStatus::Delivered, String::from("Delivered"))
-    )]
-    fn order_status_is_formatted_correctly(status: OrderStatus, expected: String) {
-        assert_eq!(expected, status.to_string())
+    pub fn get_menu(&self) -> Option<&Menu> {
+        self.menu.as_deref()
     }
 
-    #[rstest(
-        meal_id,
-        variety,
-        price,
-        case(String::from("03"), String::from("groß"), Money::new(5, 50)),
-        case(String::from("04"), String::from("riesig"), Money::new(7, 20))
-    )]
-    fn meal_can_be_added_to_order_for_user(meal_id: String, variety: String, price: Money) {
-        // Given:
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    /// Tax rate of the meal on the menu, which meals take over when they are added.
+    fn get_menu_tax_rate(&self, meal_id: &str) -> Option<TaxRate> {
+        self.menu
+            .as_ref()
+            .and_then(|menu| menu.get_item(meal_id))
+            .and_then(MenuItem::get_tax_rate)
+    }
 
-        let user_id = Id::new(1);
-        order.add_user(user_id.clone());
+    /// Deposit of the meal on the menu, which meals take over when they are added.
+    fn get_menu_deposit(&self, meal_id: &str) -> Money {
+        self.menu
+            .as_ref()
+            .and_then(|menu| menu.get_item(meal_id))
+            .map_or(Money::zero(), MenuItem::get_deposit)
+    }
 
-        // When:
-        let meal = order.add_meal_for_user(
-            user_id.clone(),
-            meal_id.clone(),
-            variety.clone(),
-            price.clone(),
-        );
+    /// Adds a meal for the given user.
+    ///
+    /// If the order has a menu, the meal has to be on it for exactly the given `price`.
+    pub fn add_meal_for_user(
+        &mut self,
+        user_id: Id<User>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.check_currency(price)?;
+        if let Some(menu) = &self.menu {
+            menu.validate_price(&meal_id, &variety, price)
+                .map_err(OrderError::Menu)?;
+        }
+        let tax_rate = self.get_menu_tax_rate(&meal_id);
+        let deposit = self.get_menu_deposit(&meal_id);
+        let meals = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?;
+        let mut meal = self.meal_factory.create_meal(meal_id, variety, price);
+        meal.set_tax_rate(tax_rate);
+        meal.set_deposit(deposit);
+        self.audit.record(Mutation::MealAdded {
+            user_id,
+            id: meal.get_id(),
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+            price,
+        });
+        Ok(meals.add_meal(meal))
+    }
 
-        // Then:
-        assert_eq!(
-            meal,
-            Ok(&mut Meal::new(
-                Id::new(0),
-                meal_id.clone(),
-                variety.clone(),
-                price.clone()
-            ))
-        );
-        let mut expected_meals = Meals::new(user_id.clone());
-        expected_meals.add_meal(Meal::new(Id::new(0), meal_id, variety, price));
-        assert_eq!(order.get_meals_for_user(user_id), Some(&mut expected_meals));
+    /// Changes number, variety and price of a meal of the given user, validated against the menu if there is one.
+    ///
+    /// Meals can only be edited while the order is open, so nobody changes them while the manager is calling
+    /// the restaurant. Edits can be undone.
+    pub fn update_meal_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.check_currency(price)?;
+        if self.status != OrderStatus::Open {
+            return Err(OrderError::WrongStatus);
+        }
+        if let Some(menu) = &self.menu {
+            menu.validate_price(&meal_id, &variety, price)
+                .map_err(OrderError::Menu)?;
+        }
+        let tax_rate = self.get_menu_tax_rate(&meal_id);
+        let deposit = self.get_menu_deposit(&meal_id);
+        let meal = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .update_meal(&id, meal_id.clone(), variety.clone(), price)
+            .ok_or(OrderError::MealNotFound)?;
+        if self.menu.is_some() {
+            meal.set_tax_rate(tax_rate);
+            meal.set_deposit(deposit);
+        }
+        self.audit.record(Mutation::MealUpdated {
+            user_id,
+            id,
+            meal_id,
+            variety,
+            price,
+        });
+        Ok(meal)
     }
 
-    #[test]
-    fn meal_cannot_be_added_to_order_for_user_if_user_is_not_participating_in_order() {
-        // Given:
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    /// Sets how a meal of the given user is prepared, validated against the kitchen of the menu if there is one.
+    ///
+    /// Like other edits, this is only possible while the order is open and can be undone.
+    pub fn set_preparations_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Meal>,
+        preparations: BTreeSet<Preparation>,
+    ) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if self.status != OrderStatus::Open {
+            return Err(OrderError::WrongStatus);
+        }
+        if let Some(menu) = &self.menu {
+            menu.validate_preparations(&preparations)
+                .map_err(OrderError::Menu)?;
+        }
+        let meal = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .set_preparations(&id, preparations.clone())
+            .ok_or(OrderError::MealNotFound)?;
+        self.audit.record(Mutation::PreparationsSet {
+            user_id,
+            id,
+            preparations,
+        });
+        Ok(meal)
+    }
 
-        let user_id = Id::new(1);
+    /// Sets a free-text note on a meal of the given user, e.g. "cut into 8 slices", or removes it with `None`.
+    ///
+    /// Like other edits, this is only possible while the order is open and can be undone.
+    pub fn set_note_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Meal>,
+        note: Option<String>,
+    ) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if self.status != OrderStatus::Open {
+            return Err(OrderError::WrongStatus);
+        }
+        let meal = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .set_note(&id, note.clone())
+            .ok_or(OrderError::MealNotFound)?;
+        self.audit.record(Mutation::NoteSet { user_id, id, note });
+        Ok(meal)
+    }
 
-        // When:
-        let meal = order.add_meal_for_user(
-            user_id.clone(),
-            String::from("03"),
-            String::from("groß"),
-            Money::new(5, 50),
-        );
+    /// Fails if the note or deposit couldn't be set on a meal, so a meal isn't added when its details are refused.
+    ///
+    /// Notes can only be changed while the order is open, see `set_note_for_user` and `set_deposit`.
+    pub fn check_meal_details(
+        &self,
+        note: Option<&String>,
+        deposit: Option<Money>,
+    ) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if note.is_some() && self.status != OrderStatus::Open {
+            return Err(OrderError::WrongStatus);
+        }
+        if let Some(deposit) = deposit {
+            self.check_currency(deposit)?;
+        }
+        Ok(())
+    }
 
-        // Then:
-        assert_eq!(meal, Err(OrderError::UserNotParticipating));
-        assert_eq!(order.get_meals_for_user(user_id), None);
+    /// Removes a meal of the given user, which can be undone.
+    pub fn remove_meal_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Meal>,
+    ) -> Result<Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        let meal = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .remove_meal_by_id(id.clone())
+            .ok_or(OrderError::MealNotFound)?;
+        self.audit.record(Mutation::MealRemoved { user_id, id });
+        Ok(meal)
     }
 
-    #[test]
-    fn user_not_participating_in_order_has_no_meals() {
-        // Given:
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    /// Adds a special to a meal of the given user, costing `price` extra if given, and returns its ID.
+    ///
+    /// Adding and removing specials can be undone.
+    pub fn add_special_for_user(
+        &mut self,
+        user_id: Id<User>,
+        meal: Id<Meal>,
+        description: String,
+        price: Option<Money>,
+    ) -> Result<Id<Special>, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if let Some(price) = price {
+            self.check_currency(price)?;
+        }
+        let id = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .add_special(&meal, description.clone(), price)
+            .ok_or(OrderError::MealNotFound)?
+            .get_id();
+        self.audit.record(Mutation::SpecialAdded {
+            user_id,
+            meal,
+            id: id.clone(),
+            description,
+            price,
+        });
+        Ok(id)
+    }
 
-        let user_id = Id::new(1);
+    pub fn remove_special_for_user(
+        &mut self,
+        user_id: Id<User>,
+        meal: Id<Meal>,
+        id: Id<Special>,
+    ) -> Result<Special, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        let special = self
+            .meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .remove_special(&meal, id.clone())
+            .ok_or(OrderError::MealNotFound)?;
+        self.audit
+            .record(Mutation::SpecialRemoved { user_id, meal, id });
+        Ok(special)
+    }
 
-        // When:
-        let meals = order.get_meals_for_user(user_id);
+    /// Adds a meal from the menu of the order for the given user, using the price on the menu.
+    ///
+    /// An option has to be chosen from every option group of the meal, see `Menu::resolve_choices`. The chosen
+    /// options are added to the meal as specials without extra charge.
+    pub fn add_menu_meal_for_user(
+        &mut self,
+        user_id: Id<User>,
+        meal_id: String,
+        variety: String,
+        choices: &[String],
+    ) -> Result<&Meal, OrderError> {
+        let menu = self.menu.as_ref().ok_or(OrderError::NoMenu)?;
+        let price = menu
+            .get_price(&meal_id, &variety)
+            .map_err(OrderError::Menu)?;
+        let options = menu
+            .resolve_choices(&meal_id, choices)
+            .map_err(OrderError::Menu)?;
+        let meal = self
+            .add_meal_for_user(user_id.clone(), meal_id, variety, price)?
+            .get_id();
+        for option in options {
+            self.add_special_for_user(user_id.clone(), meal.clone(), option, None)?;
+        }
+        Ok(self
+            .get_meals_mut(user_id)?
+            .get_meal_mut(&meal)
+            .expect("Meal was just added"))
+    }
 
-        // Then:
-        assert_eq!(meals, None);
+    /// Adds the meals of a previous order of the user again, e.g. `Favorites::get_last_order`, and returns their IDs.
+    ///
+    /// With a menu, every meal gets the price currently on it. Nothing is added if any meal or preparation isn't
+    /// offered anymore.
+    pub fn add_last_order_for_user(
+        &mut self,
+        user_id: Id<User>,
+        last_order: &[FavoriteMeal],
+    ) -> Result<Vec<Id<Meal>>, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if self.status != OrderStatus::Open {
+            return Err(OrderError::WrongStatus);
+        }
+        if !self.is_participating(&user_id) {
+            return Err(OrderError::UserNotParticipating);
+        }
+        let prices = last_order
+            .iter()
+            .map(|meal| match &self.menu {
+                Some(menu) => {
+                    menu.validate_preparations(meal.get_preparations())?;
+                    menu.get_price(meal.get_meal_id(), meal.get_variety())
+                }
+                None => Ok(meal.get_price()),
+            })
+            .collect::<Result<Vec<Money>, MenuError>>()
+            .map_err(OrderError::Menu)?;
+        let mut ids = Vec::new();
+        for (meal, price) in last_order.iter().zip(prices) {
+            let id = self
+                .add_meal_for_user(
+                    user_id.clone(),
+                    meal.get_meal_id().clone(),
+                    meal.get_variety().clone(),
+                    price,
+                )?
+                .get_id();
+            for special in meal.specials() {
+                self.add_special_for_user(
+                    user_id.clone(),
+                    id.clone(),
+                    special.get_description(),
+                    special.get_price(),
+                )?;
+            }
+            if !meal.get_preparations().is_empty() {
+                self.set_preparations_for_user(
+                    user_id.clone(),
+                    id.clone(),
+                    meal.get_preparations().clone(),
+                )?;
+            }
+            if meal.get_note().is_some() {
+                self.set_note_for_user(user_id.clone(), id.clone(), meal.get_note().cloned())?;
+            }
+            ids.push(id);
+        }
+        Ok(ids)
     }
 
-    #[test]
-    fn user_participating_in_order_has_meals() {
-        // Given:
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    /// Adds a meal bought for the office, e.g. a salad for guests.
+    ///
+    /// Office meals are part of the order placed at the restaurant, but are not paid by any participant.
+    pub fn add_office_meal(
+        &mut self,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.check_currency(price)?;
+        if let Some(menu) = &self.menu {
+            menu.validate_price(&meal_id, &variety, price)
+                .map_err(OrderError::Menu)?;
+        }
+        let tax_rate = self.get_menu_tax_rate(&meal_id);
+        let deposit = self.get_menu_deposit(&meal_id);
+        let mut meal = self.meal_factory.create_meal(meal_id, variety, price);
+        meal.set_tax_rate(tax_rate);
+        meal.set_deposit(deposit);
+        let id = meal.get_id();
+        self.audit.record(Mutation::OfficeMealAdded {
+            id: id.clone(),
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+            price,
+        });
+        Ok(self.office_meals.entry(id).or_insert(meal))
+    }
 
-        let user_id = Id::new(1);
-        order.add_user(user_id.clone());
+    pub fn remove_office_meal(&mut self, id: Id<Meal>) -> Result<Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        let meal = self
+            .office_meals
+            .remove(&id)
+            .ok_or(OrderError::MealNotFound)?;
+        self.audit.record(Mutation::OfficeMealRemoved(id));
+        Ok(meal)
+    }
 
-        // When:
-        let meals = order.get_meals_for_user(user_id.clone());
+    pub fn office_meals(&self) -> impl Iterator<Item = &Meal> {
+        self.office_meals.values()
+    }
 
-        // Then:
-        assert_eq!(meals, Some(&mut Meals::new(user_id)));
+    /// Adds a meal for everybody, e.g. a bottle of cola, whose price is split according to `get_shared_split`.
+    pub fn add_shared_meal(
+        &mut self,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.check_currency(price)?;
+        if let Some(menu) = &self.menu {
+            menu.validate_price(&meal_id, &variety, price)
+                .map_err(OrderError::Menu)?;
+        }
+        let tax_rate = self.get_menu_tax_rate(&meal_id);
+        let deposit = self.get_menu_deposit(&meal_id);
+        let mut meal = self.meal_factory.create_meal(meal_id, variety, price);
+        meal.set_tax_rate(tax_rate);
+        meal.set_deposit(deposit);
+        let id = meal.get_id();
+        self.audit.record(Mutation::SharedMealAdded {
+            id: id.clone(),
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+            price,
+        });
+        Ok(self.shared_meals.entry(id).or_insert(meal))
     }
 
-    struct MealsAttributes {
-        meal_price: Vec<Money>,
-        orderer_id: Id,
-        amount: Money,
+    pub fn remove_shared_meal(&mut self, id: Id<Meal>) -> Result<Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        let meal = self
+            .shared_meals
+            .remove(&id)
+            .ok_or(OrderError::MealNotFound)?;
+        self.audit.record(Mutation::SharedMealRemoved(id));
+        Ok(meal)
     }
 
-    #[rstest(meals_attributes, expected_total,
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
-                    orderer_id: Id::new(1),
-                    amount: Money::zero(),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
-                    orderer_id: Id::new(2),
-                    amount: Money::zero(),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(6, 83)],
-                    orderer_id: Id::new(3),
-                    amount: Money::zero(),
-                },
-            ],
-            Money::new(29, 87)),
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
-                    orderer_id: Id::new(1),
-                    amount: Money::zero(),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(5, 50)],
-                    orderer_id: Id::new(2),
-                    amount: Money::zero(),
-                },
-            ],
-            Money::new(12, 17)),
-    )]
-    fn total_price_is_calculated_correctly(
-        meals_attributes: Vec<MealsAttributes>,
-        expected_total: Money,
-    ) {
-        //Given
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    pub fn shared_meals(&self) -> impl Iterator<Item = &Meal> {
+        self.shared_meals.values()
+    }
 
-        for attributes in meals_attributes.into_iter() {
-            order.add_user(attributes.orderer_id.clone());
-            for price in attributes.meal_price.iter() {
-                order
-                    .add_meal_for_user(
-                        attributes.orderer_id.clone(),
-                        String::from("XX"),
-                        String::from("something"),
-                        price.clone(),
-                    )
-                    .unwrap();
+    pub fn get_shared_split(&self) -> FeeSplitStrategy {
+        self.shared_split
+    }
+
+    /// Sets how the price of the shared meals is split, like the delivery fee everybody pays the same by default.
+    pub fn set_shared_split(&mut self, split: FeeSplitStrategy) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.shared_split = split;
+        self.audit.record(Mutation::SharedSplitSet(split));
+        Ok(())
+    }
+
+    /// Creates a new open order with the participants and meals of `previous`, e.g. to repeat the weekly order.
+    ///
+    /// Meals get new IDs but keep their specials and preparations. Menu, currency, locale and cost center are
+    /// kept, while payments, tips, the deadline and the fee start over.
+    pub fn clone_from(previous: &Order, manager_id: Id<User>) -> Order {
+        let mut order = Order::with_audit_clock(manager_id, previous.audit.get_clock());
+        order
+            .set_currency(previous.currency)
+            .expect("New order is open and has no meals");
+        order.set_locale(previous.locale);
+        if previous.cost_center.is_some() {
+            order.set_cost_center(previous.cost_center.clone());
+        }
+        let mut user_ids: Vec<&Id<User>> = previous.meals.keys().collect();
+        user_ids.sort_by_key(|id| id.get_value());
+        for user_id in user_ids {
+            if !order.is_participating(user_id) {
+                order.add_user(user_id.clone()).expect("New order is open");
             }
+            let mut meals: Vec<&Meal> = previous.meals[user_id].meals().collect();
+            meals.sort_by_key(|meal| meal.get_id().get_value());
+            let meals: Vec<FavoriteMeal> = meals.into_iter().map(FavoriteMeal::from_meal).collect();
+            order
+                .add_last_order_for_user(user_id.clone(), &meals)
+                .expect("New order is open and has no menu yet");
         }
-        //When
-        let calculated_total = order.calculate_total_price();
-        //Then
-        assert_eq!(expected_total, calculated_total);
+        let mut office_meals: Vec<&Meal> = previous.office_meals.values().collect();
+        office_meals.sort_by_key(|meal| meal.get_id().get_value());
+        for meal in office_meals {
+            order
+                .add_office_meal(
+                    meal.get_meal_id().clone(),
+                    meal.get_variety().clone(),
+                    meal.get_price(),
+                )
+                .expect("New order is open and has no menu yet");
+        }
+        let mut shared_meals: Vec<&Meal> = previous.shared_meals.values().collect();
+        shared_meals.sort_by_key(|meal| meal.get_id().get_value());
+        for meal in shared_meals {
+            order
+                .add_shared_meal(
+                    meal.get_meal_id().clone(),
+                    meal.get_variety().clone(),
+                    meal.get_price(),
+                )
+                .expect("New order is open and has no menu yet");
+        }
+        order
+            .set_shared_split(previous.shared_split)
+            .expect("New order is open");
+        // Set last, the meals were checked against the menu when they were added to the previous order
+        if let Some(menu) = &previous.menu {
+            order.set_menu(menu.clone());
+        }
+        if let Some(restaurant) = &previous.restaurant {
+            order
+                .set_restaurant(Some(restaurant.clone()))
+                .expect("New order is open");
+        }
+        order
     }
 
-    #[rstest(meals_attributes, total_tip,
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(2, 25),
-                },
-                MealsAttributes {
-                    meal_price: vec![],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(5, 50),
-                },
-                MealsAttributes {
-                    meal_price: vec![],
-                    orderer_id: Id::new(3),
-                    amount: Money::new(7, 37),
-                },
-            ],
-            Money::new(15, 12)),
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(2, 25),
-                },
-                MealsAttributes {
-                    meal_price: vec![],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(4, 42),
-                },
-            ],
-            Money::new(6, 67)),
-    )]
-    fn total_tip_is_calculated_correctly(meals_attributes: Vec<MealsAttributes>, total_tip: Money) {
-        //Given
-        let manager_id = Id::new(0);
+    /// Creates a new open order with the same participants and meals for the restaurant of `menu`.
+    ///
+    /// Every meal is looked up on the new menu and gets its price from there. Meals which can't be found are
+    /// reported instead of being carried over with a wrong price. Payments, tips and the fee are not copied.
+    pub fn clone_for_menu(&self, manager_id: Id<User>, menu: Arc<Menu>) -> ClonedOrder {
         let mut order = Order::new(manager_id);
-
-        for attributes in meals_attributes.into_iter() {
-            order.add_user(attributes.orderer_id.clone());
-            order
-                .get_meals_for_user(attributes.orderer_id.clone())
-                .unwrap()
-                .set_tip(attributes.amount)
+        order.set_menu(menu.clone());
+        order
+            .set_currency(self.currency)
+            .expect("New order is open and has no meals");
+        order.set_locale(self.locale);
+        let mut unmatched = Vec::new();
+        let mut user_ids: Vec<&Id<User>> = self.meals.keys().collect();
+        user_ids.sort_by_key(|id| id.get_value());
+        for user_id in user_ids {
+            if !order.is_participating(user_id) {
+                order.add_user(user_id.clone()).expect("New order is open");
+            }
+            let mut meals: Vec<&Meal> = self.meals[user_id].meals().collect();
+            meals.sort_by_key(|meal| meal.get_id().get_value());
+            for meal in meals {
+                let resolved = resolve_meal(
+                    self.get_menu(),
+                    &menu,
+                    meal.get_meal_id(),
+                    meal.get_variety(),
+                );
+                match resolved {
+                    Some(resolved) => {
+                        let copy = order
+                            .add_meal_for_user(
+                                user_id.clone(),
+                                resolved.get_meal_id().clone(),
+                                resolved.get_variety().clone(),
+                                resolved.get_price(),
+                            )
+                            .expect("New order is open and the meal is on its menu")
+                            .get_id();
+                        for special in meal.specials() {
+                            order
+                                .add_special_for_user(
+                                    user_id.clone(),
+                                    copy.clone(),
+                                    special.get_description(),
+                                    special.get_price(),
+                                )
+                                .expect("Meal was just added");
+                        }
+                    }
+                    None => unmatched.push(UnmatchedMeal {
+                        user_id: Some(user_id.clone()),
+                        meal_id: meal.get_meal_id().clone(),
+                        variety: meal.get_variety().clone(),
+                    }),
+                }
+            }
         }
+        let mut office_meals: Vec<&Meal> = self.office_meals.values().collect();
+        office_meals.sort_by_key(|meal| meal.get_id().get_value());
+        let mut shared_meals: Vec<&Meal> = self.shared_meals.values().collect();
+        shared_meals.sort_by_key(|meal| meal.get_id().get_value());
+        let meals = office_meals
+            .into_iter()
+            .map(|meal| (meal, false))
+            .chain(shared_meals.into_iter().map(|meal| (meal, true)));
+        for (meal, shared) in meals {
+            match resolve_meal(
+                self.get_menu(),
+                &menu,
+                meal.get_meal_id(),
+                meal.get_variety(),
+            ) {
+                Some(resolved) => {
+                    let (meal_id, variety) = (
+                        resolved.get_meal_id().clone(),
+                        resolved.get_variety().clone(),
+                    );
+                    if shared {
+                        order.add_shared_meal(meal_id, variety, resolved.get_price())
+                    } else {
+                        order.add_office_meal(meal_id, variety, resolved.get_price())
+                    }
+                    .expect("New order is open and the meal is on its menu");
+                }
+                None => unmatched.push(UnmatchedMeal {
+                    user_id: None,
+                    meal_id: meal.get_meal_id().clone(),
+                    variety: meal.get_variety().clone(),
+                }),
+            }
+        }
+        order
+            .set_shared_split(self.shared_split)
+            .expect("New order is open");
+        ClonedOrder { order, unmatched }
+    }
 
-        //When
-        let calculated_tip = order.calculate_total_tip();
+    /// IDs of all participants, including the manager.
+    pub fn participants(&self) -> impl Iterator<Item = &Id<User>> {
+        self.meals.keys()
+    }
 
-        //Then
-        assert_eq!(total_tip, calculated_tip);
+    /// The meals and payments of all participants, including the manager, each with `Meals::get_owner_id`.
+    pub fn users(&self) -> impl Iterator<Item = &Meals> {
+        self.meals.values()
     }
 
-    #[rstest(meals_attributes, expected_change,
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(17, 00),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(8, 50),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(6, 83)],
-                    orderer_id: Id::new(3),
-                    amount: Money::new(6, 83),
-                },
-            ],
-            Money::new(2, 46),
-        ),
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(8, 25),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(5, 50)],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(5, 50),
-                },
-            ],
-            Money::new(1, 58),
-        ),
-    )]
-    fn all_paid_enough_change_is_calculated_correctly(
-        meals_attributes: Vec<MealsAttributes>,
-        expected_change: Money,
-    ) {
-        //Given
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    /// Whether `anonymize` was called, so the participants are pseudonyms instead of registered users.
+    pub fn is_anonymized(&self) -> bool {
+        self.anonymized
+    }
 
-        for attributes in meals_attributes.into_iter() {
-            order.add_user(attributes.orderer_id.clone());
-            for price in attributes.meal_price.iter() {
-                order
-                    .add_meal_for_user(
-                        attributes.orderer_id.clone(),
-                        String::from("XX"),
-                        String::from("something"),
-                        price.clone(),
-                    )
-                    .unwrap();
+    /// Removes everything linking the order to people, keeping the meals and amounts statistics need.
+    ///
+    /// Participants get consecutive IDs starting with 0 for the manager, which don't belong to registered users.
+    /// Claimed payments and the history except the creation are dropped, so the order can't be replayed anymore.
+    pub fn anonymize(&mut self) {
+        let mut user_ids: Vec<Id<User>> = self.meals.keys().cloned().collect();
+        user_ids.sort_by_key(|id| (id != &self.manager_id, id.get_value()));
+        let mut anonymized = HashMap::new();
+        for (pseudonym, user_id) in (0..).zip(user_ids) {
+            let mut meals = self
+                .meals
+                .remove(&user_id)
+                .expect("ID is taken from the meals");
+            meals.anonymize(Id::new(pseudonym));
+            anonymized.insert(Id::new(pseudonym), meals);
+        }
+        self.meals = anonymized;
+        self.manager_id = Id::new(0);
+        let created = OrderEvent::new(
+            self.get_created_at(),
+            Mutation::Created {
+                manager_id: self.manager_id.clone(),
+            },
+        );
+        self.audit.set_events(vec![created]);
+        self.anonymized = true;
+    }
+
+    /// Checks the invariants of the order which can only break through inconsistent stored data.
+    pub fn check_integrity(&self) -> Vec<IntegrityIssue> {
+        let mut issues = Vec::new();
+        let mut user_ids: Vec<&Id<User>> = self.meals.keys().collect();
+        user_ids.sort_by_key(|id| id.get_value());
+        for user_id in user_ids {
+            let owner_id = self.meals[user_id].get_owner_id();
+            if &owner_id != user_id {
+                issues.push(IntegrityIssue::OwnerMismatch {
+                    user_id: user_id.clone(),
+                    owner_id,
+                });
             }
-            order
-                .get_meals_for_user(attributes.orderer_id)
-                .unwrap()
-                .set_paid(attributes.amount)
         }
-        //When
-        let calculated_change = order.calculate_total_change().unwrap();
-        //Then
-        assert_eq!(expected_change, calculated_change);
+        let mut meal_ids = HashSet::new();
+        let mut duplicates: Vec<u32> = self
+            .all_meals()
+            .map(|meal| meal.get_id().get_value())
+            .filter(|id| !meal_ids.insert(*id))
+            .collect();
+        duplicates.sort_unstable();
+        duplicates.dedup();
+        issues.extend(
+            duplicates
+                .into_iter()
+                .map(|id| IntegrityIssue::DuplicateMealId(Id::new(id))),
+        );
+        if !self.is_participating(&self.manager_id) {
+            issues.push(IntegrityIssue::ManagerNotParticipating(
+                self.manager_id.clone(),
+            ));
+        }
+        let mut expected = self.delivery_fee;
+        for meal in self.all_meals() {
+            expected += meal.get_total_price();
+        }
+        let actual = self.calculate_total_price();
+        if expected != actual {
+            issues.push(IntegrityIssue::InconsistentTotal { expected, actual });
+        }
+        issues
     }
 
-    fn build_paid_less_hash_set(user_ids: Vec<u32>) -> HashSet<Id> {
-        user_ids.into_iter().map(|it| Id::new(it)).collect()
+    /// Iterates over every meal ordered at the restaurant: those of all participants, the office and shared meals.
+    pub fn all_meals(&self) -> impl Iterator<Item = &Meal> {
+        self.meals
+            .values()
+            .flat_map(Meals::meals)
+            .chain(self.office_meals.values())
+            .chain(self.shared_meals.values())
     }
 
-    #[rstest(meals_attributes, expected_change,
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(17, 00),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(7, 50),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(6, 83)],
-                    orderer_id: Id::new(3),
-                    amount: Money::new(6, 00),
+    pub fn is_participating(&self, user_id: &Id<User>) -> bool {
+        self.meals.contains_key(user_id)
+    }
+
+    /// Adds a special with the given description to every meal, e.g. "no onions" for the whole office.
+    ///
+    /// Returns the number of meals changed.
+    pub fn add_special_to_all_meals(&mut self, description: String) -> Result<usize, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        let mut changed = 0;
+        let meals = self
+            .meals
+            .values_mut()
+            .flat_map(Meals::meals_mut)
+            .chain(self.office_meals.values_mut())
+            .chain(self.shared_meals.values_mut());
+        for meal in meals {
+            meal.add_special(description.clone());
+            changed += 1;
+        }
+        self.audit.record(Mutation::SpecialAddedToAll(description));
+        Ok(changed)
+    }
+
+    /// Removes the given user and their meals from the order, e.g. if they changed their mind.
+    pub fn remove_user(&mut self, user_id: Id<User>) -> Result<RemovedUser, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if user_id == self.manager_id {
+            return Err(OrderError::ManagerCannotLeave);
+        }
+        let meals = self
+            .meals
+            .remove(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?;
+        self.audit.record(Mutation::UserRemoved(user_id));
+        Ok(RemovedUser {
+            refund: meals.get_paid(),
+            meals,
+        })
+    }
+
+    pub fn get_user_meals(&self, user_id: &Id<User>) -> Option<&Meals> {
+        self.meals.get(user_id)
+    }
+
+    pub fn get_meals_for_user(&self, user_id: Id<User>) -> Result<&Meals, OrderError> {
+        self.meals
+            .get(&user_id)
+            .ok_or(OrderError::UserNotParticipating)
+    }
+
+    /// Meals of the user to change, only through methods recording the change, see `history`.
+    fn get_meals_mut(&mut self, user_id: Id<User>) -> Result<&mut Meals, OrderError> {
+        self.meals
+            .get_mut(&user_id)
+            .ok_or(OrderError::UserNotParticipating)
+    }
+
+    pub fn set_paid_for_user(&mut self, user_id: Id<User>, paid: Money) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.check_currency(paid)?;
+        self.get_meals_mut(user_id.clone())?.set_paid(paid);
+        self.audit.record(Mutation::PaidSet { user_id, paid });
+        Ok(())
+    }
+
+    /// Records that the given user paid a part of their share now, see `Meals::add_payment`.
+    pub fn add_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        amount: Money,
+    ) -> Result<Id<Installment>, OrderError> {
+        let now = self.audit.get_clock().now();
+        self.add_payment_at(user_id, amount, now, None)
+    }
+
+    /// Adds an installment unless it looks like one that was already booked, see `Meals::find_duplicate`.
+    ///
+    /// A retry with the same idempotency key books nothing and a suspected duplicate is held until the manager
+    /// releases or discards it, so a payment sent twice doesn't count twice.
+    pub fn receive_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        amount: Money,
+        key: Option<String>,
+    ) -> Result<ReceivedPayment, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.check_currency(amount)?;
+        let now = self.audit.get_clock().now();
+        let duplicate =
+            self.get_meals_mut(user_id.clone())?
+                .find_duplicate(amount, now, key.as_deref());
+        match duplicate {
+            None => Ok(ReceivedPayment::Booked(
+                self.add_payment_at(user_id, amount, now, key)?,
+            )),
+            Some(Duplicate::Retry(id)) => Ok(ReceivedPayment::Repeated(id)),
+            Some(Duplicate::Suspected(reason)) => Ok(ReceivedPayment::Held(
+                self.hold_payment_at(user_id, amount, now, key, reason.clone())?,
+                reason,
+            )),
+        }
+    }
+
+    fn add_payment_at(
+        &mut self,
+        user_id: Id<User>,
+        amount: Money,
+        time: SystemTime,
+        key: Option<String>,
+    ) -> Result<Id<Installment>, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.check_currency(amount)?;
+        let id =
+            self.get_meals_mut(user_id.clone())?
+                .add_payment_with_key(amount, time, key.clone());
+        self.audit.record(Mutation::PaymentAdded {
+            user_id,
+            id: id.clone(),
+            amount,
+            time,
+            key,
+        });
+        Ok(id)
+    }
+
+    fn hold_payment_at(
+        &mut self,
+        user_id: Id<User>,
+        amount: Money,
+        time: SystemTime,
+        key: Option<String>,
+        reason: DuplicateReason,
+    ) -> Result<Id<Installment>, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        let id = self.get_meals_mut(user_id.clone())?.hold_payment(
+            amount,
+            time,
+            key.clone(),
+            reason.clone(),
+        );
+        self.audit.record(Mutation::PaymentHeld {
+            user_id,
+            id: id.clone(),
+            amount,
+            time,
+            key,
+            reason,
+        });
+        Ok(id)
+    }
+
+    /// Books a held payment which the manager confirmed is no duplicate and returns its amount.
+    pub fn release_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Installment>,
+    ) -> Result<Money, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        let amount = self
+            .get_meals_mut(user_id.clone())?
+            .release_payment(&id)
+            .map_err(|_| OrderError::PaymentNotFound)?;
+        self.audit
+            .record(Mutation::HeldPaymentReleased { user_id, id });
+        Ok(amount)
+    }
+
+    /// Drops a held payment which the manager confirmed is a duplicate.
+    pub fn discard_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Installment>,
+    ) -> Result<HeldPayment, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        let discarded = self
+            .get_meals_mut(user_id.clone())?
+            .discard_payment(&id)
+            .map_err(|_| OrderError::PaymentNotFound)?;
+        self.audit
+            .record(Mutation::HeldPaymentDiscarded { user_id, id });
+        Ok(discarded)
+    }
+
+    pub fn remove_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Installment>,
+    ) -> Result<Installment, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        let removed = self
+            .get_meals_mut(user_id.clone())?
+            .remove_payment(&id)
+            .map_err(|_| OrderError::PaymentNotFound)?;
+        self.audit.record(Mutation::PaymentRemoved { user_id, id });
+        Ok(removed)
+    }
+
+    /// Changes the amount of an installment of the given user and returns the previous amount.
+    pub fn correct_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        id: Id<Installment>,
+        amount: Money,
+    ) -> Result<Money, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.check_currency(amount)?;
+        let previous = self
+            .get_meals_mut(user_id.clone())?
+            .correct_payment(&id, amount)
+            .map_err(|_| OrderError::PaymentNotFound)?;
+        self.audit.record(Mutation::PaymentCorrected {
+            user_id,
+            id,
+            amount,
+        });
+        Ok(previous)
+    }
+
+    pub fn set_tip_for_user(&mut self, user_id: Id<User>, tip: Money) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.check_currency(tip)?;
+        self.get_meals_mut(user_id.clone())?.set_tip(tip);
+        self.audit.record(Mutation::TipSet { user_id, tip });
+        Ok(())
+    }
+
+    /// Marks whether the given user has completed their meal selection.
+    pub fn set_ready_for_user(&mut self, user_id: Id<User>, ready: bool) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        let meals = self.get_meals_mut(user_id.clone())?;
+        if ready {
+            meals.mark_ready();
+        } else {
+            meals.mark_not_ready();
+        }
+        self.audit.record(Mutation::ReadySet { user_id, ready });
+        Ok(())
+    }
+
+    /// Whether every participant completed their meal selection, also if nobody joined yet.
+    pub fn all_ready(&self) -> bool {
+        self.meals.values().all(Meals::is_ready)
+    }
+
+    /// IDs of the participants still choosing, sorted ascending.
+    pub fn get_unready_users(&self) -> Vec<Id<User>> {
+        let mut user_ids: Vec<Id<User>> = self
+            .meals
+            .values()
+            .filter(|meals| !meals.is_ready())
+            .map(Meals::get_owner_id)
+            .collect();
+        user_ids.sort();
+        user_ids
+    }
+
+    /// Makes `start_ordering` wait until every participant is ready, or stops it from waiting.
+    pub fn set_ready_required(&mut self, required: bool) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.ready_required = required;
+        self.audit.record(Mutation::ReadyRequiredSet(required));
+        Ok(())
+    }
+
+    pub fn is_ready_required(&self) -> bool {
+        self.ready_required
+    }
+
+    pub fn get_status(&self) -> &OrderStatus {
+        &self.status
+    }
+
+    /// Central guard deciding whether the given kind of change is allowed in the current status.
+    pub fn check_modifiable(&self, modification: Modification) -> Result<(), OrderError> {
+        match (&self.status, modification) {
+            (OrderStatus::Closed, _)
+            | (OrderStatus::Cancelled, _)
+            | (OrderStatus::Delivered, Modification::Meals) => Err(OrderError::WrongStatus),
+            _ => Ok(()),
+        }
+    }
+
+    /// Guard refusing amounts in another currency than the order, so its totals never mix currencies.
+    ///
+    /// The totals rely on it when adding up amounts, see `sum_amounts`.
+    ///
+    /// The currency of the order is the one set explicitly, otherwise the one of the first amount that has one.
+    pub fn check_currency(&self, amount: Money) -> Result<(), OrderError> {
+        if let Some(currency) = self.get_amounts_currency() {
+            Money::zero().with_currency(currency).try_add(amount)?;
+        }
+        Ok(())
+    }
+
+    fn get_amounts_currency(&self) -> Option<Currency> {
+        self.currency.or_else(|| {
+            self.all_meals()
+                .flat_map(|meal| {
+                    iter::once(meal.get_price())
+                        .chain(meal.specials().filter_map(Special::get_price))
+                        .chain(iter::once(meal.get_deposit()))
+                })
+                .chain(self.meals.values().flat_map(|meals| {
+                    meals
+                        .payments()
+                        .iter()
+                        .map(Installment::get_amount)
+                        .chain(iter::once(meals.get_tip()))
+                        .chain(meals.get_payment().map(Payment::get_amount))
+                }))
+                .chain(iter::once(self.delivery_fee))
+                .find_map(|amount| amount.get_currency())
+        })
+    }
+
+    /// Central guard deciding whether the acting user may make the change, independent of how they were authenticated.
+    pub fn check_authorized(
+        &self,
+        actor: &Id<User>,
+        permission: &Permission,
+    ) -> Result<(), OrderError> {
+        let allowed = match permission {
+            Permission::ChangeStatus => *actor == self.manager_id,
+            Permission::RemoveUser(user_id) => *actor == self.manager_id || actor == user_id,
+            Permission::ModifyMeals(user_id) => actor == user_id,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(OrderError::NotAuthorized)
+        }
+    }
+
+    /// `start_ordering` on behalf of the acting user, who has to be the manager.
+    pub fn start_ordering_as(&mut self, actor: &Id<User>) -> Result<(), OrderError> {
+        self.check_authorized(actor, &Permission::ChangeStatus)?;
+        self.start_ordering()
+    }
+
+    /// `mark_ordered` on behalf of the acting user, who has to be the manager.
+    pub fn mark_ordered_as(
+        &mut self,
+        actor: &Id<User>,
+        eta: Option<DateTime<Utc>>,
+    ) -> Result<(), OrderError> {
+        self.check_authorized(actor, &Permission::ChangeStatus)?;
+        self.mark_ordered(eta)
+    }
+
+    /// `mark_delivered` on behalf of the acting user, who has to be the manager.
+    pub fn mark_delivered_as(
+        &mut self,
+        actor: &Id<User>,
+        time: SystemTime,
+    ) -> Result<(), OrderError> {
+        self.check_authorized(actor, &Permission::ChangeStatus)?;
+        self.mark_delivered(time)
+    }
+
+    /// `cancel` on behalf of the acting user, who has to be the manager.
+    pub fn cancel_as(&mut self, actor: &Id<User>) -> Result<(), OrderError> {
+        self.check_authorized(actor, &Permission::ChangeStatus)?;
+        self.cancel()
+    }
+
+    /// `remove_user` on behalf of the acting user, who has to be the manager unless leaving themselves.
+    pub fn remove_user_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+    ) -> Result<RemovedUser, OrderError> {
+        self.check_authorized(actor, &Permission::RemoveUser(user_id.clone()))?;
+        self.remove_user(user_id)
+    }
+
+    /// `add_meal_for_user` on behalf of the acting user, who has to be the user.
+    pub fn add_meal_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Result<&Meal, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.add_meal_for_user(user_id, meal_id, variety, price)
+    }
+
+    /// `update_meal_for_user` on behalf of the acting user, who has to be the user.
+    pub fn update_meal_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        id: Id<Meal>,
+        meal_id: String,
+        variety: String,
+        price: Money,
+    ) -> Result<&Meal, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.update_meal_for_user(user_id, id, meal_id, variety, price)
+    }
+
+    /// `set_preparations_for_user` on behalf of the acting user, who has to be the user.
+    pub fn set_preparations_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        id: Id<Meal>,
+        preparations: BTreeSet<Preparation>,
+    ) -> Result<&Meal, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.set_preparations_for_user(user_id, id, preparations)
+    }
+
+    /// `set_note_for_user` on behalf of the acting user, who has to be the user.
+    pub fn set_note_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        id: Id<Meal>,
+        note: Option<String>,
+    ) -> Result<&Meal, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.set_note_for_user(user_id, id, note)
+    }
+
+    /// `remove_meal_for_user` on behalf of the acting user, who has to be the user.
+    pub fn remove_meal_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        id: Id<Meal>,
+    ) -> Result<Meal, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.remove_meal_for_user(user_id, id)
+    }
+
+    /// `add_special_for_user` on behalf of the acting user, who has to be the user.
+    pub fn add_special_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        meal: Id<Meal>,
+        description: String,
+        price: Option<Money>,
+    ) -> Result<Id<Special>, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.add_special_for_user(user_id, meal, description, price)
+    }
+
+    /// `remove_special_for_user` on behalf of the acting user, who has to be the user.
+    pub fn remove_special_as(
+        &mut self,
+        actor: &Id<User>,
+        user_id: Id<User>,
+        meal: Id<Meal>,
+        id: Id<Special>,
+    ) -> Result<Special, OrderError> {
+        self.check_authorized(actor, &Permission::ModifyMeals(user_id.clone()))?;
+        self.remove_special_for_user(user_id, meal, id)
+    }
+
+    pub fn get_grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    pub fn set_grace_period(&mut self, grace_period: Duration) {
+        self.grace_period = grace_period;
+        self.audit.record(Mutation::GracePeriodSet(grace_period));
+    }
+
+    pub fn get_currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    /// Sets the currency of the prices, which can't change once meals were added.
+    pub fn set_currency(&mut self, currency: Option<Currency>) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if self.all_meals().next().is_some() {
+            return Err(OrderError::WrongStatus);
+        }
+        self.currency = currency;
+        self.audit.record(Mutation::CurrencySet(currency));
+        Ok(())
+    }
+
+    pub fn get_locale(&self) -> Option<Locale> {
+        self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: Option<Locale>) {
+        self.locale = locale;
+        self.audit.record(Mutation::LocaleSet(locale));
+    }
+
+    pub fn get_cost_center(&self) -> Option<&String> {
+        self.cost_center.as_ref()
+    }
+
+    /// Bills the whole order to the cost center, e.g. a department lunch, or to the ones of the participants again.
+    ///
+    /// Possible in any status, as finance usually tags orders after they were delivered.
+    pub fn set_cost_center(&mut self, cost_center: Option<String>) {
+        self.cost_center = cost_center.clone();
+        self.audit.record(Mutation::CostCenterSet(cost_center));
+    }
+
+    /// Format for the amounts of this order, using the `defaults` for anything not set on the order.
+    pub fn get_money_format(&self, defaults: MoneyFormat) -> MoneyFormat {
+        MoneyFormat::new(
+            self.currency.unwrap_or_else(|| defaults.get_currency()),
+            self.locale.unwrap_or_else(|| defaults.get_locale()),
+        )
+    }
+
+    pub fn get_deadline(&self) -> Option<DateTime<Utc>> {
+        self.deadline
+    }
+
+    /// Sets the time at which the order closes for new meals, e.g. "order closes at 11:30", or removes it.
+    pub fn set_deadline(&mut self, deadline: Option<DateTime<Utc>>) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.deadline = deadline;
+        self.audit.record(Mutation::DeadlineSet(deadline));
+        Ok(())
+    }
+
+    /// Sets the deadline to when the restaurant stops taking orders, unless a deadline is set already.
+    ///
+    /// This respects the closed days and cutoffs of the restaurant, e.g. an earlier last order on Sundays. Returns the
+    /// deadline, which stays `None` without restaurant or if it takes no orders now.
+    pub fn set_deadline_to_cutoff(&mut self) -> Result<Option<DateTime<Utc>>, OrderError> {
+        if self.deadline.is_some() {
+            return Ok(self.deadline);
+        }
+        let now = DateTime::from(self.audit.get_clock().now());
+        let cutoff = self
+            .restaurant
+            .as_ref()
+            .and_then(|restaurant| restaurant.last_order_time(now));
+        if cutoff.is_some() {
+            self.set_deadline(cutoff)?;
+        }
+        Ok(cutoff)
+    }
+
+    pub fn is_past_deadline(&self, now: DateTime<Utc>) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Time left until the deadline, `None` without deadline and zero once it passed.
+    pub fn time_until_deadline(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.deadline
+            .map(|deadline| (deadline - now).max(chrono::Duration::zero()))
+    }
+
+    /// Starts ordering if the order is still open at `now` but its deadline passed. Returns whether it did.
+    pub fn start_ordering_if_past_deadline(&mut self, now: DateTime<Utc>) -> bool {
+        self.status == OrderStatus::Open
+            && self.is_past_deadline(now)
+            && self.begin_ordering().is_ok()
+    }
+
+    /// Closes the order for new participants, so the manager can call the restaurant.
+    ///
+    /// If readiness is required, see `set_ready_required`, every participant has to be ready.
+    pub fn start_ordering(&mut self) -> Result<(), OrderError> {
+        if self.status == OrderStatus::Open && self.ready_required && !self.all_ready() {
+            return Err(OrderError::NotReady(self.get_unready_users()));
+        }
+        self.begin_ordering()
+    }
+
+    /// `start_ordering` regardless of whether the participants are ready.
+    fn begin_ordering(&mut self) -> Result<(), OrderError> {
+        match self.status {
+            OrderStatus::Open => {
+                self.status = OrderStatus::Ordering;
+                self.audit
+                    .record(Mutation::StatusChanged(OrderStatus::Ordering));
+                Ok(())
+            }
+            _ => Err(OrderError::WrongStatus),
+        }
+    }
+
+    /// Marks the order as placed at the restaurant now, which expects to deliver at the `eta` if it said when.
+    ///
+    /// If the order has a restaurant, its meals have to reach the minimum order value and the restaurant has to be
+    /// open now. Placed meals can't be taken back, so the undo history of every participant is cleared.
+    pub fn mark_ordered(&mut self, eta: Option<DateTime<Utc>>) -> Result<(), OrderError> {
+        if self.status != OrderStatus::Ordering {
+            return Err(OrderError::WrongStatus);
+        }
+        let now = DateTime::from(self.audit.get_clock().now());
+        if let Some(restaurant) = &self.restaurant {
+            restaurant
+                .check_order(self.calculate_total_price() - self.delivery_fee, now)
+                .map_err(OrderError::Restaurant)?;
+        }
+        self.set_ordered(now, eta)
+    }
+
+    fn set_ordered(
+        &mut self,
+        placed_at: DateTime<Utc>,
+        eta: Option<DateTime<Utc>>,
+    ) -> Result<(), OrderError> {
+        match self.status {
+            OrderStatus::Ordering => {
+                self.status = OrderStatus::Ordered { placed_at, eta };
+                for meals in self.meals.values_mut() {
+                    meals.clear_history();
+                }
+                self.audit
+                    .record(Mutation::StatusChanged(self.status.clone()));
+                Ok(())
+            }
+            _ => Err(OrderError::WrongStatus),
+        }
+    }
+
+    /// Changes when the restaurant expects to deliver, e.g. after calling them again, or removes it.
+    pub fn set_eta(&mut self, eta: Option<DateTime<Utc>>) -> Result<(), OrderError> {
+        match &mut self.status {
+            OrderStatus::Ordered { eta: current, .. } => {
+                *current = eta;
+                self.audit.record(Mutation::EtaSet(eta));
+                Ok(())
+            }
+            _ => Err(OrderError::WrongStatus),
+        }
+    }
+
+    /// When the restaurant expects to deliver, `None` unless ordered with an ETA.
+    pub fn get_eta(&self) -> Option<DateTime<Utc>> {
+        match self.status {
+            OrderStatus::Ordered { eta, .. } => eta,
+            _ => None,
+        }
+    }
+
+    /// Time left until the expected delivery, `None` unless ordered with an ETA and zero once it passed.
+    pub fn time_until_delivery(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.get_eta()
+            .map(|eta| (eta - now).max(chrono::Duration::zero()))
+    }
+
+    /// When the order was marked as delivered, `None` if it wasn't yet.
+    pub fn get_delivered_at(&self) -> Option<SystemTime> {
+        self.delivered_at
+    }
+
+    /// Marks the order as delivered at the given `time`, which starts the grace period.
+    pub fn mark_delivered(&mut self, time: SystemTime) -> Result<(), OrderError> {
+        match self.status {
+            OrderStatus::Ordered { .. } => {
+                self.status = OrderStatus::Delivered;
+                self.delivered_at = Some(time);
+                self.audit.record(Mutation::Delivered(time));
+                Ok(())
+            }
+            _ => Err(OrderError::WrongStatus),
+        }
+    }
+
+    /// Calls off the order, which is possible until it is delivered.
+    pub fn cancel(&mut self) -> Result<(), OrderError> {
+        match self.status {
+            OrderStatus::Open | OrderStatus::Ordering | OrderStatus::Ordered { .. } => {
+                self.status = OrderStatus::Cancelled;
+                self.audit
+                    .record(Mutation::StatusChanged(OrderStatus::Cancelled));
+                Ok(())
+            }
+            _ => Err(OrderError::WrongStatus),
+        }
+    }
+
+    /// Closes the order if its grace period is over at `now` and returns whether it is closed.
+    pub fn close_if_expired(&mut self, now: SystemTime) -> bool {
+        if let (OrderStatus::Delivered, Some(delivered_at)) = (&self.status, self.delivered_at) {
+            if now >= delivered_at + self.grace_period {
+                self.close();
+            }
+        }
+        self.status == OrderStatus::Closed
+    }
+
+    fn close(&mut self) {
+        self.status = OrderStatus::Closed;
+        self.audit
+            .record(Mutation::StatusChanged(OrderStatus::Closed));
+    }
+
+    /// Reverts the last `steps` operations on the `Meals` of the given user and returns how many were undone.
+    ///
+    /// Undoing changes to meals is only allowed as long as meals can be changed, see `check_modifiable`.
+    pub fn undo_for_user(&mut self, user_id: Id<User>, steps: usize) -> Result<usize, OrderError> {
+        let changes_meals = self
+            .get_user_meals(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .undo_changes_meals(steps);
+        self.check_modifiable(if changes_meals {
+            Modification::Meals
+        } else {
+            Modification::Payments
+        })?;
+        let undone = self.get_meals_mut(user_id.clone())?.undo(steps)?;
+        self.audit.record(Mutation::Undone { user_id, steps });
+        Ok(undone)
+    }
+
+    /// Restores the last undone operation on the `Meals` of the given user and returns whether there was one.
+    pub fn redo_for_user(&mut self, user_id: Id<User>) -> Result<bool, OrderError> {
+        let changes_meals = self
+            .get_user_meals(&user_id)
+            .ok_or(OrderError::UserNotParticipating)?
+            .redo_changes_meals();
+        self.check_modifiable(if changes_meals {
+            Modification::Meals
+        } else {
+            Modification::Payments
+        })?;
+        let redone = self.get_meals_mut(user_id.clone())?.redo()?;
+        self.audit.record(Mutation::Redone(user_id));
+        Ok(redone)
+    }
+
+    /// Lets the given user state that they paid `amount` via `method` themselves.
+    pub fn claim_payment_for_user(
+        &mut self,
+        user_id: Id<User>,
+        amount: Money,
+        method: String,
+    ) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.check_currency(amount)?;
+        self.get_meals_mut(user_id.clone())?
+            .claim_payment(amount, method.clone());
+        self.audit.record(Mutation::PaymentClaimed {
+            user_id,
+            amount,
+            method,
+        });
+        Ok(())
+    }
+
+    /// Confirms the payment claimed by the given user and adds it to their paid money.
+    pub fn confirm_payment_for_user(&mut self, user_id: Id<User>) -> Result<Money, OrderError> {
+        let now = self.audit.get_clock().now();
+        self.confirm_payment_at(user_id, now)
+    }
+
+    fn confirm_payment_at(
+        &mut self,
+        user_id: Id<User>,
+        time: SystemTime,
+    ) -> Result<Money, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        let confirmed = self
+            .get_meals_mut(user_id.clone())?
+            .confirm_payment(time)
+            .map_err(|_| OrderError::PaymentNotPending)?;
+        self.audit
+            .record(Mutation::PaymentConfirmed { user_id, time });
+        Ok(confirmed)
+    }
+
+    pub fn dispute_payment_for_user(&mut self, user_id: Id<User>) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        self.get_meals_mut(user_id.clone())?
+            .dispute_payment()
+            .map_err(|_| OrderError::PaymentNotPending)?;
+        self.audit.record(Mutation::PaymentDisputed(user_id));
+        Ok(())
+    }
+
+    /// Sets the fee the restaurant charges for delivery and how it is shared among the participants.
+    pub fn set_delivery_fee(
+        &mut self,
+        fee: Money,
+        fee_split: FeeSplitStrategy,
+    ) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.check_currency(fee)?;
+        self.delivery_fee = fee;
+        self.fee_split = fee_split;
+        self.audit
+            .record(Mutation::DeliveryFeeSet { fee, fee_split });
+        Ok(())
+    }
+
+    /// Sets the restaurant the order goes to, which also sets its delivery fee, keeping how the fee is split.
+    pub fn set_restaurant(&mut self, restaurant: Option<Restaurant>) -> Result<(), OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        if let Some(restaurant) = &restaurant {
+            self.delivery_fee = restaurant.get_delivery_fee();
+        }
+        self.restaurant = restaurant.clone();
+        self.audit.record(Mutation::RestaurantSet(restaurant));
+        Ok(())
+    }
+
+    pub fn get_restaurant(&self) -> Option<&Restaurant> {
+        self.restaurant.as_ref()
+    }
+
+    pub fn get_delivery_fee(&self) -> Money {
+        self.delivery_fee
+    }
+
+    pub fn get_fee_split(&self) -> FeeSplitStrategy {
+        self.fee_split
+    }
+
+    /// Calculates which part of the delivery fee every participant has to pay.
+    pub fn calculate_fee_shares(&self) -> HashMap<Id<User>, Money> {
+        split_fee(
+            self.delivery_fee,
+            self.fee_split,
+            &self.manager_id,
+            &self.meal_prices(),
+        )
+    }
+
+    /// Calculates which part of the price of the shared meals every participant has to pay.
+    ///
+    /// The price is split like a fee, see `split_fee`, among the participants who ordered meals of their own.
+    pub fn calculate_shared_shares(&self) -> HashMap<Id<User>, Money> {
+        split_fee(
+            self.calculate_shared_price(),
+            self.shared_split,
+            &self.manager_id,
+            &self.meal_prices(),
+        )
+    }
+
+    /// Price of the meals of every participant, which fees and shared meals are split by.
+    fn meal_prices(&self) -> HashMap<Id<User>, Money> {
+        self.meals
+            .iter()
+            .map(|(id, meals)| (id.clone(), meals.calculate_total_price()))
+            .collect()
+    }
+
+    /// IDs of the users that still have to pay and are not waiting for a payment confirmation.
+    pub fn users_to_remind(&self) -> HashSet<Id<User>> {
+        let fee_shares = self.calculate_fee_shares();
+        let shared_shares = self.calculate_shared_shares();
+        self.meals
+            .values()
+            .filter(|meals| {
+                meals.needs_payment_reminder_with_fee(
+                    Self::fee_share(&fee_shares, meals) + Self::fee_share(&shared_shares, meals),
+                )
+            })
+            .map(Meals::get_owner_id)
+            .collect()
+    }
+
+    /// Human-readable summary for chat messages and logs, see `Verbosity`:
+    /// "Open order: 2x 03 groß (Käserand) — 11,00€, 1x 05 klein — 4,50€; total 15,50€".
+    ///
+    /// The detailed summary lists the meals of every participant on a line of their own, the amounts are written
+    /// in the format of the order.
+    pub fn summary(&self, verbosity: Verbosity) -> String {
+        let format = self.get_money_format(MoneyFormat::default());
+        let total = format.format(self.calculate_total_price());
+        match verbosity {
+            Verbosity::Brief => {
+                let people = self.meals.len();
+                let noun = if people == 1 { "person" } else { "people" };
+                format!(
+                    "{} order, {} {}, {}",
+                    self.status,
+                    people,
+                    noun,
+                    summarize_meals(self.all_meals(), verbosity, format)
+                )
+            }
+            Verbosity::Normal => format!(
+                "{} order: {}; total {}",
+                self.status,
+                summarize_meals(self.all_meals(), verbosity, format),
+                total
+            ),
+            Verbosity::Detailed => {
+                let mut summary = format!("{} order", self.status);
+                if let Some(restaurant) = &self.restaurant {
+                    summary.push_str(&format!(" at {}", restaurant.get_name()));
+                }
+                summary.push_str(&format!(", total {}", total));
+                let mut users: Vec<&Meals> = self.meals.values().collect();
+                users.sort_by_key(|meals| meals.get_owner_id().get_value());
+                for meals in users {
+                    summary.push_str(&format!(
+                        "\nUser {}: {}",
+                        meals.get_owner_id().get_value(),
+                        meals.summary_in(verbosity, format)
+                    ));
+                }
+                if !self.office_meals.is_empty() {
+                    summary.push_str(&format!(
+                        "\nOffice: {}",
+                        summarize_meals(self.office_meals(), verbosity, format)
+                    ));
+                }
+                if !self.shared_meals.is_empty() {
+                    summary.push_str(&format!(
+                        "\nShared: {}",
+                        summarize_meals(self.shared_meals(), verbosity, format)
+                    ));
+                }
+                summary
+            }
+        }
+    }
+
+    /// Calculates the price of everything ordered at the restaurant, including office and shared meals and the
+    /// delivery fee.
+    pub fn calculate_total_price(&self) -> Money {
+        sum_amounts(
+            [
+                self.calculate_office_price(),
+                self.calculate_shared_price(),
+                self.delivery_fee,
+            ]
+            .iter()
+            .copied()
+            .chain(self.meals.values().map(Meals::calculate_total_price)),
+        )
+    }
+
+    /// Splits the total price without tips into net amounts and VAT by tax rate.
+    ///
+    /// The delivery fee and meals without tax rate are reported as untaxed.
+    pub fn tax_breakdown(&self) -> TaxBreakdown {
+        TaxBreakdown::calculate(
+            self.all_meals()
+                .map(|meal| (meal.get_tax_rate(), meal.get_total_price()))
+                .chain(std::iter::once((None, self.delivery_fee))),
+        )
+    }
+
+    /// The total price rounded up to a multiple of `step`, e.g. to hand the delivery driver full euros.
+    pub fn round_up_to(&self, step: Money) -> Money {
+        self.calculate_total_price().round_up_to(step)
+    }
+
+    /// Sets the deposit charged on top of the price of a meal of anybody, e.g. when the menu doesn't list it.
+    pub fn set_deposit(&mut self, id: &Id<Meal>, deposit: Money) -> Result<&Meal, OrderError> {
+        self.check_modifiable(Modification::Meals)?;
+        self.check_currency(deposit)?;
+        self.find_meal_mut(id)
+            .ok_or(OrderError::MealNotFound)?
+            .set_deposit(deposit);
+        self.audit.record(Mutation::DepositSet {
+            id: id.clone(),
+            deposit,
+        });
+        Ok(self.find_meal_mut(id).expect("Meal was just found"))
+    }
+
+    /// Records that the bottle or can of a meal was returned after delivery and gives the refunded deposit.
+    ///
+    /// The deposit is no longer part of the price, so whoever paid for the meal gets it back as change: the
+    /// participant, all participants for shared meals or the office budget for office meals.
+    pub fn return_deposit(&mut self, id: &Id<Meal>) -> Result<Money, OrderError> {
+        self.check_modifiable(Modification::Payments)?;
+        if self.status != OrderStatus::Delivered {
+            return Err(OrderError::WrongStatus);
+        }
+        let refund = self
+            .find_meal_mut(id)
+            .ok_or(OrderError::MealNotFound)?
+            .return_deposit();
+        self.audit.record(Mutation::DepositReturned(id.clone()));
+        Ok(refund)
+    }
+
+    /// Deposits of all meals not returned yet, included in the total price.
+    pub fn calculate_open_deposit(&self) -> Money {
+        sum_amounts(self.all_meals().map(Meal::get_open_deposit))
+    }
+
+    /// Deposits refunded for returned bottles and cans.
+    pub fn calculate_returned_deposit(&self) -> Money {
+        sum_amounts(
+            self.all_meals()
+                .filter(|meal| meal.is_deposit_returned())
+                .map(Meal::get_deposit),
+        )
+    }
+
+    fn find_meal_mut(&mut self, id: &Id<Meal>) -> Option<&mut Meal> {
+        if let Some(meal) = self.office_meals.get_mut(id) {
+            return Some(meal);
+        }
+        if let Some(meal) = self.shared_meals.get_mut(id) {
+            return Some(meal);
+        }
+        self.meals
+            .values_mut()
+            .find_map(|meals| meals.get_meal_mut(id))
+    }
+
+    /// Calculates the price of the meals paid from the office budget.
+    pub fn calculate_office_price(&self) -> Money {
+        sum_amounts(self.office_meals.values().map(Meal::get_total_price))
+    }
+
+    /// Calculates the price of the meals shared by the participants.
+    pub fn calculate_shared_price(&self) -> Money {
+        sum_amounts(self.shared_meals.values().map(Meal::get_total_price))
+    }
+
+    pub fn calculate_total_tip(&self) -> Money {
+        sum_amounts(self.meals.values().map(Meals::get_tip))
+    }
+
+    /// Breaks down what every participant has to pay, paid and gets back, together with the order-wide totals.
+    pub fn payment_report(&self) -> PaymentReport {
+        let meal_prices = self.meal_prices();
+        let (fee_shares, fee_rounding) = split_fee_with_rounding(
+            self.delivery_fee,
+            self.fee_split,
+            &self.manager_id,
+            &meal_prices,
+        );
+        let (shared_shares, shared_rounding) = split_fee_with_rounding(
+            self.calculate_shared_price(),
+            self.shared_split,
+            &self.manager_id,
+            &meal_prices,
+        );
+        let users = self
+            .meals
+            .values()
+            .map(|meals| {
+                let adjustments = [
+                    (SplitAmount::DeliveryFee, &fee_rounding),
+                    (SplitAmount::SharedMeals, &shared_rounding),
+                ]
+                .iter()
+                .filter_map(|(split, rounding)| {
+                    let amount = rounding.get(&meals.get_owner_id())?;
+                    Some(RoundingAdjustment::new(*split, *amount))
+                })
+                .collect();
+                UserPayment::new(
+                    meals.get_owner_id(),
+                    meals.calculate_total_price(),
+                    Self::fee_share(&fee_shares, meals),
+                    Self::fee_share(&shared_shares, meals),
+                    meals.get_tip(),
+                    meals.get_paid(),
+                )
+                .with_adjustments(adjustments)
+            })
+            .collect();
+        PaymentReport::new(users, self.calculate_office_price())
+    }
+
+    pub fn calculate_total_change(&self) -> Result<Money, NotAllPaidEnoughError> {
+        let mut total_change = Money::zero();
+        let mut underpaid = Money::zero();
+        let mut paid_less: HashSet<Id<User>> = HashSet::new();
+        let fee_shares = self.calculate_fee_shares();
+        let shared_shares = self.calculate_shared_shares();
+        for single_order in self.meals.values() {
+            let share = Self::fee_share(&fee_shares, single_order)
+                + Self::fee_share(&shared_shares, single_order);
+            match single_order.calculate_change_with_fee(share) {
+                Ok(change) => total_change += change,
+                Err(e) => {
+                    paid_less.insert(single_order.get_owner_id());
+                    underpaid += e.get_value()
+                }
+            }
+        }
+        if underpaid.get_total_cents() == 0 {
+            Ok(total_change)
+        } else if total_change > underpaid {
+            Err(NotAllPaidEnoughError::EnoughInTotal {
+                change: total_change - underpaid,
+                paid_less,
+            })
+        } else {
+            Err(NotAllPaidEnoughError::Underpaid {
+                underpaid: underpaid - total_change,
+                paid_less,
+            })
+        }
+    }
+
+    /// The share of the owner of the meals, in shares of the delivery fee or the shared meals.
+    fn fee_share(fee_shares: &HashMap<Id<User>, Money>, meals: &Meals) -> Money {
+        fee_shares
+            .get(&meals.get_owner_id())
+            .copied()
+            .unwrap_or_else(Money::zero)
+    }
+}
+
+/// Adds up amounts of an order, which never mix currencies as every amount goes through `Order::check_currency`.
+///
+/// # Panics
+///
+/// Panics if the amounts are in different currencies anyway, e.g. because of a meal changed around the order.
+pub(crate) fn sum_amounts(amounts: impl IntoIterator<Item = Money>) -> Money {
+    Money::try_sum(amounts).unwrap_or_else(|error| panic!("{}", error))
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.summary(Verbosity::Normal))
+    }
+}
+
+impl ShortCodePrefix for Order {
+    const PREFIX: &'static str = "ORD";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::item::{MenuItem, OptionGroup};
+    use crate::order_model::report::Balance;
+    use crate::user_model::favorites::Favorites;
+    use crate::util::clock::TestClock;
+    use rstest::rstest;
+
+    #[test]
+    fn order_can_be_created() {
+        //Given
+        let user_id = Id::new(0);
+
+        //When
+        let order = Order::new(user_id.clone());
+
+        //Then
+        assert_eq!(order.meals.len(), 1);
+        assert_eq!(order.meals[&user_id], Meals::new(user_id.clone()));
+        assert_eq!(order.status, OrderStatus::Open);
+        assert_eq!(order.manager_id, user_id);
+    }
+
+    #[test]
+    fn user_can_be_added_to_order() {
+        //Given
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+
+        let user_id = Id::new(1);
+
+        //When
+        let meal = order.add_user(user_id.clone()).unwrap();
+
+        //Then
+        assert_eq!(meal, &Meals::new(user_id.clone()));
+        assert_eq!(order.meals.len(), 2);
+        assert_eq!(order.meals[&user_id], Meals::new(user_id));
+        assert_eq!(order.status, OrderStatus::Open);
+        assert_eq!(order.manager_id, manager_id);
+    }
+
+    #[rstest(
+        status,
+        expected,
+        case(OrderStatus::Open, String::from("Open")),
+        case(OrderStatus::Ordering, String::from("Ordering")),
+        case(
+            OrderStatus::Ordered {
+                placed_at: at("2020-05-04T11:45:00Z"),
+                eta: Some(at("2020-05-04T12:15:00Z"))
+            },
+            String::from("Ordered")
+        ),
+        case(OrderStatus::Delivered, String::from("Delivered")),
+        case(OrderStatus::Closed, String::from("Closed")),
+        case(OrderStatus::Cancelled, String::from("Cancelled"))
+    )]
+    fn order_status_is_formatted_correctly(status: OrderStatus, expected: String) {
+        assert_eq!(expected, status.to_string())
+    }
+
+    #[rstest(
+        status,
+        case(OrderStatus::Delivered),
+        case(OrderStatus::Closed),
+        case(OrderStatus::Cancelled)
+    )]
+    fn user_cannot_join_in_status(status: OrderStatus) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.status = status;
+
+        // When:
+        let joined = order.add_user(Id::new(1)).map(|_| ());
+
+        // Then:
+        assert_eq!(joined, Err(OrderError::WrongStatus));
+        assert!(!order.is_participating(&Id::new(1)));
+    }
+
+    #[rstest(
+        meal_id,
+        variety,
+        price,
+        case(String::from("03"), String::from("groß"), Money::new(5, 50)),
+        case(String::from("04"), String::from("riesig"), Money::new(7, 20))
+    )]
+    fn meal_can_be_added_to_order_for_user(meal_id: String, variety: String, price: Money) {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        let user_id = Id::new(1);
+        order.add_user(user_id.clone()).unwrap();
+
+        // When:
+        let meal =
+            order.add_meal_for_user(user_id.clone(), meal_id.clone(), variety.clone(), price);
+
+        // Then:
+        assert_eq!(
+            meal,
+            Ok(&Meal::new(
+                Id::new(0),
+                meal_id.clone(),
+                variety.clone(),
+                price
+            ))
+        );
+        let mut expected_meals = Meals::new(user_id.clone());
+        expected_meals.add_meal(Meal::new(Id::new(0), meal_id, variety, price));
+        assert_eq!(order.get_meals_for_user(user_id), Ok(&expected_meals));
+    }
+
+    #[test]
+    fn meal_cannot_be_added_to_order_for_user_if_user_is_not_participating_in_order() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        let user_id = Id::new(1);
+
+        // When:
+        let meal = order.add_meal_for_user(
+            user_id.clone(),
+            String::from("03"),
+            String::from("groß"),
+            Money::new(5, 50),
+        );
+
+        // Then:
+        assert_eq!(meal, Err(OrderError::UserNotParticipating));
+        assert_eq!(
+            order.get_meals_for_user(user_id),
+            Err(OrderError::UserNotParticipating)
+        );
+    }
+
+    #[test]
+    fn user_not_participating_in_order_has_no_meals() {
+        // Given:
+        let manager_id = Id::new(0);
+        let order = Order::new(manager_id);
+
+        let user_id = Id::new(1);
+
+        // When:
+        let meals = order.get_meals_for_user(user_id);
+
+        // Then:
+        assert_eq!(meals, Err(OrderError::UserNotParticipating));
+    }
+
+    #[test]
+    fn user_participating_in_order_has_meals() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        let user_id = Id::new(1);
+        order.add_user(user_id.clone()).unwrap();
+
+        // When:
+        let meals = order.get_meals_for_user(user_id.clone());
+
+        // Then:
+        assert_eq!(meals, Ok(&Meals::new(user_id)));
+    }
+
+    struct MealsAttributes {
+        meal_price: Vec<Money>,
+        orderer_id: Id<User>,
+        amount: Money,
+    }
+
+    #[rstest(meals_attributes, expected_total,
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
+                    orderer_id: Id::new(1),
+                    amount: Money::zero(),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
+                    orderer_id: Id::new(2),
+                    amount: Money::zero(),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(6, 83)],
+                    orderer_id: Id::new(3),
+                    amount: Money::zero(),
+                },
+            ],
+            Money::new(29, 87)),
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
+                    orderer_id: Id::new(1),
+                    amount: Money::zero(),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(5, 50)],
+                    orderer_id: Id::new(2),
+                    amount: Money::zero(),
+                },
+            ],
+            Money::new(12, 17)),
+    )]
+    fn total_price_is_calculated_correctly(
+        meals_attributes: Vec<MealsAttributes>,
+        expected_total: Money,
+    ) {
+        //Given
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        for attributes in meals_attributes.into_iter() {
+            order.add_user(attributes.orderer_id.clone()).unwrap();
+            for price in attributes.meal_price.iter() {
+                order
+                    .add_meal_for_user(
+                        attributes.orderer_id.clone(),
+                        String::from("XX"),
+                        String::from("something"),
+                        *price,
+                    )
+                    .unwrap();
+            }
+        }
+        //When
+        let calculated_total = order.calculate_total_price();
+        //Then
+        assert_eq!(expected_total, calculated_total);
+    }
+
+    #[rstest(meals_attributes, total_tip,
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(2, 25),
+                },
+                MealsAttributes {
+                    meal_price: vec![],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(5, 50),
+                },
+                MealsAttributes {
+                    meal_price: vec![],
+                    orderer_id: Id::new(3),
+                    amount: Money::new(7, 37),
+                },
+            ],
+            Money::new(15, 12)),
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(2, 25),
+                },
+                MealsAttributes {
+                    meal_price: vec![],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(4, 42),
+                },
+            ],
+            Money::new(6, 67)),
+    )]
+    fn total_tip_is_calculated_correctly(meals_attributes: Vec<MealsAttributes>, total_tip: Money) {
+        //Given
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        for attributes in meals_attributes.into_iter() {
+            order.add_user(attributes.orderer_id.clone()).unwrap();
+            order
+                .set_tip_for_user(attributes.orderer_id.clone(), attributes.amount)
+                .unwrap();
+        }
+
+        //When
+        let calculated_tip = order.calculate_total_tip();
+
+        //Then
+        assert_eq!(total_tip, calculated_tip);
+    }
+
+    #[rstest(meals_attributes, expected_change,
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(17, 00),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(8, 50),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(6, 83)],
+                    orderer_id: Id::new(3),
+                    amount: Money::new(6, 83),
+                },
+            ],
+            Money::new(2, 46),
+        ),
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(8, 25),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(5, 50)],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(5, 50),
+                },
+            ],
+            Money::new(1, 58),
+        ),
+    )]
+    fn all_paid_enough_change_is_calculated_correctly(
+        meals_attributes: Vec<MealsAttributes>,
+        expected_change: Money,
+    ) {
+        //Given
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        for attributes in meals_attributes.into_iter() {
+            order.add_user(attributes.orderer_id.clone()).unwrap();
+            for price in attributes.meal_price.iter() {
+                order
+                    .add_meal_for_user(
+                        attributes.orderer_id.clone(),
+                        String::from("XX"),
+                        String::from("something"),
+                        *price,
+                    )
+                    .unwrap();
+            }
+            order
+                .set_paid_for_user(attributes.orderer_id, attributes.amount)
+                .unwrap();
+        }
+        //When
+        let calculated_change = order.calculate_total_change().unwrap();
+        //Then
+        assert_eq!(expected_change, calculated_change);
+    }
+
+    fn build_paid_less_hash_set(user_ids: Vec<u32>) -> HashSet<Id<User>> {
+        user_ids.into_iter().map(Id::new).collect()
+    }
+
+    #[rstest(meals_attributes, expected_change,
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(17, 00),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(7, 50),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(6, 83)],
+                    orderer_id: Id::new(3),
+                    amount: Money::new(6, 00),
+                },
+            ],
+            NotAllPaidEnoughError::EnoughInTotal{
+                change: Money::new(0, 63),
+                paid_less: build_paid_less_hash_set(vec!(2, 3)),
+            },
+        ),
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(8, 25),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(5, 50)],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(5, 00),
+                },
+            ],
+            NotAllPaidEnoughError::EnoughInTotal{
+                change: Money::new(1, 8),
+                paid_less: build_paid_less_hash_set(vec!(2)),
+            },
+        ),
+    )]
+    fn not_all_paid_enough_change_is_positive(
+        meals_attributes: Vec<MealsAttributes>,
+        expected_change: NotAllPaidEnoughError,
+    ) {
+        //Given
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        for attributes in meals_attributes.into_iter() {
+            order.add_user(attributes.orderer_id.clone()).unwrap();
+            for price in attributes.meal_price.iter() {
+                order
+                    .add_meal_for_user(
+                        attributes.orderer_id.clone(),
+                        String::from("XX"),
+                        String::from("something"),
+                        *price,
+                    )
+                    .unwrap();
+            }
+            order
+                .set_paid_for_user(attributes.orderer_id, attributes.amount)
+                .unwrap();
+        }
+        //When
+        let calculated_change = order.calculate_total_change();
+        //Then
+        assert_eq!(Err(expected_change), calculated_change);
+    }
+
+    #[rstest(meals_attributes, expected_change,
+        case(
+            vec![
+                MealsAttributes {
+                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
+                    orderer_id: Id::new(1),
+                    amount: Money::new(16, 00),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
+                    orderer_id: Id::new(2),
+                    amount: Money::new(7, 50),
+                },
+                MealsAttributes {
+                    meal_price: vec![Money::new(6, 83)],
+                    orderer_id: Id::new(3),
+                    amount: Money::new(6, 00),
                 },
             ],
-            NotAllPaidEnoughError::EnoughInTotal{
-                change: Money::new(0, 63),
+            NotAllPaidEnoughError::Underpaid{
+                underpaid: Money::new(0, 37),
                 paid_less: build_paid_less_hash_set(vec!(2, 3)),
             },
         ),
@@ -540,7 +2968,7 @@ mod tests {
                 MealsAttributes {
                     meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
                     orderer_id: Id::new(1),
-                    amount: Money::new(8, 25),
+                    amount: Money::new(6, 25),
                 },
                 MealsAttributes {
                     meal_price: vec![Money::new(5, 50)],
@@ -548,114 +2976,2278 @@ mod tests {
                     amount: Money::new(5, 00),
                 },
             ],
-            NotAllPaidEnoughError::EnoughInTotal{
-                change: Money::new(1, 08),
-                paid_less: build_paid_less_hash_set(vec!(2)),
+            NotAllPaidEnoughError::Underpaid{
+                underpaid: Money::new(0, 92),
+                paid_less: build_paid_less_hash_set(vec!(1, 2)),
             },
         ),
     )]
-    fn not_all_paid_enough_change_is_positive(
-        meals_attributes: Vec<MealsAttributes>,
-        expected_change: NotAllPaidEnoughError,
+    fn not_all_paid_enough_change_is_negative(
+        meals_attributes: Vec<MealsAttributes>,
+        expected_change: NotAllPaidEnoughError,
+    ) {
+        //Given
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id);
+
+        for attributes in meals_attributes.into_iter() {
+            order.add_user(attributes.orderer_id.clone()).unwrap();
+            for price in attributes.meal_price.iter() {
+                order
+                    .add_meal_for_user(
+                        attributes.orderer_id.clone(),
+                        String::from("XX"),
+                        String::from("something"),
+                        *price,
+                    )
+                    .unwrap();
+            }
+            order
+                .set_paid_for_user(attributes.orderer_id, attributes.amount)
+                .unwrap();
+        }
+        //When
+        let calculated_change = order.calculate_total_change();
+        //Then
+        assert_eq!(Err(expected_change), calculated_change);
+    }
+
+    #[test]
+    fn order_goes_through_all_status() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let ordering = order.start_ordering();
+        let ordered = order.mark_ordered(None);
+        let delivered = order.mark_delivered(SystemTime::UNIX_EPOCH);
+
+        // Then:
+        assert_eq!(ordering, Ok(()));
+        assert_eq!(ordered, Ok(()));
+        assert_eq!(delivered, Ok(()));
+        assert_eq!(order.get_status(), &OrderStatus::Delivered);
+    }
+
+    #[test]
+    fn order_cannot_skip_status() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let ordered = order.mark_ordered(None);
+        let delivered = order.mark_delivered(SystemTime::UNIX_EPOCH);
+
+        // Then:
+        assert_eq!(ordered, Err(OrderError::WrongStatus));
+        assert_eq!(delivered, Err(OrderError::WrongStatus));
+        assert_eq!(order.get_status(), &OrderStatus::Open);
+    }
+
+    fn delivered_order() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.start_ordering().unwrap();
+        order.mark_ordered(None).unwrap();
+        order.mark_delivered(SystemTime::UNIX_EPOCH).unwrap();
+        order
+    }
+
+    #[test]
+    fn payments_but_no_meals_can_be_changed_after_delivery() {
+        // Given:
+        let mut order = delivered_order();
+
+        // When:
+        let meal = order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .map(|meal| meal.get_id());
+        let paid = order.set_paid_for_user(Id::new(0), Money::new(5, 50));
+
+        // Then:
+        assert_eq!(meal, Err(OrderError::WrongStatus));
+        assert_eq!(paid, Ok(()));
+    }
+
+    #[rstest(
+        elapsed,
+        closed,
+        case(Duration::from_secs(59), false),
+        case(Duration::from_secs(60), true)
+    )]
+    fn order_is_closed_after_grace_period(elapsed: Duration, closed: bool) {
+        // Given:
+        let mut order = delivered_order();
+        order.set_grace_period(Duration::from_secs(60));
+
+        // When:
+        let is_closed = order.close_if_expired(SystemTime::UNIX_EPOCH + elapsed);
+
+        // Then:
+        assert_eq!(is_closed, closed);
+        assert_eq!(
+            order.check_modifiable(Modification::Payments).is_ok(),
+            !closed
+        );
+    }
+
+    #[test]
+    fn order_is_not_closed_before_delivery() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let is_closed = order.close_if_expired(SystemTime::now() + DEFAULT_GRACE_PERIOD);
+
+        // Then:
+        assert!(!is_closed);
+        assert_eq!(order.get_status(), &OrderStatus::Open);
+    }
+
+    #[test]
+    fn closed_order_cannot_be_changed() {
+        // Given:
+        let mut order = delivered_order();
+        order.close_if_expired(SystemTime::UNIX_EPOCH + DEFAULT_GRACE_PERIOD);
+
+        // When:
+        let paid = order.set_paid_for_user(Id::new(0), Money::new(5, 50));
+        let claimed =
+            order.claim_payment_for_user(Id::new(0), Money::new(5, 50), String::from("PayPal"));
+        let undone = order.undo_for_user(Id::new(0), 1);
+
+        // Then:
+        assert_eq!(order.get_status(), &OrderStatus::Closed);
+        assert_eq!(paid, Err(OrderError::WrongStatus));
+        assert_eq!(claimed, Err(OrderError::WrongStatus));
+        assert_eq!(undone, Err(OrderError::WrongStatus));
+        assert_eq!(
+            order.add_user(Id::new(1)).map(|_| ()),
+            Err(OrderError::WrongStatus)
+        );
+        assert!(!order.is_participating(&Id::new(1)));
+    }
+
+    #[test]
+    fn removed_meal_cannot_be_restored_after_delivery() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 0),
+            )
+            .unwrap();
+        order.start_ordering().unwrap();
+        order.mark_ordered(None).unwrap();
+        order.remove_meal_for_user(Id::new(0), Id::new(0)).unwrap();
+        order
+            .set_paid_for_user(Id::new(0), Money::new(5, 0))
+            .unwrap();
+        order.mark_delivered(SystemTime::UNIX_EPOCH).unwrap();
+
+        // When:
+        let payment_undone = order.undo_for_user(Id::new(0), 1);
+        let meal_undone = order.undo_for_user(Id::new(0), 1);
+
+        // Then:
+        assert_eq!(payment_undone, Ok(1));
+        assert_eq!(meal_undone, Err(OrderError::WrongStatus));
+        assert_eq!(order.calculate_total_price(), Money::zero());
+        assert_eq!(
+            order.get_user_meals(&Id::new(0)).unwrap().get_paid(),
+            Money::zero()
+        );
+    }
+
+    #[test]
+    fn user_cannot_be_added_twice() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 0),
+            )
+            .unwrap();
+
+        // When:
+        let added = order.add_user(Id::new(1)).map(|_| ());
+
+        // Then:
+        assert_eq!(added, Err(OrderError::UserAlreadyParticipating));
+        assert_eq!(order.calculate_total_price(), Money::new(5, 0));
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[rstest(
+        now,
+        past,
+        seconds_left,
+        case("2020-05-04T11:29:30Z", false, 30),
+        case("2020-05-04T11:30:00Z", true, 0),
+        case("2020-05-04T12:00:00Z", true, 0)
+    )]
+    fn deadline_counts_down(now: &str, past: bool, seconds_left: i64) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .set_deadline(Some(at("2020-05-04T11:30:00Z")))
+            .unwrap();
+
+        // When:
+        let is_past = order.is_past_deadline(at(now));
+        let left = order.time_until_deadline(at(now));
+
+        // Then:
+        assert_eq!(is_past, past);
+        assert_eq!(left, Some(chrono::Duration::seconds(seconds_left)));
+    }
+
+    #[test]
+    fn ordering_starts_automatically_after_deadline() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .set_deadline(Some(at("2020-05-04T11:30:00Z")))
+            .unwrap();
+
+        // When:
+        let before = order.start_ordering_if_past_deadline(at("2020-05-04T11:29:59Z"));
+        let after = order.start_ordering_if_past_deadline(at("2020-05-04T11:30:00Z"));
+        let again = order.start_ordering_if_past_deadline(at("2020-05-04T11:31:00Z"));
+
+        // Then:
+        assert!(!before);
+        assert!(after);
+        assert!(!again);
+        assert_eq!(order.get_status(), &OrderStatus::Ordering);
+    }
+
+    #[test]
+    fn order_without_deadline_stays_open() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let started = order.start_ordering_if_past_deadline(at("2020-05-04T11:30:00Z"));
+
+        // Then:
+        assert!(!started);
+        assert_eq!(order.time_until_deadline(at("2020-05-04T11:30:00Z")), None);
+        assert_eq!(order.get_status(), &OrderStatus::Open);
+    }
+
+    #[test]
+    fn healthy_order_has_no_integrity_issues() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+
+        // When:
+        let issues = order.check_integrity();
+
+        // Then:
+        assert_eq!(issues, vec![]);
+    }
+
+    #[test]
+    fn broken_order_has_integrity_issues() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        let meal = Meal::new(
+            Id::new(4),
+            String::from("03"),
+            String::from("groß"),
+            Money::new(5, 50),
+        );
+        order.meals.insert(Id::new(1), Meals::new(Id::new(2)));
+        order
+            .meals
+            .get_mut(&Id::new(1))
+            .unwrap()
+            .add_meal(meal.clone());
+        order.office_meals.insert(Id::new(4), meal);
+        order.meals.remove(&Id::new(0));
+
+        // When:
+        let issues = order.check_integrity();
+
+        // Then:
+        assert_eq!(
+            issues,
+            vec![
+                IntegrityIssue::OwnerMismatch {
+                    user_id: Id::new(1),
+                    owner_id: Id::new(2)
+                },
+                IntegrityIssue::DuplicateMealId(Id::new(4)),
+                IntegrityIssue::ManagerNotParticipating(Id::new(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn money_format_falls_back_to_defaults() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.set_locale(Some(Locale::DeCh));
+        let defaults = MoneyFormat::new(Currency::Chf, Locale::DeDe);
+
+        // When:
+        let format = order.get_money_format(defaults);
+
+        // Then:
+        assert_eq!(format, MoneyFormat::new(Currency::Chf, Locale::DeCh));
+    }
+
+    #[test]
+    fn currency_cannot_change_after_meals_were_added() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+
+        // When:
+        let result = order.set_currency(Some(Currency::Chf));
+
+        // Then:
+        assert_eq!(result, Err(OrderError::WrongStatus));
+        assert_eq!(order.get_currency(), None);
+    }
+
+    #[rstest(currency, case(None), case(Some(Currency::Eur)))]
+    fn amounts_in_other_currency_are_refused(currency: Option<Currency>) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.set_currency(currency).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50).with_currency(Currency::Eur),
+            )
+            .unwrap();
+        let francs = Money::new(4, 0).with_currency(Currency::Chf);
+
+        // When:
+        let meal = order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("05"),
+                String::from("klein"),
+                francs,
+            )
+            .map(|meal| meal.get_id());
+        let paid = order.set_paid_for_user(Id::new(0), francs);
+        let fee = order.set_delivery_fee(francs, FeeSplitStrategy::ManagerPays);
+
+        // Then:
+        let mismatch = Money::zero()
+            .with_currency(Currency::Eur)
+            .try_add(francs)
+            .unwrap_err();
+        assert_eq!(meal, Err(OrderError::Currency(mismatch)));
+        assert_eq!(paid, Err(OrderError::Currency(mismatch)));
+        assert_eq!(fee, Err(OrderError::Currency(mismatch)));
+        assert_eq!(
+            order.calculate_total_price(),
+            Money::new(5, 50).with_currency(Currency::Eur)
+        );
+        assert_eq!(
+            order.set_paid_for_user(Id::new(0), Money::new(5, 50)),
+            Ok(())
+        );
+    }
+
+    fn order_with_meal() -> (Order, Id<Meal>) {
+        let mut order = Order::new(Id::new(0));
+        let id = order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap()
+            .get_id();
+        (order, id)
+    }
+
+    #[test]
+    fn meal_can_be_updated_while_open() {
+        // Given:
+        let (mut order, id) = order_with_meal();
+
+        // When:
+        let meal = order
+            .update_meal_for_user(
+                Id::new(0),
+                id,
+                String::from("12"),
+                String::from("klein"),
+                Money::new(4, 0),
+            )
+            .cloned();
+
+        // Then:
+        let meal = meal.unwrap();
+        assert_eq!(meal.get_meal_id(), "12");
+        assert_eq!(meal.get_variety(), "klein");
+        assert_eq!(meal.get_price(), Money::new(4, 0));
+        assert_eq!(order.calculate_total_price(), Money::new(4, 0));
+    }
+
+    #[rstest(
+        user_id,
+        meal_id,
+        start_ordering,
+        expected,
+        case(0, 0, true, OrderError::WrongStatus),
+        case(1, 0, false, OrderError::UserNotParticipating),
+        case(0, 5, false, OrderError::MealNotFound)
+    )]
+    fn meal_cannot_be_updated(
+        user_id: u32,
+        meal_id: u32,
+        start_ordering: bool,
+        expected: OrderError,
+    ) {
+        // Given:
+        let (mut order, _) = order_with_meal();
+        if start_ordering {
+            order.start_ordering().unwrap();
+        }
+
+        // When:
+        let result = order
+            .update_meal_for_user(
+                Id::new(user_id),
+                Id::new(meal_id),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(9, 0),
+            )
+            .map(|meal| meal.get_id());
+
+        // Then:
+        assert_eq!(result, Err(expected));
+        assert_eq!(order.calculate_total_price(), Money::new(5, 50));
+    }
+
+    #[test]
+    fn preparations_are_validated_against_menu_and_can_be_undone() {
+        // Given:
+        let (mut order, id) = order_with_meal();
+        let mut menu = Menu::new(String::from("Pizzeria"));
+        menu.offer_preparation(Preparation::WellDone);
+        order.set_menu(Arc::new(menu));
+        let well_done: BTreeSet<Preparation> = vec![Preparation::WellDone].into_iter().collect();
+        let gluten_free: BTreeSet<Preparation> =
+            vec![Preparation::GlutenFreeBase].into_iter().collect();
+
+        // When:
+        let offered = order
+            .set_preparations_for_user(Id::new(0), id.clone(), well_done.clone())
+            .map(|meal| meal.get_preparations().clone());
+        let not_offered = order
+            .set_preparations_for_user(Id::new(0), id.clone(), gluten_free)
+            .map(|meal| meal.get_preparations().clone());
+        let undone = order.undo_for_user(Id::new(0), 1);
+
+        // Then:
+        assert_eq!(offered, Ok(well_done));
+        assert_eq!(
+            not_offered,
+            Err(OrderError::Menu(MenuError::PreparationNotOffered(
+                Preparation::GlutenFreeBase
+            )))
+        );
+        assert_eq!(undone, Ok(1));
+        assert!(order
+            .all_meals()
+            .next()
+            .unwrap()
+            .get_preparations()
+            .is_empty());
+        assert_eq!(order.calculate_total_price(), Money::new(5, 50));
+    }
+
+    #[test]
+    fn note_is_replayed_and_can_be_undone() {
+        // Given:
+        let (mut order, id) = order_with_meal();
+        order
+            .set_note_for_user(
+                Id::new(0),
+                id.clone(),
+                Some(String::from("cut into 8 slices")),
+            )
+            .unwrap();
+        let replayed = Order::replay(order.history()).unwrap();
+
+        // When:
+        let undone = order.undo_for_user(Id::new(0), 1);
+
+        // Then:
+        assert_eq!(
+            replayed.all_meals().next().unwrap().get_note(),
+            Some(&String::from("cut into 8 slices"))
+        );
+        assert_eq!(undone, Ok(1));
+        assert_eq!(order.all_meals().next().unwrap().get_note(), None);
+    }
+
+    #[rstest(
+        actor,
+        permission,
+        expected,
+        case(0, Permission::ChangeStatus, Ok(())),
+        case(1, Permission::ChangeStatus, Err(OrderError::NotAuthorized)),
+        case(0, Permission::RemoveUser(Id::new(1)), Ok(())),
+        case(1, Permission::RemoveUser(Id::new(1)), Ok(())),
+        case(2, Permission::RemoveUser(Id::new(1)), Err(OrderError::NotAuthorized)),
+        case(1, Permission::ModifyMeals(Id::new(1)), Ok(())),
+        case(0, Permission::ModifyMeals(Id::new(1)), Err(OrderError::NotAuthorized))
+    )]
+    fn permission_depends_on_acting_user(
+        actor: u32,
+        permission: Permission,
+        expected: Result<(), OrderError>,
+    ) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order.add_user(Id::new(2)).unwrap();
+
+        // When:
+        let result = order.check_authorized(&Id::new(actor), &permission);
+
+        // Then:
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn participant_cannot_change_status_or_meals_of_others() {
+        // Given:
+        let (mut order, id) = order_with_meal();
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let started = order.start_ordering_as(&Id::new(1));
+        let removed = order.remove_meal_as(&Id::new(1), Id::new(0), id.clone());
+        let special =
+            order.add_special_as(&Id::new(1), Id::new(0), id, String::from("Salami"), None);
+        let kicked = order.remove_user_as(&Id::new(1), Id::new(0));
+
+        // Then:
+        assert_eq!(started, Err(OrderError::NotAuthorized));
+        assert_eq!(removed, Err(OrderError::NotAuthorized));
+        assert_eq!(special, Err(OrderError::NotAuthorized));
+        assert_eq!(kicked.map(|_| ()), Err(OrderError::NotAuthorized));
+        assert_eq!(order.get_status(), &OrderStatus::Open);
+        assert_eq!(order.all_meals().count(), 1);
+    }
+
+    #[test]
+    fn manager_changes_status_and_users_change_own_meals() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let meal = order
+            .add_meal_as(
+                &Id::new(1),
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .map(|meal| meal.get_id());
+        let started = order.start_ordering_as(&Id::new(0));
+
+        // Then:
+        assert_eq!(meal, Ok(Id::new(0)));
+        assert_eq!(started, Ok(()));
+        assert_eq!(order.get_status(), &OrderStatus::Ordering);
+    }
+
+    #[test]
+    fn cancelled_order_cannot_be_changed() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let cancelled = order.cancel();
+        let paid = order.set_paid_for_user(Id::new(0), Money::new(5, 50));
+        let meal = order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 5))
+            .map(|meal| meal.get_id());
+        let joined = order.add_user(Id::new(1)).map(|_| ());
+
+        // Then:
+        assert_eq!(cancelled, Ok(()));
+        assert_eq!(order.get_status(), &OrderStatus::Cancelled);
+        assert_eq!(paid, Err(OrderError::WrongStatus));
+        assert_eq!(meal, Err(OrderError::WrongStatus));
+        assert_eq!(joined, Err(OrderError::WrongStatus));
+        assert!(!order.is_participating(&Id::new(1)));
+        assert_eq!(order.cancel(), Err(OrderError::WrongStatus));
+    }
+
+    #[test]
+    fn delivered_order_cannot_be_cancelled() {
+        // Given:
+        let mut order = delivered_order();
+
+        // When:
+        let cancelled = order.cancel();
+
+        // Then:
+        assert_eq!(cancelled, Err(OrderError::WrongStatus));
+        assert_eq!(order.get_status(), &OrderStatus::Delivered);
+    }
+
+    #[test]
+    fn removed_user_gets_refund() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .set_paid_for_user(Id::new(1), Money::new(6, 0))
+            .unwrap();
+
+        // When:
+        let removed = order.remove_user(Id::new(1)).unwrap();
+
+        // Then:
+        assert_eq!(removed.refund, Money::new(6, 0));
+        assert_eq!(removed.meals.get_owner_id(), Id::new(1));
+        assert_eq!(removed.meals.meals().count(), 1);
+        assert!(!order.is_participating(&Id::new(1)));
+        assert_eq!(order.calculate_total_price(), Money::zero());
+    }
+
+    #[rstest(
+        user_id,
+        expected,
+        case(Id::new(0), OrderError::ManagerCannotLeave),
+        case(Id::new(2), OrderError::UserNotParticipating)
+    )]
+    fn user_cannot_be_removed(user_id: Id<User>, expected: OrderError) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let removed = order.remove_user(user_id);
+
+        // Then:
+        assert_eq!(removed, Err(expected));
+    }
+
+    #[test]
+    fn meal_can_be_undone_and_redone_for_user() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order
+            .add_meal_for_user(
+                manager_id.clone(),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+
+        // When:
+        let undone = order.undo_for_user(manager_id.clone(), 1);
+
+        // Then:
+        assert_eq!(undone, Ok(1));
+        assert_eq!(order.calculate_total_price(), Money::zero());
+        assert_eq!(order.redo_for_user(manager_id), Ok(true));
+        assert_eq!(order.calculate_total_price(), Money::new(5, 50));
+    }
+
+    #[test]
+    fn undo_for_user_not_participating_fails() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let undone = order.undo_for_user(Id::new(1), 1);
+        let redone = order.redo_for_user(Id::new(1));
+
+        // Then:
+        assert_eq!(undone, Err(OrderError::UserNotParticipating));
+        assert_eq!(redone, Err(OrderError::UserNotParticipating));
+    }
+
+    #[test]
+    fn paid_tip_and_readiness_can_be_set_for_user() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+
+        // When:
+        let paid = order.set_paid_for_user(manager_id.clone(), Money::new(10, 0));
+        let tip = order.set_tip_for_user(manager_id.clone(), Money::new(1, 0));
+        let ready = order.set_ready_for_user(manager_id.clone(), true);
+
+        // Then:
+        assert_eq!(paid, Ok(()));
+        assert_eq!(tip, Ok(()));
+        assert_eq!(ready, Ok(()));
+        assert_eq!(order.calculate_total_tip(), Money::new(1, 0));
+        assert!(order.get_meals_for_user(manager_id).unwrap().is_ready());
+    }
+
+    #[test]
+    fn paid_tip_and_readiness_cannot_be_set_for_user_not_participating() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let paid = order.set_paid_for_user(Id::new(1), Money::new(10, 0));
+        let tip = order.set_tip_for_user(Id::new(1), Money::new(1, 0));
+        let ready = order.set_ready_for_user(Id::new(1), true);
+
+        // Then:
+        assert_eq!(paid, Err(OrderError::UserNotParticipating));
+        assert_eq!(tip, Err(OrderError::UserNotParticipating));
+        assert_eq!(ready, Err(OrderError::UserNotParticipating));
+        assert!(!order.is_participating(&Id::new(1)));
+    }
+
+    #[rstest(
+        now,
+        seconds_left,
+        case("2020-05-04T12:00:00Z", 900),
+        case("2020-05-04T12:30:00Z", 0)
+    )]
+    fn delivery_counts_down_to_changed_eta(now: &str, seconds_left: i64) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.start_ordering().unwrap();
+        order
+            .mark_ordered(Some(at("2020-05-04T12:45:00Z")))
+            .unwrap();
+
+        // When:
+        let changed = order.set_eta(Some(at("2020-05-04T12:15:00Z")));
+
+        // Then:
+        assert_eq!(changed, Ok(()));
+        assert_eq!(order.get_eta(), Some(at("2020-05-04T12:15:00Z")));
+        assert_eq!(
+            order.time_until_delivery(at(now)),
+            Some(chrono::Duration::seconds(seconds_left))
+        );
+        assert_eq!(
+            Order::replay(order.history()).map(|replayed| replayed.get_eta()),
+            Ok(order.get_eta())
+        );
+    }
+
+    #[test]
+    fn eta_cannot_be_set_before_ordered() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let changed = order.set_eta(Some(at("2020-05-04T12:15:00Z")));
+
+        // Then:
+        assert_eq!(changed, Err(OrderError::WrongStatus));
+        assert_eq!(order.time_until_delivery(at("2020-05-04T12:00:00Z")), None);
+    }
+
+    #[test]
+    fn ordering_waits_for_participants_if_readiness_is_required() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order.add_user(Id::new(2)).unwrap();
+        order.set_ready_required(true).unwrap();
+        order.set_ready_for_user(Id::new(1), true).unwrap();
+
+        // When:
+        let started = order.start_ordering();
+
+        // Then:
+        assert_eq!(
+            started,
+            Err(OrderError::NotReady(vec![Id::new(0), Id::new(2)]))
+        );
+        assert_eq!(started.unwrap_err().to_string(), "users 0, 2 are not ready");
+        assert!(!order.all_ready());
+        assert_eq!(*order.get_status(), OrderStatus::Open);
+    }
+
+    #[test]
+    fn ordering_starts_once_all_participants_are_ready() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order.set_ready_required(true).unwrap();
+        order.set_ready_for_user(Id::new(0), true).unwrap();
+        order.set_ready_for_user(Id::new(1), true).unwrap();
+
+        // When:
+        let started = order.start_ordering();
+
+        // Then:
+        assert_eq!(started, Ok(()));
+        assert!(order.all_ready());
+        assert_eq!(*order.get_status(), OrderStatus::Ordering);
+    }
+
+    #[test]
+    fn ordering_does_not_wait_for_readiness_by_default() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let started = order.start_ordering();
+
+        // Then:
+        assert_eq!(started, Ok(()));
+        assert_eq!(order.get_unready_users(), vec![Id::new(0), Id::new(1)]);
+    }
+
+    #[test]
+    fn deadline_starts_ordering_without_waiting_for_readiness() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        let deadline = at("2020-05-04T11:30:00Z");
+        order.set_deadline(Some(deadline)).unwrap();
+        order.set_ready_required(true).unwrap();
+
+        // When:
+        let started = order.start_ordering_if_past_deadline(deadline);
+
+        // Then:
+        assert!(started);
+        assert_eq!(*order.get_status(), OrderStatus::Ordering);
+    }
+
+    #[test]
+    fn payment_report_contains_every_user() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .set_paid_for_user(Id::new(1), Money::new(5, 0))
+            .unwrap();
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+
+        // When:
+        let report = order.payment_report();
+
+        // Then:
+        assert_eq!(
+            report.users(),
+            &[
+                UserPayment::new(
+                    manager_id,
+                    Money::zero(),
+                    Money::zero(),
+                    Money::zero(),
+                    Money::zero(),
+                    Money::zero()
+                ),
+                UserPayment::new(
+                    Id::new(1),
+                    Money::new(5, 50),
+                    Money::zero(),
+                    Money::zero(),
+                    Money::zero(),
+                    Money::new(5, 0)
+                ),
+            ]
+        );
+        assert_eq!(report.get_total_price(), order.calculate_total_price());
+        assert_eq!(report.get_total_owed(), Money::new(0, 50));
+    }
+
+    #[test]
+    fn rounding_of_split_amounts_is_recorded_in_payment_report() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        for user_id in 0..3 {
+            if user_id > 0 {
+                order.add_user(Id::new(user_id)).unwrap();
+            }
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        order
+            .set_delivery_fee(Money::new(2, 0), FeeSplitStrategy::Equal)
+            .unwrap();
+        order
+            .add_shared_meal(String::from("90"), String::from("Cola"), Money::new(4, 0))
+            .unwrap();
+
+        // When:
+        let report = order.payment_report();
+
+        // Then:
+        let adjustments = |user_id: u32| report.get_user(&Id::new(user_id)).unwrap().adjustments();
+        assert_eq!(
+            adjustments(0),
+            &[
+                RoundingAdjustment::new(SplitAmount::DeliveryFee, Money::new(0, 1)),
+                RoundingAdjustment::new(SplitAmount::SharedMeals, Money::new(0, 1)),
+            ]
+        );
+        assert_eq!(
+            adjustments(1),
+            &[RoundingAdjustment::new(
+                SplitAmount::DeliveryFee,
+                Money::new(0, 1)
+            )]
+        );
+        assert!(adjustments(2).is_empty());
+        assert_eq!(report.get_total_adjustment(), Money::new(0, 3));
+        assert!(report.adds_up_to(order.calculate_total_price()));
+    }
+
+    #[test]
+    fn delivery_fee_is_part_of_price_and_settlement() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.add_user(Id::new(1)).unwrap();
+        for user_id in 0..2 {
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+            order
+                .set_paid_for_user(Id::new(user_id), Money::new(6, 0))
+                .unwrap();
+        }
+
+        // When:
+        order
+            .set_delivery_fee(Money::new(2, 0), FeeSplitStrategy::Equal)
+            .unwrap();
+
+        // Then:
+        assert_eq!(order.calculate_total_price(), Money::new(13, 0));
+        assert_eq!(
+            order.calculate_total_change(),
+            Err(NotAllPaidEnoughError::Underpaid {
+                underpaid: Money::new(1, 0),
+                paid_less: build_paid_less_hash_set(vec![0, 1]),
+            })
+        );
+        assert_eq!(
+            order
+                .payment_report()
+                .get_user(&Id::new(1))
+                .unwrap()
+                .get_fee_share(),
+            Money::new(1, 0)
+        );
+        assert_eq!(order.users_to_remind().len(), 2);
+    }
+
+    #[test]
+    fn delivery_fee_paid_by_manager_is_settled_by_manager() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .set_paid_for_user(Id::new(1), Money::new(5, 50))
+            .unwrap();
+
+        // When:
+        order
+            .set_delivery_fee(Money::new(2, 0), FeeSplitStrategy::ManagerPays)
+            .unwrap();
+
+        // Then:
+        assert_eq!(order.users_to_remind(), build_paid_less_hash_set(vec![0]));
+    }
+
+    #[test]
+    fn placing_order_invalidates_undo() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order
+            .add_meal_for_user(
+                manager_id.clone(),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order.start_ordering().unwrap();
+
+        // When:
+        order.mark_ordered(None).unwrap();
+
+        // Then:
+        assert_eq!(order.undo_for_user(manager_id, 1), Ok(0));
+        assert_eq!(order.calculate_total_price(), Money::new(5, 50));
+    }
+
+    #[test]
+    fn users_with_pending_payments_are_not_reminded() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        for user in 1..=2 {
+            order.add_user(Id::new(user)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(user),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+
+        // When:
+        order
+            .claim_payment_for_user(Id::new(1), Money::new(5, 50), String::from("PayPal"))
+            .unwrap();
+
+        // Then:
+        assert_eq!(order.users_to_remind(), build_paid_less_hash_set(vec![2]));
+    }
+
+    #[test]
+    fn confirmed_payment_counts_as_paid() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order
+            .add_meal_for_user(
+                manager_id.clone(),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .claim_payment_for_user(manager_id.clone(), Money::new(6, 0), String::from("Bar"))
+            .unwrap();
+
+        // When:
+        let confirmed = order.confirm_payment_for_user(manager_id);
+
+        // Then:
+        assert_eq!(confirmed, Ok(Money::new(6, 0)));
+        assert_eq!(order.calculate_total_change(), Ok(Money::new(0, 50)));
+        assert!(order.users_to_remind().is_empty());
+    }
+
+    #[test]
+    fn disputed_payment_is_reminded() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order
+            .add_meal_for_user(
+                manager_id.clone(),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .claim_payment_for_user(
+                manager_id.clone(),
+                Money::new(5, 50),
+                String::from("PayPal"),
+            )
+            .unwrap();
+
+        // When:
+        let disputed = order.dispute_payment_for_user(manager_id);
+
+        // Then:
+        assert_eq!(disputed, Ok(()));
+        assert_eq!(order.users_to_remind(), build_paid_less_hash_set(vec![0]));
+    }
+
+    #[rstest(
+        user_id,
+        expected,
+        case(Id::new(0), OrderError::PaymentNotPending),
+        case(Id::new(1), OrderError::UserNotParticipating)
+    )]
+    fn payment_cannot_be_resolved_without_claim(user_id: Id<User>, expected: OrderError) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+
+        // When:
+        let confirmed = order.confirm_payment_for_user(user_id.clone());
+        let disputed = order.dispute_payment_for_user(user_id);
+
+        // Then:
+        assert_eq!(confirmed, Err(expected.clone()));
+        assert_eq!(disputed, Err(expected));
+    }
+
+    fn luigis_menu() -> Arc<Menu> {
+        let mut item = MenuItem::new(String::from("03"), String::from("Salami"));
+        item.set_price(String::from("groß"), Money::new(5, 50));
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+        menu.add_item(item);
+        Arc::new(menu)
+    }
+
+    #[test]
+    fn order_can_be_cloned_for_other_restaurant() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(luigis_menu());
+        order.add_user(Id::new(1)).unwrap();
+        let meal = order
+            .add_menu_meal_for_user(Id::new(1), String::from("03"), String::from("groß"), &[])
+            .unwrap()
+            .get_id();
+        order
+            .add_special_for_user(Id::new(1), meal, String::from("Käserand"), None)
+            .unwrap();
+        order
+            .set_paid_for_user(Id::new(1), Money::new(6, 0))
+            .unwrap();
+        order
+            .add_office_meal(String::from("03"), String::from("groß"), Money::new(5, 50))
+            .unwrap();
+        let mut item = MenuItem::new(String::from("12"), String::from("Salami"));
+        item.set_price(String::from("Groß"), Money::new(6, 50));
+        let mut marios_menu = Menu::new(String::from("Pizzeria Mario"));
+        marios_menu.add_item(item);
+
+        // When:
+        let cloned = order.clone_for_menu(Id::new(1), Arc::new(marios_menu));
+
+        // Then:
+        let clone = cloned.order;
+        assert!(cloned.unmatched.is_empty());
+        assert!(clone.is_participating(&Id::new(0)));
+        let meals = clone.get_meals_for_user(Id::new(1)).unwrap();
+        assert_eq!(meals.get_paid(), Money::zero());
+        let meal = meals.meals().next().unwrap();
+        assert_eq!(meal.get_meal_id(), "12");
+        assert_eq!(meal.get_variety(), "Groß");
+        assert_eq!(meal.get_price(), Money::new(6, 50));
+        assert_eq!(
+            meal.specials().next().unwrap().get_description(),
+            "Käserand"
+        );
+        assert_eq!(clone.calculate_office_price(), Money::new(6, 50));
+    }
+
+    #[test]
+    fn order_can_be_cloned_from_previous_order() {
+        // Given:
+        let mut previous = Order::new(Id::new(0));
+        previous.set_menu(luigis_menu());
+        previous.add_user(Id::new(1)).unwrap();
+        let meal = previous
+            .add_menu_meal_for_user(Id::new(1), String::from("03"), String::from("groß"), &[])
+            .unwrap()
+            .get_id();
+        previous
+            .add_special_for_user(Id::new(1), meal, String::from("Käserand"), None)
+            .unwrap();
+        previous
+            .set_paid_for_user(Id::new(1), Money::new(6, 0))
+            .unwrap();
+        previous.start_ordering().unwrap();
+
+        // When:
+        let clone = Order::clone_from(&previous, Id::new(1));
+
+        // Then:
+        assert_eq!(clone.get_status(), &OrderStatus::Open);
+        assert_eq!(clone.get_manager_id(), Id::new(1));
+        assert!(clone.is_participating(&Id::new(0)));
+        assert!(clone.get_menu().is_some());
+        let meals = clone.get_user_meals(&Id::new(1)).unwrap();
+        assert_eq!(meals.get_paid(), Money::zero());
+        let meal = meals.meals().next().unwrap();
+        assert_eq!(meal.get_price(), Money::new(5, 50));
+        assert_eq!(
+            meal.specials().next().unwrap().get_description(),
+            "Käserand"
+        );
+    }
+
+    #[test]
+    fn meals_missing_on_other_menu_are_reported() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(luigis_menu());
+        order
+            .add_menu_meal_for_user(Id::new(0), String::from("03"), String::from("groß"), &[])
+            .unwrap();
+
+        // When:
+        let cloned = order.clone_for_menu(
+            Id::new(0),
+            Arc::new(Menu::new(String::from("Pizzeria Mario"))),
+        );
+
+        // Then:
+        assert_eq!(
+            cloned.unmatched,
+            vec![UnmatchedMeal {
+                user_id: Some(Id::new(0)),
+                meal_id: String::from("03"),
+                variety: String::from("groß"),
+            }]
+        );
+        assert_eq!(cloned.order.calculate_total_price(), Money::zero());
+    }
+
+    fn luigis_regular() -> Favorites {
+        let mut last = Order::new(Id::new(1));
+        let meal = last
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 0),
+            )
+            .unwrap()
+            .get_id();
+        last.add_special_for_user(Id::new(1), meal, String::from("Käserand"), None)
+            .unwrap();
+        let mut favorites = Favorites::new();
+        favorites.remember_order(&last);
+        favorites
+    }
+
+    #[test]
+    fn last_order_can_be_added_again_at_current_price() {
+        // Given:
+        let favorites = luigis_regular();
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(luigis_menu());
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let ids = order
+            .add_last_order_for_user(Id::new(1), favorites.get_last_order(&Id::new(1)).unwrap())
+            .unwrap();
+
+        // Then:
+        assert_eq!(ids.len(), 1);
+        let meal = order
+            .get_user_meals(&Id::new(1))
+            .unwrap()
+            .meals()
+            .next()
+            .unwrap();
+        assert_eq!(meal.get_id(), ids[0]);
+        assert_eq!(meal.get_price(), Money::new(5, 50));
+        assert_eq!(
+            meal.specials().next().unwrap().get_description(),
+            "Käserand"
+        );
+    }
+
+    #[test]
+    fn last_order_is_not_added_partially_if_meal_is_no_longer_offered() {
+        // Given:
+        let mut favorites = luigis_regular();
+        let mut last = Order::new(Id::new(1));
+        for meal_id in &["03", "99"] {
+            last.add_meal_for_user(
+                Id::new(1),
+                String::from(*meal_id),
+                String::from("groß"),
+                Money::new(5, 0),
+            )
+            .unwrap();
+        }
+        favorites.remember_order(&last);
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(luigis_menu());
+        order.add_user(Id::new(1)).unwrap();
+
+        // When:
+        let result = order
+            .add_last_order_for_user(Id::new(1), favorites.get_last_order(&Id::new(1)).unwrap());
+
+        // Then:
+        assert_eq!(result, Err(OrderError::Menu(MenuError::MealNotFound)));
+        assert_eq!(order.all_meals().count(), 0);
+    }
+
+    #[test]
+    fn meal_can_be_picked_from_menu() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.set_menu(luigis_menu());
+
+        // When:
+        let meal =
+            order.add_menu_meal_for_user(manager_id, String::from("03"), String::from("groß"), &[]);
+
+        // Then:
+        assert_eq!(
+            meal,
+            Ok(&Meal::new(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50)
+            ))
+        );
+    }
+
+    #[rstest(
+        meal_id,
+        variety,
+        expected,
+        case("04", "groß", OrderError::Menu(MenuError::MealNotFound)),
+        case("03", "klein", OrderError::Menu(MenuError::VarietyNotFound))
+    )]
+    fn meal_not_on_menu_cannot_be_picked(meal_id: &str, variety: &str, expected: OrderError) {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.set_menu(luigis_menu());
+
+        // When:
+        let meal = order.add_menu_meal_for_user(
+            manager_id,
+            String::from(meal_id),
+            String::from(variety),
+            &[],
+        );
+
+        // Then:
+        assert_eq!(meal, Err(expected));
+        assert_eq!(order.calculate_total_price(), Money::zero());
+    }
+
+    fn menu_with_sauces() -> Arc<Menu> {
+        let mut item = MenuItem::new(String::from("40"), String::from("Penne"));
+        item.set_price(String::from("normal"), Money::new(8, 0));
+        item.add_option_group(OptionGroup::new(
+            String::from("Soße"),
+            vec![String::from("Tomate"), String::from("Sahne")],
+        ));
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+        menu.add_item(item);
+        Arc::new(menu)
+    }
+
+    #[test]
+    fn chosen_options_are_added_as_specials() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.set_menu(menu_with_sauces());
+
+        // When:
+        let meal = order
+            .add_menu_meal_for_user(
+                manager_id,
+                String::from("40"),
+                String::from("normal"),
+                &[String::from("sahne")],
+            )
+            .unwrap();
+
+        // Then:
+        assert_eq!(meal.get_price(), Money::new(8, 0));
+        let special = meal.specials().next().unwrap();
+        assert_eq!(special.get_description(), "Sahne");
+        assert_eq!(special.get_price(), None);
+    }
+
+    #[test]
+    fn meal_without_required_choice_is_rejected() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.set_menu(menu_with_sauces());
+
+        // When:
+        let meal = order.add_menu_meal_for_user(
+            manager_id,
+            String::from("40"),
+            String::from("normal"),
+            &[],
+        );
+
+        // Then:
+        assert_eq!(
+            meal,
+            Err(OrderError::Menu(MenuError::MissingChoice {
+                group: String::from("Soße"),
+                options: vec![String::from("Tomate"), String::from("Sahne")]
+            }))
+        );
+        assert_eq!(order.all_meals().count(), 0);
+    }
+
+    #[test]
+    fn tax_rates_of_menu_are_broken_down() {
+        // Given:
+        let mut pizza = MenuItem::new(String::from("03"), String::from("Salami"));
+        pizza.set_price(String::from("groß"), Money::new(10, 70));
+        pizza.set_tax_rate(Some(TaxRate::percent(7)));
+        let mut drink = MenuItem::new(String::from("90"), String::from("Cola"));
+        drink.set_price(String::from("0,5l"), Money::new(2, 38));
+        drink.set_tax_rate(Some(TaxRate::percent(19)));
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+        menu.add_item(pizza);
+        menu.add_item(drink);
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.set_menu(Arc::new(menu));
+        order
+            .add_menu_meal_for_user(
+                manager_id.clone(),
+                String::from("03"),
+                String::from("groß"),
+                &[],
+            )
+            .unwrap();
+        order
+            .add_office_meal(String::from("90"), String::from("0,5l"), Money::new(2, 38))
+            .unwrap();
+        order
+            .set_delivery_fee(Money::new(2, 0), FeeSplitStrategy::default())
+            .unwrap();
+        order
+            .set_tip_for_user(manager_id, Money::new(1, 0))
+            .unwrap();
+
+        // When:
+        let breakdown = order.tax_breakdown();
+
+        // Then:
+        let seven = breakdown.rates()[&TaxRate::percent(7)];
+        assert_eq!(seven.get_net(), Money::new(10, 0));
+        assert_eq!(seven.get_tax(), Money::new(0, 70));
+        assert_eq!(
+            breakdown.rates()[&TaxRate::percent(19)].get_tax(),
+            Money::new(0, 38)
+        );
+        assert_eq!(breakdown.get_untaxed(), Money::new(2, 0));
+        assert_eq!(breakdown.get_gross(), order.calculate_total_price());
+    }
+
+    #[test]
+    fn meal_cannot_be_picked_without_menu() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+
+        // When:
+        let meal =
+            order.add_menu_meal_for_user(manager_id, String::from("03"), String::from("groß"), &[]);
+
+        // Then:
+        assert_eq!(meal, Err(OrderError::NoMenu));
+    }
+
+    #[test]
+    fn meal_price_is_validated_against_menu() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order.set_menu(luigis_menu());
+
+        // When:
+        let meal = order.add_meal_for_user(
+            manager_id,
+            String::from("03"),
+            String::from("groß"),
+            Money::new(15, 50),
+        );
+
+        // Then:
+        assert_eq!(
+            meal,
+            Err(OrderError::Menu(MenuError::PriceMismatch {
+                expected: Money::new(5, 50)
+            }))
+        );
+    }
+
+    #[test]
+    fn office_meal_is_part_of_total_price_but_not_of_settlement() {
+        // Given:
+        let manager_id = Id::new(0);
+        let mut order = Order::new(manager_id.clone());
+        order
+            .add_meal_for_user(
+                manager_id.clone(),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .set_paid_for_user(manager_id, Money::new(5, 50))
+            .unwrap();
+
+        // When:
+        let office_meal = order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap()
+            .get_id();
+
+        // Then:
+        assert_eq!(office_meal, Id::new(1));
+        assert_eq!(order.office_meals().count(), 1);
+        assert_eq!(order.calculate_office_price(), Money::new(4, 0));
+        assert_eq!(order.calculate_total_price(), Money::new(9, 50));
+        assert_eq!(order.calculate_total_change(), Ok(Money::zero()));
+        assert!(order.users_to_remind().is_empty());
+    }
+
+    #[rstest(
+        split,
+        manager_share,
+        user_share,
+        case(FeeSplitStrategy::Equal, Money::new(1, 50), Money::new(1, 50)),
+        case(FeeSplitStrategy::Proportional, Money::new(1, 0), Money::new(2, 0)),
+        case(FeeSplitStrategy::ManagerPays, Money::new(3, 0), Money::zero())
+    )]
+    fn shared_meal_is_split_into_settlement(
+        split: FeeSplitStrategy,
+        manager_share: Money,
+        user_share: Money,
     ) {
-        //Given
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        for (user_id, price) in [(0, Money::new(4, 0)), (1, Money::new(8, 0))] {
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    price,
+                )
+                .unwrap();
+        }
+        order.set_shared_split(split).unwrap();
+
+        // When:
+        order
+            .add_shared_meal(String::from("90"), String::from("1,5l"), Money::new(3, 0))
+            .unwrap();
+
+        // Then:
+        let report = order.payment_report();
+        assert_eq!(report.users()[0].get_shared_share(), manager_share);
+        assert_eq!(report.users()[1].get_shared_share(), user_share);
+        assert_eq!(report.users()[1].get_due(), Money::new(8, 0) + user_share);
+        assert_eq!(report.get_total_shared(), Money::new(3, 0));
+        assert_eq!(order.calculate_total_price(), Money::new(15, 0));
+        assert_eq!(report.get_total_price(), order.calculate_total_price());
+        assert_eq!(order.all_meals().count(), 3);
+    }
+
+    #[test]
+    fn shared_meals_are_replayed_and_cloned() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_shared_meal(String::from("90"), String::from("1,5l"), Money::new(3, 0))
+            .unwrap();
+        order
+            .set_shared_split(FeeSplitStrategy::Proportional)
+            .unwrap();
+
+        // When:
+        let replayed = Order::replay(order.history()).unwrap();
+        let clone = Order::clone_from(&order, Id::new(1));
+
+        // Then:
+        for order in [replayed, clone] {
+            assert_eq!(order.calculate_shared_price(), Money::new(3, 0));
+            assert_eq!(order.get_shared_split(), FeeSplitStrategy::Proportional);
+        }
+    }
+
+    #[test]
+    fn all_meals_include_office_meals() {
+        // Given:
         let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+        let mut order = Order::new(manager_id.clone());
+        order.add_user(Id::new(1)).unwrap();
+        for user_id in 0..2 {
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+
+        // When:
+        let mut meal_ids: Vec<u32> = order
+            .all_meals()
+            .map(|meal| meal.get_id().get_value())
+            .collect();
+
+        // Then:
+        meal_ids.sort_unstable();
+        assert_eq!(meal_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn office_meal_can_be_removed() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        let id = order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap()
+            .get_id();
+
+        // When:
+        let removed = order.remove_office_meal(id.clone());
+        let removed_again = order.remove_office_meal(id);
+
+        // Then:
+        assert!(removed.is_ok());
+        assert_eq!(removed_again, Err(OrderError::MealNotFound));
+        assert_eq!(order.calculate_total_price(), Money::zero());
+    }
+
+    #[test]
+    fn office_meal_is_validated_against_menu() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(luigis_menu());
+
+        // When:
+        let meal =
+            order.add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0));
+
+        // Then:
+        assert_eq!(meal, Err(OrderError::Menu(MenuError::MealNotFound)));
+    }
 
-        for attributes in meals_attributes.into_iter() {
-            order.add_user(attributes.orderer_id.clone());
-            for price in attributes.meal_price.iter() {
-                order
-                    .add_meal_for_user(
-                        attributes.orderer_id.clone(),
-                        String::from("XX"),
-                        String::from("something"),
-                        price.clone(),
-                    )
-                    .unwrap();
-            }
-            order
-                .get_meals_for_user(attributes.orderer_id)
-                .unwrap()
-                .set_paid(attributes.amount)
+    #[rstest(
+        step,
+        expected,
+        case(Money::new(0, 50), Money::new(13, 50)),
+        case(Money::new(1, 0), Money::new(14, 0)),
+        case(Money::zero(), Money::new(13, 20))
+    )]
+    fn total_is_rounded_up(step: Money, expected: Money) {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 20),
+            )
+            .unwrap();
+        order
+            .set_delivery_fee(Money::new(6, 0), FeeSplitStrategy::Equal)
+            .unwrap();
+
+        // When:
+        let rounded = order.round_up_to(step);
+
+        // Then:
+        assert_eq!(rounded, expected);
+    }
+
+    fn order_with_history(clock: &TestClock) -> (Order, Id<Meal>) {
+        let mut order = Order::with_audit_clock(Id::new(0), Arc::new(clock.clone()));
+        order.add_user(Id::new(1)).unwrap();
+        let meal = order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 50),
+            )
+            .unwrap()
+            .get_id();
+        clock.advance(Duration::from_secs(60));
+        order
+            .add_special_for_user(
+                Id::new(1),
+                meal.clone(),
+                String::from("Käserand"),
+                Some(Money::new(1, 0)),
+            )
+            .unwrap();
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+        order
+            .set_paid_for_user(Id::new(1), Money::new(10, 0))
+            .unwrap();
+        order.start_ordering().unwrap();
+        (order, meal)
+    }
+
+    #[test]
+    fn order_is_replayed_from_its_history() {
+        // Given:
+        let clock = TestClock::default();
+        let (order, _) = order_with_history(&clock);
+
+        // When:
+        let replayed = Order::replay(order.history());
+
+        // Then:
+        let replayed = replayed.unwrap();
+        assert_eq!(replayed, order);
+        assert_eq!(replayed.history(), order.history());
+    }
+
+    #[test]
+    fn duplicate_payment_is_held_until_released() {
+        // Given:
+        let clock = TestClock::default();
+        let (mut order, _) = order_with_history(&clock);
+        let paid_before = order.get_user_meals(&Id::new(1)).unwrap().get_paid();
+        let first = order
+            .receive_payment_for_user(Id::new(1), Money::new(2, 0), None)
+            .unwrap();
+        clock.advance(Duration::from_secs(60));
+
+        // When:
+        let second = order
+            .receive_payment_for_user(Id::new(1), Money::new(2, 0), None)
+            .unwrap();
+
+        // Then:
+        let ReceivedPayment::Booked(first) = first else {
+            panic!("First payment was not booked: {:?}", first);
+        };
+        let ReceivedPayment::Held(held, reason) = second else {
+            panic!("Second payment was not held: {:?}", second);
+        };
+        assert_eq!(reason, DuplicateReason::SameAmount(first));
+        let paid = || order.get_user_meals(&Id::new(1)).unwrap().get_paid();
+        assert_eq!(paid(), paid_before + Money::new(2, 0));
+        let replayed = Order::replay(order.history()).unwrap();
+        assert_eq!(replayed, order);
+        order.release_payment_for_user(Id::new(1), held).unwrap();
+        assert_eq!(
+            order.get_user_meals(&Id::new(1)).unwrap().get_paid(),
+            paid_before + Money::new(4, 0)
+        );
+    }
+
+    /// Open order in which user 1 ordered a meal for the given price.
+    fn order_at_clock(clock: &TestClock, price: Money) -> Order {
+        let mut order = Order::with_audit_clock(Id::new(0), Arc::new(clock.clone()));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(Id::new(1), String::from("03"), String::from("groß"), price)
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn restaurant_is_checked_when_order_is_placed() {
+        // Given:
+        use crate::order_model::restaurant::{OpeningHours, OpeningPeriod};
+        use chrono::{FixedOffset, NaiveTime, TimeZone, Weekday};
+        let lunch = Utc.with_ymd_and_hms(2020, 5, 4, 12, 0, 0).unwrap();
+        let clock = TestClock::new(lunch.into());
+        let hours =
+            OpeningHours::new(FixedOffset::east_opt(0).unwrap()).with_period(OpeningPeriod::new(
+                Weekday::Mon,
+                NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            ));
+        let restaurant = Restaurant::new(String::from("Pizzeria Mario"))
+            .with_min_order_value(Money::new(15, 0))
+            .with_delivery_fee(Money::new(2, 0))
+            .with_opening_hours(hours);
+        let mut small = order_at_clock(&clock, Money::new(12, 50));
+        let mut late = order_at_clock(&clock, Money::new(16, 50));
+        for order in [&mut small, &mut late] {
+            order.set_restaurant(Some(restaurant.clone())).unwrap();
+            order.start_ordering().unwrap();
         }
-        //When
-        let calculated_change = order.calculate_total_change();
-        //Then
-        assert_eq!(Err(expected_change), calculated_change);
+
+        // When:
+        let too_little = small.mark_ordered(None);
+        clock.advance(Duration::from_secs(3 * 60 * 60));
+        let closed = late.mark_ordered(None);
+
+        // Then:
+        assert_eq!(
+            too_little,
+            Err(OrderError::Restaurant(
+                RestaurantError::BelowMinimumOrderValue {
+                    total: Money::new(12, 50),
+                    minimum: Money::new(15, 0)
+                }
+            ))
+        );
+        assert_eq!(closed, Err(OrderError::Restaurant(RestaurantError::Closed)));
+        assert_eq!(late.get_delivery_fee(), Money::new(2, 0));
+        assert_eq!(late.get_status(), &OrderStatus::Ordering);
     }
 
-    #[rstest(meals_attributes, expected_change,
-        case(
-            vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(5, 50), Money::new(7, 37)],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(16, 00),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(3, 50), Money::new(4, 42)],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(7, 50),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(6, 83)],
-                    orderer_id: Id::new(3),
-                    amount: Money::new(6, 00),
-                },
-            ],
-            NotAllPaidEnoughError::Underpaid{
-                underpaid: Money::new(0, 37),
-                paid_less: build_paid_less_hash_set(vec!(2, 3)),
-            },
-        ),
-        case(
+    #[test]
+    fn deadline_is_set_to_cutoff_of_the_day() {
+        // Given:
+        use crate::order_model::restaurant::{OpeningHours, OpeningPeriod};
+        use chrono::{FixedOffset, NaiveTime, TimeZone, Weekday};
+        // Sunday
+        let clock = TestClock::new(Utc.with_ymd_and_hms(2020, 5, 10, 18, 0, 0).unwrap().into());
+        let mut hours = OpeningHours::new(FixedOffset::east_opt(0).unwrap());
+        for day in [Weekday::Sat, Weekday::Sun] {
+            hours = hours.with_period(OpeningPeriod::new(
+                day,
+                NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            ));
+        }
+        let hours = hours.with_cutoff(Weekday::Sun, NaiveTime::from_hms_opt(20, 30, 0).unwrap());
+        let mut order = order_at_clock(&clock, Money::new(7, 50));
+        order
+            .set_restaurant(Some(
+                Restaurant::new(String::from("Pizzeria Mario")).with_opening_hours(hours),
+            ))
+            .unwrap();
+
+        // When:
+        let deadline = order.set_deadline_to_cutoff();
+        clock.advance(Duration::from_secs(3 * 60 * 60));
+        order.start_ordering().unwrap();
+        let placed = order.mark_ordered(None);
+
+        // Then:
+        assert_eq!(deadline, Ok(Some(at("2020-05-10T20:30:00Z"))));
+        assert_eq!(order.get_deadline(), Some(at("2020-05-10T20:30:00Z")));
+        assert_eq!(placed, Err(OrderError::Restaurant(RestaurantError::Closed)));
+    }
+
+    #[test]
+    fn placed_order_is_replayed_outside_opening_hours() {
+        // Given:
+        use crate::order_model::restaurant::OpeningHours;
+        use chrono::FixedOffset;
+        let clock = TestClock::default();
+        let mut order = order_at_clock(&clock, Money::new(7, 50));
+        let restaurant = Restaurant::new(String::from("Pizzeria Mario"));
+        order.set_restaurant(Some(restaurant.clone())).unwrap();
+        order.start_ordering().unwrap();
+        let eta = at("2020-05-04T12:45:00Z");
+        order.mark_ordered(Some(eta)).unwrap();
+        // Closed for good since, which must not stop the replay
+        let closed =
+            restaurant.with_opening_hours(OpeningHours::new(FixedOffset::east_opt(0).unwrap()));
+        let mut events = order.history().to_vec();
+        let index = events
+            .iter()
+            .position(|event| matches!(event.get_mutation(), Mutation::RestaurantSet(_)))
+            .unwrap();
+        events[index] = OrderEvent::new(
+            events[index].get_time(),
+            Mutation::RestaurantSet(Some(closed)),
+        );
+
+        // When:
+        let replayed = Order::replay(&events);
+
+        // Then:
+        assert_eq!(
+            replayed.map(|order| order.get_status().clone()),
+            Ok(OrderStatus::Ordered {
+                placed_at: DateTime::from(clock.now()),
+                eta: Some(eta)
+            })
+        );
+    }
+
+    #[test]
+    fn installments_keep_their_time_when_replayed() {
+        // Given:
+        let clock = TestClock::default();
+        let (mut order, _) = order_with_history(&clock);
+        clock.advance(Duration::from_secs(60));
+        let id = order
+            .add_payment_for_user(Id::new(1), Money::new(2, 0))
+            .unwrap();
+        order
+            .correct_payment_for_user(Id::new(1), id.clone(), Money::new(2, 50))
+            .unwrap();
+        clock.advance(Duration::from_secs(60));
+
+        // When:
+        let replayed = Order::replay(order.history()).unwrap();
+
+        // Then:
+        let payments = replayed.get_user_meals(&Id::new(1)).unwrap().payments();
+        assert_eq!(payments[1].get_id(), id);
+        assert_eq!(payments[1].get_amount(), Money::new(2, 50));
+        assert_eq!(
+            payments[1].get_paid_at(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(120))
+        );
+        assert_eq!(replayed, order);
+    }
+
+    #[test]
+    fn earlier_state_is_rebuilt_from_history() {
+        // Given:
+        let clock = TestClock::default();
+        let (order, meal) = order_with_history(&clock);
+
+        // When:
+        let earlier = order.state_at(SystemTime::UNIX_EPOCH).unwrap();
+
+        // Then:
+        assert_eq!(earlier.get_status(), &OrderStatus::Open);
+        assert_eq!(earlier.calculate_total_price(), Money::new(7, 50));
+        assert_eq!(earlier.history().len(), 3);
+        assert!(earlier
+            .get_user_meals(&Id::new(1))
+            .unwrap()
+            .meals()
+            .all(|other| other.get_id() == meal && other.specials().count() == 0));
+    }
+
+    #[test]
+    fn history_shows_who_changed_a_meal() {
+        // Given:
+        let clock = TestClock::default();
+        let (mut order, meal) = order_with_history(&clock);
+        order.add_user(Id::new(2)).unwrap();
+
+        // When:
+        let changes: Vec<String> = order
+            .history_for_meal(&meal)
+            .map(|event| event.get_mutation().to_string())
+            .collect();
+
+        // Then:
+        assert_eq!(
+            changes,
             vec![
-                MealsAttributes {
-                    meal_price: vec![Money::new(2, 25), Money::new(4, 42)],
-                    orderer_id: Id::new(1),
-                    amount: Money::new(6, 25),
-                },
-                MealsAttributes {
-                    meal_price: vec![Money::new(5, 50)],
-                    orderer_id: Id::new(2),
-                    amount: Money::new(5, 00),
-                },
-            ],
-            NotAllPaidEnoughError::Underpaid{
-                underpaid: Money::new(0, 92),
-                paid_less: build_paid_less_hash_set(vec!(1, 2)),
-            },
-        ),
+                "meal 0 (03 groß, 7,50€) added for user 1",
+                "special \"Käserand\" added to meal 0 of user 1",
+            ]
+        );
+        assert_eq!(order.history_for_user(&Id::new(1)).count(), 4);
+        assert_eq!(order.history_for_user(&Id::new(2)).count(), 1);
+    }
+
+    #[test]
+    fn missed_events_are_taken_from_sequence_number() {
+        // Given:
+        let clock = TestClock::default();
+        let (mut order, _) = order_with_history(&clock);
+        let seen = order.get_sequence_number();
+        order.add_user(Id::new(2)).unwrap();
+
+        // When:
+        let missed = order.events_since(seen).unwrap();
+
+        // Then:
+        assert_eq!(seen, 7);
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].get_mutation(), &Mutation::UserAdded(Id::new(2)));
+        assert_eq!(order.events_since(8), Some(&[][..]));
+        assert_eq!(order.events_since(9), None);
+    }
+
+    #[test]
+    fn meals_conflict_only_with_changes_of_same_user() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        let seen = order.get_sequence_number();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(8, 0),
+            )
+            .unwrap();
+
+        // When:
+        let other_user = order.check_meals_version(&Id::new(0), seen);
+        let same_user = order.check_meals_version(&Id::new(1), seen);
+
+        // Then:
+        assert_eq!(other_user, Ok(()));
+        assert_eq!(
+            same_user,
+            Err(OrderError::Conflict(ConflictError {
+                expected: seen,
+                current: seen + 1,
+            }))
+        );
+        assert_eq!(order.get_meals_version(&Id::new(1)), Some(seen + 1));
+        assert_eq!(order.check_meals_version(&Id::new(1), seen + 1), Ok(()));
+        assert!(order.check_meals_version(&Id::new(0), seen + 2).is_err());
+    }
+
+    #[rstest(
+        mutations,
+        case(vec![]),
+        case(vec![Mutation::UserAdded(Id::new(1))]),
+        case(vec![
+            Mutation::Created { manager_id: Id::new(0) },
+            Mutation::MealRemoved { user_id: Id::new(0), id: Id::new(0) }
+        ])
     )]
-    fn not_all_paid_enough_change_is_negative(
-        meals_attributes: Vec<MealsAttributes>,
-        expected_change: NotAllPaidEnoughError,
-    ) {
-        //Given
-        let manager_id = Id::new(0);
-        let mut order = Order::new(manager_id);
+    fn invalid_history_cannot_be_replayed(mutations: Vec<Mutation>) {
+        // Given:
+        let events: Vec<OrderEvent> = mutations
+            .into_iter()
+            .map(|mutation| OrderEvent::new(SystemTime::UNIX_EPOCH, mutation))
+            .collect();
 
-        for attributes in meals_attributes.into_iter() {
-            order.add_user(attributes.orderer_id.clone());
-            for price in attributes.meal_price.iter() {
-                order
-                    .add_meal_for_user(
-                        attributes.orderer_id.clone(),
-                        String::from("XX"),
-                        String::from("something"),
-                        price.clone(),
-                    )
-                    .unwrap();
-            }
+        // When:
+        let replayed = Order::replay(&events);
+
+        // Then:
+        assert_eq!(replayed, Err(OrderError::InvalidHistory));
+    }
+
+    #[test]
+    fn users_are_iterated_with_their_meals() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        for user_id in 0..2 {
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+
+        // When:
+        let mut users: Vec<(Id<User>, usize)> = order
+            .users()
+            .map(|meals| (meals.get_owner_id(), meals.meals().count()))
+            .collect();
+        users.sort();
+
+        // Then:
+        assert_eq!(users, vec![(Id::new(0), 1), (Id::new(1), 1)]);
+        assert_eq!(order.all_meals().count(), 3);
+    }
+
+    #[test]
+    fn order_is_summarized_with_given_verbosity() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        for user_id in 0..2 {
             order
-                .get_meals_for_user(attributes.orderer_id)
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+        order
+            .set_restaurant(Some(Restaurant::new(String::from("Pizzeria Luigi"))))
+            .unwrap();
+        order.set_locale(Some(Locale::En));
+
+        // When:
+        let brief = order.summary(Verbosity::Brief);
+        let detailed = order.summary(Verbosity::Detailed);
+
+        // Then:
+        assert_eq!(brief, "Open order, 2 people, 3 meals — €15.00");
+        assert_eq!(
+            order.to_string(),
+            "Open order: 2x 03 groß — €11.00, 1x 61 Salat — €4.00; total €15.00"
+        );
+        assert_eq!(
+            detailed,
+            "Open order at Pizzeria Luigi, total €15.00\n\
+             User 0: 1x 03 groß — €5.50; paid €0.00 of €5.50\n\
+             User 1: 1x 03 groß — €5.50; paid €0.00 of €5.50\n\
+             Office: 1x 61 Salat — €4.00"
+        );
+    }
+
+    #[test]
+    fn returned_deposits_are_given_back_as_change() {
+        // Given:
+        let mut drink = MenuItem::new(String::from("90"), String::from("Cola"));
+        drink.set_price(String::from("0,5l"), Money::new(2, 0));
+        drink.set_deposit(Money::new(0, 25));
+        let mut menu = Menu::new(String::from("Pizzeria Luigi"));
+        menu.add_item(drink);
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(Arc::new(menu));
+        order.add_user(Id::new(1)).unwrap();
+        let mut ids = Vec::new();
+        for user_id in 0..2 {
+            let id = order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("90"),
+                    String::from("0,5l"),
+                    Money::new(2, 0),
+                )
                 .unwrap()
-                .set_paid(attributes.amount)
+                .get_id();
+            order
+                .set_paid_for_user(Id::new(user_id), Money::new(2, 25))
+                .unwrap();
+            ids.push(id);
         }
-        //When
-        let calculated_change = order.calculate_total_change();
-        //Then
-        assert_eq!(Err(expected_change), calculated_change);
+        let early = order.return_deposit(&ids[1]);
+        order.start_ordering().unwrap();
+        order.mark_ordered(None).unwrap();
+        order.mark_delivered(SystemTime::UNIX_EPOCH).unwrap();
+
+        // When:
+        let refund = order.return_deposit(&ids[1]);
+
+        // Then:
+        assert_eq!(early, Err(OrderError::WrongStatus));
+        assert_eq!(refund, Ok(Money::new(0, 25)));
+        assert_eq!(order.calculate_open_deposit(), Money::new(0, 25));
+        assert_eq!(order.calculate_returned_deposit(), Money::new(0, 25));
+        assert_eq!(order.calculate_total_price(), Money::new(4, 25));
+        let report = order.payment_report();
+        assert_eq!(
+            report.get_user(&Id::new(0)).unwrap().get_balance(),
+            Balance::Change(Money::zero())
+        );
+        assert_eq!(
+            report.get_user(&Id::new(1)).unwrap().get_balance(),
+            Balance::Change(Money::new(0, 25))
+        );
+        let replayed = Order::replay(order.history()).unwrap();
+        assert_eq!(replayed.calculate_returned_deposit(), Money::new(0, 25));
+    }
+
+    #[test]
+    fn deposit_can_be_set_for_meals_off_the_menu() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        let id = order
+            .add_shared_meal(String::from("90"), String::from("1l"), Money::new(3, 0))
+            .unwrap()
+            .get_id();
+
+        // When:
+        let meal = order
+            .set_deposit(&id, Money::new(0, 15))
+            .map(|meal| meal.get_deposit());
+        let missing = order.set_deposit(&Id::new(7), Money::new(0, 15)).err();
+
+        // Then:
+        assert_eq!(meal, Ok(Money::new(0, 15)));
+        assert_eq!(missing, Some(OrderError::MealNotFound));
+        assert_eq!(order.calculate_shared_price(), Money::new(3, 15));
+        assert_eq!(
+            Order::replay(order.history())
+                .unwrap()
+                .calculate_open_deposit(),
+            Money::new(0, 15)
+        );
     }
 }
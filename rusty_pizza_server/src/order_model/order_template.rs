@@ -0,0 +1,256 @@
+use crate::order_model::order::Order;
+use crate::order_model::restaurant::{OpeningHours, Restaurant};
+use crate::order_model::user::User;
+use crate::util::clock::Clock;
+use crate::util::id::Id;
+use chrono::{DateTime, FixedOffset, NaiveTime, TimeZone, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// How a recurring group order starts, e.g. the pizza order every Friday, so it can be opened with one call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderTemplate {
+    name: String,
+    manager_id: Id<User>,
+    restaurant: Restaurant,
+    /// Participants besides the manager, in the order they were added
+    participants: Vec<Id<User>>,
+    /// Time of day in the time zone of the restaurant, UTC if its opening hours are unknown
+    deadline: Option<NaiveTime>,
+}
+
+impl OrderTemplate {
+    pub fn new(name: String, manager_id: Id<User>, restaurant: Restaurant) -> OrderTemplate {
+        OrderTemplate {
+            name,
+            manager_id,
+            restaurant,
+            participants: Vec::new(),
+            deadline: None,
+        }
+    }
+
+    /// Adds a participant, the manager and users added before are ignored.
+    pub fn with_participant(mut self, user_id: Id<User>) -> OrderTemplate {
+        if user_id != self.manager_id && !self.participants.contains(&user_id) {
+            self.participants.push(user_id);
+        }
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: NaiveTime) -> OrderTemplate {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_manager_id(&self) -> Id<User> {
+        self.manager_id.clone()
+    }
+
+    pub fn get_restaurant(&self) -> &Restaurant {
+        &self.restaurant
+    }
+
+    pub fn participants(&self) -> &[Id<User>] {
+        &self.participants
+    }
+
+    pub fn get_deadline(&self) -> Option<NaiveTime> {
+        self.deadline
+    }
+
+    /// The next time the deadline is reached after `now`, later today or tomorrow.
+    pub fn next_deadline(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let deadline = self.deadline?;
+        let offset = self
+            .restaurant
+            .get_opening_hours()
+            .map(OpeningHours::get_utc_offset)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        let local_now = now.with_timezone(&offset);
+        let mut day = local_now.date_naive();
+        if local_now.time() >= deadline {
+            day = day.succ_opt()?;
+        }
+        offset
+            .from_local_datetime(&day.and_time(deadline))
+            .single()
+            .map(|deadline| deadline.with_timezone(&Utc))
+    }
+
+    /// A new open order for the restaurant with the participants of the template.
+    ///
+    /// The deadline is the next one of the template, or the last order time of the restaurant without one.
+    pub fn instantiate(&self, clock: Arc<dyn Clock + Send + Sync>) -> Order {
+        let now = DateTime::from(clock.now());
+        let mut order = Order::with_audit_clock(self.manager_id.clone(), clock);
+        for user_id in &self.participants {
+            order
+                .add_user(user_id.clone())
+                .expect("New orders are open and participants are unique");
+        }
+        order
+            .set_restaurant(Some(self.restaurant.clone()))
+            .expect("New orders are open");
+        match self.next_deadline(now) {
+            Some(deadline) => order
+                .set_deadline(Some(deadline))
+                .expect("New orders are open"),
+            None => {
+                order.set_deadline_to_cutoff().expect("New orders are open");
+            }
+        }
+        order
+    }
+}
+
+/// All templates known to the server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderTemplates {
+    templates: BTreeMap<Id<OrderTemplate>, OrderTemplate>,
+}
+
+impl OrderTemplates {
+    pub fn new() -> OrderTemplates {
+        OrderTemplates::default()
+    }
+
+    /// Adds the template and returns its ID, the one after the highest in use.
+    pub fn add(&mut self, template: OrderTemplate) -> Id<OrderTemplate> {
+        let id = Id::new(
+            self.templates
+                .keys()
+                .next_back()
+                .map_or(0, |last| last.get_value() + 1),
+        );
+        self.templates.insert(id.clone(), template);
+        id
+    }
+
+    /// Adds the template with the ID it had before, e.g. when loading saved templates, replacing one with it.
+    pub fn restore(&mut self, id: Id<OrderTemplate>, template: OrderTemplate) {
+        self.templates.insert(id, template);
+    }
+
+    pub fn get(&self, id: &Id<OrderTemplate>) -> Option<&OrderTemplate> {
+        self.templates.get(id)
+    }
+
+    pub fn remove(&mut self, id: &Id<OrderTemplate>) -> Option<OrderTemplate> {
+        self.templates.remove(id)
+    }
+
+    /// Iterates over the templates by ID.
+    pub fn templates(&self) -> impl Iterator<Item = (&Id<OrderTemplate>, &OrderTemplate)> {
+        self.templates.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::order::OrderStatus;
+    use crate::order_model::restaurant::OpeningPeriod;
+    use crate::util::clock::TestClock;
+    use chrono::Weekday;
+    use rstest::rstest;
+
+    fn friday_pizza() -> OrderTemplate {
+        let hours = OpeningHours::new(FixedOffset::east_opt(2 * 60 * 60).unwrap()).with_period(
+            OpeningPeriod::new(
+                Weekday::Fri,
+                NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            ),
+        );
+        OrderTemplate::new(
+            String::from("Friday pizza"),
+            Id::new(0),
+            Restaurant::new(String::from("Pizzeria Luigi")).with_opening_hours(hours),
+        )
+        .with_participant(Id::new(1))
+        .with_participant(Id::new(2))
+        .with_participant(Id::new(1))
+        .with_participant(Id::new(0))
+        .with_deadline(NaiveTime::from_hms_opt(11, 30, 0).unwrap())
+    }
+
+    #[rstest(
+        now,
+        expected,
+        // 10:00 in the restaurant
+        case("2020-05-08T08:00:00Z", "2020-05-08T09:30:00Z"),
+        // 12:00 in the restaurant
+        case("2020-05-08T10:00:00Z", "2020-05-09T09:30:00Z")
+    )]
+    fn deadline_is_next_in_time_zone_of_restaurant(now: &str, expected: &str) {
+        // Given:
+        let template = friday_pizza();
+
+        // When:
+        let deadline = template.next_deadline(now.parse().unwrap());
+
+        // Then:
+        assert_eq!(deadline, Some(expected.parse().unwrap()));
+    }
+
+    #[test]
+    fn order_is_instantiated_with_participants_and_deadline() {
+        // Given:
+        let clock = TestClock::default();
+        clock.set(
+            "2020-05-08T08:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .into(),
+        );
+        let template = friday_pizza();
+
+        // When:
+        let order = template.instantiate(Arc::new(clock));
+
+        // Then:
+        assert_eq!(order.get_status(), &OrderStatus::Open);
+        assert_eq!(order.get_manager_id(), Id::new(0));
+        assert_eq!(order.participants().count(), 3);
+        assert!(order.is_participating(&Id::new(2)));
+        assert_eq!(
+            order.get_restaurant().map(Restaurant::get_name),
+            Some(&String::from("Pizzeria Luigi"))
+        );
+        assert_eq!(
+            order.get_deadline(),
+            Some("2020-05-08T09:30:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn id_follows_highest_template() {
+        // Given:
+        let mut templates = OrderTemplates::new();
+        let first = templates.add(friday_pizza());
+        let second = templates.add(friday_pizza());
+        templates.remove(&first);
+
+        // When:
+        let third = templates.add(friday_pizza());
+
+        // Then:
+        assert_eq!(first, Id::new(0));
+        assert_eq!(second, Id::new(1));
+        assert_eq!(third, Id::new(2));
+        assert_eq!(templates.len(), 2);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// How close two installments of the same amount have to be to look like the same payment booked twice.
+pub const DUPLICATE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaymentStatus {
+    /// The participant says they paid, the manager has not checked yet
+    Claimed,
+    /// The manager received the money
+    Confirmed,
+    /// The manager did not receive the money
+    Disputed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PaymentError {
+    /// There is no claimed payment waiting for confirmation
+    NotPending,
+    /// The owner made no installment with the given ID
+    UnknownInstallment(Id<Installment>),
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PaymentError::*;
+        match self {
+            NotPending => write!(f, "No payment is waiting for confirmation"),
+            UnknownInstallment(id) => write!(f, "There is no payment {}", id.get_value()),
+        }
+    }
+}
+
+impl Error for PaymentError {}
+
+/// A payment a participant made themselves, e.g. "8,50€ via PayPal", which the manager has to confirm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Payment {
+    amount: Money,
+    /// How the money was transferred
+    method: String,
+    status: PaymentStatus,
+}
+
+impl Payment {
+    /// Creates a new `Payment` claimed by the participant.
+    pub fn new(amount: Money, method: String) -> Payment {
+        Payment {
+            amount,
+            method,
+            status: PaymentStatus::Claimed,
+        }
+    }
+
+    pub fn get_amount(&self) -> Money {
+        self.amount
+    }
+
+    pub fn get_method(&self) -> &String {
+        &self.method
+    }
+
+    pub fn get_status(&self) -> PaymentStatus {
+        self.status
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == PaymentStatus::Claimed
+    }
+
+    pub fn confirm(&mut self) -> Result<(), PaymentError> {
+        self.resolve(PaymentStatus::Confirmed)
+    }
+
+    pub fn dispute(&mut self) -> Result<(), PaymentError> {
+        self.resolve(PaymentStatus::Disputed)
+    }
+
+    fn resolve(&mut self, status: PaymentStatus) -> Result<(), PaymentError> {
+        if !self.is_pending() {
+            return Err(PaymentError::NotPending);
+        }
+        self.status = status;
+        Ok(())
+    }
+}
+
+/// Part of the money a participant paid, e.g. cash at lunch and the rest by transfer the next day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Installment {
+    id: Id<Installment>,
+    amount: Money,
+    /// `None` for amounts which were set as a whole instead of being paid at a known time
+    paid_at: Option<SystemTime>,
+    /// Idempotency key the client sent along, a retry with the same key books nothing
+    key: Option<String>,
+}
+
+impl Installment {
+    pub fn new(id: Id<Installment>, amount: Money, paid_at: Option<SystemTime>) -> Installment {
+        Installment {
+            id,
+            amount,
+            paid_at,
+            key: None,
+        }
+    }
+
+    pub fn with_key(mut self, key: Option<String>) -> Installment {
+        self.key = key;
+        self
+    }
+
+    pub fn get_id(&self) -> Id<Installment> {
+        self.id.clone()
+    }
+
+    pub fn get_amount(&self) -> Money {
+        self.amount
+    }
+
+    pub fn set_amount(&mut self, amount: Money) {
+        self.amount = amount;
+    }
+
+    pub fn get_paid_at(&self) -> Option<SystemTime> {
+        self.paid_at
+    }
+
+    pub fn get_key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+}
+
+/// Why an installment looks like one that was already booked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DuplicateReason {
+    /// The given installment has the same amount and was paid within the `DUPLICATE_WINDOW`
+    SameAmount(Id<Installment>),
+    /// The given installment was sent with the same idempotency key but another amount
+    KeyReused(Id<Installment>),
+}
+
+impl DuplicateReason {
+    /// The installment which was booked first.
+    pub fn get_original(&self) -> Id<Installment> {
+        match self {
+            DuplicateReason::SameAmount(id) | DuplicateReason::KeyReused(id) => id.clone(),
+        }
+    }
+}
+
+impl fmt::Display for DuplicateReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DuplicateReason::SameAmount(id) => {
+                write!(
+                    f,
+                    "same amount as payment {} shortly before",
+                    id.get_value()
+                )
+            }
+            DuplicateReason::KeyReused(id) => write!(
+                f,
+                "same idempotency key as payment {} but another amount",
+                id.get_value()
+            ),
+        }
+    }
+}
+
+/// Result of comparing a new installment with those of the participant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Duplicate {
+    /// A retry of the given installment with the same idempotency key and amount
+    Retry(Id<Installment>),
+    /// Probably booked twice by mistake, the manager has to confirm it
+    Suspected(DuplicateReason),
+}
+
+/// What became of an installment handed to `Order::receive_payment_for_user`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReceivedPayment {
+    Booked(Id<Installment>),
+    /// A retry of the given installment, nothing was booked
+    Repeated(Id<Installment>),
+    /// Held as suspected duplicate until the manager confirms it
+    Held(Id<Installment>, DuplicateReason),
+}
+
+/// An installment that is not counted as paid until the manager confirms it is no duplicate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeldPayment {
+    installment: Installment,
+    reason: DuplicateReason,
+}
+
+impl HeldPayment {
+    pub fn new(installment: Installment, reason: DuplicateReason) -> HeldPayment {
+        HeldPayment {
+            installment,
+            reason,
+        }
+    }
+
+    pub fn get_installment(&self) -> &Installment {
+        &self.installment
+    }
+
+    pub fn get_reason(&self) -> &DuplicateReason {
+        &self.reason
+    }
+
+    pub fn into_installment(self) -> Installment {
+        self.installment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_is_claimed_when_created() {
+        // When:
+        let payment = Payment::new(Money::new(8, 50), String::from("PayPal"));
+
+        // Then:
+        assert_eq!(payment.get_amount(), Money::new(8, 50));
+        assert_eq!(payment.get_method(), "PayPal");
+        assert_eq!(payment.get_status(), PaymentStatus::Claimed);
+        assert!(payment.is_pending());
+    }
+
+    #[test]
+    fn claimed_payment_can_be_confirmed() {
+        // Given:
+        let mut payment = Payment::new(Money::new(8, 50), String::from("PayPal"));
+
+        // When:
+        let result = payment.confirm();
+
+        // Then:
+        assert_eq!(result, Ok(()));
+        assert_eq!(payment.get_status(), PaymentStatus::Confirmed);
+        assert!(!payment.is_pending());
+    }
+
+    #[test]
+    fn claimed_payment_can_be_disputed() {
+        // Given:
+        let mut payment = Payment::new(Money::new(8, 50), String::from("PayPal"));
+
+        // When:
+        let result = payment.dispute();
+
+        // Then:
+        assert_eq!(result, Ok(()));
+        assert_eq!(payment.get_status(), PaymentStatus::Disputed);
+    }
+
+    #[test]
+    fn resolved_payment_cannot_be_resolved_again() {
+        // Given:
+        let mut payment = Payment::new(Money::new(8, 50), String::from("PayPal"));
+        payment.dispute().unwrap();
+
+        // When:
+        let result = payment.confirm();
+
+        // Then:
+        assert_eq!(result, Err(PaymentError::NotPending));
+        assert_eq!(payment.get_status(), PaymentStatus::Disputed);
+    }
+}
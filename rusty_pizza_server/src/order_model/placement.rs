@@ -0,0 +1,245 @@
+use crate::order_model::meal::Meal;
+use crate::order_model::order::Order;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::fmt;
+
+/// Tolerance for the prices of manually priced specials, to catch typos before the order is placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpecialPriceRules {
+    /// How far the price of a special may deviate from its price on the menu, in percent
+    tolerance_percent: u32,
+    /// Highest price of any special, whether it is on the menu or not
+    cap: Option<Money>,
+}
+
+impl SpecialPriceRules {
+    pub fn new(tolerance_percent: u32, cap: Option<Money>) -> SpecialPriceRules {
+        SpecialPriceRules {
+            tolerance_percent,
+            cap,
+        }
+    }
+
+    pub fn get_tolerance_percent(&self) -> u32 {
+        self.tolerance_percent
+    }
+
+    pub fn get_cap(&self) -> Option<Money> {
+        self.cap
+    }
+
+    fn is_outlier(&self, price: Money, menu_price: Money) -> bool {
+        let difference =
+            (i64::from(price.get_total_cents()) - i64::from(menu_price.get_total_cents())).abs();
+        difference * 100
+            > i64::from(menu_price.get_total_cents()) * i64::from(self.tolerance_percent)
+    }
+}
+
+impl Default for SpecialPriceRules {
+    /// 25% tolerance and no cap
+    fn default() -> SpecialPriceRules {
+        SpecialPriceRules::new(25, None)
+    }
+}
+
+/// A suspicious price found before placing an order, which the manager should double check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlacementWarning {
+    /// The price of a special differs from its menu price by more than the tolerance
+    SpecialPriceOutlier {
+        meal_id: Id<Meal>,
+        description: String,
+        price: Money,
+        menu_price: Money,
+    },
+    /// The price of a special is above the cap
+    SpecialPriceAboveCap {
+        meal_id: Id<Meal>,
+        description: String,
+        price: Money,
+        cap: Money,
+    },
+}
+
+impl fmt::Display for PlacementWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PlacementWarning::*;
+        match self {
+            SpecialPriceOutlier {
+                meal_id,
+                description,
+                price,
+                menu_price,
+            } => write!(
+                f,
+                "special {} of meal {} costs {} but {} on the menu",
+                description,
+                meal_id.get_value(),
+                price,
+                menu_price
+            ),
+            SpecialPriceAboveCap {
+                meal_id,
+                description,
+                price,
+                cap,
+            } => write!(
+                f,
+                "special {} of meal {} costs {}, more than {}",
+                description,
+                meal_id.get_value(),
+                price,
+                cap
+            ),
+        }
+    }
+}
+
+/// Result of `validate_placement`, the order can be placed anyway.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlacementReport {
+    /// Sorted by meal ID
+    warnings: Vec<PlacementWarning>,
+}
+
+impl PlacementReport {
+    pub fn warnings(&self) -> &[PlacementWarning] {
+        &self.warnings
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Checks the prices of the specials of all meals against the menu of the order and the `rules`.
+///
+/// Specials without price or not on the menu are only checked against the cap.
+pub fn validate_placement(order: &Order, rules: &SpecialPriceRules) -> PlacementReport {
+    let mut meals: Vec<&Meal> = order.all_meals().collect();
+    meals.sort_by_key(|meal| meal.get_id().get_value());
+    let mut warnings = Vec::new();
+    for meal in meals {
+        let mut specials: Vec<(String, Money)> = meal
+            .specials()
+            .filter_map(|special| {
+                special
+                    .get_price()
+                    .map(|price| (special.get_description(), price))
+            })
+            .collect();
+        specials.sort();
+        for (description, price) in specials {
+            let menu_price = order
+                .get_menu()
+                .and_then(|menu| menu.get_special_price(&description));
+            if let Some(menu_price) =
+                menu_price.filter(|menu_price| rules.is_outlier(price, *menu_price))
+            {
+                warnings.push(PlacementWarning::SpecialPriceOutlier {
+                    meal_id: meal.get_id(),
+                    description: description.clone(),
+                    price,
+                    menu_price,
+                });
+            }
+            if let Some(cap) = rules.cap.filter(|cap| price > *cap) {
+                warnings.push(PlacementWarning::SpecialPriceAboveCap {
+                    meal_id: meal.get_id(),
+                    description,
+                    price,
+                    cap,
+                });
+            }
+        }
+    }
+    PlacementReport { warnings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::catalog::Menu;
+    use crate::menu::item::MenuItem;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    fn order_with_special(price: Money) -> Order {
+        let mut menu = Menu::new(String::from("Mario"));
+        let mut item = MenuItem::new(String::from("03"), String::from("Margherita"));
+        item.set_price(String::from("groß"), Money::new(7, 0));
+        menu.add_item(item);
+        menu.set_special_price("Käserand", Money::new(1, 50));
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(Arc::new(menu));
+        let meal = order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 0),
+            )
+            .unwrap()
+            .get_id();
+        order
+            .add_special_for_user(Id::new(0), meal, String::from("käserand"), Some(price))
+            .unwrap();
+        order
+    }
+
+    #[rstest(
+        price,
+        outlier,
+        case(Money::new(1, 50), false),
+        case(Money::new(1, 87), false),
+        case(Money::new(1, 88), true),
+        case(Money::new(1, 12), true),
+        case(Money::new(15, 0), true)
+    )]
+    fn special_price_is_validated_against_menu(price: Money, outlier: bool) {
+        // Given:
+        let order = order_with_special(price);
+
+        // When:
+        let report = validate_placement(&order, &SpecialPriceRules::default());
+
+        // Then:
+        assert_eq!(
+            report.warnings(),
+            if outlier {
+                vec![PlacementWarning::SpecialPriceOutlier {
+                    meal_id: Id::new(0),
+                    description: String::from("käserand"),
+                    price,
+                    menu_price: Money::new(1, 50),
+                }]
+            } else {
+                vec![]
+            }
+            .as_slice()
+        );
+    }
+
+    #[test]
+    fn special_price_above_cap_is_flagged() {
+        // Given:
+        let order = order_with_special(Money::new(15, 0));
+        let rules = SpecialPriceRules::new(1000, Some(Money::new(5, 0)));
+
+        // When:
+        let report = validate_placement(&order, &rules);
+
+        // Then:
+        assert!(!report.is_clean());
+        assert_eq!(
+            report
+                .warnings()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["special käserand of meal 0 costs 15,00€, more than 5,00€"]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// How the kitchen should prepare a meal, which unlike a `Special` costs nothing extra.
+///
+/// Restaurants declare which preparations they can do on their `Menu`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Preparation {
+    WellDone,
+    GlutenFreeBase,
+    LactoseFree,
+    /// The pizza is delivered in one piece
+    Unsliced,
+}
+
+impl Preparation {
+    pub const ALL: [Preparation; 4] = [
+        Preparation::WellDone,
+        Preparation::GlutenFreeBase,
+        Preparation::LactoseFree,
+        Preparation::Unsliced,
+    ];
+
+    /// Code used in requests, e.g. "gluten-free-base".
+    pub fn get_code(&self) -> &'static str {
+        use Preparation::*;
+        match self {
+            WellDone => "well-done",
+            GlutenFreeBase => "gluten-free-base",
+            LactoseFree => "lactose-free",
+            Unsliced => "unsliced",
+        }
+    }
+}
+
+/// Written as the kitchen reads it, e.g. "gluten-free base".
+impl fmt::Display for Preparation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Preparation::*;
+        match self {
+            WellDone => write!(f, "well done"),
+            GlutenFreeBase => write!(f, "gluten-free base"),
+            LactoseFree => write!(f, "lactose-free"),
+            Unsliced => write!(f, "unsliced"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownPreparationError(String);
+
+impl fmt::Display for UnknownPreparationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown preparation {}", self.0)
+    }
+}
+
+impl Error for UnknownPreparationError {}
+
+/// Parses the code of `get_code`, ignoring case.
+impl FromStr for Preparation {
+    type Err = UnknownPreparationError;
+
+    fn from_str(code: &str) -> Result<Preparation, UnknownPreparationError> {
+        let code = code.trim().to_lowercase();
+        Preparation::ALL
+            .iter()
+            .find(|preparation| preparation.get_code() == code)
+            .copied()
+            .ok_or(UnknownPreparationError(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        code,
+        expected,
+        case("well-done", Ok(Preparation::WellDone)),
+        case(" Gluten-Free-Base", Ok(Preparation::GlutenFreeBase)),
+        case(
+            "extra crispy",
+            Err(UnknownPreparationError(String::from("extra crispy")))
+        )
+    )]
+    fn preparation_is_parsed_from_code(
+        code: &str,
+        expected: Result<Preparation, UnknownPreparationError>,
+    ) {
+        assert_eq!(code.parse::<Preparation>(), expected);
+    }
+
+    #[test]
+    fn every_code_is_parsed_back() {
+        for preparation in Preparation::ALL.iter() {
+            assert_eq!(preparation.get_code().parse(), Ok(*preparation));
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::order_model::meal::Meal;
+use crate::order_model::order::{Order, OrderError};
+use crate::order_model::report::{PaymentReport, UserPayment};
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// What a user has to pay before and after a change, including their fee share and tip.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DueChange {
+    user_id: Id<User>,
+    /// Zero if the user was not participating before
+    old_due: Money,
+    /// Zero if the user is no longer participating
+    new_due: Money,
+}
+
+impl DueChange {
+    pub fn get_user_id(&self) -> Id<User> {
+        self.user_id.clone()
+    }
+
+    pub fn get_old_due(&self) -> Money {
+        self.old_due
+    }
+
+    pub fn get_new_due(&self) -> Money {
+        self.new_due
+    }
+}
+
+impl fmt::Display for DueChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "user {}: {} -> {}",
+            self.user_id.get_value(),
+            self.old_due,
+            self.new_due
+        )
+    }
+}
+
+/// Differences between two states of an order, e.g. before and after a bulk operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderDiff {
+    /// Users whose due amount changed, sorted by ID
+    dues: Vec<DueChange>,
+    /// IDs of the meals which were added, removed or changed, sorted ascending
+    changed_meals: Vec<Id<Meal>>,
+    old_total: Money,
+    new_total: Money,
+}
+
+impl OrderDiff {
+    pub fn between(old: &Order, new: &Order) -> OrderDiff {
+        let old_report = old.payment_report();
+        let new_report = new.payment_report();
+        let user_ids: BTreeSet<u32> = old_report
+            .users()
+            .iter()
+            .chain(new_report.users())
+            .map(|user| user.get_user_id().get_value())
+            .collect();
+        let due = |report: &PaymentReport, user_id: &Id<User>| {
+            report
+                .get_user(user_id)
+                .map_or(Money::zero(), UserPayment::get_due)
+        };
+        let dues = user_ids
+            .into_iter()
+            .map(Id::new)
+            .map(|user_id| DueChange {
+                old_due: due(&old_report, &user_id),
+                new_due: due(&new_report, &user_id),
+                user_id,
+            })
+            .filter(|change| change.old_due != change.new_due)
+            .collect();
+
+        let old_meals = meals_by_id(old);
+        let new_meals = meals_by_id(new);
+        let meal_ids: BTreeSet<u32> = old_meals
+            .keys()
+            .chain(new_meals.keys())
+            .map(Id::get_value)
+            .collect();
+        let changed_meals = meal_ids
+            .into_iter()
+            .map(Id::new)
+            .filter(|id| old_meals.get(id) != new_meals.get(id))
+            .collect();
+
+        OrderDiff {
+            dues,
+            changed_meals,
+            old_total: old_report.get_total_price(),
+            new_total: new_report.get_total_price(),
+        }
+    }
+
+    pub fn dues(&self) -> &[DueChange] {
+        &self.dues
+    }
+
+    pub fn changed_meals(&self) -> &[Id<Meal>] {
+        &self.changed_meals
+    }
+
+    pub fn get_old_total(&self) -> Money {
+        self.old_total
+    }
+
+    pub fn get_new_total(&self) -> Money {
+        self.new_total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dues.is_empty() && self.changed_meals.is_empty() && self.old_total == self.new_total
+    }
+}
+
+impl fmt::Display for OrderDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "total: {} -> {}, {} meals changed",
+            self.old_total,
+            self.new_total,
+            self.changed_meals.len()
+        )?;
+        for due in &self.dues {
+            writeln!(f, "{}", due)?;
+        }
+        Ok(())
+    }
+}
+
+fn meals_by_id(order: &Order) -> HashMap<Id<Meal>, &Meal> {
+    order
+        .all_meals()
+        .map(|meal| (meal.get_id(), meal))
+        .collect()
+}
+
+/// A change staged on a copy of an order, which is only committed once the caller confirmed its `OrderDiff`.
+#[derive(Debug, PartialEq)]
+pub struct Preview {
+    /// The order as the change was staged on
+    before: Order,
+    after: Order,
+    diff: OrderDiff,
+}
+
+impl Preview {
+    /// Stages the `change` on a copy of the `order`, leaving the order itself untouched.
+    pub fn stage<F>(order: &Order, change: F) -> Result<Preview, OrderError>
+    where
+        F: FnOnce(&mut Order) -> Result<(), OrderError>,
+    {
+        let mut after = order.clone();
+        change(&mut after)?;
+        Ok(Preview {
+            diff: OrderDiff::between(order, &after),
+            before: order.clone(),
+            after,
+        })
+    }
+
+    pub fn get_diff(&self) -> &OrderDiff {
+        &self.diff
+    }
+
+    /// Commits the staged change, unless the order was changed since the preview was made.
+    pub fn apply(self, order: &mut Order) -> Result<(), OrderError> {
+        if *order != self.before {
+            return Err(OrderError::StalePreview);
+        }
+        *order = self.after;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::fee::FeeSplitStrategy;
+
+    fn order_with_meals() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        for user_id in 0..2 {
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(5, 50),
+                )
+                .unwrap();
+        }
+        order
+    }
+
+    #[test]
+    fn preview_shows_diff_without_changing_order() {
+        // Given:
+        let order = order_with_meals();
+
+        // When:
+        let preview = Preview::stage(&order, |order| {
+            order.set_delivery_fee(Money::new(2, 1), FeeSplitStrategy::Equal)
+        })
+        .unwrap();
+
+        // Then:
+        let diff = preview.get_diff();
+        assert_eq!(diff.get_old_total(), Money::new(11, 0));
+        assert_eq!(diff.get_new_total(), Money::new(13, 1));
+        assert_eq!(
+            diff.dues(),
+            &[
+                DueChange {
+                    user_id: Id::new(0),
+                    old_due: Money::new(5, 50),
+                    new_due: Money::new(6, 51)
+                },
+                DueChange {
+                    user_id: Id::new(1),
+                    old_due: Money::new(5, 50),
+                    new_due: Money::new(6, 50)
+                }
+            ]
+        );
+        assert!(diff.changed_meals().is_empty());
+        assert_eq!(order.get_delivery_fee(), Money::zero());
+    }
+
+    #[test]
+    fn confirmed_preview_is_applied() {
+        // Given:
+        let mut order = order_with_meals();
+        let preview = Preview::stage(&order, |order| {
+            order.add_special_to_all_meals(String::from("extra Käse"))?;
+            Ok(())
+        })
+        .unwrap();
+
+        // When:
+        let result = preview.apply(&mut order);
+
+        // Then:
+        assert_eq!(result, Ok(()));
+        assert!(order.all_meals().all(|meal| meal.specials().count() == 1));
+    }
+
+    #[test]
+    fn changed_meals_are_listed() {
+        // Given:
+        let order = order_with_meals();
+
+        // When:
+        let preview = Preview::stage(&order, |order| {
+            order.add_special_to_all_meals(String::from("extra Käse"))?;
+            Ok(())
+        })
+        .unwrap();
+
+        // Then:
+        assert_eq!(
+            preview.get_diff().changed_meals(),
+            &[Id::new(0), Id::new(1)]
+        );
+        assert!(preview.get_diff().dues().is_empty());
+    }
+
+    #[test]
+    fn stale_preview_is_rejected() {
+        // Given:
+        let mut order = order_with_meals();
+        let preview = Preview::stage(&order, |order| {
+            order.set_delivery_fee(Money::new(2, 0), FeeSplitStrategy::ManagerPays)
+        })
+        .unwrap();
+        order
+            .set_paid_for_user(Id::new(1), Money::new(5, 50))
+            .unwrap();
+
+        // When:
+        let result = preview.apply(&mut order);
+
+        // Then:
+        assert_eq!(result, Err(OrderError::StalePreview));
+        assert_eq!(order.get_delivery_fee(), Money::zero());
+    }
+
+    #[test]
+    fn failing_change_cannot_be_previewed() {
+        // Given:
+        let mut order = order_with_meals();
+        order.cancel().unwrap();
+
+        // When:
+        let preview = Preview::stage(&order, |order| {
+            order.set_delivery_fee(Money::new(2, 0), FeeSplitStrategy::Equal)
+        });
+
+        // Then:
+        assert_eq!(preview, Err(OrderError::WrongStatus));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::fmt;
+
+/// What is left to settle between a participant and the manager.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Balance {
+    /// The participant paid enough and gets the contained change back
+    Change(Money),
+    /// The participant still has to pay the contained amount
+    Owed(Money),
+}
+
+impl Balance {
+    /// Balance of somebody who has to pay `due` and already paid `paid`.
+    pub fn new(due: Money, paid: Money) -> Balance {
+        if paid < due {
+            Balance::Owed(due - paid)
+        } else {
+            Balance::Change(paid - due)
+        }
+    }
+}
+
+/// An amount split among the participants of an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitAmount {
+    DeliveryFee,
+    SharedMeals,
+}
+
+impl fmt::Display for SplitAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SplitAmount::DeliveryFee => write!(f, "delivery fee"),
+            SplitAmount::SharedMeals => write!(f, "shared meals"),
+        }
+    }
+}
+
+/// Cents a participant pays on top of their exact share of a split amount, rounded down, so the shares add up to
+/// the amount in whole cents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundingAdjustment {
+    split: SplitAmount,
+    amount: Money,
+}
+
+impl RoundingAdjustment {
+    pub fn new(split: SplitAmount, amount: Money) -> RoundingAdjustment {
+        RoundingAdjustment { split, amount }
+    }
+
+    pub fn get_split(&self) -> SplitAmount {
+        self.split
+    }
+
+    pub fn get_amount(&self) -> Money {
+        self.amount
+    }
+}
+
+/// Settlement of a single participant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserPayment {
+    user_id: Id<User>,
+    meal_price: Money,
+    /// Share of the delivery fee
+    fee_share: Money,
+    /// Share of the meals shared by all participants
+    shared_share: Money,
+    tip: Money,
+    paid: Money,
+    balance: Balance,
+    /// Cents added to the shares by rounding, already part of them
+    adjustments: Vec<RoundingAdjustment>,
+}
+
+impl UserPayment {
+    pub fn new(
+        user_id: Id<User>,
+        meal_price: Money,
+        fee_share: Money,
+        shared_share: Money,
+        tip: Money,
+        paid: Money,
+    ) -> UserPayment {
+        let balance = Balance::new(meal_price + fee_share + shared_share + tip, paid);
+        UserPayment {
+            user_id,
+            meal_price,
+            fee_share,
+            shared_share,
+            tip,
+            paid,
+            balance,
+            adjustments: Vec::new(),
+        }
+    }
+
+    /// Records the cents added to the shares of the participant by rounding, see `RoundingAdjustment`.
+    pub fn with_adjustments(mut self, adjustments: Vec<RoundingAdjustment>) -> UserPayment {
+        self.adjustments = adjustments;
+        self
+    }
+
+    pub fn get_user_id(&self) -> Id<User> {
+        self.user_id.clone()
+    }
+
+    pub fn get_meal_price(&self) -> Money {
+        self.meal_price
+    }
+
+    pub fn get_fee_share(&self) -> Money {
+        self.fee_share
+    }
+
+    pub fn get_shared_share(&self) -> Money {
+        self.shared_share
+    }
+
+    pub fn get_tip(&self) -> Money {
+        self.tip
+    }
+
+    /// Everything the participant has to pay: their meals, their shares and their tip.
+    pub fn get_due(&self) -> Money {
+        self.meal_price + self.fee_share + self.shared_share + self.tip
+    }
+
+    pub fn get_paid(&self) -> Money {
+        self.paid
+    }
+
+    pub fn get_balance(&self) -> Balance {
+        self.balance
+    }
+
+    pub fn adjustments(&self) -> &[RoundingAdjustment] {
+        &self.adjustments
+    }
+}
+
+/// Settlement of all participants of an order together with the order-wide totals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentReport {
+    /// Settlements sorted by user ID
+    users: Vec<UserPayment>,
+    /// Price of the meals paid from the office budget
+    office_price: Money,
+}
+
+impl PaymentReport {
+    pub fn new(mut users: Vec<UserPayment>, office_price: Money) -> PaymentReport {
+        users.sort_by_key(|user| user.user_id.get_value());
+        PaymentReport {
+            users,
+            office_price,
+        }
+    }
+
+    pub fn users(&self) -> &[UserPayment] {
+        &self.users
+    }
+
+    pub fn get_user(&self, user_id: &Id<User>) -> Option<&UserPayment> {
+        self.users.iter().find(|user| &user.user_id == user_id)
+    }
+
+    pub fn get_office_price(&self) -> Money {
+        self.office_price
+    }
+
+    /// Price of everything ordered at the restaurant, including office and shared meals and the delivery fee.
+    pub fn get_total_price(&self) -> Money {
+        self.sum(|user| user.meal_price)
+            + self.get_total_fee()
+            + self.get_total_shared()
+            + self.office_price
+    }
+
+    pub fn get_total_fee(&self) -> Money {
+        self.sum(|user| user.fee_share)
+    }
+
+    /// Price of the shared meals, as split among the participants.
+    pub fn get_total_shared(&self) -> Money {
+        self.sum(|user| user.shared_share)
+    }
+
+    pub fn get_total_tip(&self) -> Money {
+        self.sum(|user| user.tip)
+    }
+
+    pub fn get_total_paid(&self) -> Money {
+        self.sum(|user| user.paid)
+    }
+
+    /// Cents added to the shares of all participants by rounding, see `RoundingAdjustment`.
+    pub fn get_total_adjustment(&self) -> Money {
+        self.sum(|user| {
+            user.adjustments
+                .iter()
+                .fold(Money::zero(), |total, adjustment| total + adjustment.amount)
+        })
+    }
+
+    /// Whether what the participants pay without tips, together with the office meals, is exactly the `bill` of
+    /// the restaurant, i.e. no cent was lost or added when splitting.
+    pub fn adds_up_to(&self, bill: Money) -> bool {
+        self.get_total_price() == bill
+    }
+
+    /// Change the manager has to hand out to the participants who paid too much.
+    pub fn get_total_change(&self) -> Money {
+        self.sum(|user| match user.balance {
+            Balance::Change(change) => change,
+            Balance::Owed(_) => Money::zero(),
+        })
+    }
+
+    /// Money the manager still has to collect from the participants who paid too little.
+    pub fn get_total_owed(&self) -> Money {
+        self.sum(|user| match user.balance {
+            Balance::Change(_) => Money::zero(),
+            Balance::Owed(owed) => owed,
+        })
+    }
+
+    fn sum(&self, value: impl Fn(&UserPayment) -> Money) -> Money {
+        let mut sum = Money::zero();
+        for user in &self.users {
+            sum += value(user);
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        paid,
+        expected,
+        case(Money::new(10, 0), Balance::Change(Money::new(3, 50))),
+        case(Money::new(6, 50), Balance::Change(Money::zero())),
+        case(Money::new(5, 0), Balance::Owed(Money::new(1, 50)))
+    )]
+    fn balance_of_user_is_calculated(paid: Money, expected: Balance) {
+        // When:
+        let user = UserPayment::new(
+            Id::new(0),
+            Money::new(5, 0),
+            Money::new(0, 50),
+            Money::zero(),
+            Money::new(1, 0),
+            paid,
+        );
+
+        // Then:
+        assert_eq!(user.get_balance(), expected);
+    }
+
+    #[test]
+    fn totals_are_summed_over_users() {
+        // Given:
+        let users = vec![
+            UserPayment::new(
+                Id::new(1),
+                Money::new(5, 0),
+                Money::new(0, 50),
+                Money::zero(),
+                Money::new(0, 50),
+                Money::new(5, 0),
+            ),
+            UserPayment::new(
+                Id::new(0),
+                Money::new(8, 0),
+                Money::zero(),
+                Money::zero(),
+                Money::new(1, 0),
+                Money::new(10, 0),
+            ),
+        ];
+
+        // When:
+        let report = PaymentReport::new(users, Money::new(4, 0));
+
+        // Then:
+        assert_eq!(
+            report
+                .users()
+                .iter()
+                .map(|user| user.get_user_id().get_value())
+                .collect::<Vec<u32>>(),
+            vec![0, 1]
+        );
+        assert_eq!(report.get_total_price(), Money::new(17, 50));
+        assert_eq!(report.get_total_fee(), Money::new(0, 50));
+        assert_eq!(report.get_total_tip(), Money::new(1, 50));
+        assert_eq!(report.get_total_paid(), Money::new(15, 0));
+        assert_eq!(report.get_total_change(), Money::new(1, 0));
+        assert_eq!(report.get_total_owed(), Money::new(1, 0));
+    }
+
+    #[test]
+    fn rounding_adjustments_are_summed_over_users() {
+        // Given:
+        let users = vec![
+            UserPayment::new(
+                Id::new(0),
+                Money::new(4, 0),
+                Money::new(0, 34),
+                Money::new(1, 1),
+                Money::zero(),
+                Money::zero(),
+            )
+            .with_adjustments(vec![
+                RoundingAdjustment::new(SplitAmount::DeliveryFee, Money::new(0, 1)),
+                RoundingAdjustment::new(SplitAmount::SharedMeals, Money::new(0, 1)),
+            ]),
+            UserPayment::new(
+                Id::new(1),
+                Money::new(4, 0),
+                Money::new(0, 33),
+                Money::new(1, 0),
+                Money::zero(),
+                Money::zero(),
+            ),
+        ];
+
+        // When:
+        let report = PaymentReport::new(users, Money::zero());
+
+        // Then:
+        assert_eq!(report.get_total_adjustment(), Money::new(0, 2));
+        assert!(report.adds_up_to(Money::new(10, 68)));
+        assert!(!report.adds_up_to(Money::new(10, 67)));
+    }
+}
@@ -0,0 +1,425 @@
+use crate::util::money::Money;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, TimeZone, Utc, Weekday};
+use std::error::Error;
+use std::fmt;
+
+/// Why an order can't be placed at its restaurant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RestaurantError {
+    /// The meals cost less than the restaurant delivers for, the delivery fee not included
+    BelowMinimumOrderValue { total: Money, minimum: Money },
+    /// The restaurant is closed at the time the order is placed
+    Closed,
+}
+
+impl fmt::Display for RestaurantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use RestaurantError::*;
+        match self {
+            BelowMinimumOrderValue { total, minimum } => write!(
+                f,
+                "meals cost {} but the restaurant delivers from {}",
+                total, minimum
+            ),
+            Closed => write!(f, "restaurant is closed"),
+        }
+    }
+}
+
+impl Error for RestaurantError {}
+
+/// Time span in which the restaurant takes orders on a day of the week.
+///
+/// A period closing at or before it opens lasts past midnight into the next day, e.g. 18:00 to 01:00.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpeningPeriod {
+    day: Weekday,
+    opens: NaiveTime,
+    closes: NaiveTime,
+}
+
+impl OpeningPeriod {
+    pub fn new(day: Weekday, opens: NaiveTime, closes: NaiveTime) -> OpeningPeriod {
+        OpeningPeriod { day, opens, closes }
+    }
+
+    pub fn get_day(&self) -> Weekday {
+        self.day
+    }
+
+    pub fn get_opens(&self) -> NaiveTime {
+        self.opens
+    }
+
+    pub fn get_closes(&self) -> NaiveTime {
+        self.closes
+    }
+
+    /// The period ending at the cutoff if that is earlier than its closing, e.g. to stop taking orders at 20:30.
+    fn until(&self, cutoff: NaiveTime) -> OpeningPeriod {
+        let length = |end: NaiveTime| {
+            let length = end - self.opens;
+            if length <= Duration::zero() {
+                length + Duration::days(1)
+            } else {
+                length
+            }
+        };
+        if length(cutoff) < length(self.closes) {
+            OpeningPeriod {
+                closes: cutoff,
+                ..*self
+            }
+        } else {
+            *self
+        }
+    }
+
+    fn contains(&self, day: Weekday, time: NaiveTime) -> bool {
+        if self.opens < self.closes {
+            day == self.day && self.opens <= time && time < self.closes
+        } else {
+            (day == self.day && self.opens <= time)
+                || (day == self.day.succ() && time < self.closes)
+        }
+    }
+}
+
+/// Weekly opening hours in the local time of the restaurant, given as offset to UTC.
+///
+/// Closed days and cutoffs narrow the periods down, e.g. for a restaurant open every evening but closed on Mondays
+/// and taking its last orders on Sundays at 20:30.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpeningHours {
+    utc_offset: FixedOffset,
+    periods: Vec<OpeningPeriod>,
+    /// Days on which no period opens
+    closed_days: Vec<Weekday>,
+    /// Last time orders are taken in the periods opening on the day, if earlier than their closing
+    cutoffs: Vec<(Weekday, NaiveTime)>,
+}
+
+impl OpeningHours {
+    /// Opening hours without any period, i.e. always closed until periods are added.
+    pub fn new(utc_offset: FixedOffset) -> OpeningHours {
+        OpeningHours {
+            utc_offset,
+            periods: Vec::new(),
+            closed_days: Vec::new(),
+            cutoffs: Vec::new(),
+        }
+    }
+
+    pub fn with_period(mut self, period: OpeningPeriod) -> OpeningHours {
+        self.periods.push(period);
+        self
+    }
+
+    pub fn with_closed_day(mut self, day: Weekday) -> OpeningHours {
+        if !self.closed_days.contains(&day) {
+            self.closed_days.push(day);
+        }
+        self
+    }
+
+    /// Sets the last time orders are taken on the day, replacing the one set before.
+    pub fn with_cutoff(mut self, day: Weekday, cutoff: NaiveTime) -> OpeningHours {
+        self.cutoffs.retain(|(other, _)| *other != day);
+        self.cutoffs.push((day, cutoff));
+        self
+    }
+
+    pub fn get_utc_offset(&self) -> FixedOffset {
+        self.utc_offset
+    }
+
+    pub fn periods(&self) -> &[OpeningPeriod] {
+        &self.periods
+    }
+
+    pub fn closed_days(&self) -> &[Weekday] {
+        &self.closed_days
+    }
+
+    pub fn cutoffs(&self) -> &[(Weekday, NaiveTime)] {
+        &self.cutoffs
+    }
+
+    pub fn get_cutoff(&self, day: Weekday) -> Option<NaiveTime> {
+        self.cutoffs
+            .iter()
+            .find(|(other, _)| *other == day)
+            .map(|(_, cutoff)| *cutoff)
+    }
+
+    /// Whether orders are taken at the given time, i.e. within a period and before the cutoff of its day.
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        let local = at.with_timezone(&self.utc_offset);
+        self.ordering_periods()
+            .any(|period| period.contains(local.weekday(), local.time()))
+    }
+
+    /// The time at which the restaurant stops taking orders again, `None` if it doesn't take any at the given time.
+    pub fn last_order_time(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = at.with_timezone(&self.utc_offset);
+        self.ordering_periods()
+            .filter(|period| period.contains(local.weekday(), local.time()))
+            .map(|period| {
+                // The period contains the time, so it closes at the next occurrence of its closing time
+                let mut date = local.date_naive();
+                if period.closes <= local.time() {
+                    date = date.succ_opt().expect("Dates this close to now exist");
+                }
+                self.utc_offset
+                    .from_local_datetime(&date.and_time(period.closes))
+                    .single()
+                    .expect("Fixed offsets map local times unambiguously")
+                    .with_timezone(&Utc)
+            })
+            .max()
+    }
+
+    /// The periods without the closed days, each ending at the cutoff of its day.
+    fn ordering_periods(&self) -> impl Iterator<Item = OpeningPeriod> + '_ {
+        self.periods
+            .iter()
+            .filter(move |period| !self.closed_days.contains(&period.day))
+            .map(move |period| match self.get_cutoff(period.day) {
+                Some(cutoff) => period.until(cutoff),
+                None => *period,
+            })
+    }
+}
+
+/// The restaurant an order goes to, with what it takes to order there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Restaurant {
+    name: String,
+    phone: Option<String>,
+    /// Least the meals have to cost for the restaurant to deliver, zero if there is no minimum
+    min_order_value: Money,
+    delivery_fee: Money,
+    /// `None` if unknown, orders can be placed any time then
+    opening_hours: Option<OpeningHours>,
+}
+
+impl Restaurant {
+    pub fn new(name: String) -> Restaurant {
+        Restaurant {
+            name,
+            phone: None,
+            min_order_value: Money::zero(),
+            delivery_fee: Money::zero(),
+            opening_hours: None,
+        }
+    }
+
+    pub fn with_phone(mut self, phone: String) -> Restaurant {
+        self.phone = Some(phone);
+        self
+    }
+
+    pub fn with_min_order_value(mut self, min_order_value: Money) -> Restaurant {
+        self.min_order_value = min_order_value;
+        self
+    }
+
+    pub fn with_delivery_fee(mut self, delivery_fee: Money) -> Restaurant {
+        self.delivery_fee = delivery_fee;
+        self
+    }
+
+    pub fn with_opening_hours(mut self, opening_hours: OpeningHours) -> Restaurant {
+        self.opening_hours = Some(opening_hours);
+        self
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_phone(&self) -> Option<&String> {
+        self.phone.as_ref()
+    }
+
+    pub fn get_min_order_value(&self) -> Money {
+        self.min_order_value
+    }
+
+    pub fn get_delivery_fee(&self) -> Money {
+        self.delivery_fee
+    }
+
+    pub fn get_opening_hours(&self) -> Option<&OpeningHours> {
+        self.opening_hours.as_ref()
+    }
+
+    /// Whether the restaurant takes orders at the given time, always if its opening hours are unknown.
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        self.opening_hours
+            .as_ref()
+            .is_none_or(|hours| hours.is_open(at))
+    }
+
+    /// When the restaurant stops taking orders again, `None` if it doesn't take any at the given time or its opening
+    /// hours are unknown.
+    pub fn last_order_time(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.opening_hours
+            .as_ref()
+            .and_then(|hours| hours.last_order_time(at))
+    }
+
+    /// Checks that meals for `total` can be ordered at the given time.
+    pub fn check_order(&self, total: Money, at: DateTime<Utc>) -> Result<(), RestaurantError> {
+        if total < self.min_order_value {
+            return Err(RestaurantError::BelowMinimumOrderValue {
+                total,
+                minimum: self.min_order_value,
+            });
+        }
+        if !self.is_open(at) {
+            return Err(RestaurantError::Closed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// Lunch on Mondays and late evenings on Fridays, in UTC+2.
+    fn opening_hours() -> OpeningHours {
+        OpeningHours::new(FixedOffset::east_opt(2 * 60 * 60).unwrap())
+            .with_period(OpeningPeriod::new(Weekday::Mon, time(11, 30), time(14, 0)))
+            .with_period(OpeningPeriod::new(Weekday::Fri, time(18, 0), time(1, 0)))
+    }
+
+    #[rstest(
+        utc,
+        expected,
+        // Monday, 2020-05-04 at 12:00 local time
+        case(Utc.with_ymd_and_hms(2020, 5, 4, 10, 0, 0).unwrap(), true),
+        // Monday at 14:00 local time, just closed
+        case(Utc.with_ymd_and_hms(2020, 5, 4, 12, 0, 0).unwrap(), false),
+        // Saturday at 00:30 local time, still open from Friday
+        case(Utc.with_ymd_and_hms(2020, 5, 8, 22, 30, 0).unwrap(), true),
+        // Saturday at 01:30 local time
+        case(Utc.with_ymd_and_hms(2020, 5, 8, 23, 30, 0).unwrap(), false)
+    )]
+    fn opening_hours_are_checked_in_local_time(utc: DateTime<Utc>, expected: bool) {
+        assert_eq!(opening_hours().is_open(utc), expected);
+    }
+
+    /// Every evening from 18:00 to 23:00 except Mondays, last orders on Sundays at 20:30, in UTC+2.
+    fn weekly_schedule() -> OpeningHours {
+        let mut hours = OpeningHours::new(FixedOffset::east_opt(2 * 60 * 60).unwrap());
+        for day in [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ] {
+            hours = hours.with_period(OpeningPeriod::new(day, time(18, 0), time(23, 0)));
+        }
+        hours
+            .with_closed_day(Weekday::Mon)
+            .with_cutoff(Weekday::Sun, time(20, 30))
+    }
+
+    #[rstest(
+        utc,
+        expected,
+        // Monday, 2020-05-04 at 19:00 local time, the day off
+        case(Utc.with_ymd_and_hms(2020, 5, 4, 17, 0, 0).unwrap(), false),
+        // Tuesday at 22:00 local time
+        case(Utc.with_ymd_and_hms(2020, 5, 5, 20, 0, 0).unwrap(), true),
+        // Sunday at 20:00 local time, before the cutoff
+        case(Utc.with_ymd_and_hms(2020, 5, 10, 18, 0, 0).unwrap(), true),
+        // Sunday at 21:00 local time, after the cutoff
+        case(Utc.with_ymd_and_hms(2020, 5, 10, 19, 0, 0).unwrap(), false)
+    )]
+    fn closed_days_and_cutoffs_are_respected(utc: DateTime<Utc>, expected: bool) {
+        assert_eq!(weekly_schedule().is_open(utc), expected);
+    }
+
+    #[rstest(
+        utc,
+        expected,
+        // Tuesday at 19:00 local time, closing at 23:00
+        case(
+            Utc.with_ymd_and_hms(2020, 5, 5, 17, 0, 0).unwrap(),
+            Some(Utc.with_ymd_and_hms(2020, 5, 5, 21, 0, 0).unwrap())
+        ),
+        // Sunday at 19:00 local time, last orders at 20:30
+        case(
+            Utc.with_ymd_and_hms(2020, 5, 10, 17, 0, 0).unwrap(),
+            Some(Utc.with_ymd_and_hms(2020, 5, 10, 18, 30, 0).unwrap())
+        ),
+        // Monday, closed
+        case(Utc.with_ymd_and_hms(2020, 5, 4, 17, 0, 0).unwrap(), None)
+    )]
+    fn last_order_time_is_end_of_current_period(
+        utc: DateTime<Utc>,
+        expected: Option<DateTime<Utc>>,
+    ) {
+        assert_eq!(weekly_schedule().last_order_time(utc), expected);
+    }
+
+    #[test]
+    fn cutoff_past_midnight_shortens_overnight_period() {
+        // Given:
+        let hours = opening_hours().with_cutoff(Weekday::Fri, time(0, 0));
+
+        // Then:
+        // Friday at 23:30 and Saturday at 00:30 local time
+        assert!(hours.is_open(Utc.with_ymd_and_hms(2020, 5, 8, 21, 30, 0).unwrap()));
+        assert!(!hours.is_open(Utc.with_ymd_and_hms(2020, 5, 8, 22, 30, 0).unwrap()));
+        assert_eq!(
+            hours.last_order_time(Utc.with_ymd_and_hms(2020, 5, 8, 21, 30, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2020, 5, 8, 22, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn order_below_minimum_value_is_rejected() {
+        // Given:
+        let restaurant = Restaurant::new(String::from("Pizzeria Mario"))
+            .with_min_order_value(Money::new(20, 0))
+            .with_opening_hours(opening_hours());
+        let lunch = Utc.with_ymd_and_hms(2020, 5, 4, 10, 0, 0).unwrap();
+
+        // When:
+        let too_little = restaurant.check_order(Money::new(15, 0), lunch);
+        let closed = restaurant.check_order(Money::new(25, 0), lunch + chrono::Duration::hours(3));
+        let enough = restaurant.check_order(Money::new(25, 0), lunch);
+
+        // Then:
+        assert_eq!(
+            too_little,
+            Err(RestaurantError::BelowMinimumOrderValue {
+                total: Money::new(15, 0),
+                minimum: Money::new(20, 0)
+            })
+        );
+        assert_eq!(closed, Err(RestaurantError::Closed));
+        assert_eq!(enough, Ok(()));
+    }
+
+    #[test]
+    fn restaurant_without_opening_hours_is_always_open() {
+        // Given:
+        let restaurant = Restaurant::new(String::from("Pizzeria Mario"));
+
+        // Then:
+        assert!(restaurant.is_open(Utc.with_ymd_and_hms(2020, 5, 4, 3, 0, 0).unwrap()));
+    }
+}
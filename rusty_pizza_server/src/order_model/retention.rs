@@ -0,0 +1,166 @@
+use crate::order_model::order::Order;
+use crate::util::id::Id;
+use std::time::Duration;
+
+/// How long archived orders are kept, e.g. anonymized after a year and deleted after two, and when delivered orders
+/// are archived.
+///
+/// The age of an archived order is counted from its creation. Orders which are still active are only ever archived,
+/// counted from their delivery.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// `None` keeps delivered orders active until they are archived explicitly
+    archive_after: Option<Duration>,
+    /// `None` keeps the participants forever
+    anonymize_after: Option<Duration>,
+    /// `None` keeps the orders forever
+    delete_after: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    pub fn new(
+        anonymize_after: Option<Duration>,
+        delete_after: Option<Duration>,
+    ) -> RetentionPolicy {
+        RetentionPolicy {
+            archive_after: None,
+            anonymize_after,
+            delete_after,
+        }
+    }
+
+    /// Archives delivered orders once the given time passed since their delivery.
+    pub fn with_archive_after(mut self, archive_after: Duration) -> RetentionPolicy {
+        self.archive_after = Some(archive_after);
+        self
+    }
+
+    pub fn get_archive_after(&self) -> Option<Duration> {
+        self.archive_after
+    }
+
+    pub fn get_anonymize_after(&self) -> Option<Duration> {
+        self.anonymize_after
+    }
+
+    pub fn get_delete_after(&self) -> Option<Duration> {
+        self.delete_after
+    }
+
+    /// Whether the policy keeps everything as it is.
+    pub fn keeps_all(&self) -> bool {
+        self.archive_after.is_none()
+            && self.anonymize_after.is_none()
+            && self.delete_after.is_none()
+    }
+
+    /// Whether an order delivered the given time ago has to be archived.
+    pub fn archives(&self, since_delivery: Duration) -> bool {
+        self.archive_after
+            .is_some_and(|limit| since_delivery >= limit)
+    }
+
+    /// What has to happen to an archived order of the given age, `None` if it is kept as it is.
+    pub fn action_for(&self, age: Duration) -> Option<RetentionAction> {
+        if self.delete_after.is_some_and(|limit| age >= limit) {
+            Some(RetentionAction::Delete)
+        } else if self.anonymize_after.is_some_and(|limit| age >= limit) {
+            Some(RetentionAction::Anonymize)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetentionAction {
+    /// See `Order::anonymize`
+    Anonymize,
+    Delete,
+}
+
+/// Orders archived or purged by enforcing a `RetentionPolicy`, all lists sorted ascending.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetentionReport<V = u32> {
+    archived: Vec<Id<Order, V>>,
+    anonymized: Vec<Id<Order, V>>,
+    deleted: Vec<Id<Order, V>>,
+}
+
+impl<V> RetentionReport<V> {
+    pub fn new(anonymized: Vec<Id<Order, V>>, deleted: Vec<Id<Order, V>>) -> RetentionReport<V> {
+        RetentionReport {
+            archived: Vec::new(),
+            anonymized,
+            deleted,
+        }
+    }
+
+    pub fn with_archived(mut self, archived: Vec<Id<Order, V>>) -> RetentionReport<V> {
+        self.archived = archived;
+        self
+    }
+
+    /// Delivered orders moved to the archive.
+    pub fn archived(&self) -> &[Id<Order, V>] {
+        &self.archived
+    }
+
+    pub fn anonymized(&self) -> &[Id<Order, V>] {
+        &self.anonymized
+    }
+
+    pub fn deleted(&self) -> &[Id<Order, V>] {
+        &self.deleted
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archived.is_empty() && self.anonymized.is_empty() && self.deleted.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[rstest(
+        days,
+        expected,
+        case(364, None),
+        case(365, Some(RetentionAction::Anonymize)),
+        case(730, Some(RetentionAction::Delete))
+    )]
+    fn action_depends_on_age(days: u32, expected: Option<RetentionAction>) {
+        // Given:
+        let policy = RetentionPolicy::new(Some(365 * DAY), Some(730 * DAY));
+
+        // Then:
+        assert_eq!(policy.action_for(days * DAY), expected);
+    }
+
+    #[test]
+    fn delivered_orders_are_archived_after_configured_time() {
+        // Given:
+        let policy = RetentionPolicy::default().with_archive_after(7 * DAY);
+
+        // Then:
+        assert!(!policy.keeps_all());
+        assert!(!policy.archives(6 * DAY));
+        assert!(policy.archives(7 * DAY));
+        assert_eq!(policy.action_for(7 * DAY), None);
+    }
+
+    #[test]
+    fn default_policy_keeps_all_orders() {
+        // Given:
+        let policy = RetentionPolicy::default();
+
+        // Then:
+        assert!(policy.keeps_all());
+        assert_eq!(policy.action_for(100 * 365 * DAY), None);
+        assert!(!policy.archives(100 * 365 * DAY));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::order_model::fee::{split_fee, FeeSplitStrategy};
+use crate::order_model::order::Order;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A "what if" for collecting the money of an order, leaving the order itself untouched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scenario {
+    /// Everything as currently configured
+    Current,
+    /// Everybody rounds what they owe up to a multiple of the given amount, the surplus going to the manager
+    RoundUp(Money),
+    /// The company pays the delivery fee, so the participants only pay their meals and tips
+    CompanyPaysFee,
+    /// The delivery fee is split with another strategy
+    FeeSplit(FeeSplitStrategy),
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scenario::Current => write!(f, "current"),
+            Scenario::RoundUp(step) => write!(f, "round up to {}", step),
+            Scenario::CompanyPaysFee => write!(f, "company pays fee"),
+            Scenario::FeeSplit(strategy) => write!(f, "fee split {:?}", strategy),
+        }
+    }
+}
+
+/// What every participant would pay in a `Scenario`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulationReport {
+    scenario: Scenario,
+    /// Amount to collect by user ID, sorted by ID
+    dues: Vec<(Id<User>, Money)>,
+    /// Part of the delivery fee paid by the company
+    company_share: Money,
+    /// Money collected beyond the price of the order and the tips
+    surplus: Money,
+}
+
+impl SimulationReport {
+    pub fn get_scenario(&self) -> Scenario {
+        self.scenario
+    }
+
+    pub fn dues(&self) -> &[(Id<User>, Money)] {
+        &self.dues
+    }
+
+    pub fn get_due(&self, user_id: &Id<User>) -> Option<Money> {
+        self.dues
+            .iter()
+            .find(|(id, _)| id == user_id)
+            .map(|(_, due)| *due)
+    }
+
+    pub fn get_total_collected(&self) -> Money {
+        self.dues
+            .iter()
+            .fold(Money::zero(), |total, (_, due)| total + *due)
+    }
+
+    pub fn get_company_share(&self) -> Money {
+        self.company_share
+    }
+
+    pub fn get_surplus(&self) -> Money {
+        self.surplus
+    }
+
+    /// Difference between the highest and the lowest due of the participants.
+    pub fn get_spread(&self) -> Money {
+        let dues = self.dues.iter().map(|(_, due)| *due);
+        match (dues.clone().max(), dues.min()) {
+            (Some(max), Some(min)) => max - min,
+            _ => Money::zero(),
+        }
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} collected, {} paid by company, {} surplus",
+            self.scenario,
+            self.get_total_collected(),
+            self.company_share,
+            self.surplus
+        )
+    }
+}
+
+/// Calculates what every participant of the order would have to pay in the `scenario`.
+///
+/// Payments already made are ignored, the dues are what each participant pays in total.
+pub fn simulate(order: &Order, scenario: Scenario) -> SimulationReport {
+    let report = order.payment_report();
+    let meal_prices: HashMap<Id<User>, Money> = report
+        .users()
+        .iter()
+        .map(|user| (user.get_user_id(), user.get_meal_price()))
+        .collect();
+    let fee_shares = match scenario {
+        Scenario::CompanyPaysFee => HashMap::new(),
+        Scenario::FeeSplit(strategy) => split_fee(
+            order.get_delivery_fee(),
+            strategy,
+            &order.get_manager_id(),
+            &meal_prices,
+        ),
+        Scenario::Current | Scenario::RoundUp(_) => report
+            .users()
+            .iter()
+            .map(|user| (user.get_user_id(), user.get_fee_share()))
+            .collect(),
+    };
+    let mut surplus = Money::zero();
+    let dues = report
+        .users()
+        .iter()
+        .map(|user| {
+            let exact = user.get_meal_price()
+                + fee_shares
+                    .get(&user.get_user_id())
+                    .copied()
+                    .unwrap_or(Money::zero())
+                + user.get_shared_share()
+                + user.get_tip();
+            let due = match scenario {
+                Scenario::RoundUp(step) => exact.round_up_to(step),
+                _ => exact,
+            };
+            surplus += due - exact;
+            (user.get_user_id(), due)
+        })
+        .collect();
+    let company_share = match scenario {
+        Scenario::CompanyPaysFee => order.get_delivery_fee(),
+        _ => Money::zero(),
+    };
+    SimulationReport {
+        scenario,
+        dues,
+        company_share,
+        surplus,
+    }
+}
+
+/// Simulates several scenarios side by side, in the given order.
+pub fn compare(order: &Order, scenarios: &[Scenario]) -> Vec<SimulationReport> {
+    scenarios
+        .iter()
+        .map(|scenario| simulate(order, *scenario))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn order() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 20),
+            )
+            .unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("12"),
+                String::from("klein"),
+                Money::new(9, 80),
+            )
+            .unwrap();
+        order
+            .set_delivery_fee(Money::new(3, 0), FeeSplitStrategy::Equal)
+            .unwrap();
+        order
+    }
+
+    #[rstest(
+        scenario,
+        expected_dues,
+        company_share,
+        surplus,
+        case(Scenario::Current, vec![670, 1130], 0, 0),
+        case(Scenario::RoundUp(Money::new(1, 0)), vec![700, 1200], 0, 100),
+        case(Scenario::CompanyPaysFee, vec![520, 980], 300, 0),
+        case(Scenario::FeeSplit(FeeSplitStrategy::Proportional), vec![624, 1176], 0, 0),
+        case(Scenario::FeeSplit(FeeSplitStrategy::ManagerPays), vec![820, 980], 0, 0)
+    )]
+    fn scenario_is_simulated(
+        scenario: Scenario,
+        expected_dues: Vec<u32>,
+        company_share: u32,
+        surplus: u32,
+    ) {
+        // Given:
+        let order = order();
+
+        // When:
+        let report = simulate(&order, scenario);
+
+        // Then:
+        assert_eq!(
+            report.dues(),
+            &[
+                (Id::new(0), Money::from_cents(expected_dues[0])),
+                (Id::new(1), Money::from_cents(expected_dues[1]))
+            ]
+        );
+        assert_eq!(report.get_company_share(), Money::from_cents(company_share));
+        assert_eq!(report.get_surplus(), Money::from_cents(surplus));
+        assert_eq!(order.get_fee_split(), FeeSplitStrategy::Equal);
+    }
+
+    #[test]
+    fn scenarios_are_compared() {
+        // Given:
+        let order = order();
+
+        // When:
+        let reports = compare(
+            &order,
+            &[
+                Scenario::Current,
+                Scenario::FeeSplit(FeeSplitStrategy::Proportional),
+            ],
+        );
+
+        // Then:
+        assert_eq!(
+            reports.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "current: 18,00€ collected, 0,00€ paid by company, 0,00€ surplus",
+                "fee split Proportional: 18,00€ collected, 0,00€ paid by company, 0,00€ surplus"
+            ]
+        );
+        assert_eq!(reports[0].get_spread(), Money::new(4, 60));
+        assert_eq!(reports[1].get_spread(), Money::new(5, 52));
+    }
+}
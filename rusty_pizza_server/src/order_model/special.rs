@@ -1,9 +1,10 @@
 use crate::util::id::Id;
 use crate::util::id_provider::IdProvider;
+use crate::util::money::Money;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct SpecialFactory {
-    id_provider: IdProvider,
+    id_provider: IdProvider<Special>,
 }
 
 impl SpecialFactory {
@@ -16,20 +17,32 @@ impl SpecialFactory {
     pub fn create_special(&mut self, description: String) -> Special {
         Special::new(self.id_provider.generate_next(), description)
     }
+
+    pub fn create_special_with_price(&mut self, description: String, price: Money) -> Special {
+        let mut special = self.create_special(description);
+        special.set_price(Some(price));
+        special
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Special {
-    id: Id,
+    id: Id<Special>,
     description: String,
+    /// Extra charge, e.g. for additional toppings, `None` if the special is free
+    price: Option<Money>,
 }
 
 impl Special {
-    pub fn new(id: Id, description: String) -> Special {
-        Special { id, description }
+    pub fn new(id: Id<Special>, description: String) -> Special {
+        Special {
+            id,
+            description,
+            price: None,
+        }
     }
 
-    pub fn get_id(&self) -> Id {
+    pub fn get_id(&self) -> Id<Special> {
         self.id.clone()
     }
 
@@ -40,6 +53,14 @@ impl Special {
     pub fn set_description(&mut self, description: String) {
         self.description = description;
     }
+
+    pub fn get_price(&self) -> Option<Money> {
+        self.price
+    }
+
+    pub fn set_price(&mut self, price: Option<Money>) {
+        self.price = price;
+    }
 }
 
 #[cfg(test)]
@@ -56,7 +77,8 @@ mod tests {
             special,
             Special {
                 id: Id::new(0),
-                description: String::from("Käserand")
+                description: String::from("Käserand"),
+                price: None
             }
         );
     }
@@ -74,11 +96,26 @@ mod tests {
             special,
             Special {
                 id: Id::new(0),
-                description: String::from("Käserand")
+                description: String::from("Käserand"),
+                price: None
             }
         );
     }
 
+    #[test]
+    fn special_with_price_can_be_created_through_factory() {
+        // Given:
+        let mut special_factory = SpecialFactory::new();
+
+        // When:
+        let special = special_factory
+            .create_special_with_price(String::from("extra Salami"), Money::new(1, 0));
+
+        // Then:
+        assert_eq!(special.get_description(), "extra Salami");
+        assert_eq!(special.get_price(), Some(Money::new(1, 0)));
+    }
+
     #[test]
     fn specials_created_through_factory_have_unique_ids() {
         // Given:
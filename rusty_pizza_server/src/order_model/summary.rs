@@ -0,0 +1,279 @@
+use crate::order_model::meal::Meal;
+use crate::order_model::order::Order;
+use crate::order_model::report::PaymentReport;
+use crate::util::id::Id;
+use crate::util::locale::MoneyFormat;
+use crate::util::money::Money;
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Everything dashboards show about an order, computed once per change instead of on every read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderSummary {
+    /// Totals and the balance of every participant
+    report: PaymentReport,
+    participants: usize,
+    meals: usize,
+    /// Participants who completed their meal selection
+    ready: usize,
+    deadline: Option<DateTime<Utc>>,
+    /// Of the last change included, see `Order::get_sequence_number`
+    sequence_number: usize,
+}
+
+impl OrderSummary {
+    pub fn from_order(order: &Order) -> OrderSummary {
+        OrderSummary {
+            report: order.payment_report(),
+            participants: order.participants().count(),
+            meals: order.all_meals().count(),
+            ready: order
+                .participants()
+                .filter_map(|user_id| order.get_user_meals(user_id))
+                .filter(|meals| meals.is_ready())
+                .count(),
+            deadline: order.get_deadline(),
+            sequence_number: order.get_sequence_number(),
+        }
+    }
+
+    pub fn get_report(&self) -> &PaymentReport {
+        &self.report
+    }
+
+    pub fn get_participants(&self) -> usize {
+        self.participants
+    }
+
+    pub fn get_meals(&self) -> usize {
+        self.meals
+    }
+
+    pub fn get_ready(&self) -> usize {
+        self.ready
+    }
+
+    pub fn get_deadline(&self) -> Option<DateTime<Utc>> {
+        self.deadline
+    }
+
+    pub fn get_sequence_number(&self) -> usize {
+        self.sequence_number
+    }
+
+    /// Like `Order::time_until_deadline`, with the deadline as of the summary.
+    pub fn time_until_deadline(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.deadline
+            .map(|deadline| (deadline - now).max(chrono::Duration::zero()))
+    }
+}
+
+/// Latest `OrderSummary` of every order, which readers get without waiting for the lock of the orders.
+///
+/// Writers replace the whole map, which is cheap compared to the summaries themselves as those are shared.
+#[derive(Debug)]
+pub struct SummaryCache {
+    summaries: ArcSwap<HashMap<Id<Order>, Arc<OrderSummary>>>,
+    /// Counts the changes, so subscribers can wait for the next one
+    changes: watch::Sender<u64>,
+}
+
+impl SummaryCache {
+    pub fn new() -> SummaryCache {
+        SummaryCache {
+            summaries: ArcSwap::default(),
+            changes: watch::Sender::new(0),
+        }
+    }
+
+    /// All summaries as of now, which later updates don't change.
+    pub fn snapshot(&self) -> Arc<HashMap<Id<Order>, Arc<OrderSummary>>> {
+        self.summaries.load_full()
+    }
+
+    /// Gets notified whenever a summary is updated or removed.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// The summary as of the last `update`, `None` if the order was never summarized.
+    pub fn get(&self, id: &Id<Order>) -> Option<Arc<OrderSummary>> {
+        self.summaries.load().get(id).cloned()
+    }
+
+    /// Summarizes the order again, to be called after every change to it.
+    pub fn update(&self, id: &Id<Order>, order: &Order) -> Arc<OrderSummary> {
+        let summary = Arc::new(OrderSummary::from_order(order));
+        self.summaries.rcu(|summaries| {
+            let mut summaries = HashMap::clone(summaries);
+            summaries.insert(id.clone(), summary.clone());
+            summaries
+        });
+        self.changes.send_modify(|changes| *changes += 1);
+        summary
+    }
+
+    pub fn remove(&self, id: &Id<Order>) {
+        self.summaries.rcu(|summaries| {
+            let mut summaries = HashMap::clone(summaries);
+            summaries.remove(id);
+            summaries
+        });
+        self.changes.send_modify(|changes| *changes += 1);
+    }
+}
+
+impl Default for SummaryCache {
+    fn default() -> SummaryCache {
+        SummaryCache::new()
+    }
+}
+
+/// How much the `summary` of a meal, the meals of a user or an order tells, e.g. for chat messages and logs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Verbosity {
+    /// Only what was ordered, or how many meals for several: "03 groß", "3 meals — 16,50€"
+    Brief,
+    /// Specials, preparations and prices as well: "2x 03 groß (Käserand) — 11,00€", used by `Display`
+    #[default]
+    Normal,
+    /// Notes and amounts paid as well
+    Detailed,
+}
+
+/// The meal without its price, e.g. "03 groß (Käserand, well done)".
+pub fn describe_meal(meal: &Meal, verbosity: Verbosity) -> String {
+    let mut description = format!("{} {}", meal.get_meal_id(), meal.get_variety());
+    if verbosity == Verbosity::Brief {
+        return description;
+    }
+    let mut extras: Vec<String> = meal
+        .specials()
+        .map(|special| special.get_description())
+        .collect();
+    extras.sort();
+    extras.extend(
+        meal.get_preparations()
+            .iter()
+            .map(|preparation| preparation.to_string()),
+    );
+    if !extras.is_empty() {
+        description.push_str(&format!(" ({})", extras.join(", ")));
+    }
+    if let (Verbosity::Detailed, Some(note)) = (verbosity, meal.get_note()) {
+        description.push_str(&format!(" \"{}\"", note));
+    }
+    description
+}
+
+/// One line per distinct meal with quantity and price, separated by commas, or only their number for
+/// `Verbosity::Brief`.
+pub fn summarize_meals<'a>(
+    meals: impl Iterator<Item = &'a Meal>,
+    verbosity: Verbosity,
+    format: MoneyFormat,
+) -> String {
+    let mut lines: BTreeMap<String, (u32, Money)> = BTreeMap::new();
+    for meal in meals {
+        let line = lines
+            .entry(describe_meal(meal, verbosity))
+            .or_insert((0, Money::zero()));
+        line.0 += 1;
+        line.1 += meal.get_total_price();
+    }
+    if lines.is_empty() {
+        return String::from("no meals");
+    }
+    if verbosity == Verbosity::Brief {
+        let (count, total) = lines
+            .values()
+            .fold((0, Money::zero()), |(count, total), (quantity, price)| {
+                (count + quantity, total + *price)
+            });
+        let noun = if count == 1 { "meal" } else { "meals" };
+        return format!("{} {} — {}", count, noun, format.format(total));
+    }
+    lines
+        .into_iter()
+        .map(|(description, (quantity, price))| {
+            format!("{}x {} — {}", quantity, description, format.format(price))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::report::Balance;
+    use crate::util::money::Money;
+
+    #[test]
+    fn summary_counts_participants_meals_and_ready_users() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order.set_ready_for_user(Id::new(1), true).unwrap();
+
+        // When:
+        let summary = OrderSummary::from_order(&order);
+
+        // Then:
+        assert_eq!(summary.get_participants(), 2);
+        assert_eq!(summary.get_meals(), 1);
+        assert_eq!(summary.get_ready(), 1);
+        assert_eq!(
+            summary
+                .get_report()
+                .get_user(&Id::new(1))
+                .unwrap()
+                .get_balance(),
+            Balance::Owed(Money::new(5, 50))
+        );
+    }
+
+    #[test]
+    fn cache_serves_summary_of_last_update() {
+        // Given:
+        let cache = SummaryCache::new();
+        let mut order = Order::new(Id::new(0));
+        cache.update(&Id::new(3), &order);
+        let before = cache.get(&Id::new(3)).unwrap();
+
+        // When:
+        order.add_user(Id::new(1)).unwrap();
+        cache.update(&Id::new(3), &order);
+
+        // Then:
+        assert_eq!(before.get_participants(), 1);
+        assert_eq!(cache.get(&Id::new(3)).unwrap().get_participants(), 2);
+        assert_eq!(cache.get(&Id::new(4)), None);
+        cache.remove(&Id::new(3));
+        assert_eq!(cache.get(&Id::new(3)), None);
+    }
+
+    #[test]
+    fn subscribers_are_notified_of_updates() {
+        // Given:
+        let cache = SummaryCache::new();
+        let changes = cache.subscribe();
+
+        // When:
+        cache.update(&Id::new(3), &Order::new(Id::new(0)));
+
+        // Then:
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(cache.snapshot().len(), 1);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::util::money::{Money, RoundingMode};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Rate of the value added tax included in a price, e.g. 19% or 7.7%.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaxRate {
+    /// Hundredths of a percent
+    basis_points: u32,
+}
+
+impl TaxRate {
+    pub fn percent(percent: u32) -> TaxRate {
+        TaxRate::from_basis_points(percent * 100)
+    }
+
+    pub fn from_basis_points(basis_points: u32) -> TaxRate {
+        TaxRate { basis_points }
+    }
+
+    pub fn get_basis_points(&self) -> u32 {
+        self.basis_points
+    }
+
+    /// Splits a gross price into net price and tax, rounding the net price half up to the cent.
+    /// ```
+    /// # use rusty_pizza_server::order_model::tax::TaxRate;
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let amounts = TaxRate::percent(19).split(Money::new(11, 90));
+    /// assert_eq!(amounts.get_net(), Money::new(10, 0));
+    /// assert_eq!(amounts.get_tax(), Money::new(1, 90));
+    /// ```
+    pub fn split(&self, gross: Money) -> TaxAmounts {
+        let net = gross.scale(10_000, 10_000 + self.basis_points, RoundingMode::HalfUp);
+        TaxAmounts {
+            net,
+            tax: gross - net,
+        }
+    }
+}
+
+impl fmt::Display for TaxRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fraction = self.basis_points % 100;
+        if fraction == 0 {
+            write!(f, "{}%", self.basis_points / 100)
+        } else if fraction.is_multiple_of(10) {
+            write!(f, "{}.{}%", self.basis_points / 100, fraction / 10)
+        } else {
+            write!(f, "{}.{:02}%", self.basis_points / 100, fraction)
+        }
+    }
+}
+
+/// A gross amount split into net amount and tax.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaxAmounts {
+    net: Money,
+    tax: Money,
+}
+
+impl TaxAmounts {
+    pub fn get_net(&self) -> Money {
+        self.net
+    }
+
+    pub fn get_tax(&self) -> Money {
+        self.tax
+    }
+
+    pub fn get_gross(&self) -> Money {
+        self.net + self.tax
+    }
+}
+
+/// Net amounts and taxes of an order by tax rate, e.g. for reimbursing a team lunch as company expense.
+///
+/// The tax of every rate is calculated from the sum of its gross prices, as on the receipt of the restaurant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaxBreakdown {
+    rates: BTreeMap<TaxRate, TaxAmounts>,
+    /// Meals without tax rate and the delivery fee
+    untaxed: Money,
+}
+
+impl TaxBreakdown {
+    /// Breakdown of the given gross amounts, `None` for amounts without tax rate.
+    pub fn calculate(amounts: impl IntoIterator<Item = (Option<TaxRate>, Money)>) -> TaxBreakdown {
+        let mut gross_by_rate: BTreeMap<TaxRate, Money> = BTreeMap::new();
+        let mut untaxed = Money::zero();
+        for (rate, gross) in amounts {
+            match rate {
+                Some(rate) => *gross_by_rate.entry(rate).or_insert_with(Money::zero) += gross,
+                None => untaxed += gross,
+            }
+        }
+        TaxBreakdown {
+            rates: gross_by_rate
+                .into_iter()
+                .map(|(rate, gross)| (rate, rate.split(gross)))
+                .collect(),
+            untaxed,
+        }
+    }
+
+    /// Lowest rate first.
+    pub fn rates(&self) -> &BTreeMap<TaxRate, TaxAmounts> {
+        &self.rates
+    }
+
+    pub fn get_untaxed(&self) -> Money {
+        self.untaxed
+    }
+
+    /// Net amounts of all rates and the untaxed amounts.
+    pub fn get_net(&self) -> Money {
+        self.rates
+            .values()
+            .fold(self.untaxed, |net, amounts| net + amounts.get_net())
+    }
+
+    pub fn get_tax(&self) -> Money {
+        self.rates
+            .values()
+            .fold(Money::zero(), |tax, amounts| tax + amounts.get_tax())
+    }
+
+    pub fn get_gross(&self) -> Money {
+        self.get_net() + self.get_tax()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        rate,
+        expected,
+        case(TaxRate::percent(19), "19%"),
+        case(TaxRate::from_basis_points(770), "7.7%"),
+        case(TaxRate::from_basis_points(255), "2.55%")
+    )]
+    fn tax_rate_is_displayed(rate: TaxRate, expected: &str) {
+        assert_eq!(rate.to_string(), expected);
+    }
+
+    #[test]
+    fn amounts_are_summed_per_rate() {
+        // Given:
+        let amounts = vec![
+            (Some(TaxRate::percent(7)), Money::new(5, 35)),
+            (Some(TaxRate::percent(19)), Money::new(2, 38)),
+            (Some(TaxRate::percent(7)), Money::new(5, 35)),
+            (None, Money::new(2, 0)),
+        ];
+
+        // When:
+        let breakdown = TaxBreakdown::calculate(amounts);
+
+        // Then:
+        assert_eq!(
+            breakdown.rates().iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    &TaxRate::percent(7),
+                    &TaxAmounts {
+                        net: Money::new(10, 0),
+                        tax: Money::new(0, 70)
+                    }
+                ),
+                (
+                    &TaxRate::percent(19),
+                    &TaxAmounts {
+                        net: Money::new(2, 0),
+                        tax: Money::new(0, 38)
+                    }
+                )
+            ]
+        );
+        assert_eq!(breakdown.get_untaxed(), Money::new(2, 0));
+        assert_eq!(breakdown.get_net(), Money::new(14, 0));
+        assert_eq!(breakdown.get_tax(), Money::new(1, 8));
+        assert_eq!(breakdown.get_gross(), Money::new(15, 8));
+    }
+}
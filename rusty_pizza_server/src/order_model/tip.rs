@@ -0,0 +1,53 @@
+use crate::util::money::{Money, RoundingMode};
+
+/// How a tip is suggested to a participant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TipStrategy {
+    /// The given percentage of the price, rounded half up to the cent
+    Percentage(u32),
+    /// Whatever makes the price a multiple of the given amount, e.g. the next full euro
+    RoundUpTo(Money),
+}
+
+impl TipStrategy {
+    /// Suggests a tip for the given price.
+    pub fn suggest(&self, price: Money) -> Money {
+        match self {
+            TipStrategy::Percentage(percent) => price.percent(*percent, RoundingMode::HalfUp),
+            TipStrategy::RoundUpTo(step) => price.round_up_to(*step) - price,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        strategy,
+        price,
+        expected,
+        case(TipStrategy::Percentage(10), Money::new(7, 20), Money::new(0, 72)),
+        case(TipStrategy::Percentage(10), Money::new(7, 25), Money::new(0, 73)),
+        case(TipStrategy::Percentage(0), Money::new(7, 25), Money::zero()),
+        case(
+            TipStrategy::RoundUpTo(Money::new(0, 50)),
+            Money::new(7, 20),
+            Money::new(0, 30)
+        ),
+        case(
+            TipStrategy::RoundUpTo(Money::new(1, 0)),
+            Money::new(7, 20),
+            Money::new(0, 80)
+        ),
+        case(
+            TipStrategy::RoundUpTo(Money::new(1, 0)),
+            Money::new(7, 0),
+            Money::zero()
+        )
+    )]
+    fn tip_is_suggested(strategy: TipStrategy, price: Money, expected: Money) {
+        assert_eq!(strategy.suggest(price), expected);
+    }
+}
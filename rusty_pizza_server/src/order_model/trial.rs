@@ -0,0 +1,323 @@
+use crate::menu::catalog::Menu;
+use crate::menu::resolution::{resolve_meal, ResolvedMeal};
+use crate::order_model::meal::Meal;
+use crate::order_model::order::Order;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::fmt;
+
+/// A meal of a past order and what it would have cost at the candidate restaurant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrialLine {
+    /// User the meal was ordered by, `None` for office and shared meals
+    user_id: Option<Id<User>>,
+    meal_id: String,
+    variety: String,
+    /// What the meal cost, including its specials
+    price: Money,
+    /// The meal on the candidate's menu with the price of its specials, `None` if it could not be found
+    candidate: Option<(ResolvedMeal, Money)>,
+}
+
+impl TrialLine {
+    pub fn get_user_id(&self) -> Option<Id<User>> {
+        self.user_id.clone()
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    pub fn get_price(&self) -> Money {
+        self.price
+    }
+
+    /// The meal as found on the candidate's menu.
+    pub fn get_candidate(&self) -> Option<&ResolvedMeal> {
+        self.candidate.as_ref().map(|(resolved, _)| resolved)
+    }
+
+    /// What the meal including its specials would cost at the candidate restaurant.
+    pub fn get_candidate_price(&self) -> Option<Money> {
+        self.candidate
+            .as_ref()
+            .map(|(resolved, specials)| resolved.get_price() + *specials)
+    }
+}
+
+/// Cost comparison of a past order with a restaurant that is not in the rotation yet, see `reprice`.
+///
+/// Only meals found on both menus are compared, the others are listed as unmatched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrialReport {
+    /// Restaurant of the candidate menu
+    restaurant: String,
+    /// Meals of the participants sorted by user ID, office and shared meals last
+    lines: Vec<TrialLine>,
+}
+
+impl TrialReport {
+    pub fn get_restaurant(&self) -> &String {
+        &self.restaurant
+    }
+
+    pub fn lines(&self) -> &[TrialLine] {
+        &self.lines
+    }
+
+    /// Meals that could not be found on the candidate's menu.
+    pub fn unmatched(&self) -> impl Iterator<Item = &TrialLine> {
+        self.lines.iter().filter(|line| line.candidate.is_none())
+    }
+
+    /// What the matched meals cost.
+    pub fn get_current_total(&self) -> Money {
+        self.matched()
+            .fold(Money::zero(), |total, (price, _)| total + price)
+    }
+
+    /// What the matched meals would cost at the candidate restaurant.
+    pub fn get_candidate_total(&self) -> Money {
+        self.matched()
+            .fold(Money::zero(), |total, (_, candidate)| total + candidate)
+    }
+
+    /// How much less the matched meals would cost at the candidate restaurant, zero if they are not cheaper.
+    pub fn get_savings(&self) -> Money {
+        let (current, candidate) = (self.get_current_total(), self.get_candidate_total());
+        if candidate < current {
+            current - candidate
+        } else {
+            Money::zero()
+        }
+    }
+
+    /// How much more the matched meals would cost at the candidate restaurant, zero if they are not dearer.
+    pub fn get_extra_cost(&self) -> Money {
+        let (current, candidate) = (self.get_current_total(), self.get_candidate_total());
+        if candidate > current {
+            candidate - current
+        } else {
+            Money::zero()
+        }
+    }
+
+    fn matched(&self) -> impl Iterator<Item = (Money, Money)> + '_ {
+        self.lines
+            .iter()
+            .filter_map(|line| Some((line.price, line.get_candidate_price()?)))
+    }
+}
+
+impl fmt::Display for TrialReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let matched = self.matched().count();
+        write!(
+            f,
+            "{}: {} instead of {} for {} of {} meals",
+            self.restaurant,
+            self.get_candidate_total(),
+            self.get_current_total(),
+            matched,
+            self.lines.len()
+        )?;
+        let (savings, extra_cost) = (self.get_savings(), self.get_extra_cost());
+        if savings > Money::zero() {
+            write!(f, ", {} cheaper", savings)
+        } else if extra_cost > Money::zero() {
+            write!(f, ", {} more expensive", extra_cost)
+        } else {
+            write!(f, ", same price")
+        }
+    }
+}
+
+/// Prices the meals of a past order as if they had been ordered from the `candidate`, e.g. before adding a
+/// restaurant to the rotation.
+///
+/// Meals are found with the same heuristics as `Order::clone_for_menu`. Specials cost what the candidate charges
+/// for them, or what they cost in the order if the candidate doesn't list them. The order itself is untouched.
+pub fn reprice(order: &Order, candidate: &Menu) -> TrialReport {
+    let mut user_ids: Vec<&Id<User>> = order.participants().collect();
+    user_ids.sort_by_key(|id| id.get_value());
+    let mut lines = Vec::new();
+    for user_id in user_ids {
+        let meals = order.get_user_meals(user_id).unwrap().meals();
+        for meal in sorted(meals) {
+            lines.push(line(order, candidate, Some(user_id.clone()), meal));
+        }
+    }
+    for meal in sorted(order.office_meals().chain(order.shared_meals())) {
+        lines.push(line(order, candidate, None, meal));
+    }
+    TrialReport {
+        restaurant: candidate.get_restaurant().clone(),
+        lines,
+    }
+}
+
+fn sorted<'a>(meals: impl Iterator<Item = &'a Meal>) -> Vec<&'a Meal> {
+    let mut meals: Vec<&Meal> = meals.collect();
+    meals.sort_by_key(|meal| meal.get_id().get_value());
+    meals
+}
+
+fn line(order: &Order, candidate: &Menu, user_id: Option<Id<User>>, meal: &Meal) -> TrialLine {
+    let resolved = resolve_meal(
+        order.get_menu(),
+        candidate,
+        meal.get_meal_id(),
+        meal.get_variety(),
+    );
+    TrialLine {
+        user_id,
+        meal_id: meal.get_meal_id().clone(),
+        variety: meal.get_variety().clone(),
+        price: meal.get_total_price(),
+        candidate: resolved.map(|resolved| {
+            let specials = meal.specials().fold(Money::zero(), |total, special| {
+                let price = candidate
+                    .get_special_price(&special.get_description())
+                    .or_else(|| special.get_price())
+                    .unwrap_or(Money::zero());
+                total + price
+            });
+            (resolved, specials)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::item::MenuItem;
+    use std::sync::Arc;
+
+    fn menu(restaurant: &str, items: Vec<(&str, &str, Money)>) -> Menu {
+        let mut menu = Menu::new(String::from(restaurant));
+        for (meal_id, name, price) in items {
+            let mut item = MenuItem::new(String::from(meal_id), String::from(name));
+            item.set_price(String::from("groß"), price);
+            menu.add_item(item);
+        }
+        menu
+    }
+
+    fn order() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        for (user_id, meal_id) in [(1, "03"), (0, "12")] {
+            let meal = order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from(meal_id),
+                    String::from("groß"),
+                    Money::new(8, 0),
+                )
+                .unwrap()
+                .get_id();
+            order
+                .add_special_for_user(
+                    Id::new(user_id),
+                    meal,
+                    String::from("Käserand"),
+                    Some(Money::new(1, 0)),
+                )
+                .unwrap();
+        }
+        order
+            .add_office_meal(String::from("61"), String::from("Salat"), Money::new(4, 0))
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn past_order_is_repriced_against_candidate_menu() {
+        // Given:
+        let order = order();
+        let mut candidate = menu(
+            "Pizzeria Mario",
+            vec![
+                ("03", "Margherita", Money::new(7, 0)),
+                ("12", "Salami", Money::new(7, 50)),
+            ],
+        );
+        candidate.set_special_price("käserand", Money::new(1, 50));
+
+        // When:
+        let report = reprice(&order, &candidate);
+
+        // Then:
+        assert_eq!(
+            report
+                .lines()
+                .iter()
+                .map(|line| (line.get_user_id(), line.get_candidate_price()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(Id::new(0)), Some(Money::new(9, 0))),
+                (Some(Id::new(1)), Some(Money::new(8, 50))),
+                (None, None),
+            ]
+        );
+        assert_eq!(report.get_current_total(), Money::new(18, 0));
+        assert_eq!(report.get_candidate_total(), Money::new(17, 50));
+        assert_eq!(report.get_savings(), Money::new(0, 50));
+        assert_eq!(report.get_extra_cost(), Money::zero());
+        assert_eq!(
+            report
+                .unmatched()
+                .map(TrialLine::get_meal_id)
+                .collect::<Vec<_>>(),
+            vec!["61"]
+        );
+        assert_eq!(
+            report.to_string(),
+            "Pizzeria Mario: 17,50€ instead of 18,00€ for 2 of 3 meals, 0,50€ cheaper"
+        );
+    }
+
+    #[test]
+    fn meals_are_found_by_name_if_order_has_menu() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.set_menu(Arc::new(menu(
+            "Pizzeria Luigi",
+            vec![("03", "Salami", Money::new(5, 50))],
+        )));
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        let candidate = menu(
+            "Pizzeria Mario",
+            vec![
+                ("03", "Margherita", Money::new(5, 0)),
+                ("12", "Salami", Money::new(6, 50)),
+            ],
+        );
+
+        // When:
+        let report = reprice(&order, &candidate);
+
+        // Then:
+        assert_eq!(
+            report.lines()[0].get_candidate().unwrap().get_meal_id(),
+            "12"
+        );
+        assert_eq!(report.get_extra_cost(), Money::new(1, 0));
+        assert_eq!(
+            report.to_string(),
+            "Pizzeria Mario: 6,50€ instead of 5,50€ for 1 of 1 meals, 1,00€ more expensive"
+        );
+    }
+}
@@ -1,17 +1,48 @@
 use crate::util::id::Id;
+use crate::util::id_provider::{IdProvider, IdScheme, Sequential};
+use crate::util::short_code::ShortCodePrefix;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-pub struct User {
-    id: Id,
+/// Creates users with IDs of the given scheme, consecutive ones by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UserFactory<S = Sequential> {
+    id_provider: IdProvider<User, S>,
+}
+
+impl UserFactory {
+    pub fn new() -> UserFactory {
+        UserFactory {
+            id_provider: IdProvider::new(),
+        }
+    }
+}
+
+impl<S: IdScheme> UserFactory<S> {
+    /// Creates users with IDs of the provider, e.g. to continue after the ones of restored users.
+    pub fn with_id_provider(id_provider: IdProvider<User, S>) -> UserFactory<S> {
+        UserFactory { id_provider }
+    }
+
+    pub fn create_user(&mut self, name: String) -> User<S::Value> {
+        User::new(self.id_provider.generate_next(), name)
+    }
+}
+
+/// A user with an ID of any scheme, see `IdScheme`, a sequential `u32` unless stated otherwise.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct User<V = u32> {
+    id: Id<User, V>,
     name: String,
 }
 
-impl User {
-    pub fn new(id: Id, name: String) -> User {
+impl<V> User<V> {
+    pub fn new(id: Id<User, V>, name: String) -> User<V> {
         User { id, name }
     }
 
-    pub fn get_id(&self) -> Id {
+    pub fn get_id(&self) -> Id<User, V>
+    where
+        V: Clone,
+    {
         self.id.clone()
     }
 
@@ -20,9 +51,15 @@ impl User {
     }
 }
 
+impl ShortCodePrefix for User {
+    const PREFIX: &'static str = "USR";
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "uuid")]
+    use crate::util::id_provider::RandomUuid;
 
     #[test]
     fn user_can_be_created() {
@@ -38,4 +75,33 @@ mod tests {
         assert_eq!(id, user.get_id());
         assert_eq!(str_name, user.get_name());
     }
+
+    #[test]
+    fn users_created_through_factory_have_unique_ids() {
+        // Given:
+        let mut user_factory = UserFactory::new();
+
+        // When:
+        let user1 = user_factory.create_user(String::from("Peter"));
+        let user2 = user_factory.create_user(String::from("Peter"));
+
+        // Then:
+        assert_eq!(user1, User::new(Id::new(0), String::from("Peter")));
+        assert_eq!(user2, User::new(Id::new(1), String::from("Peter")));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn users_can_be_created_with_random_ids() {
+        // Given:
+        let mut user_factory = UserFactory::with_id_provider(IdProvider::with_scheme(RandomUuid));
+
+        // When:
+        let user1 = user_factory.create_user(String::from("Peter"));
+        let user2 = user_factory.create_user(String::from("Peter"));
+
+        // Then:
+        assert_ne!(user1.get_id(), user2.get_id());
+        assert_eq!(user1.get_name(), user2.get_name());
+    }
 }
@@ -0,0 +1,265 @@
+use crate::util::locale::Currency;
+use crate::util::money::Money;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Limits of the EPC069-12 guidelines for the "SEPA credit transfer" QR code.
+pub const MAX_NAME_LENGTH: usize = 70;
+pub const MAX_REMITTANCE_LENGTH: usize = 140;
+/// Largest amount which can be requested, 999,999,999.99€
+pub const MAX_CENTS: u64 = 99_999_999_999;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EpcError {
+    /// Wrong format or check digits
+    InvalidIban(String),
+    InvalidBic(String),
+    /// The name of the recipient is empty or longer than `MAX_NAME_LENGTH`
+    InvalidName(String),
+    /// SEPA transfers are always in euros
+    UnsupportedCurrency(Currency),
+    /// Nothing or more than `MAX_CENTS` can't be transferred
+    InvalidAmount(Money),
+    RemittanceTooLong(usize),
+}
+
+impl fmt::Display for EpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use EpcError::*;
+        match self {
+            InvalidIban(iban) => write!(f, "{} is not a valid IBAN", iban),
+            InvalidBic(bic) => write!(f, "{} is not a valid BIC", bic),
+            InvalidName(name) => write!(
+                f,
+                "\"{}\" is not a name of 1 to {} characters",
+                name, MAX_NAME_LENGTH
+            ),
+            UnsupportedCurrency(currency) => {
+                write!(
+                    f,
+                    "SEPA transfers are not possible in {}",
+                    currency.get_code()
+                )
+            }
+            InvalidAmount(amount) => write!(f, "{} can't be transferred", amount),
+            RemittanceTooLong(length) => write!(
+                f,
+                "remittance information has {} instead of at most {} characters",
+                length, MAX_REMITTANCE_LENGTH
+            ),
+        }
+    }
+}
+
+impl Error for EpcError {}
+
+/// International bank account number, validated by its check digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Iban {
+    /// Upper case without spaces, e.g. "DE89370400440532013000"
+    value: String,
+}
+
+impl Iban {
+    pub fn get_value(&self) -> &String {
+        &self.value
+    }
+}
+
+impl FromStr for Iban {
+    type Err = EpcError;
+
+    /// Accepts the IBAN in groups of four as printed, e.g. "DE89 3704 0044 0532 0130 00".
+    fn from_str(iban: &str) -> Result<Iban, EpcError> {
+        let value: String = iban
+            .chars()
+            .filter(|character| !character.is_whitespace())
+            .collect::<String>()
+            .to_uppercase();
+        let invalid = || EpcError::InvalidIban(String::from(iban));
+        if !(15..=34).contains(&value.len())
+            || !value
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric())
+            || !value[..2]
+                .chars()
+                .all(|character| character.is_ascii_alphabetic())
+            || !value[2..4]
+                .chars()
+                .all(|character| character.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        // ISO 13616: the country code and check digits moved to the end, letters as 10 to 35, modulo 97 is 1
+        let remainder =
+            value[4..]
+                .chars()
+                .chain(value[..4].chars())
+                .fold(0, |remainder, character| {
+                    let digits = character.to_digit(36).expect("IBAN is alphanumeric");
+                    if digits < 10 {
+                        (remainder * 10 + digits) % 97
+                    } else {
+                        (remainder * 100 + digits) % 97
+                    }
+                });
+        if remainder != 1 {
+            return Err(invalid());
+        }
+        Ok(Iban { value })
+    }
+}
+
+/// Account of the person collecting the money, who gets SEPA credit transfers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SepaRecipient {
+    name: String,
+    iban: Iban,
+    /// Only needed by banks outside the EEA
+    bic: Option<String>,
+}
+
+impl SepaRecipient {
+    pub fn new(name: String, iban: Iban, bic: Option<String>) -> Result<SepaRecipient, EpcError> {
+        let name = String::from(name.trim());
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(EpcError::InvalidName(name));
+        }
+        let bic = match bic.map(|bic| bic.trim().to_uppercase()) {
+            Some(bic) if bic.is_empty() => None,
+            Some(bic)
+                if (bic.len() == 8 || bic.len() == 11)
+                    && bic
+                        .chars()
+                        .all(|character| character.is_ascii_alphanumeric()) =>
+            {
+                Some(bic)
+            }
+            Some(bic) => return Err(EpcError::InvalidBic(bic)),
+            None => None,
+        };
+        Ok(SepaRecipient { name, iban, bic })
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_iban(&self) -> &Iban {
+        &self.iban
+    }
+
+    pub fn get_bic(&self) -> Option<&String> {
+        self.bic.as_ref()
+    }
+
+    /// Content of an EPC QR code, which banking apps scan to fill in a transfer to the recipient.
+    ///
+    /// `remittance` is the text the payer's bank passes on, e.g. the short codes of order and participant.
+    pub fn qr_payload(
+        &self,
+        amount: Money,
+        currency: Currency,
+        remittance: &str,
+    ) -> Result<String, EpcError> {
+        if currency != Currency::Eur {
+            return Err(EpcError::UnsupportedCurrency(currency));
+        }
+        if amount.is_zero() || u64::from(amount.get_total_cents()) > MAX_CENTS {
+            return Err(EpcError::InvalidAmount(amount));
+        }
+        let remittance_length = remittance.chars().count();
+        if remittance_length > MAX_REMITTANCE_LENGTH {
+            return Err(EpcError::RemittanceTooLong(remittance_length));
+        }
+        let lines = [
+            "BCD",
+            // Version 002 allows to leave out the BIC within the EEA
+            "002",
+            // UTF-8
+            "1",
+            "SCT",
+            self.bic.as_deref().unwrap_or_default(),
+            &self.name,
+            &self.iban.value,
+            &format!("EUR{}", amount.to_decimal_string()),
+            // Purpose code and structured creditor reference, which are not used
+            "",
+            "",
+            remittance,
+        ];
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn recipient() -> SepaRecipient {
+        SepaRecipient::new(
+            String::from("Franz Pizza"),
+            "DE89 3704 0044 0532 0130 00".parse().unwrap(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[rstest(
+        iban,
+        valid,
+        case("DE89 3704 0044 0532 0130 00", true),
+        case("ch9300762011623852957", true),
+        case("DE88 3704 0044 0532 0130 00", false),
+        case("DE89", false),
+        case("DE89 3704 0044 0532 0130 0!", false)
+    )]
+    fn iban_check_digits_are_verified(iban: &str, valid: bool) {
+        // When:
+        let parsed = iban.parse::<Iban>();
+
+        // Then:
+        assert_eq!(parsed.is_ok(), valid);
+    }
+
+    #[test]
+    fn payload_follows_epc_guidelines() {
+        // When:
+        let payload = recipient().qr_payload(Money::new(8, 50), Currency::Eur, "Pizza ORD-0039");
+
+        // Then:
+        assert_eq!(
+            payload,
+            Ok(String::from(
+                "BCD\n002\n1\nSCT\n\nFranz Pizza\nDE89370400440532013000\nEUR8.50\n\n\nPizza ORD-0039"
+            ))
+        );
+    }
+
+    #[test]
+    fn payload_needs_euros() {
+        // When:
+        let payload = recipient().qr_payload(Money::new(8, 50), Currency::Chf, "Pizza");
+
+        // Then:
+        assert_eq!(payload, Err(EpcError::UnsupportedCurrency(Currency::Chf)));
+    }
+
+    #[test]
+    fn bic_must_have_8_or_11_characters() {
+        // When:
+        let recipient = SepaRecipient::new(
+            String::from("Franz Pizza"),
+            "DE89370400440532013000".parse().unwrap(),
+            Some(String::from("COBADEF")),
+        );
+
+        // Then:
+        assert_eq!(
+            recipient,
+            Err(EpcError::InvalidBic(String::from("COBADEF")))
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod epc;
+pub mod paypal;
+pub mod request;
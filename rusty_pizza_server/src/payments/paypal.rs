@@ -0,0 +1,112 @@
+use crate::util::locale::Currency;
+use crate::util::money::Money;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const BASE_URL: &str = "https://paypal.me/";
+
+/// Longest name PayPal allows for a PayPal.Me profile.
+pub const MAX_NAME_LENGTH: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PayPalError {
+    /// Profile names have 1 to 20 letters and digits
+    InvalidName(String),
+}
+
+impl fmt::Display for PayPalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PayPalError::*;
+        match self {
+            InvalidName(name) => write!(f, "{} is not a PayPal.Me name", name),
+        }
+    }
+}
+
+impl Error for PayPalError {}
+
+/// PayPal.Me profile of the person collecting the money, e.g. "pizzafranz".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayPalMe {
+    name: String,
+}
+
+impl PayPalMe {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// Link which opens PayPal with the amount filled in.
+    /// ```
+    /// # use rusty_pizza_server::payments::paypal::PayPalMe;
+    /// # use rusty_pizza_server::util::locale::Currency;
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let profile: PayPalMe = "pizzafranz".parse().unwrap();
+    /// assert_eq!(
+    ///     profile.link(Money::new(8, 50), Currency::Eur),
+    ///     "https://paypal.me/pizzafranz/8.50EUR"
+    /// );
+    /// ```
+    pub fn link(&self, amount: Money, currency: Currency) -> String {
+        format!(
+            "{}{}/{}{}",
+            BASE_URL,
+            self.name,
+            amount.to_decimal_string(),
+            currency.get_code()
+        )
+    }
+}
+
+impl FromStr for PayPalMe {
+    type Err = PayPalError;
+
+    /// Accepts the name alone or the whole profile link, e.g. "paypal.me/pizzafranz".
+    fn from_str(profile: &str) -> Result<PayPalMe, PayPalError> {
+        let trimmed = profile.trim().trim_end_matches('/');
+        let name = trimmed
+            .rsplit_once("paypal.me/")
+            .map_or(trimmed, |(_, name)| name);
+        if name.is_empty()
+            || name.len() > MAX_NAME_LENGTH
+            || !name
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric())
+        {
+            return Err(PayPalError::InvalidName(String::from(profile)));
+        }
+        Ok(PayPalMe {
+            name: String::from(name),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        profile,
+        expected,
+        case("pizzafranz", Ok("pizzafranz")),
+        case("https://www.paypal.me/pizzafranz/", Ok("pizzafranz")),
+        case("pizza franz", Err(())),
+        case("", Err(())),
+        case("averyveryverylongpizzaname", Err(()))
+    )]
+    fn profile_name_is_parsed(profile: &str, expected: Result<&str, ()>) {
+        // When:
+        let parsed = profile.parse::<PayPalMe>();
+
+        // Then:
+        assert_eq!(
+            parsed
+                .as_ref()
+                .map(|profile| profile.get_name().as_str())
+                .map_err(|_| ()),
+            expected
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use crate::order_model::order::Order;
+use crate::order_model::report::Balance;
+use crate::order_model::user::User;
+use crate::payments::epc::{EpcError, SepaRecipient};
+use crate::payments::paypal::PayPalMe;
+use crate::util::id::Id;
+use crate::util::locale::Currency;
+use crate::util::money::Money;
+use crate::util::short_code::short_code;
+
+/// How participants can pay the manager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaymentMethod {
+    PayPalMe(PayPalMe),
+    /// Credit transfer started by scanning an EPC QR code
+    Sepa(SepaRecipient),
+}
+
+/// What a participant still owes, with everything they need to pay it with one click or scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentRequest {
+    user_id: Id<User>,
+    amount: Money,
+    /// Order and user code, so bank statements can be matched by `reconcile`
+    reference: String,
+    /// PayPal.Me link or EPC QR code payload
+    link: String,
+}
+
+impl PaymentRequest {
+    pub fn get_user_id(&self) -> Id<User> {
+        self.user_id.clone()
+    }
+
+    pub fn get_amount(&self) -> Money {
+        self.amount
+    }
+
+    pub fn get_reference(&self) -> &String {
+        &self.reference
+    }
+
+    pub fn get_link(&self) -> &String {
+        &self.link
+    }
+}
+
+/// Requests for every participant who owes money, except the manager, who collects it.
+pub fn payment_requests(
+    order_id: &Id<Order>,
+    order: &Order,
+    method: &PaymentMethod,
+    currency: Currency,
+) -> Result<Vec<PaymentRequest>, EpcError> {
+    order
+        .payment_report()
+        .users()
+        .iter()
+        .filter(|user| user.get_user_id() != order.get_manager_id())
+        .filter_map(|user| match user.get_balance() {
+            Balance::Owed(amount) => Some((user.get_user_id(), amount)),
+            Balance::Change(_) => None,
+        })
+        .map(|(user_id, amount)| {
+            let reference = format!("Pizza {} {}", short_code(order_id), short_code(&user_id));
+            let link = match method {
+                PaymentMethod::PayPalMe(profile) => profile.link(amount, currency),
+                PaymentMethod::Sepa(recipient) => {
+                    recipient.qr_payload(amount, currency, &reference)?
+                }
+            };
+            Ok(PaymentRequest {
+                user_id,
+                amount,
+                reference,
+                link,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn participants_who_owe_money_get_requests() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        for (user_id, euros) in [(0, 7), (1, 8), (2, 6)] {
+            if user_id != 0 {
+                order.add_user(Id::new(user_id)).unwrap();
+            }
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(euros, 50),
+                )
+                .unwrap();
+        }
+        order
+            .set_paid_for_user(Id::new(2), Money::new(6, 50))
+            .unwrap();
+        let method = PaymentMethod::PayPalMe("pizzafranz".parse().unwrap());
+
+        // When:
+        let requests = payment_requests(&Id::new(3), &order, &method, Currency::Eur);
+
+        // Then:
+        let requests = requests.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].get_user_id(), Id::new(1));
+        assert_eq!(
+            requests[0].get_link(),
+            "https://paypal.me/pizzafranz/8.50EUR"
+        );
+        assert_eq!(
+            requests[0].get_reference(),
+            &format!(
+                "Pizza {} {}",
+                short_code::<Order>(&Id::new(3)),
+                short_code::<User>(&Id::new(1))
+            )
+        );
+    }
+}
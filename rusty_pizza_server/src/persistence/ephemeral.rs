@@ -0,0 +1,191 @@
+use crate::order_model::order_template::{OrderTemplate, OrderTemplates};
+use crate::util::id::Id;
+use std::collections::BTreeMap;
+use std::mem;
+use std::time::SystemTime;
+
+/// A change to the templates that could not be saved while running ephemeral.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateWrite {
+    Added(Id<OrderTemplate>, Box<OrderTemplate>),
+    Removed(Id<OrderTemplate>),
+}
+
+/// Whether changes are saved, or only kept in memory because the storage could not be reached at startup.
+///
+/// Running ephemeral keeps the server usable, but everything changed is lost on a restart unless the storage can be
+/// reached again before, see `replay_templates`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageStatus {
+    /// Why the storage could not be used and since when, `None` while changes are saved
+    unavailable: Option<(String, SystemTime)>,
+    /// Writes made while the storage could not be used, which still have to be replayed, oldest first
+    queued_writes: Vec<TemplateWrite>,
+    /// Templates that got another ID when they were replayed, as the stored templates used theirs
+    moved_templates: BTreeMap<Id<OrderTemplate>, Id<OrderTemplate>>,
+}
+
+impl StorageStatus {
+    pub fn unavailable(reason: String, since: SystemTime) -> StorageStatus {
+        StorageStatus {
+            unavailable: Some((reason, since)),
+            ..StorageStatus::default()
+        }
+    }
+
+    pub fn is_ephemeral(&self) -> bool {
+        self.unavailable.is_some()
+    }
+
+    /// Why the storage could not be used.
+    pub fn get_reason(&self) -> Option<&String> {
+        self.unavailable.as_ref().map(|(reason, _)| reason)
+    }
+
+    /// Replaces the reason why the storage can't be used, e.g. after another attempt to replay failed.
+    pub fn set_reason(&mut self, reason: String) {
+        if let Some((current, _)) = &mut self.unavailable {
+            *current = reason;
+        }
+    }
+
+    /// Since when changes are only kept in memory.
+    pub fn get_since(&self) -> Option<SystemTime> {
+        self.unavailable.as_ref().map(|(_, since)| *since)
+    }
+
+    pub fn queued_writes(&self) -> &[TemplateWrite] {
+        &self.queued_writes
+    }
+
+    /// Remembers a write that could not be saved, meant to be called only while ephemeral.
+    pub fn queue_write(&mut self, write: TemplateWrite) {
+        self.queued_writes.push(write);
+    }
+
+    /// Old and new IDs of the templates that were moved by the last replay, see `replay_templates`.
+    pub fn moved_templates(&self) -> &BTreeMap<Id<OrderTemplate>, Id<OrderTemplate>> {
+        &self.moved_templates
+    }
+
+    /// Marks the queued writes as replayed, remembering which templates were moved, and returns the writes.
+    pub fn mark_available(
+        &mut self,
+        moved: BTreeMap<Id<OrderTemplate>, Id<OrderTemplate>>,
+    ) -> Vec<TemplateWrite> {
+        self.unavailable = None;
+        self.moved_templates = moved;
+        mem::take(&mut self.queued_writes)
+    }
+}
+
+/// Templates after replaying the writes queued while running ephemeral.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayedTemplates {
+    pub templates: OrderTemplates,
+    /// Old and new ID of each template whose ID was already used by a stored template
+    pub moved: BTreeMap<Id<OrderTemplate>, Id<OrderTemplate>>,
+}
+
+/// Applies the writes queued while running ephemeral to the templates found in the storage once it can be reached
+/// again.
+///
+/// Templates keep the ID they were created with unless a stored template uses it. They then get a new ID after all
+/// others, which is returned in `ReplayedTemplates::moved` and used for the later writes to them.
+pub fn replay_templates(stored: OrderTemplates, writes: &[TemplateWrite]) -> ReplayedTemplates {
+    let stored_ids: Vec<Id<OrderTemplate>> = stored.templates().map(|(id, _)| id.clone()).collect();
+    let mut next_id = stored_ids
+        .iter()
+        .chain(writes.iter().filter_map(|write| match write {
+            TemplateWrite::Added(id, _) => Some(id),
+            TemplateWrite::Removed(_) => None,
+        }))
+        .map(|id| id.get_value() + 1)
+        .max()
+        .unwrap_or(0);
+    let mut templates = stored;
+    let mut moved = BTreeMap::new();
+    for write in writes {
+        match write {
+            TemplateWrite::Added(id, template) if stored_ids.contains(id) => {
+                let new_id = Id::new(next_id);
+                next_id += 1;
+                templates.restore(new_id.clone(), (**template).clone());
+                moved.insert(id.clone(), new_id);
+            }
+            TemplateWrite::Added(id, template) => {
+                templates.restore(id.clone(), (**template).clone())
+            }
+            TemplateWrite::Removed(id) => {
+                templates.remove(moved.get(id).unwrap_or(id));
+            }
+        }
+    }
+    ReplayedTemplates { templates, moved }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::restaurant::Restaurant;
+
+    fn template(name: &str) -> OrderTemplate {
+        OrderTemplate::new(
+            String::from(name),
+            Id::new(0),
+            Restaurant::new(String::from("Pizzeria Luigi")),
+        )
+    }
+
+    #[test]
+    fn ephemeral_templates_keep_their_ids_unless_used_by_stored_ones() {
+        // Given:
+        let mut stored = OrderTemplates::new();
+        stored.add(template("Friday pizza"));
+        let writes = vec![
+            TemplateWrite::Added(Id::new(0), Box::new(template("Team lunch"))),
+            TemplateWrite::Added(Id::new(1), Box::new(template("Birthday"))),
+            TemplateWrite::Removed(Id::new(1)),
+            TemplateWrite::Added(Id::new(1), Box::new(template("Farewell"))),
+            TemplateWrite::Removed(Id::new(0)),
+        ];
+
+        // When:
+        let replayed = replay_templates(stored, &writes);
+
+        // Then:
+        let names: Vec<(u32, &String)> = replayed
+            .templates
+            .templates()
+            .map(|(id, template)| (id.get_value(), template.get_name()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (0, &String::from("Friday pizza")),
+                (1, &String::from("Farewell")),
+            ]
+        );
+        assert_eq!(replayed.moved, BTreeMap::from([(Id::new(0), Id::new(2))]));
+    }
+
+    #[test]
+    fn queued_writes_are_kept_until_available() {
+        // Given:
+        let mut status =
+            StorageStatus::unavailable(String::from("permission denied"), SystemTime::UNIX_EPOCH);
+        status.queue_write(TemplateWrite::Added(
+            Id::new(0),
+            Box::new(template("Team lunch")),
+        ));
+        status.queue_write(TemplateWrite::Removed(Id::new(0)));
+
+        // When:
+        let replayed = status.mark_available(BTreeMap::new());
+
+        // Then:
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(status, StorageStatus::default());
+        assert!(!status.is_ephemeral());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::notifications::event::OrderEvent;
+use crate::persistence::intern::{self, StringTable};
+use crate::persistence::journal::{JournalStorage, WriteAheadJournal};
+use crate::util::clock::{Clock, SystemClock};
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+/// When buffered records are written to the storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// Every record is written immediately, safest but slow on network filesystems
+    EveryEvent,
+    /// Records are collected and written once the given time passed since the last flush
+    Batched(Duration),
+    /// Records are written whenever the status of an order changes
+    OnStatusChange,
+}
+
+/// Something records can be written to and which can be forced to persist them.
+pub trait Storage: Write {
+    /// Makes sure everything written so far survives a crash, like `fsync`.
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+impl Storage for File {
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
+/// In memory storage, syncing does nothing.
+impl Storage for Vec<u8> {
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes `OrderEvent`s as JSON lines to a `Storage`, batching them according to a `FlushPolicy`.
+///
+/// Records still buffered are lost if the writer is dropped without calling `flush`, unless they are journaled,
+/// see `with_journal`.
+#[derive(Debug)]
+pub struct BatchWriter<S: Storage, C: Clock = SystemClock> {
+    storage: S,
+    policy: FlushPolicy,
+    /// Whether every flush is followed by a sync
+    fsync: bool,
+    /// Serialized records not written yet
+    pending: Vec<String>,
+    /// Strings written to the storage so far, `None` to write every event as is
+    strings: Option<StringTable>,
+    /// Where buffered records are kept safe until they are flushed, `None` to risk them for speed
+    journal: Option<WriteAheadJournal>,
+    /// Bytes in the storage, only tracked for the checkpoints of the journal
+    storage_len: u64,
+    last_flush: SystemTime,
+    clock: C,
+}
+
+impl<S: Storage> BatchWriter<S> {
+    pub fn new(storage: S, policy: FlushPolicy, fsync: bool) -> BatchWriter<S> {
+        BatchWriter::with_clock(storage, policy, fsync, SystemClock)
+    }
+}
+
+impl<S: Storage, C: Clock> BatchWriter<S, C> {
+    pub fn with_clock(storage: S, policy: FlushPolicy, fsync: bool, clock: C) -> BatchWriter<S, C> {
+        BatchWriter {
+            storage,
+            policy,
+            fsync,
+            pending: Vec::new(),
+            strings: None,
+            journal: None,
+            storage_len: 0,
+            last_flush: clock.now(),
+            clock,
+        }
+    }
+
+    /// Writes meals referencing a table of their strings, see `intern::encode`.
+    ///
+    /// The table has to hold the strings already in the storage, e.g. as returned by `intern::read_log`, or be
+    /// empty for new storage.
+    pub fn with_interning(mut self, strings: StringTable) -> BatchWriter<S, C> {
+        self.strings = Some(strings);
+        self
+    }
+
+    /// Journals every buffered record before `write` returns, so acknowledged events survive a crash before the
+    /// next flush. `storage_len` is the length of the storage in bytes, e.g. as returned by `journal::recover`.
+    ///
+    /// The storage is synced before every checkpoint of the journal, even if `fsync` is off.
+    pub fn with_journal(
+        mut self,
+        journal: impl JournalStorage + Send + 'static,
+        storage_len: u64,
+    ) -> io::Result<BatchWriter<S, C>> {
+        self.journal = Some(WriteAheadJournal::new(journal, storage_len)?);
+        self.storage_len = storage_len;
+        Ok(self)
+    }
+
+    /// Buffers the event and flushes if the policy demands it. Returns whether it flushed.
+    ///
+    /// With a journal the event is only buffered once it was synced to it. If that fails it stays buffered, but
+    /// may be lost in a crash.
+    pub fn write(&mut self, event: &OrderEvent) -> io::Result<bool> {
+        let buffered = self.pending.len();
+        match &mut self.strings {
+            Some(strings) => self.pending.extend(intern::encode(event, strings)),
+            None => self
+                .pending
+                .push(serde_json::to_string(event).expect("Events are always serializable")),
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.append(&self.pending[buffered..])?;
+        }
+        let flush = match self.policy {
+            FlushPolicy::EveryEvent => true,
+            FlushPolicy::Batched(_) => self.is_batch_due(),
+            FlushPolicy::OnStatusChange => matches!(event, OrderEvent::StatusChanged { .. }),
+        };
+        if flush {
+            self.flush()?;
+        }
+        Ok(flush)
+    }
+
+    /// Flushes a batch whose time is over even without new records, e.g. called by a periodic timer.
+    pub fn flush_if_due(&mut self) -> io::Result<bool> {
+        let due = !self.pending.is_empty() && self.is_batch_due();
+        if due {
+            self.flush()?;
+        }
+        Ok(due)
+    }
+
+    /// Writes all buffered records and syncs the storage if configured or journaled.
+    pub fn flush(&mut self) -> io::Result<()> {
+        for record in self.pending.drain(..) {
+            writeln!(self.storage, "{}", record)?;
+            self.storage_len += record.len() as u64 + 1;
+        }
+        self.storage.flush()?;
+        if self.fsync || self.journal.is_some() {
+            self.storage.sync()?;
+        }
+        if let Some(journal) = &mut self.journal {
+            if journal.records() > 0 {
+                journal.checkpoint(self.storage_len)?;
+            }
+        }
+        self.last_flush = self.clock.now();
+        Ok(())
+    }
+
+    /// Number of records buffered but not yet written.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn get_storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn get_journal(&self) -> Option<&WriteAheadJournal> {
+        self.journal.as_ref()
+    }
+
+    fn is_batch_due(&self) -> bool {
+        match self.policy {
+            FlushPolicy::Batched(interval) => {
+                self.clock
+                    .now()
+                    .duration_since(self.last_flush)
+                    .unwrap_or_default()
+                    >= interval
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::TestClock;
+    use rstest::rstest;
+
+    fn user_joined() -> OrderEvent {
+        OrderEvent::UserJoined {
+            order_id: 0,
+            user_id: 1,
+        }
+    }
+
+    fn status_changed() -> OrderEvent {
+        OrderEvent::StatusChanged {
+            order_id: 0,
+            status: String::from("Ordering"),
+        }
+    }
+
+    #[rstest(
+        policy,
+        event,
+        flushed,
+        case(FlushPolicy::EveryEvent, user_joined(), true),
+        case(FlushPolicy::OnStatusChange, user_joined(), false),
+        case(FlushPolicy::OnStatusChange, status_changed(), true),
+        case(
+            FlushPolicy::Batched(Duration::from_millis(100)),
+            status_changed(),
+            false
+        )
+    )]
+    fn event_is_flushed_according_to_policy(policy: FlushPolicy, event: OrderEvent, flushed: bool) {
+        // Given:
+        let mut writer = BatchWriter::new(Vec::new(), policy, false);
+
+        // When:
+        let result = writer.write(&event).unwrap();
+
+        // Then:
+        assert_eq!(result, flushed);
+        assert_eq!(writer.pending(), if flushed { 0 } else { 1 });
+        assert_eq!(writer.get_storage().is_empty(), !flushed);
+    }
+
+    #[test]
+    fn batch_is_flushed_after_interval() {
+        // Given:
+        let clock = TestClock::default();
+        let mut writer = BatchWriter::with_clock(
+            Vec::new(),
+            FlushPolicy::Batched(Duration::from_millis(100)),
+            true,
+            clock.clone(),
+        );
+        writer.write(&user_joined()).unwrap();
+        clock.advance(Duration::from_millis(99));
+        writer.write(&status_changed()).unwrap();
+
+        // When:
+        clock.advance(Duration::from_millis(1));
+        let flushed = writer.flush_if_due().unwrap();
+
+        // Then:
+        assert!(flushed);
+        assert_eq!(writer.pending(), 0);
+        let written = String::from_utf8(writer.get_storage().clone()).unwrap();
+        assert_eq!(
+            written,
+            "{\"type\":\"UserJoined\",\"order_id\":0,\"user_id\":1}\n\
+             {\"type\":\"StatusChanged\",\"order_id\":0,\"status\":\"Ordering\"}\n"
+        );
+    }
+
+    #[test]
+    fn meals_are_written_interned() {
+        // Given:
+        let mut writer = BatchWriter::new(Vec::new(), FlushPolicy::EveryEvent, false)
+            .with_interning(StringTable::new());
+        let meal = OrderEvent::MealAdded {
+            order_id: 0,
+            user_id: None,
+            meal_id: String::from("03"),
+            variety: String::from("groß"),
+        };
+
+        // When:
+        writer.write(&meal).unwrap();
+        writer.write(&meal).unwrap();
+
+        // Then:
+        let written = String::from_utf8(writer.get_storage().clone()).unwrap();
+        assert_eq!(written.matches("groß").count(), 1);
+        let mut events = Vec::new();
+        intern::read_log(written.as_bytes(), |event| events.push(event)).unwrap();
+        assert_eq!(events, vec![meal.clone(), meal]);
+    }
+
+    #[test]
+    fn empty_batch_is_not_flushed() {
+        // Given:
+        let clock = TestClock::default();
+        let mut writer = BatchWriter::with_clock(
+            Vec::new(),
+            FlushPolicy::Batched(Duration::from_millis(100)),
+            false,
+            clock.clone(),
+        );
+        clock.advance(Duration::from_secs(1));
+
+        // When:
+        let flushed = writer.flush_if_due().unwrap();
+
+        // Then:
+        assert!(!flushed);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::notifications::event::OrderEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// Strings of an event log, each stored once and referenced by its index.
+///
+/// Meal numbers and varieties like "03" or "groß" repeat in almost every `MealAdded` record, so the log only keeps
+/// their index once they were written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StringTable {
+    strings: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, u32>,
+}
+
+impl StringTable {
+    pub fn new() -> StringTable {
+        StringTable::default()
+    }
+
+    /// The index of the string and whether it was new to the table.
+    pub fn intern(&mut self, value: &str) -> (u32, bool) {
+        if let Some(id) = self.ids.get(value) {
+            return (*id, false);
+        }
+        let id = self.strings.len() as u32;
+        let value: Arc<str> = Arc::from(value);
+        self.strings.push(value.clone());
+        self.ids.insert(value, id);
+        (id, true)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize).map(AsRef::as_ref)
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Adds a string read from a log, which has to be the next index.
+    fn define(&mut self, id: u32, value: String) -> bool {
+        if id as usize != self.strings.len() {
+            return false;
+        }
+        self.intern(&value);
+        true
+    }
+}
+
+/// Records of an interned log besides the plain `OrderEvent`s.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Record {
+    /// Entry of the string table, written right before its first reference
+    String { id: u32, value: String },
+    /// `OrderEvent::MealAdded` with references to the string table
+    Meal {
+        order_id: u32,
+        user_id: Option<u32>,
+        meal_id: u32,
+        variety: u32,
+    },
+}
+
+#[derive(Debug)]
+pub enum LogError {
+    Io(io::Error),
+    /// Line number, starting at 1, and what is wrong with the line
+    Malformed(usize, String),
+    /// A record references a string which was not defined before
+    UnknownString(usize, u32),
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use LogError::*;
+        match self {
+            Io(error) => write!(f, "Could not access log: {}", error),
+            Malformed(line, message) => write!(f, "Line {} is malformed: {}", line, message),
+            UnknownString(line, id) => write!(f, "Line {} references unknown string {}", line, id),
+        }
+    }
+}
+
+impl Error for LogError {}
+
+impl From<io::Error> for LogError {
+    fn from(error: io::Error) -> LogError {
+        LogError::Io(error)
+    }
+}
+
+/// The JSON lines the event is written as, a `MealAdded` referencing the table after defining its new strings.
+pub fn encode(event: &OrderEvent, table: &mut StringTable) -> Vec<String> {
+    let OrderEvent::MealAdded {
+        order_id,
+        user_id,
+        meal_id,
+        variety,
+    } = event
+    else {
+        return vec![serde_json::to_string(event).expect("Events are always serializable")];
+    };
+    let mut lines = Vec::new();
+    let mut reference = |value: &str| {
+        let (id, new) = table.intern(value);
+        if new {
+            lines.push(to_line(&Record::String {
+                id,
+                value: String::from(value),
+            }));
+        }
+        id
+    };
+    let meal_id = reference(meal_id);
+    let variety = reference(variety);
+    lines.push(to_line(&Record::Meal {
+        order_id: *order_id,
+        user_id: *user_id,
+        meal_id,
+        variety,
+    }));
+    lines
+}
+
+fn to_line(record: &Record) -> String {
+    serde_json::to_string(record).expect("Records are always serializable")
+}
+
+/// Reads a log written with or without interning, calling `f` with every event.
+///
+/// Returns the string table of the log, for appending to it.
+pub fn read_log<R: BufRead>(
+    reader: R,
+    mut f: impl FnMut(OrderEvent),
+) -> Result<StringTable, LogError> {
+    let mut table = StringTable::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Record>(&line) {
+            Ok(Record::String { id, value }) => {
+                if !table.define(id, value) {
+                    return Err(LogError::Malformed(
+                        number,
+                        format!("string {} is out of sequence", id),
+                    ));
+                }
+            }
+            Ok(Record::Meal {
+                order_id,
+                user_id,
+                meal_id,
+                variety,
+            }) => {
+                let resolve = |id| {
+                    table
+                        .get(id)
+                        .map(String::from)
+                        .ok_or(LogError::UnknownString(number, id))
+                };
+                f(OrderEvent::MealAdded {
+                    order_id,
+                    user_id,
+                    meal_id: resolve(meal_id)?,
+                    variety: resolve(variety)?,
+                });
+            }
+            Err(_) => {
+                let event = serde_json::from_str(&line)
+                    .map_err(|error| LogError::Malformed(number, error.to_string()))?;
+                f(event);
+            }
+        }
+    }
+    Ok(table)
+}
+
+/// Counts how often each meal and variety was ordered, e.g. for statistics over the archive.
+///
+/// Meals are counted by the indices of their strings, so the keys are small and each distinct meal is only copied
+/// into the result once.
+pub fn count_meals<R: BufRead>(reader: R) -> Result<HashMap<(String, String), usize>, LogError> {
+    let mut table = StringTable::new();
+    let mut counts: HashMap<(u32, u32), usize> = HashMap::new();
+    read_log(reader, |event| {
+        if let OrderEvent::MealAdded {
+            meal_id, variety, ..
+        } = event
+        {
+            let key = (table.intern(&meal_id).0, table.intern(&variety).0);
+            *counts.entry(key).or_default() += 1;
+        }
+    })?;
+    Ok(counts
+        .into_iter()
+        .map(|((meal_id, variety), count)| {
+            let resolve = |id| String::from(table.get(id).expect("Counted strings are interned"));
+            ((resolve(meal_id), resolve(variety)), count)
+        })
+        .collect())
+}
+
+/// What `migrate` did to a log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Migration {
+    /// Number of events
+    pub events: usize,
+    /// Number of distinct strings in the table
+    pub strings: usize,
+}
+
+/// Rewrites an existing log, e.g. one written before interning, to an interned one.
+///
+/// Logs already interned are rewritten as well, which drops strings no record references anymore.
+pub fn migrate<R: BufRead, W: Write>(log: R, mut interned: W) -> Result<Migration, LogError> {
+    let mut table = StringTable::new();
+    let mut events = 0;
+    let mut written = Ok(());
+    read_log(log, |event| {
+        events += 1;
+        for line in encode(&event, &mut table) {
+            if written.is_ok() {
+                written = writeln!(interned, "{}", line);
+            }
+        }
+    })?;
+    written?;
+    interned.flush()?;
+    Ok(Migration {
+        events,
+        strings: table.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meal_added(meal_id: &str, variety: &str) -> OrderEvent {
+        OrderEvent::MealAdded {
+            order_id: 0,
+            user_id: Some(1),
+            meal_id: String::from(meal_id),
+            variety: String::from(variety),
+        }
+    }
+
+    fn events_of(log: &str) -> Vec<OrderEvent> {
+        let mut events = Vec::new();
+        read_log(log.as_bytes(), |event| events.push(event)).unwrap();
+        events
+    }
+
+    #[test]
+    fn strings_are_defined_once() {
+        // Given:
+        let mut table = StringTable::new();
+
+        // When:
+        let first = encode(&meal_added("03", "groß"), &mut table);
+        let second = encode(&meal_added("03", "groß"), &mut table);
+
+        // Then:
+        assert_eq!(
+            first,
+            vec![
+                r#"{"type":"String","id":0,"value":"03"}"#,
+                r#"{"type":"String","id":1,"value":"groß"}"#,
+                r#"{"type":"Meal","order_id":0,"user_id":1,"meal_id":0,"variety":1}"#
+            ]
+        );
+        assert_eq!(
+            second,
+            vec![r#"{"type":"Meal","order_id":0,"user_id":1,"meal_id":0,"variety":1}"#]
+        );
+    }
+
+    #[test]
+    fn plain_log_is_migrated() {
+        // Given:
+        let plain = String::from("{\"type\":\"UserJoined\",\"order_id\":0,\"user_id\":1}\n")
+            + &"{\"type\":\"MealAdded\",\"order_id\":0,\"user_id\":1,\"meal_id\":\"03\",\"variety\":\"groß\"}\n"
+                .repeat(10);
+        let mut interned = Vec::new();
+
+        // When:
+        let migration = migrate(plain.as_bytes(), &mut interned).unwrap();
+
+        // Then:
+        assert_eq!(
+            migration,
+            Migration {
+                events: 11,
+                strings: 2
+            }
+        );
+        let interned = String::from_utf8(interned).unwrap();
+        assert!(interned.len() < plain.len());
+        assert_eq!(events_of(&interned), events_of(&plain));
+    }
+
+    #[test]
+    fn meals_are_counted() {
+        // Given:
+        let mut table = StringTable::new();
+        let log: String = [
+            meal_added("03", "groß"),
+            meal_added("12", "klein"),
+            meal_added("03", "groß"),
+        ]
+        .iter()
+        .flat_map(|event| encode(event, &mut table))
+        .map(|line| line + "\n")
+        .collect();
+
+        // When:
+        let counts = count_meals(log.as_bytes()).unwrap();
+
+        // Then:
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&(String::from("03"), String::from("groß"))], 2);
+        assert_eq!(counts[&(String::from("12"), String::from("klein"))], 1);
+    }
+
+    #[test]
+    fn undefined_string_is_rejected() {
+        // Given:
+        let log = r#"{"type":"Meal","order_id":0,"user_id":null,"meal_id":0,"variety":1}"#;
+
+        // When:
+        let result = read_log(log.as_bytes(), |_| {});
+
+        // Then:
+        assert!(matches!(result, Err(LogError::UnknownString(1, 0))));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::persistence::flush::Storage;
+use crate::persistence::intern::LogError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Storage of a journal, which starts over at every checkpoint.
+pub trait JournalStorage: Storage {
+    /// Removes everything written so far, so the next write starts at the beginning.
+    fn clear(&mut self) -> io::Result<()>;
+}
+
+impl JournalStorage for File {
+    fn clear(&mut self) -> io::Result<()> {
+        self.set_len(0)?;
+        self.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+}
+
+/// In memory journal, e.g. for tests.
+impl JournalStorage for Vec<u8> {
+    fn clear(&mut self) -> io::Result<()> {
+        Vec::clear(self);
+        Ok(())
+    }
+}
+
+/// First line of a journal, the length of the storage its records are appended to.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    storage_len: u64,
+}
+
+/// Write-ahead journal of the records a `BatchWriter` buffers, see `BatchWriter::with_journal`.
+///
+/// Every record is synced to the journal before the write returns, so buffered records survive a crash. Once
+/// they are synced to the storage a checkpoint starts the journal over with the new length of the storage.
+pub struct WriteAheadJournal {
+    journal: Box<dyn JournalStorage + Send>,
+    /// Records written since the last checkpoint
+    records: usize,
+}
+
+impl WriteAheadJournal {
+    /// Starts the journal over for a storage of the given length, discarding the records written before.
+    pub fn new(
+        journal: impl JournalStorage + Send + 'static,
+        storage_len: u64,
+    ) -> io::Result<WriteAheadJournal> {
+        let mut journal = WriteAheadJournal {
+            journal: Box::new(journal),
+            records: 0,
+        };
+        journal.checkpoint(storage_len)?;
+        Ok(journal)
+    }
+
+    /// Writes the records and syncs the journal.
+    pub fn append(&mut self, records: &[String]) -> io::Result<()> {
+        for record in records {
+            writeln!(self.journal, "{}", record)?;
+        }
+        self.journal.flush()?;
+        self.journal.sync()?;
+        self.records += records.len();
+        Ok(())
+    }
+
+    /// Starts the journal over, as every record in it was synced to the storage, which is now as long as given.
+    pub fn checkpoint(&mut self, storage_len: u64) -> io::Result<()> {
+        self.journal.clear()?;
+        writeln!(
+            self.journal,
+            "{}",
+            serde_json::to_string(&Checkpoint { storage_len })
+                .expect("Checkpoints are always serializable")
+        )?;
+        self.journal.flush()?;
+        self.journal.sync()?;
+        self.records = 0;
+        Ok(())
+    }
+
+    /// Number of records written since the last checkpoint.
+    pub fn records(&self) -> usize {
+        self.records
+    }
+}
+
+impl fmt::Debug for WriteAheadJournal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WriteAheadJournal")
+            .field("records", &self.records)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Outcome of `recover`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    /// Records of the journal written to the storage again
+    pub replayed: usize,
+    /// Length of the storage after the recovery, to start the journal over with
+    pub storage_len: u64,
+}
+
+/// Brings the storage up to date with the journal after a crash, meant to be called on startup.
+///
+/// The storage is cut back to the length of the last checkpoint and the records of the journal are appended, so
+/// records written to the storage before the crash are not duplicated. A last record without line break was
+/// never acknowledged and is dropped. The journal itself is left as is, recovering again gives the same storage.
+///
+/// Afterwards the storage is positioned at its end, ready to be appended to.
+pub fn recover(storage: &mut File, journal: &mut File) -> Result<Recovery, LogError> {
+    let mut content = String::new();
+    journal.seek(SeekFrom::Start(0))?;
+    journal.read_to_string(&mut content)?;
+    let mut lines: Vec<&str> = content.split_terminator('\n').collect();
+    if !content.ends_with('\n') {
+        lines.pop();
+    }
+    let (checkpoint, records) = match lines.split_first() {
+        Some(lines) => lines,
+        None => {
+            return Ok(Recovery {
+                replayed: 0,
+                storage_len: storage.seek(SeekFrom::End(0))?,
+            })
+        }
+    };
+    let checkpoint: Checkpoint = serde_json::from_str(checkpoint)
+        .map_err(|error| LogError::Malformed(1, error.to_string()))?;
+    let storage_len = storage.metadata()?.len();
+    if storage_len < checkpoint.storage_len {
+        return Err(LogError::Malformed(
+            1,
+            format!(
+                "storage has {} bytes, less than the {} of the checkpoint",
+                storage_len, checkpoint.storage_len
+            ),
+        ));
+    }
+    storage.set_len(checkpoint.storage_len)?;
+    storage.seek(SeekFrom::End(0))?;
+    for record in records {
+        writeln!(storage, "{}", record)?;
+    }
+    storage.flush()?;
+    storage.sync()?;
+    Ok(Recovery {
+        replayed: records.len(),
+        storage_len: storage.metadata()?.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::event::OrderEvent;
+    use crate::persistence::flush::{BatchWriter, FlushPolicy};
+    use crate::persistence::intern::{self, StringTable};
+    use std::fs::{self, OpenOptions};
+    use std::path::PathBuf;
+    use std::process;
+    use std::time::Duration;
+
+    /// Storage and journal files, removed when the test ends.
+    struct Files {
+        storage: PathBuf,
+        journal: PathBuf,
+    }
+
+    impl Files {
+        fn new(test: &str) -> Files {
+            let path = |kind: &str| {
+                std::env::temp_dir().join(format!(
+                    "rusty_pizza_{}_{}_{}.jsonl",
+                    test,
+                    kind,
+                    process::id()
+                ))
+            };
+            Files {
+                storage: path("storage"),
+                journal: path("journal"),
+            }
+        }
+
+        fn open(path: &PathBuf) -> File {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)
+                .unwrap()
+        }
+
+        /// Starts a writer as the server would, recovering what the last run left behind.
+        fn start(&self) -> (BatchWriter<File>, Recovery) {
+            let mut storage = Files::open(&self.storage);
+            let mut journal = Files::open(&self.journal);
+            let recovery = recover(&mut storage, &mut journal).unwrap();
+            let (_, strings) = self.read();
+            let writer = BatchWriter::new(
+                storage,
+                FlushPolicy::Batched(Duration::from_secs(3600)),
+                false,
+            )
+            .with_interning(strings)
+            .with_journal(journal, recovery.storage_len)
+            .unwrap();
+            (writer, recovery)
+        }
+
+        fn read(&self) -> (Vec<OrderEvent>, StringTable) {
+            let mut events = Vec::new();
+            let log = fs::read(&self.storage).unwrap_or_default();
+            let strings = intern::read_log(log.as_slice(), |event| events.push(event)).unwrap();
+            (events, strings)
+        }
+    }
+
+    impl Drop for Files {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.storage);
+            let _ = fs::remove_file(&self.journal);
+        }
+    }
+
+    fn meal_added(user_id: u32) -> OrderEvent {
+        OrderEvent::MealAdded {
+            order_id: 0,
+            user_id: Some(user_id),
+            meal_id: String::from("03"),
+            variety: String::from("groß"),
+        }
+    }
+
+    #[test]
+    fn acknowledged_events_survive_crash_before_flush() {
+        // Given:
+        let files = Files::new("crash_before_flush");
+        let (mut writer, _) = files.start();
+        writer.write(&meal_added(0)).unwrap();
+        writer.flush().unwrap();
+        writer.write(&meal_added(1)).unwrap();
+        writer.write(&meal_added(2)).unwrap();
+
+        // When:
+        drop(writer);
+        let (mut writer, recovery) = files.start();
+        writer.write(&meal_added(3)).unwrap();
+        writer.flush().unwrap();
+
+        // Then:
+        assert_eq!(recovery.replayed, 2);
+        let (events, strings) = files.read();
+        assert_eq!(events, (0..4).map(meal_added).collect::<Vec<_>>());
+        assert_eq!(strings.len(), 2);
+    }
+
+    #[test]
+    fn records_are_not_duplicated_after_crash_before_checkpoint() {
+        // Given:
+        let files = Files::new("crash_before_checkpoint");
+        let (mut writer, _) = files.start();
+        writer.write(&meal_added(0)).unwrap();
+        let journal = fs::read(&files.journal).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        // As if the crash happened after the storage was synced, but before the checkpoint
+        fs::write(&files.journal, journal).unwrap();
+
+        // When:
+        let (_, first) = files.start();
+        let (_, second) = files.start();
+
+        // Then:
+        // The two strings of the meal and the meal itself
+        assert_eq!(first.replayed, 3);
+        assert_eq!(second.replayed, 0);
+        assert_eq!(files.read().0, vec![meal_added(0)]);
+    }
+
+    #[test]
+    fn torn_record_is_dropped() {
+        // Given:
+        let files = Files::new("torn_record");
+        let (mut writer, _) = files.start();
+        writer.write(&meal_added(0)).unwrap();
+        drop(writer);
+        let mut journal = OpenOptions::new()
+            .append(true)
+            .open(&files.journal)
+            .unwrap();
+        write!(journal, "{{\"type\":\"Meal\",\"order_").unwrap();
+
+        // When:
+        let (_, recovery) = files.start();
+
+        // Then:
+        // The two strings of the meal and the meal itself
+        assert_eq!(recovery.replayed, 3);
+        assert_eq!(files.read().0, vec![meal_added(0)]);
+    }
+
+    #[test]
+    fn journal_is_cleared_by_flush() {
+        // Given:
+        let mut writer = BatchWriter::new(
+            Vec::new(),
+            FlushPolicy::Batched(Duration::from_secs(3600)),
+            false,
+        )
+        .with_journal(Vec::new(), 0)
+        .unwrap();
+        writer.write(&meal_added(0)).unwrap();
+        let journaled = writer.get_journal().unwrap().records();
+
+        // When:
+        writer.flush().unwrap();
+
+        // Then:
+        assert_eq!(journaled, 1);
+        assert_eq!(writer.get_journal().unwrap().records(), 0);
+        assert!(!writer.get_storage().is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod ephemeral;
+pub mod flush;
+pub mod intern;
+pub mod journal;
+pub mod templates;
@@ -0,0 +1,246 @@
+use crate::order_model::order_template::{OrderTemplate, OrderTemplates};
+use crate::order_model::restaurant::{OpeningHours, OpeningPeriod, Restaurant};
+use crate::persistence::intern::LogError;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use chrono::{FixedOffset, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// A template as JSON line, times like "11:30" and days like "Fri".
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TemplateRecord {
+    id: u32,
+    name: String,
+    manager_id: u32,
+    participants: Vec<u32>,
+    deadline: Option<String>,
+    restaurant: RestaurantRecord,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct RestaurantRecord {
+    name: String,
+    phone: Option<String>,
+    min_order_value_cents: u32,
+    delivery_fee_cents: u32,
+    opening_hours: Option<OpeningHoursRecord>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct OpeningHoursRecord {
+    utc_offset_seconds: i32,
+    /// Day, opens and closes
+    periods: Vec<(String, String, String)>,
+    closed_days: Vec<String>,
+    /// Day and time
+    cutoffs: Vec<(String, String)>,
+}
+
+const TIME_FORMAT: &str = "%H:%M";
+
+fn format_time(time: NaiveTime) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+impl TemplateRecord {
+    fn from_template(id: &Id<OrderTemplate>, template: &OrderTemplate) -> TemplateRecord {
+        let restaurant = template.get_restaurant();
+        TemplateRecord {
+            id: id.get_value(),
+            name: template.get_name().clone(),
+            manager_id: template.get_manager_id().get_value(),
+            participants: template.participants().iter().map(Id::get_value).collect(),
+            deadline: template.get_deadline().map(format_time),
+            restaurant: RestaurantRecord {
+                name: restaurant.get_name().clone(),
+                phone: restaurant.get_phone().cloned(),
+                min_order_value_cents: restaurant.get_min_order_value().get_total_cents(),
+                delivery_fee_cents: restaurant.get_delivery_fee().get_total_cents(),
+                opening_hours: restaurant
+                    .get_opening_hours()
+                    .map(|hours| OpeningHoursRecord {
+                        utc_offset_seconds: hours.get_utc_offset().local_minus_utc(),
+                        periods: hours
+                            .periods()
+                            .iter()
+                            .map(|period| {
+                                (
+                                    period.get_day().to_string(),
+                                    format_time(period.get_opens()),
+                                    format_time(period.get_closes()),
+                                )
+                            })
+                            .collect(),
+                        closed_days: hours.closed_days().iter().map(Weekday::to_string).collect(),
+                        cutoffs: hours
+                            .cutoffs()
+                            .iter()
+                            .map(|(day, time)| (day.to_string(), format_time(*time)))
+                            .collect(),
+                    }),
+            },
+        }
+    }
+
+    /// The template, or what is wrong with the record.
+    fn into_template(self) -> Result<(Id<OrderTemplate>, OrderTemplate), String> {
+        let time = |time: &str| {
+            NaiveTime::parse_from_str(time, TIME_FORMAT)
+                .map_err(|_| format!("{} is not a time like 11:30", time))
+        };
+        let day = |day: &str| {
+            day.parse::<Weekday>()
+                .map_err(|_| format!("{} is not a day like Fri", day))
+        };
+        let record = self.restaurant;
+        let mut restaurant = Restaurant::new(record.name)
+            .with_min_order_value(Money::from_cents(record.min_order_value_cents))
+            .with_delivery_fee(Money::from_cents(record.delivery_fee_cents));
+        if let Some(phone) = record.phone {
+            restaurant = restaurant.with_phone(phone);
+        }
+        if let Some(record) = record.opening_hours {
+            let offset = FixedOffset::east_opt(record.utc_offset_seconds)
+                .ok_or_else(|| format!("{} is not a UTC offset", record.utc_offset_seconds))?;
+            let mut hours = OpeningHours::new(offset);
+            for (period_day, opens, closes) in &record.periods {
+                hours = hours.with_period(OpeningPeriod::new(
+                    day(period_day)?,
+                    time(opens)?,
+                    time(closes)?,
+                ));
+            }
+            for closed_day in &record.closed_days {
+                hours = hours.with_closed_day(day(closed_day)?);
+            }
+            for (cutoff_day, cutoff) in &record.cutoffs {
+                hours = hours.with_cutoff(day(cutoff_day)?, time(cutoff)?);
+            }
+            restaurant = restaurant.with_opening_hours(hours);
+        }
+        let mut template = OrderTemplate::new(self.name, Id::new(self.manager_id), restaurant);
+        for user_id in self.participants {
+            template = template.with_participant(Id::new(user_id));
+        }
+        if let Some(deadline) = &self.deadline {
+            template = template.with_deadline(time(deadline)?);
+        }
+        Ok((Id::new(self.id), template))
+    }
+}
+
+/// Writes the templates as JSON lines, one per template.
+pub fn write_templates<W: Write>(templates: &OrderTemplates, mut writer: W) -> io::Result<()> {
+    for (id, template) in templates.templates() {
+        let record = TemplateRecord::from_template(id, template);
+        writeln!(
+            writer,
+            "{}",
+            serde_json::to_string(&record).expect("Templates are always serializable")
+        )?;
+    }
+    writer.flush()
+}
+
+/// Reads templates written by `write_templates`, keeping their IDs.
+pub fn read_templates<R: BufRead>(reader: R) -> Result<OrderTemplates, LogError> {
+    let mut templates = OrderTemplates::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, template) = serde_json::from_str::<TemplateRecord>(&line)
+            .map_err(|error| error.to_string())
+            .and_then(TemplateRecord::into_template)
+            .map_err(|message| LogError::Malformed(number, message))?;
+        templates.restore(id, template);
+    }
+    Ok(templates)
+}
+
+/// Saves the templates to the file, replacing it only once all are written, so a crash keeps the old ones.
+pub fn save_templates(templates: &OrderTemplates, path: &Path) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let mut file = File::create(&temporary)?;
+    write_templates(templates, &mut file)?;
+    file.sync_all()?;
+    fs::rename(temporary, path)
+}
+
+/// Loads the templates saved to the file, none if it doesn't exist yet.
+pub fn load_templates(path: &Path) -> Result<OrderTemplates, LogError> {
+    match File::open(path) {
+        Ok(file) => read_templates(BufReader::new(file)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(OrderTemplates::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn friday_pizza() -> OrderTemplate {
+        let hours = OpeningHours::new(FixedOffset::east_opt(2 * 60 * 60).unwrap())
+            .with_period(OpeningPeriod::new(
+                Weekday::Fri,
+                NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            ))
+            .with_closed_day(Weekday::Mon)
+            .with_cutoff(Weekday::Sun, NaiveTime::from_hms_opt(20, 30, 0).unwrap());
+        let restaurant = Restaurant::new(String::from("Pizzeria Luigi"))
+            .with_phone(String::from("0241 12345"))
+            .with_delivery_fee(Money::new(2, 50))
+            .with_opening_hours(hours);
+        OrderTemplate::new(String::from("Friday pizza"), Id::new(0), restaurant)
+            .with_participant(Id::new(1))
+            .with_deadline(NaiveTime::from_hms_opt(11, 30, 0).unwrap())
+    }
+
+    #[test]
+    fn templates_are_read_as_written() {
+        // Given:
+        let mut templates = OrderTemplates::new();
+        templates.add(friday_pizza());
+        templates.add(OrderTemplate::new(
+            String::from("Sushi"),
+            Id::new(3),
+            Restaurant::new(String::from("Sushi Bar")),
+        ));
+        templates.remove(&Id::new(0));
+        templates.restore(Id::new(4), friday_pizza());
+        let mut written = Vec::new();
+
+        // When:
+        write_templates(&templates, &mut written).unwrap();
+        let read = read_templates(written.as_slice()).unwrap();
+
+        // Then:
+        assert_eq!(read, templates);
+        assert_eq!(read.get(&Id::new(4)), Some(&friday_pizza()));
+    }
+
+    #[test]
+    fn malformed_template_is_reported_with_line() {
+        // Given:
+        let mut written = Vec::new();
+        let mut templates = OrderTemplates::new();
+        templates.add(friday_pizza());
+        write_templates(&templates, &mut written).unwrap();
+        let log = String::from_utf8(written)
+            .unwrap()
+            .replace("11:30", "half past eleven");
+
+        // When:
+        let result = read_templates(format!("\n{}", log).as_bytes());
+
+        // Then:
+        assert!(matches!(result, Err(LogError::Malformed(2, _))));
+    }
+}
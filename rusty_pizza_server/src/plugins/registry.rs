@@ -0,0 +1,251 @@
+use crate::order_model::order::Order;
+use crate::util::id::Id;
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// A company specific rule checked before an order is placed, e.g. "Friday is vegetarian day".
+pub trait PlacementCheck: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    /// Returns why the order must not be placed at `now`, if it must not.
+    fn check(&self, order: &Order, now: DateTime<Utc>) -> Result<(), String>;
+}
+
+/// Something done once all participants of an order paid, e.g. posting to a chat.
+pub trait SettlementAction: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    fn on_settled(&self, order_id: &Id<Order>, order: &Order);
+}
+
+/// A `PlacementCheck` refused to let an order be placed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginRejection {
+    plugin: String,
+    reason: String,
+}
+
+impl PluginRejection {
+    pub fn get_plugin(&self) -> &String {
+        &self.plugin
+    }
+
+    pub fn get_reason(&self) -> &String {
+        &self.reason
+    }
+}
+
+impl fmt::Display for PluginRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rejected by {}: {}", self.plugin, self.reason)
+    }
+}
+
+impl Error for PluginRejection {}
+
+/// Plugins registered at startup, so deployments can add business rules without forking the crate.
+///
+/// Plugins are sandboxed against panics: a panicking check rejects the order, a panicking action is skipped
+/// without affecting the other actions.
+#[derive(Default)]
+pub struct PluginRegistry {
+    checks: Vec<Box<dyn PlacementCheck>>,
+    actions: Vec<Box<dyn SettlementAction>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> PluginRegistry {
+        PluginRegistry::default()
+    }
+
+    pub fn register_check(&mut self, check: Box<dyn PlacementCheck>) {
+        self.checks.push(check);
+    }
+
+    pub fn register_action(&mut self, action: Box<dyn SettlementAction>) {
+        self.actions.push(action);
+    }
+
+    /// Runs all checks in the order they were registered and returns the first rejection.
+    pub fn check_placement(
+        &self,
+        order: &Order,
+        now: DateTime<Utc>,
+    ) -> Result<(), PluginRejection> {
+        for check in &self.checks {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| check.check(order, now)))
+                .unwrap_or_else(|_| Err(String::from("check panicked")));
+            result.map_err(|reason| PluginRejection {
+                plugin: check.get_name().to_string(),
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs all actions for the settled order and returns the names of those which panicked.
+    pub fn run_settlement(&self, order_id: &Id<Order>, order: &Order) -> Vec<String> {
+        self.actions
+            .iter()
+            .filter(|action| {
+                panic::catch_unwind(AssertUnwindSafe(|| action.on_settled(order_id, order)))
+                    .is_err()
+            })
+            .map(|action| action.get_name().to_string())
+            .collect()
+    }
+}
+
+impl fmt::Debug for PluginRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PluginRegistry")
+            .field(
+                "checks",
+                &self.checks.iter().map(|c| c.get_name()).collect::<Vec<_>>(),
+            )
+            .field(
+                "actions",
+                &self
+                    .actions
+                    .iter()
+                    .map(|a| a.get_name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::money::Money;
+    use chrono::{Datelike, TimeZone, Weekday};
+    use std::sync::{Arc, Mutex};
+
+    struct VegetarianFriday;
+
+    impl PlacementCheck for VegetarianFriday {
+        fn get_name(&self) -> &str {
+            "vegetarian friday"
+        }
+
+        fn check(&self, order: &Order, now: DateTime<Utc>) -> Result<(), String> {
+            let salami = order
+                .all_meals()
+                .any(|meal| meal.get_variety().contains("Salami"));
+            if now.weekday() == Weekday::Fri && salami {
+                Err(String::from("Friday is vegetarian day"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct Panicking;
+
+    impl PlacementCheck for Panicking {
+        fn get_name(&self) -> &str {
+            "panicking"
+        }
+
+        fn check(&self, _: &Order, _: DateTime<Utc>) -> Result<(), String> {
+            panic!("broken plugin")
+        }
+    }
+
+    impl SettlementAction for Panicking {
+        fn get_name(&self) -> &str {
+            "panicking"
+        }
+
+        fn on_settled(&self, _: &Id<Order>, _: &Order) {
+            panic!("broken plugin")
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        settled: Arc<Mutex<Vec<Id<Order>>>>,
+    }
+
+    impl SettlementAction for Recorder {
+        fn get_name(&self) -> &str {
+            "recorder"
+        }
+
+        fn on_settled(&self, order_id: &Id<Order>, _: &Order) {
+            self.settled.lock().unwrap().push(order_id.clone());
+        }
+    }
+
+    fn salami_order() -> Order {
+        let mut order = Order::new(Id::new(0));
+        order
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("07"),
+                String::from("Salami"),
+                Money::new(7, 0),
+            )
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn check_rejects_order() {
+        // Given:
+        let mut registry = PluginRegistry::new();
+        registry.register_check(Box::new(VegetarianFriday));
+        let friday = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let thursday = Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap();
+
+        // When:
+        let on_friday = registry.check_placement(&salami_order(), friday);
+        let on_thursday = registry.check_placement(&salami_order(), thursday);
+
+        // Then:
+        assert_eq!(
+            on_friday.unwrap_err().to_string(),
+            "rejected by vegetarian friday: Friday is vegetarian day"
+        );
+        assert_eq!(on_thursday, Ok(()));
+    }
+
+    #[test]
+    fn panicking_check_rejects_order() {
+        // Given:
+        let mut registry = PluginRegistry::new();
+        registry.register_check(Box::new(Panicking));
+
+        // When:
+        let result = registry.check_placement(&salami_order(), Utc::now());
+
+        // Then:
+        assert_eq!(
+            result,
+            Err(PluginRejection {
+                plugin: String::from("panicking"),
+                reason: String::from("check panicked")
+            })
+        );
+    }
+
+    #[test]
+    fn panicking_action_does_not_stop_others() {
+        // Given:
+        let recorder = Recorder::default();
+        let settled = recorder.settled.clone();
+        let mut registry = PluginRegistry::new();
+        registry.register_action(Box::new(Panicking));
+        registry.register_action(Box::new(recorder));
+
+        // When:
+        let failed = registry.run_settlement(&Id::new(3), &salami_order());
+
+        // Then:
+        assert_eq!(failed, vec![String::from("panicking")]);
+        assert_eq!(*settled.lock().unwrap(), vec![Id::new(3)]);
+    }
+}
@@ -0,0 +1,333 @@
+use crate::order_model::meal_spec::MealSpec;
+use crate::util::locale::Locale;
+use crate::util::money::Money;
+use std::error::Error;
+use std::fmt;
+
+/// Error while parsing a quick entry line.
+///
+/// Every variant carries the `position` of the offending input as a zero-based character (not byte) offset, so
+/// clients can point the user directly to their typo.
+#[derive(Debug, PartialEq, Eq)]
+pub enum QuickEntryError {
+    /// There is nothing between two `;`
+    EmptyEntry { position: usize },
+    /// The quantity in front of the `x` is zero or not a valid number
+    InvalidQuantity { position: usize },
+    /// An entry does not name a meal
+    MissingMealId { position: usize },
+    /// The meal number contains a character that is neither a letter nor a digit
+    InvalidMealId { position: usize },
+    /// There is a `+` without a special description after it
+    EmptySpecial { position: usize },
+    /// The price after the `@` is no amount in the locale, or the entry has more than one price
+    InvalidPrice { position: usize },
+}
+
+impl QuickEntryError {
+    pub fn get_position(&self) -> usize {
+        use QuickEntryError::*;
+        match *self {
+            EmptyEntry { position }
+            | InvalidQuantity { position }
+            | MissingMealId { position }
+            | InvalidMealId { position }
+            | EmptySpecial { position }
+            | InvalidPrice { position } => position,
+        }
+    }
+}
+
+impl fmt::Display for QuickEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use QuickEntryError::*;
+        match *self {
+            EmptyEntry { position } => write!(f, "Empty entry at position {}", position),
+            InvalidQuantity { position } => write!(f, "Invalid quantity at position {}", position),
+            MissingMealId { position } => write!(f, "Missing meal number at position {}", position),
+            InvalidMealId { position } => {
+                write!(
+                    f,
+                    "Invalid character in meal number at position {}",
+                    position
+                )
+            }
+            EmptySpecial { position } => write!(f, "Empty special at position {}", position),
+            InvalidPrice { position } => write!(f, "Invalid price at position {}", position),
+        }
+    }
+}
+
+impl Error for QuickEntryError {}
+
+const ENTRY_SEPARATOR: char = ';';
+const SPECIAL_MARKER: char = '+';
+const PRICE_MARKER: char = '@';
+
+/// Parses a one-line order like `2x03 groß +Käserand +Knoblauch; 1x17 klein` into `MealSpec`s.
+///
+/// Entries are separated by `;`. Each entry consists of an optional quantity followed by `x` (defaults to 1), the
+/// meal number, an optional variety of one or more words and any number of specials, each introduced by `+`.
+/// A trailing `;` is allowed.
+///
+/// The price of a single meal may follow the meal number, introduced by `@` and written as in Germany, e.g.
+/// `03 groß @1.234,50`. Use `parse_localized` for users of other locales.
+pub fn parse(input: &str) -> Result<Vec<MealSpec>, QuickEntryError> {
+    parse_localized(input, Locale::default())
+}
+
+/// Like `parse`, with prices written as usual in the locale, e.g. the one of the order.
+pub fn parse_localized(input: &str, locale: Locale) -> Result<Vec<MealSpec>, QuickEntryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut specs = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = find(&chars, start, chars.len(), ENTRY_SEPARATOR).unwrap_or(chars.len());
+        let is_last = end == chars.len();
+        if chars[start..end].iter().all(|c| c.is_whitespace()) {
+            if is_last {
+                break;
+            }
+            return Err(QuickEntryError::EmptyEntry { position: end });
+        }
+        specs.push(parse_entry(&chars, start, end, locale)?);
+        if is_last {
+            break;
+        }
+        start = end + 1;
+    }
+    Ok(specs)
+}
+
+fn parse_entry(
+    chars: &[char],
+    start: usize,
+    end: usize,
+    locale: Locale,
+) -> Result<MealSpec, QuickEntryError> {
+    let head_end = find(chars, start, end, SPECIAL_MARKER).unwrap_or(end);
+    let mut words = split_words(chars, start, head_end).into_iter();
+
+    let (first_position, first_word) = words.next().ok_or(QuickEntryError::MissingMealId {
+        position: skip_whitespace(chars, start, head_end),
+    })?;
+    let (quantity, meal_id_position, meal_id) = match split_quantity(&first_word) {
+        Some((digits, rest)) => {
+            let quantity = parse_quantity(&digits, first_position)?;
+            if rest.is_empty() {
+                let after_x = first_position + digits.chars().count() + 1;
+                let (position, meal_id) = words.next().ok_or(QuickEntryError::MissingMealId {
+                    position: skip_whitespace(chars, after_x, head_end),
+                })?;
+                (quantity, position, meal_id)
+            } else {
+                (quantity, first_position + digits.chars().count() + 1, rest)
+            }
+        }
+        None => (1, first_position, first_word),
+    };
+    if let Some(offset) = meal_id.chars().position(|c| !c.is_alphanumeric()) {
+        return Err(QuickEntryError::InvalidMealId {
+            position: meal_id_position + offset,
+        });
+    }
+    let mut price = None;
+    let mut variety = Vec::new();
+    for (position, word) in words {
+        match word.strip_prefix(PRICE_MARKER) {
+            Some(amount) if price.is_none() => {
+                price = Some(
+                    Money::parse_localized(amount, locale)
+                        .map_err(|_| QuickEntryError::InvalidPrice { position })?,
+                );
+            }
+            Some(_) => return Err(QuickEntryError::InvalidPrice { position }),
+            None => variety.push(word),
+        }
+    }
+    let variety = variety.join(" ");
+
+    let mut specials = Vec::new();
+    let mut special_start = head_end;
+    while special_start < end {
+        let special_end = find(chars, special_start + 1, end, SPECIAL_MARKER).unwrap_or(end);
+        let special = split_words(chars, special_start + 1, special_end)
+            .into_iter()
+            .map(|(_, word)| word)
+            .collect::<Vec<String>>()
+            .join(" ");
+        if special.is_empty() {
+            return Err(QuickEntryError::EmptySpecial {
+                position: special_start,
+            });
+        }
+        specials.push(special);
+        special_start = special_end;
+    }
+
+    let spec = MealSpec::new(quantity, meal_id, variety, specials);
+    Ok(match price {
+        Some(price) => spec.with_price(price),
+        None => spec,
+    })
+}
+
+/// Returns the position of the first `needle` within `chars[from..to]`.
+fn find(chars: &[char], from: usize, to: usize, needle: char) -> Option<usize> {
+    chars[from..to]
+        .iter()
+        .position(|c| *c == needle)
+        .map(|offset| from + offset)
+}
+
+fn skip_whitespace(chars: &[char], from: usize, to: usize) -> usize {
+    chars[from..to]
+        .iter()
+        .position(|c| !c.is_whitespace())
+        .map_or(to, |offset| from + offset)
+}
+
+/// Splits `chars[from..to]` at whitespace into words along with their starting positions.
+fn split_words(chars: &[char], from: usize, to: usize) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (position, c) in chars.iter().enumerate().take(to).skip(from) {
+        if c.is_whitespace() {
+            words.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| (position, String::new()))
+                .1
+                .push(*c);
+        }
+    }
+    words.extend(current);
+    words
+}
+
+/// Splits a word like `2x03` into the quantity digits and the rest after the `x`.
+fn split_quantity(word: &str) -> Option<(String, String)> {
+    let digits: String = word.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let mut rest = word[digits.len()..].chars();
+    match rest.next() {
+        Some('x') | Some('X') if !digits.is_empty() => Some((digits, rest.collect())),
+        _ => None,
+    }
+}
+
+fn parse_quantity(digits: &str, position: usize) -> Result<u32, QuickEntryError> {
+    match digits.parse::<u32>() {
+        Ok(quantity) if quantity > 0 => Ok(quantity),
+        _ => Err(QuickEntryError::InvalidQuantity { position }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::locale::Currency;
+    use rstest::rstest;
+
+    fn spec(quantity: u32, meal_id: &str, variety: &str, specials: Vec<&str>) -> MealSpec {
+        MealSpec::new(
+            quantity,
+            String::from(meal_id),
+            String::from(variety),
+            specials.into_iter().map(String::from).collect(),
+        )
+    }
+
+    #[test]
+    fn order_line_is_parsed_into_meal_specs() {
+        // When:
+        let specs = parse("2x03 groß +Käserand +Knoblauch; 1x17 klein");
+
+        // Then:
+        assert_eq!(
+            specs,
+            Ok(vec![
+                spec(2, "03", "groß", vec!["Käserand", "Knoblauch"]),
+                spec(1, "17", "klein", vec![]),
+            ])
+        );
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case("03 groß", spec(1, "03", "groß", vec![])),
+        case("2X03", spec(2, "03", "", vec![])),
+        case("3x 42 Spaghetti  Carbonara", spec(3, "42", "Spaghetti Carbonara", vec![])),
+        case("17 klein+extra  scharf+ Oliven ", spec(1, "17", "klein", vec!["extra scharf", "Oliven"])),
+        case("  1x17 klein;", spec(1, "17", "klein", vec![]))
+    )]
+    fn single_entry_is_parsed(input: &str, expected: MealSpec) {
+        // When:
+        let specs = parse(input);
+
+        // Then:
+        assert_eq!(specs, Ok(vec![expected]));
+    }
+
+    #[rstest(
+        input,
+        locale,
+        expected,
+        case("03 groß @1.234,50", Locale::DeDe, Money::new(1234, 50)),
+        case("03 groß @1,234.50", Locale::En, Money::new(1234, 50)),
+        case("03 @7,50€ groß", Locale::DeDe, Money::new(7, 50).with_currency(Currency::Eur))
+    )]
+    fn price_is_parsed_in_locale(input: &str, locale: Locale, expected: Money) {
+        // When:
+        let specs = parse_localized(input, locale);
+
+        // Then:
+        assert_eq!(
+            specs,
+            Ok(vec![spec(1, "03", "groß", vec![]).with_price(expected)])
+        );
+    }
+
+    #[rstest(input, case(""), case("   "))]
+    fn blank_input_has_no_meal_specs(input: &str) {
+        // When:
+        let specs = parse(input);
+
+        // Then:
+        assert_eq!(specs, Ok(vec![]));
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case("03 groß;; 17 klein", QuickEntryError::EmptyEntry { position: 8 }),
+        case("0x03 groß", QuickEntryError::InvalidQuantity { position: 0 }),
+        case("03; 99999999999x17", QuickEntryError::InvalidQuantity { position: 4 }),
+        case("03; +Käserand", QuickEntryError::MissingMealId { position: 4 }),
+        case("2x  +Käserand", QuickEntryError::MissingMealId { position: 4 }),
+        case("2x0-3 groß", QuickEntryError::InvalidMealId { position: 3 }),
+        case("03/4 groß", QuickEntryError::InvalidMealId { position: 2 }),
+        case("2x03 groß +Käserand + +Knoblauch", QuickEntryError::EmptySpecial { position: 20 }),
+        case("03 groß @7.50,5", QuickEntryError::InvalidPrice { position: 8 }),
+        case("03 @7,50 @8", QuickEntryError::InvalidPrice { position: 9 })
+    )]
+    fn typo_is_reported_with_position(input: &str, expected: QuickEntryError) {
+        // When:
+        let specs = parse(input);
+
+        // Then:
+        assert_eq!(specs, Err(expected));
+    }
+
+    #[test]
+    fn error_position_can_be_retrieved() {
+        // Given:
+        let error = QuickEntryError::EmptySpecial { position: 7 };
+
+        // When:
+        let position = error.get_position();
+
+        // Then:
+        assert_eq!(position, 7);
+    }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+
+/// Characters the table is drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Box drawing characters, for terminals with UTF-8
+    #[default]
+    Unicode,
+    /// Only `+`, `-` and `|`, for terminals and logs without UTF-8
+    Ascii,
+}
+
+impl TableStyle {
+    /// Left, middle, right and horizontal line of the top, the line below the header and the bottom.
+    fn rules(&self) -> [[char; 4]; 3] {
+        match self {
+            TableStyle::Unicode => [
+                ['┌', '┬', '┐', '─'],
+                ['├', '┼', '┤', '─'],
+                ['└', '┴', '┘', '─'],
+            ],
+            TableStyle::Ascii => [['+', '+', '+', '-']; 3],
+        }
+    }
+
+    fn get_vertical(&self) -> char {
+        match self {
+            TableStyle::Unicode => '│',
+            TableStyle::Ascii => '|',
+        }
+    }
+
+    fn get_ellipsis(&self) -> &str {
+        match self {
+            TableStyle::Unicode => "…",
+            TableStyle::Ascii => "...",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alignment {
+    #[default]
+    Left,
+    /// For amounts, so the decimal separators line up
+    Right,
+}
+
+/// A table with a header row, written aligned for terminals.
+///
+/// Columns are as wide as their widest cell. If the table would be wider than the maximum width, the widest
+/// columns are narrowed and cells not fitting are cut off with an ellipsis.
+/// ```
+/// # use rusty_pizza_server::render::table::{Alignment, Table, TableStyle};
+/// let mut table = Table::new(vec!["Name", "Due"])
+///     .with_style(TableStyle::Ascii)
+///     .with_alignment(1, Alignment::Right);
+/// table.add_row(vec!["Jürgen", "7,50€"]);
+/// assert_eq!(
+///     table.to_string(),
+///     "+--------+-------+\n\
+///      | Name   |   Due |\n\
+///      +--------+-------+\n\
+///      | Jürgen | 7,50€ |\n\
+///      +--------+-------+\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    alignments: Vec<Alignment>,
+    style: TableStyle,
+    /// Width of the whole table in columns of the terminal, `None` for no limit
+    max_width: Option<usize>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(header: Vec<S>) -> Table {
+        let header: Vec<String> = header.into_iter().map(Into::into).collect();
+        Table {
+            alignments: vec![Alignment::default(); header.len()],
+            header,
+            rows: Vec::new(),
+            style: TableStyle::default(),
+            max_width: None,
+        }
+    }
+
+    pub fn with_style(mut self, style: TableStyle) -> Table {
+        self.style = style;
+        self
+    }
+
+    /// Aligns the column with the given index, columns are aligned left otherwise.
+    pub fn with_alignment(mut self, column: usize, alignment: Alignment) -> Table {
+        if let Some(existing) = self.alignments.get_mut(column) {
+            *existing = alignment;
+        }
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: usize) -> Table {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Adds a row, missing cells are left empty and cells beyond the header are dropped.
+    pub fn add_row<S: Into<String>>(&mut self, row: Vec<S>) {
+        let mut row: Vec<String> = row.into_iter().map(Into::into).collect();
+        row.resize(self.header.len(), String::new());
+        self.rows.push(row);
+    }
+
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// Widths of the columns, narrowed to the maximum width of the table.
+    fn column_widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = (0..self.header.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .map(|row| display_width(&row[column]))
+                    .chain(Some(display_width(&self.header[column])))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        if let Some(max_width) = self.max_width {
+            // Every column takes a space on each side and a line on its left, the last one also on its right
+            let borders = 3 * widths.len() + 1;
+            while widths.iter().sum::<usize>() + borders > max_width {
+                match widths.iter_mut().filter(|width| **width > 1).max() {
+                    Some(widest) => *widest -= 1,
+                    None => break,
+                }
+            }
+        }
+        widths
+    }
+
+    fn write_rule(&self, f: &mut fmt::Formatter, rule: [char; 4], widths: &[usize]) -> fmt::Result {
+        let [left, middle, right, line] = rule;
+        let segments: Vec<String> = widths
+            .iter()
+            .map(|width| line.to_string().repeat(width + 2))
+            .collect();
+        writeln!(f, "{}{}{}", left, segments.join(&middle.to_string()), right)
+    }
+
+    fn write_row(&self, f: &mut fmt::Formatter, row: &[String], widths: &[usize]) -> fmt::Result {
+        let vertical = self.style.get_vertical();
+        write!(f, "{}", vertical)?;
+        for ((cell, width), alignment) in row.iter().zip(widths).zip(&self.alignments) {
+            let cell = truncate(cell, *width, self.style.get_ellipsis());
+            let padding = " ".repeat(width - display_width(&cell));
+            match alignment {
+                Alignment::Left => write!(f, " {}{} {}", cell, padding, vertical)?,
+                Alignment::Right => write!(f, " {}{} {}", padding, cell, vertical)?,
+            }
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let widths = self.column_widths();
+        let [top, separator, bottom] = self.style.rules();
+        self.write_rule(f, top, &widths)?;
+        self.write_row(f, &self.header, &widths)?;
+        self.write_rule(f, separator, &widths)?;
+        for row in &self.rows {
+            self.write_row(f, row, &widths)?;
+        }
+        self.write_rule(f, bottom, &widths)
+    }
+}
+
+/// Columns the text takes in a terminal.
+///
+/// Combining marks, like the dots of an umlaut written as "u" followed by U+0308, take no column. East Asian
+/// characters and emoji like 🍕 take two.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+fn char_width(character: char) -> usize {
+    match character as u32 {
+        // Control characters, combining marks, zero width spaces and variation selectors
+        0x00..=0x1f
+        | 0x7f..=0x9f
+        | 0x0300..=0x036f
+        | 0x1ab0..=0x1aff
+        | 0x1dc0..=0x1dff
+        | 0x200b..=0x200f
+        | 0x20d0..=0x20ff
+        | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f => 0,
+        // East Asian wide characters and emoji
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// The text cut off to the width, ending with the ellipsis if there is room for it.
+fn truncate(text: &str, width: usize, ellipsis: &str) -> String {
+    if display_width(text) <= width {
+        return String::from(text);
+    }
+    let ellipsis = if display_width(ellipsis) < width {
+        ellipsis
+    } else {
+        ""
+    };
+    let room = width - display_width(ellipsis);
+    let mut truncated = String::new();
+    let mut used = 0;
+    for character in text.chars() {
+        let character_width = char_width(character);
+        if used + character_width > room {
+            break;
+        }
+        used += character_width;
+        truncated.push(character);
+    }
+    truncated.push_str(ellipsis);
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn participants() -> Table {
+        let mut table = Table::new(vec!["Name", "Meal", "Due"]).with_alignment(2, Alignment::Right);
+        table.add_row(vec!["Jürgen", "Margherita", "7,50€"]);
+        table.add_row(vec!["Zoë", "Quattro Formaggi", "11,00€"]);
+        table
+    }
+
+    #[rstest(
+        text,
+        expected,
+        case("Jurgen", 6),
+        case("Jürgen", 6),
+        case("Ju\u{308}rgen", 6),
+        case("🍕 Pizza", 8),
+        case("ピザ", 4)
+    )]
+    fn width_counts_terminal_columns(text: &str, expected: usize) {
+        assert_eq!(display_width(text), expected);
+    }
+
+    #[test]
+    fn table_is_drawn_with_box_characters() {
+        // When:
+        let rendered = participants().to_string();
+
+        // Then:
+        assert_eq!(
+            rendered,
+            "┌────────┬──────────────────┬────────┐\n\
+             │ Name   │ Meal             │    Due │\n\
+             ├────────┼──────────────────┼────────┤\n\
+             │ Jürgen │ Margherita       │  7,50€ │\n\
+             │ Zoë    │ Quattro Formaggi │ 11,00€ │\n\
+             └────────┴──────────────────┴────────┘\n"
+        );
+    }
+
+    #[test]
+    fn widest_column_is_narrowed_to_max_width() {
+        // When:
+        let rendered = participants().with_max_width(34).to_string();
+
+        // Then:
+        assert!(rendered.lines().all(|line| display_width(line) == 34));
+        assert!(rendered.contains("│ Quattro For… │"));
+    }
+
+    #[rstest(
+        text,
+        width,
+        ellipsis,
+        expected,
+        case("Margherita", 10, "…", "Margherita"),
+        case("Margherita", 6, "…", "Margh…"),
+        case("Margherita", 6, "...", "Mar..."),
+        case("Margherita", 2, "...", "Ma"),
+        case("🍕🍕🍕", 4, "…", "🍕…")
+    )]
+    fn cell_is_truncated_with_ellipsis(text: &str, width: usize, ellipsis: &str, expected: &str) {
+        assert_eq!(truncate(text, width, ellipsis), expected);
+    }
+
+    #[test]
+    fn missing_cells_are_empty() {
+        // Given:
+        let mut table = Table::new(vec!["Name", "Due"]).with_style(TableStyle::Ascii);
+
+        // When:
+        table.add_row(vec!["Anna"]);
+
+        // Then:
+        assert_eq!(table.rows(), [vec![String::from("Anna"), String::new()]]);
+        assert!(table.to_string().contains("| Anna |     |"));
+    }
+}
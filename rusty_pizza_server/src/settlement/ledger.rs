@@ -0,0 +1,242 @@
+use crate::order_model::fee::{split_fee, FeeSplitStrategy};
+use crate::order_model::report::Balance;
+use crate::util::money::Money;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// What a participant consumed, tipped and paid, identified by a key of the caller's choice.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Participant<K> {
+    key: K,
+    consumed: Money,
+    tip: Money,
+    paid: Money,
+}
+
+impl<K> Participant<K> {
+    pub fn new(key: K, consumed: Money, tip: Money, paid: Money) -> Participant<K> {
+        Participant {
+            key,
+            consumed,
+            tip,
+            paid,
+        }
+    }
+}
+
+/// Everything needed to settle a shared expense.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettlementInput<K> {
+    participants: Vec<Participant<K>>,
+    /// Shared fee, e.g. for delivery
+    fee: Money,
+    fee_split: FeeSplitStrategy,
+    /// Who pays the fee for `FeeSplitStrategy::ManagerPays` or if nobody consumed anything
+    organizer: K,
+}
+
+impl<K> SettlementInput<K> {
+    pub fn new(organizer: K) -> SettlementInput<K> {
+        SettlementInput {
+            participants: Vec::new(),
+            fee: Money::zero(),
+            fee_split: FeeSplitStrategy::default(),
+            organizer,
+        }
+    }
+
+    pub fn add_participant(&mut self, participant: Participant<K>) {
+        self.participants.push(participant);
+    }
+
+    pub fn set_fee(&mut self, fee: Money, fee_split: FeeSplitStrategy) {
+        self.fee = fee;
+        self.fee_split = fee_split;
+    }
+}
+
+/// What a single participant has to pay and gets back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettlementEntry<K> {
+    key: K,
+    consumed: Money,
+    fee_share: Money,
+    tip: Money,
+    paid: Money,
+    balance: Balance,
+}
+
+impl<K> SettlementEntry<K> {
+    pub fn get_key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get_fee_share(&self) -> Money {
+        self.fee_share
+    }
+
+    /// Consumption, fee share and tip together.
+    pub fn get_due(&self) -> Money {
+        self.consumed + self.fee_share + self.tip
+    }
+
+    pub fn get_paid(&self) -> Money {
+        self.paid
+    }
+
+    pub fn get_balance(&self) -> Balance {
+        self.balance
+    }
+}
+
+/// Result of `settle`, entries sorted by key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settlement<K> {
+    entries: Vec<SettlementEntry<K>>,
+}
+
+impl<K: Eq> Settlement<K> {
+    pub fn entries(&self) -> &[SettlementEntry<K>] {
+        &self.entries
+    }
+
+    pub fn get_entry(&self, key: &K) -> Option<&SettlementEntry<K>> {
+        self.entries.iter().find(|entry| &entry.key == key)
+    }
+
+    /// Change to hand out to those who paid too much.
+    pub fn get_total_change(&self) -> Money {
+        self.sum(|balance| match balance {
+            Balance::Change(change) => change,
+            Balance::Owed(_) => Money::zero(),
+        })
+    }
+
+    /// Money still to collect from those who paid too little.
+    pub fn get_total_owed(&self) -> Money {
+        self.sum(|balance| match balance {
+            Balance::Change(_) => Money::zero(),
+            Balance::Owed(owed) => owed,
+        })
+    }
+
+    /// Whether everybody paid at least what they owe.
+    pub fn is_settled(&self) -> bool {
+        self.get_total_owed().is_zero()
+    }
+
+    fn sum(&self, value: impl Fn(Balance) -> Money) -> Money {
+        self.entries
+            .iter()
+            .fold(Money::zero(), |sum, entry| sum + value(entry.balance))
+    }
+}
+
+/// Splits the fee and calculates the balance of every participant. Pure, the input is left untouched.
+///
+/// Works on plain amounts so other tools can settle shared expenses without an `Order`, using the same fee
+/// splitting and balances as `Order::payment_report`.
+///
+/// Participants with the same key are settled separately, keys should be unique.
+pub fn settle<K: Clone + Eq + Hash + Ord>(input: &SettlementInput<K>) -> Settlement<K> {
+    let consumed: HashMap<K, Money> = input
+        .participants
+        .iter()
+        .map(|participant| (participant.key.clone(), participant.consumed))
+        .collect();
+    let fee_shares = split_fee(input.fee, input.fee_split, &input.organizer, &consumed);
+    let mut entries: Vec<SettlementEntry<K>> = input
+        .participants
+        .iter()
+        .map(|participant| {
+            let fee_share = fee_shares
+                .get(&participant.key)
+                .copied()
+                .unwrap_or(Money::zero());
+            SettlementEntry {
+                key: participant.key.clone(),
+                consumed: participant.consumed,
+                fee_share,
+                tip: participant.tip,
+                paid: participant.paid,
+                balance: Balance::new(
+                    participant.consumed + fee_share + participant.tip,
+                    participant.paid,
+                ),
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Settlement { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat_expenses() -> SettlementInput<&'static str> {
+        let mut input = SettlementInput::new("Anna");
+        input.add_participant(Participant::new(
+            "Ben",
+            Money::new(30, 0),
+            Money::zero(),
+            Money::new(40, 0),
+        ));
+        input.add_participant(Participant::new(
+            "Anna",
+            Money::new(10, 0),
+            Money::new(1, 0),
+            Money::zero(),
+        ));
+        input.set_fee(Money::new(4, 0), FeeSplitStrategy::Proportional);
+        input
+    }
+
+    #[test]
+    fn expenses_are_settled_without_order() {
+        // When:
+        let settlement = settle(&flat_expenses());
+
+        // Then:
+        let keys: Vec<&str> = settlement.entries().iter().map(|e| *e.get_key()).collect();
+        assert_eq!(keys, vec!["Anna", "Ben"]);
+        let anna = settlement.get_entry(&"Anna").unwrap();
+        assert_eq!(anna.get_fee_share(), Money::new(1, 0));
+        assert_eq!(anna.get_due(), Money::new(12, 0));
+        assert_eq!(anna.get_balance(), Balance::Owed(Money::new(12, 0)));
+        let ben = settlement.get_entry(&"Ben").unwrap();
+        assert_eq!(ben.get_balance(), Balance::Change(Money::new(7, 0)));
+        assert_eq!(settlement.get_total_change(), Money::new(7, 0));
+        assert_eq!(settlement.get_total_owed(), Money::new(12, 0));
+        assert!(!settlement.is_settled());
+    }
+
+    #[test]
+    fn organizer_pays_fee_if_nobody_consumed() {
+        // Given:
+        let mut input = SettlementInput::new(1u32);
+        input.add_participant(Participant::new(
+            2,
+            Money::zero(),
+            Money::zero(),
+            Money::zero(),
+        ));
+        input.add_participant(Participant::new(
+            1,
+            Money::zero(),
+            Money::zero(),
+            Money::new(2, 0),
+        ));
+        input.set_fee(Money::new(2, 0), FeeSplitStrategy::Equal);
+
+        // When:
+        let settlement = settle(&input);
+
+        // Then:
+        assert_eq!(
+            settlement.get_entry(&1).unwrap().get_fee_share(),
+            Money::new(2, 0)
+        );
+        assert!(settlement.is_settled());
+    }
+}
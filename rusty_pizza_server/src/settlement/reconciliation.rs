@@ -0,0 +1,247 @@
+use crate::import::bank_statement::Transfer;
+use crate::order_model::manager::OrderManager;
+use crate::order_model::order::Order;
+use crate::order_model::report::Balance;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::short_code::{parse_short_code, ShortCodePrefix};
+
+/// A transfer which settled the debt of a participant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchedTransfer {
+    transfer: Transfer,
+    order_id: Id<Order>,
+    user_id: Id<User>,
+}
+
+impl MatchedTransfer {
+    pub fn get_transfer(&self) -> &Transfer {
+        &self.transfer
+    }
+
+    pub fn get_order_id(&self) -> Id<Order> {
+        self.order_id.clone()
+    }
+
+    pub fn get_user_id(&self) -> Id<User> {
+        self.user_id.clone()
+    }
+}
+
+/// Why a transfer has to be booked by hand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnmatchedReason {
+    /// The reference contains no valid order code
+    NoOrderCode,
+    UnknownOrder(Id<Order>),
+    /// The order can't take payments anymore, e.g. because it is closed
+    OrderNotPayable(Id<Order>),
+    /// Nobody in the order owes exactly the amount
+    NoMatchingDebt(Id<Order>),
+    /// Several participants owe exactly the amount and the reference names none of them
+    Ambiguous(Id<Order>, Vec<Id<User>>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnmatchedTransfer {
+    transfer: Transfer,
+    reason: UnmatchedReason,
+}
+
+impl UnmatchedTransfer {
+    pub fn get_transfer(&self) -> &Transfer {
+        &self.transfer
+    }
+
+    pub fn get_reason(&self) -> &UnmatchedReason {
+        &self.reason
+    }
+}
+
+/// Outcome of `reconcile`, both lists in the order of the statement.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReconciliationReport {
+    matched: Vec<MatchedTransfer>,
+    unmatched: Vec<UnmatchedTransfer>,
+}
+
+impl ReconciliationReport {
+    pub fn matched(&self) -> &[MatchedTransfer] {
+        &self.matched
+    }
+
+    pub fn unmatched(&self) -> &[UnmatchedTransfer] {
+        &self.unmatched
+    }
+}
+
+/// Marks debts as paid which incoming transfers settle.
+///
+/// A transfer settles a debt if its reference contains the short code of an active order and exactly one
+/// participant of it owes the transferred amount. If the reference also contains a user code, only that
+/// participant is considered. The transfer is booked as an installment of the participant. Transfers settle
+/// debts one after the other, so a participant who transferred twice only gets the first transfer booked.
+pub fn reconcile(transfers: Vec<Transfer>, orders: &mut OrderManager) -> ReconciliationReport {
+    let mut report = ReconciliationReport::default();
+    for transfer in transfers {
+        match settle_debt(&transfer, orders) {
+            Ok((order_id, user_id)) => report.matched.push(MatchedTransfer {
+                transfer,
+                order_id,
+                user_id,
+            }),
+            Err(reason) => report
+                .unmatched
+                .push(UnmatchedTransfer { transfer, reason }),
+        }
+    }
+    report
+}
+
+fn settle_debt(
+    transfer: &Transfer,
+    orders: &mut OrderManager,
+) -> Result<(Id<Order>, Id<User>), UnmatchedReason> {
+    let order_id: Id<Order> =
+        find_code(transfer.get_reference()).ok_or(UnmatchedReason::NoOrderCode)?;
+    let user_code: Option<Id<User>> = find_code(transfer.get_reference());
+    let order = orders
+        .get_order_mut(&order_id)
+        .ok_or_else(|| UnmatchedReason::UnknownOrder(order_id.clone()))?;
+    let candidates: Vec<_> = order
+        .payment_report()
+        .users()
+        .iter()
+        .filter(|user| {
+            user_code
+                .as_ref()
+                .is_none_or(|code| &user.get_user_id() == code)
+        })
+        .filter(|user| user.get_balance() == Balance::Owed(transfer.get_amount()))
+        .map(|user| user.get_user_id())
+        .collect();
+    match candidates.as_slice() {
+        [] => Err(UnmatchedReason::NoMatchingDebt(order_id)),
+        [user_id] => {
+            order
+                .add_payment_for_user(user_id.clone(), transfer.get_amount())
+                .map_err(|_| UnmatchedReason::OrderNotPayable(order_id.clone()))?;
+            Ok((order_id, user_id.clone()))
+        }
+        _ => Err(UnmatchedReason::Ambiguous(order_id, candidates)),
+    }
+}
+
+/// First valid code of the given kind in the reference, which may surround it with arbitrary text.
+fn find_code<T: ShortCodePrefix>(reference: &str) -> Option<Id<T>> {
+    reference
+        .split(|character: char| !character.is_ascii_alphanumeric() && character != '-')
+        .find_map(|word| parse_short_code(word).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::money::Money;
+    use crate::util::short_code::short_code;
+    use chrono::NaiveDate;
+
+    fn transfer(amount: Money, reference: String) -> Transfer {
+        Transfer::new(
+            NaiveDate::from_ymd_opt(2020, 5, 4).unwrap(),
+            amount,
+            reference,
+        )
+    }
+
+    /// Order managed by user 0 in which users 1 and 2 each ordered a meal for the given prices.
+    fn order_with_debts(orders: &mut OrderManager, first: Money, second: Money) -> Id<Order> {
+        let id = orders.create_order(Id::new(0));
+        let order = orders.get_order_mut(&id).unwrap();
+        for (user_id, price) in [(1, first), (2, second)] {
+            order.add_user(Id::new(user_id)).unwrap();
+            order
+                .add_meal_for_user(
+                    Id::new(user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    price,
+                )
+                .unwrap();
+        }
+        id
+    }
+
+    #[test]
+    fn transfer_settles_debt_with_same_amount() {
+        // Given:
+        let mut orders = OrderManager::new();
+        let order_id = order_with_debts(&mut orders, Money::new(7, 50), Money::new(6, 0));
+        let reference = format!("Pizza {}, thanks!", short_code(&order_id));
+
+        // When:
+        let report = reconcile(vec![transfer(Money::new(6, 0), reference)], &mut orders);
+
+        // Then:
+        assert_eq!(report.matched().len(), 1);
+        assert_eq!(report.matched()[0].get_user_id(), Id::new(2));
+        let payment_report = orders.get_order(&order_id).unwrap().payment_report();
+        assert_eq!(
+            payment_report.get_user(&Id::new(2)).unwrap().get_balance(),
+            Balance::Change(Money::zero())
+        );
+    }
+
+    #[test]
+    fn equal_debts_need_user_code() {
+        // Given:
+        let mut orders = OrderManager::new();
+        let order_id = order_with_debts(&mut orders, Money::new(6, 0), Money::new(6, 0));
+        let ambiguous = transfer(Money::new(6, 0), short_code(&order_id));
+        let named = transfer(
+            Money::new(6, 0),
+            format!(
+                "{} {}",
+                short_code(&order_id),
+                short_code::<User>(&Id::new(1))
+            ),
+        );
+
+        // When:
+        let report = reconcile(vec![ambiguous, named], &mut orders);
+
+        // Then:
+        assert_eq!(
+            report.unmatched()[0].get_reason(),
+            &UnmatchedReason::Ambiguous(order_id.clone(), vec![Id::new(1), Id::new(2)])
+        );
+        assert_eq!(report.matched()[0].get_user_id(), Id::new(1));
+    }
+
+    #[test]
+    fn transfers_without_matching_debt_are_left_for_review() {
+        // Given:
+        let mut orders = OrderManager::new();
+        let order_id = order_with_debts(&mut orders, Money::new(7, 50), Money::new(6, 0));
+        let transfers = vec![
+            transfer(Money::new(7, 50), String::from("Pizza")),
+            transfer(Money::new(7, 50), short_code::<Order>(&Id::new(99))),
+            transfer(Money::new(5, 0), short_code(&order_id)),
+        ];
+
+        // When:
+        let report = reconcile(transfers, &mut orders);
+
+        // Then:
+        let reasons: Vec<_> = report.unmatched().iter().map(|u| u.get_reason()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                &UnmatchedReason::NoOrderCode,
+                &UnmatchedReason::UnknownOrder(Id::new(99)),
+                &UnmatchedReason::NoMatchingDebt(order_id)
+            ]
+        );
+        assert!(report.matched().is_empty());
+    }
+}
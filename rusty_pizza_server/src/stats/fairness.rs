@@ -0,0 +1,217 @@
+use crate::order_model::order::{Order, OrderStatus};
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::collections::BTreeMap;
+
+/// How far from their fair share of duties a user is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Imbalance {
+    /// More than one duty above what their participation calls for
+    TooOften,
+    /// More than one duty below what their participation calls for
+    TooRarely,
+}
+
+/// Duties a user took on compared to how often they took part.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserFairness {
+    user_id: Id<User>,
+    participations: u32,
+    /// Orders the user managed, i.e. called the restaurant and picked up the food
+    organized: u32,
+    /// Orders in which the user paid the whole fee for others
+    fees_covered: u32,
+    imbalance: Option<Imbalance>,
+}
+
+impl UserFairness {
+    pub fn get_user_id(&self) -> Id<User> {
+        self.user_id.clone()
+    }
+
+    pub fn get_participations(&self) -> u32 {
+        self.participations
+    }
+
+    pub fn get_organized(&self) -> u32 {
+        self.organized
+    }
+
+    pub fn get_fees_covered(&self) -> u32 {
+        self.fees_covered
+    }
+
+    /// Organized orders and covered fees together.
+    pub fn get_duties(&self) -> u32 {
+        self.organized + self.fees_covered
+    }
+
+    pub fn get_imbalance(&self) -> Option<Imbalance> {
+        self.imbalance
+    }
+}
+
+/// Whether organizing orders and covering fees is shared fairly, e.g. for a manager rotating the duties.
+///
+/// Every user should take on duties in proportion to how often they take part. Users off by more than one duty
+/// are flagged, so the organizer knows whom to ask next. Cancelled orders are left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FairnessReport {
+    /// Sorted by user ID
+    users: Vec<UserFairness>,
+}
+
+impl FairnessReport {
+    pub fn calculate<'a>(orders: impl IntoIterator<Item = &'a Order>) -> FairnessReport {
+        let mut users: BTreeMap<Id<User>, UserFairness> = BTreeMap::new();
+        for order in orders {
+            if order.get_status() == &OrderStatus::Cancelled {
+                continue;
+            }
+            for user_id in order.participants() {
+                entry(&mut users, user_id).participations += 1;
+            }
+            entry(&mut users, &order.get_manager_id()).organized += 1;
+            let report = order.payment_report();
+            let fee = report.get_total_fee();
+            if fee > Money::zero() && report.users().len() > 1 {
+                if let Some(payer) = report
+                    .users()
+                    .iter()
+                    .find(|user| user.get_fee_share() == fee)
+                {
+                    entry(&mut users, &payer.get_user_id()).fees_covered += 1;
+                }
+            }
+        }
+        let participations: u64 = users
+            .values()
+            .map(|user| u64::from(user.participations))
+            .sum();
+        let duties: u64 = users
+            .values()
+            .map(|user| u64::from(user.get_duties()))
+            .sum();
+        for user in users.values_mut() {
+            // Compares the duties with the fair share `participations * duties / all participations` in integers
+            let fair = u64::from(user.participations) * duties;
+            let actual = u64::from(user.get_duties());
+            user.imbalance = if actual.saturating_sub(1) * participations > fair {
+                Some(Imbalance::TooOften)
+            } else if (actual + 1) * participations < fair {
+                Some(Imbalance::TooRarely)
+            } else {
+                None
+            };
+        }
+        FairnessReport {
+            users: users.into_values().collect(),
+        }
+    }
+
+    pub fn users(&self) -> &[UserFairness] {
+        &self.users
+    }
+
+    /// Users whose duties are off, in the order of their IDs.
+    pub fn imbalanced(&self) -> impl Iterator<Item = &UserFairness> {
+        self.users.iter().filter(|user| user.imbalance.is_some())
+    }
+}
+
+fn entry<'a>(
+    users: &'a mut BTreeMap<Id<User>, UserFairness>,
+    user_id: &Id<User>,
+) -> &'a mut UserFairness {
+    users
+        .entry(user_id.clone())
+        .or_insert_with(|| UserFairness {
+            user_id: user_id.clone(),
+            participations: 0,
+            organized: 0,
+            fees_covered: 0,
+            imbalance: None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_model::fee::FeeSplitStrategy;
+
+    /// Order managed by the manager, each participant ordering a meal, with the fee split as given.
+    fn order(manager_id: u32, participants: &[u32], fee_split: FeeSplitStrategy) -> Order {
+        let mut order = Order::new(Id::new(manager_id));
+        for user_id in participants {
+            if !order.is_participating(&Id::new(*user_id)) {
+                order.add_user(Id::new(*user_id)).unwrap();
+            }
+            order
+                .add_meal_for_user(
+                    Id::new(*user_id),
+                    String::from("03"),
+                    String::from("groß"),
+                    Money::new(7, 50),
+                )
+                .unwrap();
+        }
+        order.set_delivery_fee(Money::new(3, 0), fee_split).unwrap();
+        order
+    }
+
+    #[test]
+    fn user_organizing_every_order_is_flagged() {
+        // Given:
+        let orders: Vec<Order> = (0..4)
+            .map(|_| order(0, &[0, 1, 2], FeeSplitStrategy::ManagerPays))
+            .collect();
+
+        // When:
+        let report = FairnessReport::calculate(&orders);
+
+        // Then:
+        let anna = &report.users()[0];
+        assert_eq!(anna.get_participations(), 4);
+        assert_eq!(anna.get_organized(), 4);
+        assert_eq!(anna.get_fees_covered(), 4);
+        assert_eq!(anna.get_imbalance(), Some(Imbalance::TooOften));
+        assert_eq!(
+            report.users()[1].get_imbalance(),
+            Some(Imbalance::TooRarely)
+        );
+        assert_eq!(report.imbalanced().count(), 3);
+    }
+
+    #[test]
+    fn rotating_duties_are_fair() {
+        // Given:
+        let orders: Vec<Order> = (0..3)
+            .map(|manager_id| order(manager_id, &[0, 1, 2], FeeSplitStrategy::Equal))
+            .collect();
+
+        // When:
+        let report = FairnessReport::calculate(&orders);
+
+        // Then:
+        assert!(report.users().iter().all(|user| user.get_organized() == 1));
+        assert!(report
+            .users()
+            .iter()
+            .all(|user| user.get_fees_covered() == 0));
+        assert_eq!(report.imbalanced().count(), 0);
+    }
+
+    #[test]
+    fn cancelled_orders_are_left_out() {
+        // Given:
+        let mut cancelled = order(1, &[1, 2], FeeSplitStrategy::ManagerPays);
+        cancelled.cancel().unwrap();
+
+        // When:
+        let report = FairnessReport::calculate(&[cancelled]);
+
+        // Then:
+        assert!(report.users().is_empty());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod fairness;
+pub mod money;
+pub mod orders;
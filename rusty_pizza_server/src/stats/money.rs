@@ -0,0 +1,411 @@
+use crate::order_model::order::Order;
+use crate::util::money::Money;
+use chrono::{DateTime, Datelike, Utc};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+/// A calendar month, ordered chronologically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct YearMonth {
+    year: i32,
+    /// 1 to 12
+    month: u32,
+}
+
+impl YearMonth {
+    pub fn new(year: i32, month: u32) -> YearMonth {
+        YearMonth { year, month }
+    }
+
+    pub fn get_year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn get_month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn previous(&self) -> YearMonth {
+        match self.month {
+            1 => YearMonth::new(self.year - 1, 12),
+            month => YearMonth::new(self.year, month - 1),
+        }
+    }
+}
+
+impl From<SystemTime> for YearMonth {
+    /// Month of the given time in UTC.
+    fn from(time: SystemTime) -> YearMonth {
+        let time = DateTime::<Utc>::from(time);
+        YearMonth::new(time.year(), time.month())
+    }
+}
+
+/// Percentage with two decimal places.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Percentage {
+    /// Hundredths of a percent
+    basis_points: u32,
+}
+
+impl Percentage {
+    pub fn from_basis_points(basis_points: u32) -> Percentage {
+        Percentage { basis_points }
+    }
+
+    /// `part` as percentage of `whole`, rounded half up. `None` if `whole` is zero.
+    pub fn of(part: Money, whole: Money) -> Option<Percentage> {
+        divide_rounded(
+            u64::from(part.get_total_cents()) * 10_000,
+            u64::from(whole.get_total_cents()),
+        )
+        .map(|basis_points| Percentage::from_basis_points(basis_points as u32))
+    }
+
+    pub fn get_basis_points(&self) -> u32 {
+        self.basis_points
+    }
+}
+
+impl fmt::Display for Percentage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{:02}%",
+            self.basis_points / 100,
+            self.basis_points % 100
+        )
+    }
+}
+
+/// Change of a monthly total compared to the previous month.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trend {
+    Up(Money),
+    Down(Money),
+    Unchanged,
+}
+
+impl Trend {
+    pub fn between(previous: Money, current: Money) -> Trend {
+        if current > previous {
+            Trend::Up(current - previous)
+        } else if current < previous {
+            Trend::Down(previous - current)
+        } else {
+            Trend::Unchanged
+        }
+    }
+}
+
+/// The money-related numbers of a single order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderMoney {
+    month: YearMonth,
+    /// Number of participants who ordered at least one meal
+    heads: u32,
+    /// Price of all meals, including office meals
+    total_price: Money,
+    total_tip: Money,
+}
+
+impl OrderMoney {
+    pub fn new(month: YearMonth, heads: u32, total_price: Money, total_tip: Money) -> OrderMoney {
+        OrderMoney {
+            month,
+            heads,
+            total_price,
+            total_tip,
+        }
+    }
+
+    pub fn from_order(order: &Order, month: YearMonth) -> OrderMoney {
+        let report = order.payment_report();
+        let heads = report
+            .users()
+            .iter()
+            .filter(|user| user.get_meal_price() > Money::zero())
+            .count() as u32;
+        OrderMoney::new(
+            month,
+            heads,
+            report.get_total_price(),
+            report.get_total_tip(),
+        )
+    }
+
+    /// What was spent on the order including tip.
+    pub fn get_spend(&self) -> Money {
+        self.total_price + self.total_tip
+    }
+}
+
+/// Total spend of all orders of a month.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonthlyMoney {
+    month: YearMonth,
+    orders: u32,
+    spend: Money,
+    /// Compared to the calendar month before, `None` for the first month
+    trend: Option<Trend>,
+}
+
+impl MonthlyMoney {
+    pub fn get_month(&self) -> YearMonth {
+        self.month
+    }
+
+    pub fn get_orders(&self) -> u32 {
+        self.orders
+    }
+
+    pub fn get_spend(&self) -> Money {
+        self.spend
+    }
+
+    pub fn get_trend(&self) -> Option<Trend> {
+        self.trend
+    }
+}
+
+/// Money aggregates over many orders, as shown on the dashboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoneyStats {
+    /// Highest spend of a single order
+    largest_order: Option<Money>,
+    /// Spend of all orders divided by everybody who ordered a meal in them
+    average_per_head: Option<Money>,
+    /// Tips as percentage of the meal prices
+    tip_ratio: Option<Percentage>,
+    /// Sorted chronologically
+    months: Vec<MonthlyMoney>,
+}
+
+impl MoneyStats {
+    pub fn calculate(orders: &[OrderMoney]) -> MoneyStats {
+        let mut total_price = Money::zero();
+        let mut total_tip = Money::zero();
+        let mut heads: u64 = 0;
+        let mut spend_by_month: BTreeMap<YearMonth, (u32, Money)> = BTreeMap::new();
+        for order in orders {
+            total_price += order.total_price;
+            total_tip += order.total_tip;
+            heads += u64::from(order.heads);
+            let month = spend_by_month
+                .entry(order.month)
+                .or_insert((0, Money::zero()));
+            month.0 += 1;
+            month.1 += order.get_spend();
+        }
+        let months = spend_by_month
+            .iter()
+            .enumerate()
+            .map(|(index, (month, (count, spend)))| MonthlyMoney {
+                month: *month,
+                orders: *count,
+                spend: *spend,
+                trend: if index == 0 {
+                    None
+                } else {
+                    let previous = spend_by_month
+                        .get(&month.previous())
+                        .map_or(Money::zero(), |(_, spend)| *spend);
+                    Some(Trend::between(previous, *spend))
+                },
+            })
+            .collect();
+        MoneyStats {
+            largest_order: orders.iter().map(OrderMoney::get_spend).max(),
+            average_per_head: divide_rounded(
+                u64::from((total_price + total_tip).get_total_cents()),
+                heads,
+            )
+            .map(|cents| Money::from_cents(cents as u32)),
+            tip_ratio: Percentage::of(total_tip, total_price),
+            months,
+        }
+    }
+
+    pub fn get_largest_order(&self) -> Option<Money> {
+        self.largest_order
+    }
+
+    pub fn get_average_per_head(&self) -> Option<Money> {
+        self.average_per_head
+    }
+
+    pub fn get_tip_ratio(&self) -> Option<Percentage> {
+        self.tip_ratio
+    }
+
+    pub fn months(&self) -> &[MonthlyMoney] {
+        &self.months
+    }
+}
+
+/// Divides rounding half up, `None` when dividing by zero.
+pub fn divide_rounded(dividend: u64, divisor: u64) -> Option<u64> {
+    if divisor == 0 {
+        None
+    } else {
+        Some((dividend * 2 + divisor) / (divisor * 2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::id::Id;
+    use rstest::rstest;
+    use std::time::Duration;
+
+    #[rstest(
+        month,
+        expected,
+        case(YearMonth::new(2020, 3), YearMonth::new(2020, 2)),
+        case(YearMonth::new(2020, 1), YearMonth::new(2019, 12))
+    )]
+    fn previous_month_is_calculated(month: YearMonth, expected: YearMonth) {
+        // Then:
+        assert_eq!(month.previous(), expected);
+    }
+
+    #[test]
+    fn month_of_time_is_in_utc() {
+        // Given:
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_580_515_199);
+
+        // Then:
+        assert_eq!(YearMonth::from(time), YearMonth::new(2020, 1));
+    }
+
+    #[rstest(
+        part,
+        whole,
+        expected,
+        case(Money::new(1, 0), Money::new(8, 0), Some("12,50%")),
+        case(Money::new(1, 0), Money::new(3, 0), Some("33,33%")),
+        case(Money::new(0, 5), Money::new(1, 0), Some("5,00%")),
+        case(Money::new(1, 0), Money::zero(), None)
+    )]
+    fn percentage_is_calculated(part: Money, whole: Money, expected: Option<&str>) {
+        // When:
+        let percentage = Percentage::of(part, whole);
+
+        // Then:
+        assert_eq!(
+            percentage.map(|percentage| percentage.to_string()),
+            expected.map(String::from)
+        );
+    }
+
+    #[test]
+    fn money_of_order_counts_heads_with_meals() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(5, 50),
+            )
+            .unwrap();
+        order
+            .set_tip_for_user(Id::new(1), Money::new(0, 50))
+            .unwrap();
+
+        // When:
+        let money = OrderMoney::from_order(&order, YearMonth::new(2020, 1));
+
+        // Then:
+        assert_eq!(
+            money,
+            OrderMoney::new(
+                YearMonth::new(2020, 1),
+                1,
+                Money::new(5, 50),
+                Money::new(0, 50)
+            )
+        );
+    }
+
+    #[test]
+    fn stats_are_aggregated_over_orders() {
+        // Given:
+        let orders = vec![
+            OrderMoney::new(
+                YearMonth::new(2020, 1),
+                2,
+                Money::new(16, 0),
+                Money::new(2, 0),
+            ),
+            OrderMoney::new(
+                YearMonth::new(2020, 2),
+                1,
+                Money::new(8, 0),
+                Money::new(1, 0),
+            ),
+            OrderMoney::new(
+                YearMonth::new(2020, 2),
+                3,
+                Money::new(24, 0),
+                Money::new(0, 0),
+            ),
+            OrderMoney::new(
+                YearMonth::new(2020, 4),
+                1,
+                Money::new(6, 0),
+                Money::new(0, 0),
+            ),
+        ];
+
+        // When:
+        let stats = MoneyStats::calculate(&orders);
+
+        // Then:
+        assert_eq!(stats.get_largest_order(), Some(Money::new(24, 0)));
+        assert_eq!(stats.get_average_per_head(), Some(Money::new(8, 14)));
+        assert_eq!(
+            stats.get_tip_ratio(),
+            Some(Percentage::from_basis_points(556))
+        );
+        assert_eq!(
+            stats.months(),
+            &[
+                MonthlyMoney {
+                    month: YearMonth::new(2020, 1),
+                    orders: 1,
+                    spend: Money::new(18, 0),
+                    trend: None,
+                },
+                MonthlyMoney {
+                    month: YearMonth::new(2020, 2),
+                    orders: 2,
+                    spend: Money::new(33, 0),
+                    trend: Some(Trend::Up(Money::new(15, 0))),
+                },
+                MonthlyMoney {
+                    month: YearMonth::new(2020, 4),
+                    orders: 1,
+                    spend: Money::new(6, 0),
+                    trend: Some(Trend::Up(Money::new(6, 0))),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_orders_have_no_stats() {
+        // When:
+        let stats = MoneyStats::calculate(&[]);
+
+        // Then:
+        assert_eq!(stats.get_largest_order(), None);
+        assert_eq!(stats.get_average_per_head(), None);
+        assert_eq!(stats.get_tip_ratio(), None);
+        assert!(stats.months().is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::order_model::order::{Order, OrderStatus};
+use crate::order_model::user::User;
+use crate::stats::money::{divide_rounded, YearMonth};
+use crate::util::id::Id;
+use crate::util::money::Money;
+use chrono::{DateTime, Datelike, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+/// How often a meal was ordered over all orders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MealCount {
+    meal_id: String,
+    count: u32,
+}
+
+impl MealCount {
+    pub fn new(meal_id: String, count: u32) -> MealCount {
+        MealCount { meal_id, count }
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Aggregates over many orders, e.g. for the end-of-year pizza report. Cancelled orders are left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderStatistics {
+    orders: u32,
+    /// Most ordered first, ties by meal ID
+    meals: Vec<MealCount>,
+    /// What every user who ordered a meal spent on average over all orders, including fees and tips
+    average_spend_per_user: Option<Money>,
+    /// Sorted chronologically, months without orders are missing
+    tips_by_month: BTreeMap<YearMonth, Money>,
+    /// Monday first, in UTC like the months
+    orders_per_weekday: [u32; 7],
+}
+
+impl OrderStatistics {
+    pub fn calculate<'a>(orders: impl IntoIterator<Item = &'a Order>) -> OrderStatistics {
+        let mut count = 0;
+        let mut meals: HashMap<&String, u32> = HashMap::new();
+        let mut spend_by_user: HashMap<Id<User>, Money> = HashMap::new();
+        let mut tips_by_month = BTreeMap::new();
+        let mut orders_per_weekday = [0; 7];
+        for order in orders {
+            if order.get_status() == &OrderStatus::Cancelled {
+                continue;
+            }
+            count += 1;
+            for meal in order.all_meals() {
+                *meals.entry(meal.get_meal_id()).or_insert(0) += 1;
+            }
+            let report = order.payment_report();
+            for user in report.users() {
+                if user.get_meal_price() > Money::zero() {
+                    *spend_by_user
+                        .entry(user.get_user_id())
+                        .or_insert_with(Money::zero) += user.get_due();
+                }
+            }
+            let created_at = order.get_created_at();
+            *tips_by_month
+                .entry(YearMonth::from(created_at))
+                .or_insert_with(Money::zero) += report.get_total_tip();
+            let weekday = DateTime::<Utc>::from(created_at).weekday();
+            orders_per_weekday[weekday.num_days_from_monday() as usize] += 1;
+        }
+        let mut meals: Vec<MealCount> = meals
+            .into_iter()
+            .map(|(meal_id, count)| MealCount::new(meal_id.clone(), count))
+            .collect();
+        meals.sort_by(|a, b| b.count.cmp(&a.count).then(a.meal_id.cmp(&b.meal_id)));
+        let total_spend: u64 = spend_by_user
+            .values()
+            .map(|spend| u64::from(spend.get_total_cents()))
+            .sum();
+        OrderStatistics {
+            orders: count,
+            meals,
+            average_spend_per_user: divide_rounded(total_spend, spend_by_user.len() as u64)
+                .map(|cents| Money::from_cents(cents as u32)),
+            tips_by_month,
+            orders_per_weekday,
+        }
+    }
+
+    pub fn get_orders(&self) -> u32 {
+        self.orders
+    }
+
+    pub fn get_most_ordered_meal(&self) -> Option<&MealCount> {
+        self.meals.first()
+    }
+
+    /// Every meal ever ordered, most ordered first.
+    pub fn meals(&self) -> &[MealCount] {
+        &self.meals
+    }
+
+    pub fn get_average_spend_per_user(&self) -> Option<Money> {
+        self.average_spend_per_user
+    }
+
+    pub fn tips_by_month(&self) -> &BTreeMap<YearMonth, Money> {
+        &self.tips_by_month
+    }
+
+    /// Number of orders created on each weekday, Monday first.
+    pub fn get_orders_per_weekday(&self) -> [u32; 7] {
+        self.orders_per_weekday
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::clock::TestClock;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    /// Order created at the given seconds since the epoch with a meal and tip for each of the given users.
+    fn order(seconds: u64, meals: &[(u32, &str, Money, Money)]) -> Order {
+        let clock = TestClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        let mut order = Order::with_audit_clock(Id::new(0), Arc::new(clock));
+        for (user_id, meal_id, price, tip) in meals {
+            if !order.is_participating(&Id::new(*user_id)) {
+                order.add_user(Id::new(*user_id)).unwrap();
+            }
+            order
+                .add_meal_for_user(
+                    Id::new(*user_id),
+                    String::from(*meal_id),
+                    String::from("groß"),
+                    *price,
+                )
+                .unwrap();
+            order.set_tip_for_user(Id::new(*user_id), *tip).unwrap();
+        }
+        order
+    }
+
+    #[test]
+    fn statistics_are_calculated_over_orders() {
+        // Given:
+        // Friday, 2020-01-31
+        let january = order(
+            1_580_472_000,
+            &[
+                (0, "03", Money::new(6, 0), Money::new(0, 50)),
+                (1, "17", Money::new(4, 0), Money::zero()),
+            ],
+        );
+        // Friday, 2020-02-07
+        let february = order(
+            1_581_076_800,
+            &[(1, "03", Money::new(6, 0), Money::new(1, 0))],
+        );
+        let mut cancelled = order(1_581_076_800, &[(2, "17", Money::new(4, 0), Money::zero())]);
+        cancelled.cancel().unwrap();
+
+        // When:
+        let statistics = OrderStatistics::calculate(vec![&january, &february, &cancelled]);
+
+        // Then:
+        assert_eq!(statistics.get_orders(), 2);
+        assert_eq!(
+            statistics.meals(),
+            &[
+                MealCount::new(String::from("03"), 2),
+                MealCount::new(String::from("17"), 1)
+            ]
+        );
+        // (6,50€ + 11,00€) / 2
+        assert_eq!(
+            statistics.get_average_spend_per_user(),
+            Some(Money::new(8, 75))
+        );
+        assert_eq!(
+            statistics.tips_by_month().iter().collect::<Vec<_>>(),
+            vec![
+                (&YearMonth::new(2020, 1), &Money::new(0, 50)),
+                (&YearMonth::new(2020, 2), &Money::new(1, 0))
+            ]
+        );
+        assert_eq!(statistics.get_orders_per_weekday(), [0, 0, 0, 0, 2, 0, 0]);
+    }
+
+    #[test]
+    fn statistics_without_orders_are_empty() {
+        // When:
+        let statistics = OrderStatistics::calculate(Vec::new());
+
+        // Then:
+        assert_eq!(statistics.get_most_ordered_meal(), None);
+        assert_eq!(statistics.get_average_spend_per_user(), None);
+        assert!(statistics.tips_by_month().is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::order_model::meal::Meal;
+use crate::order_model::order::Order;
+use crate::order_model::preparation::Preparation;
+use crate::order_model::special::Special;
+use crate::order_model::user::User;
+use crate::util::id::Id;
+use crate::util::money::Money;
+use std::collections::{BTreeSet, HashMap};
+
+/// A meal as a user ordered it, so it can be ordered the same way again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FavoriteMeal {
+    meal_id: String,
+    variety: String,
+    price: Money,
+    /// In the order they were added
+    specials: Vec<Special>,
+    preparations: BTreeSet<Preparation>,
+    note: Option<String>,
+}
+
+impl FavoriteMeal {
+    pub fn from_meal(meal: &Meal) -> FavoriteMeal {
+        let mut specials: Vec<Special> = meal.specials().cloned().collect();
+        specials.sort_by_key(|special| special.get_id().get_value());
+        FavoriteMeal {
+            meal_id: meal.get_meal_id().clone(),
+            variety: meal.get_variety().clone(),
+            price: meal.get_price(),
+            specials,
+            preparations: meal.get_preparations().clone(),
+            note: meal.get_note().cloned(),
+        }
+    }
+
+    pub fn get_meal_id(&self) -> &String {
+        &self.meal_id
+    }
+
+    pub fn get_variety(&self) -> &String {
+        &self.variety
+    }
+
+    /// Price when it was last ordered, the menu of a new order may ask for a different one.
+    pub fn get_price(&self) -> Money {
+        self.price
+    }
+
+    pub fn specials(&self) -> &[Special] {
+        &self.specials
+    }
+
+    pub fn get_preparations(&self) -> &BTreeSet<Preparation> {
+        &self.preparations
+    }
+
+    pub fn get_note(&self) -> Option<&String> {
+        self.note.as_ref()
+    }
+}
+
+/// The meals every user chose in their last delivered order, so regulars can order their usual again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Favorites {
+    last_orders: HashMap<Id<User>, Vec<FavoriteMeal>>,
+}
+
+impl Favorites {
+    pub fn new() -> Favorites {
+        Favorites::default()
+    }
+
+    /// Remembers the meals of every participant, replacing what they ordered before.
+    ///
+    /// Participants without meals keep their previous favorites, they probably just paid for someone else.
+    pub fn remember_order(&mut self, order: &Order) {
+        for user_id in order.participants() {
+            let mut meals: Vec<&Meal> = order
+                .get_user_meals(user_id)
+                .map(|meals| meals.meals().collect())
+                .unwrap_or_default();
+            if meals.is_empty() {
+                continue;
+            }
+            meals.sort_by_key(|meal| meal.get_id().get_value());
+            self.last_orders.insert(
+                user_id.clone(),
+                meals.into_iter().map(FavoriteMeal::from_meal).collect(),
+            );
+        }
+    }
+
+    /// Meals of the last order of the user, `None` if they never ordered anything.
+    pub fn get_last_order(&self, user_id: &Id<User>) -> Option<&[FavoriteMeal]> {
+        self.last_orders.get(user_id).map(Vec::as_slice)
+    }
+
+    pub fn forget_user(&mut self, user_id: &Id<User>) -> bool {
+        self.last_orders.remove(user_id).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_meals_of_participants_are_remembered() {
+        // Given:
+        let mut order = Order::new(Id::new(0));
+        order.add_user(Id::new(1)).unwrap();
+        let meal = order
+            .add_meal_for_user(
+                Id::new(1),
+                String::from("03"),
+                String::from("groß"),
+                Money::new(7, 50),
+            )
+            .unwrap()
+            .get_id();
+        order
+            .add_special_for_user(
+                Id::new(1),
+                meal,
+                String::from("Käserand"),
+                Some(Money::new(1, 0)),
+            )
+            .unwrap();
+        let mut favorites = Favorites::new();
+
+        // When:
+        favorites.remember_order(&order);
+
+        // Then:
+        let last_order = favorites.get_last_order(&Id::new(1)).unwrap();
+        assert_eq!(last_order.len(), 1);
+        assert_eq!(last_order[0].get_meal_id(), "03");
+        assert_eq!(last_order[0].get_price(), Money::new(7, 50));
+        assert_eq!(
+            last_order[0].specials()[0].get_description(),
+            String::from("Käserand")
+        );
+        assert_eq!(favorites.get_last_order(&Id::new(0)), None);
+    }
+
+    #[test]
+    fn participants_without_meals_keep_their_favorites() {
+        // Given:
+        let mut first = Order::new(Id::new(0));
+        first
+            .add_meal_for_user(
+                Id::new(0),
+                String::from("17"),
+                String::from("klein"),
+                Money::new(4, 0),
+            )
+            .unwrap();
+        let mut favorites = Favorites::new();
+        favorites.remember_order(&first);
+
+        // When:
+        favorites.remember_order(&Order::new(Id::new(0)));
+
+        // Then:
+        assert_eq!(
+            favorites.get_last_order(&Id::new(0)).unwrap()[0].get_meal_id(),
+            "17"
+        );
+    }
+}
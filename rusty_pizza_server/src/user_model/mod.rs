@@ -0,0 +1,2 @@
+pub mod favorites;
+pub mod repository;
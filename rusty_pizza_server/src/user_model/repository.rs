@@ -0,0 +1,194 @@
+use crate::order_model::user::{User, UserFactory};
+use crate::util::errors::RemoveError;
+use crate::util::id::Id;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RegistrationError {
+    /// The name is empty or only whitespace
+    EmptyName,
+    /// Another user is already registered with this name, ignoring case
+    NameTaken,
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use RegistrationError::*;
+        match self {
+            EmptyName => write!(f, "User name must not be empty"),
+            NameTaken => write!(f, "User name is already taken"),
+        }
+    }
+}
+
+impl Error for RegistrationError {}
+
+/// All registered users, which can take part in orders.
+#[derive(Debug, Default)]
+pub struct UserRepository {
+    /// User by unique ID
+    users: HashMap<Id<User>, User>,
+    /// User ID by lower case name
+    ids_by_name: HashMap<String, Id<User>>,
+    /// Cost center the meals of a user are billed to, unless the whole order is billed to another one
+    cost_centers: HashMap<Id<User>, String>,
+    user_factory: UserFactory,
+}
+
+impl UserRepository {
+    pub fn new() -> UserRepository {
+        UserRepository::default()
+    }
+
+    /// Registers a new user with the given name, which must be unique ignoring case and surrounding whitespace.
+    pub fn register(&mut self, name: String) -> Result<&User, RegistrationError> {
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return Err(RegistrationError::EmptyName);
+        }
+        let key = name.to_lowercase();
+        if self.ids_by_name.contains_key(&key) {
+            return Err(RegistrationError::NameTaken);
+        }
+        let user = self.user_factory.create_user(name);
+        let id = user.get_id();
+        self.ids_by_name.insert(key, id.clone());
+        Ok(self.users.entry(id).or_insert(user))
+    }
+
+    pub fn get_user(&self, id: &Id<User>) -> Option<&User> {
+        self.users.get(id)
+    }
+
+    /// Looks up a user by name, ignoring case and surrounding whitespace.
+    pub fn get_user_by_name(&self, name: &str) -> Option<&User> {
+        self.ids_by_name
+            .get(&name.trim().to_lowercase())
+            .and_then(|id| self.users.get(id))
+    }
+
+    pub fn contains(&self, id: &Id<User>) -> bool {
+        self.users.contains_key(id)
+    }
+
+    /// Removes the user, whose name can be registered again afterwards.
+    pub fn remove_user(&mut self, id: &Id<User>) -> Result<User, RemoveError> {
+        let user = self.users.remove(id).ok_or(RemoveError::NotFound)?;
+        self.ids_by_name.remove(&user.get_name().to_lowercase());
+        self.cost_centers.remove(id);
+        Ok(user)
+    }
+
+    pub fn get_cost_center(&self, id: &Id<User>) -> Option<&String> {
+        self.cost_centers.get(id)
+    }
+
+    /// Sets or removes the cost center of the user, returns `false` if the user is not registered.
+    pub fn set_cost_center(&mut self, id: &Id<User>, cost_center: Option<String>) -> bool {
+        if !self.users.contains_key(id) {
+            return false;
+        }
+        match cost_center {
+            Some(cost_center) => self.cost_centers.insert(id.clone(), cost_center),
+            None => self.cost_centers.remove(id),
+        };
+        true
+    }
+
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn user_can_be_registered_and_looked_up() {
+        // Given:
+        let mut repository = UserRepository::new();
+
+        // When:
+        let user = repository.register(String::from(" Peter ")).cloned();
+
+        // Then:
+        let expected = User::new(Id::new(0), String::from("Peter"));
+        assert_eq!(user, Ok(expected.clone()));
+        assert_eq!(repository.get_user(&Id::new(0)), Some(&expected));
+        assert_eq!(repository.get_user_by_name("peter"), Some(&expected));
+        assert!(repository.contains(&Id::new(0)));
+        assert_eq!(repository.users().count(), 1);
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("Peter", RegistrationError::NameTaken),
+        case("PETER ", RegistrationError::NameTaken),
+        case("", RegistrationError::EmptyName),
+        case("  ", RegistrationError::EmptyName)
+    )]
+    fn invalid_name_cannot_be_registered(name: &str, expected: RegistrationError) {
+        // Given:
+        let mut repository = UserRepository::new();
+        repository.register(String::from("Peter")).unwrap();
+
+        // When:
+        let user = repository.register(String::from(name)).cloned();
+
+        // Then:
+        assert_eq!(user, Err(expected));
+        assert_eq!(repository.users().count(), 1);
+    }
+
+    #[test]
+    fn unknown_user_is_not_found() {
+        // Given:
+        let repository = UserRepository::new();
+
+        // Then:
+        assert_eq!(repository.get_user(&Id::new(0)), None);
+        assert_eq!(repository.get_user_by_name("Peter"), None);
+    }
+
+    #[test]
+    fn removed_user_name_can_be_registered_again() {
+        // Given:
+        let mut repository = UserRepository::new();
+        repository.register(String::from("Peter")).unwrap();
+
+        // When:
+        let removed = repository.remove_user(&Id::new(0));
+        let removed_again = repository.remove_user(&Id::new(0));
+        let registered = repository.register(String::from("Peter")).cloned();
+
+        // Then:
+        assert_eq!(removed, Ok(User::new(Id::new(0), String::from("Peter"))));
+        assert_eq!(removed_again, Err(RemoveError::NotFound));
+        assert_eq!(registered, Ok(User::new(Id::new(1), String::from("Peter"))));
+    }
+
+    #[test]
+    fn cost_center_is_only_set_for_registered_user() {
+        // Given:
+        let mut repository = UserRepository::new();
+        repository.register(String::from("Peter")).unwrap();
+
+        // When:
+        let set = repository.set_cost_center(&Id::new(0), Some(String::from("4711")));
+        let unknown = repository.set_cost_center(&Id::new(1), Some(String::from("4711")));
+
+        // Then:
+        assert!(set);
+        assert!(!unknown);
+        assert_eq!(
+            repository.get_cost_center(&Id::new(0)),
+            Some(&String::from("4711"))
+        );
+        assert_eq!(repository.get_cost_center(&Id::new(1)), None);
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, SystemTime};
+
+/// Cache whose entries expire a fixed time-to-live after they were inserted.
+///
+/// The current time is passed in by the caller, which keeps the cache deterministic.
+#[derive(Debug)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    /// Value and time of insertion by key
+    entries: HashMap<K, (V, SystemTime)>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration) -> TtlCache<K, V> {
+        TtlCache {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns the value for `key` unless there is none or it expired at `now`.
+    pub fn get(&self, key: &K, now: SystemTime) -> Option<V> {
+        match self.entries.get(key) {
+            Some((value, inserted))
+                if now.duration_since(*inserted).unwrap_or_default() < self.ttl =>
+            {
+                Some(value.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V, now: SystemTime) {
+        self.entries.insert(key, (value, now));
+    }
+
+    /// Returns the cached value for `key` or, if there is no fresh one, loads and caches it.
+    ///
+    /// Failed loads are not cached.
+    pub fn get_or_try_insert_with<E>(
+        &mut self,
+        key: K,
+        now: SystemTime,
+        load: impl FnOnce() -> Result<V, E>,
+    ) -> Result<V, E> {
+        if let Some(value) = self.get(&key, now) {
+            return Ok(value);
+        }
+        let value = load()?;
+        self.insert(key, value.clone(), now);
+        Ok(value)
+    }
+
+    /// Removes the entry for `key` and returns whether there was one.
+    pub fn invalidate(&mut self, key: &K) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn fresh_entry_is_returned() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache = TtlCache::new(TTL);
+        cache.insert("Luigi", 1, now);
+
+        // When:
+        let value = cache.get(&"Luigi", now + Duration::from_secs(59));
+
+        // Then:
+        assert_eq!(value, Some(1));
+    }
+
+    #[test]
+    fn expired_entry_is_not_returned() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache = TtlCache::new(TTL);
+        cache.insert("Luigi", 1, now);
+
+        // When:
+        let value = cache.get(&"Luigi", now + TTL);
+
+        // Then:
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn value_is_loaded_only_once_while_fresh() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache = TtlCache::new(TTL);
+        let mut loads = 0;
+
+        // When:
+        for seconds in 0..3 {
+            let value: Result<i32, ()> =
+                cache.get_or_try_insert_with("Luigi", now + Duration::from_secs(seconds), || {
+                    loads += 1;
+                    Ok(7)
+                });
+            assert_eq!(value, Ok(7));
+        }
+
+        // Then:
+        assert_eq!(loads, 1);
+    }
+
+    #[test]
+    fn expired_value_is_reloaded() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache = TtlCache::new(TTL);
+        cache.insert("Luigi", 1, now);
+
+        // When:
+        let value: Result<i32, ()> = cache.get_or_try_insert_with("Luigi", now + TTL, || Ok(2));
+
+        // Then:
+        assert_eq!(value, Ok(2));
+        assert_eq!(cache.get(&"Luigi", now + TTL), Some(2));
+    }
+
+    #[test]
+    fn failed_load_is_not_cached() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache: TtlCache<&str, i32> = TtlCache::new(TTL);
+
+        // When:
+        let value = cache.get_or_try_insert_with("Luigi", now, || Err("unavailable"));
+
+        // Then:
+        assert_eq!(value, Err("unavailable"));
+        assert_eq!(cache.get(&"Luigi", now), None);
+    }
+
+    #[test]
+    fn entry_can_be_invalidated() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache = TtlCache::new(TTL);
+        cache.insert("Luigi", 1, now);
+        cache.insert("Mario", 2, now);
+
+        // When:
+        let invalidated = cache.invalidate(&"Luigi");
+        let invalidated_again = cache.invalidate(&"Luigi");
+
+        // Then:
+        assert!(invalidated);
+        assert!(!invalidated_again);
+        assert_eq!(cache.get(&"Luigi", now), None);
+        assert_eq!(cache.get(&"Mario", now), Some(2));
+    }
+
+    #[test]
+    fn all_entries_can_be_invalidated() {
+        // Given:
+        let now = SystemTime::now();
+        let mut cache = TtlCache::new(TTL);
+        cache.insert("Luigi", 1, now);
+        cache.insert("Mario", 2, now);
+
+        // When:
+        cache.invalidate_all();
+
+        // Then:
+        assert_eq!(cache.get(&"Luigi", now), None);
+        assert_eq!(cache.get(&"Mario", now), None);
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Source of the current time for everything that depends on it, e.g. caches, deadlines and reminders.
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+/// Clock returning the time of the operating system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock that only moves when told to, so time-based behavior can be tested deterministically.
+///
+/// Clones share the same time, so a test can keep one and advance the clone it handed out.
+#[derive(Clone)]
+pub struct TestClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl TestClock {
+    pub fn new(start: SystemTime) -> TestClock {
+        TestClock {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().expect("Test clock lock is poisoned");
+        *now += duration;
+    }
+
+    pub fn set(&self, time: SystemTime) {
+        *self.now.lock().expect("Test clock lock is poisoned") = time;
+    }
+}
+
+impl Default for TestClock {
+    /// Starts at the Unix epoch.
+    fn default() -> TestClock {
+        TestClock::new(SystemTime::UNIX_EPOCH)
+    }
+}
+
+impl fmt::Debug for TestClock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TestClock").field(&self.now()).finish()
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().expect("Test clock lock is poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_stands_still() {
+        // Given:
+        let clock = TestClock::default();
+
+        // When:
+        let first = clock.now();
+        let second = clock.now();
+
+        // Then:
+        assert_eq!(first, SystemTime::UNIX_EPOCH);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_clock_can_be_advanced_through_clone() {
+        // Given:
+        let clock = TestClock::default();
+        let handed_out = clock.clone();
+
+        // When:
+        clock.advance(Duration::from_secs(60));
+
+        // Then:
+        assert_eq!(
+            handed_out.now(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_clock_can_be_set() {
+        // Given:
+        let clock = TestClock::default();
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(3600);
+
+        // When:
+        clock.set(time);
+
+        // Then:
+        assert_eq!(clock.now(), time);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::util::locale::{Currency, Locale};
+use crate::util::money::Money;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why a decimal can't be represented as `Money` exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecimalError {
+    /// Not a decimal like "12.30", optionally followed by a currency code like "12.30 CHF"
+    Malformed(String),
+    Negative(String),
+    /// More than two decimal places, which would need rounding
+    TooPrecise(String),
+    /// More cents than `Money` can hold
+    OutOfRange(String),
+}
+
+impl fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use DecimalError::*;
+        match self {
+            Malformed(text) => write!(f, "{} is not a decimal amount", text),
+            Negative(text) => write!(f, "{} is negative", text),
+            TooPrecise(text) => write!(f, "{} has more than two decimal places", text),
+            OutOfRange(text) => write!(f, "{} is too large", text),
+        }
+    }
+}
+
+impl Error for DecimalError {}
+
+impl Money {
+    /// Writes the amount as plain decimal with two places and a dot, e.g. "1234.05", as accounting exports expect.
+    pub fn to_decimal_string(&self) -> String {
+        format!("{}.{:02}", self.get_euros(), self.get_cents())
+    }
+
+    /// Parses an amount as users of the locale type it, e.g. "1.234,56" in Germany and "1,234.56" in English.
+    ///
+    /// Groups of thousands may be separated, but then every group needs three digits. The currency symbol or code
+    /// may be written in front of or after the amount, like in "5,50€" or "CHF 5.50".
+    /// ```
+    /// # use rusty_pizza_server::util::locale::Locale;
+    /// # use rusty_pizza_server::util::money::Money;
+    /// assert_eq!(Money::parse_localized("1.234,56", Locale::DeDe), Ok(Money::new(1234, 56)));
+    /// assert_eq!(Money::parse_localized("1,234.56", Locale::En), Ok(Money::new(1234, 56)));
+    /// ```
+    pub fn parse_localized(text: &str, locale: Locale) -> Result<Money, DecimalError> {
+        let with_text = |error: DecimalError| match error {
+            DecimalError::Malformed(_) => DecimalError::Malformed(String::from(text)),
+            DecimalError::Negative(_) => DecimalError::Negative(String::from(text)),
+            DecimalError::TooPrecise(_) => DecimalError::TooPrecise(String::from(text)),
+            DecimalError::OutOfRange(_) => DecimalError::OutOfRange(String::from(text)),
+        };
+        let (amount, currency) = strip_currency(text.trim());
+        let (units, fraction) = match amount.split_once(locale.get_decimal_separator()) {
+            Some((units, fraction)) => (units, Some(fraction)),
+            None => (amount, None),
+        };
+        let units =
+            ungroup(units, locale).ok_or_else(|| DecimalError::Malformed(String::from(text)))?;
+        let decimal = match fraction {
+            Some(fraction) => format!("{}.{}", units, fraction),
+            None => units,
+        };
+        let money = decimal.parse::<Money>().map_err(with_text)?;
+        Ok(match currency {
+            Some(currency) => money.with_currency(currency),
+            None => money,
+        })
+    }
+}
+
+/// Removes a currency symbol or code in front of or after the amount.
+fn strip_currency(text: &str) -> (&str, Option<Currency>) {
+    for currency in [Currency::Eur, Currency::Chf] {
+        for marker in [currency.get_symbol(), currency.get_code()] {
+            if let Some(amount) = text.strip_prefix(marker) {
+                return (amount.trim_start(), Some(currency));
+            }
+            if let Some(amount) = text.strip_suffix(marker) {
+                return (amount.trim_end(), Some(currency));
+            }
+        }
+    }
+    (text, None)
+}
+
+/// Removes the grouping separators of the locale, `None` if the groups aren't thousands.
+fn ungroup(units: &str, locale: Locale) -> Option<String> {
+    let separator = locale.get_grouping_separator();
+    // Swiss users type the typographic apostrophe as well
+    let groups: Vec<&str> = units
+        .split(|c| c == separator || (locale == Locale::DeCh && c == '’'))
+        .collect();
+    let (first, rest) = groups.split_first()?;
+    let is_grouped = rest
+        .iter()
+        .all(|group| group.len() == 3 && group.chars().all(|c| c.is_ascii_digit()));
+    if rest.is_empty() || (is_grouped && (1..=3).contains(&first.len())) {
+        Some(groups.concat())
+    } else {
+        None
+    }
+}
+
+/// Parses plain decimals like "12.3" or "12.30", optionally followed by a currency code like "12.30 CHF".
+impl FromStr for Money {
+    type Err = DecimalError;
+
+    fn from_str(text: &str) -> Result<Money, DecimalError> {
+        let malformed = || DecimalError::Malformed(String::from(text));
+        let mut parts = text.split_whitespace();
+        let amount = parts.next().ok_or_else(malformed)?;
+        let currency = parts
+            .next()
+            .map(|code| code.parse::<Currency>().map_err(|_| malformed()))
+            .transpose()?;
+        if parts.next().is_some() {
+            return Err(malformed());
+        }
+        if amount.starts_with('-') {
+            return Err(DecimalError::Negative(String::from(text)));
+        }
+        let (units, fraction) = match amount.split_once('.') {
+            Some((units, fraction)) => (units, fraction),
+            None => (amount, "00"),
+        };
+        let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        if !is_digits(units) || !is_digits(fraction) {
+            return Err(malformed());
+        }
+        if fraction.len() > 2 {
+            return Err(DecimalError::TooPrecise(String::from(text)));
+        }
+        let out_of_range = || DecimalError::OutOfRange(String::from(text));
+        let units: u32 = units.parse().map_err(|_| out_of_range())?;
+        let fraction: u32 = format!("{:0<2}", fraction)
+            .parse()
+            .map_err(|_| malformed())?;
+        let cents = units
+            .checked_mul(100)
+            .and_then(|cents| cents.checked_add(fraction))
+            .ok_or_else(out_of_range)?;
+        let money = Money::from_cents(cents);
+        Ok(match currency {
+            Some(currency) => money.with_currency(currency),
+            None => money,
+        })
+    }
+}
+
+/// Human-readable formats like JSON get the decimal string, e.g. "12.30 CHF", others the cents and currency code.
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let amount = self.to_decimal_string();
+            match self.get_currency() {
+                Some(currency) => {
+                    serializer.serialize_str(&format!("{} {}", amount, currency.get_code()))
+                }
+                None => serializer.serialize_str(&amount),
+            }
+        } else {
+            (
+                self.get_total_cents(),
+                self.get_currency().map(|currency| currency.get_code()),
+            )
+                .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        if deserializer.is_human_readable() {
+            let text = String::deserialize(deserializer)?;
+            text.parse().map_err(de::Error::custom)
+        } else {
+            let (cents, code) = <(u32, Option<String>)>::deserialize(deserializer)?;
+            let money = Money::from_cents(cents);
+            match code {
+                Some(code) => Ok(money.with_currency(code.parse().map_err(de::Error::custom)?)),
+                None => Ok(money),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "decimal")]
+mod exact {
+    use super::DecimalError;
+    use crate::util::money::Money;
+    use rust_decimal::Decimal;
+    use std::convert::TryFrom;
+
+    /// Always exact, the currency is dropped.
+    impl From<Money> for Decimal {
+        fn from(money: Money) -> Decimal {
+            Decimal::new(i64::from(money.get_total_cents()), 2)
+        }
+    }
+
+    /// Fails instead of rounding if the decimal has more than two places after trailing zeros are removed.
+    impl TryFrom<Decimal> for Money {
+        type Error = DecimalError;
+
+        fn try_from(decimal: Decimal) -> Result<Money, DecimalError> {
+            let normalized = decimal.normalize();
+            if normalized.is_sign_negative() && !normalized.is_zero() {
+                return Err(DecimalError::Negative(decimal.to_string()));
+            }
+            if normalized.scale() > 2 {
+                return Err(DecimalError::TooPrecise(decimal.to_string()));
+            }
+            let cents = (normalized * Decimal::ONE_HUNDRED).trunc();
+            u32::try_from(cents.mantissa() / 10_i128.pow(cents.scale()))
+                .map(Money::from_cents)
+                .map_err(|_| DecimalError::OutOfRange(decimal.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    #[rstest(
+        text,
+        expected,
+        case("12.30", Ok(Money::new(12, 30))),
+        case("12.3", Ok(Money::new(12, 30))),
+        case("12", Ok(Money::new(12, 0))),
+        case("0.05 CHF", Ok(Money::new(0, 5).with_currency(Currency::Chf))),
+        case("12.345", Err(DecimalError::TooPrecise(String::from("12.345")))),
+        case("-1.00", Err(DecimalError::Negative(String::from("-1.00")))),
+        case("12,30", Err(DecimalError::Malformed(String::from("12,30")))),
+        case("12.", Err(DecimalError::Malformed(String::from("12.")))),
+        case("12.30 USD", Err(DecimalError::Malformed(String::from("12.30 USD")))),
+        case(
+            "42949672.96",
+            Err(DecimalError::OutOfRange(String::from("42949672.96")))
+        )
+    )]
+    fn money_is_parsed_from_decimal(text: &str, expected: Result<Money, DecimalError>) {
+        assert_eq!(text.parse::<Money>(), expected);
+    }
+
+    #[rstest(
+        text,
+        locale,
+        expected,
+        case("1.234,56", Locale::DeDe, Ok(Money::new(1234, 56))),
+        case("1,234.56", Locale::En, Ok(Money::new(1234, 56))),
+        case("1'234.50", Locale::DeCh, Ok(Money::new(1234, 50))),
+        case("1’234", Locale::DeCh, Ok(Money::new(1234, 0))),
+        case("7,5", Locale::DeDe, Ok(Money::new(7, 50))),
+        case("5,50€", Locale::DeDe, Ok(Money::new(5, 50).with_currency(Currency::Eur))),
+        case("CHF 5.50", Locale::DeCh, Ok(Money::new(5, 50).with_currency(Currency::Chf))),
+        case("1.234", Locale::DeDe, Ok(Money::new(1234, 0))),
+        case("1.234", Locale::En, Err(DecimalError::TooPrecise(String::from("1.234")))),
+        case("1,234.56", Locale::DeDe, Err(DecimalError::Malformed(String::from("1,234.56")))),
+        case("12.34,5", Locale::DeDe, Err(DecimalError::Malformed(String::from("12.34,5")))),
+        case("-7,50", Locale::DeDe, Err(DecimalError::Negative(String::from("-7,50"))))
+    )]
+    fn localized_money_is_parsed(
+        text: &str,
+        locale: Locale,
+        expected: Result<Money, DecimalError>,
+    ) {
+        assert_eq!(Money::parse_localized(text, locale), expected);
+    }
+
+    #[test]
+    fn money_is_serialized_as_decimal_string() {
+        // Given:
+        let amounts = vec![
+            Money::new(5, 5),
+            Money::new(12, 30).with_currency(Currency::Chf),
+        ];
+
+        // When:
+        let json = serde_json::to_string(&amounts).unwrap();
+
+        // Then:
+        assert_eq!(json, "[\"5.05\",\"12.30 CHF\"]");
+    }
+
+    fn any_money() -> impl Strategy<Value = Money> {
+        (
+            any::<u32>(),
+            prop_oneof![
+                Just(None),
+                Just(Some(Currency::Eur)),
+                Just(Some(Currency::Chf))
+            ],
+        )
+            .prop_map(|(cents, currency)| match currency {
+                Some(currency) => Money::from_cents(cents).with_currency(currency),
+                None => Money::from_cents(cents),
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn decimal_string_round_trips_cent_exact(money in any_money()) {
+            let text = money.to_decimal_string();
+            prop_assert_eq!(text.parse::<Money>(), Ok(Money::from_cents(money.get_total_cents())));
+        }
+
+        #[test]
+        fn serde_round_trips_cent_exact(money in any_money()) {
+            let json = serde_json::to_string(&money).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), money);
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    mod exact {
+        use super::*;
+        use rust_decimal::Decimal;
+        use std::convert::TryFrom;
+
+        #[rstest(
+            decimal,
+            expected,
+            case(Decimal::new(1230, 2), Ok(Money::new(12, 30))),
+            case(Decimal::new(123000, 4), Ok(Money::new(12, 30))),
+            case(
+                Decimal::new(12345, 3),
+                Err(DecimalError::TooPrecise(String::from("12.345")))
+            ),
+            case(
+                Decimal::new(-100, 2),
+                Err(DecimalError::Negative(String::from("-1.00")))
+            )
+        )]
+        fn money_is_converted_from_decimal(
+            decimal: Decimal,
+            expected: Result<Money, DecimalError>,
+        ) {
+            assert_eq!(Money::try_from(decimal), expected);
+        }
+
+        proptest! {
+            #[test]
+            fn decimal_round_trips_cent_exact(money in any_money()) {
+                let decimal = Decimal::from(money);
+                prop_assert_eq!(decimal.to_string(), money.to_decimal_string());
+                prop_assert_eq!(
+                    Money::try_from(decimal),
+                    Ok(Money::from_cents(money.get_total_cents()))
+                );
+            }
+        }
+    }
+}
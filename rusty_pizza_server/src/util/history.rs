@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+/// Bounded undo and redo stacks.
+///
+/// Each entry is the change that reverts an operation, so undoing means applying the popped change and pushing its
+/// own revert onto the redo stack (and vice versa).
+#[derive(Clone, Debug, PartialEq)]
+pub struct History<T> {
+    /// Maximum number of changes that can be undone
+    capacity: usize,
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+}
+
+impl<T> History<T> {
+    pub fn new(capacity: usize) -> History<T> {
+        History {
+            capacity,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Records the change reverting a newly performed operation.
+    ///
+    /// This invalidates everything that could have been redone. If the history is full, the oldest change is dropped.
+    pub fn record(&mut self, revert: T) {
+        self.redo.clear();
+        self.push_undo(revert);
+    }
+
+    /// Pushes a change onto the undo stack without invalidating the redo stack.
+    pub fn push_undo(&mut self, revert: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.undo.len() == self.capacity {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(revert);
+    }
+
+    /// The changes the next `steps` undos would apply, latest first.
+    pub fn peek_undo(&self, steps: usize) -> impl Iterator<Item = &T> {
+        self.undo.iter().rev().take(steps)
+    }
+
+    pub fn pop_undo(&mut self) -> Option<T> {
+        self.undo.pop_back()
+    }
+
+    pub fn push_redo(&mut self, change: T) {
+        self.redo.push(change);
+    }
+
+    /// The change the next redo would apply.
+    pub fn peek_redo(&self) -> Option<&T> {
+        self.redo.last()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<T> {
+        self.redo.pop()
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_changes_are_undone_last_first() {
+        // Given:
+        let mut history = History::new(10);
+        history.record(1);
+        history.record(2);
+
+        // When:
+        let first = history.pop_undo();
+        let second = history.pop_undo();
+        let third = history.pop_undo();
+
+        // Then:
+        assert_eq!(first, Some(2));
+        assert_eq!(second, Some(1));
+        assert_eq!(third, None);
+    }
+
+    #[test]
+    fn oldest_change_is_dropped_when_capacity_is_exceeded() {
+        // Given:
+        let mut history = History::new(2);
+
+        // When:
+        history.record(1);
+        history.record(2);
+        history.record(3);
+
+        // Then:
+        assert_eq!(history.undo_len(), 2);
+        assert_eq!(history.pop_undo(), Some(3));
+        assert_eq!(history.pop_undo(), Some(2));
+        assert_eq!(history.pop_undo(), None);
+    }
+
+    #[test]
+    fn recording_invalidates_redo() {
+        // Given:
+        let mut history = History::new(10);
+        history.push_redo(1);
+
+        // When:
+        history.record(2);
+
+        // Then:
+        assert_eq!(history.redo_len(), 0);
+        assert_eq!(history.pop_redo(), None);
+    }
+
+    #[test]
+    fn pushing_undo_keeps_redo() {
+        // Given:
+        let mut history = History::new(10);
+        history.push_redo(1);
+
+        // When:
+        history.push_undo(2);
+
+        // Then:
+        assert_eq!(history.pop_redo(), Some(1));
+        assert_eq!(history.pop_undo(), Some(2));
+    }
+
+    #[test]
+    fn history_can_be_cleared() {
+        // Given:
+        let mut history = History::new(10);
+        history.record(1);
+        history.push_redo(2);
+
+        // When:
+        history.clear();
+
+        // Then:
+        assert_eq!(history.undo_len(), 0);
+        assert_eq!(history.redo_len(), 0);
+    }
+}
@@ -0,0 +1,369 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpError {
+    /// Only plain `http://host[:port]/path` URLs are supported, anything else needs a relay in the local network
+    UnsupportedUrl(String),
+    /// The server could not be reached
+    Connection(String),
+    /// The server answered with a status other than 2xx, or not with HTTP at all
+    Rejected(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use HttpError::*;
+        match self {
+            UnsupportedUrl(url) => write!(f, "{} is not a URL like http://host:port/path", url),
+            Connection(error) => write!(f, "server could not be reached: {}", error),
+            Rejected(status) => write!(f, "server rejected the request: {}", status),
+        }
+    }
+}
+
+impl Error for HttpError {}
+
+impl HttpError {
+    /// The status code the server answered with, if it answered at all.
+    pub fn get_status(&self) -> Option<u16> {
+        match self {
+            HttpError::Rejected(status_line) => status_line.split(' ').nth(1)?.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Where a server accepts data via `POST` or serves it via `GET`, or the base URL of an API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpEndpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl HttpEndpoint {
+    pub fn get_host(&self) -> &String {
+        &self.host
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+
+    /// Endpoint at the path below this one, e.g. "/orders/3" below "http://server:8080/pizza".
+    pub fn join(&self, path: &str) -> HttpEndpoint {
+        HttpEndpoint {
+            host: self.host.clone(),
+            port: self.port,
+            path: format!("{}{}", self.path.trim_end_matches('/'), path),
+        }
+    }
+
+    /// Endpoint sending its requests for the absolute URL to this one, a proxy which makes the actual request.
+    ///
+    /// The request line names the URL like `POST https://push.example.com/send/1 HTTP/1.1`, so a relay in the
+    /// local network can forward requests to HTTPS servers.
+    pub fn forward(&self, url: &str) -> HttpEndpoint {
+        HttpEndpoint {
+            host: self.host.clone(),
+            port: self.port,
+            path: url.replace(['\r', '\n', ' '], ""),
+        }
+    }
+
+    /// Sends the body and waits for the status of the answer.
+    pub async fn post(&self, content_type: &str, body: &str) -> Result<(), HttpError> {
+        self.post_bytes(content_type, &[], body.as_bytes()).await
+    }
+
+    /// Sends the binary body with the additional headers and waits for the status of the answer.
+    pub async fn post_bytes(
+        &self,
+        content_type: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<(), HttpError> {
+        let mut head = format!(
+            "Content-Type: {}\r\nContent-Length: {}\r\n",
+            content_type,
+            body.len()
+        );
+        for (name, value) in headers {
+            head.push_str(&format!(
+                "{}: {}\r\n",
+                name,
+                value.replace(['\r', '\n'], "")
+            ));
+        }
+        self.send("POST", &head, body)
+            .await?
+            .into_success()
+            .map(|_| ())
+    }
+
+    /// Fetches the resource with the token as `Authorization: Bearer`, returning the body of the answer.
+    pub async fn fetch(&self, bearer_token: &str) -> Result<String, HttpError> {
+        let headers = authorization(bearer_token);
+        self.send("GET", &headers, b"").await?.into_success()
+    }
+
+    /// Sends the JSON, if any, with the token, if any, and returns the answer whatever its status.
+    pub async fn send_json(
+        &self,
+        method: &str,
+        bearer_token: Option<&str>,
+        json: Option<&str>,
+    ) -> Result<HttpResponse, HttpError> {
+        let mut headers = bearer_token.map(authorization).unwrap_or_default();
+        if let Some(json) = json {
+            headers.push_str(&format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n",
+                json.len()
+            ));
+        }
+        self.send(method, &headers, json.unwrap_or_default().as_bytes())
+            .await
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        headers: &str,
+        body: &[u8],
+    ) -> Result<HttpResponse, HttpError> {
+        let connection_error = |error: std::io::Error| HttpError::Connection(error.to_string());
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(connection_error)?;
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}:{}\r\n{}Connection: close\r\n\r\n",
+            method, self.path, self.host, self.port, headers
+        )
+        .into_bytes();
+        request.extend_from_slice(body);
+        stream.write_all(&request).await.map_err(connection_error)?;
+        let mut response = Vec::new();
+        stream
+            .read_to_end(&mut response)
+            .await
+            .map_err(connection_error)?;
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+        let status_line = String::from(head.lines().next().unwrap_or_default());
+        let status = status_line
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| HttpError::Rejected(status_line.clone()))?;
+        let chunked = head
+            .lines()
+            .any(|line| line.to_lowercase().replace(' ', "") == "transfer-encoding:chunked");
+        let body = if chunked {
+            decode_chunked(body).ok_or_else(|| HttpError::Rejected(status_line.clone()))?
+        } else {
+            String::from(body)
+        };
+        Ok(HttpResponse {
+            status_line,
+            status,
+            body,
+        })
+    }
+}
+
+/// Status and body of the answer to a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    status_line: String,
+    status: u16,
+    body: String,
+}
+
+impl HttpResponse {
+    pub fn get_status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn get_body(&self) -> &String {
+        &self.body
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The body, if the status is 2xx.
+    pub fn into_success(self) -> Result<String, HttpError> {
+        if self.is_success() {
+            Ok(self.body)
+        } else {
+            Err(HttpError::Rejected(self.status_line))
+        }
+    }
+}
+
+fn authorization(bearer_token: &str) -> String {
+    format!(
+        "Authorization: Bearer {}\r\nAccept: application/json\r\n",
+        bearer_token.replace(['\r', '\n'], "")
+    )
+}
+
+/// Joins the chunks of a body sent with `Transfer-Encoding: chunked`, `None` if it is malformed.
+fn decode_chunked(mut body: &str) -> Option<String> {
+    let mut decoded = String::new();
+    loop {
+        let (size, rest) = body.split_once("\r\n")?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.push_str(rest.get(..size)?);
+        body = rest.get(size..)?.strip_prefix("\r\n")?;
+    }
+}
+
+impl FromStr for HttpEndpoint {
+    type Err = HttpError;
+
+    /// Parses an URL like "http://kitchen-screen:8000/pizza", the port defaults to 80 and the path to "/".
+    fn from_str(url: &str) -> Result<HttpEndpoint, HttpError> {
+        let unsupported = || HttpError::UnsupportedUrl(String::from(url));
+        let rest = url.trim().strip_prefix("http://").ok_or_else(unsupported)?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| unsupported())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() || host.contains('@') {
+            return Err(unsupported());
+        }
+        Ok(HttpEndpoint {
+            host: String::from(host),
+            port,
+            path: String::from(path),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::TcpListener;
+
+    #[rstest(
+        url,
+        expected,
+        case("http://kitchen:8000/pizza", Ok(("kitchen", 8000, "/pizza"))),
+        case("http://10.0.0.7", Ok(("10.0.0.7", 80, "/"))),
+        case("https://kitchen/pizza", Err(())),
+        case("http://:8000/pizza", Err(()))
+    )]
+    fn endpoint_is_parsed(url: &str, expected: Result<(&str, u16, &str), ()>) {
+        // When:
+        let endpoint = url.parse::<HttpEndpoint>();
+
+        // Then:
+        assert_eq!(
+            endpoint
+                .as_ref()
+                .map(|e| (e.get_host().as_str(), e.get_port(), e.get_path().as_str()))
+                .map_err(|_| ()),
+            expected
+        );
+    }
+
+    #[rstest(
+        base,
+        expected,
+        case("http://server:8080", "/orders/3"),
+        case("http://server:8080/pizza/", "/pizza/orders/3")
+    )]
+    fn path_is_joined_below_base(base: &str, expected: &str) {
+        // When:
+        let endpoint = base.parse::<HttpEndpoint>().unwrap().join("/orders/3");
+
+        // Then:
+        assert_eq!(endpoint.get_path(), expected);
+        assert_eq!(endpoint.get_port(), 8080);
+    }
+
+    #[test]
+    fn absolute_url_is_forwarded_to_relay() {
+        // Given:
+        let relay: HttpEndpoint = "http://relay:3128".parse().unwrap();
+
+        // When:
+        let endpoint = relay.forward("https://push.example.net/send/1\r\nX-Evil: 1");
+
+        // Then:
+        assert_eq!(endpoint.get_host(), "relay");
+        assert_eq!(endpoint.get_port(), 3128);
+        assert_eq!(
+            endpoint.get_path(),
+            "https://push.example.net/send/1X-Evil:1"
+        );
+    }
+
+    #[rstest(
+        body,
+        expected,
+        case("5\r\nhello\r\n6;x=y\r\n world\r\n0\r\n\r\n", Some("hello world")),
+        case("5\r\nhello", None),
+        case("zz\r\nhello\r\n0\r\n\r\n", None)
+    )]
+    fn chunked_body_is_decoded(body: &str, expected: Option<&str>) {
+        assert_eq!(decode_chunked(body).as_deref(), expected);
+    }
+
+    #[tokio::test]
+    async fn resource_is_fetched_with_token() {
+        // Given:
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/userinfo", listener.local_addr().unwrap());
+        let endpoint: HttpEndpoint = url.parse().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut request = Vec::new();
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                request.push(String::from(line.trim_end()));
+            }
+            stream
+                .get_mut()
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"sub\":\"42\"}",
+                )
+                .await
+                .unwrap();
+            request
+        });
+
+        // When:
+        let body = endpoint.fetch("abc\r\nX-Evil: 1").await;
+
+        // Then:
+        assert_eq!(body, Ok(String::from("{\"sub\":\"42\"}")));
+        let request = server.await.unwrap();
+        assert_eq!(request[0], "GET /userinfo HTTP/1.1");
+        assert!(request.contains(&String::from("Authorization: Bearer abcX-Evil: 1")));
+    }
+}
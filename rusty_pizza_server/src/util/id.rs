@@ -1,12 +1,94 @@
-/// A usually unique ID referencing an entity.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct Id {
-    value: u32,
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+/// A usually unique ID referencing an entity of type `T`, e.g. `Id<User>`.
+///
+/// The type parameter only marks what the ID belongs to, so IDs of different entities can't be mixed up. The
+/// value is a sequential `u32` unless stated otherwise, with the `uuid` feature also a `Uuid`, which is unique
+/// across servers and restarts without any coordination.
+pub struct Id<T, V = u32> {
+    value: V,
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    pub fn new(value: u32) -> Id<T> {
+        Id::from_value(value)
+    }
+
+    pub fn get_value(&self) -> u32 {
+        self.value
+    }
+}
+
+impl<T, V> Id<T, V> {
+    /// An ID with a value of any scheme, see `IdScheme`.
+    pub fn from_value(value: V) -> Id<T, V> {
+        Id {
+            value,
+            entity: PhantomData,
+        }
+    }
+
+    /// The value of any scheme, e.g. to display it.
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<T> Id<T, Uuid> {
+    /// A random ID, which doesn't collide with the IDs of other servers.
+    pub fn new_v4() -> Id<T, Uuid> {
+        Id::from_value(Uuid::new_v4())
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.value
+    }
+}
+
+// Implemented by hand, deriving would require `T` to implement the traits as well
+
+impl<T, V: Clone> Clone for Id<T, V> {
+    fn clone(&self) -> Id<T, V> {
+        Id::from_value(self.value.clone())
+    }
 }
 
-impl Id {
-    pub fn new(value: u32) -> Id {
-        Id { value }
+impl<T, V: fmt::Debug> fmt::Debug for Id<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Id").field("value", &self.value).finish()
+    }
+}
+
+impl<T, V: PartialEq> PartialEq for Id<T, V> {
+    fn eq(&self, other: &Id<T, V>) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T, V: Eq> Eq for Id<T, V> {}
+
+impl<T, V: Ord> PartialOrd for Id<T, V> {
+    fn partial_cmp(&self, other: &Id<T, V>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, V: Ord> Ord for Id<T, V> {
+    fn cmp(&self, other: &Id<T, V>) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T, V: Hash> Hash for Id<T, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
     }
 }
 
@@ -17,10 +99,22 @@ mod tests {
     #[test]
     fn ids_with_equal_value_are_equal() {
         // When:
-        let id1 = Id::new(0);
+        let id1: Id<()> = Id::new(0);
         let id2 = Id::new(0);
 
         // Then:
         assert_eq!(id1, id2);
     }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn random_ids_differ() {
+        // When:
+        let id1: Id<(), Uuid> = Id::new_v4();
+        let id2 = Id::new_v4();
+
+        // Then:
+        assert_ne!(id1, id2);
+        assert_eq!(id1.get_uuid().get_version_num(), 4);
+    }
 }
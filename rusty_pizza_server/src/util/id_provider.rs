@@ -1,30 +1,210 @@
 use crate::util::id::Id;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-pub struct IdProvider {
-    next_id: u32,
+/// All IDs an `IdProvider` can generate were handed out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdsExhaustedError;
+
+impl fmt::Display for IdsExhaustedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "all {} IDs were handed out", u32::MAX)
+    }
+}
+
+impl Error for IdsExhaustedError {}
+
+/// How an `IdProvider` comes up with the values of its IDs.
+pub trait IdScheme {
+    type Value;
+
+    /// The value of the next ID, unless there are none left.
+    fn next_value(&self) -> Result<Self::Value, IdsExhaustedError>;
+}
+
+/// Consecutive numbers, the default scheme.
+///
+/// Each value is handed out once, in the order the calls happened, even if they come from several threads.
+pub struct Sequential {
+    next_id: AtomicU32,
+}
+
+impl Sequential {
+    /// Continues after the IDs generated before, e.g. when entities are restored.
+    pub fn starting_at(next_id: u32) -> Sequential {
+        Sequential {
+            next_id: AtomicU32::new(next_id),
+        }
+    }
+
+    fn peek(&self) -> u32 {
+        self.next_id.load(Ordering::Relaxed)
+    }
+}
+
+/// `u32::MAX` is never generated, it only marks that there are no IDs left, instead of wrapping around and
+/// repeating them.
+impl IdScheme for Sequential {
+    type Value = u32;
+
+    fn next_value(&self) -> Result<u32, IdsExhaustedError> {
+        self.next_id
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                next.checked_add(1)
+            })
+            .map_err(|_| IdsExhaustedError)
+    }
+}
+
+impl Clone for Sequential {
+    fn clone(&self) -> Sequential {
+        Sequential::starting_at(self.peek())
+    }
+}
+
+impl fmt::Debug for Sequential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sequential")
+            .field("next_id", &self.peek())
+            .finish()
+    }
+}
+
+impl Default for Sequential {
+    fn default() -> Sequential {
+        Sequential::starting_at(0)
+    }
+}
+
+impl PartialEq for Sequential {
+    fn eq(&self, other: &Sequential) -> bool {
+        self.peek() == other.peek()
+    }
+}
+
+impl Eq for Sequential {}
+
+impl Hash for Sequential {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.peek().hash(state);
+    }
+}
+
+/// Random version 4 UUIDs, which don't collide between servers, so entities can be merged or moved.
+#[cfg(feature = "uuid")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RandomUuid;
+
+#[cfg(feature = "uuid")]
+impl IdScheme for RandomUuid {
+    type Value = Uuid;
+
+    fn next_value(&self) -> Result<Uuid, IdsExhaustedError> {
+        Ok(Uuid::new_v4())
+    }
+}
+
+/// Generates IDs for entities of type `T` according to an `IdScheme`, consecutive ones by default.
+///
+/// IDs are generated through a shared reference, so a provider can be shared between threads without a lock.
+pub struct IdProvider<T, S = Sequential> {
+    scheme: S,
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T> IdProvider<T> {
+    pub fn new() -> IdProvider<T> {
+        IdProvider::starting_at(0)
+    }
+
+    /// Continues after the IDs generated before, e.g. when entities are restored.
+    pub fn starting_at(next_id: u32) -> IdProvider<T> {
+        IdProvider::with_scheme(Sequential::starting_at(next_id))
+    }
+}
+
+impl<T, S: IdScheme> IdProvider<T, S> {
+    pub fn with_scheme(scheme: S) -> IdProvider<T, S> {
+        IdProvider {
+            scheme,
+            entity: PhantomData,
+        }
+    }
+
+    /// Generates the next ID.
+    ///
+    /// # Panics
+    ///
+    /// If all IDs were handed out, see `try_generate_next`.
+    pub fn generate_next(&self) -> Id<T, S::Value> {
+        self.try_generate_next()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Generates the next ID, unless all were handed out.
+    pub fn try_generate_next(&self) -> Result<Id<T, S::Value>, IdsExhaustedError> {
+        self.scheme.next_value().map(Id::from_value)
+    }
+}
+
+// Implemented by hand, deriving would require `T` to implement the traits as well
+
+impl<T, S: Clone> Clone for IdProvider<T, S> {
+    fn clone(&self) -> IdProvider<T, S> {
+        IdProvider {
+            scheme: self.scheme.clone(),
+            entity: PhantomData,
+        }
+    }
+}
+
+impl<T, S: fmt::Debug> fmt::Debug for IdProvider<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IdProvider")
+            .field("scheme", &self.scheme)
+            .finish()
+    }
 }
 
-impl IdProvider {
-    pub fn new() -> IdProvider {
-        IdProvider { next_id: 0 }
+impl<T, S: Default> Default for IdProvider<T, S> {
+    fn default() -> IdProvider<T, S> {
+        IdProvider {
+            scheme: S::default(),
+            entity: PhantomData,
+        }
     }
+}
 
-    pub fn generate_next(&mut self) -> Id {
-        let next = self.next_id;
-        self.next_id = next + 1;
-        Id::new(next)
+impl<T, S: PartialEq> PartialEq for IdProvider<T, S> {
+    fn eq(&self, other: &IdProvider<T, S>) -> bool {
+        self.scheme == other.scheme
+    }
+}
+
+impl<T, S: Eq> Eq for IdProvider<T, S> {}
+
+impl<T, S: Hash> Hash for IdProvider<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.scheme.hash(state);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
 
     #[test]
     fn first_id_is_zero() {
         // Given:
-        let mut id_provider = IdProvider::new();
+        let id_provider = IdProvider::<()>::new();
 
         // When:
         let id = id_provider.generate_next();
@@ -36,7 +216,7 @@ mod tests {
     #[test]
     fn second_id_is_one() {
         // Given:
-        let mut id_provider = IdProvider::new();
+        let id_provider = IdProvider::<()>::new();
         id_provider.generate_next();
 
         // When:
@@ -45,4 +225,58 @@ mod tests {
         // Then:
         assert_eq!(id, Id::new(1));
     }
+
+    #[test]
+    fn ids_generated_on_threads_are_unique_and_consecutive() {
+        // Given:
+        let id_provider = Arc::new(IdProvider::<()>::new());
+
+        // When:
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let id_provider = id_provider.clone();
+                thread::spawn(move || {
+                    (0..1000)
+                        .map(|_| id_provider.generate_next().get_value())
+                        .collect::<Vec<u32>>()
+                })
+            })
+            .collect();
+        let ids: HashSet<u32> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+
+        // Then:
+        assert_eq!(ids, (0..4000).collect());
+        assert_eq!(id_provider.generate_next(), Id::new(4000));
+    }
+
+    #[test]
+    fn exhausted_ids_are_not_repeated() {
+        // Given:
+        let id_provider = IdProvider::<()>::starting_at(u32::MAX - 1);
+
+        // When:
+        let last = id_provider.try_generate_next();
+        let exhausted = id_provider.try_generate_next();
+
+        // Then:
+        assert_eq!(last, Ok(Id::new(u32::MAX - 1)));
+        assert_eq!(exhausted, Err(IdsExhaustedError));
+        assert_eq!(id_provider.try_generate_next(), Err(IdsExhaustedError));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn random_uuids_are_generated() {
+        // Given:
+        let id_provider = IdProvider::<(), RandomUuid>::with_scheme(RandomUuid);
+
+        // When:
+        let ids: HashSet<Id<(), Uuid>> = (0..100).map(|_| id_provider.generate_next()).collect();
+
+        // Then:
+        assert_eq!(ids.len(), 100);
+    }
 }
@@ -0,0 +1,235 @@
+use crate::util::money::Money;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FormatError {
+    UnknownCurrency(String),
+    UnknownLocale(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use FormatError::*;
+        match self {
+            UnknownCurrency(code) => write!(f, "currency {} is not supported", code),
+            UnknownLocale(code) => write!(f, "locale {} is not supported", code),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Currency {
+    #[default]
+    Eur,
+    Chf,
+}
+
+impl Currency {
+    /// ISO 4217 code, e.g. "EUR"
+    pub fn get_code(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Chf => "CHF",
+        }
+    }
+
+    pub fn get_symbol(&self) -> &'static str {
+        match self {
+            Currency::Eur => "€",
+            Currency::Chf => "CHF",
+        }
+    }
+
+    /// Locale in which amounts of the currency are written unless configured otherwise.
+    pub fn get_locale(&self) -> Locale {
+        match self {
+            Currency::Eur => Locale::DeDe,
+            Currency::Chf => Locale::DeCh,
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = FormatError;
+
+    fn from_str(code: &str) -> Result<Currency, FormatError> {
+        match code.trim().to_uppercase().as_str() {
+            "EUR" => Ok(Currency::Eur),
+            "CHF" => Ok(Currency::Chf),
+            _ => Err(FormatError::UnknownCurrency(String::from(code))),
+        }
+    }
+}
+
+/// Language and region deciding how numbers and amounts are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// Germany: "5,50€"
+    #[default]
+    DeDe,
+    /// Switzerland: "CHF 5.50"
+    DeCh,
+    /// English: "€5.50"
+    En,
+}
+
+impl Locale {
+    /// BCP 47 tag, e.g. "de-DE"
+    pub fn get_tag(&self) -> &'static str {
+        match self {
+            Locale::DeDe => "de-DE",
+            Locale::DeCh => "de-CH",
+            Locale::En => "en",
+        }
+    }
+
+    pub fn get_decimal_separator(&self) -> char {
+        match self {
+            Locale::DeDe => ',',
+            Locale::DeCh | Locale::En => '.',
+        }
+    }
+
+    /// Separator between groups of thousands, e.g. the dot in "1.234,56"
+    pub fn get_grouping_separator(&self) -> char {
+        match self {
+            Locale::DeDe => '.',
+            Locale::DeCh => '\'',
+            Locale::En => ',',
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = FormatError;
+
+    fn from_str(tag: &str) -> Result<Locale, FormatError> {
+        match tag.trim().to_lowercase().replace('_', "-").as_str() {
+            "de" | "de-de" => Ok(Locale::DeDe),
+            "de-ch" => Ok(Locale::DeCh),
+            "en" | "en-gb" | "en-us" => Ok(Locale::En),
+            _ => Err(FormatError::UnknownLocale(String::from(tag))),
+        }
+    }
+}
+
+/// How money is written for the users of an order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MoneyFormat {
+    currency: Currency,
+    locale: Locale,
+}
+
+impl MoneyFormat {
+    pub fn new(currency: Currency, locale: Locale) -> MoneyFormat {
+        MoneyFormat { currency, locale }
+    }
+
+    /// Format usual for the currency of the money, euros for money without currency.
+    pub fn usual_for(money: Money) -> MoneyFormat {
+        let currency = money.get_currency().unwrap_or_default();
+        MoneyFormat::new(currency, currency.get_locale())
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Writes the amount in the currency as usual in the locale.
+    /// ```
+    /// # use rusty_pizza_server::util::locale::{Currency, Locale, MoneyFormat};
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let format = MoneyFormat::new(Currency::Chf, Locale::DeCh);
+    /// assert_eq!(format.format(Money::new(5, 50)), "CHF 5.50");
+    /// ```
+    pub fn format(&self, money: Money) -> String {
+        let amount = format!(
+            "{}{}{:02}",
+            money.get_euros(),
+            self.locale.get_decimal_separator(),
+            money.get_cents()
+        );
+        let symbol = self.currency.get_symbol();
+        // Codes like "CHF" need a space, symbols like "€" are attached
+        let space = if symbol.chars().all(char::is_alphabetic) {
+            " "
+        } else {
+            ""
+        };
+        match self.locale {
+            Locale::DeDe => format!("{}{}{}", amount, space, symbol),
+            Locale::DeCh => format!("{} {}", symbol, amount),
+            Locale::En => format!("{}{}{}", symbol, space, amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        currency,
+        locale,
+        expected,
+        case(Currency::Eur, Locale::DeDe, "1234,05€"),
+        case(Currency::Chf, Locale::DeDe, "1234,05 CHF"),
+        case(Currency::Chf, Locale::DeCh, "CHF 1234.05"),
+        case(Currency::Eur, Locale::DeCh, "€ 1234.05"),
+        case(Currency::Eur, Locale::En, "€1234.05"),
+        case(Currency::Chf, Locale::En, "CHF 1234.05")
+    )]
+    fn money_is_formatted_for_locale(currency: Currency, locale: Locale, expected: &str) {
+        // Given:
+        let format = MoneyFormat::new(currency, locale);
+
+        // When:
+        let text = format.format(Money::new(1234, 5));
+
+        // Then:
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn default_format_matches_money_display() {
+        // Given:
+        let money = Money::new(15, 5);
+
+        // When:
+        let text = MoneyFormat::default().format(money);
+
+        // Then:
+        assert_eq!(text, money.to_string());
+    }
+
+    #[rstest(
+        code,
+        expected,
+        case("chf", Ok(Currency::Chf)),
+        case(" EUR", Ok(Currency::Eur)),
+        case("USD", Err(FormatError::UnknownCurrency(String::from("USD"))))
+    )]
+    fn currency_is_parsed_from_code(code: &str, expected: Result<Currency, FormatError>) {
+        assert_eq!(code.parse::<Currency>(), expected);
+    }
+
+    #[rstest(
+        tag,
+        expected,
+        case("de_CH", Ok(Locale::DeCh)),
+        case("de", Ok(Locale::DeDe)),
+        case("fr-CH", Err(FormatError::UnknownLocale(String::from("fr-CH"))))
+    )]
+    fn locale_is_parsed_from_tag(tag: &str, expected: Result<Locale, FormatError>) {
+        assert_eq!(tag.parse::<Locale>(), expected);
+    }
+}
@@ -1,4 +1,11 @@
+pub mod cache;
+pub mod clock;
+pub mod decimal;
 pub mod errors;
+pub mod history;
+pub mod http;
 pub mod id;
 pub mod id_provider;
+pub mod locale;
 pub mod money;
+pub mod short_code;
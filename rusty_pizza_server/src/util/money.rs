@@ -1,9 +1,115 @@
+use crate::util::locale::{Currency, MoneyFormat};
+use std::convert::TryFrom;
+use std::error::Error;
 use std::fmt::{self, Display, Formatter};
-use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Sub, SubAssign};
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Hash, Copy, Clone)]
+/// Amounts in different currencies were added or subtracted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurrencyMismatchError {
+    left: Currency,
+    right: Currency,
+}
+
+impl CurrencyMismatchError {
+    pub fn get_left(&self) -> Currency {
+        self.left
+    }
+
+    pub fn get_right(&self) -> Currency {
+        self.right
+    }
+}
+
+impl Display for CurrencyMismatchError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cannot combine {} and {}",
+            self.left.get_code(),
+            self.right.get_code()
+        )
+    }
+}
+
+impl Error for CurrencyMismatchError {}
+
+/// Amounts couldn't be subtracted from each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubtractionError {
+    CurrencyMismatch(CurrencyMismatchError),
+    /// More was subtracted than there was, as `Money` can't be negative
+    Negative,
+}
+
+impl Display for SubtractionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SubtractionError::CurrencyMismatch(error) => write!(f, "{}", error),
+            SubtractionError::Negative => write!(f, "money cannot be negative"),
+        }
+    }
+}
+
+impl Error for SubtractionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SubtractionError::CurrencyMismatch(error) => Some(error),
+            SubtractionError::Negative => None,
+        }
+    }
+}
+
+impl From<CurrencyMismatchError> for SubtractionError {
+    fn from(error: CurrencyMismatchError) -> Self {
+        SubtractionError::CurrencyMismatch(error)
+    }
+}
+
+/// How fractions of a cent are rounded when an amount is scaled, e.g. for a 19% VAT.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Half a cent and more is rounded up, as usual on receipts
+    #[default]
+    HalfUp,
+    /// Fractions are cut off
+    Down,
+    /// Half a cent is rounded to the even cent, so rounding many amounts doesn't drift upwards
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// Divides rounding the result as the mode says.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denominator` is zero.
+    pub fn divide(self, numerator: u64, denominator: u64) -> u64 {
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        let round_up = match self {
+            RoundingMode::Down => false,
+            RoundingMode::HalfUp => remainder * 2 >= denominator,
+            RoundingMode::HalfEven => {
+                remainder * 2 > denominator || (remainder * 2 == denominator && quotient % 2 == 1)
+            }
+        };
+        if round_up {
+            quotient + 1
+        } else {
+            quotient
+        }
+    }
+}
+
+/// An amount of money, optionally in a specific currency.
+///
+/// Amounts without currency, like `Money::zero()`, adopt the currency of the amounts they are combined with,
+/// so existing code working in euros is unaffected.
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Copy, Clone)]
 pub struct Money {
     cents: u32,
+    currency: Option<Currency>,
 }
 
 impl Money {
@@ -11,18 +117,154 @@ impl Money {
     ///
     /// Note that this method does not limit the amount of `cents` to `99`. You can happily pass any amount:
     /// ```
+    /// # use rusty_pizza_server::util::money::Money;
     /// let money = Money::new(1, 205);
     /// assert_eq!(money, Money::new(3, 5));
     /// ```
     pub fn new(euros: u32, cents: u8) -> Money {
+        Money::from_cents(euros * 100 + cents as u32)
+    }
+
+    /// Creates a new `Money` instance from a total amount of `cents`.
+    pub fn from_cents(cents: u32) -> Money {
         Money {
-            cents: euros * 100 + cents as u32,
+            cents,
+            currency: None,
         }
     }
 
     /// Creates a new `Money` instance from 0 `euros` and 0 `cents` <==> 0,00€.
     pub fn zero() -> Money {
-        Money { cents: 0 }
+        Money::from_cents(0)
+    }
+
+    /// The same amount in the given currency.
+    pub fn with_currency(self, currency: Currency) -> Money {
+        Money {
+            currency: Some(currency),
+            ..self
+        }
+    }
+
+    /// `None` if the amount was created without currency.
+    pub fn get_currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    /// Adds the amounts, failing if they are in different currencies.
+    /// ```
+    /// # use rusty_pizza_server::util::locale::Currency;
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let francs = Money::new(5, 0).with_currency(Currency::Chf);
+    /// assert_eq!(francs.try_add(Money::new(1, 0)), Ok(Money::new(6, 0).with_currency(Currency::Chf)));
+    /// assert!(francs.try_add(Money::new(1, 0).with_currency(Currency::Eur)).is_err());
+    /// ```
+    pub fn try_add(self, other: Money) -> Result<Money, CurrencyMismatchError> {
+        Ok(Money {
+            cents: self.cents + other.cents,
+            currency: self.common_currency(other)?,
+        })
+    }
+
+    /// Adds up all amounts, failing if any two are in different currencies.
+    /// ```
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let amounts = vec![Money::new(5, 0), Money::new(2, 50)];
+    /// assert_eq!(Money::try_sum(amounts), Ok(Money::new(7, 50)));
+    /// ```
+    pub fn try_sum(
+        amounts: impl IntoIterator<Item = Money>,
+    ) -> Result<Money, CurrencyMismatchError> {
+        amounts
+            .into_iter()
+            .try_fold(Money::zero(), |total, amount| total.try_add(amount))
+    }
+
+    /// Subtracts the amounts, failing if they are in different currencies or `other` is the larger one.
+    /// ```
+    /// # use rusty_pizza_server::util::money::{Money, SubtractionError};
+    /// assert_eq!(Money::new(5, 0).try_sub(Money::new(1, 50)), Ok(Money::new(3, 50)));
+    /// assert_eq!(Money::new(1, 50).try_sub(Money::new(5, 0)), Err(SubtractionError::Negative));
+    /// ```
+    pub fn try_sub(self, other: Money) -> Result<Money, SubtractionError> {
+        let currency = self.common_currency(other)?;
+        let cents = self
+            .cents
+            .checked_sub(other.cents)
+            .ok_or(SubtractionError::Negative)?;
+        Ok(Money { cents, currency })
+    }
+
+    /// Whether both are the same amount, an amount without currency matching any currency.
+    ///
+    /// Unlike `==`, this doesn't tell apart amounts only differing in whether their currency is known:
+    /// ```
+    /// # use rusty_pizza_server::util::locale::Currency;
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let euros = Money::new(5, 0).with_currency(Currency::Eur);
+    /// assert!(euros.matches(Money::new(5, 0)));
+    /// assert!(!euros.matches(Money::new(5, 0).with_currency(Currency::Chf)));
+    /// ```
+    pub fn matches(self, other: Money) -> bool {
+        self.cents == other.cents && self.common_currency(other).is_ok()
+    }
+
+    /// Whether the amount is nothing, in whatever currency.
+    pub fn is_zero(self) -> bool {
+        self.cents == 0
+    }
+
+    /// Rounds up to the next multiple of `step`, e.g. to full euros. A zero step leaves the amount as it is.
+    /// ```
+    /// # use rusty_pizza_server::util::money::Money;
+    /// assert_eq!(Money::new(7, 20).round_up_to(Money::new(0, 50)), Money::new(7, 50));
+    /// ```
+    pub fn round_up_to(self, step: Money) -> Money {
+        let step = step.get_total_cents();
+        if step == 0 {
+            return self;
+        }
+        self.with_cents(self.cents.div_ceil(step) * step)
+    }
+
+    /// The given percentage of the amount, with fractions of a cent rounded as the mode says.
+    /// ```
+    /// # use rusty_pizza_server::util::money::{Money, RoundingMode};
+    /// assert_eq!(Money::new(7, 50).percent(19, RoundingMode::HalfUp), Money::new(1, 43));
+    /// assert_eq!(Money::new(7, 50).percent(19, RoundingMode::Down), Money::new(1, 42));
+    /// ```
+    pub fn percent(self, percent: u32, rounding: RoundingMode) -> Money {
+        self.scale(percent, 100, rounding)
+    }
+
+    /// Multiplies the amount by `numerator / denominator`, with fractions of a cent rounded as the mode says.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denominator` is zero or the result doesn't fit into the cents of a `Money`.
+    pub fn scale(self, numerator: u32, denominator: u32, rounding: RoundingMode) -> Money {
+        assert!(
+            denominator > 0,
+            "Money cannot be scaled by a zero denominator"
+        );
+        let cents = rounding.divide(
+            u64::from(self.cents) * u64::from(numerator),
+            u64::from(denominator),
+        );
+        self.with_cents(u32::try_from(cents).expect("scaled money is too large"))
+    }
+
+    fn common_currency(self, other: Money) -> Result<Option<Currency>, CurrencyMismatchError> {
+        match (self.currency, other.currency) {
+            (Some(left), Some(right)) if left != right => {
+                Err(CurrencyMismatchError { left, right })
+            }
+            (left, right) => Ok(left.or(right)),
+        }
+    }
+
+    fn with_cents(self, cents: u32) -> Money {
+        Money { cents, ..self }
     }
 
     pub fn get_euros(&self) -> u32 {
@@ -36,41 +278,86 @@ impl Money {
     pub fn get_total_cents(&self) -> u32 {
         self.cents
     }
+
+    /// Splits the money into `parts` amounts that differ by at most one cent and sum up to the original.
+    ///
+    /// The leftover cents go to the first amounts:
+    /// ```
+    /// # use rusty_pizza_server::util::money::Money;
+    /// let shares = Money::new(10, 0).split(3);
+    /// assert_eq!(shares, vec![Money::new(3, 34), Money::new(3, 33), Money::new(3, 33)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parts` is zero.
+    pub fn split(self, parts: u32) -> Vec<Money> {
+        assert!(parts > 0, "Money cannot be split into zero parts");
+        let division = self / parts;
+        let leftover = division.get_remainder().get_total_cents();
+        (0..parts)
+            .map(|part| {
+                if part < leftover {
+                    division.get_quotient() + Money::from_cents(1)
+                } else {
+                    division.get_quotient()
+                }
+            })
+            .collect()
+    }
 }
 
+/// Result of dividing `Money`, which keeps the cents that could not be divided evenly.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct DividedMoney {
+    quotient: Money,
+    remainder: Money,
+}
+
+impl DividedMoney {
+    /// The amount each part gets
+    pub fn get_quotient(&self) -> Money {
+        self.quotient
+    }
+
+    /// The cents left over after giving every part the quotient
+    pub fn get_remainder(&self) -> Money {
+        self.remainder
+    }
+}
+
+/// # Panics
+///
+/// Panics if the amounts are in different currencies, use `Money::try_add` where this can happen.
 impl Add for Money {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        Self {
-            cents: self.cents + other.cents,
-        }
+        self.try_add(other).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl AddAssign for Money {
     fn add_assign(&mut self, other: Self) {
-        *self = Self {
-            cents: self.cents + other.cents,
-        }
+        *self = *self + other;
     }
 }
 
+/// # Panics
+///
+/// Panics if the amounts are in different currencies or `other` is the larger one, use `Money::try_sub` where
+/// this can happen.
 impl Sub for Money {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        Self {
-            cents: self.cents - other.cents,
-        }
+        self.try_sub(other).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl SubAssign for Money {
     fn sub_assign(&mut self, other: Self) {
-        *self = Self {
-            cents: self.cents - other.cents,
-        }
+        *self = *self - other;
     }
 }
 
@@ -78,9 +365,7 @@ impl Mul<u8> for Money {
     type Output = Self;
 
     fn mul(self, other: u8) -> Self {
-        Self {
-            cents: self.cents * other as u32,
-        }
+        self.with_cents(self.cents * other as u32)
     }
 }
 
@@ -94,9 +379,7 @@ impl Mul<Money> for u8 {
 
 impl MulAssign<u8> for Money {
     fn mul_assign(&mut self, other: u8) {
-        *self = Self {
-            cents: self.cents * other as u32,
-        }
+        *self = *self * other;
     }
 }
 
@@ -104,9 +387,7 @@ impl Mul<u16> for Money {
     type Output = Self;
 
     fn mul(self, other: u16) -> Self {
-        Self {
-            cents: self.cents * other as u32,
-        }
+        self.with_cents(self.cents * other as u32)
     }
 }
 
@@ -120,9 +401,7 @@ impl Mul<Money> for u16 {
 
 impl MulAssign<u16> for Money {
     fn mul_assign(&mut self, other: u16) {
-        *self = Self {
-            cents: self.cents * other as u32,
-        }
+        *self = *self * other;
     }
 }
 
@@ -130,9 +409,7 @@ impl Mul<u32> for Money {
     type Output = Self;
 
     fn mul(self, other: u32) -> Self {
-        Self {
-            cents: self.cents * other,
-        }
+        self.with_cents(self.cents * other)
     }
 }
 
@@ -146,15 +423,25 @@ impl Mul<Money> for u32 {
 
 impl MulAssign<u32> for Money {
     fn mul_assign(&mut self, other: u32) {
-        *self = Self {
-            cents: self.cents * other,
+        *self = *self * other;
+    }
+}
+
+impl Div<u32> for Money {
+    type Output = DividedMoney;
+
+    fn div(self, other: u32) -> DividedMoney {
+        DividedMoney {
+            quotient: self.with_cents(self.cents / other),
+            remainder: self.with_cents(self.cents % other),
         }
     }
 }
 
+/// Written as usual for the currency, amounts without currency in euros.
 impl Display for Money {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{},{}€", self.get_euros(), self.get_cents())
+        write!(f, "{}", MoneyFormat::usual_for(*self).format(*self))
     }
 }
 
@@ -170,12 +457,24 @@ mod tests {
         let money = Money::new(euros, cents);
 
         // Then:
-        assert_eq!(money, Money { cents: expected });
+        assert_eq!(money, Money::from_cents(expected));
     }
 
-    #[rstest(addend1, addend2, sum,
-        case(Money::new(7, 20), Money::new(5, 50), Money { cents: 1270 }),
-        case(Money::new(8, 21), Money::new(4, 55), Money { cents: 1276 }),
+    #[test]
+    fn money_can_be_created_from_cents() {
+        // When:
+        let money = Money::from_cents(1205);
+
+        // Then:
+        assert_eq!(money, Money::new(12, 5));
+    }
+
+    #[rstest(
+        addend1,
+        addend2,
+        sum,
+        case(Money::new(7, 20), Money::new(5, 50), Money::from_cents(1270)),
+        case(Money::new(8, 21), Money::new(4, 55), Money::from_cents(1276))
     )]
     fn money_can_be_summed(addend1: Money, addend2: Money, sum: Money) {
         // When:
@@ -185,9 +484,12 @@ mod tests {
         assert_eq!(result, sum);
     }
 
-    #[rstest(minuend, subtrahent, difference,
-        case(Money::new(7, 20), Money::new(5, 50), Money { cents: 170 }),
-        case(Money::new(7, 20), Money::new(5, 55), Money { cents: 165 }),
+    #[rstest(
+        minuend,
+        subtrahent,
+        difference,
+        case(Money::new(7, 20), Money::new(5, 50), Money::from_cents(170)),
+        case(Money::new(7, 20), Money::new(5, 55), Money::from_cents(165))
     )]
     fn money_can_be_subtracted(minuend: Money, subtrahent: Money, difference: Money) {
         // When:
@@ -204,9 +506,12 @@ mod tests {
         let _ = Money::new(7, 20) - Money::new(7, 40);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2u8, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3u8, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2u8, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3u8, Money::from_cents(615))
     )]
     fn money_can_be_multiplied_with_u8(money: Money, factor: u8, product: Money) {
         // When:
@@ -216,9 +521,12 @@ mod tests {
         assert_eq!(result, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2u8, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3u8, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2u8, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3u8, Money::from_cents(615))
     )]
     fn u8_can_be_multiplied_with_money(money: Money, factor: u8, product: Money) {
         // When:
@@ -228,9 +536,12 @@ mod tests {
         assert_eq!(result, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2u16, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3u16, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2u16, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3u16, Money::from_cents(615))
     )]
     fn money_can_be_multiplied_with_u16(money: Money, factor: u16, product: Money) {
         // When:
@@ -240,9 +551,12 @@ mod tests {
         assert_eq!(result, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2u16, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3u16, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2u16, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3u16, Money::from_cents(615))
     )]
     fn u16_can_be_multiplied_with_money(money: Money, factor: u16, product: Money) {
         // When:
@@ -252,9 +566,12 @@ mod tests {
         assert_eq!(result, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3, Money::from_cents(615))
     )]
     fn money_can_be_multiplied_with_u32(money: Money, factor: u32, product: Money) {
         // When:
@@ -264,9 +581,12 @@ mod tests {
         assert_eq!(result, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3, Money::from_cents(615))
     )]
     fn u32_can_be_multiplied_with_money(money: Money, factor: u32, product: Money) {
         // When:
@@ -286,9 +606,18 @@ mod tests {
         let result2 = money + Money::new(1, 0);
 
         // Then:
-        assert_eq!(money, Money { cents: 200 });
-        assert_eq!(result1, Money { cents: 600 });
-        assert_eq!(result2, Money { cents: 300 });
+        assert_eq!(money, Money::from_cents(200));
+        assert_eq!(result1, Money::from_cents(600));
+        assert_eq!(result2, Money::from_cents(300));
+    }
+
+    #[test]
+    fn money_prints_cents_with_two_digits() {
+        // Given:
+        let money = Money::new(61, 5);
+
+        // Then:
+        assert_eq!(money.to_string(), "61,05€");
     }
 
     #[test]
@@ -353,8 +682,8 @@ mod tests {
         addend1,
         addend2,
         sum,
-        case(Money::new(7, 20), Money::new(5, 50), Money { cents: 1270 }),
-        case(Money::new(8, 21), Money::new(4, 55), Money { cents: 1276 }),
+        case(Money::new(7, 20), Money::new(5, 50), Money::from_cents(1270)),
+        case(Money::new(8, 21), Money::new(4, 55), Money::from_cents(1276))
     )]
     fn money_can_be_add_assigned(mut addend1: Money, addend2: Money, sum: Money) {
         // When:
@@ -364,9 +693,12 @@ mod tests {
         assert_eq!(addend1, sum);
     }
 
-    #[rstest(minuend, subtrahent, difference,
-        case(Money::new(7, 20), Money::new(5, 50), Money { cents: 170 }),
-        case(Money::new(7, 20), Money::new(5, 55), Money { cents: 165 }),
+    #[rstest(
+        minuend,
+        subtrahent,
+        difference,
+        case(Money::new(7, 20), Money::new(5, 50), Money::from_cents(170)),
+        case(Money::new(7, 20), Money::new(5, 55), Money::from_cents(165))
     )]
     fn money_can_be_sub_assigned(mut minuend: Money, subtrahent: Money, difference: Money) {
         // When:
@@ -376,9 +708,12 @@ mod tests {
         assert_eq!(minuend, difference)
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2u8, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3u8, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2u8, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3u8, Money::from_cents(615))
     )]
     fn money_can_be_mul_assigned_with_u8(mut money: Money, factor: u8, product: Money) {
         // When:
@@ -388,9 +723,12 @@ mod tests {
         assert_eq!(money, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2u16, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3u16, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2u16, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3u16, Money::from_cents(615))
     )]
     fn money_can_be_mul_assigned_with_u16(mut money: Money, factor: u16, product: Money) {
         // When:
@@ -400,9 +738,12 @@ mod tests {
         assert_eq!(money, product);
     }
 
-    #[rstest(money, factor, product,
-        case(Money::new(5, 0), 2, Money { cents: 1000 }),
-        case(Money::new(2, 5), 3, Money { cents: 615 }),
+    #[rstest(
+        money,
+        factor,
+        product,
+        case(Money::new(5, 0), 2, Money::from_cents(1000)),
+        case(Money::new(2, 5), 3, Money::from_cents(615))
     )]
     fn money_can_be_mul_assigned_with_u32(mut money: Money, factor: u32, product: Money) {
         // When:
@@ -411,4 +752,209 @@ mod tests {
         // Then:
         assert_eq!(money, product);
     }
+
+    #[rstest(
+        money,
+        divisor,
+        quotient,
+        remainder,
+        case(Money::new(10, 0), 3, Money::new(3, 33), Money::new(0, 1)),
+        case(Money::new(9, 0), 3, Money::new(3, 0), Money::zero()),
+        case(Money::new(0, 2), 3, Money::zero(), Money::new(0, 2))
+    )]
+    fn money_can_be_divided_by_u32(money: Money, divisor: u32, quotient: Money, remainder: Money) {
+        // When:
+        let result = money / divisor;
+
+        // Then:
+        assert_eq!(result.get_quotient(), quotient);
+        assert_eq!(result.get_remainder(), remainder);
+    }
+
+    #[rstest(money, parts, expected,
+        case(Money::new(10, 0), 3, vec![Money::new(3, 34), Money::new(3, 33), Money::new(3, 33)]),
+        case(Money::new(0, 5), 4, vec![Money::new(0, 2), Money::new(0, 1), Money::new(0, 1), Money::new(0, 1)]),
+        case(Money::new(0, 1), 2, vec![Money::new(0, 1), Money::zero()]),
+        case(Money::new(5, 50), 1, vec![Money::new(5, 50)]),
+    )]
+    fn money_can_be_split(money: Money, parts: u32, expected: Vec<Money>) {
+        // When:
+        let shares = money.split(parts);
+
+        // Then:
+        let mut sum = Money::zero();
+        for share in &shares {
+            sum += *share;
+        }
+        assert_eq!(shares, expected);
+        assert_eq!(sum, money);
+    }
+
+    #[rstest(
+        addend1,
+        addend2,
+        expected,
+        case(
+            Money::new(1, 0).with_currency(Currency::Chf),
+            Money::new(2, 0),
+            Ok(Money::new(3, 0).with_currency(Currency::Chf))
+        ),
+        case(
+            Money::zero(),
+            Money::new(2, 0).with_currency(Currency::Eur),
+            Ok(Money::new(2, 0).with_currency(Currency::Eur))
+        ),
+        case(
+            Money::new(1, 0).with_currency(Currency::Chf),
+            Money::new(2, 0).with_currency(Currency::Eur),
+            Err(CurrencyMismatchError { left: Currency::Chf, right: Currency::Eur })
+        )
+    )]
+    fn only_money_in_same_currency_can_be_added(
+        addend1: Money,
+        addend2: Money,
+        expected: Result<Money, CurrencyMismatchError>,
+    ) {
+        // When:
+        let result = addend1.try_add(addend2);
+
+        // Then:
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot combine CHF and EUR")]
+    fn money_in_different_currencies_cannot_be_subtracted_with_operator() {
+        // When:
+        let _ = Money::new(5, 0).with_currency(Currency::Chf)
+            - Money::new(1, 0).with_currency(Currency::Eur);
+    }
+
+    #[rstest(
+        minuend,
+        subtrahend,
+        expected,
+        case(Money::new(5, 0), Money::new(1, 50), Ok(Money::new(3, 50))),
+        case(Money::new(1, 50), Money::new(1, 50), Ok(Money::zero())),
+        case(
+            Money::new(1, 50),
+            Money::new(5, 0),
+            Err(SubtractionError::Negative)
+        ),
+        case(
+            Money::new(5, 0).with_currency(Currency::Chf),
+            Money::new(1, 0).with_currency(Currency::Eur),
+            Err(SubtractionError::CurrencyMismatch(CurrencyMismatchError {
+                left: Currency::Chf,
+                right: Currency::Eur
+            }))
+        )
+    )]
+    fn only_smaller_money_in_same_currency_can_be_subtracted(
+        minuend: Money,
+        subtrahend: Money,
+        expected: Result<Money, SubtractionError>,
+    ) {
+        // When:
+        let result = minuend.try_sub(subtrahend);
+
+        // Then:
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "money cannot be negative")]
+    fn larger_money_cannot_be_subtracted_with_operator() {
+        // When:
+        let _ = Money::new(1, 0) - Money::new(5, 0);
+    }
+
+    #[test]
+    fn amounts_in_different_currencies_are_not_summed() {
+        // Given:
+        let amounts = vec![
+            Money::new(1, 0),
+            Money::new(2, 0).with_currency(Currency::Chf),
+            Money::new(3, 0).with_currency(Currency::Eur),
+        ];
+
+        // When:
+        let sum = Money::try_sum(amounts);
+
+        // Then:
+        assert_eq!(
+            sum,
+            Err(CurrencyMismatchError {
+                left: Currency::Chf,
+                right: Currency::Eur
+            })
+        );
+    }
+
+    #[test]
+    fn currency_is_kept_by_multiplication_and_division() {
+        // Given:
+        let money = Money::new(10, 0).with_currency(Currency::Chf);
+
+        // When:
+        let product = money * 2u32;
+        let division = money / 3;
+
+        // Then:
+        assert_eq!(product.get_currency(), Some(Currency::Chf));
+        assert_eq!(division.get_quotient().get_currency(), Some(Currency::Chf));
+        assert_eq!(division.get_remainder().get_currency(), Some(Currency::Chf));
+    }
+
+    #[rstest(
+        money,
+        expected,
+        case(Money::new(5, 50), "5,50€"),
+        case(Money::new(5, 50).with_currency(Currency::Eur), "5,50€"),
+        case(Money::new(5, 50).with_currency(Currency::Chf), "CHF 5.50")
+    )]
+    fn money_is_displayed_in_its_currency(money: Money, expected: &str) {
+        assert_eq!(money.to_string(), expected);
+    }
+
+    #[rstest(
+        money,
+        percent,
+        rounding,
+        expected,
+        case(Money::new(0, 25), 10, RoundingMode::HalfUp, Money::new(0, 3)),
+        case(Money::new(0, 25), 10, RoundingMode::Down, Money::new(0, 2)),
+        case(Money::new(0, 25), 10, RoundingMode::HalfEven, Money::new(0, 2)),
+        case(Money::new(0, 35), 10, RoundingMode::HalfEven, Money::new(0, 4)),
+        case(Money::new(0, 36), 10, RoundingMode::HalfEven, Money::new(0, 4)),
+        case(Money::new(0, 34), 10, RoundingMode::HalfUp, Money::new(0, 3)),
+        case(Money::new(20, 0), 150, RoundingMode::Down, Money::new(30, 0))
+    )]
+    fn percentage_is_rounded_as_requested(
+        money: Money,
+        percent: u32,
+        rounding: RoundingMode,
+        expected: Money,
+    ) {
+        assert_eq!(money.percent(percent, rounding), expected);
+    }
+
+    #[test]
+    fn currency_is_kept_by_scaling() {
+        // Given:
+        let money = Money::new(10, 0).with_currency(Currency::Chf);
+
+        // When:
+        let scaled = money.scale(1, 3, RoundingMode::HalfUp);
+
+        // Then:
+        assert_eq!(scaled, Money::new(3, 33).with_currency(Currency::Chf));
+    }
+
+    #[test]
+    #[should_panic]
+    fn money_cannot_be_split_into_zero_parts() {
+        // When:
+        Money::new(5, 50).split(0);
+    }
 }
@@ -0,0 +1,220 @@
+use crate::util::id::Id;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Crockford's base32, which leaves out letters easily mistaken for digits.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Digits of the ID at least, so codes of the first orders don't look odd.
+const MIN_DIGITS: usize = 3;
+
+/// Entities which get short codes, e.g. "ORD-4W8P" for order 5000.
+pub trait ShortCodePrefix {
+    /// Upper case letters telling what the code belongs to
+    const PREFIX: &'static str;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShortCodeError {
+    /// The code belongs to another kind of entity, or has no known prefix at all
+    WrongPrefix(String),
+    InvalidCharacter(char),
+    /// Typo, the check character doesn't match
+    WrongCheck,
+    /// Too short, or padded with zeros an ID never gets, or too large for an ID
+    NotCanonical,
+}
+
+impl fmt::Display for ShortCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ShortCodeError::*;
+        match self {
+            WrongPrefix(code) => write!(f, "{} has an unknown prefix", code),
+            InvalidCharacter(character) => write!(f, "{} is not allowed in codes", character),
+            WrongCheck => write!(f, "code contains a typo"),
+            NotCanonical => write!(f, "code does not belong to any ID"),
+        }
+    }
+}
+
+impl Error for ShortCodeError {}
+
+/// Code like "ORD-4W8P": the prefix of the entity, the ID in base32 and a check character catching typos.
+pub fn short_code<T: ShortCodePrefix>(id: &Id<T>) -> String {
+    let mut digits = Vec::new();
+    let mut value = id.get_value();
+    while value > 0 || digits.len() < MIN_DIGITS {
+        digits.push((value % 32) as u8);
+        value /= 32;
+    }
+    digits.reverse();
+    digits.push(check(&digits));
+    let encoded: String = digits
+        .iter()
+        .map(|digit| char::from(ALPHABET[usize::from(*digit)]))
+        .collect();
+    format!("{}-{}", T::PREFIX, encoded)
+}
+
+/// Parses a code of `short_code`, ignoring case and the dash and accepting O for 0 as well as I and L for 1.
+pub fn parse_short_code<T: ShortCodePrefix>(code: &str) -> Result<Id<T>, ShortCodeError> {
+    let trimmed = code.trim();
+    let prefixed = trimmed
+        .get(..T::PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(T::PREFIX))
+        .ok_or_else(|| ShortCodeError::WrongPrefix(String::from(trimmed)))?;
+    let rest = &trimmed[prefixed.len()..];
+    let rest = rest.strip_prefix('-').unwrap_or(rest);
+    let mut digits = rest
+        .chars()
+        .map(decode_digit)
+        .collect::<Result<Vec<u8>, ShortCodeError>>()?;
+    let check_digit = digits.pop().ok_or(ShortCodeError::NotCanonical)?;
+    if digits.len() < MIN_DIGITS || (digits.len() > MIN_DIGITS && digits[0] == 0) {
+        return Err(ShortCodeError::NotCanonical);
+    }
+    if check(&digits) != check_digit {
+        return Err(ShortCodeError::WrongCheck);
+    }
+    let value = digits.iter().try_fold(0u32, |value, digit| {
+        value
+            .checked_mul(32)
+            .and_then(|value| value.checked_add(u32::from(*digit)))
+            .ok_or(ShortCodeError::NotCanonical)
+    })?;
+    Ok(Id::new(value))
+}
+
+fn decode_digit(character: char) -> Result<u8, ShortCodeError> {
+    let normalized = match character.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        other => other,
+    };
+    ALPHABET
+        .iter()
+        .position(|digit| char::from(*digit) == normalized)
+        .map(|position| position as u8)
+        .ok_or(ShortCodeError::InvalidCharacter(character))
+}
+
+/// Weighted sum modulo a prime, so a changed digit or two swapped neighbours change the check.
+fn check(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(position, digit)| (position as u32 + 1) * u32::from(*digit))
+        .sum();
+    (sum % 31) as u8
+}
+
+/// How IDs are shown to users, e.g. in announcements and on the call sheet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdFormat {
+    /// The plain number, e.g. "3"
+    #[default]
+    Numeric,
+    /// See `short_code`, e.g. "ORD-0039"
+    ShortCode,
+}
+
+impl IdFormat {
+    pub fn format<T: ShortCodePrefix>(&self, id: &Id<T>) -> String {
+        match self {
+            IdFormat::Numeric => id.get_value().to_string(),
+            IdFormat::ShortCode => short_code(id),
+        }
+    }
+}
+
+impl FromStr for IdFormat {
+    type Err = UnknownIdFormatError;
+
+    fn from_str(format: &str) -> Result<IdFormat, UnknownIdFormatError> {
+        match format.to_lowercase().as_str() {
+            "numeric" => Ok(IdFormat::Numeric),
+            "short-code" => Ok(IdFormat::ShortCode),
+            _ => Err(UnknownIdFormatError(String::from(format))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownIdFormatError(String);
+
+impl fmt::Display for UnknownIdFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is not an ID format", self.0)
+    }
+}
+
+impl Error for UnknownIdFormatError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    struct Thing;
+
+    impl ShortCodePrefix for Thing {
+        const PREFIX: &'static str = "THG";
+    }
+
+    struct Other;
+
+    impl ShortCodePrefix for Other {
+        const PREFIX: &'static str = "OTH";
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case(0, "THG-0000"),
+        case(1, "THG-0013"),
+        case(32, "THG-0102"),
+        case(32768, "THG-10001")
+    )]
+    fn id_is_encoded(value: u32, expected: &str) {
+        assert_eq!(short_code::<Thing>(&Id::new(value)), expected);
+    }
+
+    #[rstest(
+        code,
+        expected,
+        case("thg-0102", Ok(Id::new(32))),
+        case("THGOIO2", Ok(Id::new(32))),
+        case("OTH-0102", Err(ShortCodeError::WrongPrefix(String::from("OTH-0102")))),
+        case("THG-01U2", Err(ShortCodeError::InvalidCharacter('U'))),
+        case("THG-0103", Err(ShortCodeError::WrongCheck)),
+        case("THG-1002", Err(ShortCodeError::WrongCheck)),
+        case("THG-00102", Err(ShortCodeError::NotCanonical)),
+        case("THG-011", Err(ShortCodeError::NotCanonical)),
+        case("THG-ZZZZZZZ0", Err(ShortCodeError::NotCanonical))
+    )]
+    fn code_is_parsed(code: &str, expected: Result<Id<Thing>, ShortCodeError>) {
+        assert_eq!(parse_short_code::<Thing>(code), expected);
+    }
+
+    #[test]
+    fn code_of_other_entity_is_rejected() {
+        // Given:
+        let code = short_code::<Other>(&Id::new(7));
+
+        // When:
+        let parsed = parse_short_code::<Thing>(&code);
+
+        // Then:
+        assert_eq!(parsed, Err(ShortCodeError::WrongPrefix(code)));
+    }
+
+    proptest! {
+        #[test]
+        fn every_id_round_trips(value: u32) {
+            let id = Id::new(value);
+            prop_assert_eq!(parse_short_code::<Thing>(&short_code(&id)), Ok(id));
+        }
+    }
+}
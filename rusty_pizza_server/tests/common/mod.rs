@@ -0,0 +1,239 @@
+//! In-process test server for end-to-end tests of whole workflows.
+//!
+//! The full API runs on in-memory state with a `TestClock`, requests go straight to the router without a
+//! network, so tests are fast and deterministic:
+//! ```ignore
+//! let server = TestServer::start();
+//! let client = server.client();
+//! let anna = client.register_user("Anna").await;
+//! let order = client.create_order(anna).await;
+//! server.advance(Duration::from_secs(60));
+//! ```
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use rusty_pizza_server::api::routes::router;
+use rusty_pizza_server::api::state::AppState;
+use rusty_pizza_server::api::v1::dto::{
+    AddMealRequest, AddUserRequest, AmountRequest, CreateOrderRequest, CreatedOrderResponse,
+    CreatedResponse, LoginRequest, RegisterUserRequest, SessionResponse, StatusRequest,
+    TotalsResponse,
+};
+use rusty_pizza_server::util::clock::TestClock;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tower::ServiceExt;
+
+/// Monday, 2020-05-04 10:00 UTC, so tests don't start at the epoch.
+const START: Duration = Duration::from_secs(1_588_586_400);
+
+/// The whole server, started on fresh state.
+pub struct TestServer {
+    state: AppState,
+    clock: TestClock,
+}
+
+impl TestServer {
+    pub fn start() -> TestServer {
+        TestServer::start_with(|state| state)
+    }
+
+    /// Starts the server on state configured by `configure`, e.g. to require authentication.
+    pub fn start_with(configure: impl FnOnce(AppState) -> AppState) -> TestServer {
+        let clock = TestClock::new(SystemTime::UNIX_EPOCH + START);
+        let state = configure(AppState::with_clock(Arc::new(clock.clone())));
+        TestServer { state, clock }
+    }
+
+    /// The state behind the API, to check what a workflow left behind.
+    pub fn get_state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Moves the time of the server, e.g. past a deadline or the grace period after delivery.
+    pub fn advance(&self, duration: Duration) {
+        self.clock.advance(duration);
+    }
+
+    /// Client without session.
+    pub fn client(&self) -> TestClient {
+        TestClient {
+            state: self.state.clone(),
+            token: None,
+        }
+    }
+}
+
+/// Status and body of a response.
+#[derive(Debug)]
+pub struct TestResponse {
+    status: StatusCode,
+    body: Vec<u8>,
+}
+
+impl TestResponse {
+    pub fn get_status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Panics with the body if the status is a different one.
+    pub fn expect_status(self, status: StatusCode) -> TestResponse {
+        assert_eq!(
+            self.status,
+            status,
+            "unexpected response {}",
+            String::from_utf8_lossy(&self.body)
+        );
+        self
+    }
+
+    /// Panics if the body isn't a `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("{} in response {}", e, String::from_utf8_lossy(&self.body)))
+    }
+}
+
+/// Sends requests to a `TestServer`, with typed shortcuts for the steps of the usual workflow.
+///
+/// The shortcuts panic if the server doesn't accept the step, use `send` to test failures.
+#[derive(Clone)]
+pub struct TestClient {
+    state: AppState,
+    token: Option<String>,
+}
+
+impl TestClient {
+    pub async fn send<B: Serialize>(
+        &self,
+        method: &str,
+        uri: &str,
+        body: Option<B>,
+    ) -> TestResponse {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(body) => Body::from(serde_json::to_vec(&body).expect("request is not JSON")),
+            None => Body::empty(),
+        };
+        let response = router(self.state.clone())
+            .oneshot(request.body(body).expect("request is invalid"))
+            .await
+            .expect("router is infallible");
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body can't be read")
+            .to_bytes()
+            .to_vec();
+        TestResponse { status, body }
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, uri: &str) -> T {
+        self.send::<()>("GET", uri, None)
+            .await
+            .expect_status(StatusCode::OK)
+            .json()
+    }
+
+    /// Registers a user with the password "secret" and returns their ID.
+    pub async fn register_user(&self, name: &str) -> u32 {
+        let request = RegisterUserRequest {
+            name: String::from(name),
+            password: Some(String::from("secret")),
+        };
+        self.send("POST", "/users", Some(request))
+            .await
+            .expect_status(StatusCode::CREATED)
+            .json::<CreatedResponse>()
+            .id
+    }
+
+    /// Client sending the session of the user registered by `register_user`.
+    pub async fn login(&self, name: &str) -> TestClient {
+        let request = LoginRequest {
+            name: String::from(name),
+            password: String::from("secret"),
+        };
+        let session = self
+            .send("POST", "/sessions", Some(request))
+            .await
+            .expect_status(StatusCode::CREATED)
+            .json::<SessionResponse>();
+        TestClient {
+            state: self.state.clone(),
+            token: Some(session.token),
+        }
+    }
+
+    pub async fn create_order(&self, manager_id: u32) -> u32 {
+        let request = CreateOrderRequest {
+            manager_id,
+            restaurant: None,
+            currency: None,
+            locale: None,
+        };
+        self.send("POST", "/orders", Some(request))
+            .await
+            .expect_status(StatusCode::CREATED)
+            .json::<CreatedOrderResponse>()
+            .id
+    }
+
+    pub async fn join(&self, order_id: u32, user_id: u32) {
+        let uri = format!("/orders/{}/users", order_id);
+        self.send("POST", &uri, Some(AddUserRequest { user_id }))
+            .await
+            .expect_status(StatusCode::CREATED);
+    }
+
+    /// Adds a meal for the user and returns its ID.
+    pub async fn add_meal(
+        &self,
+        order_id: u32,
+        user_id: u32,
+        meal_id: &str,
+        price_cents: u32,
+    ) -> u32 {
+        let request = AddMealRequest {
+            meal_id: String::from(meal_id),
+            variety: String::from("groß"),
+            price_cents,
+            note: None,
+            deposit_cents: None,
+        };
+        let uri = format!("/orders/{}/users/{}/meals", order_id, user_id);
+        self.send("POST", &uri, Some(request))
+            .await
+            .expect_status(StatusCode::CREATED)
+            .json::<CreatedResponse>()
+            .id
+    }
+
+    pub async fn set_status(&self, order_id: u32, status: StatusRequest) {
+        let uri = format!("/orders/{}/status", order_id);
+        self.send("PUT", &uri, Some(status))
+            .await
+            .expect_status(StatusCode::NO_CONTENT);
+    }
+
+    pub async fn pay(&self, order_id: u32, user_id: u32, amount_cents: u32) -> TestResponse {
+        let uri = format!("/orders/{}/users/{}/paid", order_id, user_id);
+        self.send("PUT", &uri, Some(AmountRequest { amount_cents }))
+            .await
+    }
+
+    pub async fn totals(&self, order_id: u32) -> TotalsResponse {
+        self.get(&format!("/orders/{}/totals", order_id)).await
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestServer;
+use rusty_pizza_server::api::v1::dto::{DashboardResponse, StatusRequest};
+use rusty_pizza_server::order_model::order::DEFAULT_GRACE_PERIOD;
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn order_is_created_joined_ordered_paid_and_settled() {
+    // Given:
+    let server = TestServer::start();
+    let client = server.client();
+    let anna = client.register_user("Anna").await;
+    let ben = client.register_user("Ben").await;
+    let order = client.create_order(anna).await;
+
+    // When:
+    client.join(order, ben).await;
+    client.add_meal(order, anna, "03", 750).await;
+    client.add_meal(order, ben, "17", 500).await;
+    client.set_status(order, StatusRequest::Ordering).await;
+    client
+        .set_status(
+            order,
+            StatusRequest::Ordered {
+                eta: Some(String::from("2020-05-04T12:15:00+02:00")),
+            },
+        )
+        .await;
+    client
+        .pay(order, anna, 1000)
+        .await
+        .expect_status(StatusCode::NO_CONTENT);
+    client
+        .pay(order, ben, 500)
+        .await
+        .expect_status(StatusCode::NO_CONTENT);
+    client.set_status(order, StatusRequest::Delivered).await;
+
+    // Then:
+    let totals = client.totals(order).await;
+    assert_eq!(totals.price_cents, 1250);
+    assert_eq!(totals.change_cents, Some(250));
+    assert!(totals.paid_less.is_empty());
+    let dashboard: DashboardResponse = client.get(&format!("/orders/{}/dashboard", order)).await;
+    assert_eq!(dashboard.balance_cents[&anna], 250);
+    assert_eq!(dashboard.balance_cents[&ben], 0);
+}
+
+#[tokio::test]
+async fn order_is_closed_after_grace_period() {
+    // Given:
+    let server = TestServer::start();
+    let client = server.client();
+    let anna = client.register_user("Anna").await;
+    let order = client.create_order(anna).await;
+    client.add_meal(order, anna, "03", 750).await;
+    client.set_status(order, StatusRequest::Ordering).await;
+    client
+        .set_status(
+            order,
+            StatusRequest::Ordered {
+                eta: Some(String::from("2020-05-04T12:15:00+02:00")),
+            },
+        )
+        .await;
+    client.set_status(order, StatusRequest::Delivered).await;
+
+    // When:
+    server.advance(DEFAULT_GRACE_PERIOD + Duration::from_secs(1));
+
+    // Then:
+    let response = client.pay(order, anna, 750).await;
+    assert_eq!(response.get_status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn workflow_works_with_required_authentication() {
+    // Given:
+    let server = TestServer::start_with(|state| state.with_required_authentication());
+    let anonymous = server.client();
+    let anna = anonymous.register_user("Anna").await;
+    let session = anonymous.login("Anna").await;
+
+    // When:
+    let order = session.create_order(anna).await;
+    session.add_meal(order, anna, "03", 750).await;
+
+    // Then:
+    assert_eq!(session.totals(order).await.price_cents, 750);
+    assert_eq!(server.get_state().orders().orders().count(), 1);
+    let response = anonymous
+        .send("POST", "/orders", Some(json!({ "manager_id": anna })))
+        .await;
+    assert_eq!(response.get_status(), StatusCode::UNAUTHORIZED);
+}